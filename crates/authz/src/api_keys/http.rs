@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use atlas_http::{error::AppError, meta::authorize_admin, security::Authenticator};
+
+use super::{ApiKey, ApiKeys};
+use crate::services::SERVICE_SUBJECT_PREFIX;
+use crate::signatures::CLIENT_SUBJECT_PREFIX;
+use crate::Subject;
+
+/// Header carrying the API key token
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+fn api_key(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Verify `token` and add the key's `Subject` to `request`
+async fn authenticate(keys: &ApiKeys, request: &mut Request, token: &str) -> Result<(), AppError> {
+    let key = keys
+        .verify(token)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("invalid or revoked API key"))?;
+
+    tracing::debug!(key_id = %key.id, subject = %key.subject, "authenticated API key");
+    request.extensions_mut().insert(Subject(key.subject));
+    Ok(())
+}
+
+/// Route middleware authenticating requests by their `X-Api-Key` header
+///
+/// Valid keys add their `Subject` to the request, so `authorize` can run after it.
+pub async fn require_api_key(
+    State(keys): State<Arc<ApiKeys>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = api_key(&request).ok_or_else(|| AppError::unauthorized("API key required"))?;
+    authenticate(&keys, &mut request, &token).await?;
+    Ok(next.run(request).await)
+}
+
+/// Authenticates the `X-Api-Key` header on routes declaring `SecurityScheme::ApiKey`
+///
+/// Registered by the API keys module, so `build_router` guards those routes with it.
+pub struct ApiKeyAuthenticator {
+    keys: Arc<ApiKeys>,
+}
+
+impl ApiKeyAuthenticator {
+    pub fn new(keys: Arc<ApiKeys>) -> Self {
+        Self { keys }
+    }
+}
+
+#[async_trait]
+impl Authenticator for ApiKeyAuthenticator {
+    async fn authenticate(&self, request: &mut Request) -> Result<bool, AppError> {
+        let Some(token) = api_key(request) else {
+            return Ok(false);
+        };
+        authenticate(&self.keys, request, &token).await?;
+        Ok(true)
+    }
+}
+
+#[derive(Clone)]
+struct ManagementState {
+    keys: Arc<ApiKeys>,
+    admin_token: Arc<str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateKey {
+    name: String,
+    subject: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedKey {
+    #[serde(flatten)]
+    key: ApiKey,
+    /// Shown once; only its hash is stored
+    token: String,
+}
+
+async fn list_keys(
+    State(state): State<ManagementState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.keys.list().await?))
+}
+
+async fn create_key(
+    State(state): State<ManagementState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateKey>,
+) -> Result<(StatusCode, Json<CreatedKey>), AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+
+    let mut details = Vec::new();
+    for (field, value) in [("name", &request.name), ("subject", &request.subject)] {
+        if value.trim().is_empty() {
+            details.push(serde_json::json!({ "field": field, "error": "required" }));
+        }
+    }
+    // Service and client subjects are only ever granted to callers proving a signing secret
+    if [SERVICE_SUBJECT_PREFIX, CLIENT_SUBJECT_PREFIX]
+        .iter()
+        .any(|prefix| request.subject.starts_with(prefix))
+    {
+        details.push(serde_json::json!({ "field": "subject", "error": "reserved" }));
+    }
+    if !details.is_empty() {
+        return Err(AppError::validation(
+            details,
+            "name and subject are required; subjects may not start with 'service:' or 'client:'",
+        ));
+    }
+
+    let issued = state.keys.issue(&request.name, &request.subject).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedKey {
+            key: issued.key,
+            token: issued.token,
+        }),
+    ))
+}
+
+async fn revoke_key(
+    State(state): State<ManagementState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if state.keys.revoke(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!("no active API key '{}'", id)))
+    }
+}
+
+/// Create, list, and revoke keys; guarded by `Authorization: Bearer {admin_token}`
+pub fn management_routes(keys: Arc<ApiKeys>, admin_token: &str) -> Router {
+    Router::new()
+        .route("/", get(list_keys).post(create_key))
+        .route("/{id}", delete(revoke_key))
+        .route_layer(axum::middleware::from_fn(crate::audit::audit_admin))
+        .with_state(ManagementState {
+            keys,
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_keys::MemoryApiKeyStore;
+    use atlas_http::testing::send;
+    use atlas_kernel::clock::SystemClock;
+    use axum::{body::Body, http::header::AUTHORIZATION};
+
+    fn api_keys() -> Arc<ApiKeys> {
+        Arc::new(ApiKeys::new(
+            Arc::new(MemoryApiKeyStore::new()),
+            Arc::new(SystemClock),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_management_endpoints_issue_and_revoke() {
+        let router = Router::new().nest("/api/api_keys", management_routes(api_keys(), "s3cret"));
+        let admin = |method: &str, uri: &str, body: Body| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, "Bearer s3cret")
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        let (status, created) = send(
+            &router,
+            admin(
+                "POST",
+                "/api/api_keys",
+                Body::from(r#"{"name":"ci","subject":"deployer"}"#),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created["token"].as_str().unwrap().starts_with("atlas_"));
+        assert!(created.get("hash").is_none());
+
+        let (status, listed) = send(&router, admin("GET", "/api/api_keys", Body::empty())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["id"], created["id"]);
+        assert!(listed[0].get("token").is_none());
+
+        let revoke_uri = format!("/api/api_keys/{}", created["id"].as_str().unwrap());
+        let (status, _) = send(&router, admin("DELETE", &revoke_uri, Body::empty())).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, admin("DELETE", &revoke_uri, Body::empty())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let anonymous = axum::http::Request::builder()
+            .uri("/api/api_keys")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&router, anonymous).await.0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_sets_subject_from_key() {
+        let keys = api_keys();
+        let issued = keys.issue("ci", "deployer").await.unwrap();
+        let router =
+            Router::new()
+                .route(
+                    "/whoami",
+                    get(
+                        |axum::Extension(subject): axum::Extension<Subject>| async move {
+                            Json(subject.0)
+                        },
+                    ),
+                )
+                .route_layer(axum::middleware::from_fn_with_state(keys, require_api_key));
+        let request = |token: Option<&str>| {
+            let mut builder = axum::http::Request::builder().uri("/whoami");
+            if let Some(token) = token {
+                builder = builder.header(API_KEY_HEADER, token);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let (status, subject) = send(&router, request(Some(&issued.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(subject, "deployer");
+
+        assert_eq!(
+            send(&router, request(Some("atlas_bogus"))).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&router, request(None)).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
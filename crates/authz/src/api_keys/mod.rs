@@ -0,0 +1,128 @@
+//! API keys for machine-to-machine access
+//!
+//! Tokens are shown once at issuance; only their hash is stored.
+
+mod http;
+mod module;
+mod store;
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use time::OffsetDateTime;
+
+use atlas_kernel::{id, Clock};
+
+use crate::token;
+
+pub use http::{management_routes, require_api_key, ApiKeyAuthenticator, API_KEY_HEADER};
+pub use module::{create_module, ApiKeysModule};
+pub use store::{ApiKey, ApiKeyStore, MemoryApiKeyStore, SurrealApiKeyStore};
+
+/// Prefix of every token, so leaked keys are easy to recognise in logs and scanners
+pub const TOKEN_PREFIX: &str = "atlas_";
+
+/// Characters of the token kept in `ApiKey::prefix`
+const DISPLAY_PREFIX_LEN: usize = TOKEN_PREFIX.len() + 6;
+
+/// A newly issued key together with its token, which is never retrievable again
+#[derive(Debug, Clone)]
+pub struct IssuedKey {
+    pub key: ApiKey,
+    pub token: String,
+}
+
+/// Issues, verifies, and revokes API keys
+pub struct ApiKeys {
+    store: Arc<dyn ApiKeyStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ApiKeys {
+    pub fn new(store: Arc<dyn ApiKeyStore>, clock: Arc<dyn Clock>) -> Self {
+        Self { store, clock }
+    }
+
+    /// Create a key acting as `subject`
+    pub async fn issue(&self, name: &str, subject: &str) -> anyhow::Result<IssuedKey> {
+        let token = token::generate(TOKEN_PREFIX)?;
+        let key = ApiKey {
+            id: id::prefixed_with("key", id::ulid_at(self.clock.now()))?,
+            name: name.to_string(),
+            subject: subject.to_string(),
+            prefix: token[..DISPLAY_PREFIX_LEN].to_string(),
+            hash: token::hash(&token),
+            created_at: self.now(),
+            revoked_at: None,
+        };
+        self.store
+            .insert(key.clone())
+            .await
+            .context("failed to store API key")?;
+        tracing::info!(key_id = %key.id, subject = %key.subject, "issued API key");
+        Ok(IssuedKey { key, token })
+    }
+
+    /// The active key matching `token`, if any
+    pub async fn verify(&self, token: &str) -> anyhow::Result<Option<ApiKey>> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let key = self.store.find_by_hash(&token::hash(token)).await?;
+        Ok(key.filter(|key| !key.is_revoked()))
+    }
+
+    pub async fn list(&self) -> anyhow::Result<Vec<ApiKey>> {
+        self.store.list().await
+    }
+
+    /// Revoke a key, returning `false` if no active key has that id
+    pub async fn revoke(&self, id: &str) -> anyhow::Result<bool> {
+        let revoked = self.store.revoke(id, self.now()).await?;
+        if revoked {
+            tracing::info!(key_id = %id, "revoked API key");
+        }
+        Ok(revoked)
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::SystemClock;
+
+    fn api_keys() -> ApiKeys {
+        ApiKeys::new(Arc::new(MemoryApiKeyStore::new()), Arc::new(SystemClock))
+    }
+
+    #[tokio::test]
+    async fn test_issued_token_verifies_until_revoked() {
+        let keys = api_keys();
+        let issued = keys.issue("ci", "deployer").await.unwrap();
+
+        assert!(issued.token.starts_with(TOKEN_PREFIX));
+        assert!(issued.key.id.starts_with("key_"));
+        assert!(issued.token.starts_with(&issued.key.prefix));
+        assert_ne!(issued.key.hash, issued.token);
+
+        let verified = keys.verify(&issued.token).await.unwrap().unwrap();
+        assert_eq!(verified.subject, "deployer");
+
+        assert!(keys.revoke(&issued.key.id).await.unwrap());
+        assert!(keys.verify(&issued.token).await.unwrap().is_none());
+        assert!(!keys.revoke(&issued.key.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tokens_rejected() {
+        let keys = api_keys();
+        keys.issue("ci", "deployer").await.unwrap();
+
+        assert!(keys.verify("atlas_0000").await.unwrap().is_none());
+        assert!(keys.verify("not-an-api-key").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_http::security::Authenticators;
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SecurityScheme};
+
+use super::{
+    management_routes, ApiKeyAuthenticator, ApiKeyStore, ApiKeys, MemoryApiKeyStore,
+    SurrealApiKeyStore,
+};
+
+/// Core module issuing API keys and serving their management endpoints
+///
+/// Publishes `ApiKeys` as a shared resource and registers the `ApiKeyAuthenticator`, so
+/// routes declaring `SecurityScheme::ApiKey` accept keys. Management endpoints are
+/// mounted only when `admin.token` is set.
+#[derive(Default)]
+pub struct ApiKeysModule {
+    keys: OnceLock<Arc<ApiKeys>>,
+    admin_token: OnceLock<Option<String>>,
+}
+
+#[async_trait]
+impl Module for ApiKeysModule {
+    fn name(&self) -> &'static str {
+        "api_keys"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("API key issuance and verification")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn ApiKeyStore>(
+            &ctx.resources,
+            "api_key",
+            |db| Arc::new(SurrealApiKeyStore::new(db)),
+            || Arc::new(MemoryApiKeyStore::new()),
+        );
+        let keys = Arc::new(ApiKeys::new(store, ctx.clock.clone()));
+        ctx.resources.insert_arc(keys.clone());
+        Authenticators::published(&ctx.resources).register(
+            SecurityScheme::ApiKey,
+            Arc::new(ApiKeyAuthenticator::new(keys.clone())),
+        );
+
+        self.keys
+            .set(keys)
+            .map_err(|_| anyhow::anyhow!("api_keys module initialized twice"))?;
+        self.admin_token
+            .get_or_init(|| ctx.settings.admin.token.clone());
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match (self.keys.get(), self.admin_token.get().cloned().flatten()) {
+            (Some(keys), Some(admin_token)) => management_routes(keys.clone(), &admin_token),
+            _ => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let key = json!({
+            "type": "object",
+            "required": ["id", "name", "subject", "prefix", "created_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "subject": { "type": "string" },
+                "prefix": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "revoked_at": { "type": "string", "format": "date-time", "nullable": true }
+            }
+        });
+        Some(json!({
+            "tags": [
+                { "name": "API keys", "description": "Machine-to-machine credentials" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List API keys",
+                        "tags": ["API keys"],
+                        "responses": {
+                            "200": {
+                                "description": "Every issued key, without secrets",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": key }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "post": {
+                        "summary": "Issue an API key",
+                        "tags": ["API keys"],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["name", "subject"],
+                                        "properties": {
+                                            "name": { "type": "string" },
+                                            "subject": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "201": {
+                                "description": "The new key; `token` is shown only once",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "allOf": [
+                                                key,
+                                                {
+                                                    "type": "object",
+                                                    "required": ["token"],
+                                                    "properties": { "token": { "type": "string" } }
+                                                }
+                                            ]
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/{id}": {
+                    "delete": {
+                        "summary": "Revoke an API key",
+                        "tags": ["API keys"],
+                        "parameters": [
+                            { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }
+                        ],
+                        "responses": {
+                            "204": { "description": "Key revoked" },
+                            "404": { "description": "No active key with that id" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![RouteSecurity {
+            method: "*",
+            path: "*",
+            schemes: &[SecurityScheme::AdminToken],
+            permission: None,
+        }]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_api_key",
+            up: "DEFINE TABLE api_key SCHEMAFULL;
+DEFINE FIELD name ON api_key TYPE string;
+DEFINE FIELD subject ON api_key TYPE string;
+DEFINE FIELD prefix ON api_key TYPE string;
+DEFINE FIELD hash ON api_key TYPE string;
+DEFINE FIELD created_at ON api_key TYPE datetime;
+DEFINE FIELD revoked_at ON api_key TYPE option<datetime>;
+DEFINE INDEX api_key_hash ON api_key FIELDS hash UNIQUE;",
+            down: Some("REMOVE TABLE api_key;"),
+        }]
+    }
+}
+
+/// Create a new instance of the API keys module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(ApiKeysModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Subject;
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::{settings::Settings, ModuleRegistry};
+    use axum::{http::StatusCode, routing::get, Json};
+
+    /// App module whose only route accepts API keys
+    struct ReportsModule;
+
+    impl Module for ReportsModule {
+        fn name(&self) -> &'static str {
+            "reports"
+        }
+
+        fn routes(&self) -> Router {
+            Router::new().route("/", get(|subject: Subject| async move { Json(subject.0) }))
+        }
+
+        fn security(&self) -> Vec<RouteSecurity> {
+            vec![RouteSecurity {
+                method: "get",
+                path: "/",
+                schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+                permission: None,
+            }]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_api_keys() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        let mut settings = Settings::default();
+        settings.admin.token = Some("s3cret".to_string());
+
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings)))
+            .await
+            .unwrap();
+
+        let keys = registry.resources().require::<ApiKeys>().unwrap();
+        let issued = keys.issue("ci", "deployer").await.unwrap();
+        assert!(keys.verify(&issued.token).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_issued_keys_call_guarded_app_routes() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry.register_custom(Arc::new(ReportsModule)).unwrap();
+        let mut settings = Settings::default();
+        settings.admin.token = Some("s3cret".to_string());
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings.clone())))
+            .await
+            .unwrap();
+        let router = atlas_http::build_router(&registry, &settings)
+            .await
+            .unwrap();
+
+        let mut issue = request(
+            "POST",
+            "/api/api_keys",
+            serde_json::json!({ "name": "ci", "subject": "deployer" }),
+        );
+        issue
+            .headers_mut()
+            .insert("authorization", "Bearer s3cret".parse().unwrap());
+        let (status, created) = send(&router, issue).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let reports = |token: &str| {
+            let mut request = request("GET", "/api/reports", serde_json::Value::Null);
+            request
+                .headers_mut()
+                .insert(crate::api_keys::API_KEY_HEADER, token.parse().unwrap());
+            request
+        };
+        let (status, subject) = send(&router, reports(created["token"].as_str().unwrap())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(subject, "deployer");
+
+        let (status, _) = send(&router, reports("atlas_bogus")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(
+            &router,
+            request("GET", "/api/reports", serde_json::Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use time::OffsetDateTime;
+
+use atlas_db::{affected, datetime, QueryExecutor};
+
+/// An issued API key; only the hash of the secret token is kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiKey {
+    /// Table-prefixed id such as `key_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    pub name: String,
+    /// Subject requests made with this key act as, matched against authz policies
+    pub subject: String,
+    /// Leading characters of the token, so operators can tell keys apart
+    pub prefix: String,
+    #[serde(skip)]
+    pub hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+impl ApiKey {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Persistence for API keys
+///
+/// The module stores keys in the `api_key` table through the application's
+/// `QueryExecutor`; applications may publish their own `Arc<dyn ApiKeyStore>` resource
+/// instead. Without either, keys live in memory.
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    async fn insert(&self, key: ApiKey) -> anyhow::Result<()>;
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<ApiKey>>;
+
+    /// Every key, revoked ones included, oldest first
+    async fn list(&self) -> anyhow::Result<Vec<ApiKey>>;
+
+    /// Mark a key revoked, returning `false` if no active key has that id
+    async fn revoke(&self, id: &str, at: OffsetDateTime) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; keys are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryApiKeyStore {
+    keys: RwLock<Vec<ApiKey>>,
+}
+
+impl MemoryApiKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for MemoryApiKeyStore {
+    async fn insert(&self, key: ApiKey) -> anyhow::Result<()> {
+        self.keys
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(key);
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        Ok(self
+            .keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|key| key.hash == hash)
+            .cloned())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<ApiKey>> {
+        Ok(self
+            .keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone())
+    }
+
+    async fn revoke(&self, id: &str, at: OffsetDateTime) -> anyhow::Result<bool> {
+        let mut keys = self
+            .keys
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match keys
+            .iter_mut()
+            .find(|key| key.id == id && !key.is_revoked())
+        {
+            Some(key) => {
+                key.revoked_at = Some(at);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_KEY: &str = "SELECT *, record::id(id) AS id FROM api_key";
+
+/// Keys in the `api_key` table defined by the module's migrations
+pub struct SurrealApiKeyStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealApiKeyStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+}
+
+/// A row of the `api_key` table, which unlike `ApiKey` carries the hash
+#[derive(Deserialize)]
+struct KeyRow {
+    id: String,
+    name: String,
+    subject: String,
+    prefix: String,
+    hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    created_at: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    revoked_at: Option<OffsetDateTime>,
+}
+
+impl From<KeyRow> for ApiKey {
+    fn from(row: KeyRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            subject: row.subject,
+            prefix: row.prefix,
+            hash: row.hash,
+            created_at: row.created_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for SurrealApiKeyStore {
+    async fn insert(&self, key: ApiKey) -> anyhow::Result<()> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(key.id));
+        vars.insert("name".to_string(), json!(key.name));
+        vars.insert("subject".to_string(), json!(key.subject));
+        vars.insert("prefix".to_string(), json!(key.prefix));
+        vars.insert("hash".to_string(), json!(key.hash));
+        vars.insert("created_at".to_string(), json!(datetime(key.created_at)));
+        self.db
+            .query_with(
+                "CREATE type::thing('api_key', $id) SET name = $name, subject = $subject, \
+                 prefix = $prefix, hash = $hash, created_at = <datetime> $created_at \
+                 RETURN NONE;",
+                &vars,
+            )
+            .await
+            .context("failed to store API key")?;
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        let mut vars = Map::new();
+        vars.insert("hash".to_string(), json!(hash));
+        let results = self
+            .db
+            .query_with(&format!("{} WHERE hash = $hash;", SELECT_KEY), &vars)
+            .await
+            .context("failed to read API key")?;
+        Ok(atlas_db::records::<KeyRow>(results.into_iter().next())?
+            .into_iter()
+            .next()
+            .map(ApiKey::from))
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<ApiKey>> {
+        let results = self
+            .db
+            .query(&format!("{} ORDER BY created_at, id;", SELECT_KEY))
+            .await
+            .context("failed to list API keys")?;
+        Ok(atlas_db::records::<KeyRow>(results.into_iter().next())?
+            .into_iter()
+            .map(ApiKey::from)
+            .collect())
+    }
+
+    async fn revoke(&self, id: &str, at: OffsetDateTime) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        vars.insert("at".to_string(), json!(datetime(at)));
+        let results = self
+            .db
+            .query_with(
+                "UPDATE api_key SET revoked_at = <datetime> $at \
+                 WHERE id = type::thing('api_key', $id) AND revoked_at = NONE \
+                 RETURN VALUE record::id(id);",
+                &vars,
+            )
+            .await
+            .context("failed to revoke API key")?;
+        Ok(affected(results.first()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_db::testing::RecordingExecutor;
+
+    #[tokio::test]
+    async fn test_surreal_store_finds_keys_with_their_hash() {
+        let db = RecordingExecutor::answering(vec![
+            Ok(vec![json!([{
+                "id": "key_1",
+                "name": "ci",
+                "subject": "deployer",
+                "prefix": "atlas_ab",
+                "hash": "9f86d0",
+                "created_at": "2024-01-01T00:00:00Z"
+            }])]),
+            Ok(vec![json!([])]),
+        ]);
+        let store = SurrealApiKeyStore::new(db.clone());
+
+        let key = store.find_by_hash("9f86d0").await.unwrap().unwrap();
+        assert_eq!(key.hash, "9f86d0");
+        assert!(!key.is_revoked());
+        assert!(!store.revoke("key_1", key.created_at).await.unwrap());
+
+        let queries = db.queries();
+        assert_eq!(queries[0].1["hash"], "9f86d0");
+        assert!(queries[1].0.contains("AND revoked_at = NONE"));
+    }
+}
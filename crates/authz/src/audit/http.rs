@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{OriginalUri, Query, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+
+use atlas_http::{error::AppError, meta::authorize_admin};
+use atlas_kernel::AppContext;
+
+use super::{record_in, AuditEvent, AuditOutcome, AuditQuery, AuditStore};
+
+/// Largest page served by the audit endpoint
+const MAX_LIMIT: usize = 1000;
+
+/// Route middleware recording every request to an admin endpoint as `admin.request`
+///
+/// Install with `axum::middleware::from_fn(atlas_authz::audit::audit_admin)` on routers
+/// guarded by `authorize_admin`; rejected tokens are recorded as denied.
+pub async fn audit_admin(request: Request, next: Next) -> Response {
+    let ctx = request.extensions().get::<AppContext>().cloned();
+    let method = request.method().clone();
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| request.uri().path(), |original| original.path())
+        .to_string();
+
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let outcome = match status.as_u16() {
+        401 | 403 => AuditOutcome::Denied,
+        _ if status.is_success() => AuditOutcome::Success,
+        _ => AuditOutcome::Failure,
+    };
+    record_in(ctx.as_ref(), |at| {
+        let event = AuditEvent::new("admin.request", outcome, at)
+            .permission(method.as_str())
+            .resource(path)
+            .detail(json!({ "method": method.as_str(), "status": status.as_u16() }));
+        match outcome {
+            AuditOutcome::Denied => event,
+            _ => event.actor("admin"),
+        }
+    })
+    .await;
+    response
+}
+
+#[derive(Clone)]
+struct AuditState {
+    store: Arc<dyn AuditStore>,
+    admin_token: Arc<str>,
+}
+
+async fn list_events(
+    State(state): State<AuditState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEvent>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if query
+        .limit
+        .is_some_and(|limit| limit == 0 || limit > MAX_LIMIT)
+    {
+        return Err(AppError::validation(
+            vec![json!({ "field": "limit", "error": "out_of_range" })],
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    Ok(Json(state.store.list(&query).await?))
+}
+
+/// List audit records, newest first; guarded by `Authorization: Bearer {admin_token}`
+pub fn audit_routes(store: Arc<dyn AuditStore>, admin_token: &str) -> Router {
+    Router::new()
+        .route("/", get(list_events))
+        .with_state(AuditState {
+            store,
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditSink, MemoryAuditStore, StoreSink};
+    use atlas_kernel::{settings::Settings, Resources};
+    use axum::{
+        body::Body,
+        http::{header::AUTHORIZATION, StatusCode},
+        routing::post,
+        Extension,
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_admin_requests_are_recorded_and_listed() {
+        let store: Arc<dyn AuditStore> = Arc::new(MemoryAuditStore::new());
+        let resources = Arc::new(Resources::new());
+        resources.insert::<Arc<dyn AuditSink>>(Arc::new(StoreSink(store.clone())));
+
+        let admin = Router::new()
+            .route(
+                "/",
+                post(|headers: HeaderMap| async move {
+                    authorize_admin(&headers, "s3cret").map(|_| "ok")
+                }),
+            )
+            .route_layer(axum::middleware::from_fn(audit_admin));
+        let router = Router::new()
+            .nest("/api/policies", admin)
+            .nest("/api/audit", audit_routes(store.clone(), "s3cret"))
+            .layer(Extension(AppContext::new(
+                Arc::new(Settings::default()),
+                resources,
+            )));
+        let send = |method: &str, uri: &str, token: &str| {
+            router.clone().oneshot(
+                axum::http::Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(AUTHORIZATION, format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let status = |response: Response| response.status();
+        assert_eq!(
+            status(send("POST", "/api/policies", "s3cret").await.unwrap()),
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(send("POST", "/api/policies", "wrong").await.unwrap()),
+            StatusCode::FORBIDDEN
+        );
+
+        let response = send("GET", "/api/audit?outcome=denied", "s3cret")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let listed: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["action"], "admin.request");
+        assert_eq!(listed[0]["resource"], "/api/policies");
+        assert_eq!(listed[0]["detail"]["status"], 403);
+        assert!(listed[0]["actor"].is_null());
+
+        let all = store.list(&AuditQuery::default()).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].actor.as_deref(), Some("admin"));
+
+        assert_eq!(
+            status(send("GET", "/api/audit?limit=0", "s3cret").await.unwrap()),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(send("GET", "/api/audit", "wrong").await.unwrap()),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Audit records for security-relevant actions
+//!
+//! Every record is logged under the `atlas::audit` target and handed to the
+//! `Arc<dyn AuditSink>` resource. The audit module registers a sink persisting records
+//! to an `AuditStore` and serves them to operators; applications may register their own
+//! sink instead.
+
+mod http;
+mod module;
+mod store;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::http::Extensions;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use atlas_kernel::{AppContext, Resources};
+
+pub use http::{audit_admin, audit_routes};
+pub use module::{create_module, AuditModule};
+pub use store::{AuditQuery, AuditStore, MemoryAuditStore, StoreSink};
+
+/// How an audited action ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    Denied,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Denied => "denied",
+        }
+    }
+}
+
+/// One audited action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Dotted action name such as `password_reset.completed`
+    pub action: String,
+    /// Subject that performed or was the target of the action, when known
+    pub actor: Option<String>,
+    /// Permission checked, as `object:action` or a policy action such as `DELETE`
+    pub permission: Option<String>,
+    /// What the action touched, e.g. a request path
+    pub resource: Option<String>,
+    /// Decision taken
+    pub outcome: AuditOutcome,
+    /// Action-specific context; never secrets
+    pub detail: Value,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+}
+
+impl AuditEvent {
+    pub fn new(action: impl Into<String>, outcome: AuditOutcome, at: OffsetDateTime) -> Self {
+        Self {
+            action: action.into(),
+            actor: None,
+            permission: None,
+            resource: None,
+            outcome,
+            detail: Value::Null,
+            at,
+        }
+    }
+
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn permission(mut self, permission: impl Into<String>) -> Self {
+        self.permission = Some(permission.into());
+        self
+    }
+
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+/// Destination for audit records beyond the log
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, event: &AuditEvent) -> anyhow::Result<()>;
+}
+
+/// Log `event` and pass it to the registered sink, if any
+///
+/// Sink failures are logged rather than returned, so auditing never fails the action.
+pub async fn record(resources: &Resources, event: AuditEvent) {
+    tracing::info!(
+        target: "atlas::audit",
+        action = %event.action,
+        actor = event.actor.as_deref().unwrap_or("-"),
+        permission = event.permission.as_deref().unwrap_or("-"),
+        resource = event.resource.as_deref().unwrap_or("-"),
+        outcome = event.outcome.as_str(),
+        detail = %event.detail,
+        "audit"
+    );
+    if let Some(sink) = resources.get::<Arc<dyn AuditSink>>() {
+        if let Err(error) = sink.record(&event).await {
+            tracing::warn!(error = %error, action = %event.action, "failed to record audit event");
+        }
+    }
+}
+
+/// Record an event built at the request's time, through the request's `AppContext`
+///
+/// Without an `AppContext` extension the event is only logged.
+pub(crate) async fn record_for_request(
+    extensions: &Extensions,
+    event: impl FnOnce(OffsetDateTime) -> AuditEvent,
+) {
+    record_in(extensions.get::<AppContext>(), event).await
+}
+
+/// Record an event built at the context's time; without a context it is only logged
+pub(crate) async fn record_in(
+    ctx: Option<&AppContext>,
+    event: impl FnOnce(OffsetDateTime) -> AuditEvent,
+) {
+    match ctx {
+        Some(ctx) => {
+            let event = event(OffsetDateTime::from(ctx.clock().now()));
+            record(ctx.resources(), event).await;
+        }
+        None => record(&Resources::new(), event(OffsetDateTime::now_utc())).await,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Sink keeping records for assertions
+    #[derive(Default)]
+    pub(crate) struct RecordingSink {
+        pub(crate) events: Mutex<Vec<AuditEvent>>,
+    }
+
+    impl RecordingSink {
+        pub(crate) fn actions(&self) -> Vec<String> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|event| format!("{}:{}", event.action, event.outcome.as_str()))
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl AuditSink for RecordingSink {
+        async fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_forwards_to_registered_sink() {
+        let resources = Resources::new();
+        let event = AuditEvent::new(
+            "policy.added",
+            AuditOutcome::Success,
+            OffsetDateTime::UNIX_EPOCH,
+        )
+        .actor("admin");
+
+        // Without a sink the record is only logged
+        record(&resources, event.clone()).await;
+
+        let sink = Arc::new(RecordingSink::default());
+        resources.insert::<Arc<dyn AuditSink>>(sink.clone());
+        record(&resources, event).await;
+
+        assert_eq!(sink.actions(), vec!["policy.added:success"]);
+        assert_eq!(
+            sink.events.lock().unwrap()[0].actor.as_deref(),
+            Some("admin")
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SecurityScheme};
+
+use super::{audit_routes, AuditSink, AuditStore, MemoryAuditStore, StoreSink};
+
+/// Core module persisting audit records and serving them to operators
+///
+/// Registers an `Arc<dyn AuditSink>` writing to the `AuditStore` unless the application
+/// registered its own sink. The listing endpoint is mounted only when `admin.token` is set.
+#[derive(Default)]
+pub struct AuditModule {
+    store: OnceLock<Arc<dyn AuditStore>>,
+    admin_token: OnceLock<Option<String>>,
+}
+
+#[async_trait]
+impl Module for AuditModule {
+    fn name(&self) -> &'static str {
+        "audit"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Audit trail of authorization decisions and privileged actions")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = match ctx.resources.get::<Arc<dyn AuditStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => {
+                tracing::warn!(
+                    "no persistent audit store registered; recent audit records are kept in memory"
+                );
+                Arc::new(MemoryAuditStore::new())
+            }
+        };
+        if ctx.resources.get::<Arc<dyn AuditSink>>().is_none() {
+            ctx.resources
+                .insert::<Arc<dyn AuditSink>>(Arc::new(StoreSink(store.clone())));
+        }
+
+        self.store
+            .set(store)
+            .map_err(|_| anyhow::anyhow!("audit module initialized twice"))?;
+        self.admin_token
+            .get_or_init(|| ctx.settings.admin.token.clone());
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match (self.store.get(), self.admin_token.get().cloned().flatten()) {
+            (Some(store), Some(admin_token)) => audit_routes(store.clone(), &admin_token),
+            _ => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let query = |name: &str, schema: serde_json::Value, description: &str| json!({ "name": name, "in": "query", "required": false, "schema": schema, "description": description });
+        Some(json!({
+            "tags": [
+                { "name": "Audit", "description": "Security-relevant actions and decisions" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List audit records, newest first",
+                        "tags": ["Audit"],
+                        "parameters": [
+                            query("actor", json!({ "type": "string" }), "Exact subject"),
+                            query(
+                                "action",
+                                json!({ "type": "string" }),
+                                "Exact action, or a prefix ending in `.` such as `authz.`"
+                            ),
+                            query(
+                                "outcome",
+                                json!({ "type": "string", "enum": ["success", "failure", "denied"] }),
+                                "Decision taken"
+                            ),
+                            query(
+                                "limit",
+                                json!({ "type": "integer", "minimum": 1, "maximum": 1000, "default": 100 }),
+                                "Records returned"
+                            )
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Matching records",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": {
+                                                "type": "object",
+                                                "required": ["action", "outcome", "at"],
+                                                "properties": {
+                                                    "action": { "type": "string" },
+                                                    "actor": { "type": "string", "nullable": true },
+                                                    "permission": { "type": "string", "nullable": true },
+                                                    "resource": { "type": "string", "nullable": true },
+                                                    "outcome": { "type": "string", "enum": ["success", "failure", "denied"] },
+                                                    "detail": {},
+                                                    "at": { "type": "string", "format": "date-time" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            },
+                            "422": { "description": "Limit out of range" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![RouteSecurity {
+            method: "*",
+            path: "*",
+            schemes: &[SecurityScheme::AdminToken],
+            permission: None,
+        }]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_audit_event",
+            up: "DEFINE TABLE audit_event SCHEMAFULL;
+DEFINE FIELD action ON audit_event TYPE string;
+DEFINE FIELD actor ON audit_event TYPE option<string>;
+DEFINE FIELD permission ON audit_event TYPE option<string>;
+DEFINE FIELD resource ON audit_event TYPE option<string>;
+DEFINE FIELD outcome ON audit_event TYPE string;
+DEFINE FIELD detail ON audit_event FLEXIBLE TYPE any;
+DEFINE FIELD at ON audit_event TYPE datetime;
+DEFINE INDEX audit_event_at ON audit_event FIELDS at;
+DEFINE INDEX audit_event_actor ON audit_event FIELDS actor;",
+            down: Some("REMOVE TABLE audit_event;"),
+        }]
+    }
+}
+
+/// Create a new instance of the audit module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(AuditModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{record, tests::RecordingSink, AuditEvent, AuditOutcome, AuditQuery};
+    use atlas_kernel::{settings::Settings, ModuleRegistry};
+    use time::OffsetDateTime;
+
+    async fn init(registry: &mut ModuleRegistry) {
+        registry.register_core(create_module()).unwrap();
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(Settings::default())))
+            .await
+            .unwrap();
+    }
+
+    fn event() -> AuditEvent {
+        AuditEvent::new(
+            "authz.denied",
+            AuditOutcome::Denied,
+            OffsetDateTime::UNIX_EPOCH,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_init_persists_records_to_registered_store() {
+        let mut registry = ModuleRegistry::new();
+        let store: Arc<dyn AuditStore> = Arc::new(MemoryAuditStore::new());
+        registry.resources().insert(store.clone());
+        init(&mut registry).await;
+
+        record(registry.resources(), event()).await;
+
+        assert_eq!(store.list(&AuditQuery::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_application_sink_is_kept() {
+        let mut registry = ModuleRegistry::new();
+        let sink = Arc::new(RecordingSink::default());
+        registry
+            .resources()
+            .insert::<Arc<dyn AuditSink>>(sink.clone());
+        init(&mut registry).await;
+
+        record(registry.resources(), event()).await;
+
+        assert_eq!(sink.actions(), vec!["authz.denied:denied"]);
+    }
+}
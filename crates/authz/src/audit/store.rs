@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{AuditEvent, AuditOutcome, AuditSink};
+
+/// Filter for listing audit records; newest records come first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    /// Exact action, or a prefix ending in `.` such as `authz.`
+    pub action: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Records returned when no limit is given
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        let action = match self.action.as_deref() {
+            None => true,
+            Some(prefix) if prefix.ends_with('.') => event.action.starts_with(prefix),
+            Some(action) => event.action == action,
+        };
+        action
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|actor| event.actor.as_ref() == Some(actor))
+            && self.outcome.is_none_or(|outcome| event.outcome == outcome)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+/// Persistence for audit records
+///
+/// The database module provides a SurrealDB-backed store by publishing an
+/// `Arc<dyn AuditStore>` resource; without one, recent records live in memory.
+#[async_trait]
+pub trait AuditStore: Send + Sync {
+    async fn append(&self, event: AuditEvent) -> anyhow::Result<()>;
+
+    async fn list(&self, query: &AuditQuery) -> anyhow::Result<Vec<AuditEvent>>;
+}
+
+/// Process-local store keeping the most recent records
+#[derive(Debug)]
+pub struct MemoryAuditStore {
+    events: RwLock<VecDeque<AuditEvent>>,
+    capacity: usize,
+}
+
+impl MemoryAuditStore {
+    /// Records kept before the oldest are dropped
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: RwLock::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+impl Default for MemoryAuditStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AuditStore for MemoryAuditStore {
+    async fn append(&self, event: AuditEvent) -> anyhow::Result<()> {
+        let mut events = self
+            .events
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+        Ok(())
+    }
+
+    async fn list(&self, query: &AuditQuery) -> anyhow::Result<Vec<AuditEvent>> {
+        Ok(self
+            .events
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .rev()
+            .filter(|event| query.matches(event))
+            .take(query.limit())
+            .cloned()
+            .collect())
+    }
+}
+
+/// Sink persisting every record to a store
+pub struct StoreSink(pub Arc<dyn AuditStore>);
+
+#[async_trait]
+impl AuditSink for StoreSink {
+    async fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+        self.0.append(event.clone()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn event(action: &str, actor: &str, outcome: AuditOutcome) -> AuditEvent {
+        AuditEvent::new(action, outcome, OffsetDateTime::UNIX_EPOCH).actor(actor)
+    }
+
+    #[tokio::test]
+    async fn test_list_filters_newest_first_within_capacity() {
+        let store = MemoryAuditStore::with_capacity(3);
+        for event in [
+            event("authz.denied", "alice", AuditOutcome::Denied),
+            event("admin.request", "admin", AuditOutcome::Success),
+            event("authz.denied", "bob", AuditOutcome::Denied),
+            event("admin.request", "admin", AuditOutcome::Denied),
+        ] {
+            store.append(event).await.unwrap();
+        }
+
+        let all = store.list(&AuditQuery::default()).await.unwrap();
+        let actors: Vec<_> = all.iter().map(|e| e.actor.as_deref().unwrap()).collect();
+        assert_eq!(actors, vec!["admin", "bob", "admin"]);
+
+        let denied = store
+            .list(&AuditQuery {
+                action: Some("authz.".to_string()),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(denied.len(), 1);
+
+        let admin_denied = store
+            .list(&AuditQuery {
+                actor: Some("admin".to_string()),
+                outcome: Some(AuditOutcome::Denied),
+                limit: Some(5),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(admin_denied.len(), 1);
+    }
+}
@@ -0,0 +1,358 @@
+//! Policy enforcement against a Casbin model
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::model::{key_match, key_match2, Expr, Model};
+use crate::policy::PolicyRule;
+
+/// How deep role inheritance (`g, alice, editor` / `g, editor, admin`) is followed
+const MAX_ROLE_DEPTH: usize = 10;
+
+/// Decides whether a request such as `(subject, object, action)` is allowed
+///
+/// Rules can be added and removed while serving; `PolicyStore` keeps them persisted.
+#[derive(Debug)]
+pub struct Enforcer {
+    model: Model,
+    rules: RwLock<Rules>,
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+    policies: Vec<Vec<String>>,
+    /// Direct roles of each subject, from `g` policy lines
+    roles: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Value<'a> {
+    Str(&'a str),
+    Bool(bool),
+}
+
+impl Enforcer {
+    /// Load the model and policy files named in `[auth]` settings
+    pub fn from_files(
+        model_path: impl AsRef<Path>,
+        policy_path: impl AsRef<Path>,
+    ) -> anyhow::Result<Self> {
+        let model_path = model_path.as_ref();
+        let policy_path = policy_path.as_ref();
+        let model = std::fs::read_to_string(model_path)
+            .with_context(|| format!("failed to read casbin model {}", model_path.display()))?;
+        let policy = std::fs::read_to_string(policy_path)
+            .with_context(|| format!("failed to read casbin policy {}", policy_path.display()))?;
+        Self::from_strs(&model, &policy).with_context(|| {
+            format!(
+                "invalid casbin configuration in {} / {}",
+                model_path.display(),
+                policy_path.display()
+            )
+        })
+    }
+
+    /// Build an enforcer from model text and CSV policy lines
+    pub fn from_strs(model: &str, policy: &str) -> anyhow::Result<Self> {
+        Self::new(Model::parse(model)?, PolicyRule::parse_csv(policy)?)
+    }
+
+    /// Build an enforcer from a parsed model and rules, e.g. loaded from a `PolicyStore`
+    pub fn new(model: Model, rules: Vec<PolicyRule>) -> anyhow::Result<Self> {
+        let enforcer = Self {
+            model,
+            rules: RwLock::new(Rules::default()),
+        };
+        for (index, rule) in rules.iter().enumerate() {
+            enforcer
+                .add_rule(rule)
+                .with_context(|| format!("policy rule {}", index + 1))?;
+        }
+        Ok(enforcer)
+    }
+
+    /// Whether any policy allows the request; values follow the model's `r = ...` order
+    pub fn enforce(&self, request: &[&str]) -> anyhow::Result<bool> {
+        if request.len() != self.model.request_fields.len() {
+            bail!(
+                "request has {} values but the model defines r = {}",
+                request.len(),
+                self.model.request_fields.join(", ")
+            );
+        }
+        let rules = self.read();
+        for policy in &rules.policies {
+            if self.eval(&rules, &self.model.matcher, request, policy)? == Value::Bool(true) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Number of values `enforce` expects, from the model's `r = ...`
+    pub fn request_len(&self) -> usize {
+        self.model.request_fields.len()
+    }
+
+    /// Whether `user` holds `role`, directly or through inherited roles
+    pub fn has_role(&self, user: &str, role: &str) -> bool {
+        self.read().has_role(user, role)
+    }
+
+    /// Check a rule fits the model without applying it
+    pub fn validate_rule(&self, rule: &PolicyRule) -> anyhow::Result<()> {
+        match rule.ptype.as_str() {
+            "p" if rule.values.len() == self.model.policy_fields.len() => Ok(()),
+            "p" => bail!(
+                "expected {} values ({}), got {}",
+                self.model.policy_fields.len(),
+                self.model.policy_fields.join(", "),
+                rule.values.len()
+            ),
+            "g" if rule.values.len() == 2 => Ok(()),
+            "g" => bail!("`g` takes a user and a role"),
+            other => bail!("unknown policy type `{}`", other),
+        }
+    }
+
+    /// Apply a rule, returning `false` if it was already present
+    pub fn add_rule(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        self.validate_rule(rule)?;
+        let mut rules = self.write();
+        if rule.ptype == "g" {
+            let roles = rules.roles.entry(rule.values[0].clone()).or_default();
+            if roles.contains(&rule.values[1]) {
+                return Ok(false);
+            }
+            roles.push(rule.values[1].clone());
+        } else {
+            if rules.policies.contains(&rule.values) {
+                return Ok(false);
+            }
+            rules.policies.push(rule.values.clone());
+        }
+        Ok(true)
+    }
+
+    /// Withdraw a rule, returning `false` if it was not present
+    pub fn remove_rule(&self, rule: &PolicyRule) -> bool {
+        let mut rules = self.write();
+        if rule.ptype == "g" {
+            let [user, role] = rule.values.as_slice() else {
+                return false;
+            };
+            let Some(roles) = rules.roles.get_mut(user) else {
+                return false;
+            };
+            let before = roles.len();
+            roles.retain(|existing| existing != role);
+            let removed = roles.len() != before;
+            if roles.is_empty() {
+                rules.roles.remove(user);
+            }
+            removed
+        } else {
+            let before = rules.policies.len();
+            rules.policies.retain(|existing| existing != &rule.values);
+            rules.policies.len() != before
+        }
+    }
+
+    /// Every rule currently enforced, `p` rules first
+    pub fn rules(&self) -> Vec<PolicyRule> {
+        let rules = self.read();
+        let mut roles: Vec<PolicyRule> = rules
+            .roles
+            .iter()
+            .flat_map(|(user, roles)| {
+                roles
+                    .iter()
+                    .map(|role| PolicyRule::new("g", [user.as_str(), role.as_str()]))
+            })
+            .collect();
+        roles.sort_by(|a, b| a.values.cmp(&b.values));
+        rules
+            .policies
+            .iter()
+            .map(|values| PolicyRule::new("p", values))
+            .chain(roles)
+            .collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Rules> {
+        self.rules
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Rules> {
+        self.rules
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn eval<'a>(
+        &'a self,
+        rules: &'a Rules,
+        expr: &'a Expr,
+        request: &[&'a str],
+        policy: &'a [String],
+    ) -> anyhow::Result<Value<'a>> {
+        let string = |expr: &'a Expr| -> anyhow::Result<&'a str> {
+            match self.eval(rules, expr, request, policy)? {
+                Value::Str(value) => Ok(value),
+                Value::Bool(_) => Err(anyhow!("expected a string operand in matcher")),
+            }
+        };
+        let boolean = |expr: &'a Expr| -> anyhow::Result<bool> {
+            match self.eval(rules, expr, request, policy)? {
+                Value::Bool(value) => Ok(value),
+                Value::Str(_) => Err(anyhow!("expected a boolean operand in matcher")),
+            }
+        };
+
+        Ok(match expr {
+            Expr::Literal(value) => Value::Str(value),
+            Expr::Request(index) => Value::Str(request[*index]),
+            Expr::Policy(index) => Value::Str(&policy[*index]),
+            Expr::Field(object, field) => bail!("unknown field `{}.{}`", object, field),
+            Expr::Call(function, args) => {
+                let (left, right) = (string(&args[0])?, string(&args[1])?);
+                Value::Bool(match function.as_str() {
+                    "g" => rules.has_role(left, right),
+                    "keyMatch" => key_match(left, right),
+                    "keyMatch2" => key_match2(left, right),
+                    other => bail!("unsupported matcher function `{}`", other),
+                })
+            }
+            Expr::Not(inner) => Value::Bool(!boolean(inner)?),
+            Expr::Eq(left, right) => Value::Bool(
+                self.eval(rules, left, request, policy)?
+                    == self.eval(rules, right, request, policy)?,
+            ),
+            Expr::Ne(left, right) => Value::Bool(
+                self.eval(rules, left, request, policy)?
+                    != self.eval(rules, right, request, policy)?,
+            ),
+            Expr::And(left, right) => Value::Bool(boolean(left)? && boolean(right)?),
+            Expr::Or(left, right) => Value::Bool(boolean(left)? || boolean(right)?),
+        })
+    }
+}
+
+impl Rules {
+    fn has_role(&self, user: &str, role: &str) -> bool {
+        if user == role {
+            return true;
+        }
+        let mut seen = HashSet::new();
+        let mut frontier = vec![user];
+        for _ in 0..MAX_ROLE_DEPTH {
+            let mut next = Vec::new();
+            for member in frontier {
+                for parent in self.roles.get(member).into_iter().flatten() {
+                    if parent == role {
+                        return true;
+                    }
+                    if seen.insert(parent.as_str()) {
+                        next.push(parent.as_str());
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && keyMatch(r.obj, p.obj) && (r.act == p.act || p.act == "*")
+"#;
+
+    const POLICY: &str = "
+# subject, object, action
+p, admin, *, *
+p, reader, /api/books*, GET
+g, alice, reader
+g, root, admin
+";
+
+    fn enforcer() -> Enforcer {
+        Enforcer::from_strs(MODEL, POLICY).unwrap()
+    }
+
+    #[test]
+    fn test_roles_grant_matching_permissions() {
+        let enforcer = enforcer();
+        assert!(enforcer.enforce(&["alice", "/api/books/1", "GET"]).unwrap());
+        assert!(!enforcer
+            .enforce(&["alice", "/api/books/1", "DELETE"])
+            .unwrap());
+        assert!(!enforcer.enforce(&["alice", "/api/users", "GET"]).unwrap());
+        assert!(enforcer.enforce(&["root", "/api/users", "DELETE"]).unwrap());
+        assert!(!enforcer.enforce(&["mallory", "/api/books", "GET"]).unwrap());
+    }
+
+    #[test]
+    fn test_request_arity_must_match_model() {
+        assert!(enforcer().enforce(&["alice", "/api/books"]).is_err());
+    }
+
+    #[test]
+    fn test_malformed_policy_lines_rejected() {
+        let error = Enforcer::from_strs(MODEL, "p, admin, *").unwrap_err();
+        assert!(format!("{:#}", error).contains("expected 3 values"));
+    }
+
+    #[test]
+    fn test_rules_change_enforcement_immediately() {
+        let enforcer = enforcer();
+        let grant = PolicyRule::new("g", ["bob", "reader"]);
+        assert!(!enforcer.enforce(&["bob", "/api/books", "GET"]).unwrap());
+
+        assert!(enforcer.add_rule(&grant).unwrap());
+        assert!(!enforcer.add_rule(&grant).unwrap());
+        assert!(enforcer.enforce(&["bob", "/api/books", "GET"]).unwrap());
+        assert!(enforcer.rules().contains(&grant));
+
+        assert!(enforcer.remove_rule(&grant));
+        assert!(!enforcer.remove_rule(&grant));
+        assert!(!enforcer.enforce(&["bob", "/api/books", "GET"]).unwrap());
+
+        assert!(enforcer
+            .add_rule(&PolicyRule::new("p", ["bob", "/api"]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_repo_config_files_load() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/auth");
+        let enforcer =
+            Enforcer::from_files(root.join("model.conf"), root.join("policy.csv")).unwrap();
+        assert!(enforcer.enforce(&["admin", "/api/books", "POST"]).unwrap());
+        assert!(!enforcer.enforce(&["guest", "/api/books", "POST"]).unwrap());
+    }
+}
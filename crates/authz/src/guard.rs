@@ -0,0 +1,282 @@
+//! Per-handler permission checks
+//!
+//! ```ignore
+//! atlas_authz::permission!(pub BooksWrite = "books:write");
+//!
+//! async fn create_book(
+//!     _: RequirePermission<BooksWrite>,
+//!     Json(book): Json<NewBook>,
+//! ) -> Result<Json<Book>, AppError> { ... }
+//! ```
+
+use std::marker::PhantomData;
+
+use anyhow::anyhow;
+use axum::{
+    extract::{FromRequestParts, OriginalUri},
+    http::request::Parts,
+};
+
+use atlas_http::{error::AppError, tenant::TenantCtx};
+use atlas_kernel::AppContext;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::{Enforcer, Subject};
+
+/// A permission checked by `RequirePermission`, declared with `permission!`
+///
+/// `KEY` is `object:action`; the part after the last `:` is the action, matched against
+/// policies as `p, subject, object, action`.
+pub trait PermissionKey: Send + Sync + 'static {
+    const KEY: &'static str;
+
+    fn object() -> &'static str {
+        Self::KEY
+            .rsplit_once(':')
+            .map_or(Self::KEY, |(object, _)| object)
+    }
+
+    fn action() -> &'static str {
+        Self::KEY.rsplit_once(':').map_or("*", |(_, action)| action)
+    }
+}
+
+/// Declare a marker type usable as `RequirePermission<Name>`
+#[macro_export]
+macro_rules! permission {
+    ($(#[$meta:meta])* $vis:vis $name:ident = $key:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default)]
+        $vis struct $name;
+
+        impl $crate::guard::PermissionKey for $name {
+            const KEY: &'static str = $key;
+        }
+    };
+}
+
+/// Extractor rejecting the request unless the authenticated subject holds `P`
+///
+/// Needs a `Subject` from authentication middleware and the enforcer published by the
+/// authz module, reached through the `AppContext` extension. With a domain model the
+/// request's `TenantCtx` is passed as the domain.
+///
+/// Declare the route in `Module::security` with `permission: Some(P::KEY)` so the
+/// OpenAPI operation documents the check.
+#[derive(Debug, Clone)]
+pub struct RequirePermission<P> {
+    pub subject: Subject,
+    _permission: PhantomData<P>,
+}
+
+impl<S, P> FromRequestParts<S> for RequirePermission<P>
+where
+    S: Send + Sync,
+    P: PermissionKey,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let subject = parts
+            .extensions
+            .get::<Subject>()
+            .cloned()
+            .ok_or_else(|| AppError::unauthorized("authentication required"))?;
+        let enforcer = parts
+            .extensions
+            .get::<AppContext>()
+            .ok_or_else(|| anyhow!("RequirePermission needs the AppContext extension"))?
+            .require::<Enforcer>()?;
+
+        // Domain models (`r = sub, dom, obj, act`) check the permission within the tenant
+        let allowed = if enforcer.request_len() == 4 {
+            let tenant = parts
+                .extensions
+                .get::<TenantCtx>()
+                .ok_or_else(|| AppError::bad_request("tenant required"))?;
+            enforcer.enforce(&[&subject.0, tenant.id.as_str(), P::object(), P::action()])?
+        } else {
+            enforcer.enforce(&[&subject.0, P::object(), P::action()])?
+        };
+        if !allowed {
+            tracing::debug!(subject = %subject.0, permission = P::KEY, "permission denied");
+            let path = parts
+                .extensions
+                .get::<OriginalUri>()
+                .map_or_else(|| parts.uri.path(), |original| original.path());
+            audit::record_for_request(&parts.extensions, |at| {
+                AuditEvent::new("authz.denied", AuditOutcome::Denied, at)
+                    .actor(subject.0.as_str())
+                    .permission(P::KEY)
+                    .resource(path)
+            })
+            .await;
+            return Err(AppError::forbidden(format!(
+                "missing permission '{}'",
+                P::KEY
+            )));
+        }
+        Ok(Self {
+            subject,
+            _permission: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use atlas_kernel::{settings::Settings, Resources};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::post,
+        Extension, Router,
+    };
+    use tower::ServiceExt;
+
+    crate::permission!(BooksWrite = "books:write");
+
+    const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act
+"#;
+
+    async fn status(subject: Option<&str>) -> StatusCode {
+        status_with(Arc::new(Resources::new()), subject).await
+    }
+
+    async fn status_with(resources: Arc<Resources>, subject: Option<&str>) -> StatusCode {
+        resources.insert(
+            Enforcer::from_strs(MODEL, "p, editor, books, write\ng, alice, editor").unwrap(),
+        );
+        let router = Router::new()
+            .route(
+                "/books",
+                post(|guard: RequirePermission<BooksWrite>| async move { guard.subject.0 }),
+            )
+            .layer(Extension(AppContext::new(
+                Arc::new(Settings::default()),
+                resources,
+            )));
+
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/books")
+            .body(Body::empty())
+            .unwrap();
+        if let Some(subject) = subject {
+            request
+                .extensions_mut()
+                .insert(Subject(subject.to_string()));
+        }
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_domain_models_check_within_tenant() {
+        use atlas_http::tenant::TenantCtx;
+        use atlas_kernel::{settings::TenantSource, tenant::TenantId};
+
+        const DOMAIN_MODEL: &str = r#"
+[request_definition]
+r = sub, dom, obj, act
+
+[policy_definition]
+p = sub, dom, obj, act
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = r.sub == p.sub && r.dom == p.dom && r.obj == p.obj && r.act == p.act
+"#;
+        let resources = Arc::new(Resources::new());
+        resources
+            .insert(Enforcer::from_strs(DOMAIN_MODEL, "p, alice, acme, books, write").unwrap());
+        let router = Router::new()
+            .route(
+                "/books",
+                post(|_: RequirePermission<BooksWrite>| async { "ok" }),
+            )
+            .layer(Extension(AppContext::new(
+                Arc::new(Settings::default()),
+                resources,
+            )));
+        let send = |tenant: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/books")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(Subject("alice".to_string()));
+            if let Some(tenant) = tenant {
+                request.extensions_mut().insert(TenantCtx {
+                    id: TenantId::new(tenant).unwrap(),
+                    source: TenantSource::Header,
+                });
+            }
+            router.clone().oneshot(request)
+        };
+
+        assert_eq!(send(Some("acme")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            send(Some("globex")).await.unwrap().status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(send(None).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_key_splits_into_object_and_action() {
+        assert_eq!(BooksWrite::object(), "books");
+        assert_eq!(BooksWrite::action(), "write");
+    }
+
+    #[tokio::test]
+    async fn test_guard_checks_subject_permissions() {
+        assert_eq!(status(Some("alice")).await, StatusCode::OK);
+        assert_eq!(status(Some("bob")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_denials_are_audited() {
+        use crate::audit::{tests::RecordingSink, AuditSink};
+
+        let resources = Arc::new(Resources::new());
+        let sink = Arc::new(RecordingSink::default());
+        resources.insert::<Arc<dyn AuditSink>>(sink.clone());
+
+        assert_eq!(
+            status_with(resources.clone(), Some("alice")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_with(resources, Some("bob")).await,
+            StatusCode::FORBIDDEN
+        );
+
+        assert_eq!(sink.actions(), vec!["authz.denied:denied"]);
+        let event = sink.events.lock().unwrap()[0].clone();
+        assert_eq!(event.actor.as_deref(), Some("bob"));
+        assert_eq!(event.permission.as_deref(), Some("books:write"));
+        assert_eq!(event.resource.as_deref(), Some("/books"));
+    }
+}
@@ -0,0 +1,161 @@
+//! `LdapConnection` over the `ldap3` crate
+
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+
+use super::{LdapConnection, LdapConnector, LdapEntry};
+
+/// How long connecting, including StartTLS, may take
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long one bind or search may take
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result code of a bind with a wrong DN or password (RFC 4511)
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// Connects with the `ldap3` crate
+///
+/// Server certificates are verified against the system's trusted roots, for `ldaps://`
+/// and StartTLS alike.
+#[derive(Debug, Default)]
+pub struct Ldap3Connector;
+
+impl Ldap3Connector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl LdapConnector for Ldap3Connector {
+    async fn connect(&self, url: &str, start_tls: bool) -> anyhow::Result<Box<dyn LdapConnection>> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(CONNECT_TIMEOUT)
+            .set_starttls(start_tls);
+        let (connection, mut ldap) = LdapConnAsync::with_settings(settings, url)
+            .await
+            .with_context(|| format!("failed to connect to {}", url))?;
+        tokio::spawn(async move {
+            if let Err(error) = connection.drive().await {
+                tracing::debug!(error = %error, "LDAP connection closed");
+            }
+        });
+        ldap.with_timeout(OPERATION_TIMEOUT);
+        Ok(Box::new(Ldap3Connection { ldap }))
+    }
+}
+
+struct Ldap3Connection {
+    ldap: Ldap,
+}
+
+#[async_trait]
+impl LdapConnection for Ldap3Connection {
+    async fn simple_bind(&mut self, dn: &str, password: &str) -> anyhow::Result<bool> {
+        let result = self
+            .ldap
+            .simple_bind(dn, password)
+            .await
+            .context("LDAP bind failed")?;
+        match result.rc {
+            0 => Ok(true),
+            INVALID_CREDENTIALS => Ok(false),
+            _ => Err(result.success().unwrap_err()).context("LDAP bind failed"),
+        }
+    }
+
+    async fn search(
+        &mut self,
+        base: &str,
+        filter: &str,
+        attributes: &[&str],
+    ) -> anyhow::Result<Vec<LdapEntry>> {
+        let (entries, _) = self
+            .ldap
+            .search(base, Scope::Subtree, filter, attributes)
+            .await
+            .and_then(|result| result.success())
+            .context("LDAP search failed")?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let entry = SearchEntry::construct(entry);
+                LdapEntry {
+                    dn: entry.dn,
+                    attributes: entry.attrs,
+                }
+            })
+            .collect())
+    }
+}
+
+impl Drop for Ldap3Connection {
+    fn drop(&mut self) {
+        let mut ldap = self.ldap.clone();
+        tokio::spawn(async move {
+            let _ = ldap.unbind().await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// A directory answering every bind with `result_code`
+    async fn serve(result_code: u8) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 256];
+            while let Ok(read) = socket.read(&mut request).await {
+                // LDAPMessage: SEQUENCE, short length, INTEGER messageID, then the operation
+                if read < 7 || request[5] != 0x60 {
+                    break;
+                }
+                let id = request[4];
+                // Same messageID, then BindResponse: resultCode, empty matchedDN and message
+                let mut response = vec![0x30, 0x0c, 0x02, 0x01, id];
+                response.extend_from_slice(&[0x61, 0x07, 0x0a, 0x01, result_code]);
+                response.extend_from_slice(&[0x04, 0x00, 0x04, 0x00]);
+                if socket.write_all(&response).await.is_err() {
+                    break;
+                }
+            }
+        });
+        format!("ldap://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_are_not_an_error() {
+        let url = serve(49).await;
+        let mut connection = Ldap3Connector::new().connect(&url, false).await.unwrap();
+
+        let bound = connection
+            .simple_bind("uid=ada,ou=people,dc=example,dc=com", "wrong")
+            .await
+            .unwrap();
+
+        assert!(!bound);
+    }
+
+    #[tokio::test]
+    async fn test_other_bind_failures_are_errors() {
+        // unavailable
+        let url = serve(52).await;
+        let mut connection = Ldap3Connector::new().connect(&url, false).await.unwrap();
+
+        assert!(connection
+            .simple_bind("uid=ada,ou=people,dc=example,dc=com", "secret")
+            .await
+            .is_err());
+    }
+}
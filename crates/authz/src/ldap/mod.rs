@@ -0,0 +1,371 @@
+//! Password login against LDAP or Active Directory
+//!
+//! `LdapAuthenticator` is a `CredentialVerifier`: it finds the user's entry with the
+//! configured filter, binds as that entry with the submitted password, and syncs the
+//! user's role assignments from their directory groups through `[auth.ldap.group_roles]`.
+//!
+//! With the `ldap` feature, `Ldap3Connector` connects with the `ldap3` crate.
+
+#[cfg(feature = "ldap")]
+mod client;
+mod module;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+
+use atlas_kernel::settings::LdapSettings;
+
+use crate::policy::Roles;
+use crate::sessions::CredentialVerifier;
+
+#[cfg(feature = "ldap")]
+pub use client::Ldap3Connector;
+pub use module::{create_module, LdapModule};
+
+/// Opens connections to a directory server
+///
+/// Implementations must verify the server certificate for `ldaps://` and StartTLS.
+#[async_trait]
+pub trait LdapConnector: Send + Sync {
+    /// Connect to `url`, upgrading with StartTLS first when `start_tls` is set
+    async fn connect(&self, url: &str, start_tls: bool) -> anyhow::Result<Box<dyn LdapConnection>>;
+}
+
+/// An open directory connection; dropping it unbinds
+#[async_trait]
+pub trait LdapConnection: Send {
+    /// Simple bind, returning `false` when the server rejects the credentials
+    async fn simple_bind(&mut self, dn: &str, password: &str) -> anyhow::Result<bool>;
+
+    /// Subtree search under `base`, returning `attributes` of every matching entry
+    async fn search(
+        &mut self,
+        base: &str,
+        filter: &str,
+        attributes: &[&str],
+    ) -> anyhow::Result<Vec<LdapEntry>>;
+}
+
+/// A directory entry with the requested attributes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LdapEntry {
+    pub dn: String,
+    pub attributes: HashMap<String, Vec<String>>,
+}
+
+impl LdapEntry {
+    /// Values of an attribute; names compare case-insensitively, as LDAP does
+    pub fn values(&self, attribute: &str) -> &[String] {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(attribute))
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Escape a value for use inside a search filter (RFC 4515)
+pub fn escape_filter_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '*' | '(' | ')' | '\\' | '\0' => escaped.push_str(&format!("\\{:02x}", c as u8)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Verifies passwords by binding to the directory as the user
+pub struct LdapAuthenticator {
+    connector: Arc<dyn LdapConnector>,
+    roles: Option<Arc<Roles>>,
+    settings: LdapSettings,
+}
+
+impl LdapAuthenticator {
+    /// `roles` is required for `group_roles` to take effect
+    pub fn new(
+        connector: Arc<dyn LdapConnector>,
+        roles: Option<Arc<Roles>>,
+        settings: LdapSettings,
+    ) -> Self {
+        Self {
+            connector,
+            roles,
+            settings,
+        }
+    }
+
+    async fn find_user(
+        &self,
+        connection: &mut dyn LdapConnection,
+        username: &str,
+    ) -> anyhow::Result<Option<LdapEntry>> {
+        let settings = &self.settings;
+        if let (Some(dn), Some(password)) = (&settings.bind_dn, &settings.bind_password) {
+            if !connection.simple_bind(dn, password).await? {
+                bail!("LDAP service bind as '{}' was rejected", dn);
+            }
+        }
+
+        let filter = settings
+            .user_filter
+            .replace("{username}", &escape_filter_value(username));
+        let attributes = [
+            settings.subject_attribute.as_str(),
+            settings.group_attribute.as_str(),
+        ];
+        let mut entries = connection
+            .search(&settings.user_base_dn, &filter, &attributes)
+            .await
+            .context("LDAP user search failed")?;
+        if entries.len() > 1 {
+            tracing::warn!(
+                username,
+                matches = entries.len(),
+                "LDAP user filter matched several entries; refusing login"
+            );
+            return Ok(None);
+        }
+        Ok(entries.pop())
+    }
+
+    /// Assign the roles mapped from the user's groups and drop mapped roles they lost
+    async fn sync_roles(&self, subject: &str, entry: &LdapEntry) -> anyhow::Result<()> {
+        let Some(roles) = &self.roles else {
+            return Ok(());
+        };
+        let groups = entry.values(&self.settings.group_attribute);
+        let mut granted: Vec<&str> = Vec::new();
+        let mut mapped: Vec<&str> = Vec::new();
+        for (group, group_roles) in &self.settings.group_roles {
+            let member = groups.iter().any(|dn| dn.eq_ignore_ascii_case(group));
+            for role in group_roles {
+                mapped.push(role);
+                if member {
+                    granted.push(role);
+                }
+            }
+        }
+        for role in &mapped {
+            if granted.contains(role) {
+                roles.assign(role, subject).await?;
+            } else {
+                roles.unassign(role, subject).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CredentialVerifier for LdapAuthenticator {
+    async fn verify(&self, username: &str, password: &str) -> anyhow::Result<Option<String>> {
+        // An empty password makes most servers perform an unauthenticated bind, which succeeds
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let mut connection = self
+            .connector
+            .connect(&self.settings.url, self.settings.start_tls)
+            .await
+            .context("failed to connect to the LDAP server")?;
+        let Some(entry) = self.find_user(connection.as_mut(), username).await? else {
+            return Ok(None);
+        };
+        if !connection.simple_bind(&entry.dn, password).await? {
+            return Ok(None);
+        }
+
+        let id = entry
+            .values(&self.settings.subject_attribute)
+            .first()
+            .map(String::as_str)
+            .unwrap_or(username);
+        let subject = format!("ldap:{}", id);
+        self.sync_roles(&subject, &entry)
+            .await
+            .context("failed to sync LDAP group roles")?;
+        Ok(Some(subject))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::policy::{tests::MODEL, MemoryPolicyStore, MemoryRoleStore, Policies};
+    use crate::Enforcer;
+
+    pub(crate) const ADMINS: &str = "cn=Admins,ou=groups,dc=example,dc=com";
+    pub(crate) const EDITORS: &str = "cn=editors,ou=groups,dc=example,dc=com";
+    const ADA: &str = "uid=ada,ou=people,dc=example,dc=com";
+
+    /// A directory with one user, `ada`/`secret`, and a service account
+    #[derive(Default)]
+    pub(crate) struct FakeDirectory {
+        pub(crate) groups: Mutex<Vec<String>>,
+        pub(crate) filters: Mutex<Vec<String>>,
+    }
+
+    struct FakeConnection {
+        directory: Arc<FakeDirectory>,
+        bound: bool,
+    }
+
+    #[async_trait]
+    impl LdapConnector for Arc<FakeDirectory> {
+        async fn connect(
+            &self,
+            _url: &str,
+            _start_tls: bool,
+        ) -> anyhow::Result<Box<dyn LdapConnection>> {
+            Ok(Box::new(FakeConnection {
+                directory: self.clone(),
+                bound: false,
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl LdapConnection for FakeConnection {
+        async fn simple_bind(&mut self, dn: &str, password: &str) -> anyhow::Result<bool> {
+            self.bound = matches!(
+                (dn, password),
+                (ADA, "secret") | ("cn=atlas,dc=example,dc=com", "service")
+            );
+            Ok(self.bound)
+        }
+
+        async fn search(
+            &mut self,
+            _base: &str,
+            filter: &str,
+            _attributes: &[&str],
+        ) -> anyhow::Result<Vec<LdapEntry>> {
+            if !self.bound {
+                bail!("anonymous search is disabled");
+            }
+            self.directory
+                .filters
+                .lock()
+                .unwrap()
+                .push(filter.to_string());
+            if filter != "(&(objectClass=person)(uid=ada))" {
+                return Ok(Vec::new());
+            }
+            Ok(vec![LdapEntry {
+                dn: ADA.to_string(),
+                attributes: HashMap::from([
+                    ("uid".to_string(), vec!["ada".to_string()]),
+                    (
+                        "memberOf".to_string(),
+                        self.directory.groups.lock().unwrap().clone(),
+                    ),
+                ]),
+            }])
+        }
+    }
+
+    pub(crate) fn settings() -> LdapSettings {
+        LdapSettings {
+            enabled: true,
+            bind_dn: Some("cn=atlas,dc=example,dc=com".to_string()),
+            bind_password: Some("service".to_string()),
+            user_base_dn: "ou=people,dc=example,dc=com".to_string(),
+            group_roles: HashMap::from([
+                (ADMINS.to_string(), vec!["admin".to_string()]),
+                (EDITORS.to_string(), vec!["editor".to_string()]),
+            ]),
+            ..LdapSettings::default()
+        }
+    }
+
+    fn authenticator() -> (LdapAuthenticator, Arc<FakeDirectory>, Arc<Policies>) {
+        let directory = Arc::new(FakeDirectory::default());
+        let enforcer = Arc::new(Enforcer::from_strs(MODEL, "").unwrap());
+        let policies = Arc::new(Policies::new(
+            enforcer,
+            Arc::new(MemoryPolicyStore::default()),
+        ));
+        let roles = Arc::new(Roles::new(
+            Arc::new(MemoryRoleStore::new()),
+            policies.clone(),
+        ));
+        let authenticator =
+            LdapAuthenticator::new(Arc::new(directory.clone()), Some(roles), settings());
+        (authenticator, directory, policies)
+    }
+
+    fn assigned(policies: &Policies, subject: &str) -> Vec<String> {
+        let mut roles: Vec<String> = policies
+            .list()
+            .into_iter()
+            .filter(|rule| rule.ptype == "g" && rule.values[0] == subject)
+            .map(|rule| rule.values[1].clone())
+            .collect();
+        roles.sort();
+        roles
+    }
+
+    #[tokio::test]
+    async fn test_bind_as_user_and_map_groups_to_roles() {
+        let (authenticator, directory, policies) = authenticator();
+        *directory.groups.lock().unwrap() = vec![
+            "CN=admins,OU=groups,DC=example,DC=com".to_string(),
+            EDITORS.to_string(),
+        ];
+
+        let subject = authenticator.verify("ada", "secret").await.unwrap();
+
+        assert_eq!(subject.as_deref(), Some("ldap:ada"));
+        assert_eq!(assigned(&policies, "ldap:ada"), vec!["admin", "editor"]);
+
+        *directory.groups.lock().unwrap() = vec![EDITORS.to_string()];
+        authenticator.verify("ada", "secret").await.unwrap();
+        assert_eq!(assigned(&policies, "ldap:ada"), vec!["editor"]);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_or_empty_password_and_unknown_user_fail() {
+        let (authenticator, directory, policies) = authenticator();
+
+        assert_eq!(authenticator.verify("ada", "wrong").await.unwrap(), None);
+        assert_eq!(authenticator.verify("ada", "").await.unwrap(), None);
+        assert_eq!(authenticator.verify("bob", "secret").await.unwrap(), None);
+        assert!(assigned(&policies, "ldap:ada").is_empty());
+        assert_eq!(directory.filters.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_username_is_escaped_in_filter() {
+        let (authenticator, directory, _) = authenticator();
+
+        let subject = authenticator.verify("*)(uid=*", "secret").await.unwrap();
+
+        assert_eq!(subject, None);
+        assert_eq!(
+            directory.filters.lock().unwrap()[0],
+            "(&(objectClass=person)(uid=\\2a\\29\\28uid=\\2a))"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_service_bind_is_an_error() {
+        let (mut authenticator, _, _) = authenticator();
+        authenticator.settings.bind_password = Some("stale".to_string());
+
+        assert!(authenticator.verify("ada", "secret").await.is_err());
+    }
+
+    #[test]
+    fn test_escape_filter_value_keeps_unicode() {
+        assert_eq!(escape_filter_value("zoë\\(x)"), "zoë\\5c\\28x\\29");
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use atlas_kernel::{InitCtx, Module};
+
+use super::{LdapAuthenticator, LdapConnector};
+use crate::policy::Roles;
+use crate::sessions::CredentialVerifier;
+
+/// Core module checking session logins against `[auth.ldap]`
+///
+/// It mounts no routes: it publishes an `LdapAuthenticator` as the `CredentialVerifier`
+/// used by `/api/sessions/login`, and does nothing unless `auth.ldap.enabled` is set.
+/// It connects through the registered `Arc<dyn LdapConnector>` or, with the `ldap`
+/// feature, `Ldap3Connector`.
+#[derive(Default)]
+pub struct LdapModule;
+
+#[async_trait]
+impl Module for LdapModule {
+    fn name(&self) -> &'static str {
+        "ldap"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("LDAP and Active Directory password login")
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["authz"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.auth.ldap;
+        if !settings.enabled {
+            tracing::debug!("LDAP login disabled");
+            return Ok(());
+        }
+
+        if ctx.resources.contains::<Arc<dyn CredentialVerifier>>() {
+            tracing::warn!(
+                "auth.ldap is enabled but a CredentialVerifier is already registered; keeping it"
+            );
+            return Ok(());
+        }
+        let connector = match ctx.resources.get::<Arc<dyn LdapConnector>>() {
+            Some(connector) => connector.as_ref().clone(),
+            #[cfg(feature = "ldap")]
+            None => Arc::new(super::Ldap3Connector::new()),
+            #[cfg(not(feature = "ldap"))]
+            None => anyhow::bail!(
+                "auth.ldap is enabled but no LdapConnector resource is registered; \
+                 register one or enable the ldap feature"
+            ),
+        };
+        let roles = ctx.resources.get::<Roles>();
+        if roles.is_none() && !settings.group_roles.is_empty() {
+            tracing::warn!("no roles are published; auth.ldap.group_roles is ignored");
+        }
+
+        tracing::info!(
+            url = %settings.url,
+            groups = settings.group_roles.len(),
+            "LDAP login enabled"
+        );
+        ctx.resources
+            .insert::<Arc<dyn CredentialVerifier>>(Arc::new(LdapAuthenticator::new(
+                connector,
+                roles,
+                settings.clone(),
+            )));
+        Ok(())
+    }
+}
+
+/// Create a new instance of the LDAP module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(LdapModule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ldap::tests::{settings, FakeDirectory, EDITORS};
+    use atlas_kernel::settings::Settings;
+    use atlas_kernel::ModuleRegistry;
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry.register_core(crate::create_module()).unwrap();
+        registry
+    }
+
+    fn ctx_settings() -> Arc<Settings> {
+        let config = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/auth");
+        let mut settings = Settings::default();
+        settings.auth.casbin_model_path = config.join("model.conf").display().to_string();
+        settings.auth.casbin_policy_path = config.join("policy.csv").display().to_string();
+        settings.auth.ldap = self::settings();
+        Arc::new(settings)
+    }
+
+    #[cfg(not(feature = "ldap"))]
+    #[tokio::test]
+    async fn test_enabled_ldap_requires_connector() {
+        let registry = registry();
+        let error = registry
+            .init_core_modules(&registry.init_ctx(ctx_settings()))
+            .await
+            .unwrap_err();
+        let error = anyhow::Error::from(error);
+        assert!(format!("{:#}", error).contains("LdapConnector"));
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_credential_verifier() {
+        let registry = registry();
+        let directory = Arc::new(FakeDirectory::default());
+        *directory.groups.lock().unwrap() = vec![EDITORS.to_string()];
+        registry
+            .resources()
+            .insert::<Arc<dyn LdapConnector>>(Arc::new(directory));
+
+        registry
+            .init_core_modules(&registry.init_ctx(ctx_settings()))
+            .await
+            .unwrap();
+
+        let verifier = registry
+            .resources()
+            .require::<Arc<dyn CredentialVerifier>>()
+            .unwrap();
+        assert_eq!(
+            verifier.verify("ada", "secret").await.unwrap().as_deref(),
+            Some("ldap:ada")
+        );
+        let enforcer = registry.resources().require::<crate::Enforcer>().unwrap();
+        assert!(enforcer.has_role("ldap:ada", "editor"));
+    }
+}
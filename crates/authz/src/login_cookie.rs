@@ -0,0 +1,85 @@
+//! Binding logins in progress to the browser that started them
+//!
+//! Starting an OAuth or SAML login sets a short-lived HttpOnly cookie naming the login;
+//! its callback is refused unless the browser sends that cookie back, so a login started
+//! in one browser cannot be completed in another (login CSRF).
+
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::http::{header::COOKIE, HeaderMap, HeaderValue};
+
+use crate::token;
+
+/// Cookie of OAuth logins, holding the `state` sent to the provider
+pub(crate) const OAUTH: LoginCookie = LoginCookie {
+    name: "atlas_oauth_state",
+    cross_site: false,
+};
+
+/// Cookie of SAML logins, holding the id of the `AuthnRequest`
+///
+/// Identity providers POST responses from their own site, so the cookie must be sent on
+/// cross-site requests.
+pub(crate) const SAML: LoginCookie = LoginCookie {
+    name: "atlas_saml_request",
+    cross_site: true,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LoginCookie {
+    name: &'static str,
+    cross_site: bool,
+}
+
+impl LoginCookie {
+    /// `Set-Cookie` value binding the login `value` to the browser for `ttl`
+    pub(crate) fn set(
+        &self,
+        value: &str,
+        ttl: Duration,
+        secure: bool,
+    ) -> anyhow::Result<HeaderValue> {
+        self.render(value, ttl.as_secs(), secure)
+    }
+
+    /// `Set-Cookie` value telling the browser to drop the cookie once the login is over
+    pub(crate) fn clear(&self, secure: bool) -> anyhow::Result<HeaderValue> {
+        self.render("", 0, secure)
+    }
+
+    /// Whether the request carries the cookie with exactly `expected`
+    pub(crate) fn matches(&self, headers: &HeaderMap, expected: &str) -> bool {
+        cookie_value(headers, self.name)
+            .is_some_and(|sent| token::constant_time_eq(sent.as_bytes(), expected.as_bytes()))
+    }
+
+    /// The value the request carries, if any
+    pub(crate) fn value(&self, headers: &HeaderMap) -> Option<String> {
+        cookie_value(headers, self.name)
+    }
+
+    fn render(&self, value: &str, max_age: u64, secure: bool) -> anyhow::Result<HeaderValue> {
+        let same_site = if self.cross_site { "None" } else { "Lax" };
+        let mut cookie = format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite={}",
+            self.name, value, max_age, same_site
+        );
+        if secure || self.cross_site {
+            cookie.push_str("; Secure");
+        }
+        HeaderValue::from_str(&cookie).context("invalid login cookie")
+    }
+}
+
+/// Value of the cookie `name` sent with a request, if any
+pub(crate) fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value.to_string())
+}
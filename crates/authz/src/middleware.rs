@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_http::error::AppError;
+use atlas_kernel::AppContext;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::Enforcer;
+
+/// Authenticated caller, inserted into request extensions by authentication middleware
+///
+/// Handlers that act on the caller's own data take it as an extractor, which rejects
+/// unauthenticated requests with 401.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subject(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for Subject {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Subject>()
+            .cloned()
+            .ok_or_else(|| AppError::unauthorized("authentication required"))
+    }
+}
+
+/// Route middleware allowing a request only if a policy grants `(subject, path, method)`
+///
+/// Install with `axum::middleware::from_fn_with_state(enforcer, atlas_authz::authorize)`
+/// after the layer that authenticates the caller.
+pub async fn authorize(
+    State(enforcer): State<Arc<Enforcer>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let subject = request
+        .extensions()
+        .get::<Subject>()
+        .ok_or_else(|| AppError::unauthorized("authentication required"))?;
+    // Nested module routers see a stripped path; policies are written against the full one
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| request.uri().path(), |original| original.path());
+
+    let allowed = enforcer.enforce(&[&subject.0, path, request.method().as_str()])?;
+    if !allowed {
+        tracing::debug!(subject = %subject.0, path, method = %request.method(), "request denied by policy");
+        // The request body is not `Sync`, so nothing borrowed from the request crosses the await
+        let (actor, method, path) = (
+            subject.0.clone(),
+            request.method().clone(),
+            path.to_string(),
+        );
+        let ctx = request.extensions().get::<AppContext>().cloned();
+        audit::record_in(ctx.as_ref(), |at| {
+            AuditEvent::new("authz.denied", AuditOutcome::Denied, at)
+                .actor(actor)
+                .permission(method.as_str())
+                .resource(path)
+        })
+        .await;
+        return Err(AppError::forbidden("not allowed by authorization policy"));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::StatusCode,
+        routing::{delete, get},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = r.sub == p.sub && keyMatch2(r.obj, p.obj) && r.act == p.act
+"#;
+
+    fn router() -> Router {
+        let enforcer =
+            Arc::new(Enforcer::from_strs(MODEL, "p, alice, /api/books/:id, GET").unwrap());
+        let books = Router::new()
+            .route("/{id}", get(|| async { "book" }))
+            .route("/{id}", delete(|| async { "deleted" }))
+            .route_layer(axum::middleware::from_fn_with_state(enforcer, authorize));
+        Router::new().nest("/api/books", books)
+    }
+
+    async fn status(subject: Option<&str>, method: &str) -> StatusCode {
+        let mut request = Request::builder()
+            .method(method)
+            .uri("/api/books/1")
+            .body(Body::empty())
+            .unwrap();
+        if let Some(subject) = subject {
+            request
+                .extensions_mut()
+                .insert(Subject(subject.to_string()));
+        }
+        router().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_requests_checked_against_full_path() {
+        assert_eq!(status(Some("alice"), "GET").await, StatusCode::OK);
+        assert_eq!(status(Some("alice"), "DELETE").await, StatusCode::FORBIDDEN);
+        assert_eq!(status(Some("bob"), "GET").await, StatusCode::FORBIDDEN);
+        assert_eq!(status(None, "GET").await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,459 @@
+//! Casbin model files (`model.conf`) and their matcher expressions
+//!
+//! Supports the subset ATLAS uses: one request and policy definition, an optional
+//! `g = _, _` role definition, the `some(where (p.eft == allow))` effect, and matchers
+//! built from `==`, `!=`, `&&`, `||`, `!`, parentheses, string literals, `g(..)`,
+//! `keyMatch(..)` and `keyMatch2(..)`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context};
+
+const ALLOW_EFFECT: &str = "some(where (p.eft == allow))";
+
+/// A parsed Casbin model
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub(crate) request_fields: Vec<String>,
+    pub(crate) policy_fields: Vec<String>,
+    pub(crate) has_roles: bool,
+    pub(crate) matcher: Expr,
+}
+
+impl Model {
+    /// Parse the text of a `model.conf`
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let sections = parse_sections(text)?;
+        let entry = |section: &str, key: &str| -> anyhow::Result<&str> {
+            sections
+                .get(section)
+                .and_then(|entries| entries.get(key))
+                .map(String::as_str)
+                .ok_or_else(|| anyhow!("model is missing `{}` in [{}]", key, section))
+        };
+
+        let request_fields = field_names(entry("request_definition", "r")?);
+        let policy_fields = field_names(entry("policy_definition", "p")?);
+
+        let effect = entry("policy_effect", "e")?;
+        if effect.split_whitespace().collect::<String>()
+            != ALLOW_EFFECT.split_whitespace().collect::<String>()
+        {
+            bail!(
+                "unsupported policy effect `{}`; only `{}` is supported",
+                effect,
+                ALLOW_EFFECT
+            );
+        }
+
+        let has_roles = match sections
+            .get("role_definition")
+            .and_then(|entries| entries.get("g"))
+        {
+            Some(definition) if field_names(definition).len() == 2 => true,
+            Some(definition) => bail!(
+                "unsupported role definition `g = {}`; only `g = _, _` is supported",
+                definition
+            ),
+            None => false,
+        };
+
+        let matcher = Parser::new(entry("matchers", "m")?)?
+            .parse()
+            .context("invalid matcher")?;
+        let mut model = Self {
+            request_fields,
+            policy_fields,
+            has_roles,
+            matcher: Expr::Literal(String::new()),
+        };
+        model.matcher = model.resolve(matcher);
+        model.check(&model.matcher)?;
+        Ok(model)
+    }
+
+    /// Reject references to undefined fields or functions when the model is loaded
+    fn check(&self, expr: &Expr) -> anyhow::Result<()> {
+        match expr {
+            Expr::Literal(_) | Expr::Request(_) | Expr::Policy(_) => Ok(()),
+            Expr::Field(object, field) => bail!("unknown field `{}.{}`", object, field),
+            Expr::Call(function, args) => {
+                if args.len() != 2 {
+                    bail!("`{}` takes 2 arguments, got {}", function, args.len());
+                }
+                match function.as_str() {
+                    "keyMatch" | "keyMatch2" => {}
+                    "g" if self.has_roles => {}
+                    "g" => bail!("matcher uses `g` but the model has no [role_definition]"),
+                    other => bail!("unsupported matcher function `{}`", other),
+                }
+                args.iter().try_for_each(|arg| self.check(arg))
+            }
+            Expr::Not(inner) => self.check(inner),
+            Expr::Eq(left, right)
+            | Expr::Ne(left, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right) => {
+                self.check(left)?;
+                self.check(right)
+            }
+        }
+    }
+
+    /// Resolve `r.x`/`p.x` references to field positions
+    fn resolve(&self, expr: Expr) -> Expr {
+        let resolve = |expr: Box<Expr>| Box::new(self.resolve(*expr));
+        match expr {
+            Expr::Field(object, field) => {
+                let fields = match object.as_str() {
+                    "r" => &self.request_fields,
+                    "p" => &self.policy_fields,
+                    _ => return Expr::Field(object, field),
+                };
+                match fields.iter().position(|name| *name == field) {
+                    Some(index) if object == "r" => Expr::Request(index),
+                    Some(index) => Expr::Policy(index),
+                    None => Expr::Field(object, field),
+                }
+            }
+            Expr::Call(function, args) => Expr::Call(
+                function,
+                args.into_iter().map(|arg| self.resolve(arg)).collect(),
+            ),
+            Expr::Not(inner) => Expr::Not(resolve(inner)),
+            Expr::Eq(left, right) => Expr::Eq(resolve(left), resolve(right)),
+            Expr::Ne(left, right) => Expr::Ne(resolve(left), resolve(right)),
+            Expr::And(left, right) => Expr::And(resolve(left), resolve(right)),
+            Expr::Or(left, right) => Expr::Or(resolve(left), resolve(right)),
+            other => other,
+        }
+    }
+}
+
+fn field_names(definition: &str) -> Vec<String> {
+    definition
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+fn parse_sections(text: &str) -> anyhow::Result<HashMap<String, HashMap<String, String>>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            current = Some(name.trim().to_string());
+            continue;
+        }
+        let section = current
+            .clone()
+            .ok_or_else(|| anyhow!("line {}: entry outside of a [section]", number + 1))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("line {}: expected `key = value`", number + 1))?;
+        sections
+            .entry(section)
+            .or_default()
+            .insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(sections)
+}
+
+/// Matcher expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Literal(String),
+    /// Unresolved `object.field` reference
+    Field(String, String),
+    Request(usize),
+    Policy(usize),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+    Ne(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Dot,
+    Comma,
+    LParen,
+    RParen,
+    Eq,
+    Ne,
+    And,
+    Or,
+    Not,
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '.' | ',' | '(' | ')' => {
+                chars.next();
+                tokens.push(match c {
+                    '.' => Token::Dot,
+                    ',' => Token::Comma,
+                    '(' => Token::LParen,
+                    _ => Token::RParen,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => literal.push(c),
+                        None => bail!("unterminated string literal"),
+                    }
+                }
+                tokens.push(Token::Str(literal));
+            }
+            '=' | '!' | '&' | '|' => {
+                chars.next();
+                let pair = (c, chars.peek().copied());
+                let token = match pair {
+                    ('=', Some('=')) => Token::Eq,
+                    ('!', Some('=')) => Token::Ne,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    ('!', _) => {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                    _ => bail!("unexpected `{}`", c),
+                };
+                chars.next();
+                tokens.push(token);
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        ident.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            other => bail!("unexpected `{}`", other),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            tokens: tokenize(input)?,
+            position: 0,
+        })
+    }
+
+    fn parse(mut self) -> anyhow::Result<Expr> {
+        let expr = self.or()?;
+        match self.tokens.get(self.position) {
+            None => Ok(expr),
+            Some(token) => bail!("unexpected {:?}", token),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.tokens.get(self.position) == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> anyhow::Result<()> {
+        if self.eat(&expected) {
+            Ok(())
+        } else {
+            bail!(
+                "expected {:?}, found {:?}",
+                expected,
+                self.tokens.get(self.position)
+            )
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.or()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        if self.eat(&Token::Eq) {
+            Ok(Expr::Eq(Box::new(left), Box::new(self.operand()?)))
+        } else if self.eat(&Token::Ne) {
+            Ok(Expr::Ne(Box::new(left), Box::new(self.operand()?)))
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn operand(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::Str(literal)) => Ok(Expr::Literal(literal)),
+            Some(Token::Ident(name)) if self.eat(&Token::Dot) => match self.next() {
+                Some(Token::Ident(field)) => Ok(Expr::Field(name, field)),
+                other => bail!("expected a field name after `{}.`, found {:?}", name, other),
+            },
+            Some(Token::Ident(name)) if self.eat(&Token::LParen) => {
+                let mut args = Vec::new();
+                if !self.eat(&Token::RParen) {
+                    loop {
+                        args.push(self.or()?);
+                        if self.eat(&Token::RParen) {
+                            break;
+                        }
+                        self.expect(Token::Comma)?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            other => bail!("expected an operand, found {:?}", other),
+        }
+    }
+}
+
+/// Casbin `keyMatch`: `*` in `pattern` matches any suffix
+pub(crate) fn key_match(key: &str, pattern: &str) -> bool {
+    match pattern.find('*') {
+        Some(star) => key.starts_with(&pattern[..star]),
+        None => key == pattern,
+    }
+}
+
+/// Casbin `keyMatch2`: `:name` segments match one path segment, `*` any suffix
+pub(crate) fn key_match2(key: &str, pattern: &str) -> bool {
+    let mut keys = key.split('/');
+    let mut patterns = pattern.split('/');
+    loop {
+        match (keys.next(), patterns.next()) {
+            (_, Some("*")) => return true,
+            (Some(key), Some(pattern)) if pattern.starts_with(':') && !key.is_empty() => {}
+            (Some(key), Some(pattern)) if key == pattern => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && keyMatch(r.obj, p.obj) && (r.act == p.act || p.act == "*")
+"#;
+
+    #[test]
+    fn test_parse_model_with_roles() {
+        let model = Model::parse(MODEL).unwrap();
+        assert_eq!(model.request_fields, vec!["sub", "obj", "act"]);
+        assert!(model.has_roles);
+        assert!(matches!(model.matcher, Expr::And(_, _)));
+    }
+
+    #[test]
+    fn test_unknown_fields_and_functions_rejected() {
+        let unknown_field = MODEL.replace("r.act == p.act", "r.action == p.act");
+        assert!(Model::parse(&unknown_field)
+            .unwrap_err()
+            .to_string()
+            .contains("r.action"));
+
+        let unknown_function = MODEL.replace("keyMatch(", "regexMatch(");
+        assert!(Model::parse(&unknown_function)
+            .unwrap_err()
+            .to_string()
+            .contains("regexMatch"));
+    }
+
+    #[test]
+    fn test_unsupported_effect_rejected() {
+        let deny = MODEL.replace(
+            "some(where (p.eft == allow))",
+            "!some(where (p.eft == deny))",
+        );
+        assert!(Model::parse(&deny).is_err());
+    }
+
+    #[test]
+    fn test_key_matching() {
+        assert!(key_match("/api/books/1", "/api/books/*"));
+        assert!(key_match("/anything", "*"));
+        assert!(!key_match("/api/users", "/api/books/*"));
+
+        assert!(key_match2("/api/books/1", "/api/books/:id"));
+        assert!(!key_match2("/api/books/1/reviews", "/api/books/:id"));
+        assert!(key_match2("/api/books/1/reviews", "/api/books/*"));
+    }
+}
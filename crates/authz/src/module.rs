@@ -0,0 +1,404 @@
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SecurityScheme};
+
+use crate::policy::{
+    policy_routes, role_routes, MemoryPolicyStore, MemoryRoleStore, Policies, PolicyRule,
+    PolicyStore, RoleStore, Roles, SurrealPolicyStore,
+};
+use crate::{Enforcer, Model};
+
+/// Core module loading the enforcer from `[auth]` settings
+///
+/// The enforcer is published as a shared resource, so modules initialized later fetch it
+/// with `ctx.resources.require::<Enforcer>()`. Rules come from a registered `PolicyStore`,
+/// seeded from the CSV file on first boot, and can be edited under `/api/authz/policies`
+/// and `/api/authz/roles` when `admin.token` is set.
+#[derive(Default)]
+pub struct AuthzModule {
+    policies: OnceLock<Arc<Policies>>,
+    roles: OnceLock<Arc<Roles>>,
+    admin_token: OnceLock<Option<String>>,
+}
+
+#[async_trait]
+impl Module for AuthzModule {
+    fn name(&self) -> &'static str {
+        "authz"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Casbin policy enforcement")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let auth = &ctx.settings.auth;
+        let (model_path, policy_path) = (auth.casbin_model_file(), auth.casbin_policy_file());
+        let model = std::fs::read_to_string(&model_path)
+            .with_context(|| format!("failed to read casbin model {}", model_path.display()))?;
+        let model = Model::parse(&model)
+            .with_context(|| format!("invalid casbin model in {}", model_path.display()))?;
+        let file_rules = || -> anyhow::Result<Vec<PolicyRule>> {
+            let policy = std::fs::read_to_string(&policy_path).with_context(|| {
+                format!("failed to read casbin policy {}", policy_path.display())
+            })?;
+            PolicyRule::parse_csv(&policy)
+                .with_context(|| format!("invalid casbin policy in {}", policy_path.display()))
+        };
+
+        let store = atlas_db::select_store::<dyn PolicyStore>(
+            &ctx.resources,
+            "casbin_rule",
+            |db| Arc::new(SurrealPolicyStore::new(db)),
+            || Arc::new(MemoryPolicyStore::default()),
+        );
+        let mut rules = store.load().await.context("failed to load policies")?;
+        if rules.is_empty() {
+            rules = file_rules()?;
+            for rule in &rules {
+                store.add(rule).await.context("failed to seed policies")?;
+            }
+            tracing::info!(
+                policy = %policy_path.display(),
+                rules = rules.len(),
+                "seeded empty policy store from file"
+            );
+        }
+
+        let enforcer = Arc::new(Enforcer::new(model, rules)?);
+        ctx.resources.insert_arc(enforcer.clone());
+        let policies = Arc::new(Policies::new(enforcer, store));
+        ctx.resources.insert_arc(policies.clone());
+        tracing::info!(
+            model = %model_path.display(),
+            policy = %policy_path.display(),
+            "authorization policies loaded"
+        );
+
+        let role_store = match ctx.resources.get::<Arc<dyn RoleStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => Arc::new(MemoryRoleStore::new()) as Arc<dyn RoleStore>,
+        };
+        let roles = Arc::new(Roles::new(role_store, policies.clone()));
+        ctx.resources.insert_arc(roles.clone());
+
+        self.policies
+            .set(policies)
+            .map_err(|_| anyhow::anyhow!("authz module initialized twice"))?;
+        let _ = self.roles.set(roles);
+        self.admin_token
+            .get_or_init(|| ctx.settings.admin.token.clone());
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match (
+            self.policies.get(),
+            self.admin_token.get().cloned().flatten(),
+        ) {
+            (Some(policies), Some(admin_token)) => {
+                let router =
+                    Router::new().nest("/policies", policy_routes(policies.clone(), &admin_token));
+                match self.roles.get() {
+                    Some(roles) => router.nest("/roles", role_routes(roles.clone(), &admin_token)),
+                    None => router,
+                }
+            }
+            _ => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let rule = json!({
+            "type": "object",
+            "required": ["ptype", "values"],
+            "properties": {
+                "ptype": { "type": "string", "enum": ["p", "g"] },
+                "values": { "type": "array", "items": { "type": "string" } }
+            },
+            "example": { "ptype": "p", "values": ["editor", "/api/books/*", "PUT"] }
+        });
+        let body = json!({
+            "required": true,
+            "content": { "application/json": { "schema": rule.clone() } }
+        });
+        let role = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string", "nullable": true }
+            }
+        });
+        let permission = json!({
+            "type": "object",
+            "required": ["object", "action"],
+            "properties": {
+                "object": { "type": "string", "example": "/api/books/*" },
+                "action": { "type": "string", "example": "PUT" }
+            }
+        });
+        let role_details = json!({
+            "allOf": [
+                role.clone(),
+                {
+                    "type": "object",
+                    "properties": {
+                        "permissions": { "type": "array", "items": permission.clone() },
+                        "members": { "type": "array", "items": { "type": "string" } }
+                    }
+                }
+            ]
+        });
+        let permission_body = json!({
+            "required": true,
+            "content": { "application/json": { "schema": permission } }
+        });
+        let name = json!({ "name": "name", "in": "path", "required": true, "schema": { "type": "string" } });
+        let member = json!({
+            "name": "member",
+            "in": "path",
+            "required": true,
+            "description": "User id, tenant such as `tenant:acme`, or role",
+            "schema": { "type": "string" }
+        });
+        Some(json!({
+            "tags": [
+                { "name": "Authorization", "description": "Runtime policy and role management" }
+            ],
+            "paths": {
+                "/policies": {
+                    "get": {
+                        "summary": "List policy rules",
+                        "tags": ["Authorization"],
+                        "responses": {
+                            "200": {
+                                "description": "Every rule in effect",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": rule }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "post": {
+                        "summary": "Add a policy rule",
+                        "tags": ["Authorization"],
+                        "requestBody": body.clone(),
+                        "responses": {
+                            "201": { "description": "Rule added and enforced" },
+                            "200": { "description": "Rule already existed" },
+                            "422": { "description": "Rule does not fit the model" }
+                        }
+                    },
+                    "delete": {
+                        "summary": "Remove a policy rule",
+                        "tags": ["Authorization"],
+                        "requestBody": body,
+                        "responses": {
+                            "204": { "description": "Rule removed" },
+                            "404": { "description": "No such rule" }
+                        }
+                    }
+                },
+                "/roles": {
+                    "get": {
+                        "summary": "List roles with their permissions and members",
+                        "tags": ["Authorization"],
+                        "responses": {
+                            "200": {
+                                "description": "Every defined role",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": role_details.clone() }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "post": {
+                        "summary": "Create a role",
+                        "tags": ["Authorization"],
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": role } }
+                        },
+                        "responses": {
+                            "201": { "description": "Role created" },
+                            "409": { "description": "A role with that name exists" }
+                        }
+                    }
+                },
+                "/roles/{name}": {
+                    "get": {
+                        "summary": "Show a role",
+                        "tags": ["Authorization"],
+                        "parameters": [name.clone()],
+                        "responses": {
+                            "200": {
+                                "description": "The role",
+                                "content": { "application/json": { "schema": role_details.clone() } }
+                            },
+                            "404": { "description": "No such role" }
+                        }
+                    },
+                    "delete": {
+                        "summary": "Delete a role with its permissions and assignments",
+                        "tags": ["Authorization"],
+                        "parameters": [name.clone()],
+                        "responses": {
+                            "204": { "description": "Role deleted" },
+                            "404": { "description": "No such role" }
+                        }
+                    }
+                },
+                "/roles/{name}/permissions": {
+                    "post": {
+                        "summary": "Grant a permission to a role",
+                        "tags": ["Authorization"],
+                        "parameters": [name.clone()],
+                        "requestBody": permission_body.clone(),
+                        "responses": {
+                            "200": {
+                                "description": "The updated role",
+                                "content": { "application/json": { "schema": role_details } }
+                            },
+                            "404": { "description": "No such role" }
+                        }
+                    },
+                    "delete": {
+                        "summary": "Revoke a permission from a role",
+                        "tags": ["Authorization"],
+                        "parameters": [name.clone()],
+                        "requestBody": permission_body,
+                        "responses": {
+                            "204": { "description": "Permission revoked" },
+                            "404": { "description": "No such role or permission" }
+                        }
+                    }
+                },
+                "/roles/{name}/members/{member}": {
+                    "put": {
+                        "summary": "Assign a role to a user, tenant or role",
+                        "tags": ["Authorization"],
+                        "parameters": [name.clone(), member.clone()],
+                        "responses": {
+                            "204": { "description": "Role assigned" },
+                            "404": { "description": "No such role" }
+                        }
+                    },
+                    "delete": {
+                        "summary": "Withdraw a role assignment",
+                        "tags": ["Authorization"],
+                        "parameters": [name, member],
+                        "responses": {
+                            "204": { "description": "Assignment withdrawn" },
+                            "404": { "description": "No such role or assignment" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![RouteSecurity {
+            method: "*",
+            path: "*",
+            schemes: &[SecurityScheme::AdminToken],
+            permission: None,
+        }]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![
+            Migration {
+                id: "001_create_casbin_rule",
+                up: "DEFINE TABLE casbin_rule SCHEMAFULL;
+DEFINE FIELD ptype ON casbin_rule TYPE string;
+DEFINE FIELD values ON casbin_rule TYPE array<string>;
+DEFINE INDEX casbin_rule_unique ON casbin_rule FIELDS ptype, values UNIQUE;",
+                down: Some("REMOVE TABLE casbin_rule;"),
+            },
+            Migration {
+                id: "002_create_role",
+                up: "DEFINE TABLE role SCHEMAFULL;
+DEFINE FIELD name ON role TYPE string;
+DEFINE FIELD description ON role TYPE option<string>;
+DEFINE INDEX role_name ON role FIELDS name UNIQUE;",
+                down: Some("REMOVE TABLE role;"),
+            },
+        ]
+    }
+}
+
+/// Create a new instance of the authz module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(AuthzModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::{registry::priority, settings::Settings, ModuleRegistry};
+
+    #[tokio::test]
+    async fn test_init_publishes_enforcer() {
+        let config = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/auth");
+        let mut settings = Settings::default();
+        settings.auth.casbin_model_path = config.join("model.conf").display().to_string();
+        settings.auth.casbin_policy_path = config.join("policy.csv").display().to_string();
+
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::AUTHZ)
+            .unwrap();
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings)))
+            .await
+            .unwrap();
+
+        let enforcer = registry.resources().require::<Enforcer>().unwrap();
+        assert!(enforcer.enforce(&["admin", "/api/books", "GET"]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_registered_store_is_seeded_from_file() {
+        let config = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/auth");
+        let mut settings = Settings::default();
+        settings.auth.casbin_model_path = config.join("model.conf").display().to_string();
+        settings.auth.casbin_policy_path = config.join("policy.csv").display().to_string();
+
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::AUTHZ)
+            .unwrap();
+        let store = Arc::new(MemoryPolicyStore::default());
+        registry
+            .resources()
+            .insert::<Arc<dyn PolicyStore>>(store.clone());
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings)))
+            .await
+            .unwrap();
+
+        assert!(!store.load().await.unwrap().is_empty());
+        let policies = registry.resources().require::<Policies>().unwrap();
+        policies
+            .add(&PolicyRule::new("g", ["alice", "admin"]))
+            .await
+            .unwrap();
+        let enforcer = registry.resources().require::<Enforcer>().unwrap();
+        assert!(enforcer.enforce(&["alice", "/api/books", "GET"]).unwrap());
+    }
+}
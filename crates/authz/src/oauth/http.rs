@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::SET_COOKIE, HeaderMap},
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use atlas_http::error::AppError;
+use atlas_kernel::Resources;
+
+use super::{IdentityLinker, OAuth, OAuthError, PENDING_TTL};
+use crate::login_cookie;
+use crate::Sessions;
+
+impl From<OAuthError> for AppError {
+    fn from(error: OAuthError) -> Self {
+        match error {
+            OAuthError::UnknownProvider(_) => AppError::not_found(error.to_string()),
+            OAuthError::InvalidState => AppError::bad_request(error.to_string()),
+            OAuthError::Provider(error) => AppError::Internal(error),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct OAuthState {
+    oauth: Arc<OAuth>,
+    sessions: Arc<Sessions>,
+    /// Looked up per login, since the module providing the linker may init after us
+    resources: Arc<Resources>,
+    success_redirect: Arc<str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Callback {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+async fn list_providers(State(state): State<OAuthState>) -> Json<Vec<String>> {
+    Json(
+        state
+            .oauth
+            .provider_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+async fn authorize(
+    State(state): State<OAuthState>,
+    Path(provider): Path<String>,
+) -> Result<Response, AppError> {
+    let (url, login_state) = state.oauth.begin(&provider)?;
+    let cookie =
+        login_cookie::OAUTH.set(&login_state, PENDING_TTL, state.sessions.secure_cookies())?;
+    Ok(([(SET_COOKIE, cookie)], Redirect::to(url.as_str())).into_response())
+}
+
+async fn callback(
+    State(state): State<OAuthState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    Query(callback): Query<Callback>,
+) -> Result<Response, AppError> {
+    if let Some(error) = callback.error {
+        return Err(AppError::bad_request(format!(
+            "login with '{}' was not completed: {}",
+            provider, error
+        )));
+    }
+    let (Some(code), Some(login_state)) = (callback.code, callback.state) else {
+        return Err(AppError::bad_request("callback requires code and state"));
+    };
+    // Checked before `complete` so a callback replayed into another browser leaves the
+    // login pending for the browser that started it
+    if !login_cookie::OAUTH.matches(&headers, &login_state) {
+        return Err(OAuthError::InvalidState.into());
+    }
+
+    let identity = state.oauth.complete(&provider, &code, &login_state).await?;
+    let subject = match state.resources.get::<Arc<dyn IdentityLinker>>() {
+        Some(linker) => linker.link(&identity).await?,
+        None => identity.default_subject(),
+    };
+    tracing::info!(provider = %identity.provider, subject = %subject, "OAuth login succeeded");
+
+    let (_, token) = state.sessions.create(&subject).await?;
+    let cookies = AppendHeaders([
+        (SET_COOKIE, state.sessions.cookie(&token)?),
+        (SET_COOKIE, state.sessions.csrf_cookie(&token)?),
+        (
+            SET_COOKIE,
+            login_cookie::OAUTH.clear(state.sessions.secure_cookies())?,
+        ),
+    ]);
+    Ok((cookies, Redirect::to(&state.success_redirect)).into_response())
+}
+
+/// `GET /` lists providers; `GET /{provider}/authorize` and `/{provider}/callback` log in
+pub fn oauth_routes(
+    oauth: Arc<OAuth>,
+    sessions: Arc<Sessions>,
+    resources: Arc<Resources>,
+    success_redirect: &str,
+) -> Router {
+    Router::new()
+        .route("/", get(list_providers))
+        .route("/{provider}/authorize", get(authorize))
+        .route("/{provider}/callback", get(callback))
+        .with_state(OAuthState {
+            oauth,
+            sessions,
+            resources,
+            success_redirect: Arc::from(success_redirect),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::tests::{github, FakeTransport};
+    use crate::sessions::MemorySessionStore;
+    use async_trait::async_trait;
+    use atlas_kernel::{clock::SystemClock, settings::SessionSettings};
+    use axum::{
+        body::Body,
+        http::{
+            header::{COOKIE, LOCATION},
+            Request, StatusCode,
+        },
+    };
+    use tower::ServiceExt;
+
+    struct Prefixing;
+
+    #[async_trait]
+    impl IdentityLinker for Prefixing {
+        async fn link(&self, identity: &super::super::ExternalIdentity) -> anyhow::Result<String> {
+            Ok(format!("user-{}", identity.id))
+        }
+    }
+
+    async fn get(router: &Router, uri: &str) -> Response {
+        get_with_cookie(router, uri, "").await
+    }
+
+    async fn get_with_cookie(router: &Router, uri: &str, cookie: &str) -> Response {
+        let request = Request::builder()
+            .uri(uri)
+            .header(COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_callback_starts_session_for_linked_subject() {
+        let sessions = Arc::new(Sessions::new(
+            Arc::new(MemorySessionStore::new()),
+            Arc::new(SystemClock),
+            SessionSettings::default(),
+        ));
+        let resources = Arc::new(Resources::new());
+        resources.insert::<Arc<dyn IdentityLinker>>(Arc::new(Prefixing));
+        let oauth = Arc::new(OAuth::new(
+            vec![github()],
+            Arc::new(FakeTransport::default()),
+            Arc::new(SystemClock),
+        ));
+        let router = Router::new().nest(
+            "/api/oauth",
+            oauth_routes(oauth, sessions.clone(), resources, "/app"),
+        );
+
+        let response = get(&router, "/api/oauth/github/authorize").await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = url::Url::parse(response.headers()[LOCATION].to_str().unwrap()).unwrap();
+        assert_eq!(location.host_str(), Some("github.com"));
+        let state = location
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .unwrap()
+            .1
+            .into_owned();
+        let started = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(started.starts_with(&format!("atlas_oauth_state={};", state)));
+        assert!(started.contains("HttpOnly; SameSite=Lax"));
+
+        let callback = format!("/api/oauth/github/callback?code=good&state={}", state);
+        let started_here = format!("atlas_oauth_state={}", state);
+        for elsewhere in ["", "atlas_oauth_state=other"] {
+            let response = get_with_cookie(&router, &callback, elsewhere).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let response = get_with_cookie(&router, &callback, &started_here).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[LOCATION], "/app");
+        let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        let token = cookie
+            .split(';')
+            .next()
+            .unwrap()
+            .trim_start_matches("atlas_session=");
+        let session = sessions.resolve(token).await.unwrap().unwrap();
+        assert_eq!(session.subject, "user-42");
+        assert!(response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .any(|value| value.to_str().unwrap().starts_with("atlas_oauth_state=;")));
+
+        let replay = get_with_cookie(&router, &callback, &started_here).await;
+        assert_eq!(replay.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            get(&router, "/api/oauth/gitlab/authorize").await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Login through OAuth2 / OpenID Connect providers
+//!
+//! Runs the authorization code flow with PKCE: `/authorize` redirects to the provider,
+//! `/callback` exchanges the code, reads the user's identity, links it to a subject and
+//! starts a cookie session for it.
+
+mod http;
+mod module;
+mod provider;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use atlas_kernel::{settings::OAuthSettings, Clock};
+
+use crate::token;
+
+pub use http::oauth_routes;
+pub use module::{create_module, OAuthModule};
+pub use provider::Provider;
+
+/// How long a started login may take before its `state` is rejected
+pub(crate) const PENDING_TTL: Duration = Duration::from_secs(600);
+
+/// HTTP calls to provider token and userinfo endpoints
+///
+/// Implementations must ask for JSON (`Accept: application/json`), which GitHub only
+/// returns when asked.
+#[async_trait]
+pub trait OAuthTransport: Send + Sync {
+    /// POST `form` as `application/x-www-form-urlencoded` and parse the JSON response
+    async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> anyhow::Result<Value>;
+
+    /// GET `url` with `Authorization: Bearer {access_token}` and parse the JSON response
+    async fn get_json(&self, url: &str, access_token: &str) -> anyhow::Result<Value>;
+}
+
+/// A user as identified by an external provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalIdentity {
+    /// Configured provider name, e.g. `google`
+    pub provider: String,
+    /// The provider's stable user id
+    pub id: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
+
+impl ExternalIdentity {
+    /// Subject used when no `IdentityLinker` is registered, e.g. `github:42`
+    pub fn default_subject(&self) -> String {
+        format!("{}:{}", self.provider, self.id)
+    }
+}
+
+/// Maps an external identity to a local subject, creating or linking accounts as needed
+///
+/// Usually provided by the module owning user accounts. Without one,
+/// `ExternalIdentity::default_subject` is used.
+#[async_trait]
+pub trait IdentityLinker: Send + Sync {
+    async fn link(&self, identity: &ExternalIdentity) -> anyhow::Result<String>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("unknown OAuth provider '{0}'")]
+    UnknownProvider(String),
+    #[error("login state is unknown, expired or for another provider")]
+    InvalidState,
+    #[error(transparent)]
+    Provider(#[from] anyhow::Error),
+}
+
+struct Pending {
+    provider: String,
+    code_verifier: String,
+    started_at: SystemTime,
+}
+
+/// Configured providers and logins in progress
+pub struct OAuth {
+    providers: HashMap<String, Provider>,
+    pending: Mutex<HashMap<String, Pending>>,
+    transport: Arc<dyn OAuthTransport>,
+    clock: Arc<dyn Clock>,
+}
+
+impl OAuth {
+    pub fn new(
+        providers: Vec<Provider>,
+        transport: Arc<dyn OAuthTransport>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            providers: providers
+                .into_iter()
+                .map(|provider| (provider.name.clone(), provider))
+                .collect(),
+            pending: Mutex::new(HashMap::new()),
+            transport,
+            clock,
+        }
+    }
+
+    /// Resolve every provider in `settings`
+    pub fn providers_from_settings(settings: &OAuthSettings) -> anyhow::Result<Vec<Provider>> {
+        let base_url = settings.redirect_base_url.as_deref().unwrap_or_default();
+        let mut providers = settings
+            .providers
+            .iter()
+            .map(|(name, provider)| Provider::from_settings(name, provider, base_url))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        providers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(providers)
+    }
+
+    /// Configured provider names, sorted
+    pub fn provider_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Start a login, returning the provider URL to redirect the browser to and the
+    /// `state` it carries, which the browser must present again on the callback
+    pub fn begin(&self, provider: &str) -> Result<(Url, String), OAuthError> {
+        let provider = self
+            .providers
+            .get(provider)
+            .ok_or_else(|| OAuthError::UnknownProvider(provider.to_string()))?;
+        let state = token::generate("")?;
+        let code_verifier = token::generate("")?;
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let now = self.clock.now();
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        pending.retain(|_, login| !expired(login, now));
+        pending.insert(
+            state.clone(),
+            Pending {
+                provider: provider.name.clone(),
+                code_verifier,
+                started_at: now,
+            },
+        );
+        let url = provider.authorization_request(&state, &code_challenge);
+        Ok((url, state))
+    }
+
+    /// Finish a login from the provider's callback, returning who logged in
+    pub async fn complete(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<ExternalIdentity, OAuthError> {
+        let provider = self
+            .providers
+            .get(provider)
+            .ok_or_else(|| OAuthError::UnknownProvider(provider.to_string()))?;
+        // States are single use: remove before checking so a replay always fails.
+        let login = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(state)
+            .filter(|login| login.provider == provider.name && !expired(login, self.clock.now()))
+            .ok_or(OAuthError::InvalidState)?;
+
+        let token = self
+            .transport
+            .post_form(
+                &provider.token_url,
+                &[
+                    ("grant_type", "authorization_code"),
+                    ("code", code),
+                    ("redirect_uri", &provider.redirect_uri),
+                    ("client_id", &provider.client_id),
+                    ("client_secret", &provider.client_secret),
+                    ("code_verifier", &login.code_verifier),
+                ],
+            )
+            .await?;
+        let access_token = token
+            .get("access_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                let reason = token
+                    .get("error_description")
+                    .or_else(|| token.get("error"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("no access_token in response");
+                anyhow::anyhow!("token exchange with '{}' failed: {}", provider.name, reason)
+            })?;
+
+        let userinfo = self
+            .transport
+            .get_json(&provider.userinfo_url, access_token)
+            .await?;
+        Ok(provider.identity(&userinfo)?)
+    }
+}
+
+fn expired(login: &Pending, now: SystemTime) -> bool {
+    now.duration_since(login.started_at)
+        .is_ok_and(|elapsed| elapsed >= PENDING_TTL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use atlas_kernel::settings::{OAuthProviderKind, OAuthProviderSettings};
+    use serde_json::json;
+
+    /// Provider double recording the token request
+    #[derive(Default)]
+    pub(super) struct FakeTransport {
+        pub(super) form: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl OAuthTransport for FakeTransport {
+        async fn post_form(&self, _url: &str, form: &[(&str, &str)]) -> anyhow::Result<Value> {
+            *self.form.lock().unwrap() = form
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            let code = form.iter().find(|(key, _)| *key == "code").unwrap().1;
+            Ok(if code == "good" {
+                json!({ "access_token": "at", "token_type": "bearer" })
+            } else {
+                json!({ "error": "invalid_grant" })
+            })
+        }
+
+        async fn get_json(&self, _url: &str, access_token: &str) -> anyhow::Result<Value> {
+            assert_eq!(access_token, "at");
+            Ok(json!({ "id": 42, "login": "octocat" }))
+        }
+    }
+
+    pub(super) fn github() -> Provider {
+        let settings = OAuthProviderSettings {
+            kind: OAuthProviderKind::Github,
+            client_id: "atlas".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: Vec::new(),
+            authorize_url: None,
+            token_url: None,
+            userinfo_url: None,
+        };
+        Provider::from_settings("github", &settings, "https://app.example.com").unwrap()
+    }
+
+    fn state_of(url: &Url) -> String {
+        url.query_pairs()
+            .find(|(key, _)| key == "state")
+            .unwrap()
+            .1
+            .into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_login_completes_once_with_pkce() {
+        let transport = Arc::new(FakeTransport::default());
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let oauth = OAuth::new(vec![github()], transport.clone(), clock);
+
+        let (url, state) = oauth.begin("github").unwrap();
+        assert_eq!(state_of(&url), state);
+        let identity = oauth.complete("github", "good", &state).await.unwrap();
+        assert_eq!(identity.default_subject(), "github:42");
+
+        let challenge = url
+            .query_pairs()
+            .find(|(key, _)| key == "code_challenge")
+            .unwrap()
+            .1
+            .into_owned();
+        let form = transport.form.lock().unwrap().clone();
+        let verifier = &form
+            .iter()
+            .find(|(key, _)| key == "code_verifier")
+            .unwrap()
+            .1;
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+            challenge
+        );
+
+        assert!(matches!(
+            oauth.complete("github", "good", &state).await,
+            Err(OAuthError::InvalidState)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_failed_logins_rejected() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let oauth = OAuth::new(
+            vec![github()],
+            Arc::new(FakeTransport::default()),
+            clock.clone(),
+        );
+
+        let (_, state) = oauth.begin("github").unwrap();
+        clock.advance(PENDING_TTL);
+        assert!(matches!(
+            oauth.complete("github", "good", &state).await,
+            Err(OAuthError::InvalidState)
+        ));
+
+        let (_, state) = oauth.begin("github").unwrap();
+        let error = oauth.complete("github", "bad", &state).await.unwrap_err();
+        assert!(error.to_string().contains("invalid_grant"));
+
+        assert!(matches!(
+            oauth.begin("gitlab"),
+            Err(OAuthError::UnknownProvider(_))
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{InitCtx, Module, Resources};
+
+use super::{oauth_routes, OAuth, OAuthTransport};
+use crate::Sessions;
+
+struct Mounted {
+    oauth: Arc<OAuth>,
+    sessions: Arc<Sessions>,
+    resources: Arc<Resources>,
+    success_redirect: String,
+}
+
+/// Core module serving login through the providers under `[auth.oauth.providers]`
+///
+/// Successful logins start a cookie session, so it depends on the sessions module. It
+/// mounts nothing when no providers are configured.
+#[derive(Default)]
+pub struct OAuthModule {
+    mounted: OnceLock<Mounted>,
+}
+
+#[async_trait]
+impl Module for OAuthModule {
+    fn name(&self) -> &'static str {
+        "oauth"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("OAuth2 and OpenID Connect login")
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["sessions"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.auth.oauth;
+        if settings.providers.is_empty() {
+            tracing::debug!("no OAuth providers configured");
+            return Ok(());
+        }
+
+        let providers = OAuth::providers_from_settings(settings)?;
+        let transport = ctx
+            .resources
+            .get::<Arc<dyn OAuthTransport>>()
+            .context("OAuth providers are configured but no OAuthTransport resource is registered")?
+            .as_ref()
+            .clone();
+        let oauth = Arc::new(OAuth::new(providers, transport, ctx.clock.clone()));
+        tracing::info!(providers = ?oauth.provider_names(), "OAuth login enabled");
+
+        ctx.resources.insert_arc(oauth.clone());
+        self.mounted
+            .set(Mounted {
+                oauth,
+                sessions: ctx.resources.require::<Sessions>()?,
+                resources: ctx.resources.clone(),
+                success_redirect: settings.success_redirect.clone(),
+            })
+            .map_err(|_| anyhow::anyhow!("oauth module initialized twice"))?;
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.mounted.get() {
+            Some(mounted) => oauth_routes(
+                mounted.oauth.clone(),
+                mounted.sessions.clone(),
+                mounted.resources.clone(),
+                &mounted.success_redirect,
+            ),
+            None => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let provider = json!({ "name": "provider", "in": "path", "required": true, "schema": { "type": "string" } });
+        Some(json!({
+            "tags": [
+                { "name": "OAuth", "description": "Login through external identity providers" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List configured providers",
+                        "tags": ["OAuth"],
+                        "responses": {
+                            "200": {
+                                "description": "Provider names usable in the login paths",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": { "type": "string" } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/{provider}/authorize": {
+                    "get": {
+                        "summary": "Start a login",
+                        "tags": ["OAuth"],
+                        "parameters": [provider.clone()],
+                        "responses": {
+                            "303": { "description": "Redirect to the provider" },
+                            "404": { "description": "Unknown provider" }
+                        }
+                    }
+                },
+                "/{provider}/callback": {
+                    "get": {
+                        "summary": "Finish a login",
+                        "description": "Called by the provider; starts a session and redirects to `auth.oauth.success_redirect`.",
+                        "tags": ["OAuth"],
+                        "parameters": [
+                            provider,
+                            { "name": "code", "in": "query", "schema": { "type": "string" } },
+                            { "name": "state", "in": "query", "schema": { "type": "string" } },
+                            { "name": "error", "in": "query", "schema": { "type": "string" } }
+                        ],
+                        "responses": {
+                            "303": { "description": "Logged in; the session cookie is set" },
+                            "400": { "description": "Login state is invalid or the provider reported an error" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Create a new instance of the OAuth module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(OAuthModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::settings::{OAuthProviderKind, OAuthProviderSettings, Settings};
+    use atlas_kernel::ModuleRegistry;
+
+    fn settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.auth.oauth.redirect_base_url = Some("https://app.example.com".to_string());
+        settings.auth.oauth.providers.insert(
+            "github".to_string(),
+            OAuthProviderSettings {
+                kind: OAuthProviderKind::Github,
+                client_id: "atlas".to_string(),
+                client_secret: "secret".to_string(),
+                scopes: Vec::new(),
+                authorize_url: None,
+                token_url: None,
+                userinfo_url: None,
+            },
+        );
+        settings
+    }
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry
+            .register_core(crate::sessions::create_module())
+            .unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_configured_providers_require_transport() {
+        let registry = registry();
+        let error = registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings())))
+            .await
+            .unwrap_err();
+        let error = anyhow::Error::from(error);
+        assert!(format!("{:#}", error).contains("OAuthTransport"));
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_oauth_after_sessions() {
+        let registry = registry();
+        registry
+            .resources()
+            .insert::<Arc<dyn OAuthTransport>>(Arc::new(
+                crate::oauth::tests::FakeTransport::default(),
+            ));
+
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings())))
+            .await
+            .unwrap();
+
+        let oauth = registry.resources().require::<OAuth>().unwrap();
+        assert_eq!(oauth.provider_names(), vec!["github"]);
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use url::Url;
+
+use atlas_kernel::settings::{OAuthProviderKind, OAuthProviderSettings};
+
+use super::ExternalIdentity;
+
+/// A configured provider with its endpoints resolved
+#[derive(Debug, Clone)]
+pub struct Provider {
+    pub name: String,
+    pub kind: OAuthProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: Vec<String>,
+    pub authorize_url: Url,
+    pub token_url: String,
+    pub userinfo_url: String,
+    /// Callback URL registered with the provider
+    pub redirect_uri: String,
+}
+
+impl Provider {
+    /// Resolve `settings`, filling in built-in endpoints and scopes for known providers
+    pub fn from_settings(
+        name: &str,
+        settings: &OAuthProviderSettings,
+        redirect_base_url: &str,
+    ) -> anyhow::Result<Self> {
+        let (authorize_url, token_url, userinfo_url, scopes): (_, _, _, &[&str]) =
+            match settings.kind {
+                OAuthProviderKind::Google => (
+                    "https://accounts.google.com/o/oauth2/v2/auth",
+                    "https://oauth2.googleapis.com/token",
+                    "https://openidconnect.googleapis.com/v1/userinfo",
+                    &["openid", "email", "profile"],
+                ),
+                OAuthProviderKind::Github => (
+                    "https://github.com/login/oauth/authorize",
+                    "https://github.com/login/oauth/access_token",
+                    "https://api.github.com/user",
+                    &["read:user", "user:email"],
+                ),
+                OAuthProviderKind::Oidc => ("", "", "", &["openid", "email", "profile"]),
+            };
+        let endpoint = |configured: &Option<String>, builtin: &str, key: &str| {
+            configured
+                .clone()
+                .or_else(|| (!builtin.is_empty()).then(|| builtin.to_string()))
+                .ok_or_else(|| anyhow!("OAuth provider '{}' has no {}", name, key))
+        };
+
+        let authorize_url = endpoint(&settings.authorize_url, authorize_url, "authorize_url")?;
+        Ok(Self {
+            name: name.to_string(),
+            kind: settings.kind,
+            client_id: settings.client_id.clone(),
+            client_secret: settings.client_secret.clone(),
+            scopes: if settings.scopes.is_empty() {
+                scopes.iter().map(|scope| scope.to_string()).collect()
+            } else {
+                settings.scopes.clone()
+            },
+            authorize_url: Url::parse(&authorize_url)
+                .with_context(|| format!("invalid authorize_url for OAuth provider '{}'", name))?,
+            token_url: endpoint(&settings.token_url, token_url, "token_url")?,
+            userinfo_url: endpoint(&settings.userinfo_url, userinfo_url, "userinfo_url")?,
+            redirect_uri: format!(
+                "{}/api/oauth/{}/callback",
+                redirect_base_url.trim_end_matches('/'),
+                name
+            ),
+        })
+    }
+
+    /// Where to send the browser to start a login
+    pub fn authorization_request(&self, state: &str, code_challenge: &str) -> Url {
+        let mut url = self.authorize_url.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        url
+    }
+
+    /// The caller's identity from a userinfo response
+    pub fn identity(&self, userinfo: &Value) -> anyhow::Result<ExternalIdentity> {
+        let text = |key: &str| {
+            userinfo
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let id = match self.kind {
+            // GitHub user ids are numbers; OIDC subjects are strings.
+            OAuthProviderKind::Github => userinfo
+                .get("id")
+                .and_then(Value::as_u64)
+                .map(|id| id.to_string()),
+            OAuthProviderKind::Google | OAuthProviderKind::Oidc => text("sub"),
+        }
+        .ok_or_else(|| anyhow!("userinfo from '{}' has no user id", self.name))?;
+
+        Ok(ExternalIdentity {
+            provider: self.name.clone(),
+            id,
+            email: text("email"),
+            name: text("name").or_else(|| text("login")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(kind: OAuthProviderKind) -> OAuthProviderSettings {
+        OAuthProviderSettings {
+            kind,
+            client_id: "atlas".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: Vec::new(),
+            authorize_url: None,
+            token_url: None,
+            userinfo_url: None,
+        }
+    }
+
+    #[test]
+    fn test_builtin_providers_resolve_endpoints() {
+        let google = Provider::from_settings(
+            "google",
+            &settings(OAuthProviderKind::Google),
+            "https://app.example.com/",
+        )
+        .unwrap();
+        assert_eq!(google.token_url, "https://oauth2.googleapis.com/token");
+        assert_eq!(
+            google.redirect_uri,
+            "https://app.example.com/api/oauth/google/callback"
+        );
+
+        let url = google.authorization_request("st", "ch");
+        let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert!(query.contains(&("scope".to_string(), "openid email profile".to_string())));
+        assert!(query.contains(&("state".to_string(), "st".to_string())));
+        assert!(query.contains(&("code_challenge_method".to_string(), "S256".to_string())));
+    }
+
+    #[test]
+    fn test_oidc_requires_endpoints() {
+        let error =
+            Provider::from_settings("corp", &settings(OAuthProviderKind::Oidc), "https://app")
+                .unwrap_err();
+        assert!(error.to_string().contains("authorize_url"));
+    }
+
+    #[test]
+    fn test_identity_read_per_provider_kind() {
+        let github = Provider::from_settings(
+            "github",
+            &settings(OAuthProviderKind::Github),
+            "https://app",
+        )
+        .unwrap();
+        let identity = github
+            .identity(&json!({ "id": 42, "login": "octocat", "email": null }))
+            .unwrap();
+        assert_eq!(identity.id, "42");
+        assert_eq!(identity.name.as_deref(), Some("octocat"));
+        assert_eq!(identity.email, None);
+
+        let google = Provider::from_settings(
+            "google",
+            &settings(OAuthProviderKind::Google),
+            "https://app",
+        )
+        .unwrap();
+        let identity = google
+            .identity(&json!({ "sub": "1090", "email": "a@example.com" }))
+            .unwrap();
+        assert_eq!(identity.id, "1090");
+        assert!(google
+            .identity(&json!({ "email": "a@example.com" }))
+            .is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+
+use atlas_http::error::AppError;
+use atlas_kernel::Resources;
+
+use super::{PasswordAccounts, PasswordResets, ResetError};
+use crate::rate_limit::{rate_limit, RateLimiter};
+
+impl From<ResetError> for AppError {
+    fn from(error: ResetError) -> Self {
+        match error {
+            ResetError::InvalidToken => AppError::bad_request(error.to_string()),
+            ResetError::WeakPassword(_) => AppError::validation(
+                vec![json!({ "field": "password", "error": "too_short" })],
+                error.to_string(),
+            ),
+            ResetError::Other(error) => AppError::Internal(error),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ResetState {
+    resets: Arc<PasswordResets>,
+    /// Looked up per request, since the module providing the accounts may init after us
+    resources: Arc<Resources>,
+}
+
+impl ResetState {
+    fn accounts(&self) -> Result<Arc<Arc<dyn PasswordAccounts>>, AppError> {
+        self.resources
+            .get::<Arc<dyn PasswordAccounts>>()
+            .ok_or_else(|| {
+                anyhow!("no password accounts registered; password reset is unavailable").into()
+            })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ForgotPassword {
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResetPassword {
+    token: String,
+    password: String,
+}
+
+async fn forgot_password(
+    State(state): State<ResetState>,
+    Json(request): Json<ForgotPassword>,
+) -> Result<StatusCode, AppError> {
+    let email = request.email.trim();
+    if !email.contains('@') {
+        return Err(AppError::validation(
+            vec![json!({ "field": "email", "error": "invalid" })],
+            "email must be an email address",
+        ));
+    }
+    let accounts = state.accounts()?;
+    state
+        .resets
+        .request(accounts.as_ref().as_ref(), email)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn reset_password(
+    State(state): State<ResetState>,
+    Json(request): Json<ResetPassword>,
+) -> Result<StatusCode, AppError> {
+    let accounts = state.accounts()?;
+    state
+        .resets
+        .reset(
+            accounts.as_ref().as_ref(),
+            &request.token,
+            &request.password,
+        )
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `POST /forgot-password` and `POST /reset-password`, budgeted by `limiter` when given
+pub fn password_reset_routes(
+    resets: Arc<PasswordResets>,
+    resources: Arc<Resources>,
+    limiter: Option<Arc<RateLimiter>>,
+) -> Router {
+    let router = Router::new()
+        .route("/forgot-password", post(forgot_password))
+        .route("/reset-password", post(reset_password))
+        .with_state(ResetState { resets, resources });
+    match limiter {
+        Some(limiter) => {
+            router.route_layer(axum::middleware::from_fn_with_state(limiter, rate_limit))
+        }
+        None => router,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password_reset::{tests::FakeAccounts, MemoryResetTokenStore};
+    use atlas_kernel::{clock::SystemClock, settings::PasswordResetSettings};
+    use axum::{body::Body, extract::Request};
+    use tower::ServiceExt;
+
+    fn post_json(uri: &str, body: &str) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forgot_then_reset_password() {
+        let resources = Arc::new(Resources::new());
+        let accounts = Arc::new(FakeAccounts::default());
+        resources.insert::<Arc<dyn PasswordAccounts>>(accounts.clone());
+        let resets = Arc::new(PasswordResets::new(
+            Arc::new(MemoryResetTokenStore::new()),
+            Arc::new(SystemClock),
+            PasswordResetSettings::default(),
+            resources.clone(),
+        ));
+        let router =
+            Router::new().nest("/api/auth", password_reset_routes(resets, resources, None));
+
+        for email in ["alice@example.com", "nobody@example.com"] {
+            let response = router
+                .clone()
+                .oneshot(post_json(
+                    "/api/auth/forgot-password",
+                    &format!(r#"{{"email":"{}"}}"#, email),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+        }
+        let token = accounts.sent.lock().unwrap()[0].clone();
+
+        let reset = |password: &str| {
+            post_json(
+                "/api/auth/reset-password",
+                &format!(r#"{{"token":"{}","password":"{}"}}"#, token, password),
+            )
+        };
+        let response = router.clone().oneshot(reset("short")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = router
+            .clone()
+            .oneshot(reset("correct horse battery"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = router
+            .oneshot(reset("correct horse battery"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,313 @@
+//! Self-service password reset
+//!
+//! `POST /forgot-password` issues a single-use, expiring token and hands it to the
+//! application's `PasswordAccounts` for delivery; `POST /reset-password` trades the token
+//! for a new password. Only token hashes are stored, and both steps are audited.
+
+mod http;
+mod module;
+mod store;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::json;
+use time::OffsetDateTime;
+
+use atlas_kernel::{settings::PasswordResetSettings, Clock, Resources};
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::token;
+
+pub use http::password_reset_routes;
+pub use module::{create_module, PasswordResetModule};
+pub use store::{MemoryResetTokenStore, ResetToken, ResetTokenStore};
+
+/// Prefix of every reset token
+const TOKEN_PREFIX: &str = "rst_";
+
+/// Account operations a password reset needs from the application
+///
+/// Resets fail until one is registered, usually by the module owning user accounts.
+#[async_trait]
+pub trait PasswordAccounts: Send + Sync {
+    /// Subject of the account registered under `email`
+    async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<String>>;
+
+    /// Deliver `token` to the owner of `email`, e.g. as a link in an email
+    async fn send_reset(&self, subject: &str, email: &str, token: &str) -> anyhow::Result<()>;
+
+    /// Replace the password of `subject`
+    async fn set_password(&self, subject: &str, password: &str) -> anyhow::Result<()>;
+}
+
+/// Why a reset was refused
+#[derive(Debug, thiserror::Error)]
+pub enum ResetError {
+    #[error("invalid or expired reset token")]
+    InvalidToken,
+    #[error("password must be at least {0} characters")]
+    WeakPassword(usize),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Issues and redeems reset tokens
+pub struct PasswordResets {
+    store: Arc<dyn ResetTokenStore>,
+    clock: Arc<dyn Clock>,
+    settings: PasswordResetSettings,
+    /// Audit sink lookup happens per event, so a sink registered later is still used
+    resources: Arc<Resources>,
+}
+
+impl PasswordResets {
+    pub fn new(
+        store: Arc<dyn ResetTokenStore>,
+        clock: Arc<dyn Clock>,
+        settings: PasswordResetSettings,
+        resources: Arc<Resources>,
+    ) -> Self {
+        Self {
+            store,
+            clock,
+            settings,
+            resources,
+        }
+    }
+
+    /// Send a reset token to the account behind `email`, if there is one
+    ///
+    /// Unknown addresses and throttled requests succeed silently so callers cannot probe
+    /// which addresses have accounts; the audit log tells them apart.
+    pub async fn request(
+        &self,
+        accounts: &dyn PasswordAccounts,
+        email: &str,
+    ) -> anyhow::Result<()> {
+        let now = self.now();
+        let Some(subject) = accounts.find_by_email(email).await? else {
+            self.audit(
+                AuditEvent::new("password_reset.requested", AuditOutcome::Failure, now)
+                    .detail(json!({ "reason": "unknown_email" })),
+            )
+            .await;
+            return Ok(());
+        };
+
+        let resend_after = Duration::from_secs(self.settings.resend_interval_secs);
+        if let Some(latest) = self.store.latest_for(&subject).await? {
+            if now < latest + resend_after {
+                self.audit(
+                    AuditEvent::new("password_reset.requested", AuditOutcome::Denied, now)
+                        .actor(&subject)
+                        .detail(json!({ "reason": "throttled" })),
+                )
+                .await;
+                return Ok(());
+            }
+        }
+
+        let token = token::generate(TOKEN_PREFIX)?;
+        self.store
+            .insert(ResetToken {
+                subject: subject.clone(),
+                hash: token::hash(&token),
+                created_at: now,
+                expires_at: now + Duration::from_secs(self.settings.token_ttl_secs),
+            })
+            .await
+            .context("failed to store reset token")?;
+        accounts
+            .send_reset(&subject, email, &token)
+            .await
+            .context("failed to deliver reset token")?;
+        self.audit(
+            AuditEvent::new("password_reset.requested", AuditOutcome::Success, now).actor(&subject),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Set a new password with a reset token, returning the subject it belonged to
+    ///
+    /// A too-short password leaves the token usable; on success every other outstanding
+    /// token of the account is revoked.
+    pub async fn reset(
+        &self,
+        accounts: &dyn PasswordAccounts,
+        token: &str,
+        password: &str,
+    ) -> Result<String, ResetError> {
+        if password.chars().count() < self.settings.min_password_len {
+            return Err(ResetError::WeakPassword(self.settings.min_password_len));
+        }
+
+        let now = self.now();
+        let reset = match self.store.take_by_hash(&token::hash(token)).await? {
+            Some(reset) if !reset.is_expired(now) => reset,
+            expired => {
+                let event = AuditEvent::new("password_reset.completed", AuditOutcome::Denied, now)
+                    .detail(json!({ "reason": "invalid_token" }));
+                let event = match expired {
+                    Some(reset) => event.actor(reset.subject),
+                    None => event,
+                };
+                self.audit(event).await;
+                return Err(ResetError::InvalidToken);
+            }
+        };
+
+        accounts
+            .set_password(&reset.subject, password)
+            .await
+            .context("failed to set password")?;
+        self.store.delete_for(&reset.subject).await?;
+        self.audit(
+            AuditEvent::new("password_reset.completed", AuditOutcome::Success, now)
+                .actor(&reset.subject),
+        )
+        .await;
+        Ok(reset.subject)
+    }
+
+    pub async fn purge_expired(&self) -> anyhow::Result<usize> {
+        self.store.purge_expired(self.now()).await
+    }
+
+    async fn audit(&self, event: AuditEvent) {
+        audit::record(&self.resources, event).await;
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::audit::{tests::RecordingSink, AuditSink};
+    use atlas_kernel::clock::ManualClock;
+    use std::sync::Mutex;
+
+    /// Accounts keeping the last delivered token and password per subject
+    #[derive(Default)]
+    pub(crate) struct FakeAccounts {
+        pub(crate) sent: Mutex<Vec<String>>,
+        pub(crate) passwords: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl PasswordAccounts for FakeAccounts {
+        async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<String>> {
+            Ok((email == "alice@example.com").then(|| "user:alice".to_string()))
+        }
+
+        async fn send_reset(
+            &self,
+            _subject: &str,
+            _email: &str,
+            token: &str,
+        ) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push(token.to_string());
+            Ok(())
+        }
+
+        async fn set_password(&self, subject: &str, password: &str) -> anyhow::Result<()> {
+            self.passwords
+                .lock()
+                .unwrap()
+                .push((subject.to_string(), password.to_string()));
+            Ok(())
+        }
+    }
+
+    fn resets(clock: Arc<ManualClock>) -> (PasswordResets, Arc<RecordingSink>) {
+        let resources = Arc::new(Resources::new());
+        let sink = Arc::new(RecordingSink::default());
+        resources.insert::<Arc<dyn AuditSink>>(sink.clone());
+        let resets = PasswordResets::new(
+            Arc::new(MemoryResetTokenStore::new()),
+            clock,
+            PasswordResetSettings::default(),
+            resources,
+        );
+        (resets, sink)
+    }
+
+    fn clock() -> Arc<ManualClock> {
+        Arc::new(ManualClock::new(std::time::SystemTime::UNIX_EPOCH))
+    }
+
+    #[tokio::test]
+    async fn test_tokens_reset_once() {
+        let (resets, sink) = resets(clock());
+        let accounts = FakeAccounts::default();
+
+        resets
+            .request(&accounts, "alice@example.com")
+            .await
+            .unwrap();
+        resets
+            .request(&accounts, "nobody@example.com")
+            .await
+            .unwrap();
+        let token = accounts.sent.lock().unwrap()[0].clone();
+        assert!(token.starts_with("rst_"));
+
+        let subject = resets
+            .reset(&accounts, &token, "correct horse battery")
+            .await
+            .unwrap();
+        assert_eq!(subject, "user:alice");
+        assert!(matches!(
+            resets
+                .reset(&accounts, &token, "correct horse battery")
+                .await,
+            Err(ResetError::InvalidToken)
+        ));
+        assert_eq!(
+            sink.actions(),
+            vec![
+                "password_reset.requested:success",
+                "password_reset.requested:failure",
+                "password_reset.completed:success",
+                "password_reset.completed:denied",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_throttled_and_tokens_expire() {
+        let clock = clock();
+        let (resets, _) = resets(clock.clone());
+        let accounts = FakeAccounts::default();
+
+        resets
+            .request(&accounts, "alice@example.com")
+            .await
+            .unwrap();
+        resets
+            .request(&accounts, "alice@example.com")
+            .await
+            .unwrap();
+        assert_eq!(accounts.sent.lock().unwrap().len(), 1);
+
+        clock.advance(Duration::from_secs(3600));
+        let token = accounts.sent.lock().unwrap()[0].clone();
+        assert!(matches!(
+            resets
+                .reset(&accounts, &token, "correct horse battery")
+                .await,
+            Err(ResetError::InvalidToken)
+        ));
+        assert!(matches!(
+            resets.reset(&accounts, &token, "short").await,
+            Err(ResetError::WeakPassword(12))
+        ));
+        assert!(accounts.passwords.lock().unwrap().is_empty());
+    }
+}
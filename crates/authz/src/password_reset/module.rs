@@ -0,0 +1,168 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{InitCtx, Migration, Module, Resources};
+
+use super::{password_reset_routes, MemoryResetTokenStore, PasswordResets, ResetTokenStore};
+use crate::RateLimiter;
+
+/// How often expired reset tokens are swept from the store
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Core module serving the password reset endpoints under `/api/auth`
+///
+/// Requests are budgeted by the `RateLimiter` when the rate limit module is registered.
+#[derive(Default)]
+pub struct PasswordResetModule {
+    resets: OnceLock<Arc<PasswordResets>>,
+    resources: OnceLock<Arc<Resources>>,
+}
+
+#[async_trait]
+impl Module for PasswordResetModule {
+    fn name(&self) -> &'static str {
+        "auth"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Password reset with single-use tokens")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = match ctx.resources.get::<Arc<dyn ResetTokenStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => {
+                tracing::warn!(
+                    "no persistent reset token store registered; reset tokens are kept in memory"
+                );
+                Arc::new(MemoryResetTokenStore::new())
+            }
+        };
+        let resets = Arc::new(PasswordResets::new(
+            store,
+            ctx.clock.clone(),
+            ctx.settings.auth.password_reset.clone(),
+            ctx.resources.clone(),
+        ));
+        ctx.resources.insert_arc(resets.clone());
+
+        self.resets
+            .set(resets)
+            .map_err(|_| anyhow::anyhow!("auth module initialized twice"))?;
+        self.resources.get_or_init(|| ctx.resources.clone());
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let Some(resets) = self.resets.get().cloned() else {
+            return Ok(());
+        };
+        let clock = ctx.clock.clone();
+        ctx.spawn("purge_expired", async move {
+            loop {
+                clock.sleep(PURGE_INTERVAL).await;
+                match resets.purge_expired().await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::debug!(purged, "purged expired reset tokens"),
+                    Err(error) => {
+                        tracing::warn!(error = %error, "failed to purge expired reset tokens")
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match (self.resets.get(), self.resources.get()) {
+            (Some(resets), Some(resources)) => password_reset_routes(
+                resets.clone(),
+                resources.clone(),
+                resources.get::<RateLimiter>(),
+            ),
+            _ => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let body = |properties: serde_json::Value, required: &[&str]| {
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": { "type": "object", "required": required, "properties": properties }
+                    }
+                }
+            })
+        };
+        Some(json!({
+            "tags": [
+                { "name": "Password reset", "description": "Self-service password recovery" }
+            ],
+            "paths": {
+                "/forgot-password": {
+                    "post": {
+                        "summary": "Send a password reset token",
+                        "description": "Answers 202 whether or not the address has an account.",
+                        "tags": ["Password reset"],
+                        "requestBody": body(
+                            json!({ "email": { "type": "string", "format": "email" } }),
+                            &["email"]
+                        ),
+                        "responses": {
+                            "202": { "description": "A token is sent if the account exists" },
+                            "422": { "description": "Not an email address" },
+                            "429": { "description": "Rate limit exceeded" }
+                        }
+                    }
+                },
+                "/reset-password": {
+                    "post": {
+                        "summary": "Set a new password with a reset token",
+                        "tags": ["Password reset"],
+                        "requestBody": body(
+                            json!({
+                                "token": { "type": "string" },
+                                "password": { "type": "string", "format": "password" }
+                            }),
+                            &["token", "password"]
+                        ),
+                        "responses": {
+                            "204": { "description": "Password changed; the token is spent" },
+                            "400": { "description": "Invalid, used or expired token" },
+                            "422": { "description": "Password too short" },
+                            "429": { "description": "Rate limit exceeded" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_password_reset",
+            up: "DEFINE TABLE password_reset SCHEMAFULL;
+DEFINE FIELD subject ON password_reset TYPE string;
+DEFINE FIELD hash ON password_reset TYPE string;
+DEFINE FIELD created_at ON password_reset TYPE datetime;
+DEFINE FIELD expires_at ON password_reset TYPE datetime;
+DEFINE INDEX password_reset_hash ON password_reset FIELDS hash UNIQUE;
+DEFINE INDEX password_reset_subject ON password_reset FIELDS subject;",
+            down: Some("REMOVE TABLE password_reset;"),
+        }]
+    }
+}
+
+/// Create a new instance of the password reset module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(PasswordResetModule::default())
+}
@@ -0,0 +1,104 @@
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+/// An outstanding password reset; only the hash of the token is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResetToken {
+    /// Subject whose password the token resets
+    pub subject: String,
+    pub hash: String,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+impl ResetToken {
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Persistence for reset tokens
+///
+/// The database module provides a SurrealDB-backed store by publishing an
+/// `Arc<dyn ResetTokenStore>` resource; without one, tokens live in memory.
+#[async_trait]
+pub trait ResetTokenStore: Send + Sync {
+    async fn insert(&self, token: ResetToken) -> anyhow::Result<()>;
+
+    /// Remove and return the token with `hash`, so it can be used only once
+    async fn take_by_hash(&self, hash: &str) -> anyhow::Result<Option<ResetToken>>;
+
+    /// Creation time of the newest token issued for `subject`
+    async fn latest_for(&self, subject: &str) -> anyhow::Result<Option<OffsetDateTime>>;
+
+    /// Remove every token of `subject`, returning how many were removed
+    async fn delete_for(&self, subject: &str) -> anyhow::Result<usize>;
+
+    /// Remove every token expired at `now`, returning how many were removed
+    async fn purge_expired(&self, now: OffsetDateTime) -> anyhow::Result<usize>;
+}
+
+/// Process-local store; outstanding resets are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryResetTokenStore {
+    tokens: RwLock<Vec<ResetToken>>,
+}
+
+impl MemoryResetTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn remove_where(&self, matches: impl Fn(&ResetToken) -> bool) -> usize {
+        let mut tokens = self
+            .tokens
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = tokens.len();
+        tokens.retain(|token| !matches(token));
+        before - tokens.len()
+    }
+}
+
+#[async_trait]
+impl ResetTokenStore for MemoryResetTokenStore {
+    async fn insert(&self, token: ResetToken) -> anyhow::Result<()> {
+        self.tokens
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(token);
+        Ok(())
+    }
+
+    async fn take_by_hash(&self, hash: &str) -> anyhow::Result<Option<ResetToken>> {
+        let mut tokens = self
+            .tokens
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(tokens
+            .iter()
+            .position(|token| token.hash == hash)
+            .map(|index| tokens.remove(index)))
+    }
+
+    async fn latest_for(&self, subject: &str) -> anyhow::Result<Option<OffsetDateTime>> {
+        Ok(self
+            .tokens
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|token| token.subject == subject)
+            .map(|token| token.created_at)
+            .max())
+    }
+
+    async fn delete_for(&self, subject: &str) -> anyhow::Result<usize> {
+        Ok(self.remove_where(|token| token.subject == subject))
+    }
+
+    async fn purge_expired(&self, now: OffsetDateTime) -> anyhow::Result<usize> {
+        Ok(self.remove_where(|token| token.is_expired(now)))
+    }
+}
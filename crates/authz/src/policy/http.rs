@@ -0,0 +1,351 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post, put},
+    Json, Router,
+};
+
+use atlas_http::{error::AppError, meta::authorize_admin};
+
+use super::roles::validate_name;
+use super::{Permission, Policies, PolicyRule, Role, RoleDetails, Roles};
+
+#[derive(Clone)]
+struct PolicyState {
+    policies: Arc<Policies>,
+    admin_token: Arc<str>,
+}
+
+fn invalid(field: &str, error: anyhow::Error) -> AppError {
+    AppError::validation(
+        vec![serde_json::json!({ "field": field, "error": error.to_string() })],
+        error.to_string(),
+    )
+}
+
+fn check(policies: &Policies, rule: &PolicyRule) -> Result<(), AppError> {
+    policies
+        .enforcer()
+        .validate_rule(rule)
+        .map_err(|error| invalid("values", error.context("invalid policy rule")))
+}
+
+async fn list_rules(
+    State(state): State<PolicyState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PolicyRule>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.policies.list()))
+}
+
+async fn add_rule(
+    State(state): State<PolicyState>,
+    headers: HeaderMap,
+    Json(rule): Json<PolicyRule>,
+) -> Result<(StatusCode, Json<PolicyRule>), AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    check(&state.policies, &rule)?;
+    let status = if state.policies.add(&rule).await? {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(rule)))
+}
+
+async fn remove_rule(
+    State(state): State<PolicyState>,
+    headers: HeaderMap,
+    Json(rule): Json<PolicyRule>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if state.policies.remove(&rule).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!("no policy `{}`", rule)))
+    }
+}
+
+/// List, add, and remove rules; guarded by `Authorization: Bearer {admin_token}`
+///
+/// `POST /` answers 201 for a new rule and 200 if it already existed; `DELETE /` takes
+/// the rule to remove as its JSON body.
+pub fn policy_routes(policies: Arc<Policies>, admin_token: &str) -> Router {
+    Router::new()
+        .route("/", get(list_rules).post(add_rule).delete(remove_rule))
+        .route_layer(axum::middleware::from_fn(crate::audit::audit_admin))
+        .with_state(PolicyState {
+            policies,
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[derive(Clone)]
+struct RoleState {
+    roles: Arc<Roles>,
+    admin_token: Arc<str>,
+}
+
+async fn existing_role(roles: &Roles, name: &str) -> Result<RoleDetails, AppError> {
+    roles
+        .get(name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("no role '{}'", name)))
+}
+
+async fn list_roles(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<RoleDetails>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.roles.list().await?))
+}
+
+async fn create_role(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Json(role): Json<Role>,
+) -> Result<(StatusCode, Json<Role>), AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    validate_name("role", &role.name).map_err(|error| invalid("name", error))?;
+    if !state.roles.create(role.clone()).await? {
+        return Err(AppError::conflict(
+            vec![serde_json::json!({ "field": "name", "error": "taken" })],
+            format!("role '{}' already exists", role.name),
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+async fn show_role(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<RoleDetails>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(existing_role(&state.roles, &name).await?))
+}
+
+async fn delete_role(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if state.roles.delete(&name).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!("no role '{}'", name)))
+    }
+}
+
+async fn grant_permission(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(permission): Json<Permission>,
+) -> Result<Json<RoleDetails>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    existing_role(&state.roles, &name).await?;
+    state
+        .roles
+        .validate_permission(&name, &permission)
+        .map_err(|error| invalid("permission", error))?;
+    state.roles.grant(&name, &permission).await?;
+    Ok(Json(existing_role(&state.roles, &name).await?))
+}
+
+async fn revoke_permission(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(permission): Json<Permission>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    existing_role(&state.roles, &name).await?;
+    if state.roles.revoke(&name, &permission).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!(
+            "role '{}' has no permission {} {}",
+            name, permission.action, permission.object
+        )))
+    }
+}
+
+async fn assign_member(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path((name, member)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    existing_role(&state.roles, &name).await?;
+    validate_name("member", &member).map_err(|error| invalid("member", error))?;
+    state.roles.assign(&name, &member).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unassign_member(
+    State(state): State<RoleState>,
+    headers: HeaderMap,
+    Path((name, member)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    existing_role(&state.roles, &name).await?;
+    if state.roles.unassign(&name, &member).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!(
+            "'{}' does not have role '{}'",
+            member, name
+        )))
+    }
+}
+
+/// Manage roles, their permissions and their members; guarded like `policy_routes`
+///
+/// Members are any policy subject: a user id, a tenant such as `tenant:acme`, or another
+/// role to inherit from.
+pub fn role_routes(roles: Arc<Roles>, admin_token: &str) -> Router {
+    Router::new()
+        .route("/", get(list_roles).post(create_role))
+        .route("/{name}", get(show_role).delete(delete_role))
+        .route(
+            "/{name}/permissions",
+            post(grant_permission).delete(revoke_permission),
+        )
+        .route(
+            "/{name}/members/{member}",
+            put(assign_member).delete(unassign_member),
+        )
+        .route_layer(axum::middleware::from_fn(crate::audit::audit_admin))
+        .with_state(RoleState {
+            roles,
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{tests::MODEL, MemoryPolicyStore, MemoryRoleStore};
+    use crate::Enforcer;
+    use axum::{body::Body, http::header::AUTHORIZATION, http::Request};
+    use serde_json::Value;
+
+    async fn send(router: &Router, method: &str, body: &str, admin: bool) -> (StatusCode, Value) {
+        send_to(router, method, "/api/authz/policies", body, admin).await
+    }
+
+    async fn send_to(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        body: &str,
+        admin: bool,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if admin {
+            request = request.header(AUTHORIZATION, "Bearer s3cret");
+        }
+        atlas_http::testing::send(router, request.body(Body::from(body.to_string())).unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_edits_live_policies() {
+        let enforcer = Arc::new(Enforcer::from_strs(MODEL, "p, admin, /api/*, GET").unwrap());
+        let policies = Arc::new(Policies::new(
+            enforcer.clone(),
+            Arc::new(MemoryPolicyStore::default()),
+        ));
+        let router = Router::new().nest("/api/authz/policies", policy_routes(policies, "s3cret"));
+        let grant = r#"{"ptype":"g","values":["alice","admin"]}"#;
+
+        assert_eq!(
+            send(&router, "POST", grant, false).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&router, "POST", grant, true).await.0,
+            StatusCode::CREATED
+        );
+        assert_eq!(send(&router, "POST", grant, true).await.0, StatusCode::OK);
+        assert!(enforcer.enforce(&["alice", "/api/books", "GET"]).unwrap());
+
+        let (status, rules) = send(&router, "GET", "", true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(rules.as_array().unwrap().len(), 2);
+
+        let (status, _) = send(&router, "POST", r#"{"ptype":"p","values":["bob"]}"#, true).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(
+            send(&router, "DELETE", grant, true).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            send(&router, "DELETE", grant, true).await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert!(!enforcer.enforce(&["alice", "/api/books", "GET"]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_role_api_assigns_permissions_to_members() {
+        let enforcer = Arc::new(Enforcer::from_strs(MODEL, "").unwrap());
+        let policies = Arc::new(Policies::new(
+            enforcer.clone(),
+            Arc::new(MemoryPolicyStore::default()),
+        ));
+        let roles = Arc::new(Roles::new(Arc::new(MemoryRoleStore::new()), policies));
+        let router = Router::new().nest("/api/authz/roles", role_routes(roles, "s3cret"));
+        let editor = r#"{"name":"editor","description":"Edits books"}"#;
+        let permission = r#"{"object":"/api/books/*","action":"PUT"}"#;
+
+        let (status, _) = send_to(&router, "POST", "/api/authz/roles", editor, true).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send_to(&router, "POST", "/api/authz/roles", editor, true).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let uri = "/api/authz/roles/editor/permissions";
+        let (status, role) = send_to(&router, "POST", uri, permission, true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(role["permissions"][0]["action"], "PUT");
+
+        let uri = "/api/authz/roles/editor/members/tenant:acme";
+        assert_eq!(
+            send_to(&router, "PUT", uri, "", true).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert!(enforcer
+            .enforce(&["tenant:acme", "/api/books/1", "PUT"])
+            .unwrap());
+
+        let (status, role) = send_to(&router, "GET", "/api/authz/roles/editor", "", true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(role["members"][0], "tenant:acme");
+
+        let uri = "/api/authz/roles/ghost/members/alice";
+        assert_eq!(
+            send_to(&router, "PUT", uri, "", true).await.0,
+            StatusCode::NOT_FOUND
+        );
+        let uri = "/api/authz/roles/editor";
+        assert_eq!(
+            send_to(&router, "DELETE", uri, "", false).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send_to(&router, "DELETE", uri, "", true).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert!(!enforcer
+            .enforce(&["tenant:acme", "/api/books/1", "PUT"])
+            .unwrap());
+    }
+}
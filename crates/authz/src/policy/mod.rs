@@ -0,0 +1,327 @@
+//! Policy rules and their persistence
+//!
+//! A `PolicyStore` plays the role of a Casbin adapter: it loads rules at startup and
+//! persists changes made through `Policies`, which applies them to the live enforcer.
+//! `Roles` builds role management on top of it.
+
+mod http;
+mod roles;
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use atlas_db::{affected, QueryExecutor};
+
+use crate::Enforcer;
+
+pub use http::{policy_routes, role_routes};
+pub use roles::{MemoryRoleStore, Permission, Role, RoleDetails, RoleStore, Roles};
+
+/// A single policy line such as `p, admin, /api/*, *` or `g, alice, admin`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// `p` for permissions, `g` for role assignments
+    pub ptype: String,
+    pub values: Vec<String>,
+}
+
+impl PolicyRule {
+    pub fn new<I, S>(ptype: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            ptype: ptype.to_string(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Parse Casbin CSV policy text, skipping blank lines and `#` comments
+    pub fn parse_csv(text: &str) -> anyhow::Result<Vec<Self>> {
+        let mut rules = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(',').map(str::trim);
+            let ptype = fields.next().unwrap_or_default();
+            if ptype.is_empty() {
+                bail!("policy line {}: missing policy type", number + 1);
+            }
+            rules.push(Self::new(ptype, fields));
+        }
+        Ok(rules)
+    }
+}
+
+impl std::fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ptype)?;
+        for value in &self.values {
+            write!(f, ", {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Persistence for policy rules
+///
+/// The authz module stores rules in the `casbin_rule` table through the application's
+/// `QueryExecutor`; applications may publish their own `Arc<dyn PolicyStore>` resource
+/// instead. Without either, rules from the CSV file are kept in memory and runtime
+/// changes are lost on restart.
+#[async_trait]
+pub trait PolicyStore: Send + Sync {
+    async fn load(&self) -> anyhow::Result<Vec<PolicyRule>>;
+
+    /// Persist a rule, returning `false` if it was already stored
+    async fn add(&self, rule: &PolicyRule) -> anyhow::Result<bool>;
+
+    /// Delete a rule, returning `false` if it was not stored
+    async fn remove(&self, rule: &PolicyRule) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; rules are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryPolicyStore {
+    rules: RwLock<Vec<PolicyRule>>,
+}
+
+impl MemoryPolicyStore {
+    pub fn new(rules: Vec<PolicyRule>) -> Self {
+        Self {
+            rules: RwLock::new(rules),
+        }
+    }
+}
+
+#[async_trait]
+impl PolicyStore for MemoryPolicyStore {
+    async fn load(&self) -> anyhow::Result<Vec<PolicyRule>> {
+        Ok(self
+            .rules
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone())
+    }
+
+    async fn add(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        let mut rules = self
+            .rules
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if rules.contains(rule) {
+            return Ok(false);
+        }
+        rules.push(rule.clone());
+        Ok(true)
+    }
+
+    async fn remove(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        let mut rules = self
+            .rules
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = rules.len();
+        rules.retain(|existing| existing != rule);
+        Ok(rules.len() != before)
+    }
+}
+
+/// Unique index keeping one record per rule
+const RULE_INDEX: &str = "casbin_rule_unique";
+
+/// Rules in the `casbin_rule` table defined by the authz module's migrations
+pub struct SurrealPolicyStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealPolicyStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    fn vars(rule: &PolicyRule) -> Map<String, Value> {
+        let mut vars = Map::new();
+        vars.insert("ptype".to_string(), json!(rule.ptype));
+        vars.insert("values".to_string(), json!(rule.values));
+        vars
+    }
+}
+
+#[async_trait]
+impl PolicyStore for SurrealPolicyStore {
+    async fn load(&self) -> anyhow::Result<Vec<PolicyRule>> {
+        let results = self
+            .db
+            .query("SELECT ptype, values FROM casbin_rule ORDER BY ptype, values;")
+            .await?;
+        atlas_db::records(results.into_iter().next())
+    }
+
+    async fn add(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        match self
+            .db
+            .query_with(
+                "CREATE casbin_rule SET ptype = $ptype, values = $values RETURN NONE;",
+                &Self::vars(rule),
+            )
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error)
+                if atlas_db::unique_index_violation(&error).as_deref() == Some(RULE_INDEX) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn remove(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        let results = self
+            .db
+            .query_with(
+                "DELETE casbin_rule WHERE ptype = $ptype AND values = $values RETURN BEFORE;",
+                &Self::vars(rule),
+            )
+            .await?;
+        Ok(affected(results.first()))
+    }
+}
+
+/// Changes policies at runtime, persisting them and updating the live enforcer together
+pub struct Policies {
+    enforcer: Arc<Enforcer>,
+    store: Arc<dyn PolicyStore>,
+}
+
+impl Policies {
+    pub fn new(enforcer: Arc<Enforcer>, store: Arc<dyn PolicyStore>) -> Self {
+        Self { enforcer, store }
+    }
+
+    pub fn enforcer(&self) -> &Arc<Enforcer> {
+        &self.enforcer
+    }
+
+    pub fn list(&self) -> Vec<PolicyRule> {
+        self.enforcer.rules()
+    }
+
+    /// Add a rule, returning `false` if it already existed
+    pub async fn add(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        self.enforcer.validate_rule(rule)?;
+        // Persist first, so a failed write never leaves an unsaved rule in effect.
+        let added = self
+            .store
+            .add(rule)
+            .await
+            .with_context(|| format!("failed to store policy `{}`", rule))?;
+        self.enforcer.add_rule(rule)?;
+        if added {
+            tracing::info!(rule = %rule, "policy added");
+        }
+        Ok(added)
+    }
+
+    /// Remove a rule, returning `false` if it did not exist
+    pub async fn remove(&self, rule: &PolicyRule) -> anyhow::Result<bool> {
+        let removed = self
+            .store
+            .remove(rule)
+            .await
+            .with_context(|| format!("failed to delete policy `{}`", rule))?;
+        let applied = self.enforcer.remove_rule(rule);
+        if removed || applied {
+            tracing::info!(rule = %rule, "policy removed");
+        }
+        Ok(removed || applied)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use atlas_db::testing::RecordingExecutor;
+
+    pub(crate) const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && keyMatch(r.obj, p.obj) && r.act == p.act
+"#;
+
+    #[test]
+    fn test_csv_parses_rules() {
+        let rules =
+            PolicyRule::parse_csv("# comment\n\np, admin, /api/*, GET\ng, alice, admin\n").unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                PolicyRule::new("p", ["admin", "/api/*", "GET"]),
+                PolicyRule::new("g", ["alice", "admin"]),
+            ]
+        );
+        assert_eq!(rules[1].to_string(), "g, alice, admin");
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_persisted_and_enforced() {
+        let store = Arc::new(MemoryPolicyStore::default());
+        let enforcer = Arc::new(Enforcer::from_strs(MODEL, "p, admin, /api/*, GET").unwrap());
+        let policies = Policies::new(enforcer.clone(), store.clone());
+        let grant = PolicyRule::new("g", ["alice", "admin"]);
+
+        assert!(policies.add(&grant).await.unwrap());
+        assert!(!policies.add(&grant).await.unwrap());
+        assert!(enforcer.enforce(&["alice", "/api/books", "GET"]).unwrap());
+        assert_eq!(store.load().await.unwrap(), vec![grant.clone()]);
+
+        assert!(policies.remove(&grant).await.unwrap());
+        assert!(!enforcer.enforce(&["alice", "/api/books", "GET"]).unwrap());
+        assert!(store.load().await.unwrap().is_empty());
+
+        assert!(policies
+            .add(&PolicyRule::new("x", ["alice"]))
+            .await
+            .is_err());
+        assert!(store.load().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_reports_existing_and_missing_rules() {
+        let db = RecordingExecutor::answering(vec![
+            Ok(vec![json!([{ "ptype": "p", "values": ["admin", "/api/*", "GET"] }])]),
+            Err(anyhow::anyhow!(
+                "Database index `casbin_rule_unique` already contains ['p', ['admin']], with record `casbin_rule:1`"
+            )),
+            Ok(vec![json!([])]),
+        ]);
+        let store = SurrealPolicyStore::new(db.clone());
+        let rule = PolicyRule::new("p", ["admin", "/api/*", "GET"]);
+
+        assert_eq!(store.load().await.unwrap(), vec![rule.clone()]);
+        assert!(!store.add(&rule).await.unwrap());
+        assert!(!store.remove(&rule).await.unwrap());
+
+        let (_, vars) = db.queries()[2].clone();
+        assert_eq!(vars["values"], json!(["admin", "/api/*", "GET"]));
+    }
+}
@@ -0,0 +1,294 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::bail;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{Policies, PolicyRule};
+
+/// A named role; its permissions and members live in the policy rules
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Role {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// An `(object, action)` pair granted to a role as `p, role, object, action`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permission {
+    pub object: String,
+    pub action: String,
+}
+
+/// A role with the permissions and members currently in effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoleDetails {
+    #[serde(flatten)]
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    /// Users, tenants or other roles assigned this role through `g` rules
+    pub members: Vec<String>,
+}
+
+/// Persistence for role definitions
+///
+/// The database module provides a SurrealDB-backed store by publishing an
+/// `Arc<dyn RoleStore>` resource; without one, roles live in memory.
+#[async_trait]
+pub trait RoleStore: Send + Sync {
+    async fn list(&self) -> anyhow::Result<Vec<Role>>;
+
+    /// Persist a role, returning `false` if one with that name exists
+    async fn insert(&self, role: Role) -> anyhow::Result<bool>;
+
+    /// Delete a role, returning `false` if none has that name
+    async fn remove(&self, name: &str) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; roles are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryRoleStore {
+    roles: RwLock<Vec<Role>>,
+}
+
+impl MemoryRoleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RoleStore for MemoryRoleStore {
+    async fn list(&self) -> anyhow::Result<Vec<Role>> {
+        Ok(self
+            .roles
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone())
+    }
+
+    async fn insert(&self, role: Role) -> anyhow::Result<bool> {
+        let mut roles = self
+            .roles
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if roles.iter().any(|existing| existing.name == role.name) {
+            return Ok(false);
+        }
+        roles.push(role);
+        Ok(true)
+    }
+
+    async fn remove(&self, name: &str) -> anyhow::Result<bool> {
+        let mut roles = self
+            .roles
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = roles.len();
+        roles.retain(|role| role.name != name);
+        Ok(roles.len() != before)
+    }
+}
+
+/// Role management on top of `Policies`; every change is enforced immediately
+pub struct Roles {
+    store: Arc<dyn RoleStore>,
+    policies: Arc<Policies>,
+}
+
+impl Roles {
+    pub fn new(store: Arc<dyn RoleStore>, policies: Arc<Policies>) -> Self {
+        Self { store, policies }
+    }
+
+    pub async fn list(&self) -> anyhow::Result<Vec<RoleDetails>> {
+        let rules = self.policies.list();
+        let mut roles = self.store.list().await?;
+        roles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(roles
+            .into_iter()
+            .map(|role| details(role, &rules))
+            .collect())
+    }
+
+    pub async fn get(&self, name: &str) -> anyhow::Result<Option<RoleDetails>> {
+        let role = self
+            .store
+            .list()
+            .await?
+            .into_iter()
+            .find(|role| role.name == name);
+        Ok(role.map(|role| details(role, &self.policies.list())))
+    }
+
+    /// Define a role, returning `false` if it already exists
+    pub async fn create(&self, role: Role) -> anyhow::Result<bool> {
+        validate_name("role", &role.name)?;
+        self.store.insert(role).await
+    }
+
+    /// Delete a role with every permission and assignment referring to it
+    pub async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+        let Some(role) = self.get(name).await? else {
+            return Ok(false);
+        };
+        for permission in &role.permissions {
+            self.policies
+                .remove(&permission_rule(name, permission))
+                .await?;
+        }
+        for member in &role.members {
+            self.policies.remove(&member_rule(name, member)).await?;
+        }
+        self.store.remove(name).await
+    }
+
+    /// Check a permission fits the model's `p = sub, obj, act` shape
+    pub fn validate_permission(&self, role: &str, permission: &Permission) -> anyhow::Result<()> {
+        self.policies
+            .enforcer()
+            .validate_rule(&permission_rule(role, permission))
+    }
+
+    pub async fn grant(&self, role: &str, permission: &Permission) -> anyhow::Result<bool> {
+        self.policies.add(&permission_rule(role, permission)).await
+    }
+
+    pub async fn revoke(&self, role: &str, permission: &Permission) -> anyhow::Result<bool> {
+        self.policies
+            .remove(&permission_rule(role, permission))
+            .await
+    }
+
+    /// Assign `role` to a user, tenant or other role
+    pub async fn assign(&self, role: &str, member: &str) -> anyhow::Result<bool> {
+        validate_name("member", member)?;
+        self.policies.add(&member_rule(role, member)).await
+    }
+
+    pub async fn unassign(&self, role: &str, member: &str) -> anyhow::Result<bool> {
+        self.policies.remove(&member_rule(role, member)).await
+    }
+}
+
+/// Names end up as CSV policy fields, so commas and surrounding whitespace are not allowed
+pub(crate) fn validate_name(kind: &str, name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.trim() != name || name.contains(',') {
+        bail!(
+            "{} name must be non-empty, without commas or surrounding whitespace",
+            kind
+        );
+    }
+    Ok(())
+}
+
+fn permission_rule(role: &str, permission: &Permission) -> PolicyRule {
+    PolicyRule::new("p", [role, &permission.object, &permission.action])
+}
+
+fn member_rule(role: &str, member: &str) -> PolicyRule {
+    PolicyRule::new("g", [member, role])
+}
+
+fn details(role: Role, rules: &[PolicyRule]) -> RoleDetails {
+    let permissions = rules
+        .iter()
+        .filter(|rule| rule.ptype == "p" && rule.values.len() == 3 && rule.values[0] == role.name)
+        .map(|rule| Permission {
+            object: rule.values[1].clone(),
+            action: rule.values[2].clone(),
+        })
+        .collect();
+    let members = rules
+        .iter()
+        .filter(|rule| rule.ptype == "g" && rule.values[1] == role.name)
+        .map(|rule| rule.values[0].clone())
+        .collect();
+    RoleDetails {
+        role,
+        permissions,
+        members,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{tests::MODEL, MemoryPolicyStore};
+    use crate::Enforcer;
+
+    fn roles() -> (Roles, Arc<Enforcer>) {
+        let enforcer = Arc::new(Enforcer::from_strs(MODEL, "").unwrap());
+        let policies = Arc::new(Policies::new(
+            enforcer.clone(),
+            Arc::new(MemoryPolicyStore::default()),
+        ));
+        (
+            Roles::new(Arc::new(MemoryRoleStore::new()), policies),
+            enforcer,
+        )
+    }
+
+    fn editor() -> Role {
+        Role {
+            name: "editor".to_string(),
+            description: Some("Edits books".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_role_changes_are_enforced_immediately() {
+        let (roles, enforcer) = roles();
+        let permission = Permission {
+            object: "/api/books/*".to_string(),
+            action: "PUT".to_string(),
+        };
+
+        assert!(roles.create(editor()).await.unwrap());
+        assert!(!roles.create(editor()).await.unwrap());
+        roles.grant("editor", &permission).await.unwrap();
+        roles.assign("editor", "tenant:acme").await.unwrap();
+        assert!(enforcer
+            .enforce(&["tenant:acme", "/api/books/1", "PUT"])
+            .unwrap());
+
+        let details = roles.get("editor").await.unwrap().unwrap();
+        assert_eq!(details.permissions, vec![permission.clone()]);
+        assert_eq!(details.members, vec!["tenant:acme".to_string()]);
+
+        roles.unassign("editor", "tenant:acme").await.unwrap();
+        assert!(!enforcer
+            .enforce(&["tenant:acme", "/api/books/1", "PUT"])
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_deleting_role_removes_its_rules() {
+        let (roles, enforcer) = roles();
+        roles.create(editor()).await.unwrap();
+        roles
+            .grant(
+                "editor",
+                &Permission {
+                    object: "/api/books/*".to_string(),
+                    action: "PUT".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        roles.assign("editor", "alice").await.unwrap();
+
+        assert!(roles.delete("editor").await.unwrap());
+        assert!(enforcer.rules().is_empty());
+        assert!(roles.list().await.unwrap().is_empty());
+        assert!(!roles.delete("editor").await.unwrap());
+        assert!(roles
+            .create(Role {
+                name: "a,b".to_string(),
+                description: None
+            })
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use atlas_http::{error::AppError, ip::client_ip, tenant::TenantCtx};
+
+use super::{Budget, RateLimiter};
+use crate::Subject;
+
+/// Route middleware counting requests against the caller's budget
+///
+/// Install after the layer that authenticates the caller so the `Subject` is known;
+/// without one the request counts against its tenant, then its client IP. Responses
+/// carry `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`; exhausted
+/// budgets get 429 with `Retry-After`.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !limiter.enabled() {
+        return next.run(request).await;
+    }
+
+    let tenant = request
+        .extensions()
+        .get::<TenantCtx>()
+        .map(|tenant| tenant.id.as_str().to_string());
+    let budget = match (request.extensions().get::<Subject>(), &tenant) {
+        (Some(subject), _) => Budget::Subject(subject.0.clone()),
+        (None, Some(tenant)) => Budget::Tenant(tenant.clone()),
+        (None, None) => Budget::Ip(client_ip(&request, limiter.trust_forwarded_for())),
+    };
+
+    let decision = limiter.check(&budget, tenant.as_deref());
+    let reset = decision.reset_after.as_secs().max(1);
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        tracing::debug!(budget = %budget, limit = decision.limit, "rate limit exceeded");
+        AppError::too_many_requests("rate limit exceeded", reset).into_response()
+    };
+
+    let headers = response.headers_mut();
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(reset));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::tests::{clock, settings};
+    use axum::{
+        body::Body,
+        http::{HeaderMap, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn router(limiter: Arc<RateLimiter>, subject: Option<&'static str>) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(limiter, rate_limit))
+            .route_layer(axum::middleware::from_fn(
+                move |mut request: Request, next: Next| async move {
+                    if let Some(subject) = subject {
+                        request
+                            .extensions_mut()
+                            .insert(Subject(subject.to_string()));
+                    }
+                    next.run(request).await
+                },
+            ))
+    }
+
+    async fn status(router: &Router, forwarded_for: &str) -> (StatusCode, HeaderMap) {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-forwarded-for", forwarded_for)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_callers_are_limited_per_ip() {
+        let limiter = Arc::new(RateLimiter::new(
+            atlas_kernel::settings::RateLimitSettings {
+                trust_forwarded_for: true,
+                ..settings()
+            },
+            clock(),
+        ));
+        let router = router(limiter, None);
+
+        assert_eq!(status(&router, "10.0.0.1").await.0, StatusCode::OK);
+        let (code, headers) = status(&router, "10.0.0.1").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(headers["ratelimit-remaining"], "0");
+
+        let (code, headers) = status(&router, "10.0.0.1").await;
+        assert_eq!(code, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(headers["retry-after"], "40");
+        assert_eq!(status(&router, "10.0.0.2").await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_forwarded_for_entries_share_the_proxied_bucket() {
+        let limiter = Arc::new(RateLimiter::new(
+            atlas_kernel::settings::RateLimitSettings {
+                trust_forwarded_for: true,
+                ..settings()
+            },
+            clock(),
+        ));
+        let router = router(limiter, None);
+
+        assert_eq!(status(&router, "10.0.0.1").await.0, StatusCode::OK);
+        assert_eq!(
+            status(&router, "198.51.100.7, 10.0.0.1").await.0,
+            StatusCode::OK
+        );
+        // A fresh address in front of the proxied one does not buy a fresh budget
+        assert_eq!(
+            status(&router, "198.51.100.8, 10.0.0.1").await.0,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_callers_use_their_tier() {
+        let limiter = Arc::new(RateLimiter::new(settings(), clock()));
+        let router = router(limiter, Some("user:paid"));
+
+        for _ in 0..5 {
+            assert_eq!(status(&router, "10.0.0.1").await.0, StatusCode::OK);
+        }
+        let (code, headers) = status(&router, "10.0.0.1").await;
+        assert_eq!(code, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(headers["ratelimit-limit"], "5");
+    }
+}
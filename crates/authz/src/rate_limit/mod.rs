@@ -0,0 +1,223 @@
+//! Request budgets keyed by the authenticated caller
+//!
+//! Authenticated requests count against their subject, anonymous requests of a resolved
+//! tenant against the tenant, and the rest against the client IP. Subjects and tenants
+//! assigned a tier in `[rate_limit]` get that tier's budget.
+
+mod http;
+mod module;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+
+use atlas_kernel::{settings::RateLimitSettings, Clock};
+
+pub use http::rate_limit;
+pub use module::{create_module, RateLimitModule};
+
+/// Who a request is counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Subject(String),
+    Tenant(String),
+    Ip(IpAddr),
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Subject(subject) => write!(f, "subject:{}", subject),
+            Self::Tenant(tenant) => write!(f, "tenant:{}", tenant),
+            Self::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+/// Outcome of counting one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    /// Requests allowed per window for this budget
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the current window ends
+    pub reset_after: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    index: u64,
+    count: u32,
+}
+
+/// Fixed-window request counters, one per budget
+pub struct RateLimiter {
+    settings: RwLock<RateLimitSettings>,
+    windows: Mutex<HashMap<Budget, Window>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+            windows: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.settings().enabled
+    }
+
+    /// Whether the client IP may be read from `X-Forwarded-For`
+    pub fn trust_forwarded_for(&self) -> bool {
+        self.settings().trust_forwarded_for
+    }
+
+    /// Apply reloaded settings; counters of the current window are kept
+    pub fn update(&self, settings: RateLimitSettings) {
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+
+    /// Requests per window for `budget`, taking tiers of the subject, then its tenant
+    pub fn limit_for(&self, budget: &Budget, tenant: Option<&str>) -> u32 {
+        let settings = self.settings();
+        let tier = |principal: &str| {
+            settings
+                .assignments
+                .get(principal)
+                .and_then(|tier| settings.tiers.get(tier))
+                .copied()
+        };
+        match budget {
+            Budget::Ip(_) => settings.anonymous,
+            Budget::Subject(subject) => tier(subject)
+                .or_else(|| tenant.and_then(tier))
+                .unwrap_or(settings.authenticated),
+            Budget::Tenant(tenant) => tier(tenant).unwrap_or(settings.authenticated),
+        }
+    }
+
+    /// Count a request against `budget`
+    pub fn check(&self, budget: &Budget, tenant: Option<&str>) -> Decision {
+        let limit = self.limit_for(budget, tenant);
+        let (index, reset_after) = self.window();
+
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let window = windows
+            .entry(budget.clone())
+            .or_insert(Window { index, count: 0 });
+        if window.index != index {
+            *window = Window { index, count: 0 };
+        }
+        let allowed = window.count < limit;
+        if allowed {
+            window.count += 1;
+        }
+        Decision {
+            allowed,
+            limit,
+            remaining: limit - window.count,
+            reset_after,
+        }
+    }
+
+    /// Drop counters of past windows, returning how many were dropped
+    pub fn purge(&self) -> usize {
+        let (index, _) = self.window();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let before = windows.len();
+        windows.retain(|_, window| window.index == index);
+        before - windows.len()
+    }
+
+    /// Length of a window
+    pub fn window_length(&self) -> Duration {
+        Duration::from_secs(self.settings().window_secs.max(1))
+    }
+
+    /// Index of the current window and the time left in it
+    fn window(&self) -> (u64, Duration) {
+        let length = self.window_length();
+        let now = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let index = now.as_secs() / length.as_secs();
+        let end = Duration::from_secs((index + 1) * length.as_secs());
+        (index, end - now)
+    }
+
+    fn settings(&self) -> std::sync::RwLockReadGuard<'_, RateLimitSettings> {
+        self.settings.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::SystemTime;
+
+    pub(super) fn settings() -> RateLimitSettings {
+        RateLimitSettings {
+            enabled: true,
+            anonymous: 2,
+            authenticated: 3,
+            tiers: HashMap::from([("pro".to_string(), 5)]),
+            assignments: HashMap::from([
+                ("user:paid".to_string(), "pro".to_string()),
+                ("acme".to_string(), "pro".to_string()),
+            ]),
+            ..RateLimitSettings::default()
+        }
+    }
+
+    pub(super) fn clock() -> Arc<ManualClock> {
+        Arc::new(ManualClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ))
+    }
+
+    #[test]
+    fn test_limits_follow_tiers_of_subject_then_tenant() {
+        let limiter = RateLimiter::new(settings(), clock());
+        let subject = |name: &str| Budget::Subject(name.to_string());
+
+        assert_eq!(
+            limiter.limit_for(&Budget::Ip([10, 0, 0, 1].into()), None),
+            2
+        );
+        assert_eq!(limiter.limit_for(&subject("user:free"), None), 3);
+        assert_eq!(limiter.limit_for(&subject("user:paid"), None), 5);
+        assert_eq!(limiter.limit_for(&subject("user:free"), Some("acme")), 5);
+        assert_eq!(
+            limiter.limit_for(&Budget::Tenant("acme".to_string()), Some("acme")),
+            5
+        );
+    }
+
+    #[test]
+    fn test_budget_resets_with_the_window() {
+        let clock = clock();
+        let limiter = RateLimiter::new(settings(), clock.clone());
+        let budget = Budget::Subject("user:free".to_string());
+
+        let decisions: Vec<bool> = (0..4)
+            .map(|_| limiter.check(&budget, None).allowed)
+            .collect();
+        assert_eq!(decisions, vec![true, true, true, false]);
+        assert_eq!(limiter.check(&budget, None).remaining, 0);
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(limiter.purge(), 1);
+        let decision = limiter.check(&budget, None);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.reset_after, Duration::from_secs(40));
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+
+use atlas_kernel::{settings::Settings, InitCtx, Module};
+
+use super::RateLimiter;
+
+/// Core module publishing a `RateLimiter` configured from `[rate_limit]`
+///
+/// Modules guard their routes with `rate_limit` after authentication. Limits and tiers
+/// follow config reloads.
+#[derive(Default)]
+pub struct RateLimitModule {
+    limiter: OnceLock<Arc<RateLimiter>>,
+}
+
+#[async_trait]
+impl Module for RateLimitModule {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Request budgets per subject, tenant and client IP")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = ctx.settings.rate_limit.clone();
+        if !settings.enabled {
+            tracing::info!(
+                "rate limiting disabled; rate_limit middleware lets every request through"
+            );
+        }
+        let limiter = Arc::new(RateLimiter::new(settings, ctx.clock.clone()));
+        ctx.resources.insert_arc(limiter.clone());
+
+        self.limiter
+            .set(limiter)
+            .map_err(|_| anyhow::anyhow!("rate_limit module initialized twice"))?;
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let Some(limiter) = self.limiter.get().cloned() else {
+            return Ok(());
+        };
+        let clock = ctx.clock.clone();
+        ctx.spawn("purge_windows", async move {
+            loop {
+                clock.sleep(limiter.window_length()).await;
+                let purged = limiter.purge();
+                if purged > 0 {
+                    tracing::debug!(purged, "purged expired rate limit windows");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn on_config_change(&self, settings: &Settings) -> anyhow::Result<()> {
+        if let Some(limiter) = self.limiter.get() {
+            limiter.update(settings.rate_limit.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Create a new instance of the rate limit module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(RateLimitModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::Budget;
+    use atlas_kernel::ModuleRegistry;
+
+    #[tokio::test]
+    async fn test_reload_updates_limits() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(Settings::default())))
+            .await
+            .unwrap();
+        let limiter = registry.resources().require::<RateLimiter>().unwrap();
+        let budget = Budget::Subject("user:1".to_string());
+        assert_eq!(limiter.limit_for(&budget, None), 600);
+
+        let mut settings = Settings::default();
+        settings.rate_limit.authenticated = 1200;
+        registry.modules()[0]
+            .on_config_change(&settings)
+            .await
+            .unwrap();
+
+        assert_eq!(limiter.limit_for(&budget, None), 1200);
+    }
+}
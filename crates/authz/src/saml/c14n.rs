@@ -0,0 +1,218 @@
+//! Exclusive XML canonicalization (`http://www.w3.org/2001/10/xml-exc-c14n#`)
+//!
+//! Only what XML signatures over SAML need: one element subtree, optionally without a
+//! descendant (the enveloped signature), comments removed.
+
+use std::collections::BTreeMap;
+
+use roxmltree::{Node, NodeType};
+
+/// The canonical form of `element`, leaving out `excluded` and its descendants
+///
+/// `inclusive` lists the prefixes of an `InclusiveNamespaces` `PrefixList`; `#default`
+/// stands for the default namespace.
+pub(super) fn canonicalize(element: Node, excluded: Option<Node>, inclusive: &[&str]) -> String {
+    let mut output = String::new();
+    write_element(&mut output, element, excluded, inclusive, &BTreeMap::new());
+    output
+}
+
+fn write_node(
+    output: &mut String,
+    node: Node,
+    excluded: Option<Node>,
+    inclusive: &[&str],
+    rendered: &BTreeMap<String, String>,
+) {
+    if excluded == Some(node) {
+        return;
+    }
+    match node.node_type() {
+        NodeType::Element => write_element(output, node, excluded, inclusive, rendered),
+        NodeType::Text => escape_text(output, node.text().unwrap_or_default()),
+        NodeType::PI => {
+            if let Some(pi) = node.pi() {
+                output.push_str("<?");
+                output.push_str(pi.target);
+                if let Some(value) = pi.value {
+                    output.push(' ');
+                    output.push_str(value);
+                }
+                output.push_str("?>");
+            }
+        }
+        NodeType::Comment | NodeType::Root => {}
+    }
+}
+
+/// Writes `element`, declaring the namespaces it uses that no output ancestor declared
+///
+/// `rendered` maps each prefix declared by an output ancestor, `""` for the default
+/// namespace, to its URI.
+fn write_element(
+    output: &mut String,
+    element: Node,
+    excluded: Option<Node>,
+    inclusive: &[&str],
+    rendered: &BTreeMap<String, String>,
+) {
+    let name = qualified_name(element);
+    let (prefix, _) = split(name);
+
+    // Visibly utilized prefixes, and the inclusive ones in scope
+    let mut utilized = vec![prefix];
+    for attribute in element.attributes() {
+        let (attribute_prefix, _) = split(attribute_name(element, attribute));
+        if !attribute_prefix.is_empty() {
+            utilized.push(attribute_prefix);
+        }
+    }
+    for prefix in inclusive {
+        let prefix = if *prefix == "#default" { "" } else { prefix };
+        if element.lookup_namespace_uri(non_empty(prefix)).is_some() {
+            utilized.push(prefix);
+        }
+    }
+
+    let mut scope = rendered.clone();
+    let mut declarations = BTreeMap::new();
+    for prefix in utilized {
+        let uri = element
+            .lookup_namespace_uri(non_empty(prefix))
+            .unwrap_or_default();
+        let declared = scope.get(prefix).map(String::as_str).unwrap_or_default();
+        // The default namespace is undeclared until an ancestor declared another one
+        if uri != declared {
+            scope.insert(prefix.to_string(), uri.to_string());
+            declarations.insert(prefix, uri);
+        }
+    }
+
+    output.push('<');
+    output.push_str(name);
+    for (prefix, uri) in &declarations {
+        if prefix.is_empty() {
+            output.push_str(" xmlns=\"");
+        } else {
+            output.push_str(" xmlns:");
+            output.push_str(prefix);
+            output.push_str("=\"");
+        }
+        escape_attribute(output, uri);
+        output.push('"');
+    }
+
+    let mut attributes: Vec<_> = element.attributes().collect();
+    attributes
+        .sort_by_key(|attribute| (attribute.namespace().unwrap_or_default(), attribute.name()));
+    for attribute in attributes {
+        output.push(' ');
+        output.push_str(attribute_name(element, attribute));
+        output.push_str("=\"");
+        escape_attribute(output, attribute.value());
+        output.push('"');
+    }
+    output.push('>');
+
+    for child in element.children() {
+        write_node(output, child, excluded, inclusive, &scope);
+    }
+
+    output.push_str("</");
+    output.push_str(name);
+    output.push('>');
+}
+
+/// The element's name as written, with its prefix
+fn qualified_name<'a>(element: Node<'a, '_>) -> &'a str {
+    let input = element.document().input_text();
+    let start = element.range().start + 1;
+    let end = input[start..]
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .map_or(input.len(), |len| start + len);
+    &input[start..end]
+}
+
+/// The attribute's name as written, with its prefix
+fn attribute_name<'a>(element: Node<'a, '_>, attribute: roxmltree::Attribute<'a, '_>) -> &'a str {
+    &element.document().input_text()[attribute.range_qname()]
+}
+
+/// Prefix, empty when there is none, and local name of a qualified name
+fn split(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("", name))
+}
+
+fn non_empty(prefix: &str) -> Option<&str> {
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+fn escape_text(output: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#xD;"),
+            c => output.push(c),
+        }
+    }
+}
+
+fn escape_attribute(output: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(xml: &str, inclusive: &[&str]) -> String {
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        canonicalize(doc.root_element(), None, inclusive)
+    }
+
+    #[test]
+    fn test_only_utilized_namespaces_are_declared_where_first_used() {
+        let xml = r#"<a:root xmlns:a="urn:a" xmlns:b="urn:b" xmlns:c="urn:c" z="1" b:y='2' a="&lt;&quot;"><!-- gone --><a:empty/><b:child c:x="3">x &amp; y &gt;</b:child></a:root>"#;
+
+        assert_eq!(
+            canonical(xml, &[]),
+            concat!(
+                r#"<a:root xmlns:a="urn:a" xmlns:b="urn:b" a="&lt;&quot;" z="1" b:y="2">"#,
+                r#"<a:empty></a:empty>"#,
+                r#"<b:child xmlns:c="urn:c" c:x="3">x &amp; y &gt;</b:child>"#,
+                r#"</a:root>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_inclusive_prefixes_and_default_namespaces() {
+        let xml =
+            r#"<root xmlns="urn:d" xmlns:xs="urn:xs"><inner xmlns=""><xs:leaf/></inner></root>"#;
+
+        assert_eq!(
+            canonical(xml, &["xs"]),
+            concat!(
+                r#"<root xmlns="urn:d" xmlns:xs="urn:xs">"#,
+                r#"<inner xmlns=""><xs:leaf></xs:leaf></inner>"#,
+                r#"</root>"#,
+            )
+        );
+        assert_eq!(
+            canonical(r#"<root xmlns:xs="urn:xs"/>"#, &[]),
+            "<root></root>"
+        );
+    }
+}
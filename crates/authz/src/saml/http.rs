@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{
+        header::{CONTENT_TYPE, SET_COOKIE},
+        HeaderMap,
+    },
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use serde::Deserialize;
+
+use atlas_http::error::AppError;
+use atlas_kernel::Resources;
+
+use super::{Saml, SamlError, PENDING_TTL};
+use crate::login_cookie;
+use crate::oauth::IdentityLinker;
+use crate::Sessions;
+
+impl From<SamlError> for AppError {
+    fn from(error: SamlError) -> Self {
+        match error {
+            SamlError::UnknownProvider(_) => AppError::not_found(error.to_string()),
+            SamlError::MalformedResponse | SamlError::Rejected(_) => {
+                AppError::bad_request(error.to_string())
+            }
+            SamlError::Unverified(source) => {
+                tracing::warn!(error = %source, "SAML response failed verification");
+                AppError::unauthorized("SAML response could not be verified")
+            }
+            SamlError::Internal(error) => AppError::Internal(error),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SamlState {
+    saml: Arc<Saml>,
+    sessions: Arc<Sessions>,
+    /// Looked up per login, since the module providing the linker may init after us
+    resources: Arc<Resources>,
+    success_redirect: Arc<str>,
+}
+
+/// Form posted by the identity provider; `RelayState` is ignored so it cannot redirect
+#[derive(Debug, Deserialize)]
+struct AcsForm {
+    #[serde(rename = "SAMLResponse")]
+    saml_response: String,
+}
+
+async fn list_providers(State(state): State<SamlState>) -> Json<Vec<String>> {
+    Json(
+        state
+            .saml
+            .provider_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+async fn metadata(
+    State(state): State<SamlState>,
+    Path(idp): Path<String>,
+) -> Result<Response, AppError> {
+    let metadata = state.saml.metadata(&idp)?;
+    Ok(([(CONTENT_TYPE, "application/samlmetadata+xml")], metadata).into_response())
+}
+
+async fn login(
+    State(state): State<SamlState>,
+    Path(idp): Path<String>,
+) -> Result<Response, AppError> {
+    let (url, request_id) = state.saml.begin(&idp)?;
+    let cookie =
+        login_cookie::SAML.set(&request_id, PENDING_TTL, state.sessions.secure_cookies())?;
+    Ok(([(SET_COOKIE, cookie)], Redirect::to(url.as_str())).into_response())
+}
+
+async fn acs(
+    State(state): State<SamlState>,
+    Path(idp): Path<String>,
+    headers: HeaderMap,
+    Form(form): Form<AcsForm>,
+) -> Result<Response, AppError> {
+    let started = login_cookie::SAML.value(&headers);
+    let identity = state
+        .saml
+        .complete(&idp, &form.saml_response, started.as_deref())
+        .await?;
+    let subject = match state.resources.get::<Arc<dyn IdentityLinker>>() {
+        Some(linker) => linker.link(&identity).await?,
+        None => identity.default_subject(),
+    };
+    tracing::info!(provider = %identity.provider, subject = %subject, "SAML login succeeded");
+
+    let (_, token) = state.sessions.create(&subject).await?;
+    let cookies = AppendHeaders([
+        (SET_COOKIE, state.sessions.cookie(&token)?),
+        (SET_COOKIE, state.sessions.csrf_cookie(&token)?),
+        (
+            SET_COOKIE,
+            login_cookie::SAML.clear(state.sessions.secure_cookies())?,
+        ),
+    ]);
+    Ok((cookies, Redirect::to(&state.success_redirect)).into_response())
+}
+
+/// `GET /` lists identity providers; `GET /{idp}/metadata`, `GET /{idp}/login` and
+/// `POST /{idp}/acs` serve the service provider side of each
+pub fn saml_routes(
+    saml: Arc<Saml>,
+    sessions: Arc<Sessions>,
+    resources: Arc<Resources>,
+    success_redirect: &str,
+) -> Router {
+    Router::new()
+        .route("/", get(list_providers))
+        .route("/{idp}/metadata", get(metadata))
+        .route("/{idp}/login", get(login))
+        .route("/{idp}/acs", post(acs))
+        .with_state(SamlState {
+            saml,
+            sessions,
+            resources,
+            success_redirect: Arc::from(success_redirect),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::saml::tests::{assertion, now, request_id, settings, FakeVerifier, RESPONSE};
+    use crate::sessions::MemorySessionStore;
+    use atlas_kernel::{clock::ManualClock, settings::SessionSettings};
+    use axum::{
+        body::Body,
+        http::{
+            header::{COOKIE, LOCATION},
+            Request, StatusCode,
+        },
+    };
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_acs_starts_session_after_login() {
+        let clock = Arc::new(ManualClock::new(now()));
+        let sessions = Arc::new(Sessions::new(
+            Arc::new(MemorySessionStore::new()),
+            clock.clone(),
+            SessionSettings::default(),
+        ));
+        let verifier = Arc::new(FakeVerifier::default());
+        let saml = Arc::new(Saml::new(&settings(), verifier.clone(), clock));
+        let router = Router::new().nest(
+            "/api/saml",
+            saml_routes(saml, sessions.clone(), Arc::new(Resources::new()), "/app"),
+        );
+        let send = |request: Request<Body>| router.clone().oneshot(request);
+
+        let response = send(
+            Request::builder()
+                .uri("/api/saml/acme/metadata")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "application/samlmetadata+xml"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(std::str::from_utf8(&body)
+            .unwrap()
+            .contains(r#"Location="https://app.example.com/api/saml/acme/acs""#));
+
+        let response = send(
+            Request::builder()
+                .uri("/api/saml/acme/login")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = url::Url::parse(response.headers()[LOCATION].to_str().unwrap()).unwrap();
+        let id = request_id(&location);
+        let started = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(started.starts_with(&format!("atlas_saml_request={};", id)));
+        assert!(started.contains("HttpOnly") && started.contains("SameSite=None; Secure"));
+        *verifier.assertion.lock().unwrap() = Some(assertion(Some(id.clone())));
+
+        let post = |response: &str, cookie: &str| {
+            let form = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("SAMLResponse", response)
+                .append_pair("RelayState", "https://evil.example")
+                .finish();
+            Request::builder()
+                .method("POST")
+                .uri("/api/saml/acme/acs")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header(COOKIE, cookie)
+                .body(Body::from(form))
+                .unwrap()
+        };
+        let elsewhere = send(post(&STANDARD.encode(RESPONSE), "")).await.unwrap();
+        assert_eq!(elsewhere.status(), StatusCode::BAD_REQUEST);
+
+        // The failed attempt consumed the request; start again from this browser
+        let response = send(
+            Request::builder()
+                .uri("/api/saml/acme/login")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        let location = url::Url::parse(response.headers()[LOCATION].to_str().unwrap()).unwrap();
+        let id = request_id(&location);
+        let started_here = format!("atlas_saml_request={}", id);
+        *verifier.assertion.lock().unwrap() = Some(assertion(Some(id)));
+
+        let response = send(post(&STANDARD.encode(RESPONSE), &started_here))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[LOCATION], "/app");
+        let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        let token = cookie
+            .split(';')
+            .next()
+            .unwrap()
+            .trim_start_matches("atlas_session=");
+        let session = sessions.resolve(token).await.unwrap().unwrap();
+        assert_eq!(session.subject, "acme:jdoe@acme.example");
+
+        assert!(response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .any(|value| value.to_str().unwrap().starts_with("atlas_saml_request=;")));
+
+        let replay = send(post(&STANDARD.encode(RESPONSE), &started_here))
+            .await
+            .unwrap();
+        assert_eq!(replay.status(), StatusCode::BAD_REQUEST);
+        let forged = send(post(&STANDARD.encode("<forged/>"), &started_here))
+            .await
+            .unwrap();
+        assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+    }
+}
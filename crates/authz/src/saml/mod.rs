@@ -0,0 +1,597 @@
+//! Single sign-on through SAML 2.0 identity providers
+//!
+//! ATLAS is the service provider: `/{idp}/metadata` describes it to the identity provider,
+//! `/{idp}/login` sends the browser there with an `AuthnRequest` over the HTTP-Redirect
+//! binding, and the provider posts its response to `/{idp}/acs`. Verified assertions are
+//! mapped to an `ExternalIdentity` and linked to a subject, as OAuth logins are.
+
+#[cfg(feature = "xmldsig")]
+mod c14n;
+mod http;
+mod module;
+#[cfg(feature = "xmldsig")]
+mod verify;
+mod xml;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::DeflateEncoder, Compression};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use url::Url;
+
+use atlas_kernel::{
+    settings::{SamlAttributeSettings, SamlIdpSettings, SamlSettings},
+    Clock,
+};
+
+use crate::oauth::ExternalIdentity;
+use crate::token;
+
+pub use http::saml_routes;
+pub use module::{create_module, SamlModule};
+#[cfg(feature = "xmldsig")]
+pub use verify::XmlSignatureVerifier;
+
+/// How long a started login may take before its response is rejected
+pub(crate) const PENDING_TTL: Duration = Duration::from_secs(600);
+
+/// A configured identity provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityProvider {
+    /// Configured name, used in paths and as the `ExternalIdentity` provider
+    pub name: String,
+    pub entity_id: String,
+    pub sso_url: String,
+    /// Signing certificate, as configured
+    pub certificate: String,
+    pub attributes: SamlAttributeSettings,
+}
+
+impl IdentityProvider {
+    pub fn from_settings(name: &str, settings: &SamlIdpSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            entity_id: settings.entity_id.clone(),
+            sso_url: settings.sso_url.clone(),
+            certificate: settings.certificate.clone(),
+            attributes: settings.attributes.clone(),
+        }
+    }
+}
+
+/// The assertion of a response whose signature has been verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlAssertion {
+    /// Assertion `ID`, remembered until expiry so it cannot be replayed
+    pub id: String,
+    pub issuer: String,
+    pub name_id: String,
+    /// `ID` of the `AuthnRequest` answered; absent for unsolicited responses
+    pub in_response_to: Option<String>,
+    /// `AudienceRestriction` entries
+    pub audiences: Vec<String>,
+    /// `Recipient` of the bearer `SubjectConfirmationData`
+    pub recipient: Option<String>,
+    pub not_before: Option<SystemTime>,
+    pub not_on_or_after: Option<SystemTime>,
+    /// Attribute values by attribute `Name`
+    pub attributes: HashMap<String, Vec<String>>,
+}
+
+impl SamlAssertion {
+    fn attribute(&self, name: &Option<String>) -> Option<String> {
+        self.attributes
+            .get(name.as_deref()?)
+            .and_then(|values| values.first())
+            .cloned()
+    }
+}
+
+/// XML signature verification of SAML responses
+///
+/// Implementations must check the signature of the `Response` or its `Assertion` against
+/// `idp.certificate`, decrypt encrypted assertions, and fail for anything unsigned; the
+/// remaining conditions are checked by `Saml::complete`.
+#[async_trait]
+pub trait SamlVerifier: Send + Sync {
+    async fn verify(&self, response: &str, idp: &IdentityProvider)
+        -> anyhow::Result<SamlAssertion>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SamlError {
+    #[error("unknown SAML identity provider '{0}'")]
+    UnknownProvider(String),
+    #[error("SAML response is not base64-encoded XML")]
+    MalformedResponse,
+    #[error("SAML response could not be verified")]
+    Unverified(#[source] anyhow::Error),
+    #[error("SAML assertion rejected: {0}")]
+    Rejected(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+struct Pending {
+    provider: String,
+    started_at: SystemTime,
+}
+
+/// Configured identity providers and logins in progress
+pub struct Saml {
+    entity_id: String,
+    base_url: String,
+    allow_idp_initiated: bool,
+    clock_skew: Duration,
+    providers: HashMap<String, IdentityProvider>,
+    pending: Mutex<HashMap<String, Pending>>,
+    /// Assertion ids already accepted, with when they may be forgotten
+    consumed: Mutex<HashMap<String, SystemTime>>,
+    verifier: Arc<dyn SamlVerifier>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Saml {
+    pub fn new(
+        settings: &SamlSettings,
+        verifier: Arc<dyn SamlVerifier>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let base_url = settings
+            .base_url
+            .as_deref()
+            .unwrap_or_default()
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            entity_id: settings
+                .entity_id
+                .clone()
+                .unwrap_or_else(|| format!("{}/api/saml", base_url)),
+            base_url,
+            allow_idp_initiated: settings.allow_idp_initiated,
+            clock_skew: Duration::from_secs(settings.clock_skew_secs),
+            providers: settings
+                .identity_providers
+                .iter()
+                .map(|(name, idp)| (name.clone(), IdentityProvider::from_settings(name, idp)))
+                .collect(),
+            pending: Mutex::new(HashMap::new()),
+            consumed: Mutex::new(HashMap::new()),
+            verifier,
+            clock,
+        }
+    }
+
+    /// Entity id of this service provider
+    pub fn entity_id(&self) -> &str {
+        &self.entity_id
+    }
+
+    /// Configured identity provider names, sorted
+    pub fn provider_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Assertion consumer service URL registered with `idp`
+    pub fn acs_url(&self, idp: &str) -> String {
+        format!("{}/api/saml/{}/acs", self.base_url, idp)
+    }
+
+    /// Service provider metadata to hand to the administrator of `idp`
+    pub fn metadata(&self, idp: &str) -> Result<String, SamlError> {
+        let idp = self.provider(idp)?;
+        Ok(xml::metadata(&self.entity_id, &self.acs_url(&idp.name)))
+    }
+
+    /// Start a login, returning the identity provider URL to redirect the browser to and
+    /// the id of the request, which the browser must present again with the response
+    pub fn begin(&self, idp: &str) -> Result<(Url, String), SamlError> {
+        let idp = self.provider(idp)?;
+        // XML ids must not start with a digit
+        let id = token::generate("_")?;
+        let now = self.clock.now();
+        let issue_instant = OffsetDateTime::from(now)
+            .replace_nanosecond(0)
+            .context("invalid clock time")?
+            .format(&Rfc3339)
+            .context("failed to format IssueInstant")?;
+        let request = xml::authn_request(
+            &id,
+            &issue_instant,
+            &idp.sso_url,
+            &self.acs_url(&idp.name),
+            &self.entity_id,
+        );
+
+        // HTTP-Redirect binding: raw DEFLATE, then base64, then URL encoding
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(request.as_bytes())
+            .context("failed to encode AuthnRequest")?;
+        let deflated = encoder.finish().context("failed to encode AuthnRequest")?;
+        let mut url = Url::parse(&idp.sso_url)
+            .with_context(|| format!("invalid sso_url of SAML provider '{}'", idp.name))?;
+        url.query_pairs_mut()
+            .append_pair("SAMLRequest", &STANDARD.encode(deflated));
+
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        pending.retain(|_, login| !expired(login, now));
+        pending.insert(
+            id.clone(),
+            Pending {
+                provider: idp.name.clone(),
+                started_at: now,
+            },
+        );
+        Ok((url, id))
+    }
+
+    /// Finish a login from the base64 `SAMLResponse` posted to the ACS, returning who
+    /// logged in
+    ///
+    /// `request_id` is the request the posting browser started, as returned by `begin`; a
+    /// response to a request started elsewhere is rejected.
+    pub async fn complete(
+        &self,
+        idp: &str,
+        saml_response: &str,
+        request_id: Option<&str>,
+    ) -> Result<ExternalIdentity, SamlError> {
+        let idp = self.provider(idp)?;
+        // Some providers wrap the encoded response across lines
+        let encoded: String = saml_response
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect();
+        let response = STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(SamlError::MalformedResponse)?;
+        let assertion = self
+            .verifier
+            .verify(&response, idp)
+            .await
+            .map_err(SamlError::Unverified)?;
+
+        self.check(idp, &assertion, request_id)?;
+        self.identity(idp, &assertion)
+    }
+
+    fn provider(&self, idp: &str) -> Result<&IdentityProvider, SamlError> {
+        self.providers
+            .get(idp)
+            .ok_or_else(|| SamlError::UnknownProvider(idp.to_string()))
+    }
+
+    /// Conditions of a verified assertion: issuer, audience, recipient, validity window,
+    /// the request it answers and the browser that started it, and single use
+    fn check(
+        &self,
+        idp: &IdentityProvider,
+        assertion: &SamlAssertion,
+        started_request: Option<&str>,
+    ) -> Result<(), SamlError> {
+        let reject = |reason: String| Err(SamlError::Rejected(reason));
+        if assertion.issuer != idp.entity_id {
+            return reject(format!(
+                "issued by '{}', not '{}'",
+                assertion.issuer, idp.entity_id
+            ));
+        }
+        if !assertion.audiences.contains(&self.entity_id) {
+            return reject("not intended for this service provider".to_string());
+        }
+        if let Some(recipient) = &assertion.recipient {
+            if *recipient != self.acs_url(&idp.name) {
+                return reject(format!("addressed to '{}'", recipient));
+            }
+        }
+
+        let now = self.clock.now();
+        let Some(not_on_or_after) = assertion.not_on_or_after else {
+            return reject("assertion has no expiry".to_string());
+        };
+        if now >= not_on_or_after + self.clock_skew {
+            return reject("assertion expired".to_string());
+        }
+        if assertion
+            .not_before
+            .is_some_and(|not_before| now + self.clock_skew < not_before)
+        {
+            return reject("assertion is not valid yet".to_string());
+        }
+
+        match &assertion.in_response_to {
+            Some(request_id) => {
+                // Requests are single use: remove before checking so a replay always fails.
+                let answered = self
+                    .pending
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .remove(request_id)
+                    .is_some_and(|login| login.provider == idp.name && !expired(&login, now));
+                if !answered {
+                    return reject("response does not answer a pending login".to_string());
+                }
+                let started_here = started_request.is_some_and(|started| {
+                    token::constant_time_eq(started.as_bytes(), request_id.as_bytes())
+                });
+                if !started_here {
+                    return reject("login was started in another browser".to_string());
+                }
+            }
+            None if !self.allow_idp_initiated => {
+                return reject("unsolicited responses are not allowed".to_string());
+            }
+            None => {}
+        }
+
+        let mut consumed = self
+            .consumed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        consumed.retain(|_, forget_at| *forget_at > now);
+        if consumed
+            .insert(assertion.id.clone(), not_on_or_after + self.clock_skew)
+            .is_some()
+        {
+            return reject("assertion was already used".to_string());
+        }
+        Ok(())
+    }
+
+    fn identity(
+        &self,
+        idp: &IdentityProvider,
+        assertion: &SamlAssertion,
+    ) -> Result<ExternalIdentity, SamlError> {
+        let id = match &idp.attributes.id {
+            Some(name) => assertion
+                .attribute(&idp.attributes.id)
+                .ok_or_else(|| SamlError::Rejected(format!("missing attribute '{}'", name)))?,
+            None => assertion.name_id.clone(),
+        };
+        if id.is_empty() {
+            return Err(SamlError::Rejected("empty user id".to_string()));
+        }
+        Ok(ExternalIdentity {
+            provider: idp.name.clone(),
+            id,
+            email: assertion.attribute(&idp.attributes.email),
+            name: assertion.attribute(&idp.attributes.name),
+        })
+    }
+}
+
+fn expired(login: &Pending, now: SystemTime) -> bool {
+    now.duration_since(login.started_at)
+        .is_ok_and(|elapsed| elapsed >= PENDING_TTL)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    pub(crate) const RESPONSE: &str = "<samlp:Response>signed</samlp:Response>";
+
+    /// Verifier double returning a prepared assertion for `RESPONSE`
+    #[derive(Default)]
+    pub(crate) struct FakeVerifier {
+        pub(crate) assertion: Mutex<Option<SamlAssertion>>,
+    }
+
+    #[async_trait]
+    impl SamlVerifier for FakeVerifier {
+        async fn verify(
+            &self,
+            response: &str,
+            _idp: &IdentityProvider,
+        ) -> anyhow::Result<SamlAssertion> {
+            anyhow::ensure!(response == RESPONSE, "invalid signature");
+            self.assertion
+                .lock()
+                .unwrap()
+                .clone()
+                .context("no assertion prepared")
+        }
+    }
+
+    pub(crate) fn settings() -> SamlSettings {
+        SamlSettings {
+            base_url: Some("https://app.example.com/".to_string()),
+            identity_providers: HashMap::from([(
+                "acme".to_string(),
+                SamlIdpSettings {
+                    entity_id: "https://idp.acme.example/metadata".to_string(),
+                    sso_url: "https://idp.acme.example/sso?tenant=1".to_string(),
+                    certificate: "MIIC".to_string(),
+                    attributes: SamlAttributeSettings {
+                        id: None,
+                        email: Some("mail".to_string()),
+                        name: Some("displayName".to_string()),
+                    },
+                },
+            )]),
+            ..SamlSettings::default()
+        }
+    }
+
+    pub(crate) fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    pub(crate) fn assertion(in_response_to: Option<String>) -> SamlAssertion {
+        SamlAssertion {
+            id: "_assertion-1".to_string(),
+            issuer: "https://idp.acme.example/metadata".to_string(),
+            name_id: "jdoe@acme.example".to_string(),
+            in_response_to,
+            audiences: vec!["https://app.example.com/api/saml".to_string()],
+            recipient: Some("https://app.example.com/api/saml/acme/acs".to_string()),
+            not_before: Some(now() - Duration::from_secs(5)),
+            not_on_or_after: Some(now() + Duration::from_secs(300)),
+            attributes: HashMap::from([
+                ("mail".to_string(), vec!["jdoe@acme.example".to_string()]),
+                ("displayName".to_string(), vec!["Jane Doe".to_string()]),
+            ]),
+        }
+    }
+
+    /// `ID` of the `AuthnRequest` carried by a login redirect
+    pub(crate) fn request_id(url: &Url) -> String {
+        let encoded = url
+            .query_pairs()
+            .find(|(key, _)| key == "SAMLRequest")
+            .unwrap()
+            .1
+            .into_owned();
+        let mut request = String::new();
+        DeflateDecoder::new(STANDARD.decode(encoded).unwrap().as_slice())
+            .read_to_string(&mut request)
+            .unwrap();
+        let start = request.find(" ID=\"").unwrap() + 5;
+        request[start..start + request[start..].find('"').unwrap()].to_string()
+    }
+
+    fn saml(settings: &SamlSettings) -> (Saml, Arc<FakeVerifier>, Arc<ManualClock>) {
+        let verifier = Arc::new(FakeVerifier::default());
+        let clock = Arc::new(ManualClock::new(now()));
+        (
+            Saml::new(settings, verifier.clone(), clock.clone()),
+            verifier,
+            clock,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_login_completes_once_for_its_request() {
+        let (saml, verifier, _) = saml(&settings());
+
+        let (url, id) = saml.begin("acme").unwrap();
+        assert_eq!(url.host_str(), Some("idp.acme.example"));
+        assert!(url.query_pairs().any(|(key, _)| key == "tenant"));
+        assert_eq!(request_id(&url), id);
+        assert!(id.starts_with('_'));
+
+        *verifier.assertion.lock().unwrap() = Some(assertion(Some(id.clone())));
+        let identity = saml
+            .complete("acme", &STANDARD.encode(RESPONSE), Some(&id))
+            .await
+            .unwrap();
+        assert_eq!(identity.default_subject(), "acme:jdoe@acme.example");
+        assert_eq!(identity.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(identity.email.as_deref(), Some("jdoe@acme.example"));
+
+        let replay = saml
+            .complete("acme", &STANDARD.encode(RESPONSE), Some(&id))
+            .await;
+        assert!(matches!(replay, Err(SamlError::Rejected(_))));
+    }
+
+    #[tokio::test]
+    async fn test_assertions_failing_conditions_are_rejected() {
+        let (saml, verifier, clock) = saml(&settings());
+        let response = STANDARD.encode(RESPONSE);
+        // Responses come from the browser that started the login unless noted
+        let rejection = |assertion: SamlAssertion| {
+            let started = assertion.in_response_to.clone();
+            *verifier.assertion.lock().unwrap() = Some(assertion);
+            let saml = &saml;
+            let response = &response;
+            async move { saml.complete("acme", response, started.as_deref()).await }
+        };
+        let reason = |result: Result<ExternalIdentity, SamlError>| match result {
+            Err(SamlError::Rejected(reason)) => reason,
+            other => panic!("expected a rejection, got {:?}", other),
+        };
+
+        assert!(reason(rejection(assertion(None)).await).contains("unsolicited"));
+        assert!(
+            reason(rejection(assertion(Some("_forged".to_string()))).await)
+                .contains("pending login")
+        );
+
+        let pending = || Some(saml.begin("acme").unwrap().1);
+        let wrong_audience = SamlAssertion {
+            audiences: vec!["https://other.example.com".to_string()],
+            ..assertion(pending())
+        };
+        assert!(reason(rejection(wrong_audience).await).contains("not intended"));
+        let wrong_issuer = SamlAssertion {
+            issuer: "https://idp.evil.example".to_string(),
+            ..assertion(pending())
+        };
+        assert!(reason(rejection(wrong_issuer).await).contains("issued by"));
+
+        let (_, elsewhere) = saml.begin("acme").unwrap();
+        *verifier.assertion.lock().unwrap() = Some(assertion(Some(elsewhere)));
+        let (_, here) = saml.begin("acme").unwrap();
+        assert!(
+            reason(saml.complete("acme", &response, Some(&here)).await).contains("another browser")
+        );
+
+        let stale = assertion(pending());
+        clock.advance(Duration::from_secs(300 + 120));
+        assert!(reason(rejection(stale).await).contains("expired"));
+
+        assert!(matches!(
+            saml.complete("acme", "not base64!", None).await,
+            Err(SamlError::MalformedResponse)
+        ));
+        assert!(matches!(
+            saml.complete("acme", &STANDARD.encode("<forged/>"), None)
+                .await,
+            Err(SamlError::Unverified(_))
+        ));
+        assert!(matches!(
+            saml.begin("globex"),
+            Err(SamlError::UnknownProvider(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unsolicited_responses_map_configured_id_attribute() {
+        let mut settings = settings();
+        settings.allow_idp_initiated = true;
+        let idp = settings.identity_providers.get_mut("acme").unwrap();
+        idp.attributes.id = Some("employeeNumber".to_string());
+        let (saml, verifier, _) = saml(&settings);
+
+        *verifier.assertion.lock().unwrap() = Some(assertion(None));
+        let error = saml
+            .complete("acme", &STANDARD.encode(RESPONSE), None)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("missing attribute 'employeeNumber'"));
+
+        let mut with_id = assertion(None);
+        with_id.id = "_assertion-2".to_string();
+        with_id
+            .attributes
+            .insert("employeeNumber".to_string(), vec!["4711".to_string()]);
+        *verifier.assertion.lock().unwrap() = Some(with_id);
+        let identity = saml
+            .complete("acme", &STANDARD.encode(RESPONSE), None)
+            .await
+            .unwrap();
+        assert_eq!(identity.id, "4711");
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{InitCtx, Module, Resources};
+
+use super::{saml_routes, Saml, SamlVerifier};
+use crate::Sessions;
+
+struct Mounted {
+    saml: Arc<Saml>,
+    sessions: Arc<Sessions>,
+    resources: Arc<Resources>,
+    success_redirect: String,
+}
+
+/// Core module serving single sign-on through `[auth.saml.identity_providers]`
+///
+/// Successful logins start a cookie session, so it depends on the sessions module. It
+/// mounts nothing when no identity providers are configured. Responses are verified by
+/// the registered `Arc<dyn SamlVerifier>` or, with the `xmldsig` feature,
+/// `XmlSignatureVerifier`.
+#[derive(Default)]
+pub struct SamlModule {
+    mounted: OnceLock<Mounted>,
+}
+
+#[async_trait]
+impl Module for SamlModule {
+    fn name(&self) -> &'static str {
+        "saml"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("SAML 2.0 single sign-on")
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["sessions"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.auth.saml;
+        if settings.identity_providers.is_empty() {
+            tracing::debug!("no SAML identity providers configured");
+            return Ok(());
+        }
+
+        let verifier = match ctx.resources.get::<Arc<dyn SamlVerifier>>() {
+            Some(verifier) => verifier.as_ref().clone(),
+            #[cfg(feature = "xmldsig")]
+            None => Arc::new(super::XmlSignatureVerifier::new()),
+            #[cfg(not(feature = "xmldsig"))]
+            None => anyhow::bail!(
+                "SAML identity providers are configured but no SamlVerifier resource is \
+                 registered; register one or enable the xmldsig feature"
+            ),
+        };
+        let saml = Arc::new(Saml::new(settings, verifier, ctx.clock.clone()));
+        tracing::info!(
+            entity_id = %saml.entity_id(),
+            providers = ?saml.provider_names(),
+            "SAML single sign-on enabled"
+        );
+
+        ctx.resources.insert_arc(saml.clone());
+        self.mounted
+            .set(Mounted {
+                saml,
+                sessions: ctx.resources.require::<Sessions>()?,
+                resources: ctx.resources.clone(),
+                success_redirect: settings.success_redirect.clone(),
+            })
+            .map_err(|_| anyhow::anyhow!("saml module initialized twice"))?;
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.mounted.get() {
+            Some(mounted) => saml_routes(
+                mounted.saml.clone(),
+                mounted.sessions.clone(),
+                mounted.resources.clone(),
+                &mounted.success_redirect,
+            ),
+            None => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let idp = json!({ "name": "idp", "in": "path", "required": true, "schema": { "type": "string" } });
+        Some(json!({
+            "tags": [
+                { "name": "SAML", "description": "Single sign-on through enterprise identity providers" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List configured identity providers",
+                        "tags": ["SAML"],
+                        "responses": {
+                            "200": {
+                                "description": "Identity provider names usable in the login paths",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": { "type": "string" } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/{idp}/metadata": {
+                    "get": {
+                        "summary": "Service provider metadata for an identity provider",
+                        "tags": ["SAML"],
+                        "parameters": [idp.clone()],
+                        "responses": {
+                            "200": {
+                                "description": "SAML 2.0 `EntityDescriptor`",
+                                "content": {
+                                    "application/samlmetadata+xml": { "schema": { "type": "string" } }
+                                }
+                            },
+                            "404": { "description": "Unknown identity provider" }
+                        }
+                    }
+                },
+                "/{idp}/login": {
+                    "get": {
+                        "summary": "Start a login",
+                        "tags": ["SAML"],
+                        "parameters": [idp.clone()],
+                        "responses": {
+                            "303": { "description": "Redirect to the identity provider with an `AuthnRequest`" },
+                            "404": { "description": "Unknown identity provider" }
+                        }
+                    }
+                },
+                "/{idp}/acs": {
+                    "post": {
+                        "summary": "Assertion consumer service",
+                        "description": "Called by the identity provider; starts a session and redirects to `auth.saml.success_redirect`.",
+                        "tags": ["SAML"],
+                        "parameters": [idp],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/x-www-form-urlencoded": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["SAMLResponse"],
+                                        "properties": {
+                                            "SAMLResponse": { "type": "string" },
+                                            "RelayState": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "303": { "description": "Logged in; the session cookie is set" },
+                            "400": { "description": "Malformed response, or an assertion failing its conditions" },
+                            "401": { "description": "Signature verification failed" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Create a new instance of the SAML module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(SamlModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::settings::Settings;
+    use atlas_kernel::ModuleRegistry;
+
+    fn settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.auth.saml = crate::saml::tests::settings();
+        settings
+    }
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry
+            .register_core(crate::sessions::create_module())
+            .unwrap();
+        registry
+    }
+
+    #[cfg(not(feature = "xmldsig"))]
+    #[tokio::test]
+    async fn test_configured_providers_require_verifier() {
+        let registry = registry();
+        let error = registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings())))
+            .await
+            .unwrap_err();
+        let error = anyhow::Error::from(error);
+        assert!(format!("{:#}", error).contains("SamlVerifier"));
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_saml_after_sessions() {
+        let registry = registry();
+        registry
+            .resources()
+            .insert::<Arc<dyn SamlVerifier>>(Arc::new(crate::saml::tests::FakeVerifier::default()));
+
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings())))
+            .await
+            .unwrap();
+
+        let saml = registry.resources().require::<Saml>().unwrap();
+        assert_eq!(saml.provider_names(), vec!["acme"]);
+        assert_eq!(saml.entity_id(), "https://app.example.com/api/saml");
+    }
+}
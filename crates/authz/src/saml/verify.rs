@@ -0,0 +1,502 @@
+//! `SamlVerifier` checking XML signatures with the identity provider's certificate
+
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use roxmltree::{Document, Node, ParsingOptions};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256, Sha512};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use x509_cert::der::{Decode, Encode};
+use x509_cert::Certificate;
+
+use super::c14n::canonicalize;
+use super::{IdentityProvider, SamlAssertion, SamlVerifier};
+use crate::token;
+
+const PROTOCOL: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const ASSERTION: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const DSIG: &str = "http://www.w3.org/2000/09/xmldsig#";
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const RSA_SHA512: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+const SHA512: &str = "http://www.w3.org/2001/04/xmlenc#sha512";
+const SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+
+/// Verifies RSA signatures of responses and assertions
+///
+/// The `Response` or its single `Assertion` must carry an enveloped signature with
+/// exclusive canonicalization, SHA-256 or SHA-512, made with the key of the configured
+/// certificate; keys sent along in `KeyInfo` are ignored. Only the signed assertion is
+/// read. Encrypted assertions are refused, as the metadata publishes no encryption key.
+#[derive(Debug, Default)]
+pub struct XmlSignatureVerifier;
+
+impl XmlSignatureVerifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl SamlVerifier for XmlSignatureVerifier {
+    async fn verify(
+        &self,
+        response: &str,
+        idp: &IdentityProvider,
+    ) -> anyhow::Result<SamlAssertion> {
+        let key = public_key(&idp.certificate)
+            .with_context(|| format!("invalid certificate for '{}'", idp.name))?;
+        // Document type declarations are refused, and with them entity expansion
+        let doc = Document::parse_with_options(response, ParsingOptions::default())
+            .context("SAML response is not well-formed XML")?;
+        let root = doc.root_element();
+        if !is(root, PROTOCOL, "Response") {
+            bail!("document is not a SAML Response");
+        }
+        check_status(root)?;
+        check_ids(&doc)?;
+        if doc
+            .descendants()
+            .any(|node| is(node, ASSERTION, "EncryptedAssertion"))
+        {
+            bail!("encrypted assertions are not supported");
+        }
+        // A second assertion is how signature wrapping smuggles an unsigned one in
+        let mut assertions = doc
+            .descendants()
+            .filter(|node| is(*node, ASSERTION, "Assertion"));
+        let (Some(assertion), None) = (assertions.next(), assertions.next()) else {
+            bail!("response must contain exactly one assertion");
+        };
+        if assertion.parent() != Some(root) {
+            bail!("assertion is not a child of the response");
+        }
+
+        let mut signed = false;
+        for element in [root, assertion] {
+            if let Some(signature) = child(element, DSIG, "Signature") {
+                verify_signature(element, signature, &key)?;
+                signed = true;
+            }
+        }
+        if !signed {
+            bail!("neither the response nor its assertion is signed");
+        }
+        read_assertion(root, assertion)
+    }
+}
+
+/// RSA key of a base64 DER certificate, with or without PEM armor
+fn public_key(certificate: &str) -> anyhow::Result<RsaPublicKey> {
+    let base64: String = certificate
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .flat_map(|line| line.chars())
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let der = STANDARD
+        .decode(base64)
+        .context("certificate is not base64")?;
+    let certificate = Certificate::from_der(&der).context("certificate is not X.509")?;
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .context("invalid public key")?;
+    let spki = x509_cert::spki::SubjectPublicKeyInfoRef::try_from(spki.as_slice())
+        .context("invalid public key")?;
+    RsaPublicKey::try_from(spki).context("certificate key is not an RSA key")
+}
+
+fn check_status(response: Node) -> anyhow::Result<()> {
+    let status = child(response, PROTOCOL, "Status")
+        .and_then(|status| child(status, PROTOCOL, "StatusCode"))
+        .and_then(|code| code.attribute("Value"))
+        .context("response has no status")?;
+    if status != SUCCESS {
+        bail!("identity provider answered with status {}", status);
+    }
+    Ok(())
+}
+
+/// References must resolve to one element
+fn check_ids(doc: &Document) -> anyhow::Result<()> {
+    let mut ids = HashSet::new();
+    for id in doc.descendants().filter_map(|node| node.attribute("ID")) {
+        if !ids.insert(id) {
+            bail!("duplicate ID '{}'", id);
+        }
+    }
+    Ok(())
+}
+
+/// Checks the enveloped `signature` covers `element` and was made with `key`
+fn verify_signature(element: Node, signature: Node, key: &RsaPublicKey) -> anyhow::Result<()> {
+    let signed_info =
+        child(signature, DSIG, "SignedInfo").context("signature has no SignedInfo")?;
+    let c14n = child(signed_info, DSIG, "CanonicalizationMethod")
+        .context("signature has no CanonicalizationMethod")?;
+    if c14n.attribute("Algorithm") != Some(EXC_C14N) {
+        bail!("unsupported canonicalization method");
+    }
+    let method = child(signed_info, DSIG, "SignatureMethod")
+        .and_then(|method| method.attribute("Algorithm"))
+        .context("signature has no SignatureMethod")?;
+
+    let mut references = signed_info
+        .children()
+        .filter(|node| is(*node, DSIG, "Reference"));
+    let (Some(reference), None) = (references.next(), references.next()) else {
+        bail!("signature must have exactly one reference");
+    };
+    let id = element
+        .attribute("ID")
+        .filter(|id| !id.is_empty())
+        .context("signed element has no ID")?;
+    if reference.attribute("URI") != Some(&format!("#{}", id)) {
+        bail!("signature does not reference the element it is enveloped in");
+    }
+
+    let mut prefixes = Vec::new();
+    let mut canonical = false;
+    let transforms = child(reference, DSIG, "Transforms")
+        .into_iter()
+        .flat_map(|transforms| transforms.children())
+        .filter(|node| is(*node, DSIG, "Transform"));
+    for transform in transforms {
+        match transform.attribute("Algorithm") {
+            Some(ENVELOPED) => {}
+            Some(EXC_C14N) => {
+                canonical = true;
+                prefixes = inclusive_prefixes(transform);
+            }
+            other => bail!("unsupported transform {:?}", other.unwrap_or_default()),
+        }
+    }
+    if !canonical {
+        bail!("reference is not canonicalized with exclusive canonicalization");
+    }
+
+    let digest_method = child(reference, DSIG, "DigestMethod")
+        .and_then(|method| method.attribute("Algorithm"))
+        .context("reference has no DigestMethod")?;
+    let expected = base64_text(child(reference, DSIG, "DigestValue"))?;
+    let content = canonicalize(element, Some(signature), &prefixes);
+    let digest = match digest_method {
+        SHA256 => Sha256::digest(content).to_vec(),
+        SHA512 => Sha512::digest(content).to_vec(),
+        other => bail!("unsupported digest method {}", other),
+    };
+    if !token::constant_time_eq(&digest, &expected) {
+        bail!("digest of the signed element does not match");
+    }
+
+    let signed = canonicalize(signed_info, None, &inclusive_prefixes(c14n));
+    let value = base64_text(child(signature, DSIG, "SignatureValue"))?;
+    let value = Signature::try_from(value.as_slice()).context("invalid signature value")?;
+    let verified = match method {
+        RSA_SHA256 => VerifyingKey::<Sha256>::new(key.clone()).verify(signed.as_bytes(), &value),
+        RSA_SHA512 => VerifyingKey::<Sha512>::new(key.clone()).verify(signed.as_bytes(), &value),
+        other => bail!("unsupported signature method {}", other),
+    };
+    verified.context("signature does not match the identity provider's certificate")
+}
+
+/// `PrefixList` of the `InclusiveNamespaces` of a canonicalization method
+fn inclusive_prefixes<'a>(method: Node<'a, '_>) -> Vec<&'a str> {
+    child(method, EXC_C14N, "InclusiveNamespaces")
+        .and_then(|inclusive| inclusive.attribute("PrefixList"))
+        .map(|list| list.split_whitespace().collect())
+        .unwrap_or_default()
+}
+
+fn base64_text(node: Option<Node>) -> anyhow::Result<Vec<u8>> {
+    let text: String = node
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    STANDARD.decode(text).context("invalid base64 in signature")
+}
+
+fn read_assertion(response: Node, assertion: Node) -> anyhow::Result<SamlAssertion> {
+    let subject = child(assertion, ASSERTION, "Subject").context("assertion has no subject")?;
+    let confirmation = subject
+        .children()
+        .filter(|node| is(*node, ASSERTION, "SubjectConfirmation"))
+        .find(|node| node.attribute("Method") == Some(BEARER))
+        .and_then(|node| child(node, ASSERTION, "SubjectConfirmationData"));
+    let conditions = child(assertion, ASSERTION, "Conditions");
+
+    let mut attributes: HashMap<String, Vec<String>> = HashMap::new();
+    let statements = assertion
+        .children()
+        .filter(|node| is(*node, ASSERTION, "AttributeStatement"));
+    for attribute in statements
+        .flat_map(|statement| statement.children())
+        .filter(|node| is(*node, ASSERTION, "Attribute"))
+    {
+        let Some(name) = attribute.attribute("Name") else {
+            continue;
+        };
+        attributes.entry(name.to_string()).or_default().extend(
+            attribute
+                .children()
+                .filter(|node| is(*node, ASSERTION, "AttributeValue"))
+                .map(text),
+        );
+    }
+
+    // The earliest of the condition's and the bearer confirmation's expiry applies
+    let expiries = [conditions, confirmation]
+        .into_iter()
+        .flatten()
+        .filter_map(|node| node.attribute("NotOnOrAfter"))
+        .map(parse_time)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(SamlAssertion {
+        id: assertion
+            .attribute("ID")
+            .context("assertion has no ID")?
+            .to_string(),
+        issuer: child(assertion, ASSERTION, "Issuer")
+            .map(text)
+            .context("assertion has no issuer")?,
+        name_id: child(subject, ASSERTION, "NameID")
+            .map(text)
+            .context("assertion has no NameID")?,
+        in_response_to: confirmation
+            .and_then(|data| data.attribute("InResponseTo"))
+            .or_else(|| response.attribute("InResponseTo"))
+            .map(String::from),
+        audiences: conditions
+            .into_iter()
+            .flat_map(|conditions| conditions.children())
+            .filter(|node| is(*node, ASSERTION, "AudienceRestriction"))
+            .flat_map(|restriction| restriction.children())
+            .filter(|node| is(*node, ASSERTION, "Audience"))
+            .map(text)
+            .collect(),
+        recipient: confirmation
+            .and_then(|data| data.attribute("Recipient"))
+            .map(String::from),
+        not_before: conditions
+            .and_then(|conditions| conditions.attribute("NotBefore"))
+            .map(parse_time)
+            .transpose()?,
+        not_on_or_after: expiries.into_iter().min(),
+        attributes,
+    })
+}
+
+fn parse_time(value: &str) -> anyhow::Result<SystemTime> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map(SystemTime::from)
+        .with_context(|| format!("invalid time '{}'", value))
+}
+
+fn is(node: Node, namespace: &str, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().namespace() == Some(namespace)
+        && node.tag_name().name() == name
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &str,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| is(*child, namespace, name))
+}
+
+/// Text content of an element, trimmed
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Self-signed certificate of the key `RESPONSE` was signed with
+    const CERTIFICATE: &str = concat!(
+        "MIIDGTCCAgGgAwIBAgIUIK5uuV3NZOuckh/EJOQ3bz+N8WQwDQYJKoZIhvcNAQELBQAwGzEZMBcG",
+        "A1UEAwwQaWRwLmFjbWUuZXhhbXBsZTAgFw0yNjEwMTcwNTI2MjJaGA8yMTI2MDkyMzA1MjYyMlow",
+        "GzEZMBcGA1UEAwwQaWRwLmFjbWUuZXhhbXBsZTCCASIwDQYJKoZIhvcNAQEBBQADggEPADCCAQoC",
+        "ggEBAMWGhK7DtTHDrHuy50nTD/SiWjSUZQKLcURUDAsJraTmxj/s5wp02yORSxCL2x9ZQboLl7b5",
+        "n7d9DAROoLkcO3aspIjyHHwqmorDyW5d6FGQvyV5f1hUprvUs9AwQRU9TgSp27RZvHLSKMEGlT9d",
+        "Rv1VHvX9GVhvJ8h+vrb9BiW5a9QGisoNnNjkCfx7weHzN51UywxmHasmYit+Cp8s6eH5XhCTC7nE",
+        "PSJKNHtyWZts4ZW30bIJZ4wIzeJs6oJ5h0AmMDOvkmqUdX1v8VEzsuq26QaovGgXXcb6WN4NpyRJ",
+        "DchiNdYVC5CNkMXmHtElJ/Q7UqZeH101A00nIG6dKQUCAwEAAaNTMFEwHQYDVR0OBBYEFM8cfEwR",
+        "Eu4adLjqzsmmDJxq4z7zMB8GA1UdIwQYMBaAFM8cfEwREu4adLjqzsmmDJxq4z7zMA8GA1UdEwEB",
+        "/wQFMAMBAf8wDQYJKoZIhvcNAQELBQADggEBAK9UuOGrpSuztm37JFlnl4f2sqAFicO+hAc0FmM6",
+        "7lFD7267xap/PGH6LAeVaR0LGxW8S5UriKNeLdYxDQ6EdI8gZfAuVM35VE8XDbzYM0cGKh63Va0e",
+        "DH1b0g6MDgVVOBeZKtEehUs0U8Unwz2I+rGT4wQ3fvKqSYsbKQ7RiF36AXSoQIY8c+H6vthkpcD1",
+        "uQqn9DGJm47sxgZ053l5gULBFX0awibU9/CUaqEee9SjSc7fGTT77BcnNNUXDxxmOtKCxdfpEdgW",
+        "4+HLscc6f9pXPPZnUZVz8tpRW18bE0rwWDodgGyhwV5fxYlUvDn5kUDJ3+ATdKzMiVm1O7jn0QQ=",
+    );
+
+    /// A response with an enveloped signature on its assertion, signed with openssl over
+    /// canonical forms written by hand
+    const RESPONSE: &str = r##"<samlp:Response xmlns:samlp="urn:oasis:names:tc:SAML:2.0:protocol" xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion" ID="_resp1" Version="2.0" IssueInstant="2023-11-14T22:13:20Z" Destination="https://app.example.com/api/saml/acme/acs" InResponseTo="_req1">
+  <saml:Issuer>https://idp.acme.example/metadata</saml:Issuer>
+  <samlp:Status><samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Success"/></samlp:Status>
+  <saml:Assertion xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" Version="2.0" ID="_a1" IssueInstant="2023-11-14T22:13:20Z">
+    <saml:Issuer>https://idp.acme.example/metadata</saml:Issuer>
+    <ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#"><ds:SignedInfo><ds:CanonicalizationMethod Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/><ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"/><ds:Reference URI="#_a1"><ds:Transforms><ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/><ds:Transform Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"><ec:InclusiveNamespaces xmlns:ec="http://www.w3.org/2001/10/xml-exc-c14n#" PrefixList="xs"/></ds:Transform></ds:Transforms><ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/><ds:DigestValue>LZX4MolesIdGyjeYoGWlcpKBD54bXnvodmYThwv9tzU=</ds:DigestValue></ds:Reference></ds:SignedInfo><ds:SignatureValue>P2hSrJLZ1Sgzqig4xB/5A+LI2hlSLuYhOx8o4dkdGhO11cCakUvJ3/PnxF4/p/6s4rALTTD7qWQefXNgvm0bGC5/D+EEcuwLU3O7AHIHzt3eiKgKONFTf2bfWw+MiWuM9+DGv22zuxrF3WCfjFuGYdk+vMgAzZWHOwb7GwELLR5QNjE+tYC/HpomuXlyW/mdsH5KHBfMjBL1lO/6qAxeYU31qDP0egbN1RH79NoHyeBhSFuvj9g6nuQjle18vdzszn3VP9cDIZKVSLY1Wr+fm3OcVM9rSkADRfJXou0cq/RBp9WNBSDOorbpB7RJy1JW6QixpvOvTjozrlhieknz5Q==</ds:SignatureValue></ds:Signature>
+    <saml:Subject>
+      <saml:NameID Format="urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress">ada@acme.example</saml:NameID>
+      <saml:SubjectConfirmation Method="urn:oasis:names:tc:SAML:2.0:cm:bearer">
+        <saml:SubjectConfirmationData NotOnOrAfter="2023-11-14T22:18:20Z" Recipient="https://app.example.com/api/saml/acme/acs" InResponseTo="_req1"/>
+      </saml:SubjectConfirmation>
+    </saml:Subject>
+    <saml:Conditions NotBefore="2023-11-14T22:12:20Z" NotOnOrAfter="2023-11-14T22:18:20Z">
+      <saml:AudienceRestriction><saml:Audience>https://app.example.com/api/saml</saml:Audience></saml:AudienceRestriction>
+    </saml:Conditions>
+    <saml:AttributeStatement>
+      <saml:Attribute Name="mail"><saml:AttributeValue xsi:type="xs:string">ada@acme.example</saml:AttributeValue></saml:Attribute>
+      <saml:Attribute Name="displayName"><saml:AttributeValue xsi:type="xs:string">Ada &amp; Co</saml:AttributeValue></saml:Attribute>
+    </saml:AttributeStatement>
+  </saml:Assertion>
+</samlp:Response>"##;
+
+    fn idp() -> IdentityProvider {
+        IdentityProvider {
+            name: "acme".to_string(),
+            entity_id: "https://idp.acme.example/metadata".to_string(),
+            sso_url: "https://idp.acme.example/sso".to_string(),
+            certificate: CERTIFICATE.to_string(),
+            attributes: Default::default(),
+        }
+    }
+
+    async fn verify(response: &str) -> anyhow::Result<SamlAssertion> {
+        XmlSignatureVerifier::new().verify(response, &idp()).await
+    }
+
+    #[tokio::test]
+    async fn test_signed_assertions_are_read() {
+        let assertion = verify(RESPONSE).await.unwrap();
+
+        let at = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(assertion.id, "_a1");
+        assert_eq!(assertion.issuer, "https://idp.acme.example/metadata");
+        assert_eq!(assertion.name_id, "ada@acme.example");
+        assert_eq!(assertion.in_response_to.as_deref(), Some("_req1"));
+        assert_eq!(assertion.audiences, ["https://app.example.com/api/saml"]);
+        assert_eq!(
+            assertion.recipient.as_deref(),
+            Some("https://app.example.com/api/saml/acme/acs")
+        );
+        assert_eq!(assertion.not_before, at(1_700_000_000 - 60));
+        assert_eq!(assertion.not_on_or_after, at(1_700_000_000 + 300));
+        assert_eq!(assertion.attributes["displayName"], ["Ada & Co"]);
+    }
+
+    #[tokio::test]
+    async fn test_pem_certificates_are_accepted() {
+        let pem = format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            CERTIFICATE
+        );
+        let idp = IdentityProvider {
+            certificate: pem,
+            ..idp()
+        };
+
+        assert!(XmlSignatureVerifier::new()
+            .verify(RESPONSE, &idp)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_altered_assertions_are_rejected() {
+        let altered = RESPONSE.replace(">ada@acme.example<", ">eve@acme.example<");
+
+        let error = verify(&altered).await.unwrap_err();
+
+        assert!(error.to_string().contains("digest"));
+    }
+
+    #[tokio::test]
+    async fn test_altered_signatures_are_rejected() {
+        let start = RESPONSE.find("<ds:SignatureValue>").unwrap() + "<ds:SignatureValue>".len();
+        let mut altered = RESPONSE.to_string();
+        let flipped = if &altered[start..start + 1] == "A" {
+            "B"
+        } else {
+            "A"
+        };
+        altered.replace_range(start..start + 1, flipped);
+
+        let error = verify(&altered).await.unwrap_err();
+
+        assert!(error.to_string().contains("certificate"));
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_and_wrapped_assertions_are_rejected() {
+        let start = RESPONSE.find("<ds:Signature ").unwrap();
+        let end = RESPONSE.find("</ds:Signature>").unwrap() + "</ds:Signature>".len();
+        let unsigned = format!("{}{}", &RESPONSE[..start], &RESPONSE[end..]);
+        assert!(verify(&unsigned)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("signed"));
+
+        // An unsigned assertion for another subject next to the signed one
+        let evil = unsigned
+            .replace("ID=\"_a1\"", "ID=\"_evil\"")
+            .replace(">ada@acme.example<", ">eve@acme.example<");
+        let assertion =
+            &evil[evil.find("<saml:Assertion").unwrap()..evil.find("</samlp:Response>").unwrap()];
+        let wrapped = RESPONSE.replace(
+            "</samlp:Response>",
+            &format!("{}</samlp:Response>", assertion),
+        );
+        assert!(verify(&wrapped)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("exactly one assertion"));
+    }
+
+    #[tokio::test]
+    async fn test_other_certificates_and_failed_statuses_are_rejected() {
+        let other = IdentityProvider {
+            certificate: "MIIB".to_string(),
+            ..idp()
+        };
+        assert!(XmlSignatureVerifier::new()
+            .verify(RESPONSE, &other)
+            .await
+            .is_err());
+
+        let failed = RESPONSE.replace(SUCCESS, "urn:oasis:names:tc:SAML:2.0:status:Requester");
+        assert!(verify(&failed)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("Requester"));
+    }
+}
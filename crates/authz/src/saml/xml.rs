@@ -0,0 +1,84 @@
+//! SAML documents sent by the service provider
+//!
+//! Both documents are small and fixed, so they are written out directly; only values
+//! from settings and ids are interpolated, escaped for attribute and text content.
+
+const PROTOCOL: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const HTTP_POST: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+
+/// Escape `value` for use in XML text and double-quoted attributes
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `EntityDescriptor` of the service provider, asking for signed assertions
+pub(super) fn metadata(entity_id: &str, acs_url: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<md:EntityDescriptor xmlns:md="urn:oasis:names:tc:SAML:2.0:metadata" entityID="{entity_id}">
+  <md:SPSSODescriptor AuthnRequestsSigned="false" WantAssertionsSigned="true" protocolSupportEnumeration="{PROTOCOL}">
+    <md:NameIDFormat>urn:oasis:names:tc:SAML:1.1:nameid-format:unspecified</md:NameIDFormat>
+    <md:AssertionConsumerService Binding="{HTTP_POST}" Location="{acs_url}" index="0" isDefault="true"/>
+  </md:SPSSODescriptor>
+</md:EntityDescriptor>
+"#,
+        entity_id = escape(entity_id),
+        acs_url = escape(acs_url),
+    )
+}
+
+/// `AuthnRequest` asking for the response to be posted to `acs_url`
+pub(super) fn authn_request(
+    id: &str,
+    issue_instant: &str,
+    destination: &str,
+    acs_url: &str,
+    issuer: &str,
+) -> String {
+    format!(
+        r#"<samlp:AuthnRequest xmlns:samlp="{PROTOCOL}" xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion" ID="{id}" Version="2.0" IssueInstant="{issue_instant}" Destination="{destination}" AssertionConsumerServiceURL="{acs_url}" ProtocolBinding="{HTTP_POST}"><saml:Issuer>{issuer}</saml:Issuer><samlp:NameIDPolicy AllowCreate="true"/></samlp:AuthnRequest>"#,
+        id = escape(id),
+        issue_instant = escape(issue_instant),
+        destination = escape(destination),
+        acs_url = escape(acs_url),
+        issuer = escape(issuer),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolated_values_are_escaped() {
+        let metadata = metadata(
+            "https://app.example.com/api/saml",
+            "https://app.example.com/api/saml/a&b\"/acs",
+        );
+        assert!(metadata.contains(r#"entityID="https://app.example.com/api/saml""#));
+        assert!(
+            metadata.contains(r#"Location="https://app.example.com/api/saml/a&amp;b&quot;/acs""#)
+        );
+
+        let request = authn_request(
+            "_1",
+            "2023-11-14T22:13:20Z",
+            "https://idp.example/sso?a=1&b=2",
+            "https://app.example.com/api/saml/acme/acs",
+            "<sp>",
+        );
+        assert!(request.contains(r#"Destination="https://idp.example/sso?a=1&amp;b=2""#));
+        assert!(request.contains("<saml:Issuer>&lt;sp&gt;</saml:Issuer>"));
+    }
+}
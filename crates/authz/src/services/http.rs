@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_http::error::AppError;
+
+use super::ServiceTokens;
+use crate::Subject;
+
+/// Route middleware admitting only callers with a valid service token
+///
+/// Adds the caller's `ServicePrincipal` and a `service:{name}` `Subject`, so `authorize`
+/// can run after it.
+pub async fn require_service(
+    State(tokens): State<Arc<ServiceTokens>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::unauthorized("service token required"))?;
+    let principal = tokens.verify(token).map_err(|error| {
+        tracing::debug!(error = %error, "rejected service token");
+        AppError::unauthorized("invalid service token")
+    })?;
+
+    tracing::debug!(service = %principal.name, "authenticated service");
+    request
+        .extensions_mut()
+        .insert(Subject(principal.subject()));
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ServicePrincipal;
+    use atlas_kernel::{clock::SystemClock, settings::ServiceAuthSettings};
+    use axum::{body::Body, http::StatusCode, routing::get, Extension, Router};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_middleware_sets_service_principal() {
+        let secret = "billing-secret-billing-secret-0123".to_string();
+        let billing = ServiceTokens::new(
+            &ServiceAuthSettings {
+                name: "billing".to_string(),
+                signing_secret: Some(secret.clone()),
+                ..ServiceAuthSettings::default()
+            },
+            Arc::new(SystemClock),
+        );
+        let atlas = Arc::new(ServiceTokens::new(
+            &ServiceAuthSettings {
+                trusted_secrets: HashMap::from([("billing".to_string(), secret)]),
+                ..ServiceAuthSettings::default()
+            },
+            Arc::new(SystemClock),
+        ));
+        let router = Router::new()
+            .route(
+                "/internal",
+                get(
+                    |Extension(subject): Extension<Subject>,
+                     Extension(service): Extension<ServicePrincipal>| async move {
+                        format!("{} {}", subject.0, service.name)
+                    },
+                ),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(atlas, require_service));
+        let send = |authorization: Option<String>| {
+            let mut request = Request::builder().uri("/internal");
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let token = billing.issue("atlas").unwrap();
+        let response = send(Some(format!("Bearer {}", token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"service:billing billing");
+
+        let response = send(Some("Bearer nope.nope".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
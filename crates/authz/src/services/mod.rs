@@ -0,0 +1,401 @@
+//! Signed tokens for calls between trusted services
+//!
+//! A token is `base64url(claims).base64url(HMAC-SHA256(claims))`, signed with the calling
+//! service's secret. Callers authenticate as subject `service:{name}`, so policies can
+//! grant services permissions separately from people.
+//!
+//! Services rotating their secrets sign with one of several keys and name it in the `kid`
+//! claim; peers trust every listed key of a service, so tokens signed before a rotation
+//! keep verifying until they expire. Signing keys can be kept out of the config files and
+//! loaded from a `SecretsProvider`.
+
+mod http;
+mod module;
+mod secrets;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+use atlas_kernel::{settings::ServiceAuthSettings, Clock};
+
+use crate::token;
+
+pub use http::require_service;
+pub use module::{create_module, ServicesModule};
+pub use secrets::{EnvSecrets, FileSecrets};
+
+/// Prefix of subjects authenticated by a service token
+pub const SERVICE_SUBJECT_PREFIX: &str = "service:";
+
+/// An authenticated calling service, inserted next to its `Subject`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServicePrincipal {
+    pub name: String,
+}
+
+impl ServicePrincipal {
+    /// Subject matched against policies, e.g. `service:billing`
+    pub fn subject(&self) -> String {
+        format!("{}{}", SERVICE_SUBJECT_PREFIX, self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Claims {
+    /// Calling service
+    iss: String,
+    /// Service the token is for
+    aud: String,
+    iat: u64,
+    exp: u64,
+    /// Signing key, absent for tokens signed with the service's single secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+/// Source of signing keys kept out of the config files
+///
+/// `auth.services.key_source` selects `EnvSecrets` or `FileSecrets`; applications
+/// register an `Arc<dyn SecretsProvider>` resource to read them from elsewhere, e.g. a
+/// vault.
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    /// Secrets by name; for signing keys, the key id
+    async fn secrets(&self) -> anyhow::Result<HashMap<String, String>>;
+}
+
+/// `settings` with the keys of `provider` added to `signing_keys`, replacing configured
+/// keys with the same id
+pub async fn with_provided_keys(
+    settings: &ServiceAuthSettings,
+    provider: &dyn SecretsProvider,
+) -> anyhow::Result<ServiceAuthSettings> {
+    let mut settings = settings.clone();
+    let provided = provider
+        .secrets()
+        .await
+        .context("failed to load service signing keys")?;
+    for (kid, secret) in provided {
+        if !ServiceAuthSettings::valid_key_id(&kid) {
+            bail!(
+                "signing key id '{}' must use only ASCII letters, digits, '-' or '_'",
+                kid
+            );
+        }
+        if secret.len() < ServiceAuthSettings::MIN_SECRET_LEN {
+            bail!(
+                "signing key '{}' must be at least {} bytes",
+                kid,
+                ServiceAuthSettings::MIN_SECRET_LEN
+            );
+        }
+        settings.signing_keys.insert(kid, secret);
+    }
+    if let Some(active) = &settings.active_key {
+        if !settings.signing_keys.contains_key(active) {
+            bail!(
+                "auth.services.active_key '{}' is neither configured nor provided",
+                active
+            );
+        }
+    }
+    Ok(settings)
+}
+
+/// Issues tokens for outbound calls and verifies tokens of inbound ones
+pub struct ServiceTokens {
+    settings: RwLock<ServiceAuthSettings>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ServiceTokens {
+    pub fn new(settings: &ServiceAuthSettings, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+            clock,
+        }
+    }
+
+    /// Replace the keys and lifetimes, e.g. after a rotation was reloaded
+    pub fn update(&self, settings: &ServiceAuthSettings) {
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = settings.clone();
+    }
+
+    /// Token for calling `audience`, sent as `Authorization: Bearer {token}`
+    ///
+    /// Signed with the active key when `active_key` is set, else with `signing_secret`.
+    pub fn issue(&self, audience: &str) -> anyhow::Result<String> {
+        let settings = self.settings();
+        let (kid, secret) = match &settings.active_key {
+            Some(kid) => (
+                Some(kid.clone()),
+                settings
+                    .signing_keys
+                    .get(kid)
+                    .with_context(|| format!("auth.services.signing_keys has no key '{}'", kid))?,
+            ),
+            None => (
+                None,
+                settings
+                    .signing_secret
+                    .as_ref()
+                    .context("auth.services.signing_secret is not set")?,
+            ),
+        };
+        let now = self.unix_now()?;
+        let claims = Claims {
+            iss: settings.name.clone(),
+            aud: audience.to_string(),
+            iat: now,
+            exp: now + settings.ttl_secs,
+            kid,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?);
+        let signature =
+            URL_SAFE_NO_PAD.encode(token::hmac_sha256(secret.as_bytes(), payload.as_bytes()));
+        Ok(format!("{}.{}", payload, signature))
+    }
+
+    /// The service that signed `token` for this service
+    pub fn verify(&self, token: &str) -> anyhow::Result<ServicePrincipal> {
+        let (payload, signature) = token
+            .split_once('.')
+            .ok_or_else(|| anyhow!("malformed service token"))?;
+        let claims: Claims = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(payload)
+                .context("malformed service token")?,
+        )
+        .context("malformed service token claims")?;
+
+        // Unknown issuers and keys are rejected before any signature work
+        let settings = self.settings();
+        let secret = match &claims.kid {
+            Some(kid) => settings
+                .trusted_keys
+                .get(&claims.iss)
+                .and_then(|keys| keys.get(kid))
+                .ok_or_else(|| {
+                    anyhow!("key '{}' of service '{}' is not trusted", kid, claims.iss)
+                })?,
+            None => settings
+                .trusted_secrets
+                .get(&claims.iss)
+                .ok_or_else(|| anyhow!("service '{}' is not trusted", claims.iss))?,
+        };
+        let expected = token::hmac_sha256(secret.as_bytes(), payload.as_bytes());
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("malformed service token signature")?;
+        if !token::constant_time_eq(&expected, &signature) {
+            bail!("invalid service token signature");
+        }
+
+        if claims.aud != settings.name {
+            bail!(
+                "service token is for '{}', not '{}'",
+                claims.aud,
+                settings.name
+            );
+        }
+        let now = self.unix_now()?;
+        let leeway = settings.leeway_secs;
+        if claims.exp + leeway <= now {
+            bail!("service token expired");
+        }
+        if claims.iat > now + leeway {
+            bail!("service token issued in the future");
+        }
+        Ok(ServicePrincipal { name: claims.iss })
+    }
+
+    fn settings(&self) -> RwLockReadGuard<'_, ServiceAuthSettings> {
+        self.settings.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn unix_now(&self) -> anyhow::Result<u64> {
+        Ok(self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .context("clock is before the Unix epoch")?
+            .as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::{Duration, SystemTime};
+
+    const BILLING_SECRET: &str = "billing-secret-billing-secret-0123";
+
+    fn settings(name: &str) -> ServiceAuthSettings {
+        ServiceAuthSettings {
+            name: name.to_string(),
+            signing_secret: Some(BILLING_SECRET.to_string()),
+            trusted_secrets: HashMap::from([("billing".to_string(), BILLING_SECRET.to_string())]),
+            ..ServiceAuthSettings::default()
+        }
+    }
+
+    fn clock() -> Arc<ManualClock> {
+        Arc::new(ManualClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ))
+    }
+
+    #[test]
+    fn test_tokens_verify_for_their_audience() {
+        let clock = clock();
+        let billing = ServiceTokens::new(&settings("billing"), clock.clone());
+        let atlas = ServiceTokens::new(&settings("atlas"), clock);
+
+        let token = billing.issue("atlas").unwrap();
+        let principal = atlas.verify(&token).unwrap();
+        assert_eq!(principal.subject(), "service:billing");
+
+        let error = billing.verify(&token).unwrap_err();
+        assert!(error.to_string().contains("not 'billing'"));
+    }
+
+    #[test]
+    fn test_tampered_expired_and_untrusted_tokens_rejected() {
+        let clock = clock();
+        let billing = ServiceTokens::new(&settings("billing"), clock.clone());
+        let atlas = ServiceTokens::new(&settings("atlas"), clock.clone());
+        let token = billing.issue("atlas").unwrap();
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged_claims = URL_SAFE_NO_PAD.encode(
+            serde_json::to_vec(&Claims {
+                iss: "billing".to_string(),
+                aud: "atlas".to_string(),
+                iat: 1_700_000_000,
+                exp: 1_900_000_000,
+                kid: None,
+            })
+            .unwrap(),
+        );
+        assert!(atlas
+            .verify(&format!("{}.{}", forged_claims, signature))
+            .is_err());
+
+        let stranger = ServiceTokens::new(
+            &ServiceAuthSettings {
+                name: "stranger".to_string(),
+                signing_secret: Some("stranger-secret-stranger-secret-01".to_string()),
+                ..ServiceAuthSettings::default()
+            },
+            clock.clone(),
+        );
+        let error = atlas.verify(&stranger.issue("atlas").unwrap()).unwrap_err();
+        assert!(error.to_string().contains("not trusted"));
+
+        clock.advance(Duration::from_secs(60 + 30));
+        assert!(atlas
+            .verify(&token)
+            .unwrap_err()
+            .to_string()
+            .contains("expired"));
+    }
+
+    #[test]
+    fn test_tokens_signed_before_a_rotation_keep_verifying() {
+        const OLD_KEY: &str = "billing-key-2026-09-0123456789abcd";
+        const NEW_KEY: &str = "billing-key-2026-10-0123456789abcd";
+        let keys = |active: &str| ServiceAuthSettings {
+            name: "billing".to_string(),
+            signing_keys: HashMap::from([
+                ("2026_09".to_string(), OLD_KEY.to_string()),
+                ("2026_10".to_string(), NEW_KEY.to_string()),
+            ]),
+            active_key: Some(active.to_string()),
+            ..ServiceAuthSettings::default()
+        };
+        let clock = clock();
+        let billing = ServiceTokens::new(&keys("2026_09"), clock.clone());
+        let atlas = ServiceTokens::new(
+            &ServiceAuthSettings {
+                name: "atlas".to_string(),
+                trusted_keys: HashMap::from([(
+                    "billing".to_string(),
+                    HashMap::from([
+                        ("2026_09".to_string(), OLD_KEY.to_string()),
+                        ("2026_10".to_string(), NEW_KEY.to_string()),
+                    ]),
+                )]),
+                ..ServiceAuthSettings::default()
+            },
+            clock,
+        );
+
+        let before = billing.issue("atlas").unwrap();
+        billing.update(&keys("2026_10"));
+        let after = billing.issue("atlas").unwrap();
+
+        assert_eq!(atlas.verify(&before).unwrap().name, "billing");
+        assert_eq!(atlas.verify(&after).unwrap().name, "billing");
+
+        // Retiring the old key rejects what it signed
+        atlas.update(&ServiceAuthSettings {
+            name: "atlas".to_string(),
+            trusted_keys: HashMap::from([(
+                "billing".to_string(),
+                HashMap::from([("2026_10".to_string(), NEW_KEY.to_string())]),
+            )]),
+            ..ServiceAuthSettings::default()
+        });
+        let error = atlas.verify(&before).unwrap_err();
+        assert!(error.to_string().contains("key '2026_09'"));
+        assert!(atlas.verify(&after).is_ok());
+    }
+
+    struct FakeSecrets(HashMap<String, String>);
+
+    #[async_trait]
+    impl SecretsProvider for FakeSecrets {
+        async fn secrets(&self) -> anyhow::Result<HashMap<String, String>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provided_keys_sign_and_are_checked() {
+        const PROVIDED: &str = "billing-key-2026-10-0123456789abcd";
+        let settings = ServiceAuthSettings {
+            name: "billing".to_string(),
+            signing_keys: HashMap::from([("2026_10".to_string(), "configured".to_string())]),
+            active_key: Some("2026_10".to_string()),
+            ..ServiceAuthSettings::default()
+        };
+        let provider = FakeSecrets(HashMap::from([(
+            "2026_10".to_string(),
+            PROVIDED.to_string(),
+        )]));
+
+        let loaded = with_provided_keys(&settings, &provider).await.unwrap();
+        assert_eq!(loaded.signing_keys["2026_10"], PROVIDED);
+
+        let short = FakeSecrets(HashMap::from([(
+            "2026_11".to_string(),
+            "short".to_string(),
+        )]));
+        let error = with_provided_keys(&settings, &short).await.unwrap_err();
+        assert!(error.to_string().contains("at least 32 bytes"));
+
+        let retired = ServiceAuthSettings {
+            active_key: Some("2026_09".to_string()),
+            ..settings
+        };
+        let error = with_provided_keys(&retired, &provider).await.unwrap_err();
+        assert!(error.to_string().contains("'2026_09'"));
+    }
+}
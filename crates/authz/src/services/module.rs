@@ -0,0 +1,99 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+
+use atlas_kernel::{
+    settings::{ServiceAuthSettings, Settings},
+    InitCtx, Module,
+};
+
+use super::{secrets, with_provided_keys, SecretsProvider, ServiceTokens};
+
+struct Loaded {
+    tokens: Arc<ServiceTokens>,
+    /// Registered provider, used instead of `auth.services.key_source`
+    provider: Option<Arc<dyn SecretsProvider>>,
+}
+
+/// `settings` with the keys of the registered provider or of `key_source`
+async fn load(
+    settings: &ServiceAuthSettings,
+    registered: Option<&Arc<dyn SecretsProvider>>,
+) -> anyhow::Result<ServiceAuthSettings> {
+    let provider = registered
+        .cloned()
+        .or_else(|| settings.key_source.as_ref().map(secrets::provider));
+    match provider {
+        Some(provider) => with_provided_keys(settings, provider.as_ref()).await,
+        None => Ok(settings.clone()),
+    }
+}
+
+/// Core module publishing `ServiceTokens` from `[auth.services]`
+///
+/// Modules guard internal routes with `require_service` and sign their own outbound
+/// calls with `ServiceTokens::issue`. Signing keys are also loaded from the registered
+/// `Arc<dyn SecretsProvider>` or from `auth.services.key_source`. Reloaded settings
+/// reload the keys, so rotations take effect without a restart.
+#[derive(Default)]
+pub struct ServicesModule {
+    loaded: OnceLock<Loaded>,
+}
+
+#[async_trait]
+impl Module for ServicesModule {
+    fn name(&self) -> &'static str {
+        "services"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Service-to-service authentication")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let provider = ctx
+            .resources
+            .get::<Arc<dyn SecretsProvider>>()
+            .map(|provider| provider.as_ref().clone());
+        let settings = load(&ctx.settings.auth.services, provider.as_ref()).await?;
+        let mut trusted: Vec<&str> = settings
+            .trusted_secrets
+            .keys()
+            .chain(settings.trusted_keys.keys())
+            .map(String::as_str)
+            .collect();
+        trusted.sort_unstable();
+        trusted.dedup();
+        tracing::info!(
+            name = %settings.name,
+            signing = settings.signing_secret.is_some() || settings.active_key.is_some(),
+            active_key = settings.active_key.as_deref().unwrap_or("-"),
+            trusted = ?trusted,
+            "service authentication configured"
+        );
+        let tokens = Arc::new(ServiceTokens::new(&settings, ctx.clock.clone()));
+        ctx.resources.insert_arc(tokens.clone());
+
+        self.loaded
+            .set(Loaded { tokens, provider })
+            .map_err(|_| anyhow::anyhow!("services module initialized twice"))?;
+        Ok(())
+    }
+
+    async fn on_config_change(&self, settings: &Settings) -> anyhow::Result<()> {
+        if let Some(loaded) = self.loaded.get() {
+            let settings = load(&settings.auth.services, loaded.provider.as_ref()).await?;
+            loaded.tokens.update(&settings);
+        }
+        Ok(())
+    }
+}
+
+/// Create a new instance of the services module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(ServicesModule::default())
+}
@@ -0,0 +1,121 @@
+//! Built-in `SecretsProvider`s selected by `auth.services.key_source`
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+
+use atlas_kernel::settings::SecretsSource;
+
+use super::SecretsProvider;
+
+/// The provider reading `source`
+pub fn provider(source: &SecretsSource) -> Arc<dyn SecretsProvider> {
+    match source {
+        SecretsSource::Env { prefix } => Arc::new(EnvSecrets::new(prefix)),
+        SecretsSource::File { dir } => Arc::new(FileSecrets::new(dir.clone())),
+    }
+}
+
+/// Secrets in environment variables named `{prefix}{name}`
+#[derive(Debug, Clone)]
+pub struct EnvSecrets {
+    prefix: String,
+}
+
+impl EnvSecrets {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for EnvSecrets {
+    async fn secrets(&self) -> anyhow::Result<HashMap<String, String>> {
+        Ok(std::env::vars()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix(&self.prefix)?;
+                (!name.is_empty()).then(|| (name.to_string(), value))
+            })
+            .collect())
+    }
+}
+
+/// Secrets in the files of a directory, named by their file name
+///
+/// Surrounding whitespace is trimmed, and hidden files such as the `..data` link of a
+/// mounted Kubernetes secret are skipped.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FileSecrets {
+    async fn secrets(&self) -> anyhow::Result<HashMap<String, String>> {
+        let read_error = || format!("failed to read secrets from {}", self.dir.display());
+        let mut secrets = HashMap::new();
+        for entry in std::fs::read_dir(&self.dir).with_context(read_error)? {
+            let entry = entry.with_context(read_error)?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            // Follows links, as mounted secrets are links to the current version
+            if name.starts_with('.') || !path.is_file() {
+                continue;
+            }
+            let secret = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read secret {}", path.display()))?;
+            secrets.insert(name, secret.trim().to_string());
+        }
+        Ok(secrets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_secrets_skip_hidden_files_and_directories() {
+        let dir = std::env::temp_dir().join(format!("atlas-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("..data")).unwrap();
+        std::fs::write(dir.join("2026_10"), "rotated-secret-rotated-secret-0123\n").unwrap();
+        std::fs::write(dir.join(".hidden"), "ignored").unwrap();
+
+        let secrets = FileSecrets::new(dir.clone()).secrets().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            secrets,
+            HashMap::from([(
+                "2026_10".to_string(),
+                "rotated-secret-rotated-secret-0123".to_string()
+            )])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_env_secrets_are_named_without_their_prefix() {
+        std::env::set_var("ATLAS_TEST_SERVICE_KEY_2026_10", "from-the-environment");
+
+        let secrets = EnvSecrets::new("ATLAS_TEST_SERVICE_KEY_")
+            .secrets()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            secrets,
+            HashMap::from([("2026_10".to_string(), "from-the-environment".to_string())])
+        );
+    }
+}
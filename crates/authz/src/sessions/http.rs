@@ -0,0 +1,426 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    extract::{Request, State},
+    http::{header::SET_COOKIE, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{AppendHeaders, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use atlas_http::{error::AppError, security::Authenticator};
+use atlas_kernel::Resources;
+
+use super::{CredentialVerifier, Session, Sessions};
+use crate::Subject;
+
+/// Route middleware authenticating requests by their session cookie
+///
+/// Live sessions add their `Subject` to the request, so `authorize` can run after it.
+/// State-changing requests must also carry the session's CSRF token.
+pub async fn require_session(
+    State(sessions): State<Arc<Sessions>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let session = current_session(&sessions, request.headers()).await?;
+    check_csrf(&sessions, request.method(), request.headers())?;
+    request.extensions_mut().insert(Subject(session.subject));
+    Ok(next.run(request).await)
+}
+
+/// Route middleware rejecting state-changing requests that send a session cookie
+/// without its CSRF token
+///
+/// For routes that accept a session cookie without `require_session`, such as ones that
+/// also take API keys.
+pub async fn require_csrf(
+    State(sessions): State<Arc<Sessions>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    check_csrf(&sessions, request.method(), request.headers())?;
+    Ok(next.run(request).await)
+}
+
+/// Authenticates the session cookie on routes declaring `SecurityScheme::Session`
+///
+/// Registered by the sessions module, so `build_router` guards those routes with it.
+/// Like `require_session`, state-changing requests must carry the CSRF token.
+pub struct SessionAuthenticator {
+    sessions: Arc<Sessions>,
+}
+
+impl SessionAuthenticator {
+    pub fn new(sessions: Arc<Sessions>) -> Self {
+        Self { sessions }
+    }
+}
+
+#[async_trait]
+impl Authenticator for SessionAuthenticator {
+    async fn authenticate(&self, request: &mut Request) -> Result<bool, AppError> {
+        if self.sessions.token_from(request.headers()).is_none() {
+            return Ok(false);
+        }
+        let session = current_session(&self.sessions, request.headers()).await?;
+        check_csrf(&self.sessions, request.method(), request.headers())?;
+        request.extensions_mut().insert(Subject(session.subject));
+        Ok(true)
+    }
+}
+
+fn check_csrf(sessions: &Sessions, method: &Method, headers: &HeaderMap) -> Result<(), AppError> {
+    let safe = matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    );
+    if safe || !sessions.csrf_enabled() || sessions.csrf_valid(headers) {
+        return Ok(());
+    }
+    Err(AppError::forbidden("missing or invalid CSRF token"))
+}
+
+async fn current_session(sessions: &Sessions, headers: &HeaderMap) -> Result<Session, AppError> {
+    let token = sessions
+        .token_from(headers)
+        .ok_or_else(|| AppError::unauthorized("login required"))?;
+    sessions
+        .resolve(&token)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("session expired or logged out"))
+}
+
+#[derive(Clone)]
+struct SessionState {
+    sessions: Arc<Sessions>,
+    /// Looked up per login, since the module providing the verifier may init after us
+    resources: Arc<Resources>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Login {
+    username: String,
+    password: String,
+}
+
+async fn login(
+    State(state): State<SessionState>,
+    Json(request): Json<Login>,
+) -> Result<Response, AppError> {
+    let verifier = state
+        .resources
+        .get::<Arc<dyn CredentialVerifier>>()
+        .ok_or_else(|| anyhow!("no credential verifier registered; login is unavailable"))?;
+    let subject = verifier
+        .verify(&request.username, &request.password)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("invalid username or password"))?;
+
+    let (session, token) = state.sessions.create(&subject).await?;
+    let cookies = AppendHeaders([
+        (SET_COOKIE, state.sessions.cookie(&token)?),
+        (SET_COOKIE, state.sessions.csrf_cookie(&token)?),
+    ]);
+    Ok((cookies, Json(session)).into_response())
+}
+
+async fn logout(
+    State(state): State<SessionState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    check_csrf(&state.sessions, &method, &headers)?;
+    if let Some(token) = state.sessions.token_from(&headers) {
+        state.sessions.destroy(&token).await?;
+    }
+    let cookies = AppendHeaders([
+        (SET_COOKIE, state.sessions.removal_cookie()?),
+        (SET_COOKIE, state.sessions.csrf_removal_cookie()?),
+    ]);
+    Ok((StatusCode::NO_CONTENT, cookies).into_response())
+}
+
+#[derive(Debug, Serialize)]
+struct CsrfToken {
+    csrf_token: String,
+}
+
+/// Issue the current session's CSRF token, refreshing the CSRF cookie
+async fn csrf_token(
+    State(state): State<SessionState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    current_session(&state.sessions, &headers).await?;
+    let token = state
+        .sessions
+        .token_from(&headers)
+        .ok_or_else(|| AppError::unauthorized("login required"))?;
+    let body = CsrfToken {
+        csrf_token: state.sessions.csrf_token(&token),
+    };
+    let cookie = state.sessions.csrf_cookie(&token)?;
+    Ok(([(SET_COOKIE, cookie)], Json(body)).into_response())
+}
+
+async fn show_session(
+    State(state): State<SessionState>,
+    headers: HeaderMap,
+) -> Result<Json<Session>, AppError> {
+    Ok(Json(current_session(&state.sessions, &headers).await?))
+}
+
+/// `POST /login`, `POST /logout`, `GET /` for the current session and `GET /csrf` for
+/// its CSRF token
+pub fn session_routes(sessions: Arc<Sessions>, resources: Arc<Resources>) -> Router {
+    Router::new()
+        .route("/", get(show_session))
+        .route("/csrf", get(csrf_token))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .with_state(SessionState {
+            sessions,
+            resources,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sessions::MemorySessionStore;
+    use async_trait::async_trait;
+    use atlas_kernel::{clock::SystemClock, settings::SessionSettings};
+    use axum::{body::Body, http::header::COOKIE};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    struct Fixed;
+
+    #[async_trait]
+    impl CredentialVerifier for Fixed {
+        async fn verify(&self, username: &str, password: &str) -> anyhow::Result<Option<String>> {
+            Ok((username == "alice" && password == "hunter2").then(|| "alice".to_string()))
+        }
+    }
+
+    fn sessions() -> Arc<Sessions> {
+        Arc::new(Sessions::new(
+            Arc::new(MemorySessionStore::new()),
+            Arc::new(SystemClock),
+            SessionSettings::default(),
+        ))
+    }
+
+    fn router(sessions: Arc<Sessions>) -> Router {
+        let resources = Arc::new(Resources::new());
+        resources.insert::<Arc<dyn CredentialVerifier>>(Arc::new(Fixed));
+        Router::new().nest("/api/sessions", session_routes(sessions, resources))
+    }
+
+    fn request(method: &str, uri: &str, cookie: Option<&str>, body: Body) -> Request {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(cookie) = cookie {
+            builder = builder.header(COOKIE, cookie);
+        }
+        builder.body(body).unwrap()
+    }
+
+    fn login_body(password: &str) -> Body {
+        Body::from(format!(
+            r#"{{"username":"alice","password":"{}"}}"#,
+            password
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_login_sets_cookie_and_logout_clears_it() {
+        let router = router(sessions());
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/sessions/login",
+                None,
+                login_body("hunter2"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let set_cookies: Vec<&str> = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect();
+        assert!(set_cookies[0].contains("HttpOnly"));
+        assert!(set_cookies[1].starts_with("atlas_csrf="));
+        let cookie = set_cookies[0].split(';').next().unwrap().to_string();
+        let csrf = set_cookies[1]
+            .split(';')
+            .next()
+            .unwrap()
+            .trim_start_matches("atlas_csrf=")
+            .to_string();
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/api/sessions",
+                Some(&cookie),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let session: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(session["subject"], "alice");
+        assert!(session.get("hash").is_none());
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/sessions/logout",
+                Some(&cookie),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let mut logout = request("POST", "/api/sessions/logout", Some(&cookie), Body::empty());
+        logout
+            .headers_mut()
+            .insert("x-csrf-token", csrf.parse().unwrap());
+        let response = router.clone().oneshot(logout).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.headers()[SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .contains("Max-Age=0"));
+
+        let response = router
+            .oneshot(request(
+                "GET",
+                "/api/sessions",
+                Some(&cookie),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_state_changing_requests_need_csrf_token() {
+        let sessions = sessions();
+        let (_, token) = sessions.create("alice").await.unwrap();
+        let router = Router::new()
+            .route(
+                "/notes",
+                get(|| async { "read" }).post(|| async { "written" }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                sessions.clone(),
+                require_session,
+            ))
+            .merge(Router::new().nest(
+                "/api/sessions",
+                session_routes(sessions.clone(), Arc::new(Resources::new())),
+            ));
+        let cookie = format!("atlas_session={}", token);
+
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/notes", Some(&cookie), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router
+            .clone()
+            .oneshot(request("POST", "/notes", Some(&cookie), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/api/sessions/csrf",
+                Some(&cookie),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let issued: Value = serde_json::from_slice(&body).unwrap();
+        let csrf = issued["csrf_token"].as_str().unwrap();
+
+        let mut write = request("POST", "/notes", Some(&cookie), Body::empty());
+        write
+            .headers_mut()
+            .insert("x-csrf-token", csrf.parse().unwrap());
+        let response = router.oneshot(write).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_rejected() {
+        let response = router(sessions())
+            .oneshot(request(
+                "POST",
+                "/api/sessions/login",
+                None,
+                login_body("wrong"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().get(SET_COOKIE).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_sets_subject_from_cookie() {
+        let sessions = sessions();
+        let (_, token) = sessions.create("alice").await.unwrap();
+        let router = Router::new()
+            .route(
+                "/whoami",
+                get(|axum::Extension(subject): axum::Extension<Subject>| async move { subject.0 }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                sessions,
+                require_session,
+            ));
+
+        let cookie = format!("atlas_session={}", token);
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/whoami", Some(&cookie), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = router
+            .oneshot(request("GET", "/whoami", None, Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,283 @@
+//! Cookie sessions for first-party web UIs
+//!
+//! Logging in exchanges credentials, checked by a `CredentialVerifier` the application
+//! provides, for an opaque session cookie. Only the cookie's hash is stored.
+//!
+//! State-changing requests authenticated by the cookie must echo the session's CSRF token,
+//! derived from the session token and handed to frontends in a readable cookie.
+
+mod http;
+mod module;
+mod store;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use time::OffsetDateTime;
+
+use atlas_kernel::{id, settings::SameSite, settings::SessionSettings, Clock};
+
+use crate::{login_cookie, token};
+
+pub use http::{require_csrf, require_session, session_routes, SessionAuthenticator};
+pub use module::{create_module, SessionsModule};
+pub use store::{MemorySessionStore, Session, SessionStore, SurrealSessionStore};
+
+/// Prefix of every session cookie value
+const TOKEN_PREFIX: &str = "ses_";
+
+/// Checks login credentials, returning the subject they authenticate as
+///
+/// Login fails until one is registered, usually by the module owning user accounts.
+#[async_trait]
+pub trait CredentialVerifier: Send + Sync {
+    async fn verify(&self, username: &str, password: &str) -> anyhow::Result<Option<String>>;
+}
+
+/// Creates, resolves, and ends sessions and renders their cookies
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    clock: Arc<dyn Clock>,
+    settings: SessionSettings,
+}
+
+impl Sessions {
+    pub fn new(
+        store: Arc<dyn SessionStore>,
+        clock: Arc<dyn Clock>,
+        settings: SessionSettings,
+    ) -> Self {
+        Self {
+            store,
+            clock,
+            settings,
+        }
+    }
+
+    /// Start a session for `subject`, returning it with the cookie value
+    pub async fn create(&self, subject: &str) -> anyhow::Result<(Session, String)> {
+        let token = token::generate(TOKEN_PREFIX)?;
+        let created_at = self.now();
+        let session = Session {
+            id: id::prefixed_with("ses", id::ulid_at(self.clock.now()))?,
+            subject: subject.to_string(),
+            hash: token::hash(&token),
+            created_at,
+            expires_at: created_at + Duration::from_secs(self.settings.ttl_secs),
+        };
+        self.store
+            .insert(session.clone())
+            .await
+            .context("failed to store session")?;
+        tracing::info!(session_id = %session.id, subject = %session.subject, "session started");
+        Ok((session, token))
+    }
+
+    /// The live session for a cookie value, if any; expired sessions are removed
+    pub async fn resolve(&self, token: &str) -> anyhow::Result<Option<Session>> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let hash = token::hash(token);
+        match self.store.find_by_hash(&hash).await? {
+            Some(session) if session.is_expired(self.now()) => {
+                self.store.delete_by_hash(&hash).await?;
+                Ok(None)
+            }
+            session => Ok(session),
+        }
+    }
+
+    /// End the session for a cookie value, returning `false` if there was none
+    pub async fn destroy(&self, token: &str) -> anyhow::Result<bool> {
+        self.store.delete_by_hash(&token::hash(token)).await
+    }
+
+    pub async fn purge_expired(&self) -> anyhow::Result<usize> {
+        self.store.purge_expired(self.now()).await
+    }
+
+    /// The session cookie value sent with a request, if any
+    pub fn token_from(&self, headers: &HeaderMap) -> Option<String> {
+        login_cookie::cookie_value(headers, &self.settings.cookie_name)
+    }
+
+    /// Whether cookies are marked `Secure` under `auth.session.secure`
+    pub(crate) fn secure_cookies(&self) -> bool {
+        self.settings.secure
+    }
+
+    /// Whether state-changing requests must carry the CSRF token
+    pub fn csrf_enabled(&self) -> bool {
+        self.settings.csrf.enabled
+    }
+
+    /// CSRF token of the session behind a cookie value
+    ///
+    /// Derived from the secret session token, so it is stable for the session's lifetime,
+    /// needs no storage, and cannot be computed by a page that only sees the CSRF cookie.
+    pub fn csrf_token(&self, token: &str) -> String {
+        token::to_hex(&token::hmac_sha256(token.as_bytes(), b"atlas-csrf"))
+    }
+
+    /// Whether a request carries the CSRF token of the session it is authenticated by
+    ///
+    /// Requests without a session cookie pass; there is no ambient credential to abuse.
+    pub fn csrf_valid(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = self.token_from(headers) else {
+            return true;
+        };
+        let expected = self.csrf_token(&token);
+        headers
+            .get(self.settings.csrf.header.as_str())
+            .is_some_and(|sent| token::constant_time_eq(sent.as_bytes(), expected.as_bytes()))
+    }
+
+    /// `Set-Cookie` value exposing a session's CSRF token to frontend scripts
+    pub fn csrf_cookie(&self, token: &str) -> anyhow::Result<HeaderValue> {
+        self.render_readable_cookie(&self.csrf_token(token), self.settings.ttl_secs)
+    }
+
+    /// `Set-Cookie` value telling the browser to drop its CSRF cookie
+    pub fn csrf_removal_cookie(&self) -> anyhow::Result<HeaderValue> {
+        self.render_readable_cookie("", 0)
+    }
+
+    /// `Set-Cookie` value handing a new session to the browser
+    pub fn cookie(&self, token: &str) -> anyhow::Result<HeaderValue> {
+        self.render_cookie(token, self.settings.ttl_secs)
+    }
+
+    /// `Set-Cookie` value telling the browser to drop its session cookie
+    pub fn removal_cookie(&self) -> anyhow::Result<HeaderValue> {
+        self.render_cookie("", 0)
+    }
+
+    fn render_cookie(&self, value: &str, max_age: u64) -> anyhow::Result<HeaderValue> {
+        let cookie = self.cookie_attributes(&self.settings.cookie_name, value, max_age, true);
+        HeaderValue::from_str(&cookie).context("invalid session cookie")
+    }
+
+    fn render_readable_cookie(&self, value: &str, max_age: u64) -> anyhow::Result<HeaderValue> {
+        let cookie = self.cookie_attributes(&self.settings.csrf.cookie_name, value, max_age, false);
+        HeaderValue::from_str(&cookie).context("invalid CSRF cookie")
+    }
+
+    fn cookie_attributes(&self, name: &str, value: &str, max_age: u64, http_only: bool) -> String {
+        let mut cookie = format!("{}={}; Path=/; Max-Age={}", name, value, max_age);
+        if http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        cookie.push_str("; SameSite=");
+        cookie.push_str(self.settings.same_site.as_str());
+        if self.settings.secure || self.settings.same_site == SameSite::None {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use axum::http::header::COOKIE;
+
+    fn sessions(clock: Arc<ManualClock>) -> Sessions {
+        Sessions::new(
+            Arc::new(MemorySessionStore::new()),
+            clock,
+            SessionSettings::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sessions_resolve_until_expired() {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::UNIX_EPOCH));
+        let sessions = sessions(clock.clone());
+        let (session, token) = sessions.create("alice").await.unwrap();
+
+        assert!(session.id.starts_with("ses_"));
+        let resolved = sessions.resolve(&token).await.unwrap().unwrap();
+        assert_eq!(resolved.subject, "alice");
+
+        clock.advance(Duration::from_secs(SessionSettings::default().ttl_secs));
+        assert!(sessions.resolve(&token).await.unwrap().is_none());
+        assert!(!sessions.destroy(&token).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_destroyed_sessions_no_longer_resolve() {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::UNIX_EPOCH));
+        let sessions = sessions(clock);
+        let (_, token) = sessions.create("alice").await.unwrap();
+
+        assert!(sessions.destroy(&token).await.unwrap());
+        assert!(sessions.resolve(&token).await.unwrap().is_none());
+        assert!(sessions.resolve("not-a-session").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_csrf_token_must_match_the_session() {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::UNIX_EPOCH));
+        let sessions = sessions(clock);
+        let (_, token) = sessions.create("alice").await.unwrap();
+        let (_, other) = sessions.create("mallory").await.unwrap();
+        let headers = |csrf: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                COOKIE,
+                HeaderValue::from_str(&format!("atlas_session={}", token)).unwrap(),
+            );
+            headers.insert("x-csrf-token", HeaderValue::from_str(csrf).unwrap());
+            headers
+        };
+
+        assert_eq!(sessions.csrf_token(&token).len(), 64);
+        assert!(sessions.csrf_valid(&headers(&sessions.csrf_token(&token))));
+        assert!(!sessions.csrf_valid(&headers(&sessions.csrf_token(&other))));
+        assert!(!sessions.csrf_valid(&headers("")));
+        assert!(sessions.csrf_valid(&HeaderMap::new()));
+
+        let cookie = sessions.csrf_cookie(&token).unwrap();
+        let cookie = cookie.to_str().unwrap();
+        assert!(cookie.starts_with(&format!("atlas_csrf={};", sessions.csrf_token(&token))));
+        assert!(!cookie.contains("HttpOnly"));
+    }
+
+    #[test]
+    fn test_cookie_attributes_follow_settings() {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::UNIX_EPOCH));
+        let settings = SessionSettings {
+            same_site: SameSite::Strict,
+            ..SessionSettings::default()
+        };
+        let sessions = Sessions::new(Arc::new(MemorySessionStore::new()), clock, settings);
+
+        let cookie = sessions.cookie("ses_abc").unwrap();
+        assert_eq!(
+            cookie,
+            "atlas_session=ses_abc; Path=/; Max-Age=86400; HttpOnly; SameSite=Strict; Secure"
+        );
+        assert!(sessions
+            .removal_cookie()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("atlas_session=; Path=/; Max-Age=0;"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; atlas_session=ses_abc"),
+        );
+        assert_eq!(sessions.token_from(&headers).as_deref(), Some("ses_abc"));
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_http::security::Authenticators;
+use atlas_kernel::{InitCtx, Migration, Module, Resources, RouteSecurity, SecurityScheme};
+
+use super::{
+    session_routes, MemorySessionStore, SessionAuthenticator, SessionStore, Sessions,
+    SurrealSessionStore,
+};
+
+/// How often expired sessions are swept from the store
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Core module serving cookie login and logout for first-party web UIs
+///
+/// Publishes `Sessions` as a shared resource and registers the `SessionAuthenticator`, so
+/// routes declaring `SecurityScheme::Session` accept the session cookie and check its
+/// CSRF token.
+#[derive(Default)]
+pub struct SessionsModule {
+    sessions: OnceLock<Arc<Sessions>>,
+    resources: OnceLock<Arc<Resources>>,
+}
+
+#[async_trait]
+impl Module for SessionsModule {
+    fn name(&self) -> &'static str {
+        "sessions"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Cookie session login and logout")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn SessionStore>(
+            &ctx.resources,
+            "session",
+            |db| Arc::new(SurrealSessionStore::new(db)),
+            || Arc::new(MemorySessionStore::new()),
+        );
+        let sessions = Arc::new(Sessions::new(
+            store,
+            ctx.clock.clone(),
+            ctx.settings.auth.session.clone(),
+        ));
+        ctx.resources.insert_arc(sessions.clone());
+        Authenticators::published(&ctx.resources).register(
+            SecurityScheme::Session,
+            Arc::new(SessionAuthenticator::new(sessions.clone())),
+        );
+
+        self.sessions
+            .set(sessions)
+            .map_err(|_| anyhow::anyhow!("sessions module initialized twice"))?;
+        self.resources.get_or_init(|| ctx.resources.clone());
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let Some(sessions) = self.sessions.get().cloned() else {
+            return Ok(());
+        };
+        let clock = ctx.clock.clone();
+        ctx.spawn("purge_expired", async move {
+            loop {
+                clock.sleep(PURGE_INTERVAL).await;
+                match sessions.purge_expired().await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::debug!(purged, "purged expired sessions"),
+                    Err(error) => {
+                        tracing::warn!(error = %error, "failed to purge expired sessions")
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match (self.sessions.get(), self.resources.get()) {
+            (Some(sessions), Some(resources)) => {
+                session_routes(sessions.clone(), resources.clone())
+            }
+            _ => Router::new(),
+        }
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let session = json!({
+            "type": "object",
+            "required": ["id", "subject", "created_at", "expires_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "subject": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "expires_at": { "type": "string", "format": "date-time" }
+            }
+        });
+        let session_response = |description: &str| {
+            json!({
+                "description": description,
+                "content": { "application/json": { "schema": session.clone() } }
+            })
+        };
+        Some(json!({
+            "tags": [
+                { "name": "Sessions", "description": "Cookie authentication for web UIs" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "Show the current session",
+                        "tags": ["Sessions"],
+                        "responses": {
+                            "200": session_response("The session identified by the cookie"),
+                            "401": { "description": "No live session" }
+                        }
+                    }
+                },
+                "/login": {
+                    "post": {
+                        "summary": "Log in and receive a session cookie",
+                        "tags": ["Sessions"],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["username", "password"],
+                                        "properties": {
+                                            "username": { "type": "string" },
+                                            "password": { "type": "string", "format": "password" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "200": session_response("Logged in; the session and CSRF cookies are set with `Set-Cookie`"),
+                            "401": { "description": "Invalid username or password" }
+                        }
+                    }
+                },
+                "/csrf": {
+                    "get": {
+                        "summary": "Issue the CSRF token of the current session",
+                        "description": "Send the token in the CSRF header (`x-csrf-token` by default) on every state-changing request authenticated by the session cookie.",
+                        "tags": ["Sessions"],
+                        "responses": {
+                            "200": {
+                                "description": "The token; the CSRF cookie is refreshed",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "object",
+                                            "required": ["csrf_token"],
+                                            "properties": { "csrf_token": { "type": "string" } }
+                                        }
+                                    }
+                                }
+                            },
+                            "401": { "description": "No live session" }
+                        }
+                    }
+                },
+                "/logout": {
+                    "post": {
+                        "summary": "End the current session",
+                        "tags": ["Sessions"],
+                        "responses": {
+                            "204": { "description": "Logged out; the cookies are cleared" },
+                            "403": { "description": "Missing or invalid CSRF token" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        ["/", "/csrf"]
+            .into_iter()
+            .map(|path| RouteSecurity {
+                method: "get",
+                path,
+                schemes: &[SecurityScheme::Session],
+                permission: None,
+            })
+            .collect()
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_session",
+            up: "DEFINE TABLE session SCHEMAFULL;
+DEFINE FIELD subject ON session TYPE string;
+DEFINE FIELD hash ON session TYPE string;
+DEFINE FIELD created_at ON session TYPE datetime;
+DEFINE FIELD expires_at ON session TYPE datetime;
+DEFINE INDEX session_hash ON session FIELDS hash UNIQUE;
+DEFINE INDEX session_expires_at ON session FIELDS expires_at;",
+            down: Some("REMOVE TABLE session;"),
+        }]
+    }
+}
+
+/// Create a new instance of the sessions module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(SessionsModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sessions::CredentialVerifier;
+    use crate::Subject;
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::{settings::Settings, ModuleRegistry};
+    use axum::{
+        http::{header::COOKIE, StatusCode},
+        routing::get,
+        Json,
+    };
+    use serde_json::Value;
+
+    struct Fixed;
+
+    #[async_trait]
+    impl CredentialVerifier for Fixed {
+        async fn verify(&self, username: &str, password: &str) -> anyhow::Result<Option<String>> {
+            Ok((username == "alice" && password == "hunter2").then(|| "alice".to_string()))
+        }
+    }
+
+    /// App module whose routes accept sessions
+    struct NotesModule;
+
+    impl Module for NotesModule {
+        fn name(&self) -> &'static str {
+            "notes"
+        }
+
+        fn routes(&self) -> Router {
+            let whoami = |subject: Subject| async move { Json(subject.0) };
+            Router::new().route("/", get(whoami).post(whoami))
+        }
+
+        fn security(&self) -> Vec<RouteSecurity> {
+            vec![RouteSecurity {
+                method: "*",
+                path: "/",
+                schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+                permission: None,
+            }]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_sessions() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(Settings::default())))
+            .await
+            .unwrap();
+
+        let sessions = registry.resources().require::<Sessions>().unwrap();
+        let (_, token) = sessions.create("alice").await.unwrap();
+        assert!(sessions.resolve(&token).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_app_routes_take_sessions_and_check_csrf() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry.register_custom(Arc::new(NotesModule)).unwrap();
+        registry
+            .resources()
+            .insert::<Arc<dyn CredentialVerifier>>(Arc::new(Fixed));
+        let settings = Settings::default();
+        registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings.clone())))
+            .await
+            .unwrap();
+        let router = atlas_http::build_router(&registry, &settings)
+            .await
+            .unwrap();
+
+        let login = request(
+            "POST",
+            "/api/sessions/login",
+            serde_json::json!({ "username": "alice", "password": "hunter2" }),
+        );
+        let response = tower::ServiceExt::oneshot(router.clone(), login)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookies: Vec<String> = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .unwrap()
+                    .split(';')
+                    .next()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        let csrf = cookies[1].trim_start_matches("atlas_csrf=").to_string();
+        let notes = |method: &str, csrf: Option<&str>| {
+            let mut request = request(method, "/api/notes", Value::Null);
+            request
+                .headers_mut()
+                .insert(COOKIE, cookies.join("; ").parse().unwrap());
+            if let Some(csrf) = csrf {
+                request
+                    .headers_mut()
+                    .insert("x-csrf-token", csrf.parse().unwrap());
+            }
+            request
+        };
+
+        let (status, subject) = send(&router, notes("GET", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(subject, "alice");
+        let (status, _) = send(&router, notes("POST", None)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, subject) = send(&router, notes("POST", Some(&csrf))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(subject, "alice");
+
+        let (status, _) = send(&router, request("GET", "/api/notes", Value::Null)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+
+use atlas_db::{affected, datetime, QueryExecutor};
+
+/// A logged-in browser session; only the hash of the cookie value is kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Session {
+    /// Table-prefixed id such as `ses_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Subject requests made in this session act as, matched against authz policies
+    pub subject: String,
+    #[serde(skip)]
+    pub hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+impl Session {
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Persistence for sessions
+///
+/// The module stores sessions in the `session` table through the application's
+/// `QueryExecutor`; applications may publish their own `Arc<dyn SessionStore>` resource
+/// instead. Without either, sessions live in memory.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn insert(&self, session: Session) -> anyhow::Result<()>;
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<Session>>;
+
+    /// Remove a session, returning `false` if none has that hash
+    async fn delete_by_hash(&self, hash: &str) -> anyhow::Result<bool>;
+
+    /// Remove every session expired at `now`, returning how many were removed
+    async fn purge_expired(&self, now: OffsetDateTime) -> anyhow::Result<usize>;
+}
+
+/// Process-local store; sessions are lost on restart
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    sessions: RwLock<Vec<Session>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn insert(&self, session: Session) -> anyhow::Result<()> {
+        self.sessions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(session);
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<Session>> {
+        Ok(self
+            .sessions
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|session| session.hash == hash)
+            .cloned())
+    }
+
+    async fn delete_by_hash(&self, hash: &str) -> anyhow::Result<bool> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = sessions.len();
+        sessions.retain(|session| session.hash != hash);
+        Ok(sessions.len() != before)
+    }
+
+    async fn purge_expired(&self, now: OffsetDateTime) -> anyhow::Result<usize> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = sessions.len();
+        sessions.retain(|session| !session.is_expired(now));
+        Ok(before - sessions.len())
+    }
+}
+
+/// Sessions in the `session` table defined by the module's migrations
+pub struct SurrealSessionStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealSessionStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+}
+
+/// A row of the `session` table, which unlike `Session` carries the hash
+#[derive(Deserialize)]
+struct SessionRow {
+    id: String,
+    subject: String,
+    hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    expires_at: OffsetDateTime,
+}
+
+#[async_trait]
+impl SessionStore for SurrealSessionStore {
+    async fn insert(&self, session: Session) -> anyhow::Result<()> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(session.id));
+        vars.insert("subject".to_string(), json!(session.subject));
+        vars.insert("hash".to_string(), json!(session.hash));
+        vars.insert(
+            "created_at".to_string(),
+            json!(datetime(session.created_at)),
+        );
+        vars.insert(
+            "expires_at".to_string(),
+            json!(datetime(session.expires_at)),
+        );
+        self.db
+            .query_with(
+                "CREATE type::thing('session', $id) SET subject = $subject, hash = $hash, \
+                 created_at = <datetime> $created_at, expires_at = <datetime> $expires_at \
+                 RETURN NONE;",
+                &vars,
+            )
+            .await
+            .context("failed to store session")?;
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, hash: &str) -> anyhow::Result<Option<Session>> {
+        let mut vars = Map::new();
+        vars.insert("hash".to_string(), json!(hash));
+        let results = self
+            .db
+            .query_with(
+                "SELECT *, record::id(id) AS id FROM session WHERE hash = $hash;",
+                &vars,
+            )
+            .await
+            .context("failed to read session")?;
+        Ok(atlas_db::records::<SessionRow>(results.into_iter().next())?
+            .into_iter()
+            .next()
+            .map(|row| Session {
+                id: row.id,
+                subject: row.subject,
+                hash: row.hash,
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+            }))
+    }
+
+    async fn delete_by_hash(&self, hash: &str) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("hash".to_string(), json!(hash));
+        let results = self
+            .db
+            .query_with("DELETE session WHERE hash = $hash RETURN BEFORE;", &vars)
+            .await
+            .context("failed to delete session")?;
+        Ok(affected(results.first()))
+    }
+
+    async fn purge_expired(&self, now: OffsetDateTime) -> anyhow::Result<usize> {
+        let mut vars = Map::new();
+        vars.insert("now".to_string(), json!(datetime(now)));
+        let results = self
+            .db
+            .query_with(
+                "DELETE session WHERE expires_at <= <datetime> $now RETURN VALUE record::id(id);",
+                &vars,
+            )
+            .await
+            .context("failed to purge expired sessions")?;
+        Ok(match results.first() {
+            Some(Value::Array(purged)) => purged.len(),
+            _ => 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_db::testing::RecordingExecutor;
+
+    #[tokio::test]
+    async fn test_surreal_store_finds_and_purges_sessions() {
+        let db = RecordingExecutor::answering(vec![
+            Ok(vec![json!([{
+                "id": "ses_1",
+                "subject": "user_1",
+                "hash": "9f86d0",
+                "created_at": "2024-01-01T00:00:00Z",
+                "expires_at": "2024-01-02T00:00:00Z"
+            }])]),
+            Ok(vec![json!(["ses_1", "ses_2"])]),
+        ]);
+        let store = SurrealSessionStore::new(db.clone());
+
+        let session = store.find_by_hash("9f86d0").await.unwrap().unwrap();
+        assert_eq!(session.subject, "user_1");
+        assert_eq!(store.purge_expired(session.expires_at).await.unwrap(), 2);
+
+        let (_, vars) = db.queries()[1].clone();
+        assert_eq!(vars["now"], "2024-01-02T00:00:00Z");
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_http::error::AppError;
+
+use super::{RequestSignatures, SignatureError, CLIENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::Subject;
+
+impl From<SignatureError> for AppError {
+    fn from(error: SignatureError) -> Self {
+        match error {
+            SignatureError::Missing(_) => AppError::unauthorized(error.to_string()),
+            _ => {
+                tracing::debug!(error = %error, "rejected signed request");
+                AppError::unauthorized("invalid request signature")
+            }
+        }
+    }
+}
+
+/// Route middleware admitting only requests signed by a configured client
+///
+/// Buffers the body, up to `auth.signatures.max_body_bytes`, to check the signature
+/// and hands it on unchanged. Adds the `SignedClient` and a `client:{name}` `Subject`.
+pub async fn verify_signature(
+    State(signatures): State<Arc<RequestSignatures>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (mut parts, body) = request.into_parts();
+    let header = |name: &'static str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or(SignatureError::Missing(name))
+    };
+    let client = header(CLIENT_HEADER)?;
+    let timestamp = header(TIMESTAMP_HEADER)?;
+    let signature = header(SIGNATURE_HEADER)?;
+
+    let body = axum::body::to_bytes(body, signatures.max_body_bytes())
+        .await
+        .map_err(|_| AppError::bad_request("request body too large to verify"))?;
+    let client = signatures.verify(&client, &timestamp, &signature, &body)?;
+
+    tracing::debug!(client = %client.name, "verified request signature");
+    parts.extensions.insert(Subject(client.subject()));
+    parts.extensions.insert(client);
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::{
+        sign,
+        tests::{signatures, NOW, SECRET},
+    };
+    use axum::{http::StatusCode, routing::post, Extension, Router};
+    use tower::ServiceExt;
+
+    fn signed(body: &'static str, signature: &str) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/hooks")
+            .header(CLIENT_HEADER, "acme")
+            .header(TIMESTAMP_HEADER, NOW.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signed_body_reaches_handler() {
+        let router = Router::new()
+            .route(
+                "/hooks",
+                post(
+                    |Extension(subject): Extension<Subject>, body: String| async move {
+                        format!("{} {}", subject.0, body)
+                    },
+                ),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                Arc::new(signatures()),
+                verify_signature,
+            ));
+
+        let body = r#"{"event":"paid"}"#;
+        let response = router
+            .clone()
+            .oneshot(signed(body, &sign(SECRET, NOW, body.as_bytes())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let received = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&received[..], br#"client:acme {"event":"paid"}"#);
+
+        let response = router
+            .clone()
+            .oneshot(signed(body, &sign(SECRET, NOW, b"tampered")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let unsigned = Request::builder()
+            .method("POST")
+            .uri("/hooks")
+            .body(Body::from(body))
+            .unwrap();
+        assert_eq!(
+            router.oneshot(unsigned).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
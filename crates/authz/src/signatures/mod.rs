@@ -0,0 +1,190 @@
+//! HMAC-signed requests for partner and webhook-style integrations
+//!
+//! A client sends its name in `x-atlas-client`, the Unix time in `x-atlas-timestamp` and
+//! `sha256={hex}` in `x-atlas-signature`, where the hex is the HMAC-SHA256 of
+//! `{timestamp}.{body}` under the client's secret. Signed requests authenticate as
+//! subject `client:{name}`.
+
+mod http;
+mod module;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use atlas_kernel::{settings::SignatureSettings, Clock};
+
+use crate::token;
+
+pub use http::verify_signature;
+pub use module::{create_module, SignaturesModule};
+
+pub const CLIENT_HEADER: &str = "x-atlas-client";
+pub const TIMESTAMP_HEADER: &str = "x-atlas-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-atlas-signature";
+
+/// Prefix of subjects authenticated by a request signature
+pub const CLIENT_SUBJECT_PREFIX: &str = "client:";
+
+/// A client whose request signature checked out, inserted next to its `Subject`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedClient {
+    pub name: String,
+}
+
+impl SignedClient {
+    /// Subject matched against policies, e.g. `client:acme`
+    pub fn subject(&self) -> String {
+        format!("{}{}", CLIENT_SUBJECT_PREFIX, self.name)
+    }
+}
+
+/// Why a signed request was rejected
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("missing {0} header")]
+    Missing(&'static str),
+    #[error("unknown client")]
+    UnknownClient,
+    #[error("timestamp outside the accepted window")]
+    Stale,
+    #[error("signature mismatch")]
+    Mismatch,
+}
+
+/// `sha256={hex}` signature of `body` sent at `timestamp`
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    format!("sha256={}", token::to_hex(&mac(secret, timestamp, body)))
+}
+
+/// `sha256={hex}` HMAC-SHA256 of `body` alone, as GitHub signs its webhooks
+pub fn sign_body(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        token::to_hex(&token::hmac_sha256(secret.as_bytes(), body))
+    )
+}
+
+/// Compare a presented signature with the expected one without leaking where they differ
+pub fn signatures_match(expected: &str, presented: &str) -> bool {
+    token::constant_time_eq(expected.as_bytes(), presented.trim().as_bytes())
+}
+
+fn mac(secret: &str, timestamp: u64, body: &[u8]) -> [u8; 32] {
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    token::hmac_sha256(secret.as_bytes(), &message)
+}
+
+/// Checks request signatures against the configured client secrets
+pub struct RequestSignatures {
+    secrets: HashMap<String, String>,
+    tolerance_secs: u64,
+    max_body_bytes: usize,
+    clock: Arc<dyn Clock>,
+}
+
+impl RequestSignatures {
+    pub fn new(settings: &SignatureSettings, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            secrets: settings.client_secrets.clone(),
+            tolerance_secs: settings.tolerance_secs,
+            max_body_bytes: settings.max_body_bytes,
+            clock,
+        }
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    /// The client that signed `body`
+    pub fn verify(
+        &self,
+        client: &str,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+    ) -> Result<SignedClient, SignatureError> {
+        let secret = self
+            .secrets
+            .get(client)
+            .ok_or(SignatureError::UnknownClient)?;
+        let timestamp: u64 = timestamp.parse().map_err(|_| SignatureError::Stale)?;
+        let now = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now.abs_diff(timestamp) > self.tolerance_secs {
+            return Err(SignatureError::Stale);
+        }
+
+        if !signatures_match(&sign(secret, timestamp, body), signature) {
+            return Err(SignatureError::Mismatch);
+        }
+        Ok(SignedClient {
+            name: client.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::{Duration, SystemTime};
+
+    pub(crate) const SECRET: &str = "acme-secret-acme-secret-acme-secret";
+    pub(crate) const NOW: u64 = 1_700_000_000;
+
+    pub(crate) fn signatures() -> RequestSignatures {
+        RequestSignatures::new(
+            &SignatureSettings {
+                client_secrets: HashMap::from([("acme".to_string(), SECRET.to_string())]),
+                ..SignatureSettings::default()
+            },
+            Arc::new(ManualClock::new(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(NOW),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_body_signature_matches_github_example() {
+        assert_eq!(
+            sign_body("It's a Secret to Everybody", b"Hello, World!"),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+
+    #[test]
+    fn test_signature_over_timestamp_and_body() {
+        let signatures = signatures();
+        let body = br#"{"order":42}"#;
+        let signature = sign(SECRET, NOW, body);
+        assert!(signature.starts_with("sha256="));
+
+        let client = signatures
+            .verify("acme", &NOW.to_string(), &signature, body)
+            .unwrap();
+        assert_eq!(client.subject(), "client:acme");
+
+        assert_eq!(
+            signatures.verify("acme", &NOW.to_string(), &signature, b"{}"),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            signatures.verify("other", &NOW.to_string(), &signature, body),
+            Err(SignatureError::UnknownClient)
+        );
+        assert!(signatures_match(&signature, &format!(" {}", signature)));
+        assert!(!signatures_match(&signature, &sign_body(SECRET, body)));
+        let late = NOW - 301;
+        assert_eq!(
+            signatures.verify("acme", &late.to_string(), &sign(SECRET, late, body), body),
+            Err(SignatureError::Stale)
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use atlas_kernel::{InitCtx, Module};
+
+use super::RequestSignatures;
+
+/// Core module publishing `RequestSignatures` from `[auth.signatures]`
+///
+/// Modules guard partner-facing routes with `verify_signature`.
+#[derive(Default)]
+pub struct SignaturesModule;
+
+#[async_trait]
+impl Module for SignaturesModule {
+    fn name(&self) -> &'static str {
+        "signatures"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("HMAC request signatures for partner integrations")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.auth.signatures;
+        let mut clients: Vec<&str> = settings.client_secrets.keys().map(String::as_str).collect();
+        clients.sort_unstable();
+        tracing::info!(clients = ?clients, "request signatures configured");
+        ctx.resources
+            .insert(RequestSignatures::new(settings, ctx.clock.clone()));
+        Ok(())
+    }
+}
+
+/// Create a new instance of the signatures module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(SignaturesModule)
+}
@@ -0,0 +1,129 @@
+//! Keeping callers inside the tenants they belong to
+//!
+//! Tenants are resolved from what the client sends, such as the `x-tenant-id` header, so
+//! naming a tenant proves nothing. Tenant-scoped routes add `require_tenant_member`
+//! instead of `atlas_http::tenant::require_tenant`.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    extract::{OriginalUri, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_http::{error::AppError, tenant::TenantCtx};
+use atlas_kernel::{tenant::TenantId, AppContext};
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::Subject;
+
+/// Who belongs to which tenant, usually answered by the module owning organizations
+#[async_trait]
+pub trait TenantMembers: Send + Sync {
+    async fn is_member(&self, tenant: &TenantId, subject: &str) -> anyhow::Result<bool>;
+}
+
+/// Route middleware admitting only requests whose subject belongs to the resolved tenant
+///
+/// Rejects requests without a tenant like `require_tenant`, then requires a `Subject` from
+/// authentication middleware and asks the `Arc<dyn TenantMembers>` resource, reached
+/// through the `AppContext` extension, whether it is a member. Refusals are audited.
+pub async fn require_tenant_member(request: Request, next: Next) -> Result<Response, AppError> {
+    let tenant = request
+        .extensions()
+        .get::<TenantCtx>()
+        .ok_or_else(|| AppError::bad_request("tenant required"))?;
+    let subject = request
+        .extensions()
+        .get::<Subject>()
+        .ok_or_else(|| AppError::unauthorized("authentication required"))?;
+    let members = request
+        .extensions()
+        .get::<AppContext>()
+        .ok_or_else(|| anyhow!("require_tenant_member needs the AppContext extension"))?
+        .require::<Arc<dyn TenantMembers>>()?;
+
+    if !members.is_member(&tenant.id, &subject.0).await? {
+        tracing::debug!(subject = %subject.0, tenant = %tenant.id, "caller is not a member of the tenant");
+        // The request body is not `Sync`, so nothing borrowed from the request crosses the await
+        let (actor, tenant) = (subject.0.clone(), tenant.id.to_string());
+        let path = request
+            .extensions()
+            .get::<OriginalUri>()
+            .map_or_else(|| request.uri().path(), |original| original.path())
+            .to_string();
+        let ctx = request.extensions().get::<AppContext>().cloned();
+        audit::record_in(ctx.as_ref(), |at| {
+            AuditEvent::new("authz.denied", AuditOutcome::Denied, at)
+                .actor(actor)
+                .permission(format!("tenant:{}", tenant))
+                .resource(path)
+        })
+        .await;
+        return Err(AppError::forbidden("not a member of this tenant"));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use atlas_http::testing::send;
+    use atlas_kernel::settings::TenantSource;
+    use axum::{http::StatusCode, routing::get, Router};
+    use serde_json::Value;
+
+    struct Acme;
+
+    #[async_trait]
+    impl TenantMembers for Acme {
+        async fn is_member(&self, tenant: &TenantId, subject: &str) -> anyhow::Result<bool> {
+            Ok(tenant.as_str() == "acme" && subject == "ann")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_members_reach_the_tenant() {
+        let resources = testing::resources("");
+        resources.insert::<Arc<dyn TenantMembers>>(Arc::new(Acme));
+        let router = testing::with_context(
+            Router::new()
+                .route("/books", get(|| async { "books" }))
+                .route_layer(axum::middleware::from_fn(require_tenant_member)),
+            resources,
+        );
+        let request = |tenant: Option<&str>, subject: Option<&str>| {
+            let mut request = atlas_http::testing::request("GET", "/books", Value::Null);
+            if let Some(tenant) = tenant {
+                request.extensions_mut().insert(TenantCtx {
+                    id: TenantId::new(tenant).unwrap(),
+                    source: TenantSource::Header,
+                });
+            }
+            match subject {
+                Some(subject) => testing::as_subject(request, subject),
+                None => request,
+            }
+        };
+
+        let status = |tenant, subject| {
+            let router = router.clone();
+            async move { send(&router, request(tenant, subject)).await.0 }
+        };
+        assert_eq!(status(Some("acme"), Some("ann")).await, StatusCode::OK);
+        assert_eq!(
+            status(Some("globex"), Some("ann")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(Some("acme"), Some("bob")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(Some("acme"), None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(None, Some("ann")).await, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,138 @@
+//! Authorization setup for router tests
+//!
+//! Available to this crate's tests and, through the `testing` feature, to other crates'
+//! dev-dependencies.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{header::COOKIE, HeaderName, Request},
+    Extension, Router,
+};
+
+use atlas_kernel::{settings::Settings, AppContext, Module, ModuleRegistry, Resources};
+
+use crate::api_keys::{ApiKeys, API_KEY_HEADER};
+use crate::sessions::Sessions;
+use crate::{Enforcer, PolicyRule, Subject};
+
+/// Role-based model: `p, role, object, action` policies and `g, subject, role` grants
+pub const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act
+"#;
+
+/// Resources holding an enforcer for `policy`, one Casbin CSV rule per line, under `MODEL`
+pub fn resources(policy: &str) -> Arc<Resources> {
+    let resources = Arc::new(Resources::new());
+    resources.insert(Enforcer::from_strs(MODEL, policy).unwrap());
+    resources
+}
+
+/// `router` with the `AppContext` extension guards reach `resources` through
+pub fn with_context(router: Router, resources: Arc<Resources>) -> Router {
+    router.layer(Extension(AppContext::new(
+        Arc::new(Settings::default()),
+        resources,
+    )))
+}
+
+/// `request` as authentication middleware leaves it for `subject`
+pub fn as_subject(mut request: Request<Body>, subject: &str) -> Request<Body> {
+    request
+        .extensions_mut()
+        .insert(Subject(subject.to_string()));
+    request
+}
+
+/// Application modules served as `atlas serve` does, for tests of their guarded routes
+///
+/// The modules sit behind the API key and session modules, and handlers check
+/// permissions against `policy` under `MODEL`.
+pub struct TestApp {
+    pub registry: ModuleRegistry,
+    pub router: Router,
+    settings: Settings,
+}
+
+impl TestApp {
+    /// Initialize `modules` and route them through `atlas_http::build_router`
+    pub async fn new(modules: Vec<Arc<dyn Module>>, policy: &str) -> Self {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core(crate::api_keys::create_module())
+            .unwrap();
+        registry
+            .register_core(crate::sessions::create_module())
+            .unwrap();
+        for module in modules {
+            registry.register_custom(module).unwrap();
+        }
+        registry
+            .resources()
+            .insert(Enforcer::from_strs(MODEL, policy).unwrap());
+
+        let settings = Settings::default();
+        let ctx = registry.init_ctx(Arc::new(settings.clone()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        let router = atlas_http::build_router(&registry, &settings)
+            .await
+            .unwrap();
+        Self {
+            registry,
+            router,
+            settings,
+        }
+    }
+
+    /// Add `policy` rules, e.g. grants to users created after startup
+    pub fn add_policy(&self, policy: &str) {
+        let enforcer = self.registry.resources().require::<Enforcer>().unwrap();
+        for rule in PolicyRule::parse_csv(policy).unwrap() {
+            enforcer.add_rule(&rule).unwrap();
+        }
+    }
+
+    /// `request` with a newly issued API key of `subject`
+    pub async fn with_api_key(&self, mut request: Request<Body>, subject: &str) -> Request<Body> {
+        let keys = self.registry.resources().require::<ApiKeys>().unwrap();
+        let issued = keys.issue("test", subject).await.unwrap();
+        request
+            .headers_mut()
+            .insert(API_KEY_HEADER, issued.token.parse().unwrap());
+        request
+    }
+
+    /// `request` with the cookie and CSRF token of a new session of `subject`
+    pub async fn with_session(&self, mut request: Request<Body>, subject: &str) -> Request<Body> {
+        let sessions = self.registry.resources().require::<Sessions>().unwrap();
+        let (_, token) = sessions.create(subject).await.unwrap();
+        let session = &self.settings.auth.session;
+        let headers = request.headers_mut();
+        headers.insert(
+            COOKIE,
+            format!("{}={}", session.cookie_name, token)
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(
+            HeaderName::try_from(session.csrf.header.as_str()).unwrap(),
+            sessions.csrf_token(&token).parse().unwrap(),
+        );
+        request
+    }
+}
@@ -0,0 +1,31 @@
+//! Random bearer secrets shared by API keys and sessions, and HMAC signing for service
+//! tokens, over the primitives in `atlas_kernel::crypto`
+
+pub(crate) use atlas_kernel::crypto::{constant_time_eq, hmac_sha256, to_hex};
+
+/// A fresh `{prefix}{64 hex chars}` token
+pub(crate) fn generate(prefix: &str) -> anyhow::Result<String> {
+    atlas_kernel::crypto::generate_token(prefix)
+}
+
+/// Digest stored in place of the token
+pub(crate) fn hash(token: &str) -> String {
+    atlas_kernel::crypto::sha256_hex(token.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_unique_and_prefixed() {
+        let first = generate("atlas_").unwrap();
+        let second = generate("atlas_").unwrap();
+
+        assert!(first.starts_with("atlas_"));
+        assert_eq!(first.len(), "atlas_".len() + 64);
+        assert_ne!(first, second);
+        assert_eq!(hash(&first), hash(&first));
+        assert_ne!(hash(&first), hash(&second));
+    }
+}
@@ -0,0 +1,225 @@
+//! `atlas dev`: run the server and restart it when sources or settings change
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use atlas_kernel::reload::non_reloadable_changes;
+use atlas_kernel::settings::Settings;
+use tokio::process::{Child, Command};
+
+/// How long the server gets to shut down before it is killed
+const STOP_GRACE: Duration = Duration::from_secs(15);
+
+/// Directories never worth watching: build output, VCS metadata and other dotted dirs
+const IGNORED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Snapshot of the watched files used to detect edits between polls
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// Every file under `paths`, with its modification time and size
+fn fingerprint(paths: &[PathBuf]) -> Fingerprint {
+    let mut files = Fingerprint::new();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.push((path, metadata.modified().ok(), metadata.len()));
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let is_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false);
+            if is_dir && (name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref())) {
+                continue;
+            }
+            pending.push(entry.path());
+        }
+    }
+    files.sort();
+    files
+}
+
+async fn snapshot(paths: Vec<PathBuf>) -> Fingerprint {
+    tokio::task::spawn_blocking(move || fingerprint(&paths))
+        .await
+        .unwrap_or_default()
+}
+
+/// Why the server is being restarted
+enum Restart {
+    Sources,
+    Settings(Vec<&'static str>),
+}
+
+/// Supervises `cargo run -p atlas-cli -- server`
+///
+/// Source edits rebuild and restart the server. Config edits are hot-reloaded by the
+/// server itself, which `dev` always enables; only changes to settings that need a
+/// restart, such as `server.port`, restart it. A server that fails to build or exits is
+/// started again after the next edit.
+pub struct DevServer {
+    watch: Vec<PathBuf>,
+    interval: Duration,
+    server_flags: Vec<String>,
+    overrides: Vec<(String, String)>,
+}
+
+impl DevServer {
+    pub fn new(
+        watch: Vec<PathBuf>,
+        interval: Duration,
+        server_flags: Vec<String>,
+        overrides: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            watch,
+            interval,
+            server_flags,
+            overrides,
+        }
+    }
+
+    /// Run until interrupted with Ctrl-C
+    pub async fn run(self, mut settings: Settings) -> anyhow::Result<()> {
+        let config_dir = vec![Settings::config_dir()];
+        tracing::info!(
+            watch = ?self.watch,
+            config_dir = %config_dir[0].display(),
+            "watching for changes"
+        );
+
+        let mut sources = snapshot(self.watch.clone()).await;
+        let mut config = snapshot(config_dir.clone()).await;
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            let mut child = Some(self.spawn()?);
+            let restart = loop {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {
+                        if let Some(child) = child {
+                            stop(child).await?;
+                        }
+                        return Ok(());
+                    }
+                    status = wait(&mut child), if child.is_some() => {
+                        child = None;
+                        match status {
+                            Ok(status) => tracing::warn!(%status, "server exited; waiting for changes"),
+                            Err(error) => tracing::error!(error = %format!("{:#}", error), "failed to wait for the server"),
+                        }
+                    }
+                    _ = ticker.tick() => {
+                        let latest = snapshot(self.watch.clone()).await;
+                        if latest != sources {
+                            sources = latest;
+                            break Restart::Sources;
+                        }
+                        let latest = snapshot(config_dir.clone()).await;
+                        if latest == config {
+                            continue;
+                        }
+                        config = latest;
+                        let updated = match Settings::load_with_overrides(&self.overrides) {
+                            Ok(updated) => updated,
+                            Err(error) => {
+                                tracing::error!(error = %format!("{:#}", error), "changed settings do not load");
+                                continue;
+                            }
+                        };
+                        let changed = non_reloadable_changes(&settings, &updated);
+                        settings = updated;
+                        if !changed.is_empty() || child.is_none() {
+                            break Restart::Settings(changed);
+                        }
+                    }
+                }
+            };
+
+            match restart {
+                Restart::Sources => tracing::info!("sources changed; restarting server"),
+                Restart::Settings(fields) => {
+                    tracing::info!(fields = ?fields, "settings changed; restarting server")
+                }
+            }
+            if let Some(child) = child {
+                stop(child).await?;
+            }
+        }
+    }
+
+    fn spawn(&self) -> anyhow::Result<Child> {
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        Command::new(cargo)
+            .args(["run", "--quiet", "-p", "atlas-cli", "--", "server"])
+            .args(&self.server_flags)
+            .env("ATLAS_RELOAD__ENABLED", "true")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("failed to run `cargo run -p atlas-cli -- server`")
+    }
+}
+
+async fn wait(child: &mut Option<Child>) -> std::io::Result<std::process::ExitStatus> {
+    match child {
+        Some(child) => child.wait().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Ask the server to shut down gracefully, killing it after `STOP_GRACE`
+async fn stop(mut child: Child) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: `pid` is our own child, which has not been reaped yet
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGTERM);
+        }
+        if tokio::time::timeout(STOP_GRACE, child.wait()).await.is_ok() {
+            return Ok(());
+        }
+        tracing::warn!(
+            grace_ms = STOP_GRACE.as_millis() as u64,
+            "server did not stop in time; killing it"
+        );
+    }
+    child.kill().await.context("failed to kill the server")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_walks_directories_and_skips_build_output() {
+        let root = std::env::temp_dir().join(format!("atlas-dev-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/modules")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::write(root.join("src/modules/mod.rs"), "pub mod books;\n").unwrap();
+        std::fs::write(root.join("target/debug/atlas"), "binary").unwrap();
+
+        let watch = vec![root.join("src"), root.join("Cargo.toml")];
+        let before = fingerprint(&watch);
+        std::fs::write(root.join("target/debug/atlas"), "rebuilt binary").unwrap();
+        let unchanged = fingerprint(std::slice::from_ref(&root));
+        std::fs::write(
+            root.join("src/modules/mod.rs"),
+            "pub mod books;\npub mod users;\n",
+        )
+        .unwrap();
+        let after = fingerprint(&watch);
+
+        std::fs::remove_dir_all(&root).ok();
+        assert_eq!(before.len(), 1);
+        assert_eq!(unchanged, before);
+        assert_ne!(after, before);
+    }
+}
@@ -0,0 +1,362 @@
+//! Environment checks run by `atlas doctor` before a deployment boots
+
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use atlas_kernel::settings::Settings;
+
+/// How long a reachability check waits for a TCP connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Schemes whose database runs inside the process, with nothing to reach
+const EMBEDDED_SCHEMES: &[&str] = &["mem", "rocksdb", "surrealkv"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pass => " ok ",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+/// Result of one check, with a remediation hint when it did not pass
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Skip,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Continuation lines, such as validation issues, line up under the detail
+        let detail = self.detail.replace('\n', &format!("\n{:18}", ""));
+        write!(f, "[{}] {:<10} {}", self.outcome.label(), self.name, detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n       {:<10} hint: {}", "", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every check, print the results and fail if any check failed
+///
+/// Invalid settings are reported as the first failure and the remaining checks still run
+/// against them, or against the defaults when they do not load at all, so one mistake
+/// does not hide the others.
+pub fn run() -> anyhow::Result<()> {
+    let (settings, mut checks) = config();
+    let runtime = atlas_kernel::runtime::build_runtime(&settings.runtime)?;
+    checks.push(runtime.block_on(database(&settings)));
+    checks.push(casbin(&settings));
+    checks.push(port_free(
+        "http port",
+        &format!("{}:{}", settings.server.host, settings.server.port),
+        "stop the process holding it or set `server.port`",
+    ));
+    checks.push(match &settings.telemetry.prometheus_bind {
+        Some(bind) => port_free(
+            "metrics",
+            bind,
+            "stop the process holding it or change `telemetry.prometheus_bind`",
+        ),
+        None => Check::skip("metrics", "`telemetry.prometheus_bind` is not set"),
+    });
+    checks.push(otlp(&settings));
+
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.outcome == Outcome::Fail)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, checks.len());
+    }
+    Ok(())
+}
+
+/// Settings for the selected environment, even invalid ones, and the config checks
+fn config() -> (Settings, Vec<Check>) {
+    let config_dir = Settings::config_dir();
+    let environment = match Settings::selected_environment() {
+        Ok(environment) => environment,
+        Err(error) => {
+            return (
+                Settings::default(),
+                vec![Check::fail(
+                    "config",
+                    format!("{:#}", error),
+                    "set ATLAS_ENV to local, staging, production or another overlay name",
+                )],
+            )
+        }
+    };
+    let layers = match Settings::layers(&environment, &[]) {
+        Ok(layers) => layers,
+        Err(error) => {
+            return (
+                Settings::default(),
+                vec![Check::fail(
+                    "config",
+                    format!("{:#}", error),
+                    format!("fix the files in {}", config_dir.display()),
+                )],
+            )
+        }
+    };
+    let has_base = layers.iter().any(|layer| {
+        layer
+            .source
+            .starts_with(&config_dir.join("base.").display().to_string())
+    });
+
+    let mut checks = Vec::new();
+    let settings = match Settings::load_unvalidated_for(environment.clone(), &[]) {
+        Ok(settings) => settings,
+        Err(error) => {
+            checks.push(Check::fail(
+                "config",
+                format!("{:#}", error),
+                "run `atlas config show --sources` to see where values come from",
+            ));
+            return (Settings::default(), checks);
+        }
+    };
+    match settings.validate() {
+        Ok(()) => checks.push(Check::pass(
+            "config",
+            format!(
+                "'{}' settings load from {} layer(s)",
+                environment,
+                layers.len()
+            ),
+        )),
+        Err(error) => checks.push(Check::fail(
+            "config",
+            error.to_string(),
+            "run `atlas config validate --sources` to see where the values come from",
+        )),
+    }
+    if !has_base {
+        checks.push(Check::warn(
+            "config",
+            format!("no base config file in {}", config_dir.display()),
+            "run from the project root or set ATLAS_CONFIG_DIR",
+        ));
+    }
+    (settings, checks)
+}
+
+/// The database endpoint accepts connections, and the application's store can read
+/// migrations with its credentials when one is registered
+async fn database(settings: &Settings) -> Check {
+    let endpoint = &settings.database.endpoint;
+    let scheme = endpoint.split("://").next().unwrap_or_default();
+    if EMBEDDED_SCHEMES.contains(&scheme) {
+        return Check::pass("database", format!("{} is embedded", endpoint));
+    }
+    if let Err(error) = reachable(endpoint) {
+        return Check::fail(
+            "database",
+            format!("{} is unreachable: {:#}", endpoint, error),
+            "start SurrealDB (`docker compose up -d`) or fix `database.endpoint`",
+        );
+    }
+
+    let store = crate::build_registry(settings).ok().and_then(|registry| {
+        registry
+            .resources()
+            .get::<Arc<dyn atlas_db::MigrationStore>>()
+            .map(|store| store.as_ref().clone())
+    });
+    let Some(store) = store else {
+        return Check::warn(
+            "database",
+            format!("{} accepts connections", endpoint),
+            "credentials are not verified: no MigrationStore resource is registered",
+        );
+    };
+    match store.applied().await {
+        Ok(applied) => Check::pass(
+            "database",
+            format!(
+                "{} accepts connections; {} migration(s) applied",
+                endpoint,
+                applied.len()
+            ),
+        ),
+        Err(error) => Check::fail(
+            "database",
+            format!("{} rejected the query: {:#}", endpoint, error),
+            format!(
+                "check the credentials and that namespace '{}' / database '{}' exist",
+                settings.database.namespace, settings.database.database
+            ),
+        ),
+    }
+}
+
+/// The casbin model and policy files parse into an enforcer
+fn casbin(settings: &Settings) -> Check {
+    let (model, policy) = (
+        settings.auth.casbin_model_file(),
+        settings.auth.casbin_policy_file(),
+    );
+    match atlas_authz::Enforcer::from_files(&model, &policy) {
+        Ok(_) => Check::pass(
+            "casbin",
+            format!("{} and {} parse", model.display(), policy.display()),
+        ),
+        Err(error) => Check::fail(
+            "casbin",
+            format!("{:#}", error),
+            "fix the files or point `auth.casbin_model_path` / `auth.casbin_policy_path` at them",
+        ),
+    }
+}
+
+/// The OTLP collector accepts connections, when an endpoint is configured
+fn otlp(settings: &Settings) -> Check {
+    let endpoint = settings.telemetry.otlp_endpoint.as_deref();
+    let Some(endpoint) = endpoint.filter(|endpoint| !endpoint.is_empty()) else {
+        return Check::skip("otlp", "`telemetry.otlp_endpoint` is not set");
+    };
+    match reachable(endpoint) {
+        Ok(()) => Check::pass("otlp", format!("{} accepts connections", endpoint)),
+        Err(error) => Check::fail(
+            "otlp",
+            format!("{} is unreachable: {:#}", endpoint, error),
+            "start the collector or fix `telemetry.otlp_endpoint`",
+        ),
+    }
+}
+
+/// Nothing else listens on `address`
+fn port_free(name: &'static str, address: &str, hint: &str) -> Check {
+    match TcpListener::bind(address) {
+        Ok(_) => Check::pass(name, format!("{} is free", address)),
+        Err(error) => Check::fail(name, format!("cannot bind {}: {}", address, error), hint),
+    }
+}
+
+/// Open a TCP connection to the host and port of `endpoint`
+fn reachable(endpoint: &str) -> anyhow::Result<()> {
+    let addresses = socket_addrs(endpoint)?;
+    let mut last_error = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    match last_error {
+        Some(error) => Err(error.into()),
+        None => anyhow::bail!("host does not resolve"),
+    }
+}
+
+/// Addresses of `endpoint`'s host, on its port or the scheme's default one
+fn socket_addrs(endpoint: &str) -> anyhow::Result<Vec<SocketAddr>> {
+    let url = url::Url::parse(endpoint)?;
+    let Some(host) = url.host_str() else {
+        anyhow::bail!("no host");
+    };
+    let port = url
+        .port_or_known_default()
+        .or(match url.scheme() {
+            "grpc" => Some(4317),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("no port and no default for '{}'", url.scheme()))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Ok((host, port).to_socket_addrs()?.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_addrs_fall_back_to_scheme_ports() {
+        assert_eq!(
+            socket_addrs("ws://127.0.0.1:8000").unwrap(),
+            vec!["127.0.0.1:8000".parse().unwrap()]
+        );
+        assert_eq!(
+            socket_addrs("https://127.0.0.1").unwrap(),
+            vec!["127.0.0.1:443".parse().unwrap()]
+        );
+        assert_eq!(
+            socket_addrs("grpc://[::1]").unwrap(),
+            vec!["[::1]:4317".parse().unwrap()]
+        );
+        assert!(socket_addrs("not a url").is_err());
+    }
+
+    #[test]
+    fn test_ports_in_use_fail_with_a_hint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let taken = port_free("http port", &address, "free it");
+        assert_eq!(taken.outcome, Outcome::Fail);
+        assert!(taken.to_string().contains("hint: free it"));
+
+        drop(listener);
+        assert_eq!(port_free("http port", &address, "").outcome, Outcome::Pass);
+        assert!(reachable(&format!("http://{}", address)).is_err());
+    }
+}
@@ -1,5 +1,14 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use anyhow::Context;
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+
+mod dev;
+mod doctor;
+mod generate;
+mod scaffold;
+mod shell;
 
 #[derive(Parser)]
 #[command(name = "atlas")]
@@ -12,51 +21,596 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start the HTTP server
-    Server,
+    Server(ServerArgs),
+    /// Run the server, restarting it when sources or settings that need a restart change
+    Dev(DevArgs),
     /// Migration commands
     Migrate {
         #[command(subcommand)]
         command: MigrateCommands,
     },
+    /// Load the modules' seed data into the configured database
+    Seed(SeedArgs),
+    /// Database commands
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Scaffolding commands
+    New {
+        #[command(subcommand)]
+        command: NewCommands,
+    },
+    /// Code generation commands
+    Generate {
+        #[command(subcommand)]
+        command: GenerateCommands,
+    },
+    /// Module commands; lists the modules when no subcommand is given
+    Modules {
+        #[command(subcommand)]
+        command: Option<ModulesCommands>,
+    },
+    /// Check config, database, casbin files, ports and the OTLP collector before booting
+    Doctor,
+    /// Print a shell completion script, e.g. `atlas completions zsh > ~/.zfunc/_atlas`
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page, or write one per subcommand with `--out-dir`
+    Man(ManArgs),
+    /// Print the version, commit, build time, compiler and features of this binary
+    Version {
+        /// Print JSON, as served at `/version`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Configuration commands
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// OpenAPI commands
+    Openapi {
+        #[command(subcommand)]
+        command: OpenapiCommands,
+    },
+    /// Event commands
+    Events {
+        #[command(subcommand)]
+        command: EventsCommands,
+    },
+    /// Background job commands
+    ///
+    /// Queue commands need a persistent job store; in-memory jobs only exist inside the
+    /// server process.
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommands,
+    },
+}
+
+/// Flags layered on top of every config source, for container entrypoints and quick tests
+#[derive(Args)]
+struct ServerArgs {
+    /// Environment whose overlay to load, instead of `ATLAS_ENV`
+    #[arg(long)]
+    env: Option<atlas_kernel::settings::Environment>,
+    /// Directory holding the config files, instead of `ATLAS_CONFIG_DIR` or `./config`
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+    /// Override `server.host`
+    #[arg(long)]
+    host: Option<String>,
+    /// Override `server.port`
+    #[arg(long)]
+    port: Option<u16>,
+    /// Override `database.endpoint`
+    #[arg(long)]
+    db_endpoint: Option<String>,
+    /// Override `database.namespace`
+    #[arg(long)]
+    db_namespace: Option<String>,
+    /// Override `database.database`
+    #[arg(long)]
+    db_database: Option<String>,
+}
+
+#[derive(Args)]
+struct DevArgs {
+    #[command(flatten)]
+    server: ServerArgs,
+    /// Files and directories whose changes rebuild and restart the server
+    #[arg(long, default_values = ["src", "crates", "Cargo.toml"])]
+    watch: Vec<PathBuf>,
+    /// How often to look for changes, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    interval_ms: u64,
+}
+
+impl ServerArgs {
+    /// Point `ATLAS_ENV` and `ATLAS_CONFIG_DIR` at the flags' values
+    ///
+    /// Set on the process rather than passed along, so config reloads read the same
+    /// files. Must run before the runtime starts any threads.
+    fn apply_environment(&self) {
+        if let Some(environment) = &self.env {
+            std::env::set_var("ATLAS_ENV", environment.as_str());
+        }
+        if let Some(config_dir) = &self.config_dir {
+            std::env::set_var("ATLAS_CONFIG_DIR", config_dir);
+        }
+    }
+
+    /// Settings keys and values for every flag that was passed
+    fn overrides(&self) -> Vec<(String, String)> {
+        [
+            ("server.host", self.host.clone()),
+            ("server.port", self.port.map(|port| port.to_string())),
+            ("database.endpoint", self.db_endpoint.clone()),
+            ("database.namespace", self.db_namespace.clone()),
+            ("database.database", self.db_database.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+        .collect()
+    }
+
+    /// The override flags again, for a child `atlas server`
+    ///
+    /// `--env` and `--config-dir` reach it through the variables `apply_environment` set.
+    fn override_flags(&self) -> Vec<String> {
+        [
+            ("--host", self.host.clone()),
+            ("--port", self.port.map(|port| port.to_string())),
+            ("--db-endpoint", self.db_endpoint.clone()),
+            ("--db-namespace", self.db_namespace.clone()),
+            ("--db-database", self.db_database.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(flag, value)| value.map(|value| [flag.to_string(), value]))
+        .flatten()
+        .collect()
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective settings as JSON with secrets masked
+    Show(ConfigArgs),
+    /// Load and validate the settings, listing every problem and the layers read
+    Validate(ConfigArgs),
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    /// Environment whose overlay to load instead of the one `ATLAS_ENV` selects
+    #[arg(long)]
+    env: Option<atlas_kernel::settings::Environment>,
+    /// Include the files and variables the settings were layered from
+    #[arg(long)]
+    sources: bool,
 }
 
 #[derive(Subcommand)]
 enum MigrateCommands {
     /// Plan migrations (show what would be applied)
     Plan,
-    /// Apply migrations
+    /// Print every migration per module with its state and when it was applied
+    ///
+    /// Migrations edited after they were applied are flagged as `changed`.
+    Status,
+    /// Apply pending migrations and print what each module applied
+    ///
+    /// Needs a `MigrationStore` connected to the database.
     Up,
+    /// Revert the most recently applied migrations with their down scripts
+    Down(DownArgs),
+    /// Revert the most recently applied migration and apply pending migrations again
+    Redo(RedoArgs),
+    /// Add a numbered migration, with a down script stub, to an application module
+    Create(CreateMigrationArgs),
+}
+
+#[derive(Subcommand)]
+enum ModulesCommands {
+    /// List every module with its version, status, routes, migrations and dependencies
+    ///
+    /// Modules disabled in settings or meant for other environments are listed too.
+    List(ListModulesArgs),
+}
+
+#[derive(Args, Default)]
+struct ListModulesArgs {
+    /// Print JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+/// One module in `atlas modules list`
+#[derive(serde::Serialize)]
+struct ModuleListing {
+    #[serde(flatten)]
+    info: atlas_kernel::ModuleInfo,
+    /// `enabled`, or why the module was not registered
+    status: String,
+    /// Operations documented in the OpenAPI fragment; unknown for skipped modules
+    route_count: Option<usize>,
+    migrations: Option<usize>,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Run SurrealQL and pretty-print the results, or open a shell when none is given
+    Query {
+        /// Statements to run, e.g. "SELECT * FROM book LIMIT 5;"
+        surql: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct SeedArgs {
+    /// Modules to seed; every module when none are given
+    modules: Vec<String>,
+    /// Empty the tables the seeds write to first (local environment only)
+    #[arg(long)]
+    reset: bool,
+}
+
+#[derive(Args)]
+struct ManArgs {
+    /// Directory to write `atlas.1` and a page per subcommand into, for packaging
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum NewCommands {
+    /// Scaffold an application module with routes, models, OpenAPI and an initial migration
+    Module(NewModuleArgs),
+}
+
+#[derive(Subcommand)]
+enum GenerateCommands {
+    /// Export the merged OpenAPI spec and generate a typed client from it
+    ///
+    /// Regenerate after adding or changing modules; the spec is built from every
+    /// registered module, so their routes reach the client without further wiring.
+    Client(GenerateClientArgs),
+}
+
+#[derive(Args)]
+struct GenerateClientArgs {
+    /// Language of the client
+    #[arg(long, value_enum, default_value = "ts")]
+    lang: generate::ClientLang,
+    /// Directory the spec and the client are written to
+    #[arg(long, default_value = "sdk")]
+    out: PathBuf,
+    /// openapi-generator compatible command to run
+    #[arg(long, default_value = generate::DEFAULT_GENERATOR)]
+    generator: String,
+}
+
+#[derive(Args)]
+struct NewModuleArgs {
+    /// Name of the module, e.g. `reviews`
+    name: String,
+    /// Directory holding the application's modules
+    #[arg(long, default_value = "src/modules")]
+    modules_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct CreateMigrationArgs {
+    /// Module to add the migration to
+    module: String,
+    /// Name of the migration, e.g. `add_isbn`
+    name: String,
+    /// Directory holding the application's modules
+    #[arg(long, default_value = "src/modules")]
+    modules_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct DownArgs {
+    /// Migrations to revert
+    #[arg(long, default_value_t = 1)]
+    steps: usize,
+    /// Allow reverting in production
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args)]
+struct RedoArgs {
+    /// Allow redoing in production
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Subcommand)]
+enum EventsCommands {
+    /// Print every event modules declare, with its payload schema versions, as JSON
+    Catalog,
+    /// Re-run dead-lettered events through the handlers that failed them
+    ///
+    /// Needs a persistent dead letter store; replayed events are removed once handled.
+    Replay(ReplayArgs),
+    /// Emit a named event, running the modules' handlers and sending it through the
+    /// configured transports
+    Publish(PublishEventArgs),
+    /// Print events as JSON lines until interrupted
+    ///
+    /// Events from other processes only arrive through a Kafka or Redis transport.
+    Tail(TailEventsArgs),
+}
+
+#[derive(Args)]
+struct PublishEventArgs {
+    /// Name of the event, e.g. `books.created`
+    name: String,
+    /// JSON payload; declared events must match their schema
+    #[arg(long, default_value = "{}", value_parser = parse_json)]
+    payload: serde_json::Value,
+    /// Declared schema version to emit instead of the latest
+    #[arg(long)]
+    version: Option<u32>,
+    /// How long handlers and transports get to process the event before exiting, in
+    /// milliseconds
+    #[arg(long, default_value_t = 500)]
+    settle_ms: u64,
+}
+
+#[derive(Args)]
+struct TailEventsArgs {
+    /// Only events with this name, or starting with it when it ends in `*`; repeatable
+    #[arg(long = "event")]
+    events: Vec<String>,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// Only events with this name
+    #[arg(long)]
+    event: Option<String>,
+    /// Only events this module's handler failed
+    #[arg(long)]
+    module: Option<String>,
+    /// Only events that failed at or after this RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    since: Option<time::OffsetDateTime>,
+    /// Only events that failed before this RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    until: Option<time::OffsetDateTime>,
+    /// Most dead letters to replay
+    #[arg(long, default_value_t = 1000)]
+    limit: usize,
+    /// Print the matching dead letters without replaying them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Subcommand)]
+enum JobsCommands {
+    /// Print jobs as JSON, earliest run time first
+    List(ListJobsArgs),
+    /// Queue a failed or cancelled job to run now with a fresh set of attempts
+    Retry { id: String },
+    /// Keep a queued job from running
+    Cancel {
+        id: String,
+        /// Also cancel a running job, e.g. one stuck in its handler; its outcome is discarded
+        #[arg(long)]
+        force: bool,
+    },
+    /// Run a scheduled job now, by `module.job` or a job name only one module uses
+    Run { name: String },
+    /// Print queue depth and how long the oldest due job has waited, as JSON
+    Stats,
+}
+
+#[derive(Args)]
+struct ListJobsArgs {
+    /// Only jobs in this state: queued, running, succeeded, failed or cancelled
+    #[arg(long, value_parser = parse_job_state)]
+    state: Option<atlas_jobs::queue::JobState>,
+    /// Only jobs of this kind
+    #[arg(long)]
+    kind: Option<String>,
+    /// Most jobs to print
+    #[arg(long, default_value_t = atlas_jobs::queue::JobQuery::DEFAULT_LIMIT)]
+    limit: usize,
+}
+
+fn parse_job_state(value: &str) -> Result<atlas_jobs::queue::JobState, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown job state '{}'", value))
+}
+
+fn parse_json(value: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(value).map_err(|error| format!("expected JSON: {}", error))
+}
+
+fn parse_time(value: &str) -> Result<time::OffsetDateTime, String> {
+    time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc3339).map_err(
+        |error| {
+            format!(
+                "expected an RFC 3339 time such as 2024-05-01T00:00:00Z: {}",
+                error
+            )
+        },
+    )
+}
+
+#[derive(Subcommand)]
+enum OpenapiCommands {
+    /// Write the merged OpenAPI spec to a file without starting the server
+    Export {
+        /// Destination file for the spec
+        #[arg(short, long, default_value = "openapi.json")]
+        output: PathBuf,
+        /// Output format; inferred from the file extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<SpecFormat>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SpecFormat {
+    Json,
+    Yaml,
+}
+
+impl SpecFormat {
+    /// Pick a format from the output file extension, defaulting to JSON
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::try_init().ok();
+fn main() -> anyhow::Result<()> {
+    // Logs go to stderr so command output such as JSON listings stays pipeable
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .try_init()
+        .ok();
 
     let cli = Cli::parse();
 
-    let settings = atlas_kernel::settings::Settings::load()
-        .with_context(|| "failed to load ATLAS settings")?;
+    // Config and doctor load settings themselves so they can report what fails to load;
+    // completions, man pages and the version need no settings at all
+    let command = match cli.command {
+        Commands::Config { command } => return run_config(command),
+        Commands::Doctor => return doctor::run(),
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "atlas", &mut std::io::stdout());
+            return Ok(());
+        }
+        Commands::Man(args) => return write_man_pages(args.out_dir.as_deref()),
+        Commands::Version { json } => {
+            let build = atlas_kernel::BuildInfo::current();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&build)?);
+            } else {
+                println!("{}", build);
+            }
+            return Ok(());
+        }
+        command => command,
+    };
+
+    let overrides = match &command {
+        Commands::Server(args) | Commands::Dev(DevArgs { server: args, .. }) => {
+            args.apply_environment();
+            args.overrides()
+        }
+        _ => Vec::new(),
+    };
+    // Spec export only reads module metadata, so it works without the files and services
+    // that validation checks for
+    let exports_spec = matches!(
+        command,
+        Commands::Openapi { .. } | Commands::Generate { .. }
+    );
+    let settings = if exports_spec {
+        atlas_kernel::settings::Settings::load_unvalidated_for(
+            atlas_kernel::settings::Settings::selected_environment()?,
+            &overrides,
+        )
+    } else {
+        atlas_kernel::settings::Settings::load_with_overrides(&overrides)
+    }
+    .with_context(|| "failed to load ATLAS settings")?;
+
+    // Settings decide the runtime's shape, so it is built only once they are loaded
+    atlas_kernel::runtime::build_runtime(&settings.runtime)?
+        .block_on(run(command, settings, overrides))
+}
+
+/// Render the man page to stdout, or every page into `out_dir`
+fn write_man_pages(out_dir: Option<&Path>) -> anyhow::Result<()> {
+    let command = Cli::command();
+    match out_dir {
+        Some(out_dir) => {
+            std::fs::create_dir_all(out_dir)
+                .with_context(|| format!("failed to create {}", out_dir.display()))?;
+            clap_mangen::generate_to(command, out_dir)
+                .with_context(|| format!("failed to write man pages to {}", out_dir.display()))?;
+            println!("wrote man pages to {}", out_dir.display());
+        }
+        None => clap_mangen::Man::new(command)
+            .render(&mut std::io::stdout())
+            .context("failed to render man page")?,
+    }
+    Ok(())
+}
+
+fn run_config(command: ConfigCommands) -> anyhow::Result<()> {
+    use atlas_kernel::settings::Settings;
+
+    match command {
+        ConfigCommands::Show(args) => {
+            let environment = match args.env {
+                Some(environment) => environment,
+                None => Settings::selected_environment()?,
+            };
+            let settings = Settings::load_for(environment.clone(), &[])
+                .with_context(|| format!("failed to load '{}' settings", environment))?;
+            let rendered = if args.sources {
+                serde_json::json!({
+                    "layers": Settings::layers(&environment, &[])?,
+                    "settings": settings.to_redacted_json(),
+                })
+            } else {
+                settings.to_redacted_json()
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&rendered).context("failed to serialize settings")?
+            );
+        }
+        ConfigCommands::Validate(args) => {
+            let environment = match args.env {
+                Some(environment) => environment,
+                None => Settings::selected_environment()?,
+            };
+            if args.sources {
+                for layer in Settings::layers(&environment, &[])? {
+                    println!("{} ({} keys)", layer.source, layer.keys.len());
+                    for key in &layer.keys {
+                        println!("  {}", key);
+                    }
+                }
+            }
+            Settings::load_for(environment.clone(), &[])
+                .with_context(|| format!("'{}' settings are invalid", environment))?;
+            println!("'{}' settings are valid", environment);
+        }
+    }
+    Ok(())
+}
 
-    match cli.command {
-        Commands::Server => {
+async fn run(
+    command: Commands,
+    settings: atlas_kernel::settings::Settings,
+    overrides: Vec<(String, String)>,
+) -> anyhow::Result<()> {
+    match command {
+        Commands::Server(_) => {
             tracing::info!(
                 env = ?settings.environment,
                 "starting ATLAS server"
             );
 
-            // Create module registry
-            let mut registry = atlas_kernel::registry::ModuleRegistry::new();
-
-            // Register core modules first (excluding HTTP router)
-            // TODO: Register core modules like telemetry, db, authz, events
-
-            // Register custom modules
-            atlas_app::modules::register_all(&mut registry);
+            let registry = build_registry(&settings)?;
 
             // Initialize all modules in proper order
-            let init_ctx = atlas_kernel::module::InitCtx {
-                settings: &settings,
-            };
+            let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
 
             // Initialize core modules first (excluding HTTP)
             registry
@@ -76,24 +630,792 @@ async fn main() -> anyhow::Result<()> {
                 .await
                 .context("failed to start core modules")?;
 
-            // Start custom modules
-            registry
-                .start_custom_modules(&init_ctx)
-                .await
-                .context("failed to start custom modules")?;
+            // Start custom modules, stopping core modules again if any fails
+            if let Err(error) = registry.start_custom_modules(&init_ctx).await {
+                registry.stop_core_modules().await.ok();
+                return Err(error).context("failed to start custom modules");
+            }
+
+            let startup = registry.startup_report();
+            tracing::info!(
+                total_ms = startup.total_ms(),
+                slow = ?startup.slow(),
+                "modules started: {}",
+                startup
+            );
+
+            // Push reloadable config changes to modules while the server runs
+            let registry = Arc::new(registry);
+            let watcher = settings.reload.enabled.then(|| {
+                atlas_kernel::reload::ConfigWatcher::from_settings(&settings)
+                    .with_overrides(overrides.clone())
+                    .spawn(registry.clone(), settings.clone())
+            });
 
             // Now start HTTP server with fully initialized modules
-            atlas_http::start_server(&registry, &settings).await?;
+            let served = atlas_http::start_server(&registry, &settings).await;
+            if let Some(watcher) = watcher {
+                watcher.abort();
+            }
+
+            // Stop modules within their shutdown budgets, even if serving failed
+            let report = atlas_kernel::ShutdownCoordinator::from_settings(&settings.shutdown)
+                .shutdown(&registry)
+                .await;
+            if !report.is_clean() {
+                tracing::warn!(
+                    timed_out = ?report.timed_out(),
+                    failed = ?report.failed(),
+                    "shutdown finished with modules that did not stop cleanly"
+                );
+            }
+            served?;
+        }
+        Commands::Dev(args) => {
+            dev::DevServer::new(
+                args.watch,
+                std::time::Duration::from_millis(args.interval_ms),
+                args.server.override_flags(),
+                overrides,
+            )
+            .run(settings)
+            .await?;
         }
         Commands::Migrate { command } => match command {
             MigrateCommands::Plan => {
                 tracing::info!("migration planning not yet implemented");
             }
-            MigrateCommands::Up => {
-                tracing::info!("migration execution not yet implemented");
+            MigrateCommands::Status => migration_status(&settings).await?,
+            MigrateCommands::Up => migrate_up(&settings).await?,
+            MigrateCommands::Down(args) => {
+                migrate_down(&settings, args.steps, args.force).await?;
+            }
+            MigrateCommands::Redo(args) => {
+                migrate_down(&settings, 1, args.force).await?;
+                migrate_up(&settings).await?;
+            }
+            MigrateCommands::Create(args) => {
+                let created =
+                    scaffold::create_migration(&args.modules_dir, &args.module, &args.name)?;
+                println!("created {}", created.up.display());
+                println!("created {}", created.down.display());
+                println!("added {} to {}", created.id, created.source.display());
+            }
+        },
+        Commands::Seed(args) => seed(&settings, args).await?,
+        Commands::Db { command } => match command {
+            DbCommands::Query { surql } => {
+                let registry = build_registry(&settings)?;
+                let Some(executor) = registry
+                    .resources()
+                    .get::<Arc<dyn atlas_db::QueryExecutor>>()
+                else {
+                    anyhow::bail!(
+                        "no QueryExecutor resource is registered; there is no database to query"
+                    );
+                };
+                let executor = executor.as_ref().clone();
+                let mut stdout = std::io::stdout();
+                match surql {
+                    Some(surql) => shell::query_once(&*executor, &surql, &mut stdout)
+                        .await
+                        .context("query failed")?,
+                    None => shell::repl(&*executor, std::io::stdin().lock(), &mut stdout).await?,
+                }
+            }
+        },
+        Commands::New { command } => match command {
+            NewCommands::Module(args) => {
+                let created = scaffold::create_module(&args.modules_dir, &args.name)?;
+                for file in &created.files {
+                    println!("created {}", file.display());
+                }
+                println!("declared {} in {}", args.name, created.registry.display());
+            }
+        },
+        Commands::Generate { command } => match command {
+            GenerateCommands::Client(args) => {
+                let registry = build_registry(&settings)?;
+                let spec = atlas_http::openapi::merged_spec(&registry, &settings.openapi);
+                let spec_path =
+                    generate::generate_client(&spec, args.lang, &args.out, &args.generator)?;
+                println!(
+                    "generated {} client in {} from {}",
+                    args.lang.generator_name(),
+                    args.out.display(),
+                    spec_path.display()
+                );
+            }
+        },
+        Commands::Openapi { command } => match command {
+            OpenapiCommands::Export { output, format } => {
+                let registry = build_registry(&settings)?;
+                let spec = atlas_http::openapi::merged_spec(&registry, &settings.openapi);
+
+                let format = format.unwrap_or_else(|| SpecFormat::from_path(&output));
+                let rendered = match format {
+                    SpecFormat::Json => serde_json::to_string_pretty(&spec)
+                        .context("failed to serialize OpenAPI spec as JSON")?,
+                    SpecFormat::Yaml => serde_yaml::to_string(&spec)
+                        .context("failed to serialize OpenAPI spec as YAML")?,
+                };
+
+                std::fs::write(&output, rendered)
+                    .with_context(|| format!("failed to write {}", output.display()))?;
+
+                tracing::info!(path = %output.display(), "exported OpenAPI spec");
+            }
+        },
+        Commands::Config { .. }
+        | Commands::Doctor
+        | Commands::Completions { .. }
+        | Commands::Man(_)
+        | Commands::Version { .. } => unreachable!("handled before settings load"),
+        Commands::Events { command } => match command {
+            EventsCommands::Catalog => {
+                let registry = build_registry(&settings)?;
+                let catalog = atlas_events::EventCatalog::new(&registry.collect_event_schemas())
+                    .context("modules declare conflicting events")?;
+                let rendered = serde_json::to_string_pretty(&catalog)
+                    .context("failed to serialize event catalog")?;
+                println!("{}", rendered);
             }
+            EventsCommands::Replay(args) => replay_events(&settings, args).await?,
+            EventsCommands::Publish(args) => publish_event(&settings, args).await?,
+            EventsCommands::Tail(args) => tail_events(&settings, args).await?,
         },
+        Commands::Jobs { command } => manage_jobs(&settings, command).await?,
+        Commands::Modules { command } => {
+            match command.unwrap_or(ModulesCommands::List(ListModulesArgs::default())) {
+                ModulesCommands::List(args) => list_modules(&settings, args)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print registered and skipped modules with what each contributes
+fn list_modules(
+    settings: &atlas_kernel::settings::Settings,
+    args: ListModulesArgs,
+) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    let mut listings: Vec<ModuleListing> = registry
+        .module_info()
+        .into_iter()
+        .map(|info| {
+            let module = registry.get_module(info.name);
+            ModuleListing {
+                status: "enabled".to_string(),
+                route_count: module
+                    .map(|module| atlas_http::meta::documented_route_count(module.as_ref())),
+                migrations: module.map(|module| module.migrations().len()),
+                info,
+            }
+        })
+        .collect();
+    listings.extend(
+        registry
+            .skipped_modules()
+            .iter()
+            .map(|skipped| ModuleListing {
+                info: skipped.info.clone(),
+                status: skipped.reason.to_string(),
+                route_count: None,
+                migrations: None,
+            }),
+    );
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&listings).context("failed to serialize modules")?
+        );
+        return Ok(());
+    }
+    let count = |value: Option<usize>| value.map_or("-".to_string(), |value| value.to_string());
+    println!(
+        "{:<16} {:<7} {:<8} {:<9} {:>6} {:>10}  {:<24} DESCRIPTION",
+        "NAME", "KIND", "VERSION", "STATUS", "ROUTES", "MIGRATIONS", "DEPENDS ON"
+    );
+    for listing in &listings {
+        let depends_on = match listing.info.depends_on.as_slice() {
+            [] => "-".to_string(),
+            names => names.join(","),
+        };
+        println!(
+            "{:<16} {:<7} {:<8} {:<9} {:>6} {:>10}  {:<24} {}",
+            listing.info.name,
+            listing.info.kind,
+            listing.info.version.unwrap_or("-"),
+            listing.status,
+            count(listing.route_count),
+            count(listing.migrations),
+            depends_on,
+            listing.info.description.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Migrator for the application's database, from its `MigrationStore` resource
+fn migrator(
+    registry: &atlas_kernel::registry::ModuleRegistry,
+) -> anyhow::Result<atlas_db::Migrator> {
+    let Some(store) = registry
+        .resources()
+        .get::<Arc<dyn atlas_db::MigrationStore>>()
+    else {
+        anyhow::bail!("no MigrationStore resource is registered; there is no database to migrate");
+    };
+    Ok(atlas_db::Migrator::new(
+        store.as_ref().clone(),
+        Arc::new(atlas_kernel::clock::SystemClock),
+    ))
+}
+
+/// Print the state of every known migration as a table
+async fn migration_status(settings: &atlas_kernel::settings::Settings) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    let statuses = migrator(&registry)?
+        .status(&registry)
+        .await
+        .context("failed to read migration status")?;
+
+    println!(
+        "{:<16} {:<40} {:<9} APPLIED AT",
+        "MODULE", "MIGRATION", "STATE"
+    );
+    for status in &statuses {
+        let applied_at = status
+            .applied_at
+            .and_then(|at| {
+                at.format(&time::format_description::well_known::Rfc3339)
+                    .ok()
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let flag = match status.state {
+            atlas_db::MigrationState::Changed => "  <- script changed since it was applied",
+            atlas_db::MigrationState::Unknown => "  <- no module declares this migration",
+            _ => "",
+        };
+        println!(
+            "{:<16} {:<40} {:<9} {}{}",
+            status.module, status.id, status.state, applied_at, flag
+        );
+    }
+    let changed = statuses
+        .iter()
+        .filter(|status| status.state == atlas_db::MigrationState::Changed)
+        .count();
+    if changed > 0 {
+        eprintln!(
+            "warning: {} applied migrations changed since they ran; `atlas migrate up` refuses to run until they are restored",
+            changed
+        );
+    }
+    Ok(())
+}
+
+/// Apply every registered module's pending migrations to the application's database
+async fn migrate_up(settings: &atlas_kernel::settings::Settings) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    let report = migrator(&registry)?
+        .up(&registry)
+        .await
+        .context("failed to apply migrations")?;
+    print!("{}", report);
+    if report.applied() == 0 {
+        println!("database is up to date");
+    } else {
+        println!(
+            "applied {} migrations in {}ms",
+            report.applied(),
+            report.total_ms()
+        );
+    }
+    Ok(())
+}
+
+/// Revert the `steps` latest migrations, refusing in production unless `force` is set
+async fn migrate_down(
+    settings: &atlas_kernel::settings::Settings,
+    steps: usize,
+    force: bool,
+) -> anyhow::Result<()> {
+    if settings.environment == atlas_kernel::settings::Environment::Production && !force {
+        anyhow::bail!("refusing to revert migrations in production; pass --force to revert anyway");
+    }
+    let registry = build_registry(settings)?;
+    let reverted = migrator(&registry)?
+        .down(&registry, steps)
+        .await
+        .context("failed to revert migrations")?;
+    for record in &reverted {
+        println!("reverted {:<16} {}", record.module, record.id);
+    }
+    if reverted.is_empty() {
+        println!("no applied migrations to revert");
+    }
+    Ok(())
+}
+
+/// Run the seeds of the selected modules, refusing `--reset` outside local environments
+async fn seed(settings: &atlas_kernel::settings::Settings, args: SeedArgs) -> anyhow::Result<()> {
+    if args.reset && settings.environment != atlas_kernel::settings::Environment::Local {
+        anyhow::bail!(
+            "refusing to truncate tables in the '{}' environment; --reset is for local databases",
+            settings.environment
+        );
+    }
+    let registry = build_registry(settings)?;
+    let Some(store) = registry.resources().get::<Arc<dyn atlas_db::SeedStore>>() else {
+        anyhow::bail!("no SeedStore resource is registered; there is no database to seed");
+    };
+    let report = atlas_db::Seeder::new(store.as_ref().clone())
+        .seed(&registry, &args.modules, args.reset)
+        .await
+        .context("failed to seed the database")?;
+    print!("{}", report);
+    println!("ran {} seeds", report.seeded.len());
+    Ok(())
+}
+
+/// Start the modules without serving HTTP and re-drive the matching dead letters
+async fn replay_events(
+    settings: &atlas_kernel::settings::Settings,
+    args: ReplayArgs,
+) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    if !registry
+        .resources()
+        .contains::<Arc<dyn atlas_events::DeadLetterStore>>()
+    {
+        anyhow::bail!(
+            "no DeadLetterStore resource is registered; in-memory dead letters only exist inside the server process"
+        );
+    }
+    let query = atlas_events::DeadLetterQuery {
+        event: args.event,
+        module: args.module,
+        since: args.since,
+        until: args.until,
+        limit: Some(args.limit),
+    };
+
+    let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
+    registry
+        .init_core_modules(&init_ctx)
+        .await
+        .context("failed to initialize core modules")?;
+    registry
+        .init_custom_modules(&init_ctx)
+        .await
+        .context("failed to initialize custom modules")?;
+    let delivery = registry.resources().require::<atlas_events::Delivery>()?;
+
+    let rendered = if args.dry_run {
+        let letters = delivery.store().list(&query).await?;
+        serde_json::to_string_pretty(&letters).context("failed to serialize dead letters")?
+    } else {
+        // Starting the core modules hands every module's event handlers to the delivery
+        registry
+            .start_core_modules(&init_ctx)
+            .await
+            .context("failed to start core modules")?;
+        let replayed = delivery.replay(&query).await;
+        registry.stop_core_modules().await.ok();
+        let report = replayed.context("failed to replay dead letters")?;
+        tracing::info!(
+            delivered = report.delivered,
+            failed = report.failed.len(),
+            skipped = report.skipped.len(),
+            "replayed dead letters"
+        );
+        serde_json::to_string_pretty(&report).context("failed to serialize replay report")?
+    };
+    println!("{}", rendered);
+    Ok(())
+}
+
+/// Start the modules without serving HTTP, emit one event and give it time to be handled
+async fn publish_event(
+    settings: &atlas_kernel::settings::Settings,
+    args: PublishEventArgs,
+) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
+    registry
+        .init_core_modules(&init_ctx)
+        .await
+        .context("failed to initialize core modules")?;
+    registry
+        .init_custom_modules(&init_ctx)
+        .await
+        .context("failed to initialize custom modules")?;
+    // Starting the core modules loads the catalog and subscribes handlers and transports
+    registry
+        .start_core_modules(&init_ctx)
+        .await
+        .context("failed to start core modules")?;
+    let bus = registry.resources().require::<atlas_events::EventBus>()?;
+
+    let emitted = match args.version {
+        Some(version) => bus.emit_version(&args.name, version, &args.payload),
+        None => bus.emit(&args.name, &args.payload),
+    };
+    if emitted.is_ok() {
+        tokio::time::sleep(std::time::Duration::from_millis(args.settle_ms)).await;
+    }
+    registry.stop_core_modules().await.ok();
+    let subscribers = emitted.with_context(|| format!("failed to emit '{}'", args.name))?;
+    println!("published {} to {} subscriber(s)", args.name, subscribers);
+    Ok(())
+}
+
+/// Start the core modules without serving HTTP and print events until Ctrl-C
+async fn tail_events(
+    settings: &atlas_kernel::settings::Settings,
+    args: TailEventsArgs,
+) -> anyhow::Result<()> {
+    if !settings.events.kafka.enabled && !settings.events.redis.enabled {
+        tracing::warn!(
+            "no event transport is enabled; only events emitted by this process are shown"
+        );
+    }
+    let registry = build_registry(settings)?;
+    let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
+    registry
+        .init_core_modules(&init_ctx)
+        .await
+        .context("failed to initialize core modules")?;
+    let bus = registry.resources().require::<atlas_events::EventBus>()?;
+    let mut events = bus.subscribe::<atlas_events::NamedEvent>();
+    registry
+        .start_core_modules(&init_ctx)
+        .await
+        .context("failed to start core modules")?;
+
+    let tailed = async {
+        while let Some(event) = events.recv().await {
+            if !event_matches(&args.events, &event.name) {
+                continue;
+            }
+            let line = serde_json::json!({
+                "event": &*event.name,
+                "version": event.version,
+                "origin": event.origin,
+                "tenant": event.tenant.as_ref().map(|tenant| tenant.as_str()),
+                "payload": &*event.payload,
+            });
+            println!("{}", line);
+        }
+    };
+    tokio::select! {
+        _ = tailed => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    registry.stop_core_modules().await.ok();
+    Ok(())
+}
+
+/// Whether `name` passes the `--event` filters; every event does without filters
+fn event_matches(filters: &[String], name: &str) -> bool {
+    filters.is_empty()
+        || filters.iter().any(|filter| match filter.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => filter == name,
+        })
+}
+
+/// Initialize the modules without serving HTTP and act on the persistent job store
+async fn manage_jobs(
+    settings: &atlas_kernel::settings::Settings,
+    command: JobsCommands,
+) -> anyhow::Result<()> {
+    let registry = build_registry(settings)?;
+    if let JobsCommands::Run { name } = command {
+        return run_scheduled_job(settings, registry, &name).await;
+    }
+    if !registry
+        .resources()
+        .contains::<Arc<dyn atlas_jobs::queue::JobStore>>()
+    {
+        anyhow::bail!(
+            "no JobStore resource is registered; in-memory jobs only exist inside the server process"
+        );
+    }
+    let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
+    registry
+        .init_core_modules(&init_ctx)
+        .await
+        .context("failed to initialize core modules")?;
+    let queue = registry.resources().require::<atlas_jobs::Queue>()?;
+
+    let rendered = match command {
+        JobsCommands::List(args) => {
+            let query = atlas_jobs::queue::JobQuery {
+                state: args.state,
+                kind: args.kind,
+                limit: Some(args.limit),
+            };
+            let jobs = queue.store().list(&query).await?;
+            serde_json::to_string_pretty(&jobs).context("failed to serialize jobs")?
+        }
+        JobsCommands::Retry { id } => {
+            let job = queue.retry(&id).await?;
+            serde_json::to_string_pretty(&job).context("failed to serialize job")?
+        }
+        JobsCommands::Cancel { id, force } => {
+            let job = match queue.cancel(&id).await {
+                Err(atlas_jobs::JobError::State {
+                    state: atlas_jobs::queue::JobState::Running,
+                    ..
+                }) if force => queue.cancel_running(&id).await?,
+                cancelled => cancelled?,
+            };
+            serde_json::to_string_pretty(&job).context("failed to serialize job")?
+        }
+        JobsCommands::Run { .. } => unreachable!("handled before the job store check"),
+        JobsCommands::Stats => {
+            let stats = queue.stats().await?;
+            serde_json::to_string_pretty(&stats).context("failed to serialize queue stats")?
+        }
+    };
+    println!("{}", rendered);
+    Ok(())
+}
+
+/// Initialize the modules without serving HTTP and run one scheduled job in this process
+///
+/// Jobs disabled under `jobs.disabled` can still be run by hand.
+async fn run_scheduled_job(
+    settings: &atlas_kernel::settings::Settings,
+    registry: atlas_kernel::registry::ModuleRegistry,
+    name: &str,
+) -> anyhow::Result<()> {
+    let init_ctx = registry.init_ctx(Arc::new(settings.clone()));
+    registry
+        .init_core_modules(&init_ctx)
+        .await
+        .context("failed to initialize core modules")?;
+    registry
+        .init_custom_modules(&init_ctx)
+        .await
+        .context("failed to initialize custom modules")?;
+    let scheduler =
+        atlas_jobs::Scheduler::new(registry.collect_jobs(), &[], init_ctx.clock.clone())?;
+
+    let status = scheduler.run_now(name).await?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&status).context("failed to serialize job status")?
+    );
+    if let Some(error) = status.stats.last_error {
+        anyhow::bail!("job '{}.{}' failed: {}", status.module, status.name, error);
+    }
+    Ok(())
+}
+
+/// Create the module registry with every module registered but not yet initialized
+fn build_registry(
+    settings: &atlas_kernel::settings::Settings,
+) -> anyhow::Result<atlas_kernel::registry::ModuleRegistry> {
+    let mut registry = atlas_kernel::registry::ModuleRegistry::with_settings(settings);
+    atlas_db::connect(registry.resources(), &settings.database)
+        .context("failed to connect to the database")?;
+
+    // Register core modules first (excluding HTTP router)
+    // TODO: Register core modules like telemetry
+    registry
+        .register_core_with_priority(
+            atlas_events::create_module(),
+            atlas_kernel::registry::priority::EVENTS,
+        )
+        .context("failed to register events module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register authz module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::api_keys::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register api_keys module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::sessions::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register sessions module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::oauth::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register oauth module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::saml::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register saml module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::ldap::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register ldap module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::services::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register services module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::signatures::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register signatures module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::rate_limit::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register rate_limit module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::password_reset::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register auth module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::audit::create_module(),
+            atlas_kernel::registry::priority::AUTHZ,
+        )
+        .context("failed to register audit module")?;
+    registry
+        .register_core(atlas_webhooks::create_module())
+        .context("failed to register webhooks module")?;
+    registry
+        .register_core(atlas_webhooks::inbound::create_module())
+        .context("failed to register inbound webhooks module")?;
+    registry
+        .register_core(atlas_jobs::create_module())
+        .context("failed to register scheduler module")?;
+    registry
+        .register_core(atlas_jobs::queue::create_module())
+        .context("failed to register queue module")?;
+
+    // Register custom modules
+    atlas_app::modules::register_all(&mut registry).context("failed to register modules")?;
+
+    // Register modules shipped as plugins
+    if settings.plugins.enabled {
+        register_plugins(&mut registry, settings)?;
     }
 
+    Ok(registry)
+}
+
+#[cfg(feature = "plugins")]
+fn register_plugins(
+    registry: &mut atlas_kernel::registry::ModuleRegistry,
+    settings: &atlas_kernel::settings::Settings,
+) -> anyhow::Result<()> {
+    let loader = atlas_kernel::plugin::PluginLoader::new(&settings.plugins.dir);
+    // SAFETY: plugins are loaded only when explicitly enabled, from the operator-controlled plugins directory.
+    let registered = unsafe { loader.register_all(registry) }.context("failed to load plugins")?;
+    tracing::info!(
+        dir = %settings.plugins.dir,
+        modules = registered,
+        "registered plugin modules"
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "plugins"))]
+fn register_plugins(
+    _registry: &mut atlas_kernel::registry::ModuleRegistry,
+    _settings: &atlas_kernel::settings::Settings,
+) -> anyhow::Result<()> {
+    tracing::warn!(
+        "plugins are enabled in settings but atlas-cli was built without the `plugins` feature"
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_flags_become_settings_overrides() {
+        let cli = Cli::try_parse_from([
+            "atlas",
+            "server",
+            "--port",
+            "9090",
+            "--db-endpoint",
+            "ws://db:8000",
+            "--env",
+            "preview-42",
+            "--config-dir",
+            "/etc/atlas",
+        ])
+        .unwrap();
+        let Commands::Server(args) = cli.command else {
+            panic!("expected server command");
+        };
+
+        assert_eq!(
+            args.overrides(),
+            vec![
+                ("server.port".to_string(), "9090".to_string()),
+                ("database.endpoint".to_string(), "ws://db:8000".to_string()),
+            ]
+        );
+        assert_eq!(
+            args.env,
+            Some(atlas_kernel::settings::Environment::Other(
+                "preview-42".to_string()
+            ))
+        );
+        assert_eq!(args.config_dir, Some(PathBuf::from("/etc/atlas")));
+        assert!(Cli::try_parse_from(["atlas", "server", "--env", "../prod"]).is_err());
+    }
+
+    #[test]
+    fn event_filters_match_names_and_prefixes() {
+        assert!(event_matches(&[], "books.created"));
+        let filters = vec!["kernel.*".to_string(), "books.created".to_string()];
+        assert!(event_matches(&filters, "kernel.module_started"));
+        assert!(event_matches(&filters, "books.created"));
+        assert!(!event_matches(&filters, "books.created.v2"));
+        assert!(!event_matches(&filters, "users.created"));
+    }
+
+    #[test]
+    fn dev_forwards_override_flags_and_watches_sources_by_default() {
+        let cli = Cli::try_parse_from(["atlas", "dev", "--port", "9090", "--env", "qa"]).unwrap();
+        let Commands::Dev(args) = cli.command else {
+            panic!("expected dev command");
+        };
+
+        assert_eq!(args.server.override_flags(), vec!["--port", "9090"]);
+        assert_eq!(
+            args.watch,
+            vec![
+                PathBuf::from("src"),
+                PathBuf::from("crates"),
+                PathBuf::from("Cargo.toml")
+            ]
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Generating files in application modules
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+/// Width of migration numbers when a module has none yet, as in `001_init`
+const NUMBER_WIDTH: usize = 3;
+
+/// Files written by `create_migration`
+#[derive(Debug)]
+pub struct CreatedMigration {
+    pub id: String,
+    pub up: PathBuf,
+    pub down: PathBuf,
+    /// Module source the migration was added to
+    pub source: PathBuf,
+}
+
+/// Add migration `name` to the module at `{modules_dir}/{module}/mod.rs`
+///
+/// Writes `migrations/{id}.surql` and a `migrations/{id}.down.surql` stub next to the
+/// module source and appends an entry loading them to its `migrations()` list. The id
+/// numbers on from the module's highest migration, keeping its number width so ids
+/// still sort in order.
+pub fn create_migration(
+    modules_dir: &Path,
+    module: &str,
+    name: &str,
+) -> anyhow::Result<CreatedMigration> {
+    check_identifier("migration name", name)?;
+    let module_dir = modules_dir.join(module);
+    let source_path = module_dir.join("mod.rs");
+    let source = std::fs::read_to_string(&source_path).with_context(|| {
+        format!(
+            "module '{}' not found; expected {}",
+            module,
+            source_path.display()
+        )
+    })?;
+    let migrations_dir = module_dir.join("migrations");
+    let files = match std::fs::read_dir(&migrations_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let id = next_migration_id(&source, &files, name);
+    let up_file = format!("{}.surql", id);
+    let down_file = format!("{}.down.surql", id);
+    let wired = wire_migration(&source, &id, &up_file, &down_file)
+        .with_context(|| format!("failed to add the migration to {}", source_path.display()))?;
+
+    std::fs::create_dir_all(&migrations_dir)
+        .with_context(|| format!("failed to create {}", migrations_dir.display()))?;
+    let up = migrations_dir.join(&up_file);
+    let down = migrations_dir.join(&down_file);
+    std::fs::write(
+        &up,
+        format!("-- Migration {} of the {} module\n", id, module),
+    )
+    .with_context(|| format!("failed to write {}", up.display()))?;
+    std::fs::write(
+        &down,
+        format!("-- Undo migration {} of the {} module\n", id, module),
+    )
+    .with_context(|| format!("failed to write {}", down.display()))?;
+    std::fs::write(&source_path, wired)
+        .with_context(|| format!("failed to write {}", source_path.display()))?;
+    // Best effort: the entry is already valid Rust, rustfmt only tidies the list
+    let _ = std::process::Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .arg(&source_path)
+        .status();
+
+    Ok(CreatedMigration {
+        id,
+        up,
+        down,
+        source: source_path,
+    })
+}
+
+/// Files of a new module, relative to its directory, with `__module__`-style placeholders
+const MODULE_TEMPLATES: &[(&str, &str)] = &[
+    ("mod.rs", include_str!("../templates/module/mod.rs.tmpl")),
+    (
+        "models.rs",
+        include_str!("../templates/module/models.rs.tmpl"),
+    ),
+    (
+        "routes.rs",
+        include_str!("../templates/module/routes.rs.tmpl"),
+    ),
+    (
+        "migrations/001_init.surql",
+        include_str!("../templates/module/migrations/001_init.surql.tmpl"),
+    ),
+    (
+        "migrations/001_init.down.surql",
+        include_str!("../templates/module/migrations/001_init.down.surql.tmpl"),
+    ),
+];
+
+/// Files written by `create_module`
+#[derive(Debug)]
+pub struct CreatedModule {
+    pub files: Vec<PathBuf>,
+    /// `mod.rs` the module was declared in
+    pub registry: PathBuf,
+}
+
+/// Scaffold module `name` in `{modules_dir}/{name}/` and declare it in `{modules_dir}/mod.rs`
+///
+/// The module submits itself with `register_module!`, so the `pub mod` declaration is all
+/// `register_all` needs to pick it up.
+pub fn create_module(modules_dir: &Path, name: &str) -> anyhow::Result<CreatedModule> {
+    check_identifier("module name", name)?;
+    let registry = modules_dir.join("mod.rs");
+    let declarations = std::fs::read_to_string(&registry)
+        .with_context(|| format!("failed to read {}", registry.display()))?;
+    let module_dir = modules_dir.join(name);
+    if module_dir.exists() {
+        bail!(
+            "module '{}' already exists at {}",
+            name,
+            module_dir.display()
+        );
+    }
+    let declared = declare_module(&declarations, name);
+
+    let model = name.strip_suffix('s').filter(|_| !name.ends_with("ss"));
+    let model = model.filter(|model| !model.is_empty()).unwrap_or(name);
+    let placeholders = [
+        ("__Module__", pascal_case(name)),
+        ("__module__", name.to_string()),
+        ("__Model__", pascal_case(model)),
+        ("__model__", model.to_string()),
+    ];
+
+    let mut files = Vec::with_capacity(MODULE_TEMPLATES.len());
+    for (file, template) in MODULE_TEMPLATES {
+        let path = module_dir.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let contents = placeholders
+            .iter()
+            .fold(template.to_string(), |contents, (placeholder, value)| {
+                contents.replace(placeholder, value)
+            });
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        files.push(path);
+    }
+    std::fs::write(&registry, declared)
+        .with_context(|| format!("failed to write {}", registry.display()))?;
+
+    Ok(CreatedModule { files, registry })
+}
+
+/// `declarations` with `pub mod {name};` added, keeping the `pub mod` lines sorted
+fn declare_module(declarations: &str, name: &str) -> String {
+    let line = format!("pub mod {};", name);
+    let lines: Vec<&str> = declarations.lines().collect();
+    let position = lines
+        .iter()
+        .position(|existing| existing.starts_with("pub mod ") && **existing > *line)
+        .or_else(|| {
+            lines
+                .iter()
+                .rposition(|existing| existing.starts_with("pub mod "))
+                .map(|last| last + 1)
+        })
+        .unwrap_or(0);
+
+    let mut declared = String::with_capacity(declarations.len() + line.len() + 1);
+    for (index, existing) in lines.iter().enumerate() {
+        if index == position {
+            declared.push_str(&line);
+            declared.push('\n');
+        }
+        declared.push_str(existing);
+        declared.push('\n');
+    }
+    if position == lines.len() {
+        declared.push_str(&line);
+        declared.push('\n');
+    }
+    declared
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Reject names that would not make a valid migration id or module name
+pub fn check_identifier(what: &str, name: &str) -> anyhow::Result<()> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && name.starts_with(|c: char| c.is_ascii_lowercase());
+    if !valid {
+        bail!(
+            "invalid {} '{}'; use lowercase letters, digits and '_', starting with a letter",
+            what,
+            name
+        );
+    }
+    Ok(())
+}
+
+/// `{number}_{name}` numbered after the highest migration in `source` or `files`
+fn next_migration_id(source: &str, files: &[String], name: &str) -> String {
+    let declared: Vec<&str> = source
+        .match_indices("id: \"")
+        .map(|(index, matched)| leading_digits(&source[index + matched.len()..]))
+        .filter(|digits| !digits.is_empty())
+        .collect();
+    let width = declared
+        .iter()
+        .map(|digits| digits.len())
+        .max()
+        .unwrap_or(NUMBER_WIDTH);
+    let highest = declared
+        .iter()
+        .copied()
+        .chain(files.iter().map(|file| leading_digits(file)))
+        .filter_map(|digits| digits.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("{:0width$}_{}", highest + 1, name, width = width)
+}
+
+fn leading_digits(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// `source` with an entry loading the migration files appended to `migrations()`
+fn wire_migration(
+    source: &str,
+    id: &str,
+    up_file: &str,
+    down_file: &str,
+) -> anyhow::Result<String> {
+    let Some(function) = source.find("fn migrations(") else {
+        bail!("the module has no `fn migrations`; add one returning `vec![]` first");
+    };
+    let Some(open) = source[function..]
+        .find("vec![")
+        .map(|index| function + index + 4)
+    else {
+        bail!("`fn migrations` does not return a `vec![...]` literal");
+    };
+    let close =
+        closing_bracket(source, open).context("`fn migrations` has an unterminated `vec![`")?;
+
+    let line_start = source[..function].rfind('\n').map_or(0, |index| index + 1);
+    let indent = &source[line_start..function];
+    let items = source[open + 1..close].trim_end();
+    let entry = format!(
+        "{indent}        Migration {{\n\
+         {indent}            id: \"{id}\",\n\
+         {indent}            up: include_str!(\"migrations/{up_file}\"),\n\
+         {indent}            down: Some(include_str!(\"migrations/{down_file}\")),\n\
+         {indent}        }},\n",
+    );
+    let separator = if items.is_empty() || items.ends_with(',') {
+        ""
+    } else {
+        ","
+    };
+
+    let mut wired = String::with_capacity(source.len() + entry.len());
+    wired.push_str(&source[..open + 1]);
+    wired.push_str(items);
+    wired.push_str(separator);
+    wired.push('\n');
+    wired.push_str(&entry);
+    wired.push_str(indent);
+    wired.push_str("    ");
+    wired.push_str(&source[close..]);
+    Ok(wired)
+}
+
+/// Index of the `]` closing the `[` at `open`, skipping string literals
+fn closing_bracket(source: &str, open: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0usize;
+    let mut index = open;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            b'r' if matches!(bytes.get(index + 1), Some(b'#') | Some(b'"'))
+                && !bytes[index - 1].is_ascii_alphanumeric() =>
+            {
+                let hashes = bytes[index + 1..]
+                    .iter()
+                    .take_while(|byte| **byte == b'#')
+                    .count();
+                let terminator = format!("\"{}", "#".repeat(hashes));
+                let body = index + 2 + hashes;
+                index = body + source[body..].find(&terminator)? + terminator.len();
+                continue;
+            }
+            b'"' => {
+                index += 1;
+                while *bytes.get(index)? != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKS: &str = r##"impl Module for BooksModule {
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_init",
+            up: r#"
+                DEFINE FIELD state ON book TYPE string ASSERT $value IN ['draft'];
+                "#,
+            down: Some("REMOVE TABLE book;"),
+        }]
+    }
+}
+"##;
+
+    #[test]
+    fn test_next_id_keeps_the_module_number_width() {
+        assert_eq!(
+            next_migration_id(BOOKS, &["0001_init.surql".to_string()], "add_isbn"),
+            "002_add_isbn"
+        );
+        assert_eq!(
+            next_migration_id("", &["0007_seed.surql".to_string()], "add_isbn"),
+            "008_add_isbn"
+        );
+        assert_eq!(next_migration_id("", &[], "init"), "001_init");
+    }
+
+    #[test]
+    fn test_migration_is_appended_to_the_list() {
+        let wired = wire_migration(
+            BOOKS,
+            "002_add_isbn",
+            "002_add_isbn.surql",
+            "002_add_isbn.down.surql",
+        )
+        .unwrap();
+
+        assert!(wired.contains(
+            "            down: Some(\"REMOVE TABLE book;\"),\n        },\n            Migration {\n                id: \"002_add_isbn\",\n                up: include_str!(\"migrations/002_add_isbn.surql\"),\n"
+        ));
+        assert!(wired.ends_with("            },\n        ]\n    }\n}\n"));
+        assert!(wire_migration("fn routes() {}", "002_x", "a", "b").is_err());
+    }
+
+    #[test]
+    fn test_module_is_declared_in_order() {
+        let declarations = "pub mod books;\npub mod users;\n\nuse atlas_kernel::ModuleRegistry;\n";
+
+        assert_eq!(
+            declare_module(declarations, "reviews"),
+            "pub mod books;\npub mod reviews;\npub mod users;\n\nuse atlas_kernel::ModuleRegistry;\n"
+        );
+        assert_eq!(
+            declare_module(declarations, "zebras"),
+            "pub mod books;\npub mod users;\npub mod zebras;\n\nuse atlas_kernel::ModuleRegistry;\n"
+        );
+        assert_eq!(declare_module("", "books"), "pub mod books;\n");
+        assert_eq!(pascal_case("book_reviews"), "BookReviews");
+    }
+
+    #[test]
+    fn test_identifiers_are_checked() {
+        assert!(check_identifier("migration name", "add_isbn").is_ok());
+        assert!(check_identifier("migration name", "Add-ISBN").is_err());
+        assert!(check_identifier("migration name", "2fa").is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! `atlas db query`: one-shot SurrealQL and an interactive shell
+
+use std::io::{BufRead, Write};
+
+use atlas_db::QueryExecutor;
+
+const PROMPT: &str = "atlas> ";
+const CONTINUATION: &str = "  ...> ";
+
+/// Run `surql` and print every statement's result
+pub async fn query_once(
+    executor: &dyn QueryExecutor,
+    surql: &str,
+    output: &mut impl Write,
+) -> anyhow::Result<()> {
+    let results = executor.query(surql).await?;
+    print_results(&results, output)
+}
+
+/// Read statements from `input` until it ends or `exit` is entered, printing results
+///
+/// A statement runs once a line ends with `;`, so it may span several lines. Failing
+/// statements print their error and the shell keeps going.
+pub async fn repl(
+    executor: &dyn QueryExecutor,
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> anyhow::Result<()> {
+    writeln!(
+        output,
+        "SurrealQL shell; end statements with ';', type exit to quit"
+    )?;
+    write!(output, "{}", PROMPT)?;
+    output.flush()?;
+
+    let mut statement = String::new();
+    for line in input.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if statement.is_empty() && matches!(trimmed, "exit" | "quit" | "\\q") {
+            return Ok(());
+        }
+        if !trimmed.is_empty() {
+            if !statement.is_empty() {
+                statement.push('\n');
+            }
+            statement.push_str(&line);
+        }
+
+        if statement.trim_end().ends_with(';') {
+            if let Err(error) = query_once(executor, &statement, output).await {
+                writeln!(output, "error: {:#}", error)?;
+            }
+            statement.clear();
+        }
+        let prompt = if statement.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION
+        };
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+    }
+    writeln!(output)?;
+    Ok(())
+}
+
+/// Pretty JSON per statement, numbered when there are several
+fn print_results(results: &[serde_json::Value], output: &mut impl Write) -> anyhow::Result<()> {
+    for (index, result) in results.iter().enumerate() {
+        if results.len() > 1 {
+            writeln!(output, "-- statement {} --", index + 1)?;
+        }
+        writeln!(output, "{}", serde_json::to_string_pretty(result)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Returns each statement's text, failing statements that mention `missing`
+    struct Echo;
+
+    #[async_trait]
+    impl QueryExecutor for Echo {
+        async fn query_with(
+            &self,
+            surql: &str,
+            _vars: &serde_json::Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            if surql.contains("missing") {
+                anyhow::bail!("table 'missing' does not exist");
+            }
+            Ok(surql
+                .split(';')
+                .map(str::trim)
+                .filter(|statement| !statement.is_empty())
+                .map(|statement| json!({ "statement": statement }))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_statements_span_lines_and_errors_do_not_end_the_shell() {
+        let input = "SELECT *\nFROM book;\nSELECT * FROM missing;\n\nINFO FOR DB; INFO FOR NS;\nexit\nSELECT 1;\n";
+        let mut output = Vec::new();
+
+        repl(&Echo, input.as_bytes(), &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\"statement\": \"SELECT *\\nFROM book\""));
+        assert!(output.contains("  ...> "));
+        assert!(output.contains("error: table 'missing' does not exist"));
+        assert!(output.contains("-- statement 2 --"));
+        assert!(!output.contains("SELECT 1"));
+    }
+}
@@ -0,0 +1,33 @@
+use assert_cmd::Command;
+
+#[test]
+fn prints_completions_for_every_subcommand() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .args(["completions", "zsh"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let script = String::from_utf8_lossy(&output.stdout);
+    assert!(script.starts_with("#compdef atlas"));
+    assert!(script.contains("migrate"));
+    assert!(script.contains("doctor"));
+}
+
+#[test]
+fn writes_a_man_page_per_subcommand() {
+    let out_dir = std::env::temp_dir().join(format!("atlas-man-{}", std::process::id()));
+
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .args(["man", "--out-dir"])
+        .arg(&out_dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(out_dir.join("atlas.1").is_file());
+    assert!(out_dir.join("atlas-migrate-up.1").is_file());
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
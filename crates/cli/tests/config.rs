@@ -0,0 +1,50 @@
+use assert_cmd::Command;
+
+/// Run from the workspace root so the shared `config/` directory is picked up
+fn config(args: &[&str], env: &[(&str, &str)]) -> std::process::Output {
+    Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .arg("config")
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn show_loads_the_requested_environment_with_its_layers() {
+    let output = config(&["show", "--env", "production", "--sources"], &[]);
+
+    assert!(output.status.success());
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown["settings"]["environment"], "production");
+    let sources: Vec<&str> = shown["layers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|layer| layer["source"].as_str().unwrap())
+        .collect();
+    assert!(sources[0].ends_with("base.toml"));
+    assert!(sources[1].ends_with("production.toml"));
+}
+
+#[test]
+fn validate_lists_every_problem() {
+    let output = config(
+        &["validate"],
+        &[
+            ("ATLAS_DATABASE__ENDPOINT", "not-a-url"),
+            ("ATLAS_SERVER__HOST", " "),
+        ],
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("database.endpoint"));
+    assert!(stderr.contains("server.host"));
+
+    assert!(config(&["validate", "--env", "staging"], &[])
+        .status
+        .success());
+}
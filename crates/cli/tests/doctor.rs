@@ -0,0 +1,46 @@
+use assert_cmd::Command;
+
+/// Run from the workspace root with an embedded database and ephemeral ports so the
+/// checks do not depend on what the machine is running
+fn doctor(env: &[(&str, &str)]) -> (bool, String) {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .unwrap()
+        .port();
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .arg("doctor")
+        .env("ATLAS_DATABASE__ENDPOINT", "mem://")
+        .env("ATLAS_SERVER__HOST", "127.0.0.1")
+        .env("ATLAS_SERVER__PORT", port.to_string())
+        .env("ATLAS_TELEMETRY__PROMETHEUS_BIND", "127.0.0.1:0")
+        .envs(env.iter().copied())
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn passes_on_a_healthy_environment() {
+    let (success, stdout) = doctor(&[]);
+
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("[ ok ] config"));
+    assert!(stdout.contains("[ ok ] casbin"));
+}
+
+#[test]
+fn reports_failures_with_hints() {
+    let (success, stdout) = doctor(&[("ATLAS_AUTH__CASBIN_MODEL_PATH", "missing/model.conf")]);
+
+    assert!(!success);
+    assert!(stdout.contains("[fail] config"));
+    assert!(stdout.contains("auth.casbin_model_path"));
+    assert!(stdout.contains("[ ok ] database"));
+    assert!(stdout.contains("[fail] casbin"));
+    assert!(stdout.contains("hint: fix the files"));
+}
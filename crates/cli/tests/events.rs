@@ -0,0 +1,45 @@
+use assert_cmd::Command;
+
+fn atlas() -> Command {
+    let mut command = Command::cargo_bin("atlas-cli").unwrap();
+    command.current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."));
+    // Keeps the modules' stores in memory rather than on a database server
+    command.env("ATLAS_DATABASE__ENDPOINT", "mem://");
+    command
+}
+
+#[test]
+fn publishes_undeclared_events() {
+    let output = atlas()
+        .args([
+            "events",
+            "publish",
+            "cli.test",
+            "--payload",
+            r#"{"id": 1}"#,
+            "--settle-ms",
+            "0",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("published cli.test to "));
+}
+
+#[test]
+fn rejects_payloads_that_do_not_match_the_schema() {
+    let output = atlas()
+        .args([
+            "events",
+            "publish",
+            "kernel.module_failed",
+            "--settle-ms",
+            "0",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not match its schema"));
+}
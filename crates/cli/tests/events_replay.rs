@@ -0,0 +1,32 @@
+use assert_cmd::Command;
+
+/// Run from the workspace root so the shared `config/` directory is picked up
+fn replay(args: &[&str]) -> (bool, String) {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args(["events", "replay"])
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn rejects_times_that_are_not_rfc3339() {
+    let (success, stderr) = replay(&["--since", "yesterday"]);
+
+    assert!(!success);
+    assert!(stderr.contains("RFC 3339"));
+}
+
+#[test]
+fn refuses_to_replay_without_a_persistent_store() {
+    let (success, stderr) = replay(&["--event", "books.created", "--dry-run"]);
+
+    assert!(!success);
+    assert!(stderr.contains("DeadLetterStore"));
+}
@@ -0,0 +1,48 @@
+use assert_cmd::Command;
+
+#[test]
+fn exports_the_spec_and_runs_the_generator_on_it() {
+    let out = std::env::temp_dir().join(format!("atlas-sdk-{}", std::process::id()));
+
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args([
+            "generate",
+            "client",
+            "--lang",
+            "ts",
+            "--generator",
+            "echo",
+            "--out",
+        ])
+        .arg(&out)
+        .output()
+        .unwrap();
+
+    let spec = std::fs::read_to_string(out.join("openapi.json")).unwrap();
+    std::fs::remove_dir_all(&out).ok();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("generate -i "));
+    assert!(stdout.contains("-g typescript-fetch"));
+    let spec: serde_json::Value = serde_json::from_str(&spec).unwrap();
+    assert!(spec["paths"]["/api/books/"].is_object());
+}
+
+#[test]
+fn fails_when_the_generator_fails() {
+    let out = std::env::temp_dir().join(format!("atlas-sdk-fail-{}", std::process::id()));
+
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args(["generate", "client", "--generator", "false", "--out"])
+        .arg(&out)
+        .output()
+        .unwrap();
+
+    std::fs::remove_dir_all(&out).ok();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`false` failed"));
+}
@@ -0,0 +1,30 @@
+use assert_cmd::Command;
+
+#[test]
+fn running_an_unknown_scheduled_job_fails() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_DATABASE__ENDPOINT", "mem://")
+        .args(["jobs", "run", "books.vacuum"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("no scheduled job named 'books.vacuum'")
+    );
+}
+
+#[test]
+fn queue_commands_need_a_persistent_store() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args(["jobs", "cancel", "job_1", "--force"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no JobStore resource is registered"));
+}
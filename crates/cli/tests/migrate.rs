@@ -0,0 +1,124 @@
+use assert_cmd::Command;
+
+/// Run from the workspace root so the shared `config/` directory is picked up, against an
+/// embedded database nothing registers stores for
+fn migrate(args: &[&str]) -> (bool, String) {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_DATABASE__ENDPOINT", "mem://")
+        .arg("migrate")
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn up_refuses_to_run_without_a_database() {
+    let (success, stderr) = migrate(&["up"]);
+
+    assert!(!success);
+    assert!(stderr.contains("MigrationStore"));
+}
+
+#[test]
+fn status_refuses_to_run_without_a_database() {
+    let (success, stderr) = migrate(&["status"]);
+
+    assert!(!success);
+    assert!(stderr.contains("MigrationStore"));
+}
+
+#[test]
+fn down_refuses_production_without_force() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_ENV", "production")
+        .args(["migrate", "down", "--steps", "2"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+}
+
+#[test]
+fn redo_without_a_database_fails() {
+    let (success, stderr) = migrate(&["redo"]);
+
+    assert!(!success);
+    assert!(stderr.contains("MigrationStore"));
+}
+
+#[test]
+fn create_rejects_unknown_modules_and_bad_names() {
+    let (success, stderr) = migrate(&["create", "nope", "add_isbn"]);
+    assert!(!success);
+    assert!(stderr.contains("module 'nope' not found"));
+
+    let (success, stderr) = migrate(&["create", "books", "Add-ISBN"]);
+    assert!(!success);
+    assert!(stderr.contains("invalid migration name"));
+}
+
+#[test]
+fn seed_reset_is_refused_outside_local() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_ENV", "staging")
+        .args(["seed", "books", "--reset"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--reset is for local"));
+}
+
+#[test]
+fn seed_without_a_database_fails() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_DATABASE__ENDPOINT", "mem://")
+        .arg("seed")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SeedStore"));
+}
+
+#[test]
+fn db_query_without_a_database_fails() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_DATABASE__ENDPOINT", "mem://")
+        .args(["db", "query", "INFO FOR DB;"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("QueryExecutor"));
+}
+
+#[test]
+fn status_reads_the_configured_database() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_DATABASE__ENDPOINT", "http://127.0.0.1:9")
+        .args(["migrate", "status"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("failed to reach the database at http://127.0.0.1:9/sql"));
+}
@@ -0,0 +1,31 @@
+use assert_cmd::Command;
+
+#[test]
+fn lists_enabled_and_disabled_modules() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .env("ATLAS_MODULES__BOOKS", "false")
+        .args(["modules", "list", "--json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let modules: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let module = |name: &str| {
+        modules
+            .iter()
+            .find(|module| module["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(module("users")["status"], "enabled");
+    assert_eq!(module("users")["migrations"], 3);
+    assert!(module("users")["route_count"].as_u64().unwrap() > 0);
+    assert_eq!(
+        module("webhooks")["depends_on"],
+        serde_json::json!(["events"])
+    );
+    assert_eq!(module("books")["status"], "disabled");
+    assert!(module("books")["route_count"].is_null());
+}
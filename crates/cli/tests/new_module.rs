@@ -0,0 +1,44 @@
+use assert_cmd::Command;
+
+#[test]
+fn scaffolds_and_declares_a_module() {
+    let modules_dir = std::env::temp_dir().join(format!("atlas-new-module-{}", std::process::id()));
+    std::fs::create_dir_all(&modules_dir).unwrap();
+    std::fs::write(
+        modules_dir.join("mod.rs"),
+        "pub mod books;\npub mod users;\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args(["new", "module", "reviews", "--modules-dir"])
+        .arg(&modules_dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let module = std::fs::read_to_string(modules_dir.join("reviews/mod.rs")).unwrap();
+    assert!(module.contains("pub struct ReviewsModule;"));
+    assert!(module.contains("\"#/components/schemas/Review\""));
+    assert!(modules_dir
+        .join("reviews/migrations/001_init.down.surql")
+        .exists());
+    assert_eq!(
+        std::fs::read_to_string(modules_dir.join("mod.rs")).unwrap(),
+        "pub mod books;\npub mod reviews;\npub mod users;\n"
+    );
+
+    let again = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .current_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."))
+        .args(["new", "module", "reviews", "--modules-dir"])
+        .arg(&modules_dir)
+        .output()
+        .unwrap();
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("already exists"));
+
+    std::fs::remove_dir_all(&modules_dir).unwrap();
+}
@@ -0,0 +1,45 @@
+use assert_cmd::Command;
+
+/// The shared `config/` directory of the workspace
+fn config_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config")
+}
+
+fn export_to(file_name: &str, extra_args: &[&str]) -> String {
+    let output = std::env::temp_dir().join(format!("{}-{}", std::process::id(), file_name));
+
+    Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .env("ATLAS_CONFIG_DIR", config_dir())
+        .env("ATLAS_AUTH__CASBIN_MODEL_PATH", "missing/model.conf")
+        .args(["openapi", "export", "--output"])
+        .arg(&output)
+        .args(extra_args)
+        .assert()
+        .success();
+
+    let contents = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).ok();
+    contents
+}
+
+#[test]
+fn exports_merged_spec_as_json() {
+    let contents = export_to("openapi.json", &[]);
+    let spec: serde_json::Value = serde_json::from_str(&contents).unwrap();
+
+    assert_eq!(spec["openapi"], "3.0.0");
+    assert!(spec["paths"]["/api/books/"].is_object());
+    assert!(spec["paths"]["/api/users/"].is_object());
+}
+
+#[test]
+fn exports_yaml_when_extension_or_flag_requests_it() {
+    for contents in [
+        export_to("openapi.yaml", &[]),
+        export_to("spec.out", &["--format", "yaml"]),
+    ] {
+        let spec: serde_yaml::Value = serde_yaml::from_str(&contents).unwrap();
+        assert_eq!(spec["openapi"].as_str(), Some("3.0.0"));
+    }
+}
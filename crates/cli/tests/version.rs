@@ -0,0 +1,17 @@
+use assert_cmd::Command;
+
+#[test]
+fn prints_build_metadata() {
+    let output = Command::cargo_bin("atlas-cli")
+        .unwrap()
+        .args(["version", "--json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let build: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(build["version"], env!("CARGO_PKG_VERSION"));
+    assert!(build["git_sha"].as_str().is_some_and(|sha| !sha.is_empty()));
+    assert!(build["rustc"].as_str().unwrap().starts_with("rustc "));
+    assert!(build["features"].is_array());
+}
@@ -1,6 +1,28 @@
-//! Placeholder database crate for SurrealDB integration.
+//! SurrealDB access for the application and its modules
+//!
+//! [`connect`] publishes the database in `[database]` through [`SurrealHttp`].
+//!
+//! [`migrate`] applies the modules' migrations through the application's
+//! `MigrationStore`, [`seed`] loads their seed data through its `SeedStore`, and
+//! [`query`] runs SurrealQL through its `QueryExecutor`, for `atlas db query` and for the
+//! modules' SurrealDB-backed stores, which [`select_store`] picks when nothing more
+//! specific is registered.
 
-/// Attempt to establish a SurrealDB connection (stub).
-pub fn init() {
-    tracing::info!(target: "atlas-db", "database bootstrap pending implementation");
-}
+pub mod migrate;
+pub mod query;
+pub mod seed;
+pub mod store;
+pub mod surreal;
+pub mod tenant;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use migrate::{
+    MemoryMigrationStore, MigrationReport, MigrationState, MigrationStatus, MigrationStore,
+    Migrator, SurrealMigrationStore,
+};
+pub use query::{affected, datetime, records, unique_index_violation, QueryExecutor};
+pub use seed::{MemorySeedStore, SeedReport, SeedStore, Seeder, SurrealSeedStore};
+pub use store::select_store;
+pub use surreal::{connect, SurrealHttp};
+pub use tenant::TenantFilter;
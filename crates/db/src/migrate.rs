@@ -0,0 +1,687 @@
+//! Applying module migrations and recording which ones ran
+//!
+//! Every applied migration is recorded with a checksum of its script, so a migration
+//! edited after it ran is caught instead of silently diverging between databases.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+use atlas_kernel::{Clock, LifecycleEvent, Migration, ModuleRegistry};
+
+use crate::{records, QueryExecutor};
+
+/// A migration recorded as applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub module: String,
+    /// `Migration::id`
+    pub id: String,
+    /// `checksum` of the script when it was applied
+    pub checksum: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub applied_at: OffsetDateTime,
+}
+
+/// The database migrations are applied to
+///
+/// Applications apply migrations by publishing an `Arc<dyn MigrationStore>` resource
+/// connected to their database; the SurrealDB one records them in the `_migrations`
+/// table.
+#[async_trait]
+pub trait MigrationStore: Send + Sync {
+    /// Every migration recorded as applied
+    async fn applied(&self) -> anyhow::Result<Vec<AppliedMigration>>;
+
+    /// Run the migration's script and record it as applied
+    ///
+    /// Both must happen in one transaction, so a failing script leaves no record.
+    async fn apply(&self, migration: &Migration, record: AppliedMigration) -> anyhow::Result<()>;
+
+    /// Run the migration's down script and remove its record, in one transaction
+    async fn revert(&self, migration: &Migration, record: &AppliedMigration) -> anyhow::Result<()>;
+}
+
+/// Process-local migration store, recording the scripts it was asked to run
+#[derive(Debug, Default)]
+pub struct MemoryMigrationStore {
+    applied: Mutex<Vec<AppliedMigration>>,
+    scripts: Mutex<Vec<String>>,
+}
+
+impl MemoryMigrationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scripts applied so far, in order
+    pub fn scripts(&self) -> Vec<String> {
+        self.scripts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl MigrationStore for MemoryMigrationStore {
+    async fn applied(&self) -> anyhow::Result<Vec<AppliedMigration>> {
+        Ok(self
+            .applied
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone())
+    }
+
+    async fn apply(&self, migration: &Migration, record: AppliedMigration) -> anyhow::Result<()> {
+        self.scripts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(migration.up.to_string());
+        self.applied
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(record);
+        Ok(())
+    }
+
+    async fn revert(&self, migration: &Migration, record: &AppliedMigration) -> anyhow::Result<()> {
+        let down = migration
+            .down
+            .with_context(|| format!("migration '{}' has no down script", migration.id))?;
+        self.scripts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(down.to_string());
+        self.applied
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|applied| applied != record);
+        Ok(())
+    }
+}
+
+/// Migrations recorded in the `_migrations` table, applied through a `QueryExecutor`
+///
+/// The migration id is stored as `migration`, since `id` is the record's own.
+pub struct SurrealMigrationStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealMigrationStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// `record`'s fields bound as `$module`, `$migration`, `$checksum` and `$applied_at`
+    fn vars(record: &AppliedMigration) -> anyhow::Result<Map<String, Value>> {
+        let applied_at = record
+            .applied_at
+            .format(&time::format_description::well_known::Rfc3339)
+            .context("failed to format the migration's time")?;
+        let mut vars = Map::new();
+        vars.insert("module".to_string(), json!(record.module));
+        vars.insert("migration".to_string(), json!(record.id));
+        vars.insert("checksum".to_string(), json!(record.checksum));
+        vars.insert("applied_at".to_string(), json!(applied_at));
+        Ok(vars)
+    }
+}
+
+/// A row of the `_migrations` table
+#[derive(Deserialize)]
+struct MigrationRow {
+    module: String,
+    migration: String,
+    checksum: String,
+    #[serde(with = "time::serde::rfc3339")]
+    applied_at: OffsetDateTime,
+}
+
+/// `script` run in one transaction with `statement`
+fn transaction(script: &str, statement: &str) -> String {
+    let script = script.trim_end();
+    let separator = if script.ends_with(';') { "" } else { ";" };
+    format!(
+        "BEGIN TRANSACTION;\n{}{}\n{}\nCOMMIT TRANSACTION;",
+        script, separator, statement
+    )
+}
+
+#[async_trait]
+impl MigrationStore for SurrealMigrationStore {
+    async fn applied(&self) -> anyhow::Result<Vec<AppliedMigration>> {
+        let results = self
+            .db
+            .query(
+                "SELECT module, migration, checksum, applied_at FROM _migrations \
+                 ORDER BY applied_at, module, migration;",
+            )
+            .await?;
+        Ok(records::<MigrationRow>(results.into_iter().next())?
+            .into_iter()
+            .map(|row| AppliedMigration {
+                module: row.module,
+                id: row.migration,
+                checksum: row.checksum,
+                applied_at: row.applied_at,
+            })
+            .collect())
+    }
+
+    async fn apply(&self, migration: &Migration, record: AppliedMigration) -> anyhow::Result<()> {
+        let surql = transaction(
+            migration.up,
+            "CREATE _migrations SET module = $module, migration = $migration, \
+             checksum = $checksum, applied_at = $applied_at RETURN NONE;",
+        );
+        self.db.query_with(&surql, &Self::vars(&record)?).await?;
+        Ok(())
+    }
+
+    async fn revert(&self, migration: &Migration, record: &AppliedMigration) -> anyhow::Result<()> {
+        let down = migration
+            .down
+            .with_context(|| format!("migration '{}' has no down script", migration.id))?;
+        let surql = transaction(
+            down,
+            "DELETE _migrations WHERE module = $module AND migration = $migration;",
+        );
+        self.db.query_with(&surql, &Self::vars(record)?).await?;
+        Ok(())
+    }
+}
+
+/// Hex SHA-256 of a migration's script
+pub fn checksum(migration: &Migration) -> String {
+    Sha256::digest(migration.up.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Where a known migration stands in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but its script has changed since
+    Changed,
+    /// Recorded as applied, but no registered module declares it anymore
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Changed => "changed",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// One migration as reported by `Migrator::status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    pub module: String,
+    pub id: String,
+    pub state: MigrationState,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub applied_at: Option<OffsetDateTime>,
+}
+
+/// A migration applied by `Migrator::up`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationTiming {
+    pub id: String,
+    pub elapsed_ms: u64,
+}
+
+/// What `Migrator::up` did for one module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModuleMigrations {
+    pub module: String,
+    /// Migrations applied by this run, in order
+    pub applied: Vec<MigrationTiming>,
+    /// Migrations found already applied
+    pub unchanged: usize,
+}
+
+/// Per-module outcome of `Migrator::up`, in the order migrations ran
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub modules: Vec<ModuleMigrations>,
+}
+
+impl MigrationReport {
+    /// Migrations applied by this run
+    pub fn applied(&self) -> usize {
+        self.modules.iter().map(|module| module.applied.len()).sum()
+    }
+
+    /// Time spent applying migrations
+    pub fn total_ms(&self) -> u64 {
+        self.modules
+            .iter()
+            .flat_map(|module| &module.applied)
+            .map(|timing| timing.elapsed_ms)
+            .sum()
+    }
+
+    fn module(&mut self, module: &str) -> &mut ModuleMigrations {
+        match self.modules.iter().position(|entry| entry.module == module) {
+            Some(index) => &mut self.modules[index],
+            None => {
+                self.modules.push(ModuleMigrations {
+                    module: module.to_string(),
+                    ..ModuleMigrations::default()
+                });
+                self.modules.last_mut().expect("just pushed")
+            }
+        }
+    }
+}
+
+/// One line per module, e.g. `events   2 applied (14ms), 1 unchanged`
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for module in &self.modules {
+            let elapsed: u64 = module.applied.iter().map(|timing| timing.elapsed_ms).sum();
+            writeln!(
+                f,
+                "{:<16} {} applied ({}ms), {} unchanged",
+                module.module,
+                module.applied.len(),
+                elapsed,
+                module.unchanged
+            )?;
+            for timing in &module.applied {
+                writeln!(f, "  {:<40} {}ms", timing.id, timing.elapsed_ms)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies the registered modules' migrations to a `MigrationStore`
+pub struct Migrator {
+    store: Arc<dyn MigrationStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Migrator {
+    pub fn new(store: Arc<dyn MigrationStore>, clock: Arc<dyn Clock>) -> Self {
+        Self { store, clock }
+    }
+
+    pub fn store(&self) -> &Arc<dyn MigrationStore> {
+        &self.store
+    }
+
+    /// Applied migrations keyed by module and id
+    async fn recorded(&self) -> anyhow::Result<HashMap<(String, String), AppliedMigration>> {
+        Ok(self
+            .store
+            .applied()
+            .await
+            .context("failed to read applied migrations")?
+            .into_iter()
+            .map(|record| ((record.module.clone(), record.id.clone()), record))
+            .collect())
+    }
+
+    /// Every migration the modules declare or the store has recorded, ordered by module
+    /// then id
+    pub async fn status(&self, registry: &ModuleRegistry) -> anyhow::Result<Vec<MigrationStatus>> {
+        let mut applied = self.recorded().await?;
+        let mut statuses: Vec<MigrationStatus> = registry
+            .collect_migrations()
+            .into_iter()
+            .map(|(module, migration)| {
+                let record = applied.remove(&(module.clone(), migration.id.to_string()));
+                let state = match &record {
+                    None => MigrationState::Pending,
+                    Some(record) if record.checksum != checksum(&migration) => {
+                        MigrationState::Changed
+                    }
+                    Some(_) => MigrationState::Applied,
+                };
+                MigrationStatus {
+                    module,
+                    id: migration.id.to_string(),
+                    state,
+                    applied_at: record.map(|record| record.applied_at),
+                }
+            })
+            .collect();
+        statuses.extend(applied.into_values().map(|record| MigrationStatus {
+            module: record.module,
+            id: record.id,
+            state: MigrationState::Unknown,
+            applied_at: Some(record.applied_at),
+        }));
+        statuses.sort_by(|a, b| a.module.cmp(&b.module).then_with(|| a.id.cmp(&b.id)));
+        Ok(statuses)
+    }
+
+    /// Apply every migration not recorded yet, module by module in id order
+    ///
+    /// Each applied migration is announced as `MigrationApplied`. Stops at the first
+    /// failing migration, and before applying anything if an applied migration's script
+    /// has changed since.
+    pub async fn up(&self, registry: &ModuleRegistry) -> anyhow::Result<MigrationReport> {
+        let applied = self.recorded().await?;
+        let migrations = registry.collect_migrations();
+        for (module, migration) in &migrations {
+            if let Some(record) = applied.get(&(module.clone(), migration.id.to_string())) {
+                if record.checksum != checksum(migration) {
+                    bail!(
+                        "migration '{}' of module '{}' changed after it was applied",
+                        migration.id,
+                        module
+                    );
+                }
+            }
+        }
+
+        let mut report = MigrationReport::default();
+        for (module, migration) in migrations {
+            if applied.contains_key(&(module.clone(), migration.id.to_string())) {
+                report.module(&module).unchanged += 1;
+                continue;
+            }
+            let record = AppliedMigration {
+                module: module.clone(),
+                id: migration.id.to_string(),
+                checksum: checksum(&migration),
+                applied_at: OffsetDateTime::from(self.clock.now()),
+            };
+            let started = Instant::now();
+            self.store
+                .apply(&migration, record)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to apply migration '{}' of module '{}'",
+                        migration.id, module
+                    )
+                })?;
+            let elapsed = started.elapsed();
+            tracing::info!(
+                module = %module,
+                migration = migration.id,
+                elapsed_ms = millis(elapsed),
+                "applied migration"
+            );
+            report.module(&module).applied.push(MigrationTiming {
+                id: migration.id.to_string(),
+                elapsed_ms: millis(elapsed),
+            });
+            registry.announce(LifecycleEvent::MigrationApplied {
+                module,
+                migration: migration.id.to_string(),
+            });
+        }
+        Ok(report)
+    }
+
+    /// Revert the `steps` most recently applied migrations, latest first, returning them
+    ///
+    /// Migrations applied in the same run are undone in the reverse of the order `up`
+    /// applied them. Nothing is reverted if one of them has no down script, has changed
+    /// since it was applied, or is no longer declared by a module.
+    pub async fn down(
+        &self,
+        registry: &ModuleRegistry,
+        steps: usize,
+    ) -> anyhow::Result<Vec<AppliedMigration>> {
+        let migrations = registry.collect_migrations();
+        let mut applied: Vec<(usize, AppliedMigration)> = Vec::new();
+        for record in self.recorded().await?.into_values() {
+            let Some(position) = migrations.iter().position(|(module, migration)| {
+                *module == record.module && migration.id == record.id
+            }) else {
+                bail!(
+                    "migration '{}' of module '{}' is applied but no module declares it",
+                    record.id,
+                    record.module
+                );
+            };
+            applied.push((position, record));
+        }
+        applied.sort_by(|(a_position, a), (b_position, b)| {
+            (b.applied_at, b_position).cmp(&(a.applied_at, a_position))
+        });
+        applied.truncate(steps);
+
+        for (position, record) in &applied {
+            let migration = &migrations[*position].1;
+            if record.checksum != checksum(migration) {
+                bail!(
+                    "migration '{}' of module '{}' changed after it was applied",
+                    record.id,
+                    record.module
+                );
+            }
+            if migration.down.is_none() {
+                bail!(
+                    "migration '{}' of module '{}' has no down script",
+                    record.id,
+                    record.module
+                );
+            }
+        }
+
+        let mut reverted = Vec::new();
+        for (position, record) in applied {
+            let migration = &migrations[position].1;
+            let started = Instant::now();
+            self.store
+                .revert(migration, &record)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to revert migration '{}' of module '{}'",
+                        record.id, record.module
+                    )
+                })?;
+            tracing::info!(
+                module = %record.module,
+                migration = %record.id,
+                elapsed_ms = millis(started.elapsed()),
+                "reverted migration"
+            );
+            reverted.push(record);
+        }
+        Ok(reverted)
+    }
+}
+
+pub(crate) fn millis(elapsed: Duration) -> u64 {
+    u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use atlas_kernel::clock::SystemClock;
+    use atlas_kernel::Module;
+
+    use super::*;
+    use crate::testing::RecordingExecutor;
+
+    struct Books(&'static [Migration]);
+
+    impl Module for Books {
+        fn name(&self) -> &'static str {
+            "books"
+        }
+
+        fn migrations(&self) -> Vec<Migration> {
+            self.0.to_vec()
+        }
+    }
+
+    const CREATE: Migration = Migration {
+        id: "001_create_book",
+        up: "DEFINE TABLE book SCHEMAFULL;",
+        down: Some("REMOVE TABLE book;"),
+    };
+    const INDEX: Migration = Migration {
+        id: "002_index_book_title",
+        up: "DEFINE INDEX book_title ON book FIELDS title;",
+        down: None,
+    };
+
+    fn registry(migrations: &'static [Migration]) -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(Books(migrations)))
+            .unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_up_applies_pending_migrations_once() {
+        let store = Arc::new(MemoryMigrationStore::new());
+        let migrator = Migrator::new(store.clone(), Arc::new(SystemClock));
+
+        let first = migrator.up(&registry(&[CREATE])).await.unwrap();
+        let second = migrator.up(&registry(&[CREATE, INDEX])).await.unwrap();
+
+        assert_eq!(first.applied(), 1);
+        assert_eq!(second.modules[0].module, "books");
+        assert_eq!(second.modules[0].unchanged, 1);
+        assert_eq!(second.modules[0].applied[0].id, "002_index_book_title");
+        assert_eq!(store.scripts(), vec![CREATE.up, INDEX.up]);
+        assert_eq!(
+            store.applied().await.unwrap()[0].checksum,
+            checksum(&CREATE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_flags_pending_changed_and_unknown_migrations() {
+        let store = Arc::new(MemoryMigrationStore::new());
+        let migrator = Migrator::new(store.clone(), Arc::new(SystemClock));
+        migrator.up(&registry(&[CREATE])).await.unwrap();
+        store
+            .apply(
+                &INDEX,
+                AppliedMigration {
+                    module: "authors".to_string(),
+                    id: "001_create_author".to_string(),
+                    checksum: checksum(&INDEX),
+                    applied_at: OffsetDateTime::UNIX_EPOCH,
+                },
+            )
+            .await
+            .unwrap();
+
+        const EDITED: Migration = Migration {
+            id: "001_create_book",
+            up: "DEFINE TABLE book SCHEMALESS;",
+            down: Some("REMOVE TABLE book;"),
+        };
+        let status = migrator.status(&registry(&[EDITED, INDEX])).await.unwrap();
+
+        let states: Vec<_> = status
+            .iter()
+            .map(|status| (status.module.as_str(), status.id.as_str(), status.state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("authors", "001_create_author", MigrationState::Unknown),
+                ("books", "001_create_book", MigrationState::Changed),
+                ("books", "002_index_book_title", MigrationState::Pending),
+            ]
+        );
+        assert!(status[1].applied_at.is_some());
+        assert_eq!(status[2].applied_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_down_reverts_latest_migrations_with_down_scripts() {
+        const SEED: Migration = Migration {
+            id: "003_seed_book",
+            up: "CREATE book:intro SET title = 'Intro';",
+            down: Some("DELETE book:intro;"),
+        };
+        let store = Arc::new(MemoryMigrationStore::new());
+        let migrator = Migrator::new(store.clone(), Arc::new(SystemClock));
+        let registry = registry(&[CREATE, INDEX, SEED]);
+        migrator.up(&registry).await.unwrap();
+
+        let reverted = migrator.down(&registry, 1).await.unwrap();
+        assert_eq!(reverted[0].id, "003_seed_book");
+        assert_eq!(store.scripts().last().unwrap(), "DELETE book:intro;");
+
+        let error = migrator.down(&registry, 2).await.unwrap_err();
+        assert!(error.to_string().contains("has no down script"));
+        assert_eq!(store.applied().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_up_refuses_edited_migrations() {
+        let store = Arc::new(MemoryMigrationStore::new());
+        let migrator = Migrator::new(store.clone(), Arc::new(SystemClock));
+        migrator.up(&registry(&[CREATE])).await.unwrap();
+
+        const EDITED: Migration = Migration {
+            id: "001_create_book",
+            up: "DEFINE TABLE book SCHEMALESS;",
+            down: Some("REMOVE TABLE book;"),
+        };
+        let error = migrator.up(&registry(&[EDITED, INDEX])).await.unwrap_err();
+
+        assert!(error.to_string().contains("changed after it was applied"));
+        assert_eq!(store.scripts().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_records_migrations_in_their_transaction() {
+        let db = RecordingExecutor::answering(vec![Ok(vec![json!([{
+            "module": "books",
+            "migration": "001_create_book",
+            "checksum": checksum(&CREATE),
+            "applied_at": "2026-01-02T03:04:05Z",
+        }])])]);
+        let store = SurrealMigrationStore::new(db.clone());
+
+        let applied = store.applied().await.unwrap();
+        assert_eq!(applied[0].id, "001_create_book");
+        assert_eq!(applied[0].applied_at.year(), 2026);
+
+        store.apply(&INDEX, applied[0].clone()).await.unwrap();
+        store.revert(&CREATE, &applied[0]).await.unwrap();
+
+        let queries = db.queries();
+        let (apply, vars) = &queries[1];
+        assert!(apply.starts_with(&format!(
+            "BEGIN TRANSACTION;\n{}\nCREATE _migrations",
+            INDEX.up
+        )));
+        assert!(apply.ends_with("COMMIT TRANSACTION;"));
+        assert_eq!(vars["migration"], "001_create_book");
+        assert_eq!(vars["applied_at"], "2026-01-02T03:04:05Z");
+        let (revert, _) = &queries[2];
+        assert!(revert.contains("REMOVE TABLE book;\nDELETE _migrations WHERE"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Running SurrealQL against the application's database
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use time::OffsetDateTime;
+
+/// The database `atlas db query` and the modules' SurrealDB stores talk to
+///
+/// Connects with the credentials in `[database]`, usually through the same client as the
+/// `MigrationStore`.
+#[async_trait]
+pub trait QueryExecutor: Send + Sync {
+    /// Run `surql` with `vars` bound as `$name` parameters and return one result per
+    /// statement, in order
+    ///
+    /// A statement the database rejects fails the whole call with its error, whose
+    /// message must be the database's so callers can recognise e.g. index violations.
+    async fn query_with(
+        &self,
+        surql: &str,
+        vars: &Map<String, Value>,
+    ) -> anyhow::Result<Vec<Value>>;
+
+    /// Run `surql` without bound parameters
+    async fn query(&self, surql: &str) -> anyhow::Result<Vec<Value>> {
+        self.query_with(surql, &Map::new()).await
+    }
+}
+
+/// Name of the unique index a failed query violated, if that is why it failed
+///
+/// Matches SurrealDB's ``Database index `name` already contains ...`` error.
+pub fn unique_index_violation(error: &anyhow::Error) -> Option<String> {
+    error.chain().find_map(|cause| {
+        let message = cause.to_string();
+        let (_, rest) = message.split_once("Database index `")?;
+        let (index, rest) = rest.split_once('`')?;
+        rest.trim_start()
+            .starts_with("already contains")
+            .then(|| index.to_string())
+    })
+}
+
+/// Records returned by one statement, e.g. `results.into_iter().next()` for the first
+///
+/// A missing or `NULL` result has no records.
+pub fn records<T: DeserializeOwned>(result: Option<Value>) -> anyhow::Result<Vec<T>> {
+    match result {
+        Some(Value::Array(records)) => records
+            .into_iter()
+            .map(|record| {
+                serde_json::from_value(record.clone())
+                    .with_context(|| format!("unexpected record {}", record))
+            })
+            .collect(),
+        Some(Value::Null) | None => Ok(Vec::new()),
+        Some(other) => anyhow::bail!("expected an array of records, got {}", other),
+    }
+}
+
+/// `at` as an RFC 3339 string, for binding to a `<datetime>` cast
+pub fn datetime(at: OffsetDateTime) -> String {
+    at.format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default()
+}
+
+/// Whether a statement ending in `RETURN VALUE ...` or `RETURN BEFORE` matched a record
+pub fn affected(result: Option<&Value>) -> bool {
+    matches!(result, Some(Value::Array(records)) if !records.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_index_violations_are_recognised() {
+        let error = Err::<(), _>(anyhow::anyhow!(
+            "Database index `user_email_unique` already contains 'ann@example.com', with record `user:ann`"
+        ))
+        .context("failed to create user")
+        .unwrap_err();
+        assert_eq!(
+            unique_index_violation(&error).as_deref(),
+            Some("user_email_unique")
+        );
+
+        let error = anyhow::anyhow!("Found NONE for field `name`, but expected a string");
+        assert_eq!(unique_index_violation(&error), None);
+    }
+
+    #[test]
+    fn test_records_decode_statement_results() {
+        let names: Vec<String> = records(Some(serde_json::json!(["a", "b"]))).unwrap();
+        assert_eq!(names, ["a", "b"]);
+        assert!(records::<String>(None).unwrap().is_empty());
+        assert!(records::<String>(Some(serde_json::json!({ "a": 1 }))).is_err());
+    }
+}
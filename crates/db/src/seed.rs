@@ -0,0 +1,282 @@
+//! Loading the modules' seed data into the application's database
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use atlas_kernel::{ModuleRegistry, Seed};
+
+use crate::migrate::millis;
+use crate::surreal::is_identifier;
+use crate::QueryExecutor;
+
+/// The database seeds are loaded into
+///
+/// Applications seed by publishing an `Arc<dyn SeedStore>` resource connected to their
+/// database, usually the same connection as their `MigrationStore`.
+#[async_trait]
+pub trait SeedStore: Send + Sync {
+    /// Run the seed's script
+    async fn run(&self, seed: &Seed) -> anyhow::Result<()>;
+
+    /// Delete every record in `table`, keeping its definition
+    async fn truncate(&self, table: &str) -> anyhow::Result<()>;
+}
+
+/// Process-local seed store, recording the scripts and truncations it was asked for
+#[derive(Debug, Default)]
+pub struct MemorySeedStore {
+    statements: Mutex<Vec<String>>,
+}
+
+impl MemorySeedStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scripts run and `DELETE {table};` for every truncation, in order
+    pub fn statements(&self) -> Vec<String> {
+        self.statements
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn record(&self, statement: String) {
+        self.statements
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(statement);
+    }
+}
+
+#[async_trait]
+impl SeedStore for MemorySeedStore {
+    async fn run(&self, seed: &Seed) -> anyhow::Result<()> {
+        self.record(seed.script.to_string());
+        Ok(())
+    }
+
+    async fn truncate(&self, table: &str) -> anyhow::Result<()> {
+        self.record(format!("DELETE {};", table));
+        Ok(())
+    }
+}
+
+/// Seeds run through a `QueryExecutor`
+pub struct SurrealSeedStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealSeedStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SeedStore for SurrealSeedStore {
+    async fn run(&self, seed: &Seed) -> anyhow::Result<()> {
+        self.db.query(seed.script).await?;
+        Ok(())
+    }
+
+    async fn truncate(&self, table: &str) -> anyhow::Result<()> {
+        if !is_identifier(table) {
+            bail!("invalid table name '{}'", table);
+        }
+        self.db.query(&format!("DELETE {};", table)).await?;
+        Ok(())
+    }
+}
+
+/// A seed loaded by `Seeder::seed`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeedTiming {
+    pub module: String,
+    pub id: String,
+    pub elapsed_ms: u64,
+}
+
+/// What `Seeder::seed` did, in order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeedReport {
+    /// Tables emptied before seeding
+    pub truncated: Vec<String>,
+    pub seeded: Vec<SeedTiming>,
+}
+
+/// One line per seed, e.g. `books    catalog   3ms`
+impl fmt::Display for SeedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.truncated.is_empty() {
+            writeln!(f, "truncated {}", self.truncated.join(", "))?;
+        }
+        for seed in &self.seeded {
+            writeln!(
+                f,
+                "{:<16} {:<32} {}ms",
+                seed.module, seed.id, seed.elapsed_ms
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the registered modules' seeds into a `SeedStore`
+pub struct Seeder {
+    store: Arc<dyn SeedStore>,
+}
+
+impl Seeder {
+    pub fn new(store: Arc<dyn SeedStore>) -> Self {
+        Self { store }
+    }
+
+    /// Run the seeds of `modules`, or of every module when empty, in declaration order
+    ///
+    /// With `reset`, every table those seeds write to is emptied first so seeding can be
+    /// repeated. Stops at the first failing seed.
+    pub async fn seed(
+        &self,
+        registry: &ModuleRegistry,
+        modules: &[String],
+        reset: bool,
+    ) -> anyhow::Result<SeedReport> {
+        for module in modules {
+            if registry.get_module(module).is_none() {
+                bail!("no module named '{}' is registered", module);
+            }
+        }
+        let seeds: Vec<(String, Seed)> = registry
+            .collect_seeds()
+            .into_iter()
+            .filter(|(module, _)| modules.is_empty() || modules.contains(module))
+            .collect();
+
+        let mut report = SeedReport::default();
+        if reset {
+            for table in seeds.iter().flat_map(|(_, seed)| seed.tables) {
+                if report.truncated.iter().any(|truncated| truncated == table) {
+                    continue;
+                }
+                self.store
+                    .truncate(table)
+                    .await
+                    .with_context(|| format!("failed to truncate table '{}'", table))?;
+                report.truncated.push(table.to_string());
+            }
+        }
+
+        for (module, seed) in seeds {
+            let started = Instant::now();
+            self.store.run(&seed).await.with_context(|| {
+                format!("failed to run seed '{}' of module '{}'", seed.id, module)
+            })?;
+            let elapsed_ms = millis(started.elapsed());
+            tracing::info!(module = %module, seed = seed.id, elapsed_ms, "ran seed");
+            report.seeded.push(SeedTiming {
+                module,
+                id: seed.id.to_string(),
+                elapsed_ms,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atlas_kernel::Module;
+
+    use super::*;
+
+    struct Seeded {
+        name: &'static str,
+        seeds: Vec<Seed>,
+    }
+
+    #[async_trait]
+    impl Module for Seeded {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn seeds(&self) -> Vec<Seed> {
+            self.seeds.clone()
+        }
+    }
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(Seeded {
+                name: "books",
+                seeds: vec![
+                    Seed {
+                        id: "authors",
+                        tables: &["author"],
+                        script: "CREATE author:ann;",
+                    },
+                    Seed {
+                        id: "catalog",
+                        tables: &["book", "author"],
+                        script: "CREATE book:one;",
+                    },
+                ],
+            }))
+            .unwrap();
+        registry
+            .register_custom(Arc::new(Seeded {
+                name: "users",
+                seeds: vec![Seed {
+                    id: "admins",
+                    tables: &["user"],
+                    script: "CREATE user:admin;",
+                }],
+            }))
+            .unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_reset_truncates_each_table_once_before_seeding() {
+        let store = Arc::new(MemorySeedStore::new());
+        let seeder = Seeder::new(store.clone());
+
+        let report = seeder
+            .seed(&registry(), &["books".to_string()], true)
+            .await
+            .unwrap();
+
+        assert_eq!(report.truncated, vec!["author", "book"]);
+        assert_eq!(
+            store.statements(),
+            vec![
+                "DELETE author;",
+                "DELETE book;",
+                "CREATE author:ann;",
+                "CREATE book:one;"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_module_is_seeded_without_a_selection() {
+        let store = Arc::new(MemorySeedStore::new());
+        let seeder = Seeder::new(store.clone());
+
+        let report = seeder.seed(&registry(), &[], false).await.unwrap();
+
+        assert_eq!(report.seeded.len(), 3);
+        assert!(report.truncated.is_empty());
+        assert!(seeder
+            .seed(&registry(), &["orders".to_string()], false)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,34 @@
+//! Choosing where a module persists its records
+
+use std::sync::Arc;
+
+use atlas_kernel::Resources;
+
+use crate::QueryExecutor;
+
+/// The store a module keeps its records in
+///
+/// A store the application registered as an `Arc<S>` resource wins; otherwise `surreal`
+/// builds one over the registered `Arc<dyn QueryExecutor>`; otherwise records are kept
+/// in memory by `memory`'s store, lost on restart and not shared between instances,
+/// with a warning naming `kind`.
+pub fn select_store<S: ?Sized + Send + Sync + 'static>(
+    resources: &Resources,
+    kind: &str,
+    surreal: impl FnOnce(Arc<dyn QueryExecutor>) -> Arc<S>,
+    memory: impl FnOnce() -> Arc<S>,
+) -> Arc<S> {
+    if let Some(store) = resources.get::<Arc<S>>() {
+        return store.as_ref().clone();
+    }
+    match resources.get::<Arc<dyn QueryExecutor>>() {
+        Some(db) => surreal(db.as_ref().clone()),
+        None => {
+            tracing::warn!(
+                store = kind,
+                "no persistent store or query executor registered; records are kept in memory"
+            );
+            memory()
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! SurrealDB over its HTTP API
+//!
+//! [`SurrealHttp`] sends SurrealQL to the `/sql` endpoint of the server in `[database]`;
+//! [`connect`] publishes it together with the migration and seed stores built on it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use url::Url;
+
+use atlas_kernel::{settings::DatabaseSettings, Resources};
+
+use crate::{MigrationStore, QueryExecutor, SeedStore, SurrealMigrationStore, SurrealSeedStore};
+
+/// How long one request may take, including the statements it runs
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Message of statements SurrealDB skipped because an earlier one failed the transaction
+const FAILED_TRANSACTION: &str = "The query was not executed due to a failed transaction";
+
+/// Schemes `SurrealHttp` can reach; the others embed the database in the process
+const REMOTE_SCHEMES: &[&str] = &["http", "https", "ws", "wss"];
+
+/// A SurrealDB server reached through its HTTP `/sql` endpoint
+///
+/// `ws` and `wss` endpoints are reached over `http` and `https` on the same host and
+/// port, and user info in the endpoint, e.g. `ws://root:root@db:8000`, signs in with
+/// basic authentication. Every call is one request, so a transaction must begin and
+/// commit within one script.
+pub struct SurrealHttp {
+    client: reqwest::Client,
+    sql: Url,
+    namespace: String,
+    database: String,
+    credentials: Option<(String, String)>,
+}
+
+impl SurrealHttp {
+    pub fn new(settings: &DatabaseSettings) -> anyhow::Result<Self> {
+        let endpoint = Url::parse(&settings.endpoint).context("invalid database endpoint")?;
+        let scheme = match endpoint.scheme() {
+            "http" | "ws" => "http",
+            "https" | "wss" => "https",
+            other => bail!("'{}' databases are not reachable over HTTP", other),
+        };
+        let host = endpoint
+            .host_str()
+            .context("database endpoint has no host")?;
+        let mut sql = Url::parse(&format!("{}://{}/sql", scheme, host))
+            .context("invalid database endpoint")?;
+        sql.set_port(endpoint.port())
+            .map_err(|()| anyhow::anyhow!("invalid database endpoint port"))?;
+        let credentials = (!endpoint.username().is_empty()).then(|| {
+            (
+                decode(endpoint.username()),
+                endpoint.password().map(decode).unwrap_or_default(),
+            )
+        });
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("failed to create the database client")?;
+        Ok(Self {
+            client,
+            sql,
+            namespace: settings.namespace.clone(),
+            database: settings.database.clone(),
+            credentials,
+        })
+    }
+}
+
+/// Percent-decoded user info from an endpoint URL
+fn decode(part: &str) -> String {
+    percent_decode_str(part).decode_utf8_lossy().into_owned()
+}
+
+/// Whether `name` can be used bare as a SurrealQL parameter or table name
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `surql` preceded by a `LET` statement for every variable
+///
+/// JSON values are valid SurrealQL literals, so each variable keeps its type.
+fn with_vars(surql: &str, vars: &Map<String, Value>) -> anyhow::Result<String> {
+    let mut script = String::new();
+    for (name, value) in vars {
+        if !is_identifier(name) {
+            bail!("invalid query parameter name '{}'", name);
+        }
+        script.push_str(&format!("LET ${} = {};\n", name, value));
+    }
+    script.push_str(surql);
+    Ok(script)
+}
+
+/// One statement's outcome in a `/sql` response
+#[derive(Debug, Deserialize)]
+struct StatementResult {
+    status: String,
+    #[serde(default)]
+    result: Value,
+}
+
+#[async_trait]
+impl QueryExecutor for SurrealHttp {
+    async fn query_with(
+        &self,
+        surql: &str,
+        vars: &Map<String, Value>,
+    ) -> anyhow::Result<Vec<Value>> {
+        let mut request = self
+            .client
+            .post(self.sql.clone())
+            .header("accept", "application/json")
+            .header("surreal-ns", &self.namespace)
+            .header("surreal-db", &self.database)
+            .body(with_vars(surql, vars)?);
+        if let Some((username, password)) = &self.credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("failed to reach the database at {}", self.sql))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("failed to read the database's response")?;
+        if !status.is_success() {
+            // Parse errors and failed sign-ins come back as one error for the request
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|error| {
+                    error
+                        .get("information")
+                        .and_then(Value::as_str)
+                        .map(String::from)
+                })
+                .unwrap_or(body);
+            bail!("{}", message);
+        }
+        let results: Vec<StatementResult> =
+            serde_json::from_str(&body).context("unexpected response from the database")?;
+        let errors: Vec<&StatementResult> = results
+            .iter()
+            .filter(|result| result.status != "OK")
+            .collect();
+        // The failing statement's error, rather than the statements it cancelled
+        let error = errors
+            .iter()
+            .find(|result| result.result.as_str() != Some(FAILED_TRANSACTION))
+            .or(errors.first());
+        if let Some(error) = error {
+            match &error.result {
+                Value::String(message) => bail!("{}", message),
+                other => bail!("{}", other),
+            }
+        }
+        Ok(results
+            .into_iter()
+            .skip(vars.len())
+            .map(|result| result.result)
+            .collect())
+    }
+}
+
+/// Publish the database in `[database]` as `Arc<dyn QueryExecutor>`, `MigrationStore`
+/// and `SeedStore` resources, keeping any the application registered already
+///
+/// Embedded endpoints (`mem`, `rocksdb`, `surrealkv`) need the SurrealDB engine linked
+/// into the application, which registers its own stores; nothing is published for them.
+pub fn connect(resources: &Resources, settings: &DatabaseSettings) -> anyhow::Result<()> {
+    let scheme = settings.endpoint.split("://").next().unwrap_or_default();
+    if !REMOTE_SCHEMES.contains(&scheme) {
+        tracing::warn!(
+            endpoint = %settings.endpoint,
+            "embedded databases are not connected by atlas-db; register the stores in the application"
+        );
+        return Ok(());
+    }
+    let db: Arc<dyn QueryExecutor> = match resources.get::<Arc<dyn QueryExecutor>>() {
+        Some(db) => db.as_ref().clone(),
+        None => {
+            let db: Arc<dyn QueryExecutor> = Arc::new(SurrealHttp::new(settings)?);
+            resources.insert(db.clone());
+            db
+        }
+    };
+    if !resources.contains::<Arc<dyn MigrationStore>>() {
+        resources
+            .insert::<Arc<dyn MigrationStore>>(Arc::new(SurrealMigrationStore::new(db.clone())));
+    }
+    if !resources.contains::<Arc<dyn SeedStore>>() {
+        resources.insert::<Arc<dyn SeedStore>>(Arc::new(SurrealSeedStore::new(db)));
+    }
+    tracing::debug!(endpoint = %settings.endpoint, "database stores registered");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+    use serde_json::json;
+
+    /// Headers and body of every request, answered with `response`
+    #[derive(Clone)]
+    struct Server {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        response: Value,
+    }
+
+    async fn sql(State(server): State<Server>, headers: HeaderMap, body: String) -> Json<Value> {
+        server.requests.lock().unwrap().push((headers, body));
+        Json(server.response.clone())
+    }
+
+    /// Serve `response` on a local port and return the endpoint and the recorded requests
+    async fn serve(response: Value) -> (String, Arc<Mutex<Vec<(HeaderMap, String)>>>) {
+        let server = Server {
+            requests: Arc::default(),
+            response,
+        };
+        let requests = server.requests.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/sql", post(sql)).with_state(server);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("ws://root:p%40ss@{}/rpc", addr), requests)
+    }
+
+    fn settings(endpoint: String) -> DatabaseSettings {
+        DatabaseSettings {
+            endpoint,
+            ..DatabaseSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queries_are_sent_with_their_variables_and_credentials() {
+        let (endpoint, requests) = serve(json!([
+            { "status": "OK", "time": "1ms", "result": null },
+            { "status": "OK", "time": "1ms", "result": [{ "title": "Dune" }] },
+        ]))
+        .await;
+        let db = SurrealHttp::new(&settings(endpoint)).unwrap();
+
+        let mut vars = Map::new();
+        vars.insert("title".to_string(), json!("Du\"ne"));
+        let results = db
+            .query_with("SELECT title FROM book WHERE title = $title;", &vars)
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![json!([{ "title": "Dune" }])]);
+        let requests = requests.lock().unwrap();
+        let (headers, body) = &requests[0];
+        assert_eq!(
+            body,
+            "LET $title = \"Du\\\"ne\";\nSELECT title FROM book WHERE title = $title;"
+        );
+        assert_eq!(headers["surreal-ns"], "atlas");
+        assert_eq!(headers["surreal-db"], "core");
+        // root:p@ss
+        assert_eq!(headers["authorization"], "Basic cm9vdDpwQHNz");
+    }
+
+    #[tokio::test]
+    async fn test_failed_statements_fail_with_the_databases_error() {
+        let (endpoint, _) = serve(json!([
+            { "status": "ERR", "time": "1ms", "result": FAILED_TRANSACTION },
+            {
+                "status": "ERR",
+                "time": "1ms",
+                "result": "Database index `book_slug` already contains 'dune', with record `book:1`"
+            },
+        ]))
+        .await;
+        let db = SurrealHttp::new(&settings(endpoint)).unwrap();
+
+        let error = db.query("CREATE book;").await.unwrap_err();
+
+        assert_eq!(
+            crate::unique_index_violation(&error).as_deref(),
+            Some("book_slug")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_parameter_names_are_rejected() {
+        let db = SurrealHttp::new(&settings("http://127.0.0.1:9".to_string())).unwrap();
+        let mut vars = Map::new();
+        vars.insert("x = 1; REMOVE TABLE book; LET $y".to_string(), json!(1));
+
+        let error = db.query_with("RETURN $x;", &vars).await.unwrap_err();
+
+        assert!(error.to_string().contains("invalid query parameter name"));
+    }
+
+    #[test]
+    fn test_only_remote_endpoints_are_connected() {
+        let resources = Resources::new();
+        connect(&resources, &settings("mem://".to_string())).unwrap();
+        assert!(!resources.contains::<Arc<dyn QueryExecutor>>());
+
+        connect(&resources, &settings("wss://db.example.com".to_string())).unwrap();
+        assert!(resources.contains::<Arc<dyn QueryExecutor>>());
+        assert!(resources.contains::<Arc<dyn MigrationStore>>());
+        assert!(resources.contains::<Arc<dyn SeedStore>>());
+    }
+}
@@ -0,0 +1,69 @@
+//! Tenant scoping for repository queries
+//!
+//! Repositories build every query on tenant-owned tables through `TenantFilter`, so a
+//! handler can never read or write another tenant's rows by forgetting a `WHERE`.
+
+use anyhow::anyhow;
+
+use atlas_kernel::tenant::{self, TenantId};
+
+/// Field holding the owning tenant on tenant-scoped tables
+pub const TENANT_FIELD: &str = "tenant";
+
+/// The `tenant = $tenant` condition for the current tenant, with its bind value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantFilter {
+    pub tenant: TenantId,
+}
+
+impl TenantFilter {
+    /// Filter for the tenant of the surrounding request; errors outside a tenant scope
+    pub fn current() -> anyhow::Result<Self> {
+        tenant::current()
+            .map(|tenant| Self { tenant })
+            .ok_or_else(|| anyhow!("no tenant in scope for a tenant-scoped query"))
+    }
+
+    /// Condition to AND into the query's `WHERE` clause
+    pub fn clause(&self) -> String {
+        format!("{} = ${}", TENANT_FIELD, TENANT_FIELD)
+    }
+
+    /// `(name, value)` to bind alongside `clause`
+    pub fn bind(&self) -> (&'static str, String) {
+        (TENANT_FIELD, self.tenant.to_string())
+    }
+
+    /// `SELECT * FROM {table} WHERE tenant = $tenant [AND {condition}]`
+    pub fn select(&self, table: &str, condition: Option<&str>) -> String {
+        match condition {
+            Some(condition) => format!(
+                "SELECT * FROM {} WHERE {} AND ({})",
+                table,
+                self.clause(),
+                condition
+            ),
+            None => format!("SELECT * FROM {} WHERE {}", table, self.clause()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_filter_follows_tenant_scope() {
+        assert!(TenantFilter::current().is_err());
+
+        let acme = TenantId::new("acme").unwrap();
+        let filter = tenant::scope(acme, async { TenantFilter::current() })
+            .await
+            .unwrap();
+        assert_eq!(filter.bind(), ("tenant", "acme".to_string()));
+        assert_eq!(
+            filter.select("book", Some("isbn = $isbn")),
+            "SELECT * FROM book WHERE tenant = $tenant AND (isbn = $isbn)"
+        );
+    }
+}
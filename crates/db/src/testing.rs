@@ -0,0 +1,61 @@
+//! A `QueryExecutor` for testing SurrealDB-backed stores without a database
+//!
+//! Available to this crate's tests and, through the `testing` feature, to other crates'
+//! dev-dependencies.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+
+use crate::QueryExecutor;
+
+/// Records every query and answers with canned results, in order
+///
+/// Once the answers run out every query returns no results.
+#[derive(Default)]
+pub struct RecordingExecutor {
+    queries: Mutex<Vec<(String, Map<String, Value>)>>,
+    answers: Mutex<VecDeque<anyhow::Result<Vec<Value>>>>,
+}
+
+impl RecordingExecutor {
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    pub fn answering(answers: Vec<anyhow::Result<Vec<Value>>>) -> Arc<Self> {
+        Arc::new(Self {
+            queries: Mutex::default(),
+            answers: Mutex::new(answers.into()),
+        })
+    }
+
+    /// Every query run so far with its bound variables
+    pub fn queries(&self) -> Vec<(String, Map<String, Value>)> {
+        self.queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl QueryExecutor for RecordingExecutor {
+    async fn query_with(
+        &self,
+        surql: &str,
+        vars: &Map<String, Value>,
+    ) -> anyhow::Result<Vec<Value>> {
+        self.queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((surql.to_string(), vars.clone()));
+        self.answers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_front()
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+}
@@ -0,0 +1,186 @@
+//! Dropping cached entries when the events that make them stale are emitted
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use atlas_kernel::{CacheInvalidation, InitCtx};
+
+use crate::{EventBus, NamedEvent};
+
+/// The caching layer's side of `Module::cache_invalidations`
+///
+/// A cache module publishes an `Arc<dyn CacheInvalidator>` resource during init; the
+/// events module then drops the keys modules declared whenever their event is emitted.
+#[async_trait]
+pub trait CacheInvalidator: Send + Sync {
+    /// Drop the entry stored under `key`, if any
+    async fn invalidate(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Drop every entry whose key starts with `prefix`
+    async fn invalidate_prefix(&self, prefix: &str) -> anyhow::Result<()>;
+}
+
+/// A cache key an event made stale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleKey {
+    Exact(String),
+    Prefix(String),
+}
+
+/// Keys `invalidation` declares stale for `event`
+///
+/// A key whose placeholder the event cannot fill, such as `{tenant}` outside a tenant
+/// scope or a field missing from the payload, is logged and left out.
+pub fn stale_keys(invalidation: &CacheInvalidation, event: &NamedEvent) -> Vec<StaleKey> {
+    invalidation
+        .keys
+        .iter()
+        .filter_map(|template| {
+            let (template, prefix) = match template.strip_suffix('*') {
+                Some(prefix) => (prefix, true),
+                None => (*template, false),
+            };
+            match render(template, event) {
+                Ok(key) if prefix => Some(StaleKey::Prefix(key)),
+                Ok(key) => Some(StaleKey::Exact(key)),
+                Err(placeholder) => {
+                    tracing::warn!(
+                        event = %event.name,
+                        key = template,
+                        placeholder,
+                        "event does not fill cache key placeholder; key not invalidated"
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Fill the `{...}` placeholders of `template`, or name the first one the event lacks
+fn render<'a>(template: &'a str, event: &NamedEvent) -> Result<String, &'a str> {
+    let mut key = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        key.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..end];
+        let value = if placeholder == "tenant" {
+            event
+                .tenant
+                .as_ref()
+                .map(|tenant| tenant.as_str().to_string())
+        } else {
+            field(&event.payload, placeholder)
+        };
+        key.push_str(&value.ok_or(placeholder)?);
+        rest = &rest[end + 1..];
+    }
+    key.push_str(rest);
+    Ok(key)
+}
+
+fn field(payload: &Value, path: &str) -> Option<String> {
+    let value = path
+        .split('.')
+        .try_fold(payload, |value, segment| value.get(segment))?;
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Invalidate the declared keys of every named event in a task owned by `ctx`'s module
+///
+/// Events received through transports invalidate too, so replicas with their own cache
+/// see writes made elsewhere. A failing invalidation is logged and the task keeps going.
+pub(crate) fn run(
+    ctx: &InitCtx,
+    bus: &EventBus,
+    invalidator: Arc<dyn CacheInvalidator>,
+    invalidations: Vec<(&'static str, CacheInvalidation)>,
+) {
+    let mut subscription = bus.subscribe::<NamedEvent>();
+    ctx.spawn("cache invalidation", async move {
+        while let Some(event) = subscription.recv().await {
+            for (module, invalidation) in &invalidations {
+                if *event.name != *invalidation.event {
+                    continue;
+                }
+                for key in stale_keys(invalidation, &event) {
+                    let result = match &key {
+                        StaleKey::Exact(key) => invalidator.invalidate(key).await,
+                        StaleKey::Prefix(prefix) => invalidator.invalidate_prefix(prefix).await,
+                    };
+                    match result {
+                        Ok(()) => {
+                            tracing::debug!(module, event = %event.name, ?key, "cache invalidated")
+                        }
+                        Err(error) => tracing::warn!(
+                            module,
+                            event = %event.name,
+                            ?key,
+                            error = format!("{:#}", error),
+                            "failed to invalidate cache"
+                        ),
+                    }
+                }
+            }
+        }
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use atlas_kernel::tenant::TenantId;
+
+    use super::*;
+
+    fn event(payload: Value, tenant: Option<&str>) -> NamedEvent {
+        NamedEvent {
+            name: "books.updated".into(),
+            payload: Arc::new(payload),
+            version: None,
+            origin: None,
+            tenant: tenant.map(|tenant| TenantId::new(tenant).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_keys_are_filled_from_payload_and_tenant() {
+        let invalidation = CacheInvalidation::new(
+            "books.updated",
+            &[
+                "books:{id}",
+                "{tenant}:authors:{author.id}:books",
+                "books:list:*",
+                "books:isbn:{isbn}",
+            ],
+        );
+
+        let keys = stale_keys(
+            &invalidation,
+            &event(json!({ "id": 7, "author": { "id": "ann" } }), Some("acme")),
+        );
+
+        assert_eq!(
+            keys,
+            vec![
+                StaleKey::Exact("books:7".to_string()),
+                StaleKey::Exact("acme:authors:ann:books".to_string()),
+                StaleKey::Prefix("books:list:".to_string()),
+            ]
+        );
+        let untenanted = stale_keys(&invalidation, &event(json!({ "id": 7 }), None));
+        assert_eq!(untenanted.len(), 2);
+    }
+}
@@ -0,0 +1,194 @@
+//! Declared events and the schemas their payloads must match
+
+use anyhow::bail;
+use serde::Serialize;
+use serde_json::Value;
+
+use atlas_http::validation::ResponseValidator;
+use atlas_kernel::EventSchema;
+
+/// One version of a declared event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub version: u32,
+    /// Module declaring the event
+    pub module: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub schema: Value,
+}
+
+/// Every declared event version, ordered by name then version
+///
+/// Events that are not declared can still be emitted, unchecked.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct EventCatalog {
+    entries: Vec<CatalogEntry>,
+}
+
+impl EventCatalog {
+    /// Build the catalog from the schemas modules declare, as `(module, schema)` pairs
+    ///
+    /// An event belongs to the first module declaring it; another module declaring the
+    /// same name, or a version declared twice, is an error.
+    pub fn new(declared: &[(&str, EventSchema)]) -> anyhow::Result<Self> {
+        let mut entries: Vec<CatalogEntry> = Vec::new();
+        for (module, schema) in declared {
+            if schema.version == 0 {
+                bail!(
+                    "module '{}' declares event '{}' with version 0; versions start at 1",
+                    module,
+                    schema.name
+                );
+            }
+            if let Some(existing) = entries.iter().find(|entry| entry.name == schema.name) {
+                if existing.module != *module {
+                    bail!(
+                        "event '{}' is declared by both '{}' and '{}'",
+                        schema.name,
+                        existing.module,
+                        module
+                    );
+                }
+            }
+            if entries
+                .iter()
+                .any(|entry| entry.name == schema.name && entry.version == schema.version)
+            {
+                bail!(
+                    "module '{}' declares version {} of event '{}' twice",
+                    module,
+                    schema.version,
+                    schema.name
+                );
+            }
+            entries.push(CatalogEntry {
+                name: schema.name.to_string(),
+                version: schema.version,
+                module: module.to_string(),
+                description: schema.description.map(str::to_string),
+                schema: schema.schema.clone(),
+            });
+        }
+        entries.sort_by(|a, b| (&a.name, a.version).cmp(&(&b.name, b.version)));
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    /// A specific version of an event, or its latest when `version` is `None`
+    pub fn get(&self, name: &str, version: Option<u32>) -> Option<&CatalogEntry> {
+        self.entries.iter().rfind(|entry| {
+            entry.name == name && version.is_none_or(|version| entry.version == version)
+        })
+    }
+
+    /// Check a payload about to be emitted, returning the version it was checked against
+    ///
+    /// Undeclared events pass unchecked unless a version is asked for.
+    pub fn validate(
+        &self,
+        name: &str,
+        version: Option<u32>,
+        payload: &Value,
+    ) -> anyhow::Result<Option<u32>> {
+        let Some(entry) = self.get(name, version) else {
+            if let Some(version) = version {
+                bail!("version {} of event '{}' is not declared", version, name);
+            }
+            return Ok(None);
+        };
+        let errors = ResponseValidator::new(Value::Null).validate(&entry.schema, payload);
+        if !errors.is_empty() {
+            bail!(
+                "payload of event '{}' v{} does not match its schema: {}",
+                name,
+                entry.version,
+                errors.join("; ")
+            );
+        }
+        Ok(Some(entry.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(name: &'static str, version: u32, required: &[&str]) -> EventSchema {
+        EventSchema {
+            name,
+            version,
+            description: None,
+            schema: json!({
+                "type": "object",
+                "required": required,
+                "properties": { "id": { "type": "integer" }, "slug": { "type": "string" } }
+            }),
+        }
+    }
+
+    fn catalog() -> EventCatalog {
+        EventCatalog::new(&[
+            ("books", schema("books.created", 2, &["id", "slug"])),
+            ("books", schema("books.created", 1, &["id"])),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_payloads_checked_against_latest_or_requested_version() {
+        let catalog = catalog();
+
+        assert_eq!(catalog.entries()[0].version, 1);
+        assert_eq!(
+            catalog
+                .validate("books.created", None, &json!({ "id": 1, "slug": "dune" }))
+                .unwrap(),
+            Some(2)
+        );
+        let error = catalog
+            .validate("books.created", None, &json!({ "id": 1 }))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("missing required property 'slug'"));
+        assert_eq!(
+            catalog
+                .validate("books.created", Some(1), &json!({ "id": 1 }))
+                .unwrap(),
+            Some(1)
+        );
+        assert!(catalog
+            .validate("books.created", Some(3), &json!({ "id": 1 }))
+            .is_err());
+        assert_eq!(
+            catalog.validate("books.deleted", None, &json!(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_conflicting_declarations_rejected() {
+        let twice = EventCatalog::new(&[
+            ("books", schema("books.created", 1, &[])),
+            ("books", schema("books.created", 1, &[])),
+        ]);
+        assert!(twice.unwrap_err().to_string().contains("twice"));
+
+        let shared = EventCatalog::new(&[
+            ("books", schema("books.created", 1, &[])),
+            ("search", schema("books.created", 2, &[])),
+        ]);
+        assert!(shared
+            .unwrap_err()
+            .to_string()
+            .contains("declared by both 'books' and 'search'"));
+    }
+}
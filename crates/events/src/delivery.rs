@@ -0,0 +1,392 @@
+//! Delivery of named events to module handlers, with retries and dead-lettering
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+
+use atlas_kernel::{Clock, EventHandler, RetryPolicy};
+
+use crate::dlq::{DeadLetter, DeadLetterQuery, DeadLetterStore};
+use crate::NamedEvent;
+
+/// Handler outcomes since startup
+#[derive(Debug, Default)]
+pub struct DeliveryMetrics {
+    delivered: AtomicU64,
+    retried: AtomicU64,
+    dead_lettered: AtomicU64,
+    redriven: AtomicU64,
+}
+
+impl DeliveryMetrics {
+    /// Counters as reported in the events module's health details
+    pub fn snapshot(&self) -> Value {
+        json!({
+            "delivered": self.delivered.load(Ordering::Relaxed),
+            "retried": self.retried.load(Ordering::Relaxed),
+            "dead_lettered": self.dead_lettered.load(Ordering::Relaxed),
+            "redriven": self.redriven.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Result of re-driving a dead letter
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redrive {
+    /// The handler succeeded and the dead letter was removed
+    Delivered,
+    /// The handler failed again; the stored dead letter was updated
+    Failed(DeadLetter),
+    /// The module no longer handles the event
+    NoHandler,
+}
+
+/// Outcome of replaying a batch of dead letters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReplayReport {
+    /// Dead letters handled and removed
+    pub delivered: usize,
+    /// Dead letters whose handler failed again
+    pub failed: Vec<String>,
+    /// Dead letters of handlers that no longer exist
+    pub skipped: Vec<String>,
+}
+
+/// Runs module handlers for named events
+///
+/// A failing handler is retried under `events.retry`; once the attempts are exhausted
+/// the event is stored as a dead letter, from which an operator can re-drive it.
+pub struct Delivery {
+    policy: RetryPolicy,
+    store: Arc<dyn DeadLetterStore>,
+    clock: Arc<dyn Clock>,
+    handlers: OnceLock<Vec<(&'static str, EventHandler)>>,
+    metrics: DeliveryMetrics,
+}
+
+impl Delivery {
+    pub fn new(
+        policy: RetryPolicy,
+        store: Arc<dyn DeadLetterStore>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            policy,
+            store,
+            clock,
+            handlers: OnceLock::new(),
+            metrics: DeliveryMetrics::default(),
+        }
+    }
+
+    pub fn store(&self) -> &Arc<dyn DeadLetterStore> {
+        &self.store
+    }
+
+    pub fn metrics(&self) -> &DeliveryMetrics {
+        &self.metrics
+    }
+
+    /// Record the handlers dead letters can be re-driven to; only the first call counts
+    pub(crate) fn set_handlers(&self, handlers: Vec<(&'static str, EventHandler)>) {
+        let _ = self.handlers.set(handlers);
+    }
+
+    /// Run `module`'s handler for `event`, dead-lettering it when every attempt fails
+    pub async fn deliver(&self, module: &str, handler: &EventHandler, event: &NamedEvent) {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let mut attempt = 1;
+        let error = loop {
+            match handler.handle(event.payload.as_ref().clone()).await {
+                Ok(()) => {
+                    self.metrics.delivered.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                Err(error) if attempt >= max_attempts => break error,
+                Err(error) => {
+                    let backoff = self.policy.backoff(attempt);
+                    tracing::debug!(
+                        module,
+                        event = %event.name,
+                        attempt,
+                        backoff_ms = backoff.as_millis() as u64,
+                        error = format!("{:#}", error),
+                        "event handler failed; retrying"
+                    );
+                    self.metrics.retried.fetch_add(1, Ordering::Relaxed);
+                    self.clock.sleep(backoff).await;
+                    attempt += 1;
+                }
+            }
+        };
+
+        let letter = DeadLetter {
+            id: atlas_kernel::id::prefixed_with("dlq", atlas_kernel::id::ulid_at(self.clock.now()))
+                .expect("dlq is a valid id prefix"),
+            event: event.name.to_string(),
+            version: event.version,
+            payload: event.payload.as_ref().clone(),
+            module: module.to_string(),
+            error: format!("{:#}", error),
+            attempts: attempt,
+            failed_at: OffsetDateTime::from(self.clock.now()),
+        };
+        tracing::warn!(
+            module,
+            event = %event.name,
+            attempts = attempt,
+            id = %letter.id,
+            error = %letter.error,
+            "event handler failed; dead-lettered"
+        );
+        self.metrics.dead_lettered.fetch_add(1, Ordering::Relaxed);
+        if let Err(error) = self.store.insert(letter).await {
+            tracing::error!(
+                module,
+                event = %event.name,
+                error = format!("{:#}", error),
+                "failed to store dead letter; the event is lost"
+            );
+        }
+    }
+
+    /// Re-drive every dead letter matching `query`, oldest failure first
+    ///
+    /// Each event goes back to the handler that dead-lettered it rather than to every
+    /// subscriber, so consumers that already handled it do not see it twice.
+    pub async fn replay(&self, query: &DeadLetterQuery) -> anyhow::Result<ReplayReport> {
+        let mut letters = self.store.list(query).await?;
+        letters.reverse();
+        let mut report = ReplayReport::default();
+        for letter in letters {
+            match self.redrive(&letter.id).await? {
+                Some(Redrive::Delivered) => report.delivered += 1,
+                Some(Redrive::Failed(_)) => report.failed.push(letter.id),
+                Some(Redrive::NoHandler) => report.skipped.push(letter.id),
+                // Removed meanwhile, e.g. re-driven by an operator
+                None => {}
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run the handler of a dead letter once more; `None` when no such dead letter exists
+    pub async fn redrive(&self, id: &str) -> anyhow::Result<Option<Redrive>> {
+        let Some(mut letter) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+        let Some(handler) = self
+            .handlers
+            .get()
+            .into_iter()
+            .flatten()
+            .find(|(module, handler)| *module == letter.module && handler.event == letter.event)
+            .map(|(_, handler)| handler)
+        else {
+            return Ok(Some(Redrive::NoHandler));
+        };
+
+        match handler.handle(letter.payload.clone()).await {
+            Ok(()) => {
+                self.store
+                    .remove(id)
+                    .await
+                    .context("failed to remove re-driven dead letter")?;
+                self.metrics.redriven.fetch_add(1, Ordering::Relaxed);
+                tracing::info!(id, module = %letter.module, event = %letter.event, "dead letter re-driven");
+                Ok(Some(Redrive::Delivered))
+            }
+            Err(error) => {
+                letter.attempts += 1;
+                letter.error = format!("{:#}", error);
+                letter.failed_at = OffsetDateTime::from(self.clock.now());
+                self.store.update(letter.clone()).await?;
+                Ok(Some(Redrive::Failed(letter)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlq::{DeadLetterQuery, MemoryDeadLetterStore};
+    use atlas_kernel::clock::ManualClock;
+    use std::sync::atomic::AtomicU32;
+    use std::time::{Duration, SystemTime};
+
+    /// A handler failing its first `failures` calls
+    fn flaky(failures: u32) -> (EventHandler, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counted = calls.clone();
+        let handler = EventHandler::new("books.created", move |_| {
+            let call = counted.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if call <= failures {
+                    anyhow::bail!("index unavailable ({})", call);
+                }
+                Ok(())
+            }
+        });
+        (handler, calls)
+    }
+
+    fn event() -> NamedEvent {
+        NamedEvent {
+            name: Arc::from("books.created"),
+            payload: Arc::new(json!({ "id": 7 })),
+            version: Some(1),
+            origin: None,
+            tenant: None,
+        }
+    }
+
+    fn delivery() -> (Delivery, Arc<ManualClock>) {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = Arc::new(ManualClock::new(start));
+        let delivery = Delivery::new(
+            RetryPolicy::exponential(3, Duration::from_secs(1)),
+            Arc::new(MemoryDeadLetterStore::new()),
+            clock.clone(),
+        );
+        (delivery, clock)
+    }
+
+    #[tokio::test]
+    async fn test_retries_with_backoff_until_the_handler_succeeds() {
+        let (delivery, clock) = delivery();
+        let (handler, calls) = flaky(2);
+        let start = clock.now();
+
+        delivery.deliver("search", &handler, &event()).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            clock.now().duration_since(start).unwrap(),
+            Duration::from_secs(3)
+        );
+        let metrics = delivery.metrics().snapshot();
+        assert_eq!(metrics["delivered"], 1);
+        assert_eq!(metrics["retried"], 2);
+        assert_eq!(metrics["dead_lettered"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_dead_letter_the_event() {
+        let (delivery, _) = delivery();
+        let (handler, calls) = flaky(u32::MAX);
+
+        delivery.deliver("search", &handler, &event()).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let letters = delivery
+            .store()
+            .list(&DeadLetterQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(letters.len(), 1);
+        assert!(letters[0].id.starts_with("dlq_"));
+        assert_eq!(letters[0].module, "search");
+        assert_eq!(letters[0].event, "books.created");
+        assert_eq!(letters[0].payload, json!({ "id": 7 }));
+        assert_eq!(letters[0].attempts, 3);
+        assert_eq!(letters[0].error, "index unavailable (3)");
+        assert_eq!(delivery.metrics().snapshot()["dead_lettered"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_redrive_removes_on_success_and_updates_on_failure() {
+        let (delivery, _) = delivery();
+        let (handler, _) = flaky(4);
+        delivery.set_handlers(vec![("search", handler.clone())]);
+        delivery.deliver("search", &handler, &event()).await;
+        let id = delivery
+            .store()
+            .list(&DeadLetterQuery::default())
+            .await
+            .unwrap()[0]
+            .id
+            .clone();
+
+        let Some(Redrive::Failed(letter)) = delivery.redrive(&id).await.unwrap() else {
+            panic!("expected the fourth call to fail");
+        };
+        assert_eq!(letter.attempts, 4);
+        assert_eq!(letter.error, "index unavailable (4)");
+
+        assert_eq!(
+            delivery.redrive(&id).await.unwrap(),
+            Some(Redrive::Delivered)
+        );
+        assert_eq!(delivery.store().get(&id).await.unwrap(), None);
+        assert_eq!(delivery.redrive(&id).await.unwrap(), None);
+        assert_eq!(delivery.metrics().snapshot()["redriven"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_redrives_matching_letters_oldest_first() {
+        let (delivery, clock) = delivery();
+        let (failing, _) = flaky(u32::MAX);
+        delivery.deliver("search", &failing, &event()).await;
+        clock.advance(Duration::from_secs(60));
+        let mut deleted = event();
+        deleted.name = Arc::from("books.deleted");
+        delivery.deliver("search", &failing, &deleted).await;
+        clock.advance(Duration::from_secs(60));
+        let recent = OffsetDateTime::from(clock.now());
+        delivery.deliver("search", &failing, &event()).await;
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        delivery.set_handlers(vec![(
+            "search",
+            EventHandler::new("books.created", move |payload| {
+                recorded.lock().unwrap().push(payload);
+                async { Ok(()) }
+            }),
+        )]);
+        let report = delivery
+            .replay(&DeadLetterQuery {
+                event: Some("books.created".to_string()),
+                since: Some(recent),
+                ..DeadLetterQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.delivered, 1);
+        assert_eq!(seen.lock().unwrap().len(), 1);
+
+        let report = delivery.replay(&DeadLetterQuery::default()).await.unwrap();
+        assert_eq!(report.delivered, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+        let remaining = delivery
+            .store()
+            .list(&DeadLetterQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].event, "books.deleted");
+    }
+
+    #[tokio::test]
+    async fn test_redrive_without_handler() {
+        let (delivery, _) = delivery();
+        let (handler, _) = flaky(u32::MAX);
+        delivery.deliver("search", &handler, &event()).await;
+        let letters = delivery
+            .store()
+            .list(&DeadLetterQuery::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            delivery.redrive(&letters[0].id).await.unwrap(),
+            Some(Redrive::NoHandler)
+        );
+    }
+}
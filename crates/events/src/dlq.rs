@@ -0,0 +1,225 @@
+//! Dead letters: named events a module's handler kept failing on
+
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+/// An event delivery that exhausted its retries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// `dlq_`-prefixed ULID
+    pub id: String,
+    /// Name the event was emitted under
+    pub event: String,
+    /// Declared schema version of the payload, when the event is declared
+    pub version: Option<u32>,
+    pub payload: Value,
+    /// Module whose handler failed
+    pub module: String,
+    /// Error of the last attempt
+    pub error: String,
+    /// Deliveries attempted so far, re-drives included
+    pub attempts: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub failed_at: OffsetDateTime,
+}
+
+/// Filter for listing dead letters; the most recent failures come first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeadLetterQuery {
+    pub event: Option<String>,
+    pub module: Option<String>,
+    /// Only dead letters that failed at or after this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub since: Option<OffsetDateTime>,
+    /// Only dead letters that failed before this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+    pub limit: Option<usize>,
+}
+
+impl DeadLetterQuery {
+    /// Dead letters returned when no limit is given
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, letter: &DeadLetter) -> bool {
+        self.event
+            .as_ref()
+            .is_none_or(|event| letter.event == *event)
+            && self
+                .module
+                .as_ref()
+                .is_none_or(|module| letter.module == *module)
+            && self.since.is_none_or(|since| letter.failed_at >= since)
+            && self.until.is_none_or(|until| letter.failed_at < until)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+/// Persistence for dead letters
+///
+/// Applications keep dead letters across restarts by publishing an
+/// `Arc<dyn DeadLetterStore>` resource; without one they live in memory.
+#[async_trait]
+pub trait DeadLetterStore: Send + Sync {
+    async fn insert(&self, letter: DeadLetter) -> anyhow::Result<()>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<DeadLetter>>;
+
+    async fn list(&self, query: &DeadLetterQuery) -> anyhow::Result<Vec<DeadLetter>>;
+
+    /// Replace a stored dead letter with the same id
+    async fn update(&self, letter: DeadLetter) -> anyhow::Result<()>;
+
+    /// Remove a dead letter, returning whether it existed
+    async fn remove(&self, id: &str) -> anyhow::Result<bool>;
+}
+
+/// Process-local dead letter store
+#[derive(Debug, Default)]
+pub struct MemoryDeadLetterStore {
+    letters: RwLock<Vec<DeadLetter>>,
+}
+
+impl MemoryDeadLetterStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn letters(&self) -> std::sync::RwLockWriteGuard<'_, Vec<DeadLetter>> {
+        self.letters
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for MemoryDeadLetterStore {
+    async fn insert(&self, letter: DeadLetter) -> anyhow::Result<()> {
+        self.letters().push(letter);
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<DeadLetter>> {
+        Ok(self
+            .letters()
+            .iter()
+            .find(|letter| letter.id == id)
+            .cloned())
+    }
+
+    async fn list(&self, query: &DeadLetterQuery) -> anyhow::Result<Vec<DeadLetter>> {
+        let mut letters: Vec<DeadLetter> = self
+            .letters()
+            .iter()
+            .filter(|letter| query.matches(letter))
+            .cloned()
+            .collect();
+        letters.sort_by(|a, b| b.failed_at.cmp(&a.failed_at).then(b.id.cmp(&a.id)));
+        letters.truncate(query.limit());
+        Ok(letters)
+    }
+
+    async fn update(&self, letter: DeadLetter) -> anyhow::Result<()> {
+        let mut letters = self.letters();
+        match letters.iter_mut().find(|stored| stored.id == letter.id) {
+            Some(stored) => *stored = letter,
+            None => anyhow::bail!("dead letter '{}' does not exist", letter.id),
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<bool> {
+        let mut letters = self.letters();
+        let before = letters.len();
+        letters.retain(|letter| letter.id != id);
+        Ok(letters.len() != before)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(crate) fn letter(id: &str, event: &str, module: &str, seconds: i64) -> DeadLetter {
+        DeadLetter {
+            id: id.to_string(),
+            event: event.to_string(),
+            version: Some(1),
+            payload: json!({ "id": 7 }),
+            module: module.to_string(),
+            error: "index unavailable".to_string(),
+            attempts: 3,
+            failed_at: OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(seconds),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_filters_newest_first() {
+        let store = MemoryDeadLetterStore::new();
+        store
+            .insert(letter("dlq_1", "books.created", "search", 1))
+            .await
+            .unwrap();
+        store
+            .insert(letter("dlq_2", "books.deleted", "search", 2))
+            .await
+            .unwrap();
+        store
+            .insert(letter("dlq_3", "books.created", "mailer", 3))
+            .await
+            .unwrap();
+
+        let ids = |letters: Vec<DeadLetter>| -> Vec<String> {
+            letters.into_iter().map(|letter| letter.id).collect()
+        };
+        let all = store.list(&DeadLetterQuery::default()).await.unwrap();
+        assert_eq!(ids(all), vec!["dlq_3", "dlq_2", "dlq_1"]);
+        let created = DeadLetterQuery {
+            event: Some("books.created".to_string()),
+            limit: Some(1),
+            ..DeadLetterQuery::default()
+        };
+        assert_eq!(ids(store.list(&created).await.unwrap()), vec!["dlq_3"]);
+        let search = DeadLetterQuery {
+            module: Some("search".to_string()),
+            ..DeadLetterQuery::default()
+        };
+        assert_eq!(
+            ids(store.list(&search).await.unwrap()),
+            vec!["dlq_2", "dlq_1"]
+        );
+        let window = DeadLetterQuery {
+            since: Some(OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(2)),
+            until: Some(OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(3)),
+            ..DeadLetterQuery::default()
+        };
+        assert_eq!(ids(store.list(&window).await.unwrap()), vec!["dlq_2"]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_update_and_remove() {
+        let store = MemoryDeadLetterStore::new();
+        let mut stored = letter("dlq_1", "books.created", "search", 1);
+        store.insert(stored.clone()).await.unwrap();
+
+        stored.attempts = 4;
+        store.update(stored.clone()).await.unwrap();
+        assert_eq!(store.get("dlq_1").await.unwrap(), Some(stored));
+        assert!(store
+            .update(letter("dlq_2", "books.created", "search", 1))
+            .await
+            .is_err());
+
+        assert!(store.remove("dlq_1").await.unwrap());
+        assert!(!store.remove("dlq_1").await.unwrap());
+        assert_eq!(store.get("dlq_1").await.unwrap(), None);
+    }
+}
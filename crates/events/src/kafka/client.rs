@@ -0,0 +1,188 @@
+//! `KafkaClient` over librdkafka
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+
+use atlas_kernel::settings::{KafkaCommit, KafkaSettings};
+
+use super::{KafkaClient, KafkaConnector, KafkaOffset, KafkaRecord};
+
+/// How long connecting waits for the brokers' metadata
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a record may wait in the producer queue when it is full
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long producing waits for the broker's acknowledgement, in milliseconds
+const DELIVERY_TIMEOUT_MS: &str = "30000";
+
+/// How long `poll` waits for a first record
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Most records `poll` returns at once
+const MAX_BATCH: usize = 100;
+
+/// Connects with librdkafka through the `rdkafka` crate
+///
+/// The consumer is only created when `settings.consume` lists topics.
+#[derive(Debug, Default)]
+pub struct RdKafkaConnector;
+
+impl RdKafkaConnector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Configuration shared by the producer and the consumer
+fn client_config(settings: &KafkaSettings) -> ClientConfig {
+    let mut config = ClientConfig::new();
+    config
+        .set("bootstrap.servers", settings.brokers.join(","))
+        .set("client.id", &settings.client_id);
+    config
+}
+
+fn producer_config(settings: &KafkaSettings) -> ClientConfig {
+    let mut config = client_config(settings);
+    config.set("message.timeout.ms", DELIVERY_TIMEOUT_MS);
+    config
+}
+
+fn consumer_config(settings: &KafkaSettings) -> ClientConfig {
+    let mut config = client_config(settings);
+    let auto_commit = matches!(settings.commit, KafkaCommit::Auto);
+    config
+        .set("group.id", &settings.consumer_group)
+        .set("enable.auto.commit", auto_commit.to_string());
+    config
+}
+
+#[async_trait]
+impl KafkaConnector for RdKafkaConnector {
+    async fn connect(&self, settings: &KafkaSettings) -> anyhow::Result<Arc<dyn KafkaClient>> {
+        let producer: FutureProducer = producer_config(settings)
+            .create()
+            .context("failed to create the Kafka producer")?;
+        let probe = producer.clone();
+        tokio::task::spawn_blocking(move || probe.client().fetch_metadata(None, CONNECT_TIMEOUT))
+            .await?
+            .context("failed to reach the Kafka brokers")?;
+        let consumer = if settings.consume.is_empty() {
+            None
+        } else {
+            let consumer: StreamConsumer = consumer_config(settings)
+                .create()
+                .context("failed to create the Kafka consumer")?;
+            let topics: Vec<&str> = settings.consume.iter().map(String::as_str).collect();
+            consumer
+                .subscribe(&topics)
+                .context("failed to subscribe to the consumed topics")?;
+            Some(Arc::new(consumer))
+        };
+        Ok(Arc::new(RdKafkaClient { producer, consumer }))
+    }
+}
+
+struct RdKafkaClient {
+    producer: FutureProducer,
+    consumer: Option<Arc<StreamConsumer>>,
+}
+
+impl RdKafkaClient {
+    fn consumer(&self) -> anyhow::Result<&Arc<StreamConsumer>> {
+        self.consumer
+            .as_ref()
+            .context("no topics are consumed from Kafka")
+    }
+}
+
+#[async_trait]
+impl KafkaClient for RdKafkaClient {
+    async fn produce(&self, topic: &str, key: &str, payload: Vec<u8>) -> anyhow::Result<()> {
+        self.producer
+            .send(
+                FutureRecord::to(topic).key(key).payload(&payload),
+                QUEUE_TIMEOUT,
+            )
+            .await
+            .map_err(|(error, _)| error)
+            .with_context(|| format!("failed to produce to '{}'", topic))?;
+        Ok(())
+    }
+
+    async fn poll(&self) -> anyhow::Result<Vec<KafkaRecord>> {
+        let consumer = self.consumer()?;
+        let mut records = Vec::new();
+        let mut wait = POLL_TIMEOUT;
+        // After the first record, take only what is already buffered
+        while records.len() < MAX_BATCH {
+            let Ok(message) = tokio::time::timeout(wait, consumer.recv()).await else {
+                break;
+            };
+            let message = message.context("failed to consume from Kafka")?;
+            records.push(KafkaRecord {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                payload: message.payload().unwrap_or_default().to_vec(),
+            });
+            wait = Duration::ZERO;
+        }
+        Ok(records)
+    }
+
+    async fn commit(&self, offsets: &[KafkaOffset]) -> anyhow::Result<()> {
+        let consumer = self.consumer()?.clone();
+        let mut list = TopicPartitionList::new();
+        for offset in offsets {
+            list.add_partition_offset(
+                &offset.topic,
+                offset.partition,
+                Offset::Offset(offset.offset),
+            )?;
+        }
+        // A synchronous commit blocks until the group coordinator answers
+        tokio::task::spawn_blocking(move || consumer.commit(&list, CommitMode::Sync))
+            .await?
+            .context("failed to commit Kafka offsets")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(commit: KafkaCommit) -> KafkaSettings {
+        KafkaSettings {
+            enabled: true,
+            brokers: vec!["kafka-1:9092".to_string(), "kafka-2:9092".to_string()],
+            consume: vec!["atlas.books".to_string()],
+            commit,
+            ..KafkaSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_auto_commit_is_enabled_only_in_auto_mode() {
+        let config = consumer_config(&settings(KafkaCommit::Auto));
+        assert_eq!(config.get("enable.auto.commit"), Some("true"));
+        assert_eq!(config.get("group.id"), Some("atlas"));
+        assert_eq!(
+            config.get("bootstrap.servers"),
+            Some("kafka-1:9092,kafka-2:9092")
+        );
+
+        for commit in [KafkaCommit::AfterBatch, KafkaCommit::AfterRecord] {
+            let config = consumer_config(&settings(commit));
+            assert_eq!(config.get("enable.auto.commit"), Some("false"));
+        }
+    }
+}
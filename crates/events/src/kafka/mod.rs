@@ -0,0 +1,312 @@
+//! Kafka transport for named events
+//!
+//! Events are produced as JSON envelopes to the topic `events.kafka` maps their name to,
+//! keyed by event name so each event type stays ordered within its partition. Records of the
+//! `consume` topics are emitted on the local bus, and offsets committed per `commit`.
+//!
+//! With the `kafka` feature, `RdKafkaConnector` connects with librdkafka.
+
+#[cfg(feature = "kafka")]
+mod client;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use atlas_kernel::settings::{KafkaCommit, KafkaSettings};
+
+use crate::transport::{self, EventTransport};
+use crate::{EventBus, NamedEvent};
+
+#[cfg(feature = "kafka")]
+pub use client::RdKafkaConnector;
+
+/// Creates Kafka clients
+///
+/// The consumer must join `settings.consumer_group` and subscribe to `settings.consume`,
+/// enabling the library's auto-commit only for `KafkaCommit::Auto`.
+#[async_trait]
+pub trait KafkaConnector: Send + Sync {
+    async fn connect(&self, settings: &KafkaSettings) -> anyhow::Result<Arc<dyn KafkaClient>>;
+}
+
+/// Producer and consumer of one connection
+#[async_trait]
+pub trait KafkaClient: Send + Sync {
+    /// Produce a record, waiting for the broker's acknowledgement
+    async fn produce(&self, topic: &str, key: &str, payload: Vec<u8>) -> anyhow::Result<()>;
+
+    /// Wait for the next batch of consumed records; empty when the poll timed out
+    async fn poll(&self) -> anyhow::Result<Vec<KafkaRecord>>;
+
+    /// Commit, per partition, the offset of the next record to consume
+    async fn commit(&self, offsets: &[KafkaOffset]) -> anyhow::Result<()>;
+}
+
+/// A consumed record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaRecord {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub payload: Vec<u8>,
+}
+
+/// A committed consumer position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaOffset {
+    pub topic: String,
+    pub partition: i32,
+    /// Offset of the next record to consume
+    pub offset: i64,
+}
+
+impl KafkaRecord {
+    /// Position after this record
+    fn next_offset(&self) -> KafkaOffset {
+        KafkaOffset {
+            topic: self.topic.clone(),
+            partition: self.partition,
+            offset: self.offset + 1,
+        }
+    }
+}
+
+/// Forwards named events to and from Kafka
+pub struct KafkaTransport {
+    client: Arc<dyn KafkaClient>,
+    settings: KafkaSettings,
+    instance: String,
+}
+
+impl KafkaTransport {
+    /// `instance` is the `EventBus::instance` of this process
+    pub fn new(client: Arc<dyn KafkaClient>, settings: KafkaSettings, instance: &str) -> Self {
+        Self {
+            client,
+            settings,
+            instance: instance.to_string(),
+        }
+    }
+
+    /// Emit a record on the bus; undecodable records are skipped so they cannot block
+    /// the partition
+    fn deliver(&self, bus: &EventBus, record: &KafkaRecord) {
+        match transport::decode(self.name(), &self.instance, &record.payload) {
+            Ok(Some(event)) => {
+                bus.publish(event);
+            }
+            Ok(None) => {}
+            Err(error) => tracing::warn!(
+                topic = %record.topic,
+                partition = record.partition,
+                offset = record.offset,
+                error = format!("{:#}", error),
+                "skipping undecodable Kafka record"
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl EventTransport for KafkaTransport {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn send(&self, event: &NamedEvent) -> anyhow::Result<()> {
+        let topic = self.settings.topic_for(&event.name);
+        self.client
+            .produce(topic, &event.name, transport::encode(event, &self.instance))
+            .await
+    }
+
+    async fn receive(&self, bus: Arc<EventBus>) -> anyhow::Result<()> {
+        if self.settings.consume.is_empty() {
+            return Ok(());
+        }
+        loop {
+            let records = self.client.poll().await?;
+            let mut batch: HashMap<(String, i32), KafkaOffset> = HashMap::new();
+            for record in &records {
+                self.deliver(&bus, record);
+                match self.settings.commit {
+                    KafkaCommit::Auto => {}
+                    KafkaCommit::AfterRecord => {
+                        self.client.commit(&[record.next_offset()]).await?;
+                    }
+                    KafkaCommit::AfterBatch => {
+                        let next = record.next_offset();
+                        batch
+                            .entry((next.topic.clone(), next.partition))
+                            .and_modify(|offset| offset.offset = offset.offset.max(next.offset))
+                            .or_insert(next);
+                    }
+                }
+            }
+            if !batch.is_empty() {
+                let mut offsets: Vec<KafkaOffset> = batch.into_values().collect();
+                offsets.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+                self.client.commit(&offsets).await?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Records consumed once, then the broker goes away
+    #[derive(Default)]
+    struct FakeKafka {
+        produced: Mutex<Vec<(String, String, Vec<u8>)>>,
+        pending: Mutex<Vec<Vec<KafkaRecord>>>,
+        commits: Mutex<Vec<Vec<KafkaOffset>>>,
+    }
+
+    #[async_trait]
+    impl KafkaClient for FakeKafka {
+        async fn produce(&self, topic: &str, key: &str, payload: Vec<u8>) -> anyhow::Result<()> {
+            self.produced
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), key.to_string(), payload));
+            Ok(())
+        }
+
+        async fn poll(&self) -> anyhow::Result<Vec<KafkaRecord>> {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.is_empty() {
+                anyhow::bail!("broker disconnected");
+            }
+            Ok(pending.remove(0))
+        }
+
+        async fn commit(&self, offsets: &[KafkaOffset]) -> anyhow::Result<()> {
+            self.commits.lock().unwrap().push(offsets.to_vec());
+            Ok(())
+        }
+    }
+
+    fn record(partition: i32, offset: i64, payload: &[u8]) -> KafkaRecord {
+        KafkaRecord {
+            topic: "atlas.books".to_string(),
+            partition,
+            offset,
+            payload: payload.to_vec(),
+        }
+    }
+
+    fn transport(client: Arc<FakeKafka>, commit: KafkaCommit) -> KafkaTransport {
+        let settings = KafkaSettings {
+            enabled: true,
+            brokers: vec!["kafka:9092".to_string()],
+            topics: HashMap::from([("books.".to_string(), "atlas.books".to_string())]),
+            consume: vec!["atlas.books".to_string()],
+            commit,
+            ..KafkaSettings::default()
+        };
+        KafkaTransport::new(client, settings, "api-1")
+    }
+
+    #[tokio::test]
+    async fn test_send_produces_envelope_to_mapped_topic() {
+        let client = Arc::new(FakeKafka::default());
+        let transport = transport(client.clone(), KafkaCommit::AfterBatch);
+
+        transport
+            .send(&NamedEvent {
+                name: "books.created".into(),
+                payload: Arc::new(json!({ "id": 7 })),
+                version: None,
+                origin: None,
+                tenant: None,
+            })
+            .await
+            .unwrap();
+
+        let produced = client.produced.lock().unwrap();
+        assert_eq!(produced[0].0, "atlas.books");
+        assert_eq!(produced[0].1, "books.created");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&produced[0].2).unwrap(),
+            json!({ "event": "books.created", "payload": { "id": 7 }, "source": "api-1" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receive_emits_records_and_commits_each_batch() {
+        let client = Arc::new(FakeKafka::default());
+        let created = br#"{"event":"books.created","payload":{"id":7},"source":"api-2"}"#;
+        let own = br#"{"event":"books.created","payload":{"id":8},"source":"api-1"}"#;
+        client.pending.lock().unwrap().push(vec![
+            record(0, 10, created),
+            record(1, 4, b"garbage"),
+            record(1, 5, own),
+            record(0, 11, created),
+        ]);
+        let bus = Arc::new(EventBus::new(16));
+        let mut received = bus.subscribe::<NamedEvent>();
+
+        let error = transport(client.clone(), KafkaCommit::AfterBatch)
+            .receive(bus.clone())
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("disconnected"));
+        let event = received.try_recv().unwrap();
+        assert_eq!(&*event.name, "books.created");
+        assert_eq!(event.origin, Some("kafka"));
+        assert!(received.try_recv().is_some());
+        assert!(received.try_recv().is_none());
+        assert_eq!(
+            *client.commits.lock().unwrap(),
+            vec![vec![
+                KafkaOffset {
+                    topic: "atlas.books".to_string(),
+                    partition: 0,
+                    offset: 12
+                },
+                KafkaOffset {
+                    topic: "atlas.books".to_string(),
+                    partition: 1,
+                    offset: 6
+                },
+            ]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_strategies() {
+        for (commit, commits) in [
+            (KafkaCommit::Auto, 0),
+            (KafkaCommit::AfterRecord, 2),
+            (KafkaCommit::AfterBatch, 1),
+        ] {
+            let client = Arc::new(FakeKafka::default());
+            client
+                .pending
+                .lock()
+                .unwrap()
+                .push(vec![record(0, 0, b"{}"), record(0, 1, b"{}")]);
+
+            transport(client.clone(), commit)
+                .receive(Arc::new(EventBus::new(16)))
+                .await
+                .unwrap_err();
+
+            assert_eq!(
+                client.commits.lock().unwrap().len(),
+                commits,
+                "{:?}",
+                commit
+            );
+        }
+    }
+}
@@ -1,6 +1,472 @@
-//! Placeholder event bus implementation.
+//! In-process publish/subscribe for ATLAS modules
+//!
+//! The `events` core module publishes an `EventBus` resource. Any `Clone + Send + Sync`
+//! type is an event: each type gets its own broadcast channel, so subscribers only see the
+//! events they asked for. Delivery is best effort; nothing is persisted, and a subscriber
+//! falling more than `events.capacity` events behind misses the oldest ones.
+//!
+//! Events can also be emitted by name with a JSON payload, for the handlers modules declare
+//! through `Module::event_handlers`; the events module runs those while it is started.
+//! Configured transports, Kafka or Redis, carry named events between processes.
+//!
+//! Modules declare the events they emit through `Module::event_schemas`; once the events
+//! module has started, emitted payloads are checked against the declared schemas.
+//!
+//! Failing handlers are retried with backoff and then kept as dead letters, which
+//! operators can list and re-drive through the events module's admin routes.
+//!
+//! Modules map named events to the cache keys they make stale through
+//! `Module::cache_invalidations`; with a `CacheInvalidator` registered, the events module
+//! drops those keys whenever the event is emitted.
+//!
+//! Multi-step workflows that undo their completed steps on failure run as [`saga`]s,
+//! recorded so they resume after a restart and started by the events that trigger them.
+//!
+//! Kernel lifecycle events (`kernel.module_started`, `kernel.module_failed`,
+//! `kernel.migration_applied` and `kernel.shutdown_begun`) are published on the bus both
+//! as `atlas_kernel::LifecycleEvent` and by name, once the events module is initialized.
 
-/// Publish an event (stub).
-pub fn publish(_event: &str) {
-    tracing::info!(target: "atlas-events", "event publishing pending implementation");
+pub mod cache;
+mod catalog;
+mod delivery;
+mod dlq;
+pub mod kafka;
+mod lifecycle;
+mod module;
+pub mod redis;
+pub mod saga;
+pub mod transport;
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use tokio::sync::broadcast;
+
+use atlas_kernel::{tenant::TenantId, AppContext, InitCtx};
+
+pub use cache::CacheInvalidator;
+pub use catalog::{CatalogEntry, EventCatalog};
+pub use delivery::{Delivery, DeliveryMetrics, Redrive, ReplayReport};
+pub use dlq::{DeadLetter, DeadLetterQuery, DeadLetterStore, MemoryDeadLetterStore};
+pub use module::{create_module, EventsModule};
+pub use saga::{
+    MemorySagaStore, Saga, SagaInstance, SagaState, SagaStore, Sagas, SurrealSagaStore,
+};
+pub use transport::EventTransport;
+
+/// Anything that can be published on the bus
+pub trait Event: Clone + Send + Sync + 'static {}
+
+impl<T: Clone + Send + Sync + 'static> Event for T {}
+
+/// An event emitted by name, as delivered to `Module::event_handlers`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedEvent {
+    pub name: Arc<str>,
+    pub payload: Arc<serde_json::Value>,
+    /// Declared schema version the payload matched; `None` for undeclared events
+    pub version: Option<u32>,
+    /// Transport the event arrived through; `None` when emitted in this process
+    pub origin: Option<&'static str>,
+    /// Tenant whose `atlas_kernel::tenant::scope` the event was emitted in
+    pub tenant: Option<TenantId>,
+}
+
+/// A payload type for one version of a declared event
+pub trait VersionedEvent: serde::Serialize {
+    const NAME: &'static str;
+    const VERSION: u32;
+}
+
+/// Typed in-process event bus
+pub struct EventBus {
+    instance: String,
+    capacity: usize,
+    channels: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+    catalog: RwLock<Arc<EventCatalog>>,
+}
+
+impl EventBus {
+    /// Create a bus buffering up to `capacity` events per event type
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            instance: atlas_kernel::id::ulid().to_string().to_lowercase(),
+            capacity: capacity.max(1),
+            channels: RwLock::new(HashMap::new()),
+            catalog: RwLock::default(),
+        }
+    }
+
+    /// Declared events that emitted payloads are checked against
+    pub fn catalog(&self) -> Arc<EventCatalog> {
+        self.catalog
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_catalog(&self, catalog: EventCatalog) {
+        *self
+            .catalog
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(catalog);
+    }
+
+    /// Identifies this process in events sent through transports
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    fn sender<E: Event>(&self) -> broadcast::Sender<E> {
+        let key = TypeId::of::<E>();
+        if let Some(sender) = self
+            .channels
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&key)
+        {
+            return downcast::<E>(sender).clone();
+        }
+        let mut channels = self
+            .channels
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let sender = channels
+            .entry(key)
+            .or_insert_with(|| Box::new(broadcast::channel::<E>(self.capacity).0));
+        downcast::<E>(sender).clone()
+    }
+
+    /// Deliver `event` to every current subscriber of its type, returning how many there are
+    ///
+    /// Publishing without subscribers is not an error; the event is dropped.
+    pub fn publish<E: Event>(&self, event: E) -> usize {
+        let delivered = self.sender::<E>().send(event).unwrap_or(0);
+        tracing::trace!(event = type_name::<E>(), delivered, "event published");
+        delivered
+    }
+
+    /// Emit the event `name` with `payload` serialized as JSON, returning how many
+    /// named-event subscribers there are
+    ///
+    /// A declared event's payload must match the schema of its latest version. Events
+    /// emitted inside a tenant scope carry that tenant.
+    pub fn emit(&self, name: &str, payload: impl serde::Serialize) -> anyhow::Result<usize> {
+        self.emit_named(name, None, payload)
+    }
+
+    /// Emit a specific declared version of the event `name`
+    pub fn emit_version(
+        &self,
+        name: &str,
+        version: u32,
+        payload: impl serde::Serialize,
+    ) -> anyhow::Result<usize> {
+        self.emit_named(name, Some(version), payload)
+    }
+
+    /// Emit a typed event as the version its type declares
+    pub fn emit_event<E: VersionedEvent>(&self, event: &E) -> anyhow::Result<usize> {
+        self.emit_named(E::NAME, Some(E::VERSION), event)
+    }
+
+    fn emit_named(
+        &self,
+        name: &str,
+        version: Option<u32>,
+        payload: impl serde::Serialize,
+    ) -> anyhow::Result<usize> {
+        let payload = serde_json::to_value(payload)
+            .with_context(|| format!("failed to serialize the payload of event '{}'", name))?;
+        let version = self.catalog().validate(name, version, &payload)?;
+        Ok(self.publish(NamedEvent {
+            name: name.into(),
+            payload: Arc::new(payload),
+            version,
+            origin: None,
+            tenant: atlas_kernel::tenant::current(),
+        }))
+    }
+
+    /// Receive every `E` published from now on
+    pub fn subscribe<E: Event>(&self) -> Subscription<E> {
+        Subscription {
+            receiver: self.sender::<E>().subscribe(),
+        }
+    }
+
+    /// Number of live subscriptions to `E`
+    pub fn subscribers<E: Event>(&self) -> usize {
+        self.channels
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&TypeId::of::<E>())
+            .map_or(0, |sender| downcast::<E>(sender).receiver_count())
+    }
+
+    /// Run `handler` for every `E` in a background task owned by the calling module
+    ///
+    /// The subscription starts immediately, so events published after this returns are not
+    /// missed. A failing handler is logged and the task keeps listening.
+    pub fn listen<E, F, Fut>(&self, ctx: &InitCtx, handler: F)
+    where
+        E: Event,
+        F: Fn(E) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let mut subscription = self.subscribe::<E>();
+        ctx.spawn(format!("listen {}", type_name::<E>()), async move {
+            while let Some(event) = subscription.recv().await {
+                if let Err(error) = handler(event).await {
+                    tracing::warn!(
+                        event = type_name::<E>(),
+                        error = format!("{:#}", error),
+                        "event handler failed"
+                    );
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("instance", &self.instance)
+            .field("capacity", &self.capacity)
+            .field(
+                "event_types",
+                &self
+                    .channels
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .len(),
+            )
+            .finish()
+    }
+}
+
+fn downcast<E: Event>(sender: &Box<dyn Any + Send + Sync>) -> &broadcast::Sender<E> {
+    sender
+        .downcast_ref::<broadcast::Sender<E>>()
+        .expect("event channels are keyed by their event type")
+}
+
+/// Events of one type, in publication order
+pub struct Subscription<E> {
+    receiver: broadcast::Receiver<E>,
+}
+
+impl<E: Event> Subscription<E> {
+    /// The next event, or `None` once the bus is gone
+    ///
+    /// Events missed by falling behind are skipped with a warning.
+    pub async fn recv(&mut self) -> Option<E> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => lagged::<E>(missed),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The next event if one is already waiting
+    pub fn try_recv(&mut self) -> Option<E> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::TryRecvError::Lagged(missed)) => lagged::<E>(missed),
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+fn lagged<E>(missed: u64) {
+    tracing::warn!(
+        event = type_name::<E>(),
+        missed,
+        "event subscriber fell behind; increase events.capacity"
+    );
+}
+
+/// Access to the event bus from module contexts
+pub trait EventsExt {
+    /// The bus published by the events module
+    fn events(&self) -> anyhow::Result<Arc<EventBus>>;
+
+    /// The saga runner published by the events module
+    fn sagas(&self) -> anyhow::Result<Arc<Sagas>>;
+}
+
+impl EventsExt for InitCtx {
+    fn events(&self) -> anyhow::Result<Arc<EventBus>> {
+        self.resources.require::<EventBus>()
+    }
+
+    fn sagas(&self) -> anyhow::Result<Arc<Sagas>> {
+        self.resources.require::<Sagas>()
+    }
+}
+
+impl EventsExt for AppContext {
+    fn events(&self) -> anyhow::Result<Arc<EventBus>> {
+        self.require::<EventBus>()
+    }
+
+    fn sagas(&self) -> anyhow::Result<Arc<Sagas>> {
+        self.require::<Sagas>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use atlas_kernel::{settings::Settings, Resources};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct BookCreated(u32);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct BookDeleted(u32);
+
+    #[tokio::test]
+    async fn test_subscribers_only_receive_their_event_type() {
+        let bus = EventBus::new(16);
+        let mut created = bus.subscribe::<BookCreated>();
+        let mut deleted = bus.subscribe::<BookDeleted>();
+
+        assert_eq!(bus.publish(BookCreated(1)), 1);
+        assert_eq!(bus.publish(BookDeleted(2)), 1);
+        assert_eq!(bus.publish(BookCreated(3)), 1);
+
+        assert_eq!(created.recv().await, Some(BookCreated(1)));
+        assert_eq!(created.recv().await, Some(BookCreated(3)));
+        assert_eq!(created.try_recv(), None);
+        assert_eq!(deleted.recv().await, Some(BookDeleted(2)));
+    }
+
+    #[test]
+    fn test_publish_without_subscribers_is_dropped() {
+        let bus = EventBus::new(16);
+        assert_eq!(bus.publish(BookCreated(1)), 0);
+
+        let mut late = bus.subscribe::<BookCreated>();
+        assert_eq!(bus.subscribers::<BookCreated>(), 1);
+        assert_eq!(late.try_recv(), None);
+        drop(late);
+        assert_eq!(bus.subscribers::<BookCreated>(), 0);
+    }
+
+    #[test]
+    fn test_lagging_subscriber_skips_oldest_events() {
+        let bus = EventBus::new(2);
+        let mut subscription = bus.subscribe::<BookCreated>();
+        for id in 1..=4 {
+            bus.publish(BookCreated(id));
+        }
+
+        assert_eq!(subscription.try_recv(), Some(BookCreated(3)));
+        assert_eq!(subscription.try_recv(), Some(BookCreated(4)));
+        assert_eq!(subscription.try_recv(), None);
+    }
+
+    #[derive(serde::Serialize)]
+    struct BookPublished {
+        id: u32,
+    }
+
+    impl VersionedEvent for BookPublished {
+        const NAME: &'static str = "books.published";
+        const VERSION: u32 = 1;
+    }
+
+    #[test]
+    fn test_declared_events_are_validated_when_emitted() {
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe::<NamedEvent>();
+        bus.set_catalog(
+            EventCatalog::new(&[(
+                "books",
+                atlas_kernel::EventSchema {
+                    name: "books.published",
+                    version: 1,
+                    description: None,
+                    schema: serde_json::json!({
+                        "type": "object",
+                        "required": ["id"],
+                        "properties": { "id": { "type": "integer" } }
+                    }),
+                },
+            )])
+            .unwrap(),
+        );
+
+        bus.emit_event(&BookPublished { id: 7 }).unwrap();
+        assert!(bus
+            .emit("books.published", serde_json::json!({ "id": "7" }))
+            .is_err());
+        assert!(bus
+            .emit_version("books.published", 2, BookPublished { id: 7 })
+            .is_err());
+        bus.emit("books.archived", 7).unwrap();
+
+        let published = events.try_recv().unwrap();
+        assert_eq!(published.version, Some(1));
+        assert_eq!(events.try_recv().unwrap().version, None);
+        assert!(events.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_emit_records_the_current_tenant() {
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe::<NamedEvent>();
+        let acme = TenantId::new("acme").unwrap();
+
+        bus.emit("books.archived", 7).unwrap();
+        atlas_kernel::tenant::scope(acme.clone(), async {
+            bus.emit("books.archived", 8).unwrap();
+        })
+        .await;
+
+        assert_eq!(events.try_recv().unwrap().tenant, None);
+        assert_eq!(events.try_recv().unwrap().tenant, Some(acme));
+    }
+
+    #[tokio::test]
+    async fn test_listen_runs_handler_in_module_task() {
+        let resources = Arc::new(Resources::new());
+        resources.insert(EventBus::new(16));
+        let ctx = InitCtx::new(Arc::new(Settings::default()), resources);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (done, mut finished) = tokio::sync::mpsc::unbounded_channel();
+
+        let bus = ctx.events().unwrap();
+        let handler_seen = seen.clone();
+        bus.listen(&ctx, move |event: BookCreated| {
+            let seen = handler_seen.clone();
+            let done = done.clone();
+            async move {
+                if event.0 == 0 {
+                    anyhow::bail!("book 0 does not exist");
+                }
+                seen.lock().unwrap().push(event.0);
+                done.send(()).ok();
+                Ok(())
+            }
+        });
+        bus.publish(BookCreated(0));
+        bus.publish(BookCreated(7));
+
+        finished.recv().await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![7]);
+    }
 }
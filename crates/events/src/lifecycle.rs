@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use serde_json::json;
+
+use atlas_kernel::{EventSchema, LifecycleEvent, LifecycleListener};
+
+use crate::EventBus;
+
+/// Publishes kernel lifecycle events on the bus
+///
+/// Each event is published as a typed `LifecycleEvent` and emitted by name, so both
+/// `bus.subscribe::<LifecycleEvent>()` and `Module::event_handlers` see it.
+pub(crate) struct LifecyclePublisher {
+    pub(crate) bus: Arc<EventBus>,
+}
+
+impl LifecycleListener for LifecyclePublisher {
+    fn on_lifecycle(&self, event: &LifecycleEvent) {
+        self.bus.publish(event.clone());
+        if let Err(error) = self.bus.emit(event.name(), event) {
+            tracing::warn!(
+                event = event.name(),
+                error = format!("{:#}", error),
+                "failed to emit lifecycle event"
+            );
+        }
+    }
+}
+
+/// Schemas of the lifecycle events, declared by the events module on the kernel's behalf
+pub(crate) fn schemas() -> Vec<EventSchema> {
+    let module = json!({ "type": "string" });
+    let kind = json!({ "type": "string", "enum": ["core", "custom"] });
+    vec![
+        EventSchema {
+            name: LifecycleEvent::MODULE_STARTED,
+            version: 1,
+            description: Some("A module finished starting"),
+            schema: json!({
+                "type": "object",
+                "required": ["module", "kind"],
+                "properties": { "module": module, "kind": kind }
+            }),
+        },
+        EventSchema {
+            name: LifecycleEvent::MODULE_FAILED,
+            version: 1,
+            description: Some("A module failed to initialize, start or stop"),
+            schema: json!({
+                "type": "object",
+                "required": ["module", "kind", "phase", "error"],
+                "properties": {
+                    "module": module,
+                    "kind": kind,
+                    "phase": { "type": "string", "enum": ["init", "start", "stop", "config_change"] },
+                    "error": { "type": "string" }
+                }
+            }),
+        },
+        EventSchema {
+            name: LifecycleEvent::MIGRATION_APPLIED,
+            version: 1,
+            description: Some("A module's migration was applied"),
+            schema: json!({
+                "type": "object",
+                "required": ["module", "migration"],
+                "properties": { "module": module, "migration": { "type": "string" } }
+            }),
+        },
+        EventSchema {
+            name: LifecycleEvent::SHUTDOWN_BEGUN,
+            version: 1,
+            description: Some("Shutdown began; started modules stop next"),
+            schema: json!({
+                "type": "object",
+                "required": ["modules"],
+                "properties": { "modules": { "type": "integer" } }
+            }),
+        },
+    ]
+}
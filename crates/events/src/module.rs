@@ -0,0 +1,917 @@
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde_json::json;
+
+use atlas_http::{error::AppError, meta::authorize_admin};
+use atlas_kernel::{
+    EventSchema, InitCtx, LifecycleListener, Migration, Module, ModuleCacheInvalidations,
+    ModuleEventHandlers, ModuleEventSchemas, ModuleHealth, RouteSecurity, SecurityScheme,
+};
+
+use crate::cache::{self, CacheInvalidator};
+use crate::delivery::{Delivery, Redrive};
+use crate::dlq::{DeadLetter, DeadLetterQuery, DeadLetterStore, MemoryDeadLetterStore};
+use crate::kafka::{KafkaConnector, KafkaTransport};
+use crate::lifecycle::{self, LifecyclePublisher};
+use crate::redis::{RedisConnector, RedisTransport};
+use crate::saga::{self, MemorySagaStore, SagaStore, Sagas, SurrealSagaStore};
+use crate::transport::{self, EventTransport};
+use crate::{EventBus, EventCatalog, NamedEvent};
+
+/// Core module publishing the `EventBus` resource
+///
+/// Modules publishing or subscribing during `init` should list `events` in `depends_on`.
+/// A bus registered by the application before startup is kept.
+///
+/// On start it loads every module's `event_schemas` into the bus's catalog, subscribes
+/// their `event_handlers`, each in its own task, and starts the configured transports; the
+/// tasks belong to this module, so they are cancelled when it stops. A failing handler is
+/// retried under `[events.retry]`, holding up that handler's later events meanwhile, and
+/// then dead-lettered; with `admin.token` set, operators list and re-drive dead letters
+/// under `/api/events/dead_letters`.
+///
+/// The Kafka and Redis transports connect through the registered
+/// `Arc<dyn KafkaConnector>` and `Arc<dyn RedisConnector>` or, with the `kafka` and
+/// `redis` features, `RdKafkaConnector` and `RedisRsConnector`.
+///
+/// When modules declare `cache_invalidations`, the registered `Arc<dyn CacheInvalidator>`
+/// drops their keys as the events are emitted.
+///
+/// It also publishes the `Sagas` runner, recording runs in the registered
+/// `Arc<dyn SagaStore>`, in the `saga` table when the application has a
+/// `QueryExecutor`, or else in memory. On start, registered sagas begin listening for
+/// their trigger events and runs a previous process left unfinished are resumed.
+///
+/// Unless the application registered its own `Arc<dyn LifecycleListener>`, kernel
+/// lifecycle events from init on are published on the bus; the events module declares
+/// their schemas.
+#[derive(Default)]
+pub struct EventsModule {
+    bus: OnceLock<Arc<EventBus>>,
+    transports: OnceLock<Vec<Arc<dyn EventTransport>>>,
+    delivery: OnceLock<Arc<Delivery>>,
+    sagas: OnceLock<Arc<Sagas>>,
+    admin_token: OnceLock<Option<String>>,
+}
+
+#[async_trait]
+impl Module for EventsModule {
+    fn name(&self) -> &'static str {
+        "events"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("In-process event bus")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let bus = match ctx.resources.get::<EventBus>() {
+            Some(bus) => {
+                tracing::debug!("event bus already registered");
+                bus
+            }
+            None => {
+                let capacity = ctx.settings.events.capacity;
+                let bus = Arc::new(EventBus::new(capacity));
+                ctx.resources.insert_arc(bus.clone());
+                tracing::info!(capacity, instance = bus.instance(), "event bus ready");
+                bus
+            }
+        };
+
+        let mut transports: Vec<Arc<dyn EventTransport>> = Vec::new();
+        let kafka = &ctx.settings.events.kafka;
+        if kafka.enabled {
+            let connector = match ctx.resources.get::<Arc<dyn KafkaConnector>>() {
+                Some(connector) => connector.as_ref().clone(),
+                #[cfg(feature = "kafka")]
+                None => Arc::new(crate::kafka::RdKafkaConnector::new()),
+                #[cfg(not(feature = "kafka"))]
+                None => anyhow::bail!(
+                    "events.kafka is enabled but no KafkaConnector resource is registered; \
+                     register one or enable the kafka feature"
+                ),
+            };
+            let client = connector
+                .connect(kafka)
+                .await
+                .context("failed to connect to Kafka")?;
+            tracing::info!(
+                brokers = ?kafka.brokers,
+                consume = ?kafka.consume,
+                "Kafka event transport enabled"
+            );
+            transports.push(Arc::new(KafkaTransport::new(
+                client,
+                kafka.clone(),
+                bus.instance(),
+            )));
+        }
+        let redis = &ctx.settings.events.redis;
+        if redis.enabled {
+            let connector = match ctx.resources.get::<Arc<dyn RedisConnector>>() {
+                Some(connector) => connector.as_ref().clone(),
+                #[cfg(feature = "redis")]
+                None => Arc::new(crate::redis::RedisRsConnector::new()),
+                #[cfg(not(feature = "redis"))]
+                None => anyhow::bail!(
+                    "events.redis is enabled but no RedisConnector resource is registered; \
+                     register one or enable the redis feature"
+                ),
+            };
+            let client = connector
+                .connect(&redis.url)
+                .await
+                .context("failed to connect to Redis")?;
+            tracing::info!(
+                mode = ?redis.mode,
+                channel = %redis.channel,
+                "Redis event transport enabled"
+            );
+            transports.push(Arc::new(RedisTransport::new(
+                client,
+                redis.clone(),
+                bus.instance(),
+            )));
+        }
+        self.transports
+            .set(transports)
+            .map_err(|_| anyhow::anyhow!("events module initialized twice"))?;
+        if ctx.resources.get::<Arc<dyn LifecycleListener>>().is_none() {
+            ctx.resources
+                .insert::<Arc<dyn LifecycleListener>>(Arc::new(LifecyclePublisher {
+                    bus: bus.clone(),
+                }));
+        }
+        let _ = self.bus.set(bus.clone());
+
+        let store = match ctx.resources.get::<Arc<dyn DeadLetterStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => {
+                tracing::debug!(
+                    "no persistent dead letter store registered; keeping them in memory"
+                );
+                Arc::new(MemoryDeadLetterStore::new())
+            }
+        };
+        let delivery = Arc::new(Delivery::new(
+            ctx.settings.events.retry.policy(),
+            store,
+            ctx.clock.clone(),
+        ));
+        ctx.resources.insert_arc(delivery.clone());
+        let _ = self.delivery.set(delivery);
+
+        let store = atlas_db::select_store::<dyn SagaStore>(
+            &ctx.resources,
+            "saga",
+            |db| Arc::new(SurrealSagaStore::new(db)),
+            || Arc::new(MemorySagaStore::new()),
+        );
+        let sagas = Arc::new(Sagas::new(bus, store, ctx.clock.clone()));
+        ctx.resources.insert_arc(sagas.clone());
+        let _ = self.sagas.set(sagas);
+        self.admin_token
+            .get_or_init(|| ctx.settings.admin.token.clone());
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let bus = ctx.resources.require::<EventBus>()?;
+        if let Some(declared) = ctx.resources.get::<ModuleEventSchemas>() {
+            let catalog = EventCatalog::new(&declared.schemas)?;
+            tracing::info!(events = catalog.entries().len(), "event catalog loaded");
+            bus.set_catalog(catalog);
+        }
+        for transport in self.transports.get().into_iter().flatten() {
+            transport::run(ctx, bus.clone(), transport.clone());
+        }
+        if let Some(sagas) = self.sagas.get() {
+            saga::run(ctx, sagas.clone());
+        }
+        if let Some(declared) = ctx.resources.get::<ModuleCacheInvalidations>() {
+            if !declared.invalidations.is_empty() {
+                match ctx.resources.get::<Arc<dyn CacheInvalidator>>() {
+                    Some(invalidator) => {
+                        cache::run(
+                            ctx,
+                            &bus,
+                            invalidator.as_ref().clone(),
+                            declared.invalidations.clone(),
+                        );
+                        tracing::info!(
+                            invalidations = declared.invalidations.len(),
+                            "cache invalidation subscribed"
+                        );
+                    }
+                    None => tracing::warn!(
+                        "modules declare cache invalidations but no CacheInvalidator resource is registered"
+                    ),
+                }
+            }
+        }
+
+        let Some(declared) = ctx.resources.get::<ModuleEventHandlers>() else {
+            return Ok(());
+        };
+        let delivery = self
+            .delivery
+            .get()
+            .context("events module started before init")?;
+        delivery.set_handlers(declared.handlers.clone());
+        for (module, handler) in declared.handlers.iter().cloned() {
+            let mut subscription = bus.subscribe::<NamedEvent>();
+            let delivery = delivery.clone();
+            ctx.spawn(format!("{} on {}", module, handler.event), async move {
+                while let Some(event) = subscription.recv().await {
+                    if *event.name == *handler.event {
+                        delivery.deliver(module, &handler, &event).await;
+                    }
+                }
+                Ok(())
+            });
+        }
+        if !declared.handlers.is_empty() {
+            tracing::info!(
+                handlers = declared.handlers.len(),
+                "event handlers subscribed"
+            );
+        }
+        Ok(())
+    }
+
+    fn event_schemas(&self) -> Vec<EventSchema> {
+        lifecycle::schemas()
+            .into_iter()
+            .chain(saga::schemas())
+            .collect()
+    }
+
+    async fn health(&self) -> ModuleHealth {
+        match self.delivery.get() {
+            Some(delivery) => ModuleHealth::healthy()
+                .with_details(json!({ "handlers": delivery.metrics().snapshot() })),
+            None => ModuleHealth::healthy(),
+        }
+    }
+
+    fn routes(&self) -> Router {
+        let Some(bus) = self.bus.get() else {
+            return Router::new();
+        };
+        let router = Router::new()
+            .route("/catalog", get(catalog))
+            .with_state(bus.clone());
+        match (
+            self.delivery.get(),
+            self.admin_token.get().cloned().flatten(),
+        ) {
+            (Some(delivery), Some(admin_token)) => router.merge(
+                Router::new()
+                    .route("/dead_letters", get(list_dead_letters))
+                    .route("/dead_letters/{id}", delete(delete_dead_letter))
+                    .route("/dead_letters/{id}/redrive", post(redrive_dead_letter))
+                    .with_state(DeadLetterState {
+                        delivery: delivery.clone(),
+                        admin_token: Arc::from(admin_token),
+                    }),
+            ),
+            _ => router,
+        }
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        [
+            "/dead_letters",
+            "/dead_letters/{id}",
+            "/dead_letters/{id}/redrive",
+        ]
+        .into_iter()
+        .map(|path| RouteSecurity {
+            method: "*",
+            path,
+            schemes: &[SecurityScheme::AdminToken],
+            permission: None,
+        })
+        .collect()
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![
+            Migration {
+                id: "001_create_dead_letter",
+                up: "DEFINE TABLE dead_letter SCHEMAFULL;
+DEFINE FIELD event ON dead_letter TYPE string;
+DEFINE FIELD version ON dead_letter TYPE option<int>;
+DEFINE FIELD payload ON dead_letter FLEXIBLE TYPE any;
+DEFINE FIELD module ON dead_letter TYPE string;
+DEFINE FIELD error ON dead_letter TYPE string;
+DEFINE FIELD attempts ON dead_letter TYPE int;
+DEFINE FIELD failed_at ON dead_letter TYPE datetime;
+DEFINE INDEX dead_letter_failed_at ON dead_letter FIELDS failed_at;
+DEFINE INDEX dead_letter_event ON dead_letter FIELDS event;",
+                down: Some("REMOVE TABLE dead_letter;"),
+            },
+            Migration {
+                id: "002_create_saga",
+                up: "DEFINE TABLE saga SCHEMAFULL;
+DEFINE FIELD saga ON saga TYPE string;
+DEFINE FIELD state ON saga TYPE string;
+DEFINE FIELD data ON saga FLEXIBLE TYPE any;
+DEFINE FIELD completed ON saga TYPE int;
+DEFINE FIELD error ON saga TYPE option<string>;
+DEFINE FIELD tenant ON saga TYPE option<string>;
+DEFINE FIELD started_at ON saga TYPE datetime;
+DEFINE FIELD updated_at ON saga TYPE datetime;
+DEFINE INDEX saga_state ON saga FIELDS state;",
+                down: Some("REMOVE TABLE saga;"),
+            },
+        ]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let query = |name: &str, schema: serde_json::Value, description: &str| json!({ "name": name, "in": "query", "required": false, "schema": schema, "description": description });
+        let id =
+            json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } });
+        Some(json!({
+            "tags": [
+                { "name": "Events", "description": "Events modules emit and their payload schemas" }
+            ],
+            "paths": {
+                "/catalog": {
+                    "get": {
+                        "summary": "List declared events",
+                        "description": "Every declared version of every event, ordered by name then version.",
+                        "tags": ["Events"],
+                        "responses": {
+                            "200": {
+                                "description": "Event catalog",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": { "$ref": "#/components/schemas/EventCatalogEntry" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/dead_letters": {
+                    "get": {
+                        "summary": "List dead letters",
+                        "description": "Events whose handler failed every retry, most recent failure first. Requires `admin.token`.",
+                        "tags": ["Events"],
+                        "parameters": [
+                            query("event", json!({ "type": "string" }), "Event name"),
+                            query("module", json!({ "type": "string" }), "Module whose handler failed"),
+                            query("since", json!({ "type": "string", "format": "date-time" }), "Failed at or after this time"),
+                            query("until", json!({ "type": "string", "format": "date-time" }), "Failed before this time"),
+                            query("limit", json!({ "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": DeadLetterQuery::DEFAULT_LIMIT }), "Dead letters to return")
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Dead letters",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": { "$ref": "#/components/schemas/DeadLetter" }
+                                        }
+                                    }
+                                }
+                            },
+                            "422": { "description": "Invalid limit" }
+                        }
+                    }
+                },
+                "/dead_letters/{id}": {
+                    "delete": {
+                        "summary": "Discard a dead letter",
+                        "tags": ["Events"],
+                        "parameters": [id.clone()],
+                        "responses": {
+                            "204": { "description": "Discarded" },
+                            "404": { "description": "No such dead letter" }
+                        }
+                    }
+                },
+                "/dead_letters/{id}/redrive": {
+                    "post": {
+                        "summary": "Re-drive a dead letter",
+                        "description": "Run the failed module handler once more. On success the dead letter is removed; otherwise its attempts and error are updated.",
+                        "tags": ["Events"],
+                        "parameters": [id],
+                        "responses": {
+                            "204": { "description": "Handled; the dead letter was removed" },
+                            "404": { "description": "No such dead letter" },
+                            "409": { "description": "The handler failed again or no longer exists" }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "EventCatalogEntry": {
+                        "type": "object",
+                        "required": ["name", "version", "module", "schema"],
+                        "properties": {
+                            "name": { "type": "string", "example": "books.created" },
+                            "version": { "type": "integer", "example": 1 },
+                            "module": { "type": "string", "example": "books" },
+                            "description": { "type": "string" },
+                            "schema": { "type": "object", "description": "JSON Schema of the payload" }
+                        }
+                    },
+                    "DeadLetter": {
+                        "type": "object",
+                        "required": ["id", "event", "payload", "module", "error", "attempts", "failed_at"],
+                        "properties": {
+                            "id": { "type": "string", "example": "dlq_01HV6Y5J8Q4ZK3X2T9R7M1N0PA" },
+                            "event": { "type": "string", "example": "books.created" },
+                            "version": { "type": "integer", "nullable": true },
+                            "payload": {},
+                            "module": { "type": "string", "example": "search" },
+                            "error": { "type": "string" },
+                            "attempts": { "type": "integer", "example": 3 },
+                            "failed_at": { "type": "string", "format": "date-time" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+async fn catalog(State(bus): State<Arc<EventBus>>) -> Json<EventCatalog> {
+    Json(bus.catalog().as_ref().clone())
+}
+
+/// Largest page served by the dead letter endpoint
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Clone)]
+struct DeadLetterState {
+    delivery: Arc<Delivery>,
+    admin_token: Arc<str>,
+}
+
+async fn list_dead_letters(
+    State(state): State<DeadLetterState>,
+    headers: HeaderMap,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<Vec<DeadLetter>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if query
+        .limit
+        .is_some_and(|limit| limit == 0 || limit > MAX_LIMIT)
+    {
+        return Err(AppError::validation(
+            vec![json!({ "field": "limit", "error": "out_of_range" })],
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    Ok(Json(state.delivery.store().list(&query).await?))
+}
+
+async fn redrive_dead_letter(
+    State(state): State<DeadLetterState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    match state.delivery.redrive(&id).await? {
+        Some(Redrive::Delivered) => Ok(StatusCode::NO_CONTENT),
+        Some(Redrive::Failed(letter)) => Err(AppError::conflict(
+            vec![json!({ "attempts": letter.attempts, "error": letter.error })],
+            "event handler failed again",
+        )),
+        Some(Redrive::NoHandler) => Err(AppError::conflict(
+            Vec::new(),
+            "the module no longer handles this event",
+        )),
+        None => Err(AppError::not_found(format!(
+            "dead letter '{}' not found",
+            id
+        ))),
+    }
+}
+
+async fn delete_dead_letter(
+    State(state): State<DeadLetterState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if state.delivery.store().remove(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!(
+            "dead letter '{}' not found",
+            id
+        )))
+    }
+}
+
+/// Create a new instance of the events module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(EventsModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventsExt;
+    use atlas_kernel::{registry::priority, settings::Settings, ModuleRegistry};
+    use serde_json::json;
+
+    #[derive(Clone)]
+    struct Ping;
+
+    /// Forwards the payloads of `books.created` to a channel
+    struct Search(tokio::sync::mpsc::UnboundedSender<serde_json::Value>);
+
+    #[async_trait]
+    impl Module for Search {
+        fn name(&self) -> &'static str {
+            "search"
+        }
+
+        fn event_schemas(&self) -> Vec<atlas_kernel::EventSchema> {
+            vec![atlas_kernel::EventSchema {
+                name: "search.indexed",
+                version: 1,
+                description: Some("A book became searchable"),
+                schema: json!({ "type": "object" }),
+            }]
+        }
+
+        fn event_handlers(&self) -> Vec<atlas_kernel::EventHandler> {
+            let indexed = self.0.clone();
+            vec![atlas_kernel::EventHandler::new(
+                "books.created",
+                move |payload| {
+                    let indexed = indexed.clone();
+                    async move {
+                        indexed.send(payload)?;
+                        Ok(())
+                    }
+                },
+            )]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_publishes_bus_to_dependents() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+
+        let bus = ctx.events().unwrap();
+        let mut subscription = bus.subscribe::<Ping>();
+        assert_eq!(
+            registry
+                .resources()
+                .require::<EventBus>()
+                .unwrap()
+                .publish(Ping),
+            1
+        );
+        assert!(subscription.try_recv().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_declared_handlers_run_between_start_and_stop() {
+        let (indexed, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Search(indexed))).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+
+        let bus = ctx.events().unwrap();
+        assert_eq!(bus.emit("books.deleted", 1).unwrap(), 1);
+        assert_eq!(bus.emit("books.created", json!({ "id": 7 })).unwrap(), 1);
+        assert_eq!(received.recv().await.unwrap(), json!({ "id": 7 }));
+
+        registry.stop_custom_modules().await.unwrap();
+        registry.stop_core_modules().await.unwrap();
+        assert_eq!(registry.tasks().running("events"), 0);
+        // Aborted tasks release their subscriptions once the runtime drops them
+        tokio::task::yield_now().await;
+        assert_eq!(bus.subscribers::<NamedEvent>(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events_are_published() {
+        let (indexed, _received) = tokio::sync::mpsc::unbounded_channel();
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Search(indexed))).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        let bus = ctx.events().unwrap();
+        let mut typed = bus.subscribe::<atlas_kernel::LifecycleEvent>();
+        let mut named = bus.subscribe::<NamedEvent>();
+
+        registry.start_core_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+
+        assert_eq!(
+            typed.try_recv(),
+            Some(atlas_kernel::LifecycleEvent::ModuleStarted {
+                module: "events",
+                kind: atlas_kernel::ModuleKind::Core,
+            })
+        );
+        assert!(matches!(
+            typed.try_recv(),
+            Some(atlas_kernel::LifecycleEvent::ModuleStarted {
+                module: "search",
+                ..
+            })
+        ));
+        let event = named.try_recv().unwrap();
+        assert_eq!(&*event.name, "kernel.module_started");
+        assert_eq!(event.version, Some(1));
+        assert_eq!(named.try_recv().unwrap().payload["module"], "search");
+
+        registry.stop_custom_modules().await.unwrap();
+        registry.stop_core_modules().await.unwrap();
+    }
+
+    /// Caches books under `books:{id}` and their listings under `books:list:`
+    struct Catalog;
+
+    #[async_trait]
+    impl Module for Catalog {
+        fn name(&self) -> &'static str {
+            "catalog"
+        }
+
+        fn cache_invalidations(&self) -> Vec<atlas_kernel::CacheInvalidation> {
+            vec![atlas_kernel::CacheInvalidation::new(
+                "books.updated",
+                &["books:{id}", "books:list:*"],
+            )]
+        }
+    }
+
+    /// Forwards invalidated keys to a channel, prefixes ending in `*`
+    struct Invalidated(tokio::sync::mpsc::UnboundedSender<String>);
+
+    #[async_trait]
+    impl CacheInvalidator for Invalidated {
+        async fn invalidate(&self, key: &str) -> anyhow::Result<()> {
+            Ok(self.0.send(key.to_string())?)
+        }
+
+        async fn invalidate_prefix(&self, prefix: &str) -> anyhow::Result<()> {
+            Ok(self.0.send(format!("{}*", prefix))?)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_declared_cache_keys_invalidated_on_emit() {
+        let (invalidated, mut keys) = tokio::sync::mpsc::unbounded_channel();
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Catalog)).unwrap();
+        registry
+            .resources()
+            .insert::<Arc<dyn CacheInvalidator>>(Arc::new(Invalidated(invalidated)));
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let bus = ctx.events().unwrap();
+        bus.emit("books.created", json!({ "id": 6 })).unwrap();
+        bus.emit("books.updated", json!({ "id": 7 })).unwrap();
+
+        assert_eq!(keys.recv().await.unwrap(), "books:7");
+        assert_eq!(keys.recv().await.unwrap(), "books:list:*");
+        registry.stop_core_modules().await.unwrap();
+        assert!(keys.try_recv().is_err());
+    }
+
+    /// Provisions a tenant's workspace whenever `tenants.created` is emitted
+    struct Provisioning;
+
+    #[async_trait]
+    impl Module for Provisioning {
+        fn name(&self) -> &'static str {
+            "provisioning"
+        }
+
+        async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            ctx.sagas()?.register(
+                crate::Saga::new("provision_tenant")
+                    .started_by("tenants.created")
+                    .step(
+                        "create_workspace",
+                        |mut data| async move {
+                            data["workspace"] = json!("ws_1");
+                            Ok(data)
+                        },
+                        |_| async { Ok(()) },
+                    ),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sagas_start_on_their_trigger_event() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Provisioning)).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let bus = ctx.events().unwrap();
+        let mut events = bus.subscribe::<NamedEvent>();
+        bus.emit("tenants.created", json!({ "tenant": "acme" }))
+            .unwrap();
+        let mut finished = events.recv().await.unwrap();
+        while &*finished.name != "saga.completed" {
+            finished = events.recv().await.unwrap();
+        }
+
+        let id = finished.payload["id"].as_str().unwrap();
+        let run = ctx.sagas().unwrap().store().get(id).await.unwrap().unwrap();
+        assert_eq!(run.saga, "provision_tenant");
+        assert_eq!(run.data, json!({ "tenant": "acme", "workspace": "ws_1" }));
+        registry.stop_core_modules().await.unwrap();
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    #[tokio::test]
+    async fn test_enabled_kafka_requires_connector() {
+        let mut settings = Settings::default();
+        settings.events.kafka.enabled = true;
+        settings.events.kafka.brokers = vec!["kafka:9092".to_string()];
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+
+        let error = registry
+            .init_core_modules(&registry.init_ctx(Arc::new(settings)))
+            .await
+            .unwrap_err();
+
+        let error = anyhow::Error::from(error);
+        assert!(format!("{:#}", error).contains("KafkaConnector"));
+    }
+
+    /// Fails on every `books.created`
+    struct Broken;
+
+    #[async_trait]
+    impl Module for Broken {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+
+        fn event_handlers(&self) -> Vec<atlas_kernel::EventHandler> {
+            vec![atlas_kernel::EventHandler::new(
+                "books.created",
+                |_| async { anyhow::bail!("index unavailable") },
+            )]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_listed_redriven_and_discarded() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::{header::AUTHORIZATION, Request};
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.admin.token = Some("s3cret".to_string());
+        settings.events.retry.max_attempts = 1;
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Broken)).unwrap();
+        let ctx = registry.init_ctx(Arc::new(settings));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let module = registry.get_module("events").unwrap();
+        ctx.events()
+            .unwrap()
+            .emit("books.created", json!({ "id": 7 }))
+            .unwrap();
+        let send = |method: &str, uri: &str| {
+            module.routes().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(AUTHORIZATION, "Bearer s3cret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        let list = || async {
+            let response = send("GET", "/dead_letters?module=broken").await.unwrap();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Vec<DeadLetter>>(&body).unwrap()
+        };
+        let mut letters = list().await;
+        while letters.is_empty() {
+            tokio::task::yield_now().await;
+            letters = list().await;
+        }
+        assert_eq!(letters[0].event, "books.created");
+        assert_eq!(letters[0].error, "index unavailable");
+        let id = letters[0].id.clone();
+
+        let redriven = send("POST", &format!("/dead_letters/{}/redrive", id))
+            .await
+            .unwrap();
+        assert_eq!(redriven.status(), StatusCode::CONFLICT);
+        assert_eq!(list().await[0].attempts, 2);
+        let health = module.health().await;
+        assert_eq!(health.details.unwrap()["handlers"]["dead_lettered"], 1);
+
+        let deleted = send("DELETE", &format!("/dead_letters/{}", id))
+            .await
+            .unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+        assert!(list().await.is_empty());
+        let missing = send("DELETE", &format!("/dead_letters/{}", id))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        registry.stop_core_modules().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_catalog_served_after_start() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let (indexed, _received) = tokio::sync::mpsc::unbounded_channel();
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_custom(Arc::new(Search(indexed))).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let module = registry.get_module("events").unwrap();
+        let response = module
+            .routes()
+            .oneshot(Request::get("/catalog").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let catalog: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // Ordered by name, after the kernel's lifecycle events
+        let names: Vec<_> = catalog
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names.last(), Some(&"search.indexed"));
+        assert!(names.contains(&"kernel.module_started"));
+        let search = catalog.as_array().unwrap().last().unwrap();
+        assert_eq!(search["module"], "search");
+        assert_eq!(search["version"], 1);
+    }
+}
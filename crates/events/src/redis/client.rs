@@ -0,0 +1,277 @@
+//! `RedisClient` over the `redis` crate
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::streams::StreamReadReply;
+use tokio::sync::mpsc;
+
+use super::{RedisClient, RedisConnector, RedisStreamEntry};
+
+/// How long `XREADGROUP` waits for entries before returning none
+const BLOCK_MS: usize = 5_000;
+
+/// Messages buffered between a subscription and the transport
+const SUBSCRIPTION_BUFFER: usize = 256;
+
+/// Connects with the `redis` crate
+///
+/// Commands share one multiplexed connection. Blocking stream reads get their own, so
+/// they do not hold up publishing, and every subscription opens a dedicated one.
+#[derive(Debug, Default)]
+pub struct RedisRsConnector;
+
+impl RedisRsConnector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl RedisConnector for RedisRsConnector {
+    async fn connect(&self, url: &str) -> anyhow::Result<Arc<dyn RedisClient>> {
+        let client = redis::Client::open(url).context("invalid Redis URL")?;
+        let commands = client
+            .get_multiplexed_async_connection()
+            .await
+            .context("failed to connect to Redis")?;
+        let reads = client
+            .get_multiplexed_async_connection()
+            .await
+            .context("failed to connect to Redis")?;
+        Ok(Arc::new(RedisRsClient {
+            client,
+            commands,
+            reads,
+        }))
+    }
+}
+
+struct RedisRsClient {
+    client: redis::Client,
+    commands: MultiplexedConnection,
+    reads: MultiplexedConnection,
+}
+
+#[async_trait]
+impl RedisClient for RedisRsClient {
+    async fn publish(&self, channel: &str, payload: Vec<u8>) -> anyhow::Result<()> {
+        redis::cmd("PUBLISH")
+            .arg(channel)
+            .arg(payload)
+            .query_async::<()>(&mut self.commands.clone())
+            .await
+            .with_context(|| format!("failed to publish to '{}'", channel))
+    }
+
+    async fn subscribe(&self, channel: &str) -> anyhow::Result<mpsc::Receiver<Vec<u8>>> {
+        let mut pubsub = self
+            .client
+            .get_async_pubsub()
+            .await
+            .context("failed to connect to Redis")?;
+        pubsub
+            .subscribe(channel)
+            .await
+            .with_context(|| format!("failed to subscribe to '{}'", channel))?;
+        let (sender, receiver) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                if sender
+                    .send(message.get_payload_bytes().to_vec())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Ok(receiver)
+    }
+
+    async fn xadd(&self, stream: &str, max_len: usize, payload: Vec<u8>) -> anyhow::Result<()> {
+        redis::cmd("XADD")
+            .arg(stream)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(max_len)
+            .arg("*")
+            .arg("payload")
+            .arg(payload)
+            .query_async::<()>(&mut self.commands.clone())
+            .await
+            .with_context(|| format!("failed to append to '{}'", stream))
+    }
+
+    async fn create_group(&self, stream: &str, group: &str) -> anyhow::Result<()> {
+        let created = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(stream)
+            .arg(group)
+            .arg("$")
+            .arg("MKSTREAM")
+            .query_async::<()>(&mut self.commands.clone())
+            .await;
+        match created {
+            Err(error) if error.code() != Some("BUSYGROUP") => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn read_group(
+        &self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        count: usize,
+    ) -> anyhow::Result<Vec<RedisStreamEntry>> {
+        let reply: Option<StreamReadReply> = redis::cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg(group)
+            .arg(consumer)
+            .arg("COUNT")
+            .arg(count)
+            .arg("BLOCK")
+            .arg(BLOCK_MS)
+            .arg("STREAMS")
+            .arg(stream)
+            .arg(">")
+            .query_async(&mut self.reads.clone())
+            .await
+            .with_context(|| format!("failed to read from '{}'", stream))?;
+        Ok(reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .map(|entry| RedisStreamEntry {
+                payload: entry.get("payload").unwrap_or_default(),
+                id: entry.id,
+            })
+            .collect())
+    }
+
+    async fn ack(&self, stream: &str, group: &str, ids: &[String]) -> anyhow::Result<()> {
+        redis::cmd("XACK")
+            .arg(stream)
+            .arg(group)
+            .arg(ids)
+            .query_async::<()>(&mut self.commands.clone())
+            .await
+            .with_context(|| format!("failed to acknowledge entries of '{}'", stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Reads one RESP command array, or `None` once the client disconnected
+    async fn command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<String>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+        let count: usize = line.trim().strip_prefix('*')?.parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).await.ok()?;
+            let len: usize = line.trim().strip_prefix('$')?.parse().ok()?;
+            let mut arg = vec![0; len + 2];
+            reader.read_exact(&mut arg).await.ok()?;
+            arg.truncate(len);
+            args.push(String::from_utf8_lossy(&arg).into_owned());
+        }
+        Some(args)
+    }
+
+    /// A Redis server recording commands; the group exists and streams are empty
+    async fn serve() -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let recorded = commands.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = socket.into_split();
+                    let mut reader = BufReader::new(reader);
+                    while let Some(args) = command(&mut reader).await {
+                        let reply: &[u8] = match args[0].as_str() {
+                            "XADD" => b"$3\r\n1-0\r\n",
+                            "XGROUP" => b"-BUSYGROUP Consumer Group name already exists\r\n",
+                            "XREADGROUP" => b"*-1\r\n",
+                            _ => b"+OK\r\n",
+                        };
+                        recorded.lock().unwrap().push(args);
+                        if writer.write_all(reply).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (format!("redis://{}", addr), commands)
+    }
+
+    #[tokio::test]
+    async fn test_stream_commands_are_sent_and_answered() {
+        let (url, commands) = serve().await;
+        let client = RedisRsConnector::new().connect(&url).await.unwrap();
+
+        client
+            .xadd("atlas.events", 1000, b"{}".to_vec())
+            .await
+            .unwrap();
+        client.create_group("atlas.events", "atlas").await.unwrap();
+        let entries = client
+            .read_group("atlas.events", "atlas", "api-1", 10)
+            .await
+            .unwrap();
+
+        assert!(entries.is_empty());
+        let commands = commands.lock().unwrap();
+        let sent = |name: &str| {
+            commands
+                .iter()
+                .find(|args| args[0] == name)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(
+            sent("XADD"),
+            [
+                "XADD",
+                "atlas.events",
+                "MAXLEN",
+                "~",
+                "1000",
+                "*",
+                "payload",
+                "{}"
+            ]
+        );
+        assert_eq!(
+            sent("XREADGROUP")[..6],
+            ["XREADGROUP", "GROUP", "atlas", "api-1", "COUNT", "10"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_servers_fail_to_connect() {
+        let error = RedisRsConnector::new()
+            .connect("redis://127.0.0.1:9")
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("failed to connect"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Redis transport for named events
+//!
+//! In `pub_sub` mode events are published on one channel and every connected instance
+//! receives them; nothing is kept for instances that are down. In `streams` mode they are
+//! appended to a stream read through a consumer group, so each event reaches one instance
+//! and is acknowledged once it is on the local bus.
+//!
+//! With the `redis` feature, `RedisRsConnector` connects with the `redis` crate.
+
+#[cfg(feature = "redis")]
+mod client;
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use atlas_kernel::settings::{RedisEventMode, RedisEventSettings};
+
+use crate::transport::{self, EventTransport};
+use crate::{EventBus, NamedEvent};
+
+#[cfg(feature = "redis")]
+pub use client::RedisRsConnector;
+
+/// Creates Redis clients
+#[async_trait]
+pub trait RedisConnector: Send + Sync {
+    async fn connect(&self, url: &str) -> anyhow::Result<Arc<dyn RedisClient>>;
+}
+
+/// The Redis commands the transport uses
+#[async_trait]
+pub trait RedisClient: Send + Sync {
+    /// `PUBLISH channel payload`
+    async fn publish(&self, channel: &str, payload: Vec<u8>) -> anyhow::Result<()>;
+
+    /// `SUBSCRIBE channel` on a dedicated connection; the receiver closes when it drops
+    async fn subscribe(&self, channel: &str) -> anyhow::Result<mpsc::Receiver<Vec<u8>>>;
+
+    /// `XADD stream MAXLEN ~ max_len * payload <payload>`
+    async fn xadd(&self, stream: &str, max_len: usize, payload: Vec<u8>) -> anyhow::Result<()>;
+
+    /// `XGROUP CREATE stream group $ MKSTREAM`, succeeding if the group exists
+    async fn create_group(&self, stream: &str, group: &str) -> anyhow::Result<()>;
+
+    /// `XREADGROUP GROUP group consumer COUNT count BLOCK .. STREAMS stream >`; empty when
+    /// the block timed out
+    async fn read_group(
+        &self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        count: usize,
+    ) -> anyhow::Result<Vec<RedisStreamEntry>>;
+
+    /// `XACK stream group id...`
+    async fn ack(&self, stream: &str, group: &str, ids: &[String]) -> anyhow::Result<()>;
+}
+
+/// A stream entry's id and `payload` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisStreamEntry {
+    pub id: String,
+    pub payload: Vec<u8>,
+}
+
+/// Forwards named events to and from Redis
+pub struct RedisTransport {
+    client: Arc<dyn RedisClient>,
+    settings: RedisEventSettings,
+    instance: String,
+}
+
+impl RedisTransport {
+    /// `instance` is the `EventBus::instance` of this process, also used as the stream
+    /// consumer name
+    pub fn new(client: Arc<dyn RedisClient>, settings: RedisEventSettings, instance: &str) -> Self {
+        Self {
+            client,
+            settings,
+            instance: instance.to_string(),
+        }
+    }
+
+    fn deliver(&self, bus: &EventBus, payload: &[u8]) {
+        match transport::decode(self.name(), &self.instance, payload) {
+            Ok(Some(event)) => {
+                bus.publish(event);
+            }
+            Ok(None) => {}
+            Err(error) => tracing::warn!(
+                channel = %self.settings.channel,
+                error = format!("{:#}", error),
+                "skipping undecodable Redis message"
+            ),
+        }
+    }
+
+    async fn receive_messages(&self, bus: &EventBus) -> anyhow::Result<()> {
+        let mut messages = self.client.subscribe(&self.settings.channel).await?;
+        while let Some(payload) = messages.recv().await {
+            self.deliver(bus, &payload);
+        }
+        anyhow::bail!("Redis subscription to '{}' closed", self.settings.channel)
+    }
+
+    async fn receive_stream(&self, bus: &EventBus) -> anyhow::Result<()> {
+        let settings = &self.settings;
+        self.client
+            .create_group(&settings.channel, &settings.consumer_group)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to create consumer group '{}'",
+                    settings.consumer_group
+                )
+            })?;
+        loop {
+            let entries = self
+                .client
+                .read_group(
+                    &settings.channel,
+                    &settings.consumer_group,
+                    &self.instance,
+                    settings.batch_size,
+                )
+                .await?;
+            if entries.is_empty() {
+                continue;
+            }
+            for entry in &entries {
+                self.deliver(bus, &entry.payload);
+            }
+            let ids: Vec<String> = entries.into_iter().map(|entry| entry.id).collect();
+            self.client
+                .ack(&settings.channel, &settings.consumer_group, &ids)
+                .await?;
+        }
+    }
+}
+
+#[async_trait]
+impl EventTransport for RedisTransport {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn send(&self, event: &NamedEvent) -> anyhow::Result<()> {
+        let payload = transport::encode(event, &self.instance);
+        match self.settings.mode {
+            RedisEventMode::PubSub => self.client.publish(&self.settings.channel, payload).await,
+            RedisEventMode::Streams => {
+                self.client
+                    .xadd(&self.settings.channel, self.settings.max_len, payload)
+                    .await
+            }
+        }
+    }
+
+    async fn receive(&self, bus: Arc<EventBus>) -> anyhow::Result<()> {
+        match self.settings.mode {
+            RedisEventMode::PubSub => self.receive_messages(&bus).await,
+            RedisEventMode::Streams => self.receive_stream(&bus).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+
+    const REMOTE: &[u8] = br#"{"event":"books.created","payload":{"id":7},"source":"api-2"}"#;
+    const OWN: &[u8] = br#"{"event":"books.created","payload":{"id":8},"source":"api-1"}"#;
+
+    /// Serves one batch of messages or stream entries, then disconnects
+    #[derive(Default)]
+    struct FakeRedis {
+        sent: Mutex<Vec<(&'static str, String, Vec<u8>)>>,
+        entries: Mutex<Option<Vec<RedisStreamEntry>>>,
+        acked: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl RedisClient for FakeRedis {
+        async fn publish(&self, channel: &str, payload: Vec<u8>) -> anyhow::Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(("publish", channel.to_string(), payload));
+            Ok(())
+        }
+
+        async fn subscribe(&self, _channel: &str) -> anyhow::Result<mpsc::Receiver<Vec<u8>>> {
+            let (sender, receiver) = mpsc::channel(4);
+            sender.send(REMOTE.to_vec()).await?;
+            sender.send(OWN.to_vec()).await?;
+            Ok(receiver)
+        }
+
+        async fn xadd(
+            &self,
+            stream: &str,
+            _max_len: usize,
+            payload: Vec<u8>,
+        ) -> anyhow::Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(("xadd", stream.to_string(), payload));
+            Ok(())
+        }
+
+        async fn create_group(&self, _stream: &str, _group: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn read_group(
+            &self,
+            _stream: &str,
+            _group: &str,
+            consumer: &str,
+            _count: usize,
+        ) -> anyhow::Result<Vec<RedisStreamEntry>> {
+            assert_eq!(consumer, "api-1");
+            self.entries
+                .lock()
+                .unwrap()
+                .take()
+                .context("connection reset")
+        }
+
+        async fn ack(&self, _stream: &str, _group: &str, ids: &[String]) -> anyhow::Result<()> {
+            self.acked.lock().unwrap().extend_from_slice(ids);
+            Ok(())
+        }
+    }
+
+    fn transport(client: Arc<FakeRedis>, mode: RedisEventMode) -> RedisTransport {
+        let settings = RedisEventSettings {
+            enabled: true,
+            mode,
+            ..RedisEventSettings::default()
+        };
+        RedisTransport::new(client, settings, "api-1")
+    }
+
+    fn event() -> NamedEvent {
+        NamedEvent {
+            name: "books.created".into(),
+            payload: Arc::new(json!({ "id": 7 })),
+            version: None,
+            origin: None,
+            tenant: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_publishes_or_appends_by_mode() {
+        let client = Arc::new(FakeRedis::default());
+
+        transport(client.clone(), RedisEventMode::PubSub)
+            .send(&event())
+            .await
+            .unwrap();
+        transport(client.clone(), RedisEventMode::Streams)
+            .send(&event())
+            .await
+            .unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!((sent[0].0, sent[0].1.as_str()), ("publish", "atlas.events"));
+        assert_eq!((sent[1].0, sent[1].1.as_str()), ("xadd", "atlas.events"));
+    }
+
+    #[tokio::test]
+    async fn test_pub_sub_emits_messages_from_other_instances() {
+        let bus = Arc::new(EventBus::new(16));
+        let mut received = bus.subscribe::<NamedEvent>();
+
+        let error = transport(Arc::new(FakeRedis::default()), RedisEventMode::PubSub)
+            .receive(bus.clone())
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("closed"));
+        let event = received.try_recv().unwrap();
+        assert_eq!(*event.payload, json!({ "id": 7 }));
+        assert_eq!(event.origin, Some("redis"));
+        assert!(received.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streams_acknowledge_delivered_entries() {
+        let client = Arc::new(FakeRedis::default());
+        *client.entries.lock().unwrap() = Some(vec![
+            RedisStreamEntry {
+                id: "1-0".to_string(),
+                payload: REMOTE.to_vec(),
+            },
+            RedisStreamEntry {
+                id: "2-0".to_string(),
+                payload: b"garbage".to_vec(),
+            },
+        ]);
+        let bus = Arc::new(EventBus::new(16));
+        let mut received = bus.subscribe::<NamedEvent>();
+
+        transport(client.clone(), RedisEventMode::Streams)
+            .receive(bus.clone())
+            .await
+            .unwrap_err();
+
+        assert!(received.try_recv().is_some());
+        assert_eq!(*client.acked.lock().unwrap(), vec!["1-0", "2-0"]);
+    }
+}
@@ -0,0 +1,795 @@
+//! Sagas: multi-step workflows that undo their completed steps when a later one fails
+//!
+//! A saga is a list of steps, each an action and the compensation undoing it, run in
+//! order over a JSON document of data. Every step's outcome is recorded in a
+//! `SagaStore`, so a saga interrupted by a restart picks up where it stopped. When a
+//! step fails, the steps before it are compensated in reverse order; the failing step
+//! itself must leave nothing behind.
+//!
+//! Sagas can be started by a named event, whose payload becomes the saga's data, and
+//! emit `saga.completed`, `saga.compensated` or `saga.failed` when they finish.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+
+use atlas_db::{affected, datetime, QueryExecutor};
+use atlas_kernel::{id, tenant, tenant::TenantId, Clock, EventSchema, InitCtx};
+
+use crate::{EventBus, NamedEvent};
+
+/// Future returned by a saga step's action or compensation
+pub type SagaFuture<T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send>>;
+
+type Action = Arc<dyn Fn(Value) -> SagaFuture<Value> + Send + Sync>;
+type Compensation = Arc<dyn Fn(Value) -> SagaFuture<()> + Send + Sync>;
+
+struct Step {
+    name: &'static str,
+    action: Action,
+    compensation: Compensation,
+}
+
+/// A named sequence of steps with compensations
+///
+/// ```ignore
+/// Saga::new("tenant_provisioning")
+///     .started_by("tenants.created")
+///     .step("create_database", create_database, drop_database)
+///     .step("send_welcome", send_welcome, |_| async { Ok(()) })
+/// ```
+pub struct Saga {
+    name: &'static str,
+    trigger: Option<&'static str>,
+    steps: Vec<Step>,
+}
+
+impl Saga {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            trigger: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Start the saga whenever the named event is emitted, with its payload as the data
+    pub fn started_by(mut self, event: &'static str) -> Self {
+        self.trigger = Some(event);
+        self
+    }
+
+    /// Append a step
+    ///
+    /// `action` receives the saga's data and returns it, possibly with fields added for
+    /// later steps, such as the id of a created record. `compensation` receives the data
+    /// as the action returned it. Either may run again after a restart, so both must be
+    /// idempotent.
+    pub fn step<A, AFut, C, CFut>(mut self, name: &'static str, action: A, compensation: C) -> Self
+    where
+        A: Fn(Value) -> AFut + Send + Sync + 'static,
+        AFut: Future<Output = anyhow::Result<Value>> + Send + 'static,
+        C: Fn(Value) -> CFut + Send + Sync + 'static,
+        CFut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.steps.push(Step {
+            name,
+            action: Arc::new(move |data| Box::pin(action(data))),
+            compensation: Arc::new(move |data| Box::pin(compensation(data))),
+        });
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Named event starting the saga, if any
+    pub fn trigger(&self) -> Option<&'static str> {
+        self.trigger
+    }
+
+    /// Names of the steps, in order
+    pub fn steps(&self) -> Vec<&'static str> {
+        self.steps.iter().map(|step| step.name).collect()
+    }
+}
+
+impl fmt::Debug for Saga {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Saga")
+            .field("name", &self.name)
+            .field("trigger", &self.trigger)
+            .field("steps", &self.steps())
+            .finish()
+    }
+}
+
+/// Where a saga run stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaState {
+    /// Running its steps in order
+    Running,
+    /// A step failed; undoing the completed ones
+    Compensating,
+    Completed,
+    /// Every completed step was undone after a failure
+    Compensated,
+    /// A compensation failed; an operator has to clean up
+    Failed,
+}
+
+impl SagaState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Compensating => "compensating",
+            Self::Completed => "completed",
+            Self::Compensated => "compensated",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Whether the run has nothing left to do
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Compensated | Self::Failed)
+    }
+}
+
+impl fmt::Display for SagaState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One run of a saga
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaInstance {
+    /// `saga_`-prefixed ULID
+    pub id: String,
+    /// Name of the saga being run
+    pub saga: String,
+    pub state: SagaState,
+    /// Data as returned by the last completed step
+    pub data: Value,
+    /// Steps completed and not compensated yet
+    pub completed: usize,
+    /// Error of the step that failed, then of the compensation that failed
+    pub error: Option<String>,
+    /// Tenant the saga was started for; its steps run in that tenant's scope
+    pub tenant: Option<TenantId>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Persistence for saga runs
+///
+/// The events module records runs in the `saga` table through the application's
+/// `QueryExecutor`, so they resume across restarts; applications may publish their own
+/// `Arc<dyn SagaStore>` resource instead. Without either, runs live in memory.
+#[async_trait]
+pub trait SagaStore: Send + Sync {
+    async fn insert(&self, instance: SagaInstance) -> anyhow::Result<()>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<SagaInstance>>;
+
+    /// Replace a stored run with the same id
+    async fn update(&self, instance: SagaInstance) -> anyhow::Result<()>;
+
+    /// Runs still running or compensating, oldest first
+    async fn unfinished(&self) -> anyhow::Result<Vec<SagaInstance>>;
+}
+
+/// Process-local saga store
+#[derive(Debug, Default)]
+pub struct MemorySagaStore {
+    instances: RwLock<Vec<SagaInstance>>,
+}
+
+impl MemorySagaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn instances(&self) -> std::sync::RwLockWriteGuard<'_, Vec<SagaInstance>> {
+        self.instances
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl SagaStore for MemorySagaStore {
+    async fn insert(&self, instance: SagaInstance) -> anyhow::Result<()> {
+        self.instances().push(instance);
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<SagaInstance>> {
+        Ok(self
+            .instances()
+            .iter()
+            .find(|instance| instance.id == id)
+            .cloned())
+    }
+
+    async fn update(&self, instance: SagaInstance) -> anyhow::Result<()> {
+        let mut instances = self.instances();
+        match instances.iter_mut().find(|stored| stored.id == instance.id) {
+            Some(stored) => *stored = instance,
+            None => anyhow::bail!("saga run '{}' does not exist", instance.id),
+        }
+        Ok(())
+    }
+
+    async fn unfinished(&self) -> anyhow::Result<Vec<SagaInstance>> {
+        let mut unfinished: Vec<SagaInstance> = self
+            .instances()
+            .iter()
+            .filter(|instance| !instance.state.is_finished())
+            .cloned()
+            .collect();
+        unfinished.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(unfinished)
+    }
+}
+
+/// Runs in the `saga` table defined by the events module's migrations
+pub struct SurrealSagaStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealSagaStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// `SET` clause and variables writing every field of `instance`
+    fn assignments(instance: &SagaInstance) -> (String, Map<String, Value>) {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(instance.id));
+        vars.insert("saga".to_string(), json!(instance.saga));
+        vars.insert("state".to_string(), json!(instance.state.as_str()));
+        vars.insert("data".to_string(), instance.data.clone());
+        vars.insert("completed".to_string(), json!(instance.completed));
+        vars.insert(
+            "started_at".to_string(),
+            json!(datetime(instance.started_at)),
+        );
+        vars.insert(
+            "updated_at".to_string(),
+            json!(datetime(instance.updated_at)),
+        );
+        let mut fields = vec![
+            "saga = $saga".to_string(),
+            "state = $state".to_string(),
+            "data = $data".to_string(),
+            "completed = $completed".to_string(),
+            "started_at = <datetime> $started_at".to_string(),
+            "updated_at = <datetime> $updated_at".to_string(),
+        ];
+        for (field, value) in [
+            ("error", instance.error.clone()),
+            (
+                "tenant",
+                instance
+                    .tenant
+                    .as_ref()
+                    .map(|tenant| tenant.as_str().to_string()),
+            ),
+        ] {
+            match value {
+                Some(value) => {
+                    fields.push(format!("{} = ${}", field, field));
+                    vars.insert(field.to_string(), json!(value));
+                }
+                None => fields.push(format!("{} = NONE", field)),
+            }
+        }
+        (fields.join(", "), vars)
+    }
+}
+
+#[async_trait]
+impl SagaStore for SurrealSagaStore {
+    async fn insert(&self, instance: SagaInstance) -> anyhow::Result<()> {
+        let (set, vars) = Self::assignments(&instance);
+        let surql = format!("CREATE type::thing('saga', $id) SET {} RETURN NONE;", set);
+        self.db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to store saga run")?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<SagaInstance>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let results = self
+            .db
+            .query_with(
+                "SELECT *, record::id(id) AS id FROM type::thing('saga', $id);",
+                &vars,
+            )
+            .await
+            .context("failed to read saga run")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn update(&self, instance: SagaInstance) -> anyhow::Result<()> {
+        let (set, vars) = Self::assignments(&instance);
+        let surql = format!(
+            "UPDATE saga SET {} WHERE id = type::thing('saga', $id) \
+             RETURN VALUE record::id(id);",
+            set
+        );
+        let results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to update saga run")?;
+        if !affected(results.first()) {
+            anyhow::bail!("saga run '{}' does not exist", instance.id);
+        }
+        Ok(())
+    }
+
+    async fn unfinished(&self) -> anyhow::Result<Vec<SagaInstance>> {
+        let results = self
+            .db
+            .query(
+                "SELECT *, record::id(id) AS id FROM saga \
+                 WHERE state IN ['running', 'compensating'] ORDER BY started_at, id;",
+            )
+            .await
+            .context("failed to read unfinished saga runs")?;
+        atlas_db::records(results.into_iter().next())
+    }
+}
+
+/// Runs registered sagas and records their progress
+///
+/// The events module publishes it as a resource; modules register their sagas during
+/// `init`, with `events` in `depends_on`.
+pub struct Sagas {
+    sagas: RwLock<HashMap<&'static str, Arc<Saga>>>,
+    store: Arc<dyn SagaStore>,
+    bus: Arc<EventBus>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Sagas {
+    pub fn new(bus: Arc<EventBus>, store: Arc<dyn SagaStore>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            sagas: RwLock::default(),
+            store,
+            bus,
+            clock,
+        }
+    }
+
+    pub fn store(&self) -> &Arc<dyn SagaStore> {
+        &self.store
+    }
+
+    /// Make `saga` available to `run` and, once the events module starts, its trigger
+    pub fn register(&self, saga: Saga) -> anyhow::Result<()> {
+        let mut sagas = self
+            .sagas
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if sagas.contains_key(saga.name) {
+            anyhow::bail!("saga '{}' is already registered", saga.name);
+        }
+        sagas.insert(saga.name, Arc::new(saga));
+        Ok(())
+    }
+
+    fn saga(&self, name: &str) -> anyhow::Result<Arc<Saga>> {
+        self.sagas
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .cloned()
+            .with_context(|| format!("saga '{}' is not registered", name))
+    }
+
+    /// Run the saga `name` over `data` until it completes or is compensated
+    ///
+    /// Runs started inside a tenant scope belong to that tenant. A failing step is not an
+    /// error here; it shows in the returned run's state.
+    pub async fn run(&self, name: &str, data: Value) -> anyhow::Result<SagaInstance> {
+        let saga = self.saga(name)?;
+        let now = OffsetDateTime::from(self.clock.now());
+        let instance = SagaInstance {
+            id: id::prefixed_with("saga", id::ulid_at(self.clock.now()))
+                .expect("saga is a valid id prefix"),
+            saga: saga.name.to_string(),
+            state: SagaState::Running,
+            data,
+            completed: 0,
+            error: None,
+            tenant: tenant::current(),
+            started_at: now,
+            updated_at: now,
+        };
+        self.store.insert(instance.clone()).await?;
+        self.drive(&saga, instance).await
+    }
+
+    /// Carry on with every run a previous process left unfinished, returning them
+    pub async fn resume(&self) -> anyhow::Result<Vec<SagaInstance>> {
+        let mut resumed = Vec::new();
+        for instance in self.store.unfinished().await? {
+            let saga = self.saga(&instance.saga)?;
+            tracing::info!(saga = saga.name, id = %instance.id, state = %instance.state, "resuming saga");
+            let finished = match instance.tenant.clone() {
+                Some(tenant) => tenant::scope(tenant, self.drive(&saga, instance)).await?,
+                None => self.drive(&saga, instance).await?,
+            };
+            resumed.push(finished);
+        }
+        Ok(resumed)
+    }
+
+    async fn drive(&self, saga: &Saga, mut instance: SagaInstance) -> anyhow::Result<SagaInstance> {
+        while instance.state == SagaState::Running && instance.completed < saga.steps.len() {
+            let step = &saga.steps[instance.completed];
+            match (step.action)(instance.data.clone()).await {
+                Ok(data) => {
+                    instance.data = data;
+                    instance.completed += 1;
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        saga = saga.name,
+                        id = %instance.id,
+                        step = step.name,
+                        error = format!("{:#}", error),
+                        "saga step failed; compensating"
+                    );
+                    instance.state = SagaState::Compensating;
+                    instance.error = Some(format!("step '{}' failed: {:#}", step.name, error));
+                }
+            }
+            self.save(&mut instance).await?;
+        }
+        if instance.state == SagaState::Running {
+            instance.state = SagaState::Completed;
+            self.save(&mut instance).await?;
+        }
+
+        while instance.state == SagaState::Compensating && instance.completed > 0 {
+            let step = &saga.steps[instance.completed - 1];
+            match (step.compensation)(instance.data.clone()).await {
+                Ok(()) => instance.completed -= 1,
+                Err(error) => {
+                    tracing::error!(
+                        saga = saga.name,
+                        id = %instance.id,
+                        step = step.name,
+                        error = format!("{:#}", error),
+                        "saga compensation failed"
+                    );
+                    instance.state = SagaState::Failed;
+                    instance.error = Some(format!(
+                        "compensating step '{}' failed: {:#}",
+                        step.name, error
+                    ));
+                }
+            }
+            self.save(&mut instance).await?;
+        }
+        if instance.state == SagaState::Compensating {
+            instance.state = SagaState::Compensated;
+            self.save(&mut instance).await?;
+        }
+
+        let event = format!("saga.{}", instance.state);
+        self.bus.emit(
+            &event,
+            json!({ "id": instance.id, "saga": instance.saga, "error": instance.error }),
+        )?;
+        Ok(instance)
+    }
+
+    async fn save(&self, instance: &mut SagaInstance) -> anyhow::Result<()> {
+        instance.updated_at = OffsetDateTime::from(self.clock.now());
+        self.store
+            .update(instance.clone())
+            .await
+            .with_context(|| format!("failed to record progress of saga run '{}'", instance.id))
+    }
+}
+
+impl fmt::Debug for Sagas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sagas")
+            .field(
+                "sagas",
+                &self
+                    .sagas
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .len(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Start sagas from their trigger events, and resume unfinished runs, in tasks owned by
+/// `ctx`'s module
+///
+/// Runs of one saga started by events go one at a time, in the order the events came.
+pub(crate) fn run(ctx: &InitCtx, sagas: Arc<Sagas>) {
+    let registered: Vec<Arc<Saga>> = sagas
+        .sagas
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    for saga in registered {
+        let Some(trigger) = saga.trigger else {
+            continue;
+        };
+        let mut subscription = sagas.bus.subscribe::<NamedEvent>();
+        let sagas = sagas.clone();
+        ctx.spawn(format!("saga {} on {}", saga.name, trigger), async move {
+            while let Some(event) = subscription.recv().await {
+                if *event.name != *trigger {
+                    continue;
+                }
+                let run = sagas.run(saga.name, (*event.payload).clone());
+                let result = match event.tenant.clone() {
+                    Some(tenant) => tenant::scope(tenant, run).await,
+                    None => run.await,
+                };
+                if let Err(error) = result {
+                    tracing::warn!(
+                        saga = saga.name,
+                        error = format!("{:#}", error),
+                        "failed to run saga"
+                    );
+                }
+            }
+            Ok(())
+        });
+    }
+    ctx.spawn("resume sagas", async move {
+        sagas.resume().await.context("failed to resume sagas")?;
+        Ok(())
+    });
+}
+
+/// Schemas of the events sagas emit when they finish
+pub(crate) fn schemas() -> Vec<EventSchema> {
+    let schema = json!({
+        "type": "object",
+        "required": ["id", "saga"],
+        "properties": {
+            "id": { "type": "string" },
+            "saga": { "type": "string" },
+            "error": { "type": "string", "nullable": true }
+        }
+    });
+    [
+        ("saga.completed", "A saga ran every step"),
+        (
+            "saga.compensated",
+            "A saga step failed and the completed steps were undone",
+        ),
+        (
+            "saga.failed",
+            "A saga compensation failed; the run needs cleaning up",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description)| EventSchema {
+        name,
+        version: 1,
+        description: Some(description),
+        schema: schema.clone(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use atlas_db::testing::RecordingExecutor;
+    use atlas_kernel::clock::SystemClock;
+
+    use super::*;
+
+    /// A saga recording the steps run and undone, failing at `fail_at` or compensating
+    /// `break_compensation` with an error
+    fn provisioning(
+        log: Arc<Mutex<Vec<String>>>,
+        fail_at: Option<&'static str>,
+        break_compensation: Option<&'static str>,
+    ) -> Saga {
+        ["database", "bucket", "welcome"].into_iter().fold(
+            Saga::new("provisioning"),
+            |saga, name| {
+                let done = log.clone();
+                let undone = log.clone();
+                saga.step(
+                    name,
+                    move |mut data: Value| {
+                        let done = done.clone();
+                        async move {
+                            if fail_at == Some(name) {
+                                anyhow::bail!("{} unavailable", name);
+                            }
+                            done.lock().unwrap().push(format!("+{}", name));
+                            data[name] = json!(true);
+                            Ok(data)
+                        }
+                    },
+                    move |data: Value| {
+                        let undone = undone.clone();
+                        async move {
+                            anyhow::ensure!(data[name] == json!(true), "{} never ran", name);
+                            if break_compensation == Some(name) {
+                                anyhow::bail!("{} is stuck", name);
+                            }
+                            undone.lock().unwrap().push(format!("-{}", name));
+                            Ok(())
+                        }
+                    },
+                )
+            },
+        )
+    }
+
+    fn sagas() -> (Arc<Sagas>, Arc<EventBus>) {
+        let bus = Arc::new(EventBus::new(16));
+        let sagas = Sagas::new(
+            bus.clone(),
+            Arc::new(MemorySagaStore::new()),
+            Arc::new(SystemClock),
+        );
+        (Arc::new(sagas), bus)
+    }
+
+    #[tokio::test]
+    async fn test_saga_runs_every_step_in_order() {
+        let (sagas, bus) = sagas();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        sagas
+            .register(provisioning(log.clone(), None, None))
+            .unwrap();
+        assert!(sagas.register(Saga::new("provisioning")).is_err());
+        let mut events = bus.subscribe::<NamedEvent>();
+
+        let run = sagas
+            .run("provisioning", json!({ "tenant": "acme" }))
+            .await
+            .unwrap();
+
+        assert_eq!(run.state, SagaState::Completed);
+        assert_eq!(run.completed, 3);
+        assert_eq!(run.data["bucket"], true);
+        assert!(run.id.starts_with("saga_"));
+        assert_eq!(*log.lock().unwrap(), ["+database", "+bucket", "+welcome"]);
+        assert_eq!(sagas.store().get(&run.id).await.unwrap(), Some(run.clone()));
+        assert_eq!(&*events.try_recv().unwrap().name, "saga.completed");
+        assert!(sagas.run("missing", json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_compensates_completed_steps_in_reverse() {
+        let (sagas, _bus) = sagas();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        sagas
+            .register(provisioning(log.clone(), Some("welcome"), None))
+            .unwrap();
+
+        let run = sagas.run("provisioning", json!({})).await.unwrap();
+
+        assert_eq!(run.state, SagaState::Compensated);
+        assert_eq!(run.completed, 0);
+        assert_eq!(
+            run.error.as_deref(),
+            Some("step 'welcome' failed: welcome unavailable")
+        );
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["+database", "+bucket", "-bucket", "-database"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_compensation_leaves_run_failed() {
+        let (sagas, _bus) = sagas();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        sagas
+            .register(provisioning(log.clone(), Some("welcome"), Some("database")))
+            .unwrap();
+
+        let run = sagas.run("provisioning", json!({})).await.unwrap();
+
+        assert_eq!(run.state, SagaState::Failed);
+        assert_eq!(run.completed, 1);
+        assert!(run.error.unwrap().contains("database is stuck"));
+        assert!(sagas.store().unfinished().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_from_recorded_step() {
+        let (sagas, _bus) = sagas();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        sagas
+            .register(provisioning(log.clone(), None, None))
+            .unwrap();
+        let now = OffsetDateTime::now_utc();
+        sagas
+            .store()
+            .insert(SagaInstance {
+                id: "saga_1".to_string(),
+                saga: "provisioning".to_string(),
+                state: SagaState::Running,
+                data: json!({ "database": true }),
+                completed: 1,
+                error: None,
+                tenant: Some(TenantId::new("acme").unwrap()),
+                started_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+        let resumed = sagas.resume().await.unwrap();
+
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].state, SagaState::Completed);
+        assert_eq!(*log.lock().unwrap(), ["+bucket", "+welcome"]);
+        assert!(sagas.store().unfinished().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_reads_runs_and_refuses_unknown_updates() {
+        let db = RecordingExecutor::answering(vec![
+            Ok(vec![json!([{
+                "id": "saga_1",
+                "saga": "provision_tenant",
+                "state": "compensating",
+                "data": { "database": "db_1" },
+                "completed": 1,
+                "error": "mail server down",
+                "started_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:00:05Z"
+            }])]),
+            Ok(vec![json!([])]),
+        ]);
+        let store = SurrealSagaStore::new(db.clone());
+
+        let mut run = store.unfinished().await.unwrap().remove(0);
+        assert_eq!(run.state, SagaState::Compensating);
+        assert_eq!(run.tenant, None);
+
+        run.state = SagaState::Compensated;
+        run.error = None;
+        assert!(store.update(run).await.is_err());
+
+        let (surql, vars) = db.queries()[1].clone();
+        assert!(surql.contains("error = NONE"));
+        assert_eq!(vars["state"], "compensated");
+        assert_eq!(vars["data"], json!({ "database": "db_1" }));
+    }
+}
@@ -0,0 +1,145 @@
+//! Carrying named events between processes through a message broker
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Serialize;
+
+use atlas_kernel::{tenant::TenantId, InitCtx};
+
+use crate::{EventBus, NamedEvent};
+
+/// A broker connection forwarding named events both ways
+///
+/// The events module sends every locally emitted `NamedEvent` through each transport, and
+/// runs `receive` in a background task so remote events reach local handlers. Events that
+/// arrived through a transport are never sent back out.
+#[async_trait]
+pub trait EventTransport: Send + Sync {
+    /// Short name recorded as the `origin` of received events, e.g. `kafka`
+    fn name(&self) -> &'static str;
+
+    /// Deliver a locally emitted event to the broker
+    async fn send(&self, event: &NamedEvent) -> anyhow::Result<()>;
+
+    /// Emit events received from the broker on `bus` until the broker goes away
+    async fn receive(&self, bus: Arc<EventBus>) -> anyhow::Result<()>;
+}
+
+/// Wire format of a named event on a broker
+#[derive(Debug, Serialize)]
+struct Envelope<'a> {
+    event: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    payload: &'a serde_json::Value,
+    /// `EventBus::instance` of the sending process
+    source: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant: Option<&'a str>,
+}
+
+/// Encode an event as the JSON envelope `{"event": name, "version": 1, "payload": ..., "source": ...}`
+pub fn encode(event: &NamedEvent, source: &str) -> Vec<u8> {
+    serde_json::to_vec(&Envelope {
+        event: &event.name,
+        version: event.version,
+        payload: &event.payload,
+        source,
+        tenant: event.tenant.as_ref().map(TenantId::as_str),
+    })
+    .unwrap_or_default()
+}
+
+/// Decode an envelope received through `transport`
+///
+/// Returns `None` for events this process sent itself, whose local handlers already ran.
+pub fn decode(
+    transport: &'static str,
+    instance: &str,
+    bytes: &[u8],
+) -> anyhow::Result<Option<NamedEvent>> {
+    let envelope: serde_json::Value =
+        serde_json::from_slice(bytes).context("event is not a JSON envelope")?;
+    let name = envelope["event"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .context("event envelope has no name")?;
+    if envelope["source"].as_str() == Some(instance) {
+        return Ok(None);
+    }
+    Ok(Some(NamedEvent {
+        name: name.into(),
+        payload: Arc::new(envelope["payload"].clone()),
+        version: envelope["version"]
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok()),
+        origin: Some(transport),
+        tenant: envelope["tenant"]
+            .as_str()
+            .map(TenantId::new)
+            .transpose()
+            .context("event envelope has an invalid tenant")?,
+    }))
+}
+
+/// Start forwarding local events to `transport` and receiving remote ones
+///
+/// Both tasks belong to the module owning `ctx` and stop with it.
+pub(crate) fn run(ctx: &InitCtx, bus: Arc<EventBus>, transport: Arc<dyn EventTransport>) {
+    let mut local = bus.subscribe::<NamedEvent>();
+    let outgoing = transport.clone();
+    ctx.spawn(format!("{} send", transport.name()), async move {
+        while let Some(event) = local.recv().await {
+            if event.origin.is_some() {
+                continue;
+            }
+            if let Err(error) = outgoing.send(&event).await {
+                tracing::warn!(
+                    transport = outgoing.name(),
+                    event = %event.name,
+                    error = format!("{:#}", error),
+                    "failed to forward event"
+                );
+            }
+        }
+        Ok(())
+    });
+    ctx.spawn(format!("{} receive", transport.name()), async move {
+        transport
+            .receive(bus)
+            .await
+            .with_context(|| format!("{} transport stopped receiving", transport.name()))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_envelope_round_trip_marks_origin() {
+        let event = NamedEvent {
+            name: "books.created".into(),
+            payload: Arc::new(json!({ "id": 7 })),
+            version: Some(2),
+            origin: None,
+            tenant: Some(TenantId::new("acme").unwrap()),
+        };
+
+        let encoded = encode(&event, "api-1");
+        let decoded = decode("kafka", "api-2", &encoded).unwrap().unwrap();
+
+        assert_eq!(&*decoded.name, "books.created");
+        assert_eq!(*decoded.payload, json!({ "id": 7 }));
+        assert_eq!(decoded.version, Some(2));
+        assert_eq!(decoded.origin, Some("kafka"));
+        assert_eq!(decoded.tenant.as_ref().map(TenantId::as_str), Some("acme"));
+        assert_eq!(decode("kafka", "api-1", &encoded).unwrap(), None);
+        assert!(decode("kafka", "api-2", br#"{"payload": 1}"#).is_err());
+        assert!(decode("kafka", "api-2", b"not json").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Deprecation and sunset signalling for module routes
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use serde_json::{json, Value};
+use time::{format_description::FormatItem, macros::format_description, Date};
+
+use atlas_kernel::RouteDeprecation;
+
+const SUNSET_DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+const HTTP_DATE_FORMAT: &[FormatItem<'static>] =
+    format_description!("[weekday repr:short], [day] [month repr:short] [year] 00:00:00 GMT");
+
+/// A deprecated route resolved to its mounted path
+#[derive(Debug, Clone)]
+struct DeprecatedRoute {
+    method: String,
+    path: String,
+    sunset: Option<HeaderValue>,
+}
+
+/// Format a `YYYY-MM-DD` sunset date as the HTTP-date expected by the `Sunset` header
+fn sunset_header(sunset: &str) -> Option<HeaderValue> {
+    let date = Date::parse(sunset, SUNSET_DATE_FORMAT).ok()?;
+    let formatted = date.format(HTTP_DATE_FORMAT).ok()?;
+    HeaderValue::from_str(&formatted).ok()
+}
+
+/// Normalize a route path so `/api/books` and `/api/books/` compare equal
+fn normalize(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        "/"
+    } else {
+        trimmed
+    }
+}
+
+/// Middleware adding `Deprecation` and `Sunset` headers to deprecated routes
+async fn signal_deprecation(
+    State(routes): State<Arc<Vec<DeprecatedRoute>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().as_str().to_lowercase();
+    let matched = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| normalize(matched.as_str()).to_string());
+
+    let mut response = next.run(request).await;
+
+    let Some(matched) = matched else {
+        return response;
+    };
+    let deprecated = routes
+        .iter()
+        .find(|route| route.method == method && route.path == matched);
+    if let Some(route) = deprecated {
+        let headers = response.headers_mut();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        if let Some(sunset) = &route.sunset {
+            headers.insert("sunset", sunset.clone());
+        }
+    }
+
+    response
+}
+
+/// Wrap a module router so its deprecated routes announce themselves in response headers
+pub fn with_deprecation_headers(
+    module_name: &str,
+    module_router: Router,
+    deprecations: &[RouteDeprecation],
+) -> Router {
+    if deprecations.is_empty() {
+        return module_router;
+    }
+
+    let routes: Vec<DeprecatedRoute> = deprecations
+        .iter()
+        .map(|deprecation| {
+            let sunset = deprecation.sunset.and_then(|sunset| {
+                let header = sunset_header(sunset);
+                if header.is_none() {
+                    tracing::warn!(
+                        module = module_name,
+                        path = deprecation.path,
+                        sunset,
+                        "ignoring sunset date that is not formatted as YYYY-MM-DD"
+                    );
+                }
+                header
+            });
+            let mounted_path = format!("/api/{}{}", module_name, deprecation.path);
+            DeprecatedRoute {
+                method: deprecation.method.to_lowercase(),
+                path: normalize(&mounted_path).to_string(),
+                sunset,
+            }
+        })
+        .collect();
+
+    module_router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(routes),
+        signal_deprecation,
+    ))
+}
+
+/// Flag deprecated operations in a module's OpenAPI fragment
+pub fn mark_deprecated(module_spec: &mut Value, deprecations: &[RouteDeprecation]) {
+    for deprecation in deprecations {
+        let method = deprecation.method.to_lowercase();
+        let Some(operation) = module_spec
+            .get_mut("paths")
+            .and_then(|paths| paths.get_mut(deprecation.path))
+            .and_then(|path_item| path_item.get_mut(&method))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+
+        operation.insert("deprecated".to_string(), json!(true));
+        if let Some(sunset) = deprecation.sunset {
+            operation.insert("x-sunset".to_string(), json!(sunset));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    fn deprecations() -> Vec<RouteDeprecation> {
+        vec![RouteDeprecation {
+            method: "get",
+            path: "/legacy",
+            sunset: Some("2026-12-31"),
+        }]
+    }
+
+    #[test]
+    fn test_sunset_header_uses_http_date() {
+        let header = sunset_header("2026-12-31").unwrap();
+        assert_eq!(header, "Thu, 31 Dec 2026 00:00:00 GMT");
+        assert!(sunset_header("31/12/2026").is_none());
+    }
+
+    #[test]
+    fn test_mark_deprecated_flags_operation() {
+        let mut spec = json!({
+            "paths": {
+                "/legacy": { "get": { "summary": "Old" }, "post": { "summary": "Still fine" } }
+            }
+        });
+
+        mark_deprecated(&mut spec, &deprecations());
+
+        assert_eq!(spec["paths"]["/legacy"]["get"]["deprecated"], true);
+        assert_eq!(spec["paths"]["/legacy"]["get"]["x-sunset"], "2026-12-31");
+        assert!(spec["paths"]["/legacy"]["post"].get("deprecated").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_route_emits_headers() {
+        let module_router = Router::new()
+            .route("/legacy", get(|| async { "old" }))
+            .route("/current", get(|| async { "new" }));
+        let router = Router::new().nest(
+            "/api/books",
+            with_deprecation_headers("books", module_router, &deprecations()),
+        );
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/books/legacy")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(
+            response.headers()["sunset"],
+            "Thu, 31 Dec 2026 00:00:00 GMT"
+        );
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/api/books/current")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.headers().get("deprecation").is_none());
+    }
+}
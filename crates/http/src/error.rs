@@ -1,15 +1,33 @@
 //! Error handling for ATLAS HTTP layer
 
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 use serde_json::json;
+use std::future::Future;
+use std::sync::Arc;
 use thiserror::Error;
 use time::OffsetDateTime;
-use uuid::Uuid;
+
+use atlas_kernel::{id, Clock};
+
+tokio::task_local! {
+    static CLOCK: Arc<dyn Clock>;
+}
+
+/// Run `future` with error timestamps taken from `clock` instead of the system clock
+pub async fn with_clock<F: Future>(clock: Arc<dyn Clock>, future: F) -> F::Output {
+    CLOCK.scope(clock, future).await
+}
+
+fn now() -> OffsetDateTime {
+    CLOCK
+        .try_with(|clock| OffsetDateTime::from(clock.now()))
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+}
 
 /// Standard error response format for all HTTP errors
 #[derive(Debug, Serialize)]
@@ -50,6 +68,13 @@ pub enum AppError {
     #[error("bad request: {message}")]
     BadRequest { message: String, code: String },
 
+    #[error("too many requests: {message}")]
+    TooManyRequests {
+        message: String,
+        code: String,
+        retry_after_secs: u64,
+    },
+
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
@@ -104,13 +129,23 @@ impl AppError {
             code: "bad_request".to_string(),
         }
     }
+
+    /// Create a rate limit error, answered with `Retry-After: {retry_after_secs}`
+    pub fn too_many_requests(message: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::TooManyRequests {
+            message: message.into(),
+            code: "rate_limited".to_string(),
+            retry_after_secs,
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let error_id = Uuid::new_v4();
-        let timestamp = OffsetDateTime::now_utc().to_string();
+        let error_id = id::uuid_v7();
+        let timestamp = now().to_string();
 
+        let mut retry_after = None;
         let (status, error_code, message, details) = match self {
             AppError::Validation {
                 details,
@@ -135,6 +170,14 @@ impl IntoResponse for AppError {
             AppError::BadRequest { message, code } => {
                 (StatusCode::BAD_REQUEST, code, message, None)
             }
+            AppError::TooManyRequests {
+                message,
+                code,
+                retry_after_secs,
+            } => {
+                retry_after = Some(retry_after_secs);
+                (StatusCode::TOO_MANY_REQUESTS, code, message, None)
+            }
             AppError::Internal(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "internal_error".to_string(),
@@ -168,7 +211,13 @@ impl IntoResponse for AppError {
             }
         });
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -211,6 +260,13 @@ mod tests {
         assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[test]
+    fn test_too_many_requests_sets_retry_after() {
+        let response = AppError::too_many_requests("slow down", 12).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "12");
+    }
+
     #[test]
     fn test_error_response_format() {
         let error = AppError::not_found("Test resource not found");
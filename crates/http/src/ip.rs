@@ -0,0 +1,206 @@
+//! Client addresses and CIDR allow/deny lists
+//!
+//! `filter_ip` refuses requests whose client address the `[ip_filter]` rules exclude.
+//! The router installs it outside every other layer, so refused requests never reach
+//! authentication or module handlers.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_kernel::{
+    net::{IpNet, IpNetError},
+    settings::{IpFilterSettings, IpRules},
+};
+
+use crate::error::AppError;
+
+/// Client address from the proxy header when trusted, else from the connection
+///
+/// Only the last `X-Forwarded-For` entry is used: it is the one the trusted proxy in front
+/// of us appended, while earlier entries are whatever the client chose to send. Requests
+/// served without connect info report the unspecified address.
+pub fn client_ip(request: &Request, trust_forwarded_for: bool) -> IpAddr {
+    trust_forwarded_for
+        .then(|| forwarded_for(request.headers()))
+        .flatten()
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[derive(Debug, Default)]
+struct Ranges {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl Ranges {
+    fn parse(rules: &IpRules) -> Result<Self, IpNetError> {
+        let parse = |ranges: &[String]| {
+            ranges
+                .iter()
+                .map(|range| range.parse())
+                .collect::<Result<Vec<IpNet>, _>>()
+        };
+        Ok(Self {
+            allow: parse(&rules.allow)?,
+            deny: parse(&rules.deny)?,
+        })
+    }
+
+    fn allows(&self, ip: IpAddr) -> bool {
+        !self.deny.iter().any(|range| range.contains(ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|range| range.contains(ip)))
+    }
+}
+
+/// Compiled `[ip_filter]` rules
+#[derive(Debug)]
+pub struct IpFilter {
+    trust_forwarded_for: bool,
+    global: Ranges,
+    modules: HashMap<String, Ranges>,
+}
+
+impl IpFilter {
+    pub fn from_settings(settings: &IpFilterSettings) -> Result<Self, IpNetError> {
+        Ok(Self {
+            trust_forwarded_for: settings.trust_forwarded_for,
+            global: Ranges::parse(&settings.global())?,
+            modules: settings
+                .modules
+                .iter()
+                .map(|(module, rules)| Ok((module.clone(), Ranges::parse(rules)?)))
+                .collect::<Result<_, IpNetError>>()?,
+        })
+    }
+
+    /// Modules with rules of their own
+    pub fn modules(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    /// Whether `ip` may request `path`; module rules apply under `/api/{module}`
+    pub fn allows(&self, ip: IpAddr, path: &str) -> bool {
+        let module = path
+            .strip_prefix("/api/")
+            .map(|rest| rest.split('/').next().unwrap_or(rest))
+            .and_then(|module| self.modules.get(module));
+        self.global.allows(ip) && module.is_none_or(|ranges| ranges.allows(ip))
+    }
+}
+
+/// Middleware refusing requests from addresses the filter excludes with 403
+pub async fn filter_ip(
+    State(filter): State<Arc<IpFilter>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let ip = client_ip(&request, filter.trust_forwarded_for);
+    if !filter.allows(ip, request.uri().path()) {
+        tracing::debug!(%ip, path = request.uri().path(), "client address refused");
+        return Err(AppError::forbidden("client address not allowed"));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn filter() -> IpFilter {
+        IpFilter::from_settings(&IpFilterSettings {
+            enabled: true,
+            trust_forwarded_for: true,
+            allow: Vec::new(),
+            deny: vec!["203.0.113.0/24".to_string()],
+            modules: HashMap::from([(
+                "_meta".to_string(),
+                IpRules {
+                    allow: vec!["10.8.0.0/16".to_string()],
+                    deny: vec!["10.8.9.9".to_string()],
+                },
+            )]),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_module_rules_apply_on_top_of_global_rules() {
+        let filter = filter();
+        let ip = |addr: &str| addr.parse::<IpAddr>().unwrap();
+
+        assert!(filter.allows(ip("198.51.100.1"), "/api/books/1"));
+        assert!(!filter.allows(ip("203.0.113.5"), "/api/books/1"));
+        assert!(!filter.allows(ip("198.51.100.1"), "/api/_meta/modules"));
+        assert!(filter.allows(ip("10.8.0.3"), "/api/_meta"));
+        assert!(!filter.allows(ip("10.8.9.9"), "/api/_meta/config"));
+        assert!(filter.allows(ip("198.51.100.1"), "/api/_metadata"));
+        assert!(filter.allows(ip("198.51.100.1"), "/healthz"));
+    }
+
+    #[tokio::test]
+    async fn test_refused_addresses_get_forbidden() {
+        let router = Router::new()
+            .route("/api/_meta/modules", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(filter()),
+                filter_ip,
+            ));
+        let status = |forwarded_for: &'static str| {
+            router.clone().oneshot(
+                Request::builder()
+                    .uri("/api/_meta/modules")
+                    .header("x-forwarded-for", forwarded_for)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(status("10.8.1.1").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            status("192.0.2.1, 10.8.1.1").await.unwrap().status(),
+            StatusCode::OK
+        );
+        // A client cannot sneak in an allowed address ahead of the one the proxy added
+        assert_eq!(
+            status("10.8.1.1, 192.0.2.1").await.unwrap().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn test_invalid_ranges_fail_to_compile() {
+        let settings = IpFilterSettings {
+            deny: vec!["not-an-address".to_string()],
+            ..IpFilterSettings::default()
+        };
+        assert!(IpFilter::from_settings(&settings).is_err());
+    }
+}
@@ -1,12 +1,24 @@
 //! HTTP server facade for ATLAS with Axum, error handling, and OpenAPI support.
 
+use std::sync::Arc;
+
 use anyhow::Context;
-use axum::{routing::get, Router};
+use axum::{routing::get, Json, Router};
 
-use atlas_kernel::ModuleRegistry;
+use atlas_kernel::{settings::Environment, BuildInfo, ModuleRegistry, SecurityScheme};
 
+pub mod deprecation;
 pub mod error;
+pub mod ip;
+pub mod meta;
+pub mod openapi;
+pub mod pagination;
 pub mod router;
+pub mod security;
+pub mod tenant;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod validation;
 
 use router::RouterBuilder;
 
@@ -38,16 +50,53 @@ pub async fn start_server(
         settings.server.port
     );
 
-    // Start serving
-    axum::serve(listener, app)
-        .await
-        .context("HTTP server failed")?;
+    // Start serving until Ctrl+C or SIGTERM, letting in-flight requests finish
+    // Client addresses feed per-IP budgets of anonymous callers
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .context("HTTP server failed")?;
 
+    tracing::info!("HTTP server stopped");
     Ok(())
 }
 
+/// Resolve when the process is asked to terminate
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %error, "failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(error) => {
+                tracing::error!(error = %error, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received");
+}
+
 /// Build the main HTTP router with all module routes mounted
-async fn build_router(
+pub async fn build_router(
     registry: &ModuleRegistry,
     settings: &atlas_kernel::settings::Settings,
 ) -> anyhow::Result<Router> {
@@ -63,10 +112,28 @@ async fn build_router(
     // Add health check route
     router_builder = router_builder.route("/healthz", get(health_check));
 
+    // Tell support exactly which build is deployed
+    router_builder = router_builder.route("/version", get(version));
+
+    // Authenticate the routes modules document as secured, with the credentials modules registered
+    let authenticators = security::Authenticators::published(registry.resources());
+    if let Some(admin_token) = &settings.admin.token {
+        authenticators.register(
+            SecurityScheme::AdminToken,
+            Arc::new(security::AdminTokenAuthenticator::new(admin_token)),
+        );
+    }
+
     // Mount module routes
     for module in registry.modules() {
         let module_name = module.name();
-        let module_router = module.routes();
+        let module_router = deprecation::with_deprecation_headers(
+            module_name,
+            module.routes(),
+            &module.deprecations(),
+        );
+        let module_router =
+            security::guard(module_router, &module.security(), authenticators.clone());
 
         // Check if the module router has any routes by trying to get the first route
         // This is a simple check - in practice, we'll mount all module routers
@@ -78,8 +145,42 @@ async fn build_router(
         router_builder = router_builder.mount_module(module_name, module_router);
     }
 
+    // Expose module and config introspection to operators holding the admin token
+    if let Some(admin_token) = &settings.admin.token {
+        tracing::info!("mounting admin introspection routes under /api/_meta");
+        router_builder = router_builder
+            .mount_module("_meta", meta::meta_routes(registry, settings, admin_token));
+    }
+
+    // Catch spec drift outside production by checking responses against the documented schemas
+    if settings.environment != Environment::Production {
+        let spec = openapi::merged_spec(registry, &settings.openapi);
+        router_builder = router_builder.with_response_validation(spec);
+    }
+
     // Add OpenAPI documentation
-    router_builder = router_builder.with_openapi(registry);
+    router_builder = router_builder.with_openapi(registry, &settings.openapi);
+
+    // Resolve each request's tenant before handlers and repositories need it
+    if settings.tenancy.enabled {
+        router_builder = router_builder.with_tenancy(&settings.tenancy);
+    }
+
+    // Give handlers access to settings and the services modules published
+    router_builder =
+        router_builder.with_app_context(registry.app_context(Arc::new(settings.clone())));
+
+    // Refuse excluded client addresses before any other middleware sees the request
+    if settings.ip_filter.enabled {
+        let filter =
+            ip::IpFilter::from_settings(&settings.ip_filter).context("invalid ip_filter ranges")?;
+        for module in filter.modules() {
+            if module != "_meta" && !registry.modules().iter().any(|m| m.name() == module) {
+                tracing::warn!(module, "ip_filter rules name an unregistered module");
+            }
+        }
+        router_builder = router_builder.with_ip_filter(filter);
+    }
 
     Ok(router_builder.build())
 }
@@ -88,3 +189,151 @@ async fn build_router(
 async fn health_check() -> &'static str {
     "ok"
 }
+
+/// Build metadata endpoint
+async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use atlas_kernel::{settings::Settings, Module, RouteSecurity};
+    use axum::{extract::Request, http::StatusCode, routing::delete};
+    use serde_json::{json, Value};
+
+    use crate::error::AppError;
+    use crate::security::{Authenticator, Authenticators};
+    use crate::testing::{request, send};
+
+    struct CatalogModule;
+
+    impl Module for CatalogModule {
+        fn name(&self) -> &'static str {
+            "catalog"
+        }
+
+        fn routes(&self) -> Router {
+            Router::new()
+                .route("/", get(|| async { "list" }).post(|| async { "created" }))
+                .route("/search", get(|| async { "found" }))
+                .route("/drafts", delete(|| async { "discarded" }))
+                .route(
+                    "/{id}",
+                    get(|| async { "shown" }).delete(|| async { "deleted" }),
+                )
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            let ok = json!({ "responses": { "200": { "description": "OK" } } });
+            Some(json!({
+                "paths": {
+                    "/": { "get": ok, "post": ok },
+                    "/search": { "get": ok },
+                    "/{id}": { "get": ok, "delete": ok }
+                }
+            }))
+        }
+
+        fn security(&self) -> Vec<RouteSecurity> {
+            ["post", "delete"]
+                .into_iter()
+                .map(|method| RouteSecurity {
+                    method,
+                    path: if method == "post" { "/" } else { "/{id}" },
+                    schemes: &[SecurityScheme::AdminToken, SecurityScheme::ApiKey],
+                    permission: None,
+                })
+                .collect()
+        }
+    }
+
+    struct HeaderKey;
+
+    #[async_trait]
+    impl Authenticator for HeaderKey {
+        async fn authenticate(&self, request: &mut Request) -> Result<bool, AppError> {
+            match request.headers().get("x-api-key") {
+                None => Ok(false),
+                Some(key) if key == "valid" => Ok(true),
+                Some(_) => Err(AppError::unauthorized("invalid API key")),
+            }
+        }
+    }
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register_custom(Arc::new(CatalogModule)).unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_every_operation_documented_as_secured_is_guarded() {
+        let registry = registry();
+        let settings = Settings::default();
+        let router = build_router(&registry, &settings).await.unwrap();
+        let spec = openapi::merged_spec(&registry, &settings.openapi);
+
+        let mut secured = Vec::new();
+        for (path, path_item) in spec["paths"].as_object().unwrap() {
+            for (method, operation) in path_item.as_object().unwrap() {
+                if operation.get("security").is_some() {
+                    let uri = path.trim_end_matches('/').replace("{id}", "42");
+                    secured.push((method.to_uppercase(), uri));
+                }
+            }
+        }
+        assert_eq!(secured.len(), 2);
+
+        for (method, uri) in secured {
+            let (status, _) = send(&router, request(&method, &uri, Value::Null)).await;
+            assert_eq!(
+                status,
+                StatusCode::UNAUTHORIZED,
+                "{method} {uri} is not guarded"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secured_routes_accept_any_registered_scheme() {
+        let registry = registry();
+        Authenticators::published(registry.resources())
+            .register(SecurityScheme::ApiKey, Arc::new(HeaderKey));
+        let mut settings = Settings::default();
+        settings.admin.token = Some("s3cret".to_string());
+        let router = build_router(&registry, &settings).await.unwrap();
+
+        let with_key = |key: &str| {
+            let mut request = request("POST", "/api/catalog", Value::Null);
+            request
+                .headers_mut()
+                .insert("x-api-key", key.parse().unwrap());
+            request
+        };
+        let (status, _) = send(&router, with_key("valid")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&router, with_key("forged")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let mut admin = request("DELETE", "/api/catalog/42", Value::Null);
+        admin
+            .headers_mut()
+            .insert("authorization", "Bearer s3cret".parse().unwrap());
+        let (status, _) = send(&router, admin).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Reads are not secured, and `/drafts` is not the `/{id}` route
+        for uri in ["/api/catalog", "/api/catalog/search", "/api/catalog/42"] {
+            let (status, _) = send(&router, request("GET", uri, Value::Null)).await;
+            assert_eq!(status, StatusCode::OK, "{uri}");
+        }
+        let (status, _) = send(
+            &router,
+            request("DELETE", "/api/catalog/drafts", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
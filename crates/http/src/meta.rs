@@ -0,0 +1,250 @@
+//! Admin-only introspection of the registered modules and effective settings
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap},
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use atlas_kernel::{crypto, settings::Settings, Module, ModuleHealth, ModuleInfo, ModuleRegistry};
+
+use crate::error::AppError;
+
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Registered module together with the metadata captured when the router was built
+struct RegisteredModule {
+    info: ModuleInfo,
+    module: Arc<dyn Module>,
+}
+
+#[derive(Clone)]
+struct MetaState {
+    modules: Arc<Vec<RegisteredModule>>,
+    /// Effective settings with secrets masked, captured when the router was built
+    config: Arc<Value>,
+    admin_token: Arc<str>,
+}
+
+/// One entry of the `/api/_meta/modules` listing
+#[derive(Debug, Serialize)]
+struct ModuleSummary {
+    #[serde(flatten)]
+    info: ModuleInfo,
+    mount_path: String,
+    /// Operations documented in the module's OpenAPI fragment
+    route_count: usize,
+    health: ModuleHealth,
+    /// Migration ids in the order the module declares them, applied or not
+    declared_migrations: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModulesResponse {
+    modules: Vec<ModuleSummary>,
+}
+
+/// Count the operations a module documents; axum routers cannot be enumerated
+pub fn documented_route_count(module: &dyn Module) -> usize {
+    let Some(spec) = module.openapi() else {
+        return 0;
+    };
+    spec.get("paths")
+        .and_then(Value::as_object)
+        .map(|paths| {
+            paths
+                .values()
+                .filter_map(Value::as_object)
+                .flat_map(|path_item| path_item.keys())
+                .filter(|key| HTTP_METHODS.contains(&key.as_str()))
+                .count()
+        })
+        .unwrap_or_default()
+}
+
+/// Require `Authorization: Bearer {admin_token}`: 401 when absent, 403 when wrong
+pub fn authorize_admin(headers: &HeaderMap, admin_token: &str) -> Result<(), AppError> {
+    let presented = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::unauthorized("admin bearer token required"))?;
+
+    if !crypto::constant_time_eq(presented.as_bytes(), admin_token.as_bytes()) {
+        return Err(AppError::forbidden("invalid admin token"));
+    }
+    Ok(())
+}
+
+async fn list_modules(
+    State(state): State<MetaState>,
+    headers: HeaderMap,
+) -> Result<Json<ModulesResponse>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+
+    let mut modules = Vec::with_capacity(state.modules.len());
+    for registered in state.modules.iter() {
+        let module = registered.module.as_ref();
+        modules.push(ModuleSummary {
+            info: registered.info.clone(),
+            mount_path: format!("/api/{}", module.name()),
+            route_count: documented_route_count(module),
+            health: module.health().await,
+            declared_migrations: module
+                .migrations()
+                .iter()
+                .map(|migration| migration.id)
+                .collect(),
+        });
+    }
+
+    Ok(Json(ModulesResponse { modules }))
+}
+
+async fn show_config(
+    State(state): State<MetaState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.config.as_ref().clone()))
+}
+
+/// Routes served under `/api/_meta`, guarded by `Authorization: Bearer {admin_token}`
+pub fn meta_routes(registry: &ModuleRegistry, settings: &Settings, admin_token: &str) -> Router {
+    let modules = registry
+        .module_info()
+        .into_iter()
+        .filter_map(|info| {
+            let module = registry.get_module(info.name)?.clone();
+            Some(RegisteredModule { info, module })
+        })
+        .collect();
+
+    Router::new()
+        .route("/modules", get(list_modules))
+        .route("/config", get(show_config))
+        .with_state(MetaState {
+            modules: Arc::new(modules),
+            config: Arc::new(settings.to_redacted_json()),
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::Migration;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    struct CatalogModule;
+
+    impl Module for CatalogModule {
+        fn name(&self) -> &'static str {
+            "catalog"
+        }
+
+        fn version(&self) -> Option<&'static str> {
+            Some("1.2.0")
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            Some(json!({
+                "paths": {
+                    "/": { "get": {}, "post": {} },
+                    "/{id}": { "get": {}, "parameters": [] }
+                }
+            }))
+        }
+
+        fn migrations(&self) -> Vec<Migration> {
+            vec![Migration {
+                id: "001_create_catalog",
+                up: "DEFINE TABLE catalog;",
+                down: Some("REMOVE TABLE catalog;"),
+            }]
+        }
+    }
+
+    fn router() -> Router {
+        let mut registry = ModuleRegistry::new();
+        registry.register_custom(Arc::new(CatalogModule)).unwrap();
+        let mut settings = Settings::default();
+        settings.admin.token = Some("s3cret".to_string());
+        Router::new().nest("/api/_meta", meta_routes(&registry, &settings, "s3cret"))
+    }
+
+    async fn get_meta(uri: &str, authorization: Option<&str>) -> axum::response::Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_modules_listing_requires_admin_token() {
+        assert_eq!(
+            get_meta("/api/_meta/modules", None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            get_meta("/api/_meta/modules", Some("Bearer wrong!"))
+                .await
+                .status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn test_modules_listing_describes_modules() {
+        let response = get_meta("/api/_meta/modules", Some("Bearer s3cret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let payload: Value = serde_json::from_slice(&body).unwrap();
+        let module = &payload["modules"][0];
+
+        assert_eq!(module["name"], "catalog");
+        assert_eq!(module["kind"], "custom");
+        assert_eq!(module["version"], "1.2.0");
+        assert_eq!(module["mount_path"], "/api/catalog");
+        assert_eq!(module["route_count"], 3);
+        assert_eq!(module["health"]["status"], "healthy");
+        assert_eq!(module["declared_migrations"], json!(["001_create_catalog"]));
+    }
+
+    #[tokio::test]
+    async fn test_config_endpoint_masks_secrets() {
+        assert_eq!(
+            get_meta("/api/_meta/config", None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = get_meta("/api/_meta/config", Some("Bearer s3cret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let payload: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["admin"]["token"], "***");
+        assert_eq!(payload["server"]["port"], 8080);
+    }
+}
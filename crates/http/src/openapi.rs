@@ -0,0 +1,617 @@
+//! OpenAPI document assembly for ATLAS modules
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{json, Value};
+
+use atlas_kernel::{settings::OpenApiSettings, Module, ModuleRegistry, SchemaExample};
+
+use crate::{deprecation, security};
+
+/// Build the base OpenAPI document shared by the merged and per-module specs
+fn base_spec(settings: &OpenApiSettings) -> Value {
+    let mut spec = json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": settings.title,
+            "version": settings.version
+        },
+        "paths": {},
+        "components": {
+            "schemas": {}
+        }
+    });
+
+    if let Some(description) = &settings.description {
+        spec["info"]["description"] = json!(description);
+    }
+    if let Some(contact) = &settings.contact {
+        spec["info"]["contact"] = json!(contact);
+    }
+    if !settings.servers.is_empty() {
+        spec["servers"] = json!(settings.servers);
+    }
+
+    // Add common error response schema
+    spec["components"]["schemas"]["ErrorResponse"] = json!({
+        "type": "object",
+        "properties": {
+            "error": {
+                "type": "object",
+                "properties": {
+                    "code": {
+                        "type": "string"
+                    },
+                    "message": {
+                        "type": "string"
+                    },
+                    "details": {
+                        "type": "array",
+                        "items": {}
+                    },
+                    "trace_id": {
+                        "type": "string"
+                    },
+                    "timestamp": {
+                        "type": "string"
+                    }
+                },
+                "required": ["code", "message", "trace_id", "timestamp"]
+            }
+        },
+        "required": ["error"]
+    });
+
+    spec
+}
+
+const SCHEMA_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Names of the schemas a module fragment declares under `components.schemas`
+fn schema_names(module_spec: &Value) -> Vec<String> {
+    module_spec
+        .get("components")
+        .and_then(|components| components.get("schemas"))
+        .and_then(Value::as_object)
+        .map(|schemas| schemas.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Attach example payloads to the schemas they name
+fn attach_examples(module_name: &str, module_spec: &mut Value, examples: Vec<SchemaExample>) {
+    for example in examples {
+        let schema = module_spec
+            .get_mut("components")
+            .and_then(|components| components.get_mut("schemas"))
+            .and_then(|schemas| schemas.get_mut(example.schema))
+            .and_then(Value::as_object_mut);
+        match schema {
+            Some(schema) => {
+                schema.insert("example".to_string(), example.value);
+            }
+            None => tracing::warn!(
+                module = module_name,
+                schema = example.schema,
+                "ignoring example for undeclared OpenAPI schema"
+            ),
+        }
+    }
+}
+
+/// Apply module-level annotations (examples, deprecations) to its raw fragment
+fn annotated_fragment(module: &dyn Module) -> Option<Value> {
+    let mut fragment = module.openapi()?;
+    attach_examples(module.name(), &mut fragment, module.openapi_examples());
+    deprecation::mark_deprecated(&mut fragment, &module.deprecations());
+    security::mark_secured(&mut fragment, &module.security());
+    Some(fragment)
+}
+
+/// Find schema names that would clash when merged, either across modules or with core schemas
+fn colliding_schemas<'a>(
+    spec: &Value,
+    fragments: impl Iterator<Item = &'a Value>,
+) -> HashSet<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    if let Some(core_schemas) = spec["components"]["schemas"].as_object() {
+        for name in core_schemas.keys() {
+            *counts.entry(name.clone()).or_default() += 1;
+        }
+    }
+    for fragment in fragments {
+        for name in schema_names(fragment) {
+            *counts.entry(name).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Rename colliding schemas to `{module_name}.{Schema}` and rewrite the `$ref`s pointing at them
+fn namespace_schemas(module_name: &str, module_spec: &mut Value, colliding: &HashSet<String>) {
+    let renames: HashMap<String, String> = schema_names(module_spec)
+        .into_iter()
+        .filter(|name| colliding.contains(name))
+        .map(|name| {
+            let namespaced = format!("{}.{}", module_name, name);
+            (name, namespaced)
+        })
+        .collect();
+    if renames.is_empty() {
+        return;
+    }
+
+    if let Some(schemas) = module_spec["components"]["schemas"].as_object_mut() {
+        for (name, namespaced) in &renames {
+            tracing::debug!(
+                module = module_name,
+                schema = %name,
+                "namespacing colliding OpenAPI schema as {}",
+                namespaced
+            );
+            if let Some(schema_def) = schemas.remove(name) {
+                schemas.insert(namespaced.clone(), schema_def);
+            }
+        }
+    }
+
+    rewrite_refs(module_spec, &renames);
+}
+
+/// Recursively point `$ref`s at renamed schemas
+fn rewrite_refs(value: &mut Value, renames: &HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if key == "$ref" {
+                    let renamed = child
+                        .as_str()
+                        .and_then(|reference| reference.strip_prefix(SCHEMA_REF_PREFIX))
+                        .and_then(|name| renames.get(name));
+                    if let Some(namespaced) = renamed {
+                        *child = json!(format!("{}{}", SCHEMA_REF_PREFIX, namespaced));
+                    }
+                } else {
+                    rewrite_refs(child, renames);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                rewrite_refs(item, renames);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Merge a module's OpenAPI fragment into `spec`, prefixing its paths with `/api/{module_name}`
+fn merge_module(spec: &mut Value, module_name: &str, module_spec: &Value) {
+    // Merge paths from module
+    if let Some(paths_obj) = module_spec.get("paths").and_then(Value::as_object) {
+        for (path, path_item) in paths_obj {
+            let prefixed_path = format!("/api/{}{}", module_name, path);
+            spec["paths"][prefixed_path] = path_item.clone();
+        }
+    }
+
+    // Merge schemas from module
+    if let Some(schemas_obj) = module_spec
+        .get("components")
+        .and_then(|components| components.get("schemas"))
+        .and_then(Value::as_object)
+    {
+        for (schema_name, schema_def) in schemas_obj {
+            spec["components"]["schemas"][schema_name] = schema_def.clone();
+        }
+    }
+
+    // Merge tag metadata from module; the first declaration of a tag wins
+    if let Some(tags) = module_spec.get("tags").and_then(Value::as_array) {
+        for tag in tags {
+            let Some(name) = tag.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            if !has_tag(spec, name) {
+                push_tag(spec, tag.clone());
+            }
+        }
+    }
+}
+
+fn has_tag(spec: &Value, name: &str) -> bool {
+    spec["tags"]
+        .as_array()
+        .is_some_and(|tags| tags.iter().any(|tag| tag["name"] == name))
+}
+
+fn push_tag(spec: &mut Value, tag: Value) {
+    if !spec["tags"].is_array() {
+        spec["tags"] = json!([]);
+    }
+    if let Some(tags) = spec["tags"].as_array_mut() {
+        tags.push(tag);
+    }
+}
+
+/// Complete and order the top-level `tags` array
+///
+/// Tags used by operations but never declared are appended bare, then tags are
+/// stably sorted by their optional `x-order` extension (undeclared order sorts last).
+fn finalize_tags(spec: &mut Value) {
+    let operation_tags: Vec<String> = spec["paths"]
+        .as_object()
+        .into_iter()
+        .flat_map(|paths| paths.values())
+        .filter_map(Value::as_object)
+        .flat_map(|path_item| path_item.values())
+        .filter_map(|operation| operation.get("tags").and_then(Value::as_array))
+        .flatten()
+        .filter_map(|tag| tag.as_str().map(str::to_string))
+        .collect();
+    for name in operation_tags {
+        if !has_tag(spec, &name) {
+            push_tag(spec, json!({ "name": name }));
+        }
+    }
+
+    if let Some(tags) = spec["tags"].as_array_mut() {
+        tags.sort_by_key(|tag| {
+            tag.get("x-order")
+                .and_then(Value::as_i64)
+                .unwrap_or(i64::MAX)
+        });
+    }
+}
+
+/// Build the merged OpenAPI document covering every registered module
+pub fn merged_spec(registry: &ModuleRegistry, settings: &OpenApiSettings) -> Value {
+    let mut spec = base_spec(settings);
+
+    // Add server health endpoint
+    spec["paths"]["/healthz"] = json!({
+        "get": {
+            "summary": "Health check",
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "content": {
+                        "text/plain": {
+                            "schema": {
+                                "type": "string"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    // Collect OpenAPI specs from all modules
+    let mut fragments: Vec<(&'static str, Value)> = registry
+        .modules()
+        .into_iter()
+        .filter_map(|module| {
+            annotated_fragment(module.as_ref()).map(|fragment| (module.name(), fragment))
+        })
+        .collect();
+
+    // Namespace schemas that more than one contributor defines so none silently wins
+    let colliding = colliding_schemas(&spec, fragments.iter().map(|(_, fragment)| fragment));
+    for (module_name, fragment) in &mut fragments {
+        namespace_schemas(module_name, fragment, &colliding);
+        merge_module(&mut spec, module_name, fragment);
+    }
+    finalize_tags(&mut spec);
+    security::add_security_schemes(&mut spec);
+
+    spec
+}
+
+/// Build a standalone OpenAPI document for a single module
+///
+/// Returns `None` when the module does not contribute an OpenAPI fragment.
+pub fn module_spec(module: &dyn Module, settings: &OpenApiSettings) -> Option<Value> {
+    let mut module_spec = annotated_fragment(module)?;
+    let mut spec = base_spec(settings);
+    if let Some(version) = module.version() {
+        spec["info"]["version"] = json!(version);
+    }
+    if let Some(description) = module.description() {
+        spec["info"]["description"] = json!(description);
+    }
+
+    let colliding = colliding_schemas(&spec, std::iter::once(&module_spec));
+    namespace_schemas(module.name(), &mut module_spec, &colliding);
+    merge_module(&mut spec, module.name(), &module_spec);
+    finalize_tags(&mut spec);
+    security::add_security_schemes(&mut spec);
+    Some(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::settings::{OpenApiContact, OpenApiServer};
+    use std::sync::Arc;
+
+    struct DocumentedModule {
+        name: &'static str,
+    }
+
+    impl Module for DocumentedModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn version(&self) -> Option<&'static str> {
+            Some("2.0.0")
+        }
+
+        fn description(&self) -> Option<&'static str> {
+            Some("Documented test module")
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            Some(json!({
+                "paths": { "/": { "get": { "summary": "List" } } },
+                "components": { "schemas": { "Item": { "type": "object" } } }
+            }))
+        }
+    }
+
+    struct UndocumentedModule;
+
+    impl Module for UndocumentedModule {
+        fn name(&self) -> &'static str {
+            "plain"
+        }
+    }
+
+    #[test]
+    fn test_merged_spec_prefixes_module_paths() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(DocumentedModule { name: "books" }))
+            .unwrap();
+        registry
+            .register_custom(Arc::new(DocumentedModule { name: "users" }))
+            .unwrap();
+
+        let spec = merged_spec(&registry, &OpenApiSettings::default());
+
+        assert!(spec["paths"]["/healthz"].is_object());
+        assert!(spec["paths"]["/api/books/"].is_object());
+        assert!(spec["paths"]["/api/users/"].is_object());
+        assert!(spec["components"]["schemas"]["ErrorResponse"].is_object());
+    }
+
+    #[test]
+    fn test_module_spec_contains_only_its_module() {
+        let spec = module_spec(
+            &DocumentedModule { name: "books" },
+            &OpenApiSettings::default(),
+        )
+        .unwrap();
+
+        let paths = spec["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(paths.contains_key("/api/books/"));
+        assert!(spec["components"]["schemas"]["Item"].is_object());
+        assert!(spec["components"]["schemas"]["ErrorResponse"].is_object());
+        assert_eq!(spec["info"]["version"], "2.0.0");
+        assert_eq!(spec["info"]["description"], "Documented test module");
+    }
+
+    #[test]
+    fn test_module_spec_none_without_fragment() {
+        assert!(module_spec(&UndocumentedModule, &OpenApiSettings::default()).is_none());
+    }
+
+    struct UserSchemaModule {
+        name: &'static str,
+    }
+
+    impl Module for UserSchemaModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            Some(json!({
+                "paths": {
+                    "/": {
+                        "get": {
+                            "responses": {
+                                "200": { "schema": { "$ref": "#/components/schemas/User" } },
+                                "500": { "schema": { "$ref": "#/components/schemas/ErrorResponse" } }
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "User": { "type": "object" },
+                        "Unique": { "type": "string" }
+                    }
+                }
+            }))
+        }
+    }
+
+    #[test]
+    fn test_colliding_schemas_are_namespaced_per_module() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(UserSchemaModule { name: "users" }))
+            .unwrap();
+        registry
+            .register_custom(Arc::new(UserSchemaModule { name: "admins" }))
+            .unwrap();
+
+        let spec = merged_spec(&registry, &OpenApiSettings::default());
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+
+        assert!(!schemas.contains_key("User"));
+        assert!(schemas.contains_key("users.User"));
+        assert!(schemas.contains_key("admins.User"));
+        assert!(schemas.contains_key("users.Unique"));
+
+        let users_responses = &spec["paths"]["/api/users/"]["get"]["responses"];
+        assert_eq!(
+            users_responses["200"]["schema"]["$ref"],
+            "#/components/schemas/users.User"
+        );
+        // References to core schemas stay untouched
+        assert_eq!(
+            users_responses["500"]["schema"]["$ref"],
+            "#/components/schemas/ErrorResponse"
+        );
+    }
+
+    #[test]
+    fn test_unique_schemas_keep_their_names() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(UserSchemaModule { name: "users" }))
+            .unwrap();
+
+        let spec = merged_spec(&registry, &OpenApiSettings::default());
+
+        assert!(spec["components"]["schemas"]["User"].is_object());
+        assert_eq!(
+            spec["paths"]["/api/users/"]["get"]["responses"]["200"]["schema"]["$ref"],
+            "#/components/schemas/User"
+        );
+    }
+
+    struct TaggedModule {
+        name: &'static str,
+        tags: Value,
+    }
+
+    impl Module for TaggedModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            Some(json!({
+                "tags": self.tags,
+                "paths": {
+                    "/": { "get": { "tags": ["Catalog", "Untagged"] } }
+                }
+            }))
+        }
+    }
+
+    #[test]
+    fn test_module_tags_are_merged_and_ordered() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(TaggedModule {
+                name: "books",
+                tags: json!([
+                    { "name": "Catalog", "description": "Books — catalog management", "x-order": 2 }
+                ]),
+            }))
+            .unwrap();
+        registry
+            .register_custom(Arc::new(TaggedModule {
+                name: "users",
+                tags: json!([
+                    { "name": "Accounts", "description": "User accounts", "x-order": 1 },
+                    { "name": "Catalog", "description": "Duplicate declaration" }
+                ]),
+            }))
+            .unwrap();
+
+        let spec = merged_spec(&registry, &OpenApiSettings::default());
+        let tags = spec["tags"].as_array().unwrap();
+        let names: Vec<&str> = tags
+            .iter()
+            .map(|tag| tag["name"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(names, vec!["Accounts", "Catalog", "Untagged"]);
+        assert_eq!(tags[1]["description"], "Books — catalog management");
+        assert!(tags[2].get("description").is_none());
+    }
+
+    #[derive(Default, serde::Serialize)]
+    struct Item {
+        name: String,
+        quantity: u32,
+    }
+
+    struct ExampleModule;
+
+    impl Module for ExampleModule {
+        fn name(&self) -> &'static str {
+            "inventory"
+        }
+
+        fn openapi(&self) -> Option<Value> {
+            Some(json!({
+                "components": {
+                    "schemas": {
+                        "Item": { "type": "object" },
+                        "CreateItem": { "type": "object" }
+                    }
+                }
+            }))
+        }
+
+        fn openapi_examples(&self) -> Vec<SchemaExample> {
+            vec![
+                SchemaExample::from_default::<Item>("Item"),
+                SchemaExample::new("CreateItem", json!({ "name": "Widget", "quantity": 3 })),
+                SchemaExample::new("Missing", json!({})),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_examples_are_attached_to_schemas() {
+        let spec = module_spec(&ExampleModule, &OpenApiSettings::default()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        assert_eq!(
+            schemas["Item"]["example"],
+            json!({ "name": "", "quantity": 0 })
+        );
+        assert_eq!(schemas["CreateItem"]["example"]["name"], "Widget");
+        assert!(schemas.get("Missing").is_none());
+    }
+
+    #[test]
+    fn test_info_and_servers_come_from_settings() {
+        let settings = OpenApiSettings {
+            title: "Storefront API".to_string(),
+            version: "2.3.0".to_string(),
+            description: None,
+            contact: Some(OpenApiContact {
+                name: Some("Platform Team".to_string()),
+                email: Some("platform@example.com".to_string()),
+                url: None,
+            }),
+            servers: vec![OpenApiServer {
+                url: "https://api.example.com".to_string(),
+                description: Some("Production".to_string()),
+            }],
+        };
+
+        let spec = merged_spec(&ModuleRegistry::new(), &settings);
+
+        assert_eq!(spec["info"]["title"], "Storefront API");
+        assert_eq!(spec["info"]["version"], "2.3.0");
+        assert!(spec["info"].get("description").is_none());
+        assert_eq!(spec["info"]["contact"]["email"], "platform@example.com");
+        assert!(spec["info"]["contact"].get("url").is_none());
+        assert_eq!(spec["servers"][0]["url"], "https://api.example.com");
+    }
+}
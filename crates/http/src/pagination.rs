@@ -0,0 +1,315 @@
+//! Query-string pagination and sorting shared by list endpoints
+//!
+//! Handlers take `Pagination` as an extractor alongside their own `Query<...>` of
+//! filters, parse `?sort=` with `Sort::parse`, and answer with a `Page`. The `openapi`
+//! helpers describe the same parameters and envelope in module fragments.
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+
+/// `?page=` and `?per_page=`, validated; pages are numbered from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: u64,
+    pub per_page: u64,
+}
+
+impl Pagination {
+    /// Items per page when `per_page` is not given
+    pub const DEFAULT_PER_PAGE: u64 = 20;
+    /// Largest accepted `per_page`
+    pub const MAX_PER_PAGE: u64 = 100;
+
+    /// Items skipped before this page
+    pub fn offset(&self) -> u64 {
+        (self.page - 1).saturating_mul(self.per_page)
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.per_page
+    }
+
+    /// This page of an already filtered and sorted list
+    pub fn slice<T>(&self, items: Vec<T>) -> Vec<T> {
+        let offset = usize::try_from(self.offset()).unwrap_or(usize::MAX);
+        items
+            .into_iter()
+            .skip(offset)
+            .take(self.per_page as usize)
+            .collect()
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: Self::DEFAULT_PER_PAGE,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawPagination {
+    page: Option<String>,
+    per_page: Option<String>,
+}
+
+fn parse_param(
+    field: &str,
+    value: Option<&str>,
+    default: u64,
+    max: u64,
+    details: &mut Vec<Value>,
+) -> u64 {
+    let Some(value) = value else {
+        return default;
+    };
+    match value.parse::<u64>() {
+        Ok(parsed) if (1..=max).contains(&parsed) => parsed,
+        Ok(_) => {
+            details.push(json!({ "field": field, "error": "out_of_range" }));
+            default
+        }
+        Err(_) => {
+            details.push(json!({ "field": field, "error": "invalid" }));
+            default
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Pagination {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let raw = Query::<RawPagination>::try_from_uri(&parts.uri)
+            .map(|Query(raw)| raw)
+            .unwrap_or_default();
+
+        let mut details = Vec::new();
+        let page = parse_param("page", raw.page.as_deref(), 1, u64::MAX, &mut details);
+        let per_page = parse_param(
+            "per_page",
+            raw.per_page.as_deref(),
+            Self::DEFAULT_PER_PAGE,
+            Self::MAX_PER_PAGE,
+            &mut details,
+        );
+        if !details.is_empty() {
+            return Err(AppError::validation(
+                details,
+                format!(
+                    "page must be a positive integer and per_page between 1 and {}",
+                    Self::MAX_PER_PAGE
+                ),
+            ));
+        }
+        Ok(Self { page, per_page })
+    }
+}
+
+/// `?sort=field` for ascending or `?sort=-field` for descending order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    /// One of the fields the endpoint allows, so it is safe to put into a query
+    pub field: &'static str,
+    pub descending: bool,
+}
+
+impl Sort {
+    pub const fn asc(field: &'static str) -> Self {
+        Self {
+            field,
+            descending: false,
+        }
+    }
+
+    pub const fn desc(field: &'static str) -> Self {
+        Self {
+            field,
+            descending: true,
+        }
+    }
+
+    /// Parse a `sort` parameter naming one of `allowed`, or `default` when it is absent
+    pub fn parse(
+        value: Option<&str>,
+        allowed: &[&'static str],
+        default: Sort,
+    ) -> Result<Self, AppError> {
+        let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+            return Ok(default);
+        };
+        let (name, descending) = match value.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (value, false),
+        };
+        allowed
+            .iter()
+            .find(|field| **field == name)
+            .map(|field| Self { field, descending })
+            .ok_or_else(|| {
+                AppError::validation(
+                    vec![json!({ "field": "sort", "error": "invalid" })],
+                    format!(
+                        "sort must be one of {}, optionally prefixed with '-'",
+                        allowed.join(", ")
+                    ),
+                )
+            })
+    }
+
+    /// Apply the direction to an ascending comparison
+    pub fn order(&self, ordering: std::cmp::Ordering) -> std::cmp::Ordering {
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// `ORDER BY` clause for SurrealQL
+    pub fn to_surql(&self) -> String {
+        format!(
+            "ORDER BY {} {}",
+            self.field,
+            if self.descending { "DESC" } else { "ASC" }
+        )
+    }
+}
+
+/// One page of a list endpoint's results
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    /// Items matching the filters across all pages
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: u64, pagination: Pagination) -> Self {
+        Self {
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total,
+        }
+    }
+}
+
+/// OpenAPI descriptions of the pagination parameters and envelope
+pub mod openapi {
+    use serde_json::{json, Value};
+
+    use super::{Pagination, Sort};
+
+    /// `page` and `per_page` query parameters
+    pub fn parameters() -> Vec<Value> {
+        vec![
+            json!({
+                "name": "page",
+                "in": "query",
+                "description": "Page number, starting at 1",
+                "schema": { "type": "integer", "minimum": 1, "default": 1 }
+            }),
+            json!({
+                "name": "per_page",
+                "in": "query",
+                "description": "Items per page",
+                "schema": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": Pagination::MAX_PER_PAGE,
+                    "default": Pagination::DEFAULT_PER_PAGE
+                }
+            }),
+        ]
+    }
+
+    /// `sort` query parameter accepting `allowed` fields, each optionally prefixed with `-`
+    pub fn sort_parameter(allowed: &[&str], default: Sort) -> Value {
+        let values: Vec<String> = allowed
+            .iter()
+            .flat_map(|field| [field.to_string(), format!("-{}", field)])
+            .collect();
+        let default = if default.descending {
+            format!("-{}", default.field)
+        } else {
+            default.field.to_string()
+        };
+        json!({
+            "name": "sort",
+            "in": "query",
+            "description": "Field to sort by; prefix with `-` for descending order",
+            "schema": { "type": "string", "enum": values, "default": default }
+        })
+    }
+
+    /// Envelope of a `Page` whose items match `item`
+    pub fn page_schema(item: Value) -> Value {
+        json!({
+            "type": "object",
+            "required": ["items", "page", "per_page", "total"],
+            "properties": {
+                "items": { "type": "array", "items": item },
+                "page": { "type": "integer" },
+                "per_page": { "type": "integer" },
+                "total": { "type": "integer" }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    async fn extract(uri: &str) -> Result<Pagination, AppError> {
+        let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        Pagination::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_pagination_defaults_and_bounds() {
+        assert_eq!(extract("/books").await.unwrap(), Pagination::default());
+
+        let pagination = extract("/books?author=Jim&page=3&per_page=10")
+            .await
+            .unwrap();
+        assert_eq!((pagination.offset(), pagination.limit()), (20, 10));
+        assert_eq!(
+            pagination.slice((0..25).collect()),
+            vec![20, 21, 22, 23, 24]
+        );
+
+        for uri in ["/books?page=0", "/books?per_page=101", "/books?page=two"] {
+            assert!(
+                matches!(extract(uri).await, Err(AppError::Validation { .. })),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn test_sort_accepts_only_allowed_fields() {
+        let allowed = ["title", "created_at"];
+        let default = Sort::asc("created_at");
+
+        assert_eq!(Sort::parse(None, &allowed, default).unwrap(), default);
+        let sort = Sort::parse(Some("-created_at"), &allowed, default).unwrap();
+        assert_eq!(sort, Sort::desc("created_at"));
+        assert_eq!(sort.to_surql(), "ORDER BY created_at DESC");
+        assert!(Sort::parse(Some("id; REMOVE TABLE book"), &allowed, default).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+//! OpenAPI security requirements for guarded module routes, and the guard enforcing them
+//!
+//! `mark_secured` documents a module's `Module::security` in its OpenAPI fragment, and
+//! `guard` authenticates requests to the same routes, so the spec cannot promise a check
+//! the server does not make.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use serde_json::{json, Map, Value};
+
+use atlas_kernel::{Resources, RouteSecurity, SecurityScheme};
+
+use crate::error::AppError;
+use crate::meta::authorize_admin;
+
+const SCHEMES: [SecurityScheme; 5] = [
+    SecurityScheme::AdminToken,
+    SecurityScheme::ApiKey,
+    SecurityScheme::Session,
+    SecurityScheme::ServiceToken,
+    SecurityScheme::Signature,
+];
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// OpenAPI definition of a security scheme
+fn scheme_definition(scheme: SecurityScheme) -> Value {
+    match scheme {
+        SecurityScheme::AdminToken => json!({
+            "type": "http",
+            "scheme": "bearer",
+            "description": "Operator token configured as `admin.token`"
+        }),
+        SecurityScheme::ApiKey => json!({
+            "type": "apiKey",
+            "in": "header",
+            "name": "x-api-key",
+            "description": "API key issued by the api_keys module"
+        }),
+        SecurityScheme::Session => json!({
+            "type": "apiKey",
+            "in": "cookie",
+            "name": "atlas_session",
+            "description": "Session cookie; the name follows `auth.session.cookie_name`"
+        }),
+        SecurityScheme::ServiceToken => json!({
+            "type": "http",
+            "scheme": "bearer",
+            "description": "Signed service token for internal callers"
+        }),
+        SecurityScheme::Signature => json!({
+            "type": "apiKey",
+            "in": "header",
+            "name": "x-atlas-signature",
+            "description": "`sha256=` HMAC of `{x-atlas-timestamp}.{body}` under the secret of the client named in `x-atlas-client`"
+        }),
+    }
+}
+
+/// Operations of a path item matching `method`, which may be `*`
+fn operations<'a>(
+    path_item: &'a mut Value,
+    method: &'a str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    path_item
+        .as_object_mut()
+        .into_iter()
+        .flat_map(|path_item| path_item.iter_mut())
+        .filter(move |(name, _)| {
+            METHODS.contains(&name.as_str()) && (method == "*" || name.as_str() == method)
+        })
+        .filter_map(|(_, operation)| operation.as_object_mut())
+}
+
+fn secure_operation(operation: &mut Map<String, Value>, security: &RouteSecurity) {
+    let requirements: Vec<Value> = security
+        .schemes
+        .iter()
+        .map(|scheme| json!({ scheme.name(): [] }))
+        .collect();
+    if !requirements.is_empty() {
+        operation.insert("security".to_string(), json!(requirements));
+    }
+
+    if let Some(permission) = security.permission {
+        operation.insert("x-permission".to_string(), json!(permission));
+    }
+
+    let Some(responses) = operation
+        .entry("responses")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+    else {
+        return;
+    };
+    if !security.schemes.is_empty() {
+        responses
+            .entry("401")
+            .or_insert_with(|| json!({ "description": "Missing or invalid credentials" }));
+    }
+    if let Some(permission) = security.permission {
+        responses.entry("403").or_insert_with(
+            || json!({ "description": format!("Caller lacks the `{}` permission", permission) }),
+        );
+    }
+}
+
+/// Add security requirements to the guarded operations of a module's OpenAPI fragment
+///
+/// Entries are applied in order, so a specific route listed after a `*` entry overrides it.
+pub fn mark_secured(module_spec: &mut Value, securities: &[RouteSecurity]) {
+    for security in securities {
+        let method = security.method.to_lowercase();
+        let Some(paths) = module_spec.get_mut("paths").and_then(Value::as_object_mut) else {
+            return;
+        };
+        for (path, path_item) in paths.iter_mut() {
+            if security.path != "*" && security.path != path {
+                continue;
+            }
+            for operation in operations(path_item, &method) {
+                secure_operation(operation, security);
+            }
+        }
+    }
+}
+
+/// Checks one kind of credential, e.g. API keys
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Authenticate `request`, adding the caller's identity to its extensions
+    ///
+    /// `Ok(false)` when the request carries no credential of this kind, so the next
+    /// scheme the operation accepts is tried.
+    async fn authenticate(&self, request: &mut Request) -> Result<bool, AppError>;
+}
+
+/// The `Authenticator` of each security scheme, published as a resource
+///
+/// Modules providing a credential register its authenticator during `init`; `guard`
+/// rejects requests to operations whose schemes have none.
+#[derive(Default)]
+pub struct Authenticators {
+    schemes: RwLock<HashMap<SecurityScheme, Arc<dyn Authenticator>>>,
+}
+
+impl Authenticators {
+    /// The authenticators published in `resources`, publishing an empty set if needed
+    pub fn published(resources: &Resources) -> Arc<Self> {
+        match resources.get::<Self>() {
+            Some(authenticators) => authenticators,
+            None => {
+                let authenticators = Arc::new(Self::default());
+                resources.insert_arc(authenticators.clone());
+                authenticators
+            }
+        }
+    }
+
+    /// Check `scheme` with `authenticator`, replacing any registered before
+    pub fn register(&self, scheme: SecurityScheme, authenticator: Arc<dyn Authenticator>) {
+        self.schemes
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(scheme, authenticator);
+    }
+
+    pub fn get(&self, scheme: SecurityScheme) -> Option<Arc<dyn Authenticator>> {
+        self.schemes
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&scheme)
+            .cloned()
+    }
+
+    pub fn contains(&self, scheme: SecurityScheme) -> bool {
+        self.get(scheme).is_some()
+    }
+}
+
+/// `Authorization: Bearer {admin.token}`
+pub struct AdminTokenAuthenticator {
+    token: String,
+}
+
+impl AdminTokenAuthenticator {
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: token.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Authenticator for AdminTokenAuthenticator {
+    async fn authenticate(&self, request: &mut Request) -> Result<bool, AppError> {
+        if !request
+            .headers()
+            .contains_key(axum::http::header::AUTHORIZATION)
+        {
+            return Ok(false);
+        }
+        authorize_admin(request.headers(), &self.token).map(|()| true)
+    }
+}
+
+/// The route template that matched `path`, relative to the mount point, e.g. `/{id}`
+///
+/// `matched` is the request's `MatchedPath`, which includes the mount point.
+fn route_template(matched: &str, path: &str) -> String {
+    let depth = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .count();
+    let segments: Vec<&str> = matched
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    format!(
+        "/{}",
+        segments[segments.len().saturating_sub(depth)..].join("/")
+    )
+}
+
+/// The schemes guarding `route`, as `mark_secured` documents them
+///
+/// Entries without schemes secure nothing, and later entries win as in the spec.
+fn guarding_schemes<'a>(
+    securities: &'a [RouteSecurity],
+    method: &str,
+    route: &str,
+) -> Option<&'a [SecurityScheme]> {
+    securities
+        .iter()
+        .rev()
+        .filter(|security| !security.schemes.is_empty())
+        .filter(|security| security.method == "*" || security.method.eq_ignore_ascii_case(method))
+        .find(|security| security.path == "*" || security.path == route)
+        .map(|security| security.schemes)
+}
+
+#[derive(Clone)]
+struct Guard {
+    securities: Arc<Vec<RouteSecurity>>,
+    authenticators: Arc<Authenticators>,
+}
+
+/// Authenticate requests to the routes `securities` secures
+///
+/// `securities` is the module's `Module::security`, which `mark_secured` documents. A
+/// route accepts a request once any of its schemes authenticates it; without a
+/// credential, or when no authenticator is registered for its schemes, the request is
+/// rejected with 401. Permissions are left to the handlers' `RequirePermission` checks.
+pub fn guard(
+    router: Router,
+    securities: &[RouteSecurity],
+    authenticators: Arc<Authenticators>,
+) -> Router {
+    if securities
+        .iter()
+        .all(|security| security.schemes.is_empty())
+    {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Guard {
+            securities: Arc::new(securities.to_vec()),
+            authenticators,
+        },
+        authenticate,
+    ))
+}
+
+async fn authenticate(
+    State(guard): State<Guard>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    // HEAD is answered by the GET handler
+    let method = match request.method().as_str() {
+        "HEAD" => "get",
+        method => method,
+    }
+    .to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => route_template(matched.as_str(), request.uri().path()),
+        None => request.uri().path().to_string(),
+    };
+    let Some(schemes) = guarding_schemes(&guard.securities, &method, &route) else {
+        return Ok(next.run(request).await);
+    };
+
+    let mut rejection = None;
+    for scheme in schemes {
+        let Some(authenticator) = guard.authenticators.get(*scheme) else {
+            continue;
+        };
+        match authenticator.authenticate(&mut request).await {
+            Ok(true) => return Ok(next.run(request).await),
+            Ok(false) => {}
+            Err(error) => {
+                rejection.get_or_insert(error);
+            }
+        }
+    }
+    Err(rejection.unwrap_or_else(|| AppError::unauthorized("authentication required")))
+}
+
+/// Declare every security scheme the operations of `spec` refer to
+pub fn add_security_schemes(spec: &mut Value) {
+    let used: Vec<String> = spec["paths"]
+        .as_object()
+        .into_iter()
+        .flat_map(|paths| paths.values())
+        .filter_map(Value::as_object)
+        .flat_map(|path_item| path_item.values())
+        .filter_map(|operation| operation.get("security").and_then(Value::as_array))
+        .flatten()
+        .filter_map(Value::as_object)
+        .flat_map(|requirement| requirement.keys().cloned())
+        .collect();
+
+    for scheme in SCHEMES {
+        if used.iter().any(|name| name == scheme.name())
+            && spec["components"]["securitySchemes"][scheme.name()].is_null()
+        {
+            spec["components"]["securitySchemes"][scheme.name()] = scheme_definition(scheme);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment() -> Value {
+        json!({
+            "paths": {
+                "/": {
+                    "get": { "responses": { "200": { "description": "List" } } },
+                    "post": { "responses": { "201": { "description": "Created" } } }
+                },
+                "/{id}": {
+                    "parameters": [],
+                    "delete": { "responses": { "204": { "description": "Deleted" } } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_mark_secured_adds_requirements_and_responses() {
+        let mut spec = fragment();
+
+        mark_secured(
+            &mut spec,
+            &[RouteSecurity {
+                method: "post",
+                path: "/",
+                schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+                permission: Some("books:write"),
+            }],
+        );
+
+        let post = &spec["paths"]["/"]["post"];
+        assert_eq!(
+            post["security"],
+            json!([{ "apiKey": [] }, { "session": [] }])
+        );
+        assert_eq!(post["x-permission"], "books:write");
+        assert!(post["responses"]["401"].is_object());
+        assert!(post["responses"]["403"]["description"]
+            .as_str()
+            .unwrap()
+            .contains("books:write"));
+        assert!(spec["paths"]["/"]["get"].get("security").is_none());
+    }
+
+    #[test]
+    fn test_wildcards_cover_every_operation_and_later_entries_win() {
+        let mut spec = fragment();
+
+        mark_secured(
+            &mut spec,
+            &[
+                RouteSecurity {
+                    method: "*",
+                    path: "*",
+                    schemes: &[SecurityScheme::AdminToken],
+                    permission: None,
+                },
+                RouteSecurity {
+                    method: "get",
+                    path: "/",
+                    schemes: &[SecurityScheme::ServiceToken],
+                    permission: None,
+                },
+            ],
+        );
+
+        assert_eq!(
+            spec["paths"]["/"]["get"]["security"],
+            json!([{ "serviceToken": [] }])
+        );
+        assert_eq!(
+            spec["paths"]["/{id}"]["delete"]["security"],
+            json!([{ "adminToken": [] }])
+        );
+        assert!(spec["paths"]["/{id}"]["parameters"].is_array());
+    }
+
+    #[test]
+    fn test_only_used_schemes_are_declared() {
+        let mut spec = json!({
+            "paths": { "/api/books/": { "get": { "security": [{ "apiKey": [] }] } } },
+            "components": { "schemas": {} }
+        });
+
+        add_security_schemes(&mut spec);
+
+        let schemes = spec["components"]["securitySchemes"].as_object().unwrap();
+        assert_eq!(schemes.len(), 1);
+        assert_eq!(schemes["apiKey"]["name"], "x-api-key");
+    }
+}
@@ -0,0 +1,218 @@
+//! Per-request tenant resolution
+//!
+//! `resolve_tenant` finds the tenant of a request from the sources in `[tenancy]`, stores
+//! a `TenantCtx` in the request extensions and runs the rest of the request inside
+//! `atlas_kernel::tenant::scope`, so repositories see it through `tenant::current()`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{header::HOST, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+use atlas_kernel::{
+    settings::{TenancySettings, TenantSource},
+    tenant::{self, TenantId},
+};
+
+use crate::error::AppError;
+
+/// Tenant asserted by the caller's credentials, inserted by authentication middleware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantClaim(pub TenantId);
+
+/// The tenant a request runs for
+///
+/// Extracting `TenantCtx` rejects requests without a tenant; use `Option<TenantCtx>`
+/// where a tenant is optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantCtx {
+    pub id: TenantId,
+    pub source: TenantSource,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for TenantCtx {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<TenantCtx>()
+            .cloned()
+            .ok_or_else(|| AppError::bad_request("tenant required"))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for TenantCtx {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<TenantCtx>().cloned())
+    }
+}
+
+/// Middleware resolving the request's tenant
+///
+/// The router applies it to every route when `tenancy.enabled`. Claims are set by
+/// authentication middleware, which runs later on module routes, so modules honouring
+/// the `claim` source add it again with `route_layer` after their authentication layer.
+pub async fn resolve_tenant(
+    State(settings): State<Arc<TenancySettings>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(tenant) = resolve(&settings, &request)? else {
+        return Ok(next.run(request).await);
+    };
+    request.extensions_mut().insert(tenant.clone());
+    Ok(tenant::scope(tenant.id, next.run(request)).await)
+}
+
+/// Middleware rejecting requests that `resolve_tenant` found no tenant for
+///
+/// Modules whose data is scoped by tenant add it with `route_layer` when
+/// `tenancy.enabled`, so their repositories never run without a tenant in scope.
+pub async fn require_tenant(request: Request, next: Next) -> Result<Response, AppError> {
+    if request.extensions().get::<TenantCtx>().is_none() {
+        return Err(AppError::bad_request("tenant required"));
+    }
+    Ok(next.run(request).await)
+}
+
+fn resolve(settings: &TenancySettings, request: &Request) -> Result<Option<TenantCtx>, AppError> {
+    for &source in &settings.sources {
+        let id = match source {
+            TenantSource::Claim => request
+                .extensions()
+                .get::<TenantClaim>()
+                .map(|claim| claim.0.clone()),
+            TenantSource::Header => request
+                .headers()
+                .get(settings.header.as_str())
+                .map(|value| {
+                    let value = value
+                        .to_str()
+                        .map_err(|_| AppError::bad_request("tenant header is not valid text"))?;
+                    TenantId::new(value).map_err(|error| AppError::bad_request(error.to_string()))
+                })
+                .transpose()?,
+            TenantSource::Subdomain => settings
+                .base_domain
+                .as_deref()
+                .and_then(|base_domain| subdomain(request, base_domain))
+                .and_then(|label| TenantId::new(label).ok()),
+        };
+        if let Some(id) = id {
+            return Ok(Some(TenantCtx { id, source }));
+        }
+    }
+    Ok(None)
+}
+
+/// `acme` for a request to `acme.example.com:8080` with base domain `example.com`
+fn subdomain<'a>(request: &'a Request, base_domain: &str) -> Option<&'a str> {
+    let host = request
+        .headers()
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| request.uri().host())?;
+    let host = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    let label = host
+        .strip_suffix(base_domain.trim_start_matches('.'))?
+        .strip_suffix('.')?;
+    (!label.is_empty() && !label.contains('.')).then_some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let settings = TenancySettings {
+            enabled: true,
+            base_domain: Some("example.com".to_string()),
+            ..TenancySettings::default()
+        };
+        Router::new()
+            .route(
+                "/required",
+                get(|tenant: TenantCtx| async move {
+                    // Handlers and the repositories they call see the same tenant
+                    assert_eq!(tenant::current().as_ref(), Some(&tenant.id));
+                    tenant.id.to_string()
+                }),
+            )
+            .route(
+                "/optional",
+                get(|tenant: Option<TenantCtx>| async move {
+                    tenant.map_or("none".to_string(), |tenant| tenant.id.to_string())
+                }),
+            )
+            .route(
+                "/guarded",
+                get(|| async { "ok" }).route_layer(axum::middleware::from_fn(require_tenant)),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(settings),
+                resolve_tenant,
+            ))
+    }
+
+    async fn send(uri: &str, headers: &[(&str, &str)]) -> (StatusCode, String) {
+        let mut request = Request::builder().uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_tenant_resolved_from_header_then_subdomain() {
+        assert_eq!(
+            send(
+                "/required",
+                &[("x-tenant-id", "acme"), ("host", "globex.example.com")]
+            )
+            .await,
+            (StatusCode::OK, "acme".to_string())
+        );
+        assert_eq!(
+            send("/required", &[("host", "globex.example.com:8080")]).await,
+            (StatusCode::OK, "globex".to_string())
+        );
+        assert_eq!(
+            send("/optional", &[("host", "a.b.example.com")]).await,
+            (StatusCode::OK, "none".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_invalid_tenant_rejected() {
+        assert_eq!(send("/required", &[]).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send("/guarded", &[]).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            send("/guarded", &[("x-tenant-id", "acme")]).await,
+            (StatusCode::OK, "ok".to_string())
+        );
+        assert_eq!(
+            send("/optional", &[("x-tenant-id", "Not Valid")]).await.0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
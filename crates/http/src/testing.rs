@@ -0,0 +1,38 @@
+//! Request helpers for router tests
+//!
+//! Available to this crate's tests and, through the `testing` feature, to other crates'
+//! dev-dependencies.
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+/// A JSON request; a `Value::Null` body sends an empty one
+pub fn request(method: &str, uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(if body.is_null() {
+            Body::empty()
+        } else {
+            Body::from(body.to_string())
+        })
+        .unwrap()
+}
+
+/// Send `request` through `router`, returning the status and the JSON body
+///
+/// Bodies that are not JSON, including empty ones, come back as `Value::Null`.
+pub async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
@@ -0,0 +1,360 @@
+//! Development-time validation of outgoing JSON responses against the OpenAPI spec
+//!
+//! Only a pragmatic subset of JSON Schema is checked (`$ref`, `type`, `properties`,
+//! `required`, `items`, `enum`, `nullable`); mismatches are logged, never enforced.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{MatchedPath, Request, State},
+    http::header::{CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+
+const SCHEMA_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Largest response body the validator buffers; bigger bodies are passed through unchecked
+const MAX_VALIDATED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Validates responses against the schemas declared in a merged OpenAPI document
+#[derive(Clone)]
+pub struct ResponseValidator {
+    spec: Arc<Value>,
+}
+
+impl ResponseValidator {
+    /// Create a validator for the given OpenAPI document
+    pub fn new(spec: Value) -> Self {
+        Self {
+            spec: Arc::new(spec),
+        }
+    }
+
+    /// Look up the JSON response schema declared for an operation and status code
+    fn response_schema(&self, path: &str, method: &str, status: u16) -> Option<&Value> {
+        let paths = self.spec.get("paths")?;
+        // Module routers mounted at "/" are documented with a trailing slash
+        let path_item = paths
+            .get(path)
+            .or_else(|| paths.get(format!("{}/", path.trim_end_matches('/'))))?;
+        let responses = path_item.get(method)?.get("responses")?;
+        let response = responses
+            .get(status.to_string())
+            .or_else(|| responses.get("default"))?;
+        response
+            .get("content")?
+            .get("application/json")?
+            .get("schema")
+    }
+
+    /// Validate `value` against `schema`, returning a message per mismatch
+    pub fn validate(&self, schema: &Value, value: &Value) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.validate_at(schema, value, "$", &mut errors);
+        errors
+    }
+
+    fn validate_at(&self, schema: &Value, value: &Value, location: &str, errors: &mut Vec<String>) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(resolved) => self.validate_at(resolved, value, location, errors),
+                None => errors.push(format!(
+                    "{}: unresolved schema reference {}",
+                    location, reference
+                )),
+            }
+            return;
+        }
+
+        if value.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+            return;
+        }
+
+        if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+            if !matches_type(expected, value) {
+                errors.push(format!(
+                    "{}: expected {}, found {}",
+                    location,
+                    expected,
+                    type_name(value)
+                ));
+                return;
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                errors.push(format!(
+                    "{}: value {} is not one of the allowed values",
+                    location, value
+                ));
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(field) {
+                        errors.push(format!(
+                            "{}: missing required property '{}'",
+                            location, field
+                        ));
+                    }
+                }
+            }
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (field, field_schema) in properties {
+                    if let Some(field_value) = object.get(field) {
+                        let field_location = format!("{}.{}", location, field);
+                        self.validate_at(field_schema, field_value, &field_location, errors);
+                    }
+                }
+            }
+        }
+
+        if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+            for (index, item) in items.iter().enumerate() {
+                let item_location = format!("{}[{}]", location, index);
+                self.validate_at(item_schema, item, &item_location, errors);
+            }
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let name = reference.strip_prefix(SCHEMA_REF_PREFIX)?;
+        self.spec.get("components")?.get("schemas")?.get(name)
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Middleware that logs a warning when a JSON response drifts from its documented schema
+pub async fn validate_response(
+    State(validator): State<ResponseValidator>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().as_str().to_lowercase();
+    let matched_path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string());
+
+    let response = next.run(request).await;
+
+    let Some(path) = matched_path else {
+        return response;
+    };
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+    let status = response.status().as_u16();
+    let Some(schema) = validator.response_schema(&path, &method, status) else {
+        return response;
+    };
+    let declared_length = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let is_small_enough = declared_length
+        .or_else(|| response.body().size_hint().upper())
+        .is_some_and(|length| length <= MAX_VALIDATED_BODY_BYTES as u64);
+    if !is_small_enough {
+        return response;
+    }
+    let schema = schema.clone();
+
+    let (parts, body) = response.into_parts();
+    let bytes = match buffer(body, &path).await {
+        Ok(bytes) => bytes,
+        Err(original) => return Response::from_parts(parts, original),
+    };
+
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(payload) => {
+            for mismatch in validator.validate(&schema, &payload) {
+                tracing::warn!(
+                    route = %path,
+                    method = %method,
+                    status,
+                    mismatch = %mismatch,
+                    "response does not match OpenAPI schema"
+                );
+            }
+        }
+        Err(error) => {
+            tracing::warn!(route = %path, %error, "response declared as JSON failed to parse");
+        }
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Read a body of at most `MAX_VALIDATED_BODY_BYTES`, or give back one that replays what
+/// was read followed by the rest (or the error) of the original, so the client always
+/// gets the body the handler produced
+async fn buffer(body: Body, path: &str) -> Result<Bytes, Body> {
+    let mut data = body.into_data_stream();
+    let mut chunks = Vec::new();
+    let mut length = 0;
+    while let Some(chunk) = data.next().await {
+        match chunk {
+            Ok(chunk) => {
+                length += chunk.len();
+                chunks.push(chunk);
+                if length > MAX_VALIDATED_BODY_BYTES {
+                    let read = stream::iter(chunks.into_iter().map(Ok));
+                    return Err(Body::from_stream(read.chain(data)));
+                }
+            }
+            Err(error) => {
+                tracing::warn!(route = %path, %error, "unable to buffer response for schema validation");
+                let read = chunks.into_iter().map(Ok);
+                return Err(Body::from_stream(stream::iter(
+                    read.chain(std::iter::once(Err(error))),
+                )));
+            }
+        }
+    }
+    Ok(Bytes::from(chunks.concat()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validator() -> ResponseValidator {
+        ResponseValidator::new(json!({
+            "paths": {
+                "/api/books/": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": { "$ref": "#/components/schemas/Book" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Book": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "pages": { "type": "integer" }
+                        },
+                        "required": ["id"]
+                    }
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn test_response_schema_lookup_tolerates_trailing_slash() {
+        let validator = validator();
+        assert!(validator
+            .response_schema("/api/books", "get", 200)
+            .is_some());
+        assert!(validator
+            .response_schema("/api/books/", "get", 200)
+            .is_some());
+        assert!(validator
+            .response_schema("/api/books", "get", 404)
+            .is_none());
+        assert!(validator
+            .response_schema("/api/books", "post", 200)
+            .is_none());
+    }
+
+    #[test]
+    fn test_matching_payload_has_no_errors() {
+        let validator = validator();
+        let schema = validator
+            .response_schema("/api/books", "get", 200)
+            .unwrap()
+            .clone();
+        let errors = validator.validate(&schema, &json!([{ "id": "book-1", "pages": 300 }]));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_mismatches_are_reported_with_location() {
+        let validator = validator();
+        let schema = validator
+            .response_schema("/api/books", "get", 200)
+            .unwrap()
+            .clone();
+        let errors = validator.validate(&schema, &json!([{ "pages": "many" }]));
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("$[0]: missing required property 'id'"));
+        assert!(errors[1].contains("$[0].pages: expected integer, found string"));
+    }
+
+    #[tokio::test]
+    async fn test_unbufferable_bodies_are_passed_on() {
+        let failing = Body::from_stream(stream::iter([
+            Ok(Bytes::from_static(b"[{")),
+            Err(std::io::Error::other("connection reset")),
+        ]));
+        let mut passed_on = buffer(failing, "/api/books")
+            .await
+            .unwrap_err()
+            .into_data_stream();
+        assert_eq!(passed_on.next().await.unwrap().unwrap(), "[{");
+        assert!(passed_on.next().await.unwrap().is_err());
+
+        let large = vec![b' '; MAX_VALIDATED_BODY_BYTES];
+        let chunked = Body::from_stream(stream::iter([
+            Ok::<_, std::io::Error>(Bytes::from(large.clone())),
+            Ok(Bytes::from_static(b"[]")),
+        ]));
+        let passed_on = buffer(chunked, "/api/books").await.unwrap_err();
+        let bytes = axum::body::to_bytes(passed_on, usize::MAX).await.unwrap();
+        assert_eq!(bytes.len(), MAX_VALIDATED_BODY_BYTES + 2);
+
+        let small = buffer(Body::from("[]"), "/api/books").await.unwrap();
+        assert_eq!(small, "[]");
+    }
+}
@@ -0,0 +1,286 @@
+//! Cron expressions
+//!
+//! `minute hour day month weekday`, with an optional leading seconds field. Each field
+//! takes `*`, values, ranges `a-b`, steps `*/n` or `a-b/n`, and comma-separated lists;
+//! months and weekdays also take three-letter names, and weekday `7` is Sunday. When
+//! both day and weekday are restricted a time matches either, as in Vixie cron.
+//! `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly` stand for the usual
+//! expressions. Times are UTC.
+
+use std::fmt;
+use std::str::FromStr;
+
+use time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Years searched for the next match before a schedule is treated as never firing
+const SEARCH_YEARS: i32 = 9;
+
+/// Why a cron expression was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid cron expression '{expression}': {reason}")]
+pub struct CronError {
+    pub expression: String,
+    pub reason: String,
+}
+
+/// Set of values a field matches, as a bitmask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field(u64);
+
+impl Field {
+    fn contains(self, value: u8) -> bool {
+        self.0 & (1 << value) != 0
+    }
+
+    fn parse(text: &str, min: u8, max: u8, names: &[&str]) -> Result<Self, String> {
+        let value = |text: &str| -> Result<u8, String> {
+            let value = match names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(text))
+            {
+                Some(index) => index as u8 + min,
+                None => text
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number", text))?,
+            };
+            if value < min || value > max {
+                return Err(format!("{} is outside {}-{}", value, min, max));
+            }
+            Ok(value)
+        };
+
+        let mut bits = 0u64;
+        for item in text.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => match step.parse::<u8>() {
+                    Ok(step) if step > 0 => (range, Some(step)),
+                    _ => return Err(format!("'{}' is not a valid step", step)),
+                },
+                None => (item, None),
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (value(start)?, value(end)?),
+                    None if step.is_some() => (value(range)?, max),
+                    None => {
+                        let value = value(range)?;
+                        (value, value)
+                    }
+                },
+            };
+            if start > end {
+                return Err(format!("range '{}' runs backwards", range));
+            }
+            for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(Self(bits))
+    }
+}
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    seconds: Field,
+    minutes: Field,
+    hours: Field,
+    days: Field,
+    months: Field,
+    weekdays: Field,
+    /// Whether the day and weekday fields were restricted, rather than `*`
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Schedule {
+    /// The expression as written
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    fn day_matches(&self, date: Date) -> bool {
+        let day = self.days.contains(date.day());
+        let weekday = self
+            .weekdays
+            .contains(date.weekday().number_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// The first matching time strictly after `after`, in UTC
+    ///
+    /// `None` when nothing matches within the next few years, e.g. for February 30.
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(UtcOffset::UTC);
+        let limit = after.year() + SEARCH_YEARS;
+        let mut time = after.replace_nanosecond(0).ok()? + Duration::SECOND;
+        while time.year() <= limit {
+            if !self.months.contains(u8::from(time.month())) {
+                let (year, month) = match time.month() {
+                    Month::December => (time.year() + 1, Month::January),
+                    month => (time.year(), month.next()),
+                };
+                time = Date::from_calendar_date(year, month, 1)
+                    .ok()?
+                    .midnight()
+                    .assume_utc();
+            } else if !self.day_matches(time.date()) {
+                time = time.date().next_day()?.midnight().assume_utc();
+            } else if !self.hours.contains(time.hour()) {
+                time = time.replace_minute(0).ok()?.replace_second(0).ok()? + Duration::HOUR;
+            } else if !self.minutes.contains(time.minute()) {
+                time = time.replace_second(0).ok()? + Duration::MINUTE;
+            } else if !self.seconds.contains(time.second()) {
+                time += Duration::SECOND;
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = CronError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| CronError {
+            expression: expression.to_string(),
+            reason,
+        };
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let mut fields: Vec<&str> = expanded.split_whitespace().collect();
+        match fields.len() {
+            5 => fields.insert(0, "0"),
+            6 => {}
+            count => return Err(error(format!("expected 5 or 6 fields, found {}", count))),
+        }
+        let field = |index: usize, name: &str, min: u8, max: u8, names: &[&str]| {
+            Field::parse(fields[index], min, max, names)
+                .map_err(|reason| error(format!("{}: {}", name, reason)))
+        };
+
+        let mut weekdays = field(5, "weekday", 0, 7, &WEEKDAYS)?;
+        if weekdays.contains(7) {
+            weekdays = Field((weekdays.0 | 1) & !(1 << 7));
+        }
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            seconds: field(0, "second", 0, 59, &[])?,
+            minutes: field(1, "minute", 0, 59, &[])?,
+            hours: field(2, "hour", 0, 23, &[])?,
+            days: field(3, "day", 1, 31, &[])?,
+            months: field(4, "month", 1, 12, &MONTHS)?,
+            weekdays,
+            days_restricted: !fields[3].starts_with('*'),
+            weekdays_restricted: !fields[5].starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn next(expression: &str, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        expression.parse::<Schedule>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_next_matching_times() {
+        let at = datetime!(2024-02-28 13:45:30 UTC);
+
+        assert_eq!(
+            next("* * * * *", at),
+            Some(datetime!(2024-02-28 13:46:00 UTC))
+        );
+        assert_eq!(
+            next("*/15 * * * * *", at),
+            Some(datetime!(2024-02-28 13:45:45 UTC))
+        );
+        assert_eq!(
+            next("30 9 * * *", at),
+            Some(datetime!(2024-02-29 09:30:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 1 * *", at),
+            Some(datetime!(2024-03-01 00:00:00 UTC))
+        );
+        assert_eq!(
+            next("@yearly", at),
+            Some(datetime!(2025-01-01 00:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 12 * * MON-FRI", at),
+            Some(datetime!(2024-02-29 12:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 * * 7", at),
+            Some(datetime!(2024-03-03 00:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 29 feb *", at),
+            Some(datetime!(2024-02-29 00:00:00 UTC))
+        );
+        assert_eq!(next("0 0 30 2 *", at), None);
+    }
+
+    #[test]
+    fn test_day_or_weekday_when_both_are_restricted() {
+        // The 15th, or any Monday
+        let at = datetime!(2024-03-01 00:00:00 UTC);
+        assert_eq!(
+            next("0 0 15 * 1", at),
+            Some(datetime!(2024-03-04 00:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 15 * 1", datetime!(2024-03-11 00:00:00 UTC)),
+            Some(datetime!(2024-03-15 00:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * foo *",
+        ] {
+            assert!(expression.parse::<Schedule>().is_err(), "{}", expression);
+        }
+        let error = "* 25 * * *".parse::<Schedule>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid cron expression '* 25 * * *': hour: 25 is outside 0-23"
+        );
+    }
+}
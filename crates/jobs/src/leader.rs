@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use atlas_kernel::{id, InitCtx};
+
+use crate::lock::Locks;
+
+/// Elects one leader among the replicas campaigning for the same lock
+///
+/// The leader holds a lease on the lock and renews it every third of the lease. When it
+/// stops renewing, e.g. because its process died, another replica takes the lock on its
+/// next campaign after the lease lapsed. A leader that cannot reach the lock store steps
+/// down rather than risk two leaders.
+pub struct LeaderElection {
+    locks: Arc<Locks>,
+    name: String,
+    holder: String,
+    lease: Duration,
+    leader: AtomicBool,
+}
+
+impl LeaderElection {
+    pub fn new(locks: Arc<Locks>, name: impl Into<String>, lease: Duration) -> Self {
+        Self {
+            locks,
+            name: name.into(),
+            holder: id::ulid().to_string().to_lowercase(),
+            lease,
+            leader: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this replica held the lease at its latest campaign
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Acquire)
+    }
+
+    /// Id this replica campaigns under
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// Take or renew the lease if it is free, returning whether this replica leads
+    pub async fn campaign(&self) -> bool {
+        let leading = match self
+            .locks
+            .acquire_as(&self.name, &self.holder, self.lease)
+            .await
+        {
+            Ok(lock) => lock.is_some(),
+            Err(error) => {
+                tracing::warn!(
+                    lock = %self.name,
+                    error = format!("{:#}", error),
+                    "failed to renew the leader lease"
+                );
+                false
+            }
+        };
+        if self.leader.swap(leading, Ordering::AcqRel) != leading {
+            if leading {
+                tracing::info!(lock = %self.name, holder = %self.holder, "became leader");
+            } else {
+                tracing::warn!(lock = %self.name, holder = %self.holder, "no longer leader");
+            }
+        }
+        leading
+    }
+
+    /// Campaign every third of the lease until the app stops
+    pub(crate) fn spawn(self: &Arc<Self>, ctx: &InitCtx) {
+        let election = self.clone();
+        let clock = ctx.clock.clone();
+        ctx.spawn(format!("leader election {}", self.name), async move {
+            loop {
+                clock.sleep(election.lease / 3).await;
+                election.campaign().await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::MemoryLockStore;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    #[tokio::test]
+    async fn test_one_replica_leads_until_its_lease_lapses() {
+        let clock = Arc::new(ManualClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let locks = Arc::new(Locks::new(Arc::new(MemoryLockStore::new()), clock.clone()));
+        let lease = Duration::from_secs(30);
+        let first = LeaderElection::new(locks.clone(), "scheduler", lease);
+        let second = LeaderElection::new(locks, "scheduler", lease);
+
+        assert!(first.campaign().await);
+        assert!(!second.campaign().await);
+        clock.advance(Duration::from_secs(20));
+        assert!(first.campaign().await);
+        clock.advance(Duration::from_secs(20));
+        assert!(!second.campaign().await);
+
+        // The first replica stops renewing
+        clock.advance(lease);
+        assert!(second.campaign().await);
+        assert!(!first.campaign().await);
+        assert!(!first.is_leader());
+        assert!(second.is_leader());
+    }
+}
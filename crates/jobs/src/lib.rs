@@ -0,0 +1,21 @@
+//! Scheduled and queued background jobs
+//!
+//! Modules declare recurring jobs through `Module::jobs()`, each with a cron expression
+//! (see [`cron`]). The scheduler module runs them, never two runs of the same job at
+//! once and, across replicas, only in the elected leader. It counts runs, failures and
+//! skipped runs per job. One-off work goes through the durable [`queue`], and [`lock`]
+//! keeps work from running twice at once across workers.
+
+pub mod cron;
+mod leader;
+pub mod lock;
+mod module;
+pub mod queue;
+mod scheduler;
+
+pub use cron::{CronError, Schedule};
+pub use leader::LeaderElection;
+pub use lock::{Lock, Locks, LocksExt, SurrealLockStore};
+pub use module::{create_module, SchedulerModule};
+pub use queue::{EnqueueOptions, JobError, JobsExt, Queue};
+pub use scheduler::{JobStats, JobStatus, Scheduler};
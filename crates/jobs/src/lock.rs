@@ -0,0 +1,349 @@
+//! Distributed locks
+//!
+//! A lock is a lease on a name: it is held by one holder until it is released or its
+//! lease runs out, so a crashed holder never keeps it for longer than the lease. Holders
+//! that keep working renew the lease before it lapses. `Locks::run_exclusive` does both
+//! around a future, for work that must not run twice at once across workers, e.g.
+//! charging the same invoice.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map};
+use time::OffsetDateTime;
+
+use atlas_db::{affected, datetime, QueryExecutor};
+use atlas_kernel::{id, AppContext, Clock, InitCtx};
+
+/// Persistence for lock leases
+///
+/// Locks are shared between processes through the `lock` table when the application
+/// has a `QueryExecutor`, or through its own `Arc<dyn LockStore>` resource. Both
+/// methods must be atomic, e.g. a conditional `UPSERT ... WHERE` in SurrealDB.
+#[async_trait]
+pub trait LockStore: Send + Sync {
+    /// Lease `name` to `holder` until `until` if it is free, its lease lapsed at `now`,
+    /// or `holder` already has it, returning whether `holder` has it now
+    async fn acquire(
+        &self,
+        name: &str,
+        holder: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<bool>;
+
+    /// Free `name` if `holder` has it, returning whether it did
+    async fn release(&self, name: &str, holder: &str) -> anyhow::Result<bool>;
+
+    /// Whether other processes see the same leases
+    fn shared(&self) -> bool {
+        true
+    }
+}
+
+/// Process-local lock store
+#[derive(Debug, Default)]
+pub struct MemoryLockStore {
+    /// Holder and lease end by lock name
+    leases: Mutex<HashMap<String, (String, OffsetDateTime)>>,
+}
+
+impl MemoryLockStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn leases(&self) -> std::sync::MutexGuard<'_, HashMap<String, (String, OffsetDateTime)>> {
+        self.leases
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl LockStore for MemoryLockStore {
+    async fn acquire(
+        &self,
+        name: &str,
+        holder: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<bool> {
+        let mut leases = self.leases();
+        let free = leases
+            .get(name)
+            .is_none_or(|(current, expires)| current == holder || *expires <= now);
+        if free {
+            leases.insert(name.to_string(), (holder.to_string(), until));
+        }
+        Ok(free)
+    }
+
+    async fn release(&self, name: &str, holder: &str) -> anyhow::Result<bool> {
+        let mut leases = self.leases();
+        let held = leases
+            .get(name)
+            .is_some_and(|(current, _)| current == holder);
+        if held {
+            leases.remove(name);
+        }
+        Ok(held)
+    }
+
+    fn shared(&self) -> bool {
+        false
+    }
+}
+
+/// Leases in the `lock` table defined by the queue module's migrations, one record per
+/// lock name
+pub struct SurrealLockStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealLockStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl LockStore for SurrealLockStore {
+    async fn acquire(
+        &self,
+        name: &str,
+        holder: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("name".to_string(), json!(name));
+        vars.insert("holder".to_string(), json!(holder));
+        vars.insert("now".to_string(), json!(datetime(now)));
+        vars.insert("until".to_string(), json!(datetime(until)));
+        // A missing record has no holder, so the condition also creates free locks
+        let results = self
+            .db
+            .query_with(
+                "UPSERT type::thing('lock', $name) \
+                 SET name = $name, holder = $holder, until = <datetime> $until \
+                 WHERE holder = NONE OR holder = $holder OR until <= <datetime> $now \
+                 RETURN VALUE holder;",
+                &vars,
+            )
+            .await
+            .with_context(|| format!("failed to acquire lock '{}'", name))?;
+        Ok(affected(results.first()))
+    }
+
+    async fn release(&self, name: &str, holder: &str) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("name".to_string(), json!(name));
+        vars.insert("holder".to_string(), json!(holder));
+        let results = self
+            .db
+            .query_with(
+                "DELETE type::thing('lock', $name) WHERE holder = $holder RETURN BEFORE;",
+                &vars,
+            )
+            .await
+            .with_context(|| format!("failed to release lock '{}'", name))?;
+        Ok(affected(results.first()))
+    }
+}
+
+/// Takes named locks in a `LockStore`
+pub struct Locks {
+    store: Arc<dyn LockStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Locks {
+    pub fn new(store: Arc<dyn LockStore>, clock: Arc<dyn Clock>) -> Self {
+        Self { store, clock }
+    }
+
+    pub fn store(&self) -> &Arc<dyn LockStore> {
+        &self.store
+    }
+
+    /// Take `name` for `ttl` under a new holder id, or `None` if someone else has it
+    pub async fn acquire(&self, name: &str, ttl: Duration) -> anyhow::Result<Option<Lock>> {
+        self.acquire_as(name, &id::ulid().to_string().to_lowercase(), ttl)
+            .await
+    }
+
+    /// Take `name` for `ttl` as `holder`; a holder that already has it extends its lease
+    pub async fn acquire_as(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<Option<Lock>> {
+        let now: OffsetDateTime = self.clock.now().into();
+        if !self.store.acquire(name, holder, now, now + ttl).await? {
+            return Ok(None);
+        }
+        Ok(Some(Lock {
+            name: name.to_string(),
+            holder: holder.to_string(),
+            ttl,
+            store: self.store.clone(),
+            clock: self.clock.clone(),
+        }))
+    }
+
+    /// Run `work` holding `name`, renewing the lease until it finishes
+    ///
+    /// Returns `None` without running `work` when the lock is held elsewhere.
+    pub async fn run_exclusive<T>(
+        &self,
+        name: &str,
+        ttl: Duration,
+        work: impl Future<Output = T>,
+    ) -> anyhow::Result<Option<T>> {
+        let Some(lock) = self.acquire(name, ttl).await? else {
+            return Ok(None);
+        };
+        tokio::pin!(work);
+        let output = loop {
+            tokio::select! {
+                output = &mut work => break output,
+                _ = tokio::time::sleep(ttl / 3) => match lock.renew().await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!(lock = %lock.name, "lost the lock while running"),
+                    Err(error) => tracing::warn!(lock = %lock.name, error = format!("{:#}", error), "failed to renew the lock"),
+                },
+            }
+        };
+        if let Err(error) = lock.release().await {
+            tracing::warn!(error = format!("{:#}", error), "failed to release the lock");
+        }
+        Ok(Some(output))
+    }
+}
+
+/// A lock taken through `Locks`
+///
+/// Dropping it without `release` leaves the lock taken until its lease lapses.
+pub struct Lock {
+    name: String,
+    holder: String,
+    ttl: Duration,
+    store: Arc<dyn LockStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Lock {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// Extend the lease by its ttl from now, returning `false` if the lock was lost
+    pub async fn renew(&self) -> anyhow::Result<bool> {
+        let now: OffsetDateTime = self.clock.now().into();
+        self.store
+            .acquire(&self.name, &self.holder, now, now + self.ttl)
+            .await
+    }
+
+    /// Free the lock, returning `false` if it had already been lost
+    pub async fn release(self) -> anyhow::Result<bool> {
+        self.store.release(&self.name, &self.holder).await
+    }
+}
+
+/// Access to the `Locks` published by the queue module
+pub trait LocksExt {
+    fn locks(&self) -> anyhow::Result<Arc<Locks>>;
+}
+
+impl LocksExt for InitCtx {
+    fn locks(&self) -> anyhow::Result<Arc<Locks>> {
+        self.resources.require::<Locks>()
+    }
+}
+
+impl LocksExt for AppContext {
+    fn locks(&self) -> anyhow::Result<Arc<Locks>> {
+        self.require::<Locks>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_db::testing::RecordingExecutor;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    fn locks() -> (Locks, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        (
+            Locks::new(Arc::new(MemoryLockStore::new()), clock.clone()),
+            clock,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lock_excludes_others_until_released_or_lapsed() {
+        let (locks, clock) = locks();
+        let ttl = Duration::from_secs(30);
+
+        let lock = locks.acquire("invoice:42", ttl).await.unwrap().unwrap();
+        assert!(locks.acquire("invoice:42", ttl).await.unwrap().is_none());
+        assert!(locks.acquire("invoice:43", ttl).await.unwrap().is_some());
+        assert!(lock.release().await.unwrap());
+
+        let lock = locks.acquire("invoice:42", ttl).await.unwrap().unwrap();
+        clock.advance(ttl);
+        let other = locks.acquire("invoice:42", ttl).await.unwrap().unwrap();
+        assert!(!lock.renew().await.unwrap());
+        assert!(!lock.release().await.unwrap());
+        assert!(other.renew().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_exclusive_skips_work_while_held() {
+        let (locks, _) = locks();
+        let ttl = Duration::from_secs(30);
+
+        let held = locks.acquire("reindex", ttl).await.unwrap().unwrap();
+        let skipped = locks.run_exclusive("reindex", ttl, async { 1 }).await;
+        assert_eq!(skipped.unwrap(), None);
+
+        held.release().await.unwrap();
+        let ran = locks.run_exclusive("reindex", ttl, async { 2 }).await;
+        assert_eq!(ran.unwrap(), Some(2));
+        assert!(locks.acquire("reindex", ttl).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_reports_whether_the_lease_was_taken() {
+        let now = OffsetDateTime::from(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let until = now + Duration::from_secs(30);
+        let taken = RecordingExecutor::answering(vec![Ok(vec![json!(["w1"])])]);
+        let held = SurrealLockStore::new(RecordingExecutor::new());
+
+        assert!(SurrealLockStore::new(taken.clone())
+            .acquire("reindex", "w1", now, until)
+            .await
+            .unwrap());
+        assert!(!held.acquire("reindex", "w2", now, until).await.unwrap());
+        assert!(!held.release("reindex", "w2").await.unwrap());
+        assert!(held.shared());
+        assert!(!MemoryLockStore::new().shared());
+        let (_, vars) = taken.queries()[0].clone();
+        assert_eq!(vars["until"], "2023-11-14T22:13:50Z");
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::json;
+
+use atlas_kernel::{settings::Environment, InitCtx, Module, ModuleHealth, ModuleJobs};
+
+use crate::leader::LeaderElection;
+use crate::lock::{Locks, MemoryLockStore};
+use crate::Scheduler;
+
+/// Core module running every module's scheduled jobs
+///
+/// Jobs are collected when core modules start, once every module is initialized. The
+/// `Scheduler` is published as a resource, and `health()` reports each job's counters.
+/// Does nothing when `jobs.scheduler_enabled` is off.
+///
+/// With `jobs.leader_election` on, replicas campaign for the `scheduler` lock published
+/// by the queue module and only the leader runs jobs; when it goes away another replica
+/// takes over within `jobs.leader_lease_secs`. Outside the local environment it refuses
+/// to start when the lease is only visible to this process.
+#[derive(Default)]
+pub struct SchedulerModule {
+    scheduler: OnceLock<Arc<Scheduler>>,
+}
+
+#[async_trait]
+impl Module for SchedulerModule {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Cron-scheduled jobs declared by modules")
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.jobs;
+        if !settings.scheduler_enabled {
+            tracing::debug!("job scheduler disabled");
+            return Ok(());
+        }
+        let jobs = ctx
+            .resources
+            .get::<ModuleJobs>()
+            .map(|jobs| jobs.jobs.clone())
+            .unwrap_or_default();
+        let mut scheduler = Scheduler::new(jobs, &settings.disabled, ctx.clock.clone())
+            .context("failed to schedule module jobs")?;
+        if settings.leader_election {
+            let locks = ctx.resources.get::<Locks>().unwrap_or_else(|| {
+                Arc::new(Locks::new(
+                    Arc::new(MemoryLockStore::new()),
+                    ctx.clock.clone(),
+                ))
+            });
+            // Every replica would win a lease only it can see
+            if !locks.store().shared() && ctx.settings.environment != Environment::Local {
+                anyhow::bail!(
+                    "jobs.leader_election needs a lock store shared between replicas; \
+                     configure `[database]` or register an `Arc<dyn LockStore>`"
+                );
+            }
+            let leader = Arc::new(LeaderElection::new(
+                locks,
+                "scheduler",
+                Duration::from_secs(settings.leader_lease_secs),
+            ));
+            leader.campaign().await;
+            leader.spawn(ctx);
+            scheduler = scheduler.with_leader(leader);
+        }
+        let scheduler = Arc::new(scheduler);
+        tracing::info!(
+            jobs = scheduler.jobs().len(),
+            leader = scheduler.is_leader(),
+            "job scheduler started"
+        );
+        scheduler.spawn(ctx);
+        ctx.resources.insert_arc(scheduler.clone());
+        self.scheduler
+            .set(scheduler)
+            .map_err(|_| anyhow::anyhow!("scheduler module started twice"))?;
+        Ok(())
+    }
+
+    async fn health(&self) -> ModuleHealth {
+        match self.scheduler.get() {
+            Some(scheduler) => ModuleHealth::healthy().with_details(json!({
+                "leader": scheduler.is_leader(),
+                "jobs": scheduler.jobs(),
+            })),
+            None => ModuleHealth::healthy(),
+        }
+    }
+}
+
+/// Create a new instance of the scheduler module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(SchedulerModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::{settings::Settings, Job, ModuleRegistry};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct Ticking {
+        runs: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Module for Ticking {
+        fn name(&self) -> &'static str {
+            "ticking"
+        }
+
+        fn jobs(&self) -> Vec<atlas_kernel::Job> {
+            let runs = self.runs.clone();
+            vec![Job::new("tick", "* * * * * *", move || {
+                let runs = runs.clone();
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_declared_jobs_run_on_schedule() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let module = Arc::new(SchedulerModule::default());
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(module.clone()).unwrap();
+        registry
+            .register_custom(Arc::new(Ticking { runs: runs.clone() }))
+            .unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        tokio::time::timeout(std::time::Duration::from_secs(3), async {
+            while runs.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let health = module.health().await;
+        let details = health.details.unwrap();
+        assert_eq!(details["jobs"][0]["name"], "tick");
+        assert_eq!(details["leader"], true);
+
+        registry.stop_core_modules().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disabled_scheduler_publishes_nothing() {
+        let mut settings = Settings::default();
+        settings.jobs.scheduler_enabled = false;
+        let registry = ModuleRegistry::new();
+        let ctx = registry.init_ctx(Arc::new(settings));
+
+        SchedulerModule::default().start(&ctx).await.unwrap();
+
+        assert!(!registry.resources().contains::<Scheduler>());
+    }
+
+    #[tokio::test]
+    async fn test_leader_election_needs_shared_locks_outside_local() {
+        let registry = ModuleRegistry::new();
+        let ctx = registry.init_ctx(Arc::new(Settings {
+            environment: Environment::Production,
+            ..Settings::default()
+        }));
+
+        let error = SchedulerModule::default().start(&ctx).await.unwrap_err();
+
+        assert!(error.to_string().contains("jobs.leader_election"));
+        assert!(!registry.resources().contains::<Scheduler>());
+    }
+}
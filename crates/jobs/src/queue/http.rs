@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+
+use atlas_http::{error::AppError, meta::authorize_admin};
+
+use super::{JobError, JobQuery, Queue, QueueStats, QueuedJob};
+
+/// Largest page served by the job listing
+pub(crate) const MAX_LIMIT: usize = 1000;
+
+#[derive(Clone)]
+struct AdminState {
+    queue: Arc<Queue>,
+    admin_token: Arc<str>,
+}
+
+impl From<JobError> for AppError {
+    fn from(error: JobError) -> Self {
+        match error {
+            JobError::NotFound(_) => AppError::not_found(error.to_string()),
+            JobError::State { state, .. } => {
+                AppError::conflict(vec![json!({ "state": state })], error.to_string())
+            }
+            JobError::Store(error) => error.into(),
+        }
+    }
+}
+
+async fn list_jobs(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<Vec<QueuedJob>>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    if query
+        .limit
+        .is_some_and(|limit| limit == 0 || limit > MAX_LIMIT)
+    {
+        return Err(AppError::validation(
+            vec![json!({ "field": "limit", "error": "out_of_range" })],
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    Ok(Json(state.queue.store().list(&query).await?))
+}
+
+async fn show_job(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<QueuedJob>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    match state.queue.store().get(&id).await? {
+        Some(job) => Ok(Json(job)),
+        None => Err(JobError::NotFound(id).into()),
+    }
+}
+
+async fn retry_job(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<QueuedJob>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.queue.retry(&id).await?))
+}
+
+async fn cancel_job(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<QueuedJob>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.queue.cancel(&id).await?))
+}
+
+async fn stats(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+) -> Result<Json<QueueStats>, AppError> {
+    authorize_admin(&headers, &state.admin_token)?;
+    Ok(Json(state.queue.stats().await?))
+}
+
+/// List, retry and cancel jobs and read queue depth; guarded by
+/// `Authorization: Bearer {admin_token}`
+pub(crate) fn admin_routes(queue: Arc<Queue>, admin_token: &str) -> Router {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{id}", get(show_job))
+        .route("/jobs/{id}/retry", post(retry_job))
+        .route("/jobs/{id}/cancel", post(cancel_job))
+        .route("/stats", get(stats))
+        .with_state(AdminState {
+            queue,
+            admin_token: Arc::from(admin_token),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::MemoryJobStore;
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::{clock::SystemClock, settings::QueueSettings};
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use serde_json::Value;
+
+    async fn admin(router: &Router, method: &str, uri: &str, token: &str) -> (StatusCode, Value) {
+        let mut request = request(method, uri, Value::Null);
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        send(router, request).await
+    }
+
+    #[tokio::test]
+    async fn test_admin_lists_cancels_and_retries_jobs() {
+        let queue = Arc::new(Queue::new(
+            Arc::new(MemoryJobStore::new()),
+            Arc::new(SystemClock),
+            QueueSettings::default(),
+        ));
+        let job = queue.enqueue("emails.send", json!({})).await.unwrap();
+        let router = admin_routes(queue, "secret");
+
+        let (status, _) = admin(&router, "GET", "/jobs", "wrong").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, body) = admin(&router, "GET", "/jobs?state=queued", "secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["id"], job.id.as_str());
+        let (status, _) = admin(&router, "GET", "/jobs?limit=0", "secret").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let cancel = format!("/jobs/{}/cancel", job.id);
+        let (status, body) = admin(&router, "POST", &cancel, "secret").await;
+        assert_eq!(
+            (status, &body["state"]),
+            (StatusCode::OK, &json!("cancelled"))
+        );
+        let (status, _) = admin(&router, "POST", &cancel, "secret").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let retry = format!("/jobs/{}/retry", job.id);
+        let (status, body) = admin(&router, "POST", &retry, "secret").await;
+        assert_eq!((status, &body["state"]), (StatusCode::OK, &json!("queued")));
+        let (status, _) = admin(&router, "GET", "/jobs/job_missing", "secret").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = admin(&router, "GET", "/stats", "secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["queued"], 1);
+    }
+}
@@ -0,0 +1,733 @@
+//! Durable background job queue
+//!
+//! Handlers and modules enqueue jobs by kind with a JSON payload; the queue module's
+//! workers run them with the handler a module declared through `Module::queue_handlers()`.
+//! A running job is leased to its worker, which renews the lease while the handler runs.
+//! When a worker stops without finishing, the lease lapses after
+//! `jobs.queue.visibility_timeout_secs` and another worker runs the job again, so every
+//! job runs at least once. Failed runs are retried with backoff up to the job's
+//! `max_attempts`, after which the job stays `failed`.
+//!
+//! A job can be held back until a later time with `enqueue_at`/`enqueue_in`, e.g. a
+//! reminder before a trial expires. Delayed jobs are stored like any other; idle workers
+//! sleep until the earliest one is due rather than keeping a timer per job.
+//!
+//! Enqueuing with an idempotency key stores the job only once per tenant and kind; later
+//! enqueues under the same key return the stored job, whatever state it is in. Work that
+//! must not overlap across jobs can take a [`Locks`](crate::lock::Locks) lock.
+//!
+//! Operators list jobs, retry failed or cancelled ones and cancel queued ones through
+//! `/api/queue/jobs` (with `admin.token` set) or `atlas jobs`, and watch the backlog with
+//! `Queue::stats`.
+
+mod http;
+mod module;
+mod store;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::{Notify, Semaphore};
+use tracing::Instrument;
+
+use atlas_kernel::{id, settings::QueueSettings, tenant, AppContext, Clock, InitCtx, QueueHandler};
+
+pub use module::{create_module, QueueModule};
+pub use store::{
+    JobQuery, JobState, JobStore, MemoryJobStore, QueueStats, QueuedJob, SurrealJobStore,
+};
+
+/// Why an operator action on a job was refused
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    #[error("job '{0}' not found")]
+    NotFound(String),
+    /// The job is not in a state the action applies to
+    #[error("job '{id}' is {state}")]
+    State { id: String, state: JobState },
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Options for a single enqueued job
+#[derive(Debug, Clone, Default)]
+pub struct EnqueueOptions {
+    /// Runs including the first one; defaults to `jobs.queue.max_attempts`
+    pub max_attempts: Option<u32>,
+    /// Earliest time the job may run; defaults to now
+    pub run_at: Option<OffsetDateTime>,
+    /// Enqueue the job only if none of its kind was enqueued for the tenant under this key
+    pub idempotency_key: Option<String>,
+}
+
+/// Counters of finished runs since the process started
+#[derive(Debug, Default)]
+pub struct QueueMetrics {
+    succeeded: AtomicU64,
+    retried: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Point-in-time copy of `QueueMetrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueueMetricsSnapshot {
+    pub succeeded: u64,
+    /// Failed runs rescheduled for another attempt
+    pub retried: u64,
+    /// Jobs that failed their last attempt
+    pub failed: u64,
+}
+
+impl QueueMetrics {
+    pub fn snapshot(&self) -> QueueMetricsSnapshot {
+        QueueMetricsSnapshot {
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Enqueues jobs and runs them with the declared queue handlers
+pub struct Queue {
+    store: Arc<dyn JobStore>,
+    clock: Arc<dyn Clock>,
+    settings: QueueSettings,
+    /// Handlers by kind, with the module that declared them; set when core modules start
+    handlers: OnceLock<HashMap<&'static str, (&'static str, QueueHandler)>>,
+    wakeup: Notify,
+    worker: String,
+    metrics: QueueMetrics,
+}
+
+impl Queue {
+    pub fn new(store: Arc<dyn JobStore>, clock: Arc<dyn Clock>, settings: QueueSettings) -> Self {
+        Self {
+            store,
+            clock,
+            settings,
+            handlers: OnceLock::new(),
+            wakeup: Notify::new(),
+            worker: id::ulid().to_string().to_lowercase(),
+            metrics: QueueMetrics::default(),
+        }
+    }
+
+    pub fn store(&self) -> &Arc<dyn JobStore> {
+        &self.store
+    }
+
+    pub fn metrics(&self) -> &QueueMetrics {
+        &self.metrics
+    }
+
+    /// Id this process's workers lease jobs under
+    pub fn worker(&self) -> &str {
+        &self.worker
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        self.clock.now().into()
+    }
+
+    fn lease(&self) -> Duration {
+        Duration::from_secs(self.settings.visibility_timeout_secs)
+    }
+
+    /// Register the declared handlers; a kind declared twice is an error
+    pub(crate) fn set_handlers(
+        &self,
+        declared: Vec<(&'static str, QueueHandler)>,
+    ) -> anyhow::Result<()> {
+        let mut handlers = HashMap::new();
+        for (module, handler) in declared {
+            if let Some((other, _)) = handlers.insert(handler.kind, (module, handler.clone())) {
+                bail!(
+                    "job kind '{}' is handled by both '{}' and '{}'",
+                    handler.kind,
+                    other,
+                    module
+                );
+            }
+        }
+        self.handlers
+            .set(handlers)
+            .map_err(|_| anyhow!("queue handlers were already registered"))
+    }
+
+    /// Enqueue a job of `kind` for the current tenant
+    pub async fn enqueue(&self, kind: &str, payload: impl Serialize) -> anyhow::Result<QueuedJob> {
+        self.enqueue_with(kind, payload, EnqueueOptions::default())
+            .await
+    }
+
+    /// Enqueue a job of `kind` for the current tenant that runs no earlier than `at`
+    pub async fn enqueue_at(
+        &self,
+        kind: &str,
+        payload: impl Serialize,
+        at: OffsetDateTime,
+    ) -> anyhow::Result<QueuedJob> {
+        let options = EnqueueOptions {
+            run_at: Some(at),
+            ..EnqueueOptions::default()
+        };
+        self.enqueue_with(kind, payload, options).await
+    }
+
+    /// Enqueue a job of `kind` for the current tenant that runs once `delay` has passed
+    pub async fn enqueue_in(
+        &self,
+        kind: &str,
+        payload: impl Serialize,
+        delay: Duration,
+    ) -> anyhow::Result<QueuedJob> {
+        self.enqueue_at(kind, payload, self.now() + delay).await
+    }
+
+    /// Enqueue a job of `kind` with per-job options
+    ///
+    /// Once handlers are registered, a kind no module handles is refused. With an
+    /// idempotency key that was used before, the job enqueued then is returned instead.
+    pub async fn enqueue_with(
+        &self,
+        kind: &str,
+        payload: impl Serialize,
+        options: EnqueueOptions,
+    ) -> anyhow::Result<QueuedJob> {
+        if let Some(handlers) = self.handlers.get() {
+            if !handlers.contains_key(kind) {
+                bail!("no module handles jobs of kind '{}'", kind);
+            }
+        }
+        let payload = serde_json::to_value(payload)
+            .with_context(|| format!("failed to serialize the payload of job '{}'", kind))?;
+        let now = self.now();
+        let job = QueuedJob {
+            id: id::prefixed_with("job", id::ulid_at(now.into()))?,
+            kind: kind.to_string(),
+            payload,
+            tenant: tenant::current(),
+            state: JobState::Queued,
+            attempts: 0,
+            max_attempts: options
+                .max_attempts
+                .unwrap_or(self.settings.max_attempts)
+                .max(1),
+            run_at: options.run_at.unwrap_or(now),
+            locked_by: None,
+            locked_until: None,
+            last_error: None,
+            idempotency_key: options.idempotency_key,
+            created_at: now,
+            updated_at: now,
+        };
+        let stored = self.store.insert(job.clone()).await?;
+        if stored.id != job.id {
+            tracing::debug!(job_id = %stored.id, kind, "job already enqueued under its idempotency key");
+            return Ok(stored);
+        }
+        tracing::debug!(job_id = %job.id, kind, run_at = %job.run_at, "job enqueued");
+        self.wakeup.notify_one();
+        Ok(job)
+    }
+
+    /// Queue depth and the age of the oldest due job
+    pub async fn stats(&self) -> anyhow::Result<QueueStats> {
+        self.store.stats(self.now()).await
+    }
+
+    /// Queue a failed or cancelled job to run now with a fresh set of attempts
+    pub async fn retry(&self, id: &str) -> Result<QueuedJob, JobError> {
+        let job = self
+            .transition(id, &[JobState::Failed, JobState::Cancelled], |job, now| {
+                job.state = JobState::Queued;
+                job.attempts = 0;
+                job.run_at = now;
+            })
+            .await?;
+        tracing::info!(job_id = %job.id, kind = %job.kind, "job retried");
+        self.wakeup.notify_one();
+        Ok(job)
+    }
+
+    /// Keep a queued job from running; a running job cannot be cancelled
+    pub async fn cancel(&self, id: &str) -> Result<QueuedJob, JobError> {
+        let job = self
+            .transition(id, &[JobState::Queued], |job, _| {
+                job.state = JobState::Cancelled;
+            })
+            .await?;
+        tracing::info!(job_id = %job.id, kind = %job.kind, "job cancelled");
+        Ok(job)
+    }
+
+    /// Cancel a running job whose handler is stuck
+    ///
+    /// The job gives up its lease, so its worker can neither renew it nor store the
+    /// outcome; the handler itself is not interrupted.
+    pub async fn cancel_running(&self, id: &str) -> Result<QueuedJob, JobError> {
+        let job = self
+            .transition(id, &[JobState::Running], |job, _| {
+                job.state = JobState::Cancelled;
+                job.locked_by = None;
+                job.locked_until = None;
+            })
+            .await?;
+        tracing::warn!(job_id = %job.id, kind = %job.kind, "running job cancelled");
+        Ok(job)
+    }
+
+    /// Apply `change` to the job if it is in one of the `from` states
+    async fn transition(
+        &self,
+        id: &str,
+        from: &[JobState],
+        change: impl FnOnce(&mut QueuedJob, OffsetDateTime),
+    ) -> Result<QueuedJob, JobError> {
+        let Some(mut job) = self.store.get(id).await? else {
+            return Err(JobError::NotFound(id.to_string()));
+        };
+        let state = job.state;
+        if !from.contains(&state) {
+            return Err(JobError::State { id: job.id, state });
+        }
+        let now = self.now();
+        change(&mut job, now);
+        job.updated_at = now;
+        if !self.store.update(job.clone(), state).await? {
+            // Claimed or changed by someone else meanwhile
+            let state = match self.store.get(id).await? {
+                Some(current) => current.state,
+                None => return Err(JobError::NotFound(id.to_string())),
+            };
+            return Err(JobError::State {
+                id: id.to_string(),
+                state,
+            });
+        }
+        Ok(job)
+    }
+
+    /// Claim one due job and run it to completion, returning whether there was one
+    pub async fn run_next(&self) -> anyhow::Result<bool> {
+        match self.claim().await? {
+            Some(job) => {
+                self.execute(job).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn claim(&self) -> anyhow::Result<Option<QueuedJob>> {
+        let now = self.now();
+        self.store
+            .claim(&self.worker, now, now + self.lease())
+            .await
+    }
+
+    /// Run the handler of a claimed job, renewing its lease, and store the outcome
+    async fn execute(&self, mut job: QueuedJob) -> anyhow::Result<()> {
+        let span = tracing::info_span!("queued_job", job_id = %job.id, kind = %job.kind, attempt = job.attempts);
+        let result = self.run_handler(&job).instrument(span.clone()).await;
+
+        let now = self.now();
+        job.locked_by = None;
+        job.locked_until = None;
+        job.updated_at = now;
+        span.in_scope(|| match result {
+            Ok(()) => {
+                job.state = JobState::Succeeded;
+                job.last_error = None;
+                self.metrics.succeeded.fetch_add(1, Ordering::Relaxed);
+                tracing::info!("job succeeded");
+            }
+            Err(error) => {
+                let message = format!("{:#}", error);
+                job.last_error = Some(message.clone());
+                if job.attempts < job.max_attempts {
+                    let backoff = self.settings.policy(job.max_attempts).backoff(job.attempts);
+                    job.state = JobState::Queued;
+                    job.run_at = now + backoff;
+                    self.metrics.retried.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(error = %message, retry_in_ms = backoff.as_millis() as u64, "job failed; retrying");
+                } else {
+                    job.state = JobState::Failed;
+                    self.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    tracing::error!(error = %message, "job failed on its last attempt");
+                }
+            }
+        });
+        if !self.store.finish(&self.worker, job).await? {
+            span.in_scope(|| {
+                tracing::warn!(
+                    "job was cancelled, or its lease lapsed and another worker runs it again, before the run finished"
+                )
+            });
+        }
+        Ok(())
+    }
+
+    async fn run_handler(&self, job: &QueuedJob) -> anyhow::Result<()> {
+        let Some((_, handler)) = self
+            .handlers
+            .get()
+            .and_then(|handlers| handlers.get(job.kind.as_str()))
+        else {
+            bail!("no module handles jobs of kind '{}'", job.kind);
+        };
+        let run = handler.handle(job.payload.clone());
+        let run = async move {
+            match job.tenant.clone() {
+                Some(tenant) => tenant::scope(tenant, run).await,
+                None => run.await,
+            }
+        };
+        tokio::pin!(run);
+
+        let renew_every = self.lease() / 3;
+        loop {
+            tokio::select! {
+                result = &mut run => return result,
+                _ = self.clock.sleep(renew_every) => {
+                    let until = self.now() + self.lease();
+                    match self.store.renew(&job.id, &self.worker, until).await {
+                        Ok(true) => {}
+                        Ok(false) => tracing::warn!("lost the job lease while running"),
+                        Err(error) => tracing::warn!(error = format!("{:#}", error), "failed to renew the job lease"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// How long idle workers wait before claiming again: until the next job is due, and
+    /// at most `poll` so jobs enqueued by other processes are picked up
+    async fn idle_wait(&self, poll: Duration) -> Duration {
+        match self.store.next_run_at().await {
+            Ok(Some(at)) => Duration::try_from(at - self.now())
+                .unwrap_or_default()
+                .min(poll),
+            Ok(None) => poll,
+            Err(error) => {
+                tracing::warn!(
+                    error = format!("{:#}", error),
+                    "failed to read the next due job"
+                );
+                poll
+            }
+        }
+    }
+
+    /// Spawn the task that claims jobs and runs up to `jobs.queue.concurrency` at a time
+    ///
+    /// Idle workers wake up when a job is enqueued in this process or the next delayed
+    /// job is due, and at least every `jobs.queue.poll_interval_ms` for jobs enqueued
+    /// elsewhere.
+    pub(crate) fn spawn_workers(self: &Arc<Self>, ctx: &InitCtx) {
+        let queue = self.clone();
+        let runs = ctx.clone();
+        let poll = Duration::from_millis(self.settings.poll_interval_ms);
+        let permits = Arc::new(Semaphore::new(self.settings.concurrency));
+        ctx.spawn("workers", async move {
+            loop {
+                let permit = permits.clone().acquire_owned().await?;
+                match queue.claim().await {
+                    Ok(Some(job)) => {
+                        let queue = queue.clone();
+                        runs.spawn(format!("job {}", job.id), async move {
+                            let _permit = permit;
+                            queue.execute(job).await
+                        });
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        tracing::warn!(error = format!("{:#}", error), "failed to claim a job");
+                        drop(permit);
+                        queue.clock.sleep(poll).await;
+                        continue;
+                    }
+                }
+                drop(permit);
+                let wait = queue.idle_wait(poll).await;
+                tokio::select! {
+                    _ = queue.wakeup.notified() => {}
+                    _ = queue.clock.sleep(wait) => {}
+                }
+            }
+        });
+    }
+}
+
+/// Access to the `Queue` published by the queue module
+pub trait JobsExt {
+    fn queue(&self) -> anyhow::Result<Arc<Queue>>;
+}
+
+impl JobsExt for InitCtx {
+    fn queue(&self) -> anyhow::Result<Arc<Queue>> {
+        self.resources.require::<Queue>()
+    }
+}
+
+impl JobsExt for AppContext {
+    fn queue(&self) -> anyhow::Result<Arc<Queue>> {
+        self.require::<Queue>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    fn queue() -> (Arc<Queue>, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(store::tests::NOW.into()));
+        let queue = Arc::new(Queue::new(
+            Arc::new(MemoryJobStore::new()),
+            clock.clone(),
+            QueueSettings {
+                max_attempts: 2,
+                ..QueueSettings::default()
+            },
+        ));
+        (queue, clock)
+    }
+
+    /// Payloads a handler saw, with the tenant it ran for
+    type Seen = Arc<Mutex<Vec<(Value, Option<String>)>>>;
+
+    fn recording(seen: Seen) -> QueueHandler {
+        QueueHandler::new("emails.send", move |payload| {
+            let seen = seen.clone();
+            async move {
+                let tenant = tenant::current().map(|tenant| tenant.as_str().to_string());
+                seen.lock().unwrap().push((payload, tenant));
+                Ok(())
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_enqueued_job_runs_in_its_tenant() {
+        let (queue, _) = queue();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        queue
+            .set_handlers(vec![("emails", recording(seen.clone()))])
+            .unwrap();
+
+        let tenant = tenant::TenantId::new("acme").unwrap();
+        let job = tenant::scope(tenant, queue.enqueue("emails.send", json!({ "to": "ada" })))
+            .await
+            .unwrap();
+        assert!(queue.run_next().await.unwrap());
+        assert!(!queue.run_next().await.unwrap());
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(json!({ "to": "ada" }), Some("acme".to_string()))]
+        );
+        let stored = queue.store().get(&job.id).await.unwrap().unwrap();
+        assert_eq!(stored.state, JobState::Succeeded);
+        assert_eq!(queue.metrics().snapshot().succeeded, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_is_retried_after_backoff_then_fails() {
+        let (queue, clock) = queue();
+        queue
+            .set_handlers(vec![(
+                "exports",
+                QueueHandler::new("exports.build", |_| async { anyhow::bail!("disk full") }),
+            )])
+            .unwrap();
+        let job = queue.enqueue("exports.build", json!({})).await.unwrap();
+
+        assert!(queue.run_next().await.unwrap());
+        let stored = queue.store().get(&job.id).await.unwrap().unwrap();
+        assert_eq!(stored.state, JobState::Queued);
+        assert_eq!(stored.last_error.as_deref(), Some("disk full"));
+        assert!(!queue.run_next().await.unwrap());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(queue.run_next().await.unwrap());
+        let stored = queue.store().get(&job.id).await.unwrap().unwrap();
+        assert_eq!((stored.state, stored.attempts), (JobState::Failed, 2));
+        assert_eq!(
+            queue.metrics().snapshot(),
+            QueueMetricsSnapshot {
+                succeeded: 0,
+                retried: 1,
+                failed: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delayed_job_runs_once_due() {
+        let (queue, clock) = queue();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        queue
+            .set_handlers(vec![("emails", recording(seen.clone()))])
+            .unwrap();
+        let poll = Duration::from_secs(1);
+
+        let job = queue
+            .enqueue_in(
+                "emails.send",
+                json!({ "to": "ada" }),
+                Duration::from_secs(3 * 86_400),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            job.run_at,
+            store::tests::NOW + Duration::from_secs(3 * 86_400)
+        );
+        assert!(!queue.run_next().await.unwrap());
+        assert_eq!(queue.idle_wait(poll).await, poll);
+
+        clock.advance(Duration::from_secs(3 * 86_400 - 1) - Duration::from_millis(250));
+        assert_eq!(queue.idle_wait(poll).await, poll);
+        clock.advance(Duration::from_millis(1000));
+        assert_eq!(queue.idle_wait(poll).await, Duration::from_millis(250));
+        assert!(!queue.run_next().await.unwrap());
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(queue.idle_wait(poll).await, Duration::ZERO);
+        assert!(queue.run_next().await.unwrap());
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_enqueues_once() {
+        let (queue, _) = queue();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        queue
+            .set_handlers(vec![("emails", recording(seen.clone()))])
+            .unwrap();
+        let options = || EnqueueOptions {
+            idempotency_key: Some("welcome:ada".to_string()),
+            ..EnqueueOptions::default()
+        };
+
+        let first = queue
+            .enqueue_with("emails.send", json!({ "to": "ada" }), options())
+            .await
+            .unwrap();
+        assert!(queue.run_next().await.unwrap());
+        let again = queue
+            .enqueue_with("emails.send", json!({ "to": "ada" }), options())
+            .await
+            .unwrap();
+
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.state, JobState::Succeeded);
+        assert!(!queue.run_next().await.unwrap());
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_operators_retry_failed_and_cancel_queued_jobs() {
+        let (queue, clock) = queue();
+        queue
+            .set_handlers(vec![(
+                "exports",
+                QueueHandler::new("exports.build", |_| async { anyhow::bail!("disk full") }),
+            )])
+            .unwrap();
+        let job = queue.enqueue("exports.build", json!({})).await.unwrap();
+        assert!(matches!(
+            queue.retry(&job.id).await,
+            Err(JobError::State {
+                state: JobState::Queued,
+                ..
+            })
+        ));
+
+        queue.run_next().await.unwrap();
+        clock.advance(Duration::from_secs(1));
+        queue.run_next().await.unwrap();
+        assert_eq!(queue.stats().await.unwrap().failed, 1);
+
+        let retried = queue.retry(&job.id).await.unwrap();
+        assert_eq!((retried.state, retried.attempts), (JobState::Queued, 0));
+        let cancelled = queue.cancel(&job.id).await.unwrap();
+        assert_eq!(cancelled.state, JobState::Cancelled);
+        assert!(!queue.run_next().await.unwrap());
+        assert!(matches!(
+            queue.cancel("job_missing").await,
+            Err(JobError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stuck_running_jobs_can_be_cancelled() {
+        let (queue, _) = queue();
+        let release = Arc::new(tokio::sync::Notify::new());
+        queue
+            .set_handlers(vec![(
+                "exports",
+                QueueHandler::new("exports.build", {
+                    let release = release.clone();
+                    move |_| {
+                        let release = release.clone();
+                        async move {
+                            release.notified().await;
+                            Ok(())
+                        }
+                    }
+                }),
+            )])
+            .unwrap();
+        let job = queue.enqueue("exports.build", json!({})).await.unwrap();
+        assert!(queue.cancel_running(&job.id).await.is_err());
+
+        let worker = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.run_next().await }
+        });
+        while queue.store().get(&job.id).await.unwrap().unwrap().state != JobState::Running {
+            tokio::task::yield_now().await;
+        }
+        let cancelled = queue.cancel_running(&job.id).await.unwrap();
+        assert_eq!(cancelled.state, JobState::Cancelled);
+        assert_eq!(cancelled.locked_by, None);
+
+        release.notify_one();
+        assert!(worker.await.unwrap().unwrap());
+        let stored = queue.store().get(&job.id).await.unwrap().unwrap();
+        assert_eq!(stored.state, JobState::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kinds_and_duplicate_handlers_are_refused() {
+        let (queue, _) = queue();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        // Before handlers are known any kind is accepted
+        queue.enqueue("emails.send", json!({})).await.unwrap();
+
+        let error = queue
+            .set_handlers(vec![
+                ("emails", recording(seen.clone())),
+                ("billing", recording(seen.clone())),
+            ])
+            .unwrap_err();
+        assert!(error.to_string().contains("'emails' and 'billing'"));
+
+        let (queue, _) = self::queue();
+        queue
+            .set_handlers(vec![("emails", recording(seen))])
+            .unwrap();
+        assert!(queue.enqueue("emails.sned", json!({})).await.is_err());
+    }
+}
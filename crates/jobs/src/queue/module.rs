@@ -0,0 +1,399 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_kernel::{
+    settings::Environment, InitCtx, Migration, Module, ModuleHealth, ModuleQueueHandlers,
+    RouteSecurity, SecurityScheme,
+};
+
+use super::http::{admin_routes, MAX_LIMIT};
+use super::{JobQuery, JobStore, MemoryJobStore, Queue, SurrealJobStore};
+use crate::lock::{LockStore, Locks, MemoryLockStore, SurrealLockStore};
+
+/// Core module running queued jobs
+///
+/// Publishes the `Queue` and `Locks` at init so modules can enqueue and take locks from
+/// then on; outside the local environment it refuses to start with locks that only
+/// exclude within this process. When core modules start it registers every module's
+/// queue handlers and, unless `jobs.queue.enabled` is off, starts this process's workers. With `admin.token` set, operators manage jobs and
+/// read the queue depth under `/api/queue`.
+#[derive(Default)]
+pub struct QueueModule {
+    queue: OnceLock<Arc<Queue>>,
+    admin_token: OnceLock<Option<String>>,
+}
+
+#[async_trait]
+impl Module for QueueModule {
+    fn name(&self) -> &'static str {
+        "queue"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Durable background job queue")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn JobStore>(
+            &ctx.resources,
+            "job",
+            |db| Arc::new(SurrealJobStore::new(db)),
+            || Arc::new(MemoryJobStore::new()),
+        );
+        let queue = Arc::new(Queue::new(
+            store,
+            ctx.clock.clone(),
+            ctx.settings.jobs.queue.clone(),
+        ));
+        ctx.resources.insert_arc(queue.clone());
+
+        let locks = atlas_db::select_store::<dyn LockStore>(
+            &ctx.resources,
+            "lock",
+            |db| Arc::new(SurrealLockStore::new(db)),
+            || Arc::new(MemoryLockStore::new()),
+        );
+        if !locks.shared() && ctx.settings.environment != Environment::Local {
+            anyhow::bail!(
+                "locks would only exclude within this process in the '{}' environment; \
+                 configure `[database]` or register an `Arc<dyn LockStore>`",
+                ctx.settings.environment
+            );
+        }
+        ctx.resources
+            .insert_arc(Arc::new(Locks::new(locks, ctx.clock.clone())));
+        self.queue
+            .set(queue)
+            .map_err(|_| anyhow::anyhow!("queue module initialized twice"))?;
+        self.admin_token
+            .get_or_init(|| ctx.settings.admin.token.clone());
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let Some(queue) = self.queue.get() else {
+            return Ok(());
+        };
+        let handlers = ctx
+            .resources
+            .get::<ModuleQueueHandlers>()
+            .map(|handlers| handlers.handlers.clone())
+            .unwrap_or_default();
+        let kinds = handlers.len();
+        queue.set_handlers(handlers)?;
+
+        let settings = &ctx.settings.jobs.queue;
+        if !settings.enabled {
+            tracing::info!("queue workers disabled; jobs are only enqueued");
+            return Ok(());
+        }
+        tracing::info!(
+            kinds,
+            concurrency = settings.concurrency,
+            worker = queue.worker(),
+            "queue workers started"
+        );
+        queue.spawn_workers(ctx);
+        Ok(())
+    }
+
+    async fn health(&self) -> ModuleHealth {
+        let Some(queue) = self.queue.get() else {
+            return ModuleHealth::healthy();
+        };
+        let depth = match queue.stats().await {
+            Ok(stats) => json!(stats),
+            Err(error) => {
+                tracing::warn!(error = format!("{:#}", error), "failed to read queue stats");
+                serde_json::Value::Null
+            }
+        };
+        ModuleHealth::healthy().with_details(json!({
+            "jobs": queue.metrics().snapshot(),
+            "depth": depth,
+        }))
+    }
+
+    fn routes(&self) -> Router {
+        match (self.queue.get(), self.admin_token.get().cloned().flatten()) {
+            (Some(queue), Some(admin_token)) => admin_routes(queue.clone(), &admin_token),
+            _ => Router::new(),
+        }
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![RouteSecurity {
+            method: "*",
+            path: "*",
+            schemes: &[SecurityScheme::AdminToken],
+            permission: None,
+        }]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![
+            Migration {
+                id: "001_create_job",
+                up: "DEFINE TABLE job SCHEMAFULL;
+DEFINE FIELD kind ON job TYPE string;
+DEFINE FIELD payload ON job FLEXIBLE TYPE any;
+DEFINE FIELD tenant ON job TYPE option<string>;
+DEFINE FIELD state ON job TYPE string ASSERT $value IN ['queued', 'running', 'succeeded', 'failed'];
+DEFINE FIELD attempts ON job TYPE int;
+DEFINE FIELD max_attempts ON job TYPE int;
+DEFINE FIELD run_at ON job TYPE datetime;
+DEFINE FIELD locked_by ON job TYPE option<string>;
+DEFINE FIELD locked_until ON job TYPE option<datetime>;
+DEFINE FIELD last_error ON job TYPE option<string>;
+DEFINE FIELD created_at ON job TYPE datetime;
+DEFINE FIELD updated_at ON job TYPE datetime;
+DEFINE INDEX job_claim ON job FIELDS state, run_at;",
+                down: Some("REMOVE TABLE job;"),
+            },
+            Migration {
+                id: "002_add_job_idempotency_key",
+                up: "DEFINE FIELD idempotency_key ON job TYPE option<string>;
+DEFINE INDEX job_idempotency ON job FIELDS tenant, kind, idempotency_key UNIQUE;",
+                down: Some(
+                    "REMOVE INDEX job_idempotency ON job;
+REMOVE FIELD idempotency_key ON job;",
+                ),
+            },
+            Migration {
+                id: "003_create_lock",
+                up: "DEFINE TABLE lock SCHEMAFULL;
+DEFINE FIELD name ON lock TYPE string;
+DEFINE FIELD holder ON lock TYPE string;
+DEFINE FIELD until ON lock TYPE datetime;
+DEFINE INDEX lock_name ON lock FIELDS name UNIQUE;",
+                down: Some("REMOVE TABLE lock;"),
+            },
+            Migration {
+                id: "004_allow_cancelled_job",
+                up: "DEFINE FIELD OVERWRITE state ON job TYPE string ASSERT $value IN ['queued', 'running', 'succeeded', 'failed', 'cancelled'];",
+                down: Some("DEFINE FIELD OVERWRITE state ON job TYPE string ASSERT $value IN ['queued', 'running', 'succeeded', 'failed'];"),
+            },
+        ]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let query = |name: &str, schema: serde_json::Value, description: &str| json!({ "name": name, "in": "query", "required": false, "schema": schema, "description": description });
+        let id =
+            json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } });
+        let job = |description: &str| {
+            json!({
+                "description": description,
+                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/QueuedJob" } } }
+            })
+        };
+        Some(json!({
+            "tags": [
+                { "name": "Queue", "description": "Background jobs and queue depth. Requires `admin.token`." }
+            ],
+            "paths": {
+                "/jobs": {
+                    "get": {
+                        "summary": "List jobs",
+                        "description": "Jobs ordered by the time they run, earliest first.",
+                        "tags": ["Queue"],
+                        "parameters": [
+                            query("state", json!({ "type": "string", "enum": ["queued", "running", "succeeded", "failed", "cancelled"] }), "Job state"),
+                            query("kind", json!({ "type": "string" }), "Job kind"),
+                            query("limit", json!({ "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": JobQuery::DEFAULT_LIMIT }), "Jobs to return")
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Jobs",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": { "$ref": "#/components/schemas/QueuedJob" }
+                                        }
+                                    }
+                                }
+                            },
+                            "422": { "description": "Invalid limit" }
+                        }
+                    }
+                },
+                "/jobs/{id}": {
+                    "get": {
+                        "summary": "Show a job",
+                        "tags": ["Queue"],
+                        "parameters": [id.clone()],
+                        "responses": {
+                            "200": job("The job"),
+                            "404": { "description": "No such job" }
+                        }
+                    }
+                },
+                "/jobs/{id}/retry": {
+                    "post": {
+                        "summary": "Retry a failed or cancelled job",
+                        "description": "Queue the job to run now with a fresh set of attempts.",
+                        "tags": ["Queue"],
+                        "parameters": [id.clone()],
+                        "responses": {
+                            "200": job("The queued job"),
+                            "404": { "description": "No such job" },
+                            "409": { "description": "The job is neither failed nor cancelled" }
+                        }
+                    }
+                },
+                "/jobs/{id}/cancel": {
+                    "post": {
+                        "summary": "Cancel a queued job",
+                        "tags": ["Queue"],
+                        "parameters": [id],
+                        "responses": {
+                            "200": job("The cancelled job"),
+                            "404": { "description": "No such job" },
+                            "409": { "description": "The job is not queued" }
+                        }
+                    }
+                },
+                "/stats": {
+                    "get": {
+                        "summary": "Queue depth",
+                        "description": "Jobs per state and how long the oldest due job has waited, for alerting on a backlog.",
+                        "tags": ["Queue"],
+                        "responses": {
+                            "200": {
+                                "description": "Queue depth",
+                                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/QueueStats" } } }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "QueuedJob": {
+                        "type": "object",
+                        "required": ["id", "kind", "payload", "state", "attempts", "max_attempts", "run_at", "created_at", "updated_at"],
+                        "properties": {
+                            "id": { "type": "string", "example": "job_01HV6Y5J8Q4ZK3X2T9R7M1N0PA" },
+                            "kind": { "type": "string", "example": "emails.send" },
+                            "payload": {},
+                            "tenant": { "type": "string", "nullable": true },
+                            "state": { "type": "string", "enum": ["queued", "running", "succeeded", "failed", "cancelled"] },
+                            "attempts": { "type": "integer" },
+                            "max_attempts": { "type": "integer" },
+                            "run_at": { "type": "string", "format": "date-time" },
+                            "locked_by": { "type": "string", "nullable": true },
+                            "locked_until": { "type": "string", "format": "date-time", "nullable": true },
+                            "last_error": { "type": "string", "nullable": true },
+                            "idempotency_key": { "type": "string" },
+                            "created_at": { "type": "string", "format": "date-time" },
+                            "updated_at": { "type": "string", "format": "date-time" }
+                        }
+                    },
+                    "QueueStats": {
+                        "type": "object",
+                        "required": ["queued", "due", "running", "failed"],
+                        "properties": {
+                            "queued": { "type": "integer" },
+                            "due": { "type": "integer", "description": "Queued jobs whose run time has passed" },
+                            "running": { "type": "integer" },
+                            "failed": { "type": "integer" },
+                            "oldest_due_secs": { "type": "integer", "nullable": true }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Create a new instance of the queue module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(QueueModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::LocksExt;
+    use crate::queue::{JobState, JobsExt};
+    use atlas_kernel::{settings::Settings, ModuleRegistry, QueueHandler};
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    struct Mailer {
+        sent: mpsc::UnboundedSender<Value>,
+    }
+
+    #[async_trait]
+    impl Module for Mailer {
+        fn name(&self) -> &'static str {
+            "mailer"
+        }
+
+        fn queue_handlers(&self) -> Vec<QueueHandler> {
+            let sent = self.sent.clone();
+            vec![QueueHandler::new("emails.send", move |payload| {
+                let sent = sent.clone();
+                async move {
+                    sent.send(payload)?;
+                    Ok(())
+                }
+            })]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workers_run_enqueued_jobs() {
+        let (sent, mut received) = mpsc::unbounded_channel();
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        registry.register_custom(Arc::new(Mailer { sent })).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let queue = ctx.queue().unwrap();
+        assert!(ctx.locks().is_ok());
+        let job = queue
+            .enqueue("emails.send", json!({ "to": "ada@example.com" }))
+            .await
+            .unwrap();
+
+        let payload = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload["to"], "ada@example.com");
+        let state = loop {
+            let stored = queue.store().get(&job.id).await.unwrap().unwrap();
+            if stored.state != JobState::Running {
+                break stored.state;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert_eq!(state, JobState::Succeeded);
+
+        registry.stop_core_modules().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_process_local_locks_are_refused_outside_local() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(create_module()).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings {
+            environment: Environment::Staging,
+            ..Settings::default()
+        }));
+
+        let error = registry.init_core_modules(&ctx).await.unwrap_err();
+
+        assert!(format!("{:#}", anyhow::Error::from(error)).contains("LockStore"));
+    }
+}
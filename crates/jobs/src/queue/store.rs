@@ -0,0 +1,895 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+
+use atlas_db::{affected, datetime, QueryExecutor};
+use atlas_kernel::tenant::TenantId;
+
+/// Where a queued job stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for `run_at`, or for a free worker
+    Queued,
+    /// Leased by a worker until `locked_until`
+    Running,
+    Succeeded,
+    /// Failed on its last attempt
+    Failed,
+    /// Cancelled by an operator before it ran
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the job will not run again unless an operator retries it
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A job in the queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    /// `job_`-prefixed ULID
+    pub id: String,
+    /// Kind naming the queue handler that runs the job
+    pub kind: String,
+    pub payload: Value,
+    /// Tenant the job was enqueued for; its handler runs in that tenant's scope
+    pub tenant: Option<TenantId>,
+    pub state: JobState,
+    /// Runs started so far
+    pub attempts: u32,
+    pub max_attempts: u32,
+    /// Earliest time the job may run; pushed back after a failed attempt
+    #[serde(with = "time::serde::rfc3339")]
+    pub run_at: OffsetDateTime,
+    /// Worker holding the lease of a running job
+    pub locked_by: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub locked_until: Option<OffsetDateTime>,
+    /// Error of the latest failed attempt
+    pub last_error: Option<String>,
+    /// Key under which the job is enqueued at most once per tenant and kind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl QueuedJob {
+    /// Whether a worker may claim the job at `now`: it is due, or its lease lapsed
+    pub fn claimable(&self, now: OffsetDateTime) -> bool {
+        match self.state {
+            JobState::Queued => self.run_at <= now,
+            JobState::Running => self.locked_until.is_none_or(|until| until <= now),
+            JobState::Succeeded | JobState::Failed | JobState::Cancelled => false,
+        }
+    }
+
+    /// Whether `other` was enqueued under the same idempotency key as this job
+    pub fn same_key(&self, other: &QueuedJob) -> bool {
+        self.idempotency_key.is_some()
+            && self.idempotency_key == other.idempotency_key
+            && self.kind == other.kind
+            && self.tenant == other.tenant
+    }
+}
+
+/// Filter for listing jobs; the earliest `run_at` comes first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobQuery {
+    pub state: Option<JobState>,
+    pub kind: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl JobQuery {
+    /// Jobs returned when no limit is given
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, job: &QueuedJob) -> bool {
+        self.state.is_none_or(|state| job.state == state)
+            && self.kind.as_ref().is_none_or(|kind| job.kind == *kind)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+/// Queue depth and age, for alerting on a backlog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueStats {
+    /// Queued jobs, due or not
+    pub queued: u64,
+    /// Queued jobs whose `run_at` has passed
+    pub due: u64,
+    pub running: u64,
+    /// Jobs that failed their last attempt and wait for an operator
+    pub failed: u64,
+    /// How long the longest-waiting due job has been due
+    pub oldest_due_secs: Option<u64>,
+}
+
+/// Persistence for queued jobs
+///
+/// The queue keeps jobs across restarts in the `job` table through the application's
+/// `QueryExecutor`; applications may publish their own `Arc<dyn JobStore>` resource
+/// instead. Without either the queue lives in memory. `claim`, `renew` and `finish` must
+/// be atomic, e.g. a conditional `UPDATE ... WHERE` in SurrealDB, so that two workers
+/// never hold the same lease, and `insert` must rely on the unique `job_idempotency`
+/// index so that a key is only ever stored once.
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    /// Store `job` and return it, or return the stored job enqueued under the same
+    /// idempotency key instead
+    async fn insert(&self, job: QueuedJob) -> anyhow::Result<QueuedJob>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<QueuedJob>>;
+
+    /// Lease the claimable job that has been due longest to `worker` until `until`,
+    /// counting an attempt
+    async fn claim(
+        &self,
+        worker: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<Option<QueuedJob>>;
+
+    /// Extend `worker`'s lease on a running job, returning `false` if it lost the lease
+    async fn renew(&self, id: &str, worker: &str, until: OffsetDateTime) -> anyhow::Result<bool>;
+
+    /// Store the outcome of a run if `worker` still holds the lease, returning whether it did
+    async fn finish(&self, worker: &str, job: QueuedJob) -> anyhow::Result<bool>;
+
+    async fn list(&self, query: &JobQuery) -> anyhow::Result<Vec<QueuedJob>>;
+
+    /// Replace the stored job with the same id if it is still in `state`, returning
+    /// whether it was
+    async fn update(&self, job: QueuedJob, state: JobState) -> anyhow::Result<bool>;
+
+    async fn stats(&self, now: OffsetDateTime) -> anyhow::Result<QueueStats>;
+
+    /// Earliest time a job becomes claimable: the `run_at` of a queued job or the
+    /// `locked_until` of a running one
+    ///
+    /// Idle workers sleep until then instead of polling for delayed jobs. Stores that
+    /// return `None` are only polled every `jobs.queue.poll_interval_ms`.
+    async fn next_run_at(&self) -> anyhow::Result<Option<OffsetDateTime>> {
+        Ok(None)
+    }
+}
+
+/// Process-local job store; finished jobs beyond its capacity are dropped, oldest first
+#[derive(Debug)]
+pub struct MemoryJobStore {
+    jobs: RwLock<Vec<QueuedJob>>,
+    capacity: usize,
+}
+
+impl MemoryJobStore {
+    /// Jobs kept before finished ones are dropped
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            jobs: RwLock::new(Vec::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn jobs(&self) -> std::sync::RwLockWriteGuard<'_, Vec<QueuedJob>> {
+        self.jobs
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn held<'a>(jobs: &'a mut [QueuedJob], id: &str, worker: &str) -> Option<&'a mut QueuedJob> {
+        jobs.iter_mut().find(|job| {
+            job.id == id
+                && job.state == JobState::Running
+                && job.locked_by.as_deref() == Some(worker)
+        })
+    }
+}
+
+impl Default for MemoryJobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl JobStore for MemoryJobStore {
+    async fn insert(&self, job: QueuedJob) -> anyhow::Result<QueuedJob> {
+        let mut jobs = self.jobs();
+        if let Some(stored) = jobs.iter().find(|stored| stored.same_key(&job)) {
+            return Ok(stored.clone());
+        }
+        jobs.push(job.clone());
+        let mut excess = jobs.len().saturating_sub(self.capacity);
+        jobs.retain(|job| {
+            let drop = excess > 0 && job.state.is_finished();
+            if drop {
+                excess -= 1;
+            }
+            !drop
+        });
+        Ok(job)
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<QueuedJob>> {
+        Ok(self.jobs().iter().find(|job| job.id == id).cloned())
+    }
+
+    async fn claim(
+        &self,
+        worker: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<Option<QueuedJob>> {
+        let mut jobs = self.jobs();
+        let Some(job) = jobs
+            .iter_mut()
+            .filter(|job| job.claimable(now))
+            .min_by(|a, b| a.run_at.cmp(&b.run_at).then(a.id.cmp(&b.id)))
+        else {
+            return Ok(None);
+        };
+        job.state = JobState::Running;
+        job.attempts += 1;
+        job.locked_by = Some(worker.to_string());
+        job.locked_until = Some(until);
+        job.updated_at = now;
+        Ok(Some(job.clone()))
+    }
+
+    async fn renew(&self, id: &str, worker: &str, until: OffsetDateTime) -> anyhow::Result<bool> {
+        let mut jobs = self.jobs();
+        Ok(match Self::held(&mut jobs, id, worker) {
+            Some(job) => {
+                job.locked_until = Some(until);
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn finish(&self, worker: &str, job: QueuedJob) -> anyhow::Result<bool> {
+        let mut jobs = self.jobs();
+        Ok(match Self::held(&mut jobs, &job.id, worker) {
+            Some(stored) => {
+                *stored = job;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn next_run_at(&self) -> anyhow::Result<Option<OffsetDateTime>> {
+        Ok(self
+            .jobs()
+            .iter()
+            .filter_map(|job| match job.state {
+                JobState::Queued => Some(job.run_at),
+                JobState::Running => job.locked_until,
+                JobState::Succeeded | JobState::Failed | JobState::Cancelled => None,
+            })
+            .min())
+    }
+
+    async fn list(&self, query: &JobQuery) -> anyhow::Result<Vec<QueuedJob>> {
+        let mut jobs: Vec<_> = self
+            .jobs()
+            .iter()
+            .filter(|job| query.matches(job))
+            .cloned()
+            .collect();
+        jobs.sort_by(|a, b| a.run_at.cmp(&b.run_at).then(a.id.cmp(&b.id)));
+        jobs.truncate(query.limit());
+        Ok(jobs)
+    }
+
+    async fn update(&self, job: QueuedJob, state: JobState) -> anyhow::Result<bool> {
+        let mut jobs = self.jobs();
+        Ok(
+            match jobs
+                .iter_mut()
+                .find(|stored| stored.id == job.id && stored.state == state)
+            {
+                Some(stored) => {
+                    *stored = job;
+                    true
+                }
+                None => false,
+            },
+        )
+    }
+
+    async fn stats(&self, now: OffsetDateTime) -> anyhow::Result<QueueStats> {
+        let mut stats = QueueStats::default();
+        let mut oldest_due: Option<OffsetDateTime> = None;
+        for job in self.jobs().iter() {
+            match job.state {
+                JobState::Queued => {
+                    stats.queued += 1;
+                    if job.run_at <= now {
+                        stats.due += 1;
+                        oldest_due = Some(oldest_due.map_or(job.run_at, |at| at.min(job.run_at)));
+                    }
+                }
+                JobState::Running => stats.running += 1,
+                JobState::Failed => stats.failed += 1,
+                JobState::Succeeded | JobState::Cancelled => {}
+            }
+        }
+        stats.oldest_due_secs = oldest_due.map(|at| (now - at).whole_seconds().max(0) as u64);
+        Ok(stats)
+    }
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_JOB: &str = "SELECT *, record::id(id) AS id FROM job";
+
+/// Condition matching the jobs a worker may claim at `$now`, as `QueuedJob::claimable`
+const CLAIMABLE: &str = "(state = 'queued' AND run_at <= <datetime> $now) \
+     OR (state = 'running' AND (locked_until = NONE OR locked_until <= <datetime> $now))";
+
+/// Jobs in the `job` table defined by the queue module's migrations
+pub struct SurrealJobStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealJobStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// `SET` clause writing every field of `job` but its id, with the bind values
+    fn assignments(job: &QueuedJob) -> (String, Map<String, Value>) {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(job.id));
+        vars.insert("kind".to_string(), json!(job.kind));
+        vars.insert("payload".to_string(), job.payload.clone());
+        vars.insert("state".to_string(), json!(job.state));
+        vars.insert("attempts".to_string(), json!(job.attempts));
+        vars.insert("max_attempts".to_string(), json!(job.max_attempts));
+        vars.insert("run_at".to_string(), json!(datetime(job.run_at)));
+        vars.insert("created_at".to_string(), json!(datetime(job.created_at)));
+        vars.insert("updated_at".to_string(), json!(datetime(job.updated_at)));
+        let mut fields = vec![
+            "kind = $kind".to_string(),
+            "payload = $payload".to_string(),
+            "state = $state".to_string(),
+            "attempts = $attempts".to_string(),
+            "max_attempts = $max_attempts".to_string(),
+            "run_at = <datetime> $run_at".to_string(),
+            "created_at = <datetime> $created_at".to_string(),
+            "updated_at = <datetime> $updated_at".to_string(),
+        ];
+        let locked_until = job.locked_until.map(datetime);
+        for (field, value, cast) in [
+            (
+                "tenant",
+                job.tenant
+                    .as_ref()
+                    .map(|tenant| tenant.as_str().to_string()),
+                "",
+            ),
+            ("locked_by", job.locked_by.clone(), ""),
+            ("locked_until", locked_until, "<datetime> "),
+            ("last_error", job.last_error.clone(), ""),
+            ("idempotency_key", job.idempotency_key.clone(), ""),
+        ] {
+            match value {
+                Some(value) => {
+                    fields.push(format!("{} = {}${}", field, cast, field));
+                    vars.insert(field.to_string(), json!(value));
+                }
+                None => fields.push(format!("{} = NONE", field)),
+            }
+        }
+        (fields.join(", "), vars)
+    }
+
+    /// Run an `UPDATE` of one job, returning whether it matched
+    async fn update_where(
+        &self,
+        job: &QueuedJob,
+        condition: &str,
+        mut extra: Map<String, Value>,
+    ) -> anyhow::Result<bool> {
+        let (set, mut vars) = Self::assignments(job);
+        vars.append(&mut extra);
+        let surql = format!(
+            "UPDATE job SET {} WHERE id = type::thing('job', $id) AND {} \
+             RETURN VALUE record::id(id);",
+            set, condition
+        );
+        let results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to update job")?;
+        Ok(affected(results.first()))
+    }
+}
+
+#[async_trait]
+impl JobStore for SurrealJobStore {
+    async fn insert(&self, job: QueuedJob) -> anyhow::Result<QueuedJob> {
+        let (set, vars) = Self::assignments(&job);
+        let surql = format!("CREATE type::thing('job', $id) SET {} RETURN NONE;", set);
+        match self.db.query_with(&surql, &vars).await {
+            Ok(_) => Ok(job),
+            Err(error)
+                if atlas_db::unique_index_violation(&error).as_deref()
+                    == Some(IDEMPOTENCY_INDEX) =>
+            {
+                let surql = format!(
+                    "{} WHERE tenant = $tenant AND kind = $kind \
+                     AND idempotency_key = $idempotency_key LIMIT 1;",
+                    SELECT_JOB
+                );
+                let results = self
+                    .db
+                    .query_with(&surql, &vars)
+                    .await
+                    .context("failed to read the job enqueued under the same key")?;
+                atlas_db::records(results.into_iter().next())?
+                    .into_iter()
+                    .next()
+                    .context("job with the same idempotency key disappeared")
+            }
+            Err(error) => Err(error.context("failed to enqueue job")),
+        }
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<QueuedJob>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let results = self
+            .db
+            .query_with(
+                &format!("{} WHERE id = type::thing('job', $id);", SELECT_JOB),
+                &vars,
+            )
+            .await
+            .context("failed to read job")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn claim(
+        &self,
+        worker: &str,
+        now: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> anyhow::Result<Option<QueuedJob>> {
+        let mut vars = Map::new();
+        vars.insert("worker".to_string(), json!(worker));
+        vars.insert("now".to_string(), json!(datetime(now)));
+        vars.insert("until".to_string(), json!(datetime(until)));
+        // The `UPDATE` checks the job is still claimable, so a worker racing for the
+        // same job gets none rather than a lease someone else holds
+        let surql = format!(
+            "BEGIN TRANSACTION;\n\
+             LET $next = (SELECT id, run_at FROM job WHERE {claimable} \
+             ORDER BY run_at, id LIMIT 1)[0].id;\n\
+             LET $claimed = IF $next THEN (UPDATE $next SET state = 'running', \
+             attempts += 1, locked_by = $worker, locked_until = <datetime> $until, \
+             updated_at = <datetime> $now WHERE {claimable} RETURN VALUE id) ELSE [] END;\n\
+             SELECT *, record::id(id) AS id FROM $claimed;\n\
+             COMMIT TRANSACTION;",
+            claimable = CLAIMABLE
+        );
+        let results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to claim a job")?;
+        Ok(atlas_db::records(results.into_iter().last())?
+            .into_iter()
+            .next())
+    }
+
+    async fn renew(&self, id: &str, worker: &str, until: OffsetDateTime) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        vars.insert("worker".to_string(), json!(worker));
+        vars.insert("until".to_string(), json!(datetime(until)));
+        let results = self
+            .db
+            .query_with(
+                "UPDATE job SET locked_until = <datetime> $until \
+                 WHERE id = type::thing('job', $id) AND state = 'running' \
+                 AND locked_by = $worker RETURN VALUE record::id(id);",
+                &vars,
+            )
+            .await
+            .context("failed to renew the job's lease")?;
+        Ok(affected(results.first()))
+    }
+
+    async fn finish(&self, worker: &str, job: QueuedJob) -> anyhow::Result<bool> {
+        let mut extra = Map::new();
+        extra.insert("worker".to_string(), json!(worker));
+        self.update_where(&job, "state = 'running' AND locked_by = $worker", extra)
+            .await
+    }
+
+    async fn list(&self, query: &JobQuery) -> anyhow::Result<Vec<QueuedJob>> {
+        let mut vars = Map::new();
+        let mut conditions = Vec::new();
+        if let Some(state) = query.state {
+            vars.insert("state".to_string(), json!(state));
+            conditions.push("state = $state");
+        }
+        if let Some(kind) = &query.kind {
+            vars.insert("kind".to_string(), json!(kind));
+            conditions.push("kind = $kind");
+        }
+        vars.insert("limit".to_string(), json!(query.limit()));
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let surql = format!(
+            "{} {} ORDER BY run_at, id LIMIT $limit;",
+            SELECT_JOB, filter
+        );
+        let results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to list jobs")?;
+        atlas_db::records(results.into_iter().next())
+    }
+
+    async fn update(&self, job: QueuedJob, state: JobState) -> anyhow::Result<bool> {
+        let mut extra = Map::new();
+        extra.insert("expected".to_string(), json!(state));
+        self.update_where(&job, "state = $expected", extra).await
+    }
+
+    async fn stats(&self, now: OffsetDateTime) -> anyhow::Result<QueueStats> {
+        let mut vars = Map::new();
+        vars.insert("now".to_string(), json!(datetime(now)));
+        let mut results = self
+            .db
+            .query_with(
+                "SELECT state, count() AS total FROM job GROUP BY state;\n\
+                 SELECT count() AS total, math::min(run_at) AS oldest FROM job \
+                 WHERE state = 'queued' AND run_at <= <datetime> $now GROUP ALL;",
+                &vars,
+            )
+            .await
+            .context("failed to read queue stats")?
+            .into_iter();
+        let mut stats = QueueStats::default();
+        for row in atlas_db::records::<StateCount>(results.next())? {
+            match row.state {
+                JobState::Queued => stats.queued = row.total,
+                JobState::Running => stats.running = row.total,
+                JobState::Failed => stats.failed = row.total,
+                JobState::Succeeded | JobState::Cancelled => {}
+            }
+        }
+        if let Some(due) = atlas_db::records::<DueJobs>(results.next())?.first() {
+            stats.due = due.total;
+            stats.oldest_due_secs = due
+                .oldest
+                .map(|at| (now - at).whole_seconds().max(0) as u64);
+        }
+        Ok(stats)
+    }
+
+    async fn next_run_at(&self) -> anyhow::Result<Option<OffsetDateTime>> {
+        let mut results = self
+            .db
+            .query(
+                "SELECT math::min(run_at) AS at FROM job WHERE state = 'queued' GROUP ALL;\n\
+                 SELECT math::min(locked_until) AS at FROM job \
+                 WHERE state = 'running' AND locked_until != NONE GROUP ALL;",
+            )
+            .await
+            .context("failed to read the next run time")?
+            .into_iter();
+        let queued = atlas_db::records::<NextRun>(results.next())?;
+        let lapsing = atlas_db::records::<NextRun>(results.next())?;
+        Ok(queued
+            .into_iter()
+            .chain(lapsing)
+            .filter_map(|row| row.at)
+            .min())
+    }
+}
+
+/// Unique index keeping one job per tenant, kind and idempotency key
+const IDEMPOTENCY_INDEX: &str = "job_idempotency";
+
+/// Row of the jobs-per-state query
+#[derive(Deserialize)]
+struct StateCount {
+    state: JobState,
+    total: u64,
+}
+
+/// Row of the due-jobs query
+#[derive(Deserialize)]
+struct DueJobs {
+    total: u64,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    oldest: Option<OffsetDateTime>,
+}
+
+/// Row of the next-run queries
+#[derive(Deserialize)]
+struct NextRun {
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    at: Option<OffsetDateTime>,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use atlas_db::testing::RecordingExecutor;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    pub(crate) const NOW: OffsetDateTime = datetime!(2024-05-01 12:00:00 UTC);
+
+    pub(crate) fn job(id: &str, run_at: OffsetDateTime) -> QueuedJob {
+        QueuedJob {
+            id: id.to_string(),
+            kind: "emails.send".to_string(),
+            payload: json!({ "to": "ada@example.com" }),
+            tenant: None,
+            state: JobState::Queued,
+            attempts: 0,
+            max_attempts: 3,
+            run_at,
+            locked_by: None,
+            locked_until: None,
+            last_error: None,
+            idempotency_key: None,
+            created_at: run_at,
+            updated_at: run_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claim_leases_due_jobs_oldest_first() {
+        let store = MemoryJobStore::new();
+        let lease = NOW + time::Duration::minutes(5);
+        store.insert(job("job_b", NOW)).await.unwrap();
+        store
+            .insert(job("job_a", NOW - time::Duration::minutes(1)))
+            .await
+            .unwrap();
+        store
+            .insert(job("job_later", NOW + time::Duration::hours(1)))
+            .await
+            .unwrap();
+
+        let first = store.claim("w1", NOW, lease).await.unwrap().unwrap();
+        let second = store.claim("w2", NOW, lease).await.unwrap().unwrap();
+
+        assert_eq!((first.id.as_str(), second.id.as_str()), ("job_a", "job_b"));
+        assert_eq!(first.state, JobState::Running);
+        assert_eq!(first.attempts, 1);
+        assert!(store.claim("w1", NOW, lease).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lapsed_lease_moves_job_to_another_worker() {
+        let store = MemoryJobStore::new();
+        store.insert(job("job_a", NOW)).await.unwrap();
+        let lease = NOW + time::Duration::minutes(5);
+        let claimed = store.claim("w1", NOW, lease).await.unwrap().unwrap();
+
+        assert!(store.claim("w2", NOW, lease).await.unwrap().is_none());
+        let reclaimed = store
+            .claim("w2", lease, lease + time::Duration::minutes(5))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(reclaimed.attempts, 2);
+        assert!(!store.renew("job_a", "w1", lease).await.unwrap());
+        let mut finished = claimed;
+        finished.state = JobState::Succeeded;
+        assert!(!store.finish("w1", finished.clone()).await.unwrap());
+        assert!(store.finish("w2", finished).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_next_run_at_is_the_earliest_due_or_lapsing_job() {
+        let store = MemoryJobStore::new();
+        assert!(store.next_run_at().await.unwrap().is_none());
+
+        let tomorrow = NOW + time::Duration::days(1);
+        store.insert(job("job_later", tomorrow)).await.unwrap();
+        store.insert(job("job_a", NOW)).await.unwrap();
+        assert_eq!(store.next_run_at().await.unwrap(), Some(NOW));
+
+        let lease = NOW + time::Duration::minutes(5);
+        store.claim("w1", NOW, lease).await.unwrap().unwrap();
+        assert_eq!(store.next_run_at().await.unwrap(), Some(lease));
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_is_stored_once_per_tenant_and_kind() {
+        let store = MemoryJobStore::new();
+        let keyed = |id: &str| QueuedJob {
+            idempotency_key: Some("invoice:42".to_string()),
+            ..job(id, NOW)
+        };
+
+        assert_eq!(store.insert(keyed("job_a")).await.unwrap().id, "job_a");
+        assert_eq!(store.insert(keyed("job_b")).await.unwrap().id, "job_a");
+        let other_tenant = QueuedJob {
+            tenant: Some(TenantId::new("acme").unwrap()),
+            ..keyed("job_c")
+        };
+        assert_eq!(store.insert(other_tenant).await.unwrap().id, "job_c");
+        assert_eq!(store.insert(job("job_d", NOW)).await.unwrap().id, "job_d");
+        assert!(store.get("job_b").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_update_and_stats() {
+        let store = MemoryJobStore::new();
+        store
+            .insert(job("job_a", NOW - time::Duration::minutes(10)))
+            .await
+            .unwrap();
+        store.insert(job("job_b", NOW)).await.unwrap();
+        store
+            .insert(job("job_later", NOW + time::Duration::hours(1)))
+            .await
+            .unwrap();
+        let mut failed = job("job_failed", NOW);
+        failed.state = JobState::Failed;
+        store.insert(failed.clone()).await.unwrap();
+
+        let queued = store
+            .list(&JobQuery {
+                state: Some(JobState::Queued),
+                limit: Some(2),
+                ..JobQuery::default()
+            })
+            .await
+            .unwrap();
+        let ids: Vec<_> = queued.iter().map(|job| job.id.as_str()).collect();
+        assert_eq!(ids, vec!["job_a", "job_b"]);
+        assert_eq!(
+            store.stats(NOW).await.unwrap(),
+            QueueStats {
+                queued: 3,
+                due: 2,
+                running: 0,
+                failed: 1,
+                oldest_due_secs: Some(600),
+            }
+        );
+
+        failed.state = JobState::Queued;
+        assert!(!store
+            .update(failed.clone(), JobState::Queued)
+            .await
+            .unwrap());
+        assert!(store.update(failed, JobState::Failed).await.unwrap());
+        assert_eq!(store.stats(NOW).await.unwrap().failed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_finished_jobs_are_dropped_beyond_capacity() {
+        let store = MemoryJobStore::with_capacity(2);
+        let mut done = job("job_done", NOW);
+        done.state = JobState::Succeeded;
+        store.insert(done).await.unwrap();
+        store.insert(job("job_a", NOW)).await.unwrap();
+        store.insert(job("job_b", NOW)).await.unwrap();
+
+        assert!(store.get("job_done").await.unwrap().is_none());
+        assert!(store.get("job_a").await.unwrap().is_some());
+    }
+
+    fn row(id: &str) -> Value {
+        json!({
+            "id": id,
+            "kind": "emails.send",
+            "payload": { "to": "ada@example.com" },
+            "state": "running",
+            "attempts": 1,
+            "max_attempts": 3,
+            "run_at": "2024-05-01T12:00:00Z",
+            "locked_by": "w1",
+            "locked_until": "2024-05-01T12:05:00Z",
+            "created_at": "2024-05-01T12:00:00Z",
+            "updated_at": "2024-05-01T12:00:00Z"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_claims_in_one_transaction() {
+        let db = RecordingExecutor::answering(vec![Ok(vec![
+            Value::Null,
+            Value::Null,
+            json!([row("job_a")]),
+        ])]);
+        let store = SurrealJobStore::new(db.clone());
+
+        let claimed = store
+            .claim("w1", NOW, NOW + time::Duration::minutes(5))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(claimed.id, "job_a");
+        assert_eq!(claimed.last_error, None);
+        let (surql, vars) = db.queries()[0].clone();
+        assert!(surql.starts_with("BEGIN TRANSACTION;"));
+        assert!(surql.contains("locked_by = $worker"));
+        assert_eq!(vars["worker"], "w1");
+        assert_eq!(vars["until"], "2024-05-01T12:05:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_returns_the_job_stored_under_the_same_key() {
+        let db = RecordingExecutor::answering(vec![
+            Err(anyhow::anyhow!(
+                "Database index `job_idempotency` already contains [NONE, 'emails.send', 'invoice:42'], with record `job:job_a`"
+            )),
+            Ok(vec![json!([row("job_a")])]),
+        ]);
+        let store = SurrealJobStore::new(db.clone());
+
+        let stored = store
+            .insert(QueuedJob {
+                idempotency_key: Some("invoice:42".to_string()),
+                ..job("job_b", NOW)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(stored.id, "job_a");
+        let queries = db.queries();
+        assert!(queries[0].0.contains("tenant = NONE"));
+        assert!(queries[0].0.contains("idempotency_key = $idempotency_key"));
+        assert!(queries[1]
+            .0
+            .contains("WHERE tenant = $tenant AND kind = $kind"));
+    }
+}
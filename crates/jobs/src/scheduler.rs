@@ -0,0 +1,390 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::Instrument;
+
+use atlas_kernel::{Clock, InitCtx, Job};
+
+use crate::cron::Schedule;
+use crate::leader::LeaderElection;
+
+/// Run counters and the outcome of the latest run of a scheduled job
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct JobStats {
+    pub runs: u64,
+    pub failures: u64,
+    /// Scheduled times skipped because the previous run was still going
+    pub skipped: u64,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_started_at: Option<OffsetDateTime>,
+    pub last_duration_ms: Option<u64>,
+    /// Error of the latest run, if it failed
+    pub last_error: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub next_run_at: Option<OffsetDateTime>,
+}
+
+/// A scheduled job as reported by `Scheduler::jobs`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub module: &'static str,
+    pub name: &'static str,
+    pub schedule: String,
+    pub running: bool,
+    #[serde(flatten)]
+    pub stats: JobStats,
+}
+
+struct ScheduledJob {
+    module: &'static str,
+    job: Job,
+    schedule: Schedule,
+    running: AtomicBool,
+    stats: Mutex<JobStats>,
+}
+
+impl ScheduledJob {
+    fn stats(&self) -> std::sync::MutexGuard<'_, JobStats> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Clears a job's running flag when its run ends, is cancelled or panics
+struct Running(Arc<ScheduledJob>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::Release);
+    }
+}
+
+/// Runs the jobs modules declare through `Module::jobs()` on their cron schedules
+pub struct Scheduler {
+    jobs: Vec<Arc<ScheduledJob>>,
+    clock: Arc<dyn Clock>,
+    leader: Option<Arc<LeaderElection>>,
+}
+
+impl Scheduler {
+    /// Parse the schedules of `jobs`, leaving out the `disabled` ones (`{module}.{job}`)
+    pub fn new(
+        jobs: Vec<(&'static str, Job)>,
+        disabled: &[String],
+        clock: Arc<dyn Clock>,
+    ) -> anyhow::Result<Self> {
+        let mut scheduled = Vec::new();
+        for (module, job) in jobs {
+            let schedule: Schedule = job
+                .schedule
+                .parse()
+                .with_context(|| format!("job '{}' of module '{}'", job.name, module))?;
+            if disabled.contains(&format!("{}.{}", module, job.name)) {
+                tracing::info!(module, job = job.name, "scheduled job disabled");
+                continue;
+            }
+            scheduled.push(Arc::new(ScheduledJob {
+                module,
+                job,
+                schedule,
+                running: AtomicBool::new(false),
+                stats: Mutex::new(JobStats::default()),
+            }));
+        }
+        Ok(Self {
+            jobs: scheduled,
+            clock,
+            leader: None,
+        })
+    }
+
+    /// Run jobs only while `leader` holds the lease
+    pub fn with_leader(mut self, leader: Arc<LeaderElection>) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+
+    /// Whether this replica runs scheduled jobs: it is the leader, or there is no election
+    pub fn is_leader(&self) -> bool {
+        self.leader.as_ref().is_none_or(|leader| leader.is_leader())
+    }
+
+    /// Every scheduled job with its counters, in declaration order
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs
+            .iter()
+            .map(|job| JobStatus {
+                module: job.module,
+                name: job.job.name,
+                schedule: job.schedule.to_string(),
+                running: job.running.load(Ordering::Acquire),
+                stats: job.stats().clone(),
+            })
+            .collect()
+    }
+
+    /// Run the job `{module}.{job}`, or the only job named `job`, once now and return its
+    /// status afterwards
+    ///
+    /// Fails when no job or several jobs match, or when a run of the job is still going.
+    /// The run's own error is recorded in the returned status rather than returned.
+    pub async fn run_now(&self, name: &str) -> anyhow::Result<JobStatus> {
+        let matching: Vec<_> = self
+            .jobs
+            .iter()
+            .filter(|job| {
+                name == job.job.name || name == format!("{}.{}", job.module, job.job.name)
+            })
+            .collect();
+        let job = match matching.as_slice() {
+            [job] => *job,
+            [] => anyhow::bail!("no scheduled job named '{}'", name),
+            several => anyhow::bail!(
+                "several modules declare a job named '{}'; use one of {}",
+                name,
+                several
+                    .iter()
+                    .map(|job| format!("{}.{}", job.module, job.job.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let Some(run) = self.start(job) else {
+            anyhow::bail!("job '{}.{}' is already running", job.module, job.job.name);
+        };
+        run.await?;
+        let status = self
+            .jobs()
+            .into_iter()
+            .find(|status| status.module == job.module && status.name == job.job.name)
+            .context("scheduled job disappeared")?;
+        Ok(status)
+    }
+
+    /// Spawn one task per job that waits for each scheduled time and starts a run
+    pub(crate) fn spawn(self: &Arc<Self>, ctx: &InitCtx) {
+        for job in &self.jobs {
+            let scheduler = self.clone();
+            let job = job.clone();
+            let runs = ctx.clone();
+            ctx.spawn(
+                format!("schedule {}.{}", job.module, job.job.name),
+                async move {
+                    loop {
+                        let now = scheduler.clock.now();
+                        let Some(next) = job.schedule.next_after(now.into()) else {
+                            tracing::warn!(
+                                module = job.module,
+                                job = job.job.name,
+                                schedule = %job.schedule,
+                                "schedule never fires again"
+                            );
+                            return Ok(());
+                        };
+                        job.stats().next_run_at = Some(next);
+                        let wait = next - OffsetDateTime::from(now);
+                        scheduler
+                            .clock
+                            .sleep(wait.try_into().unwrap_or_default())
+                            .await;
+                        if !scheduler.is_leader() {
+                            tracing::debug!(
+                                module = job.module,
+                                job = job.job.name,
+                                "not the leader; leaving the run to another replica"
+                            );
+                            continue;
+                        }
+                        if let Some(run) = scheduler.start(&job) {
+                            runs.spawn(format!("job {}.{}", job.module, job.job.name), run);
+                        }
+                    }
+                },
+            );
+        }
+    }
+
+    /// The next run of `job`, or `None` when the previous run is still going
+    fn start(
+        &self,
+        job: &Arc<ScheduledJob>,
+    ) -> Option<impl Future<Output = anyhow::Result<()>> + Send + 'static> {
+        if job.running.swap(true, Ordering::AcqRel) {
+            job.stats().skipped += 1;
+            tracing::warn!(
+                module = job.module,
+                job = job.job.name,
+                "previous run is still going; skipping this one"
+            );
+            return None;
+        }
+        let running = Running(job.clone());
+        let clock = self.clock.clone();
+        let span = tracing::info_span!("job", module = job.module, job = job.job.name);
+        Some(
+            async move {
+                let job = &running.0;
+                let started = clock.now();
+                job.stats().last_started_at = Some(started.into());
+                tracing::info!("job started");
+
+                let result = job.job.run().await;
+                let duration = clock.now().duration_since(started).unwrap_or_default();
+                let mut stats = job.stats();
+                stats.runs += 1;
+                stats.last_duration_ms = Some(duration.as_millis() as u64);
+                match result {
+                    Ok(()) => {
+                        stats.last_error = None;
+                        tracing::info!(duration_ms = duration.as_millis() as u64, "job finished");
+                    }
+                    Err(error) => {
+                        stats.failures += 1;
+                        stats.last_error = Some(format!("{:#}", error));
+                        tracing::error!(
+                            duration_ms = duration.as_millis() as u64,
+                            error = format!("{:#}", error),
+                            "job failed"
+                        );
+                    }
+                }
+                Ok(())
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tokio::sync::Notify;
+
+    fn scheduler(jobs: Vec<(&'static str, Job)>, disabled: &[String]) -> Scheduler {
+        Scheduler::new(
+            jobs,
+            disabled,
+            Arc::new(ManualClock::new(
+                UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            )),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_runs_are_skipped() {
+        let release = Arc::new(Notify::new());
+        let job = Job::new("sync", "* * * * *", {
+            let release = release.clone();
+            move || {
+                let release = release.clone();
+                async move {
+                    release.notified().await;
+                    Ok(())
+                }
+            }
+        });
+        let scheduler = scheduler(vec![("books", job)], &[]);
+        let scheduled = scheduler.jobs[0].clone();
+
+        let first = tokio::spawn(scheduler.start(&scheduled).unwrap());
+        assert!(scheduler.start(&scheduled).is_none());
+        assert!(scheduler.jobs()[0].running);
+
+        release.notify_one();
+        first.await.unwrap().unwrap();
+        let status = &scheduler.jobs()[0];
+        assert!(!status.running);
+        assert_eq!((status.stats.runs, status.stats.skipped), (1, 1));
+        assert!(scheduler.start(&scheduled).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_recorded() {
+        let job = Job::new("sync", "@hourly", || async {
+            anyhow::bail!("upstream down")
+        });
+        let scheduler = scheduler(vec![("books", job)], &[]);
+
+        scheduler.start(&scheduler.jobs[0]).unwrap().await.unwrap();
+
+        let stats = &scheduler.jobs()[0].stats;
+        assert_eq!((stats.runs, stats.failures), (1, 1));
+        assert_eq!(stats.last_error.as_deref(), Some("upstream down"));
+        assert!(stats.last_started_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_only_the_leader_runs_jobs() {
+        use crate::lock::{Locks, MemoryLockStore};
+
+        let clock = Arc::new(ManualClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let locks = Arc::new(Locks::new(Arc::new(MemoryLockStore::new()), clock));
+        let lease = Duration::from_secs(30);
+        let rival = LeaderElection::new(locks.clone(), "scheduler", lease);
+        assert!(rival.campaign().await);
+
+        let leader = Arc::new(LeaderElection::new(locks, "scheduler", lease));
+        let scheduler = scheduler(vec![], &[]).with_leader(leader.clone());
+        assert!(!leader.campaign().await);
+        assert!(!scheduler.is_leader());
+
+        // Without an election every replica runs jobs
+        assert!(self::scheduler(vec![], &[]).is_leader());
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_now_by_name_or_qualified_name() {
+        let job = |name| Job::new(name, "@daily", || async { Ok(()) });
+        let scheduler = scheduler(
+            vec![
+                ("books", job("reindex")),
+                ("books", job("sync")),
+                ("users", job("sync")),
+            ],
+            &[],
+        );
+
+        let status = scheduler.run_now("reindex").await.unwrap();
+        assert_eq!((status.module, status.stats.runs), ("books", 1));
+        let status = scheduler.run_now("users.sync").await.unwrap();
+        assert_eq!((status.module, status.name), ("users", "sync"));
+
+        let error = scheduler.run_now("sync").await.unwrap_err().to_string();
+        assert!(error.contains("books.sync, users.sync"));
+        assert!(scheduler.run_now("vacuum").await.is_err());
+    }
+
+    #[test]
+    fn test_invalid_schedules_fail_and_disabled_jobs_are_left_out() {
+        let job = |name, schedule| Job::new(name, schedule, || async { Ok(()) });
+
+        let error = Scheduler::new(
+            vec![("books", job("reindex", "every day"))],
+            &[],
+            Arc::new(atlas_kernel::clock::SystemClock),
+        )
+        .err()
+        .unwrap();
+        assert!(format!("{:#}", error).contains("job 'reindex' of module 'books'"));
+
+        let scheduler = scheduler(
+            vec![
+                ("books", job("reindex", "@daily")),
+                ("books", job("sync", "@hourly")),
+            ],
+            &["books.reindex".to_string()],
+        );
+        let names: Vec<_> = scheduler.jobs().iter().map(|job| job.name).collect();
+        assert_eq!(names, vec!["sync"]);
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rerun-if-env-changed=ATLAS_GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Reported by `atlas version`; plugins must also come from the host's compiler, as they
+    // share Rust trait objects with it.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = output(Command::new(rustc).arg("--version")).unwrap_or_else(unknown);
+    println!("cargo:rustc-env=ATLAS_RUSTC_VERSION={}", version);
+
+    // Builds without a checkout, e.g. in a Docker context, pass the commit as ATLAS_GIT_SHA
+    let git_sha = std::env::var("ATLAS_GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(git_sha)
+        .unwrap_or_else(unknown);
+    println!("cargo:rustc-env=ATLAS_GIT_SHA={}", git_sha);
+
+    // SOURCE_DATE_EPOCH pins the timestamp for reproducible builds
+    let epoch = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=ATLAS_BUILT_AT={}", rfc3339(epoch));
+
+    let profile = std::env::var("PROFILE").unwrap_or_else(|_| unknown());
+    println!("cargo:rustc-env=ATLAS_BUILD_PROFILE={}", profile);
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(name, _)| {
+            name.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=ATLAS_FEATURES={}", features.join(","));
+}
+
+fn unknown() -> String {
+    "unknown".to_string()
+}
+
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Commit checked out in the repository, re-running the build script when it moves
+fn git_sha() -> Option<String> {
+    let git_dir = output(Command::new("git").args(["rev-parse", "--absolute-git-dir"]))?;
+    let git_dir = PathBuf::from(git_dir);
+    rerun_if_changed(&git_dir.join("HEAD"));
+    rerun_if_changed(&git_dir.join("packed-refs"));
+    if let Some(branch) = output(Command::new("git").args(["symbolic-ref", "-q", "HEAD"])) {
+        rerun_if_changed(&git_dir.join(branch));
+    }
+    output(Command::new("git").args(["rev-parse", "--short=12", "HEAD"]))
+}
+
+fn rerun_if_changed(path: &Path) {
+    if path.exists() {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+/// `2024-05-01T12:00:00Z` for seconds since the Unix epoch
+fn rfc3339(epoch: u64) -> String {
+    let days = (epoch / 86_400) as i64;
+    let seconds = epoch % 86_400;
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
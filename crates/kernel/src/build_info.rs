@@ -0,0 +1,76 @@
+//! What the running binary was built from, embedded at compile time by `build.rs`
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Version, commit, time, compiler, profile and features of the build
+///
+/// Fields the build could not determine, such as the commit of a build outside a git
+/// checkout without `ATLAS_GIT_SHA`, are `unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    /// RFC 3339 time the kernel was compiled, or `SOURCE_DATE_EPOCH`
+    pub built_at: &'static str,
+    pub rustc: &'static str,
+    /// Cargo profile, `debug` or `release`
+    pub profile: &'static str,
+    /// Enabled `atlas-kernel` features, e.g. `plugins`
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    /// Metadata of the binary running this code
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("ATLAS_GIT_SHA"),
+            built_at: env!("ATLAS_BUILT_AT"),
+            rustc: env!("ATLAS_RUSTC_VERSION"),
+            profile: env!("ATLAS_BUILD_PROFILE"),
+            features: env!("ATLAS_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// e.g. `atlas 0.1.0 (3f2c1a9e07b4, release, built 2024-05-01T12:00:00Z)`
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "atlas {} ({}, {}, built {})",
+            self.version, self.git_sha, self.profile, self.built_at
+        )?;
+        writeln!(f, "{}", self.rustc)?;
+        if self.features.is_empty() {
+            write!(f, "features: none")
+        } else {
+            write!(f, "features: {}", self.features.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_build_is_described() {
+        let build = BuildInfo::current();
+
+        assert_eq!(build.version, env!("CARGO_PKG_VERSION"));
+        assert!(build.rustc.starts_with("rustc "));
+        assert_eq!(build.built_at.len(), "2024-05-01T12:00:00Z".len());
+        assert!(build.built_at.ends_with('Z'));
+        assert_eq!(
+            build.features.contains(&"plugins"),
+            cfg!(feature = "plugins")
+        );
+        assert!(build.to_string().starts_with("atlas "));
+    }
+}
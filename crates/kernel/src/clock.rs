@@ -0,0 +1,99 @@
+//! Injectable source of time
+
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+
+/// Wall-clock time and delays, injected so tests can control them
+///
+/// Modules get it from `InitCtx::clock`, handlers from `AppContext::clock`.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Current wall-clock time
+    fn now(&self) -> SystemTime;
+
+    /// Wait for `duration` to pass on this clock
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The real system clock, backed by the Tokio timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Clock that only moves when told to, for deterministic tests
+///
+/// `sleep` advances the clock by the requested duration and returns after yielding once,
+/// so loops that wait on the clock do not starve other tasks.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<SystemTime>,
+}
+
+impl ManualClock {
+    /// Create a clock frozen at `now`
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self
+            .now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) += duration;
+    }
+
+    /// Jump to a specific time
+    pub fn set(&self, now: SystemTime) {
+        *self
+            .now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = now;
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self
+            .now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+        tokio::task::yield_now().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_clock_moves_only_when_told() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_secs(5));
+        clock.sleep(Duration::from_secs(60)).await;
+
+        assert_eq!(clock.now(), start + Duration::from_secs(65));
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
+use crate::resources::Resources;
+use crate::settings::Settings;
+
+/// Shared services available to route handlers
+///
+/// The HTTP server inserts it as an `axum::Extension` on every route, so handlers take
+/// `Extension(ctx): Extension<AppContext>`. Core modules publish their handles (database
+/// client, event bus, metrics recorder, ...) into the registry's resources during `init`,
+/// and handlers look them up here by type.
+#[derive(Clone)]
+pub struct AppContext {
+    settings: Arc<Settings>,
+    resources: Arc<Resources>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AppContext {
+    /// Create a context from shared settings and resources
+    pub fn new(settings: Arc<Settings>, resources: Arc<Resources>) -> Self {
+        Self {
+            settings,
+            resources,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Use `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Settings the server was started with
+    pub fn settings(&self) -> &Arc<Settings> {
+        &self.settings
+    }
+
+    /// Source of time shared with the modules
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// All handles published by modules
+    pub fn resources(&self) -> &Arc<Resources> {
+        &self.resources
+    }
+
+    /// Get a shared service by type
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.resources.get::<T>()
+    }
+
+    /// Get a shared service by type, failing with the type name when no module provides it
+    pub fn require<T: Send + Sync + 'static>(&self) -> anyhow::Result<Arc<T>> {
+        self.resources.require::<T>()
+    }
+}
+
+impl std::fmt::Debug for AppContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppContext")
+            .field("settings", &self.settings)
+            .field("resources", &self.resources)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EventBus(&'static str);
+
+    #[test]
+    fn test_services_resolved_from_shared_resources() {
+        let resources = Arc::new(Resources::new());
+        let ctx = AppContext::new(Arc::new(Settings::default()), resources.clone());
+        assert!(ctx.get::<EventBus>().is_none());
+
+        // Handles published after the context is built are still visible through it
+        resources.insert(EventBus("in-memory"));
+
+        assert_eq!(ctx.require::<EventBus>().unwrap().0, "in-memory");
+        assert_eq!(ctx.settings().server.port, 8080);
+    }
+}
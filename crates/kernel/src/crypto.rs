@@ -0,0 +1,73 @@
+//! Random secrets, digests, MACs and secret comparison shared by every crate that handles
+//! tokens
+//!
+//! Tokens carry 256 bits of entropy, so a plain SHA-256 digest is enough to store them;
+//! a slow password hash would add latency to every request for no benefit.
+
+use anyhow::anyhow;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// A fresh `{prefix}{64 hex chars}` token
+pub fn generate_token(prefix: &str) -> anyhow::Result<String> {
+    let mut secret = [0u8; 32];
+    getrandom::fill(&mut secret).map_err(|error| anyhow!("failed to generate token: {}", error))?;
+    Ok(format!("{}{}", prefix, to_hex(&secret)))
+}
+
+/// Hex SHA-256 digest, stored in place of a token
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// HMAC-SHA256 of `message` under `key`
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// Compare secrets without leaking where they differ through timing
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_unique_and_prefixed() {
+        let first = generate_token("atlas_").unwrap();
+        let second = generate_token("atlas_").unwrap();
+
+        assert!(first.starts_with("atlas_"));
+        assert_eq!(first.len(), "atlas_".len() + 64);
+        assert_ne!(first, second);
+        assert_eq!(sha256_hex(first.as_bytes()).len(), 64);
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc_4231() {
+        // Test cases 2 and 6: a short key and a key longer than the block size
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Structured errors for module registration and lifecycle failures
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::module::{ModuleKind, ModuleState};
+
+/// Lifecycle phase in which a module failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecyclePhase {
+    Init,
+    Start,
+    Stop,
+    ConfigChange,
+}
+
+impl LifecyclePhase {
+    /// Verb used when rendering the phase in error messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Init => "initialize",
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::ConfigChange => "apply configuration change to",
+        }
+    }
+}
+
+impl LifecyclePhase {
+    /// Whether a module in `state` may enter this phase
+    ///
+    /// Stopped modules may be started again; configuration changes are accepted in any state.
+    pub fn allowed_from(&self, state: ModuleState) -> bool {
+        match self {
+            Self::Init => state == ModuleState::Registered,
+            Self::Start => matches!(state, ModuleState::Initialized | ModuleState::Stopped),
+            Self::Stop => state == ModuleState::Started,
+            Self::ConfigChange => true,
+        }
+    }
+}
+
+impl std::fmt::Display for LifecyclePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Errors raised by the module registry
+#[derive(Error, Debug)]
+pub enum KernelError {
+    #[error("failed to {phase} {kind} module '{module}'")]
+    Lifecycle {
+        module: String,
+        kind: ModuleKind,
+        phase: LifecyclePhase,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    #[error("cannot {phase} module '{module}' while it is {state}")]
+    InvalidState {
+        module: String,
+        phase: LifecyclePhase,
+        state: ModuleState,
+    },
+
+    #[error("module '{module}' is already registered; module names must be unique")]
+    DuplicateModule { module: String },
+
+    #[error("module dependency cycle detected: {}", cycle.join(" -> "))]
+    DependencyCycle { cycle: Vec<String> },
+
+    #[error("module '{module}' depends on '{dependency}', which is not registered before it")]
+    MissingDependency { module: String, dependency: String },
+}
+
+impl KernelError {
+    /// Wrap a module's own error with the module and phase it failed in
+    pub fn lifecycle(
+        module: &str,
+        kind: ModuleKind,
+        phase: LifecyclePhase,
+        source: anyhow::Error,
+    ) -> Self {
+        Self::Lifecycle {
+            module: module.to_string(),
+            kind,
+            phase,
+            source,
+        }
+    }
+
+    /// Name of the module the error is about, if it concerns a single module
+    pub fn module(&self) -> Option<&str> {
+        match self {
+            Self::Lifecycle { module, .. }
+            | Self::InvalidState { module, .. }
+            | Self::DuplicateModule { module }
+            | Self::MissingDependency { module, .. } => Some(module),
+            Self::DependencyCycle { .. } => None,
+        }
+    }
+
+    /// Lifecycle phase that failed or was refused, for errors raised while running modules
+    pub fn phase(&self) -> Option<LifecyclePhase> {
+        match self {
+            Self::Lifecycle { phase, .. } | Self::InvalidState { phase, .. } => Some(*phase),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_error_names_module_and_phase() {
+        let error = KernelError::lifecycle(
+            "db",
+            ModuleKind::Core,
+            LifecyclePhase::Start,
+            anyhow::anyhow!("connection refused"),
+        );
+
+        assert_eq!(error.to_string(), "failed to start core module 'db'");
+        assert_eq!(error.module(), Some("db"));
+        assert_eq!(error.phase(), Some(LifecyclePhase::Start));
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "connection refused"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+
+/// Health status reported by a module, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+/// Health of a single module
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ModuleHealth {
+    /// Module is fully operational
+    pub fn healthy() -> Self {
+        Self {
+            status: HealthStatus::Healthy,
+            message: None,
+            details: None,
+        }
+    }
+
+    /// Module works with reduced capability
+    pub fn degraded(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Degraded,
+            message: Some(message.into()),
+            details: None,
+        }
+    }
+
+    /// Module cannot serve requests
+    pub fn unhealthy(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Unhealthy,
+            message: Some(message.into()),
+            details: None,
+        }
+    }
+
+    /// Attach structured details (latency, pool sizes, ...) to the report
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Health entry for one module inside a `HealthReport`
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleHealthEntry {
+    pub module: String,
+    #[serde(flatten)]
+    pub health: ModuleHealth,
+}
+
+/// Aggregated health of every registered module
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// Worst status reported by any module
+    pub status: HealthStatus,
+    pub modules: Vec<ModuleHealthEntry>,
+}
+
+impl HealthReport {
+    /// Aggregate per-module health, keeping the given module order
+    pub fn new(modules: Vec<ModuleHealthEntry>) -> Self {
+        let status = modules
+            .iter()
+            .map(|entry| entry.health.status)
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+        Self { status, modules }
+    }
+
+    /// Whether the application can serve traffic (no module is unhealthy)
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Unhealthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(module: &str, health: ModuleHealth) -> ModuleHealthEntry {
+        ModuleHealthEntry {
+            module: module.to_string(),
+            health,
+        }
+    }
+
+    #[test]
+    fn test_report_takes_worst_status() {
+        let report = HealthReport::new(vec![
+            entry("db", ModuleHealth::healthy()),
+            entry("cache", ModuleHealth::degraded("replica lagging")),
+        ]);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.is_ready());
+
+        let report = HealthReport::new(vec![
+            entry("db", ModuleHealth::unhealthy("connection refused")),
+            entry("cache", ModuleHealth::degraded("replica lagging")),
+        ]);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert!(!report.is_ready());
+    }
+
+    #[test]
+    fn test_report_serializes_flat_entries() {
+        let report = HealthReport::new(vec![entry(
+            "db",
+            ModuleHealth::degraded("slow").with_details(serde_json::json!({ "latency_ms": 250 })),
+        )]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["modules"][0]["module"], "db");
+        assert_eq!(json["modules"][0]["message"], "slow");
+        assert_eq!(json["modules"][0]["details"]["latency_ms"], 250);
+    }
+}
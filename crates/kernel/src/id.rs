@@ -0,0 +1,124 @@
+//! Identifier generation shared by repositories, request ids, and error traces
+//!
+//! Both formats are time-ordered, so ids sort by creation time and index well.
+
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+pub use ulid::Ulid;
+pub use uuid::Uuid;
+
+/// Separator between a table prefix and the ULID, as in `book_01HV6Y...`
+pub const PREFIX_SEPARATOR: char = '_';
+
+/// Reasons a prefixed id is rejected
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdError {
+    #[error("id prefix '{0}' must be non-empty lowercase ASCII letters or digits")]
+    InvalidPrefix(String),
+    #[error("id '{id}' does not start with '{expected}{PREFIX_SEPARATOR}'")]
+    WrongPrefix { id: String, expected: String },
+    #[error("id '{id}' does not end in a valid ULID: {source}")]
+    InvalidUlid {
+        id: String,
+        #[source]
+        source: ulid::DecodeError,
+    },
+}
+
+/// A new UUIDv7, for columns and headers that expect the UUID format
+pub fn uuid_v7() -> Uuid {
+    Uuid::now_v7()
+}
+
+/// A new ULID
+pub fn ulid() -> Ulid {
+    Ulid::new()
+}
+
+/// A new ULID whose timestamp part is `time`, e.g. `clock.now()` from an injected clock
+pub fn ulid_at(time: SystemTime) -> Ulid {
+    Ulid::from_datetime(time)
+}
+
+/// A new table-prefixed id such as `book_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+pub fn prefixed(prefix: &str) -> Result<String, IdError> {
+    prefixed_with(prefix, ulid())
+}
+
+/// Format an existing ULID with a table prefix
+pub fn prefixed_with(prefix: &str, ulid: Ulid) -> Result<String, IdError> {
+    check_prefix(prefix)?;
+    Ok(format!("{}{}{}", prefix, PREFIX_SEPARATOR, ulid))
+}
+
+/// Parse a table-prefixed id, checking it belongs to `prefix`
+pub fn parse_prefixed(prefix: &str, id: &str) -> Result<Ulid, IdError> {
+    check_prefix(prefix)?;
+    let encoded = id
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix(PREFIX_SEPARATOR))
+        .ok_or_else(|| IdError::WrongPrefix {
+            id: id.to_string(),
+            expected: prefix.to_string(),
+        })?;
+    Ulid::from_string(encoded).map_err(|source| IdError::InvalidUlid {
+        id: id.to_string(),
+        source,
+    })
+}
+
+fn check_prefix(prefix: &str) -> Result<(), IdError> {
+    let valid = !prefix.is_empty()
+        && prefix
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit());
+    if valid {
+        Ok(())
+    } else {
+        Err(IdError::InvalidPrefix(prefix.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_prefixed_ids_round_trip() {
+        let id = prefixed("book").unwrap();
+        assert!(id.starts_with("book_"));
+        assert_eq!(id.len(), "book_".len() + 26);
+
+        let ulid = parse_prefixed("book", &id).unwrap();
+        assert_eq!(prefixed_with("book", ulid).unwrap(), id);
+    }
+
+    #[test]
+    fn test_parse_rejects_foreign_or_malformed_ids() {
+        let id = prefixed("user").unwrap();
+        assert!(matches!(
+            parse_prefixed("book", &id),
+            Err(IdError::WrongPrefix { .. })
+        ));
+        assert!(matches!(
+            parse_prefixed("book", "book_not-a-ulid"),
+            Err(IdError::InvalidUlid { .. })
+        ));
+        assert_eq!(
+            prefixed("Book").unwrap_err(),
+            IdError::InvalidPrefix("Book".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ids_sort_by_creation_time() {
+        let earlier = ulid_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let later = ulid_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_001));
+        assert!(earlier.to_string() < later.to_string());
+
+        assert_eq!(uuid_v7().get_version_num(), 7);
+    }
+}
@@ -1,7 +1,46 @@
+pub mod build_info;
+pub mod clock;
+pub mod context;
+pub mod crypto;
+pub mod error;
+pub mod health;
+pub mod id;
+pub mod lifecycle;
 pub mod module;
+pub mod net;
+#[cfg(feature = "plugins")]
+pub mod plugin;
 pub mod registry;
+pub mod reload;
+pub mod resources;
+pub mod retry;
+pub mod runtime;
 pub mod settings;
+pub mod shutdown;
+pub mod startup;
+pub mod tasks;
+pub mod tenant;
+
+#[doc(hidden)]
+pub use inventory;
 
 /// Re-export commonly used types
-pub use module::{InitCtx, Migration, Module};
+pub use build_info::BuildInfo;
+pub use clock::Clock;
+pub use context::AppContext;
+pub use error::{KernelError, LifecyclePhase};
+pub use health::{HealthReport, HealthStatus, ModuleHealth};
+pub use lifecycle::{LifecycleEvent, LifecycleListener};
+pub use module::{
+    CacheInvalidation, EventFuture, EventHandler, EventSchema, InitCtx, Job, JobFuture, Migration,
+    Module, ModuleCacheInvalidations, ModuleEventHandlers, ModuleEventSchemas, ModuleInfo,
+    ModuleJobs, ModuleKind, ModuleQueueHandlers, ModuleRegistration, ModuleState, QueueHandler,
+    RouteDeprecation, RouteSecurity, SchemaExample, SecurityScheme, Seed, SkipReason,
+    SkippedModule,
+};
 pub use registry::ModuleRegistry;
+pub use resources::Resources;
+pub use retry::RetryPolicy;
+pub use shutdown::{ShutdownCoordinator, ShutdownReport};
+pub use startup::StartupReport;
+pub use tasks::TaskSupervisor;
@@ -0,0 +1,84 @@
+//! Lifecycle events
+//!
+//! The registry announces modules starting and failing, and the shutdown coordinator
+//! announces the start of shutdown, to the `Arc<dyn LifecycleListener>` resource if one is
+//! registered. The events module registers one that publishes them on the event bus, so
+//! operational tooling and modules can react to them like to any other event.
+
+use serde::Serialize;
+
+use crate::error::LifecyclePhase;
+use crate::module::ModuleKind;
+
+/// A change in the lifecycle of the app or one of its modules
+///
+/// Serializes to the payload of the event named by `name()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum LifecycleEvent {
+    /// A module finished `start`
+    ModuleStarted {
+        module: &'static str,
+        kind: ModuleKind,
+    },
+    /// A module's `init`, `start` or `stop` failed
+    ModuleFailed {
+        module: &'static str,
+        kind: ModuleKind,
+        phase: LifecyclePhase,
+        error: String,
+    },
+    /// A module's migration was applied to the database
+    MigrationApplied { module: String, migration: String },
+    /// Shutdown began; started modules stop next
+    ShutdownBegun {
+        /// Modules still started
+        modules: usize,
+    },
+}
+
+impl LifecycleEvent {
+    pub const MODULE_STARTED: &'static str = "kernel.module_started";
+    pub const MODULE_FAILED: &'static str = "kernel.module_failed";
+    pub const MIGRATION_APPLIED: &'static str = "kernel.migration_applied";
+    pub const SHUTDOWN_BEGUN: &'static str = "kernel.shutdown_begun";
+
+    /// Name the event is emitted under
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ModuleStarted { .. } => Self::MODULE_STARTED,
+            Self::ModuleFailed { .. } => Self::MODULE_FAILED,
+            Self::MigrationApplied { .. } => Self::MIGRATION_APPLIED,
+            Self::ShutdownBegun { .. } => Self::SHUTDOWN_BEGUN,
+        }
+    }
+}
+
+/// Receives lifecycle events as they happen
+///
+/// Called inline from the lifecycle, so implementations must not block.
+pub trait LifecycleListener: Send + Sync {
+    fn on_lifecycle(&self, event: &LifecycleEvent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_events_serialize_to_their_payload() {
+        let failed = LifecycleEvent::ModuleFailed {
+            module: "search",
+            kind: ModuleKind::Custom,
+            phase: LifecyclePhase::Start,
+            error: "index missing".to_string(),
+        };
+
+        assert_eq!(failed.name(), "kernel.module_failed");
+        assert_eq!(
+            serde_json::to_value(&failed).unwrap(),
+            json!({ "module": "search", "kind": "custom", "phase": "start", "error": "index missing" })
+        );
+    }
+}
@@ -1,12 +1,82 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use axum::Router;
+use serde::Serialize;
+
+use crate::clock::{Clock, SystemClock};
+use crate::resources::Resources;
+use crate::retry::RetryPolicy;
+use crate::settings::Settings;
+use crate::tasks::{TaskSupervisor, KERNEL_OWNER};
 
 /// Context provided to modules during initialization
-pub struct InitCtx<'a> {
-    pub settings: &'a crate::settings::Settings,
-    // TODO: Add db and events when those crates are implemented
-    // pub db: &'a surrealdb::Surreal<surrealdb::engine::remote::ws::Client>,
-    // pub events: &'a crate::events::EventBus,
+///
+/// Cheap to clone; modules may keep it, or parts of it, for tasks that outlive `init`.
+#[derive(Clone)]
+pub struct InitCtx {
+    pub settings: Arc<Settings>,
+    /// Handles shared between modules (db client, event bus, cache, ...)
+    pub resources: Arc<Resources>,
+    /// Source of time; replaced with a `ManualClock` in tests
+    pub clock: Arc<dyn Clock>,
+    tasks: TaskSupervisor,
+    /// Module the registry handed this context to; owns the tasks spawned through it
+    module: &'static str,
+}
+
+impl InitCtx {
+    /// Create a context from shared settings and resources
+    pub fn new(settings: Arc<Settings>, resources: Arc<Resources>) -> Self {
+        Self::with_tasks(settings, resources, TaskSupervisor::new())
+    }
+
+    /// Create a context whose spawned tasks are tracked by `tasks`
+    pub fn with_tasks(
+        settings: Arc<Settings>,
+        resources: Arc<Resources>,
+        tasks: TaskSupervisor,
+    ) -> Self {
+        Self {
+            settings,
+            resources,
+            clock: Arc::new(SystemClock),
+            tasks,
+            module: KERNEL_OWNER,
+        }
+    }
+
+    /// Use `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The same context, with spawned tasks owned by `module`
+    pub(crate) fn scoped(&self, module: &'static str) -> Self {
+        Self {
+            module,
+            ..self.clone()
+        }
+    }
+
+    /// Spawn a background task owned by the module this context was handed to
+    ///
+    /// The task is aborted after the module stops, and a panic is logged and recorded
+    /// instead of disappearing silently.
+    pub fn spawn<F>(&self, name: impl Into<String>, future: F)
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.tasks.spawn(self.module, name, future);
+    }
+
+    /// Supervisor tracking the tasks spawned through this context
+    pub fn tasks(&self) -> &TaskSupervisor {
+        &self.tasks
+    }
 }
 
 /// Migration definition for modules
@@ -14,6 +84,424 @@ pub struct InitCtx<'a> {
 pub struct Migration {
     pub id: &'static str,
     pub up: &'static str,
+    /// Script undoing `up`, run by `atlas migrate down`; `None` if it cannot be undone
+    pub down: Option<&'static str>,
+}
+
+/// Sample or reference data loaded with `atlas seed`
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub id: &'static str,
+    /// Tables the script writes to, emptied first by `atlas seed --reset`
+    pub tables: &'static [&'static str],
+    /// SurrealQL inserting the data
+    pub script: &'static str,
+}
+
+/// Deprecation notice for one of a module's routes
+#[derive(Debug, Clone)]
+pub struct RouteDeprecation {
+    /// Lowercase HTTP method, as used in the OpenAPI fragment (e.g. `get`)
+    pub method: &'static str,
+    /// Route path relative to the module mount point, as used in the OpenAPI fragment
+    pub path: &'static str,
+    /// Date the route stops working, formatted as `YYYY-MM-DD`
+    pub sunset: Option<&'static str>,
+}
+
+/// Credential accepted by a guarded route, documented as an OpenAPI security scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityScheme {
+    /// `Authorization: Bearer {admin.token}`
+    AdminToken,
+    /// API key in the `x-api-key` header
+    ApiKey,
+    /// Session cookie set by the sessions module
+    Session,
+    /// Signed service token in `Authorization: Bearer`
+    ServiceToken,
+    /// HMAC request signature in the `x-atlas-signature` header
+    Signature,
+}
+
+impl SecurityScheme {
+    /// Name of the scheme under `components.securitySchemes`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AdminToken => "adminToken",
+            Self::ApiKey => "apiKey",
+            Self::Session => "session",
+            Self::ServiceToken => "serviceToken",
+            Self::Signature => "requestSignature",
+        }
+    }
+}
+
+/// Authentication and authorization guarding one or more of a module's routes
+#[derive(Debug, Clone)]
+pub struct RouteSecurity {
+    /// Lowercase HTTP method as used in the OpenAPI fragment, or `*` for every method
+    pub method: &'static str,
+    /// Route path relative to the module mount point, or `*` for every path
+    pub path: &'static str,
+    /// Credentials the route accepts; any one of them is enough
+    pub schemes: &'static [SecurityScheme],
+    /// Policy permission checked after authentication, as `object:action`
+    pub permission: Option<&'static str>,
+}
+
+/// Example payload for one of a module's OpenAPI schemas
+#[derive(Debug, Clone)]
+pub struct SchemaExample {
+    /// Schema name under `components.schemas` in the module's fragment
+    pub schema: &'static str,
+    /// Example value shown by Swagger UI
+    pub value: serde_json::Value,
+}
+
+impl SchemaExample {
+    /// Create an example from an explicit JSON value
+    pub fn new(schema: &'static str, value: serde_json::Value) -> Self {
+        Self { schema, value }
+    }
+
+    /// Create an example by serializing the model's `Default` value
+    pub fn from_default<T: Default + serde::Serialize>(schema: &'static str) -> Self {
+        Self {
+            schema,
+            value: serde_json::to_value(T::default()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Future returned by an `EventHandler`
+pub type EventFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// A module's subscription to a named event, such as `books.created`
+///
+/// Handlers receive the JSON payload the event was emitted with.
+#[derive(Clone)]
+pub struct EventHandler {
+    /// Name the event is emitted under
+    pub event: &'static str,
+    handler: Arc<dyn Fn(serde_json::Value) -> EventFuture + Send + Sync>,
+}
+
+impl EventHandler {
+    pub fn new<F, Fut>(event: &'static str, handler: F) -> Self
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            event,
+            handler: Arc::new(move |payload| Box::pin(handler(payload))),
+        }
+    }
+
+    /// Run the handler for one occurrence of the event
+    pub fn handle(&self, payload: serde_json::Value) -> EventFuture {
+        (self.handler)(payload)
+    }
+}
+
+impl std::fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventHandler")
+            .field("event", &self.event)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cache keys that go stale when a module's named event is emitted
+///
+/// Keys are templates: `{field}` is replaced with that field of the event's payload (a
+/// dotted path such as `{author.id}` reaches nested objects) and `{tenant}` with the tenant
+/// the event was emitted in. A key ending in `*` drops every key starting with the rest.
+#[derive(Debug, Clone)]
+pub struct CacheInvalidation {
+    /// Name the event is emitted under
+    pub event: &'static str,
+    pub keys: &'static [&'static str],
+}
+
+impl CacheInvalidation {
+    pub const fn new(event: &'static str, keys: &'static [&'static str]) -> Self {
+        Self { event, keys }
+    }
+}
+
+/// Future returned by a `Job`
+pub type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// A recurring job a module runs on a cron schedule
+///
+/// The schedule is a cron expression evaluated in UTC, `minute hour day month weekday`
+/// with an optional leading seconds field, or a macro such as `@hourly`.
+#[derive(Clone)]
+pub struct Job {
+    /// Name of the job, unique within its module
+    pub name: &'static str,
+    pub schedule: &'static str,
+    run: Arc<dyn Fn() -> JobFuture + Send + Sync>,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: &'static str, schedule: &'static str, run: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            name,
+            schedule,
+            run: Arc::new(move || Box::pin(run())),
+        }
+    }
+
+    /// Run the job once
+    pub fn run(&self) -> JobFuture {
+        (self.run)()
+    }
+}
+
+impl std::fmt::Debug for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Job")
+            .field("name", &self.name)
+            .field("schedule", &self.schedule)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A module's handler for queued jobs of one kind, such as `emails.send`
+///
+/// Jobs are enqueued with a JSON payload and may run more than once: after a failure, or
+/// when a worker stops before finishing. Handlers must be idempotent.
+#[derive(Clone)]
+pub struct QueueHandler {
+    /// Kind the jobs are enqueued under
+    pub kind: &'static str,
+    handler: Arc<dyn Fn(serde_json::Value) -> JobFuture + Send + Sync>,
+}
+
+impl QueueHandler {
+    pub fn new<F, Fut>(kind: &'static str, handler: F) -> Self
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            kind,
+            handler: Arc::new(move |payload| Box::pin(handler(payload))),
+        }
+    }
+
+    /// Run the handler for one queued job
+    pub fn handle(&self, payload: serde_json::Value) -> JobFuture {
+        (self.handler)(payload)
+    }
+}
+
+impl std::fmt::Debug for QueueHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueueHandler")
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A named event a module emits, with the JSON Schema of one version of its payload
+///
+/// A breaking payload change gets a new version; older versions stay declared while
+/// emitters or consumers still use them.
+#[derive(Debug, Clone)]
+pub struct EventSchema {
+    pub name: &'static str,
+    pub version: u32,
+    pub description: Option<&'static str>,
+    /// JSON Schema subset checked when the event is emitted
+    pub schema: serde_json::Value,
+}
+
+/// Every module's event schemas, published as a resource before core modules start
+#[derive(Debug, Clone, Default)]
+pub struct ModuleEventSchemas {
+    pub schemas: Vec<(&'static str, EventSchema)>,
+}
+
+/// Every module's event handlers, published as a resource before core modules start
+///
+/// The events module subscribes them when it starts; each entry names the module that
+/// declared the handler.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleEventHandlers {
+    pub handlers: Vec<(&'static str, EventHandler)>,
+}
+
+/// Every module's cache invalidations, published as a resource before core modules start
+#[derive(Debug, Clone, Default)]
+pub struct ModuleCacheInvalidations {
+    pub invalidations: Vec<(&'static str, CacheInvalidation)>,
+}
+
+/// Every module's recurring jobs, published as a resource before core modules start
+///
+/// The scheduler module runs them once it starts; each entry names the module that
+/// declared the job.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleJobs {
+    pub jobs: Vec<(&'static str, Job)>,
+}
+
+/// Every module's queue handlers, published as a resource before core modules start
+#[derive(Debug, Clone, Default)]
+pub struct ModuleQueueHandlers {
+    pub handlers: Vec<(&'static str, QueueHandler)>,
+}
+
+/// Whether a module was registered as core or custom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleKind {
+    Core,
+    Custom,
+}
+
+impl std::fmt::Display for ModuleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Core => "core",
+            Self::Custom => "custom",
+        })
+    }
+}
+
+/// Why a module was not registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Switched off in the `[modules]` settings
+    Disabled,
+    /// `Module::environments` does not include the current environment
+    OtherEnvironment,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Disabled => "disabled",
+            Self::OtherEnvironment => "other env",
+        })
+    }
+}
+
+/// A module whose registration was skipped, kept so tooling can still list it
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedModule {
+    pub info: ModuleInfo,
+    pub reason: SkipReason,
+}
+
+/// Lifecycle state of a registered module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleState {
+    Registered,
+    Initialized,
+    Started,
+    Stopped,
+}
+
+impl std::fmt::Display for ModuleState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Registered => "registered",
+            Self::Initialized => "initialized",
+            Self::Started => "started",
+            Self::Stopped => "stopped",
+        })
+    }
+}
+
+/// Descriptive metadata of a registered module, for introspection and documentation
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleInfo {
+    pub name: &'static str,
+    pub kind: ModuleKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<&'static str>,
+    pub depends_on: Vec<&'static str>,
+}
+
+impl ModuleInfo {
+    /// Collect the metadata a module reports about itself
+    pub fn of(module: &dyn Module, kind: ModuleKind) -> Self {
+        Self {
+            name: module.name(),
+            kind,
+            version: module.version(),
+            description: module.description(),
+            author: module.author(),
+            depends_on: module.depends_on().to_vec(),
+        }
+    }
+}
+
+/// Module submitted for automatic registration with [`register_module!`](crate::register_module)
+pub struct ModuleRegistration {
+    pub kind: ModuleKind,
+    /// Core module priority; ignored for custom modules
+    pub priority: i32,
+    pub create: fn() -> Arc<dyn Module>,
+}
+
+impl ModuleRegistration {
+    /// Submit a custom module
+    pub const fn custom(create: fn() -> Arc<dyn Module>) -> Self {
+        Self {
+            kind: ModuleKind::Custom,
+            priority: crate::registry::priority::DEFAULT,
+            create,
+        }
+    }
+
+    /// Submit a core module running at `priority`
+    pub const fn core(create: fn() -> Arc<dyn Module>, priority: i32) -> Self {
+        Self {
+            kind: ModuleKind::Core,
+            priority,
+            create,
+        }
+    }
+}
+
+inventory::collect!(ModuleRegistration);
+
+/// Register a module at link time instead of listing it in `register_all`
+///
+/// `ModuleRegistry::register_submitted` picks up every submitted module:
+///
+/// ```ignore
+/// atlas_kernel::register_module!(create_module);
+/// atlas_kernel::register_module!(core, create_db_module, priority = atlas_kernel::registry::priority::DB);
+/// ```
+#[macro_export]
+macro_rules! register_module {
+    (core, $create:expr, priority = $priority:expr) => {
+        $crate::inventory::submit! {
+            $crate::module::ModuleRegistration::core($create, $priority)
+        }
+    };
+    ($create:expr) => {
+        $crate::inventory::submit! {
+            $crate::module::ModuleRegistration::custom($create)
+        }
+    };
 }
 
 /// Core module trait that all ATLAS modules must implement
@@ -22,12 +510,45 @@ pub trait Module: Sync + Send {
     /// Unique name for this module
     fn name(&self) -> &'static str;
 
+    /// Version of this module, typically `env!("CARGO_PKG_VERSION")`
+    fn version(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// One-line summary of what this module provides
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Team or person maintaining this module
+    fn author(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Names of modules that must be initialized and started before this one
+    /// The registry orders lifecycle phases so dependencies come first and stop last
+    fn depends_on(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Environments (`local`, `staging`, `production`, or custom names) this module runs in
+    /// Empty means every environment; the registry skips the module elsewhere
+    fn environments(&self) -> &[&'static str] {
+        &[]
+    }
+
     /// Initialize the module with the provided context
     /// Called during application startup before migrations
-    async fn init(&self, _ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    async fn init(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
         Ok(())
     }
 
+    /// How the registry retries a failed `init` before giving up
+    /// Override for modules whose backing services may come up after the application
+    fn init_retry(&self) -> RetryPolicy {
+        RetryPolicy::none()
+    }
+
     /// Return the Axum router for this module's routes
     /// Routes will be mounted under `/api/{module_name}`
     fn routes(&self) -> Router {
@@ -40,18 +561,90 @@ pub trait Module: Sync + Send {
         None
     }
 
+    /// Return example payloads for this module's OpenAPI schemas
+    /// Each example is attached to the schema it names before fragments are merged
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![]
+    }
+
+    /// Return routes this module has deprecated
+    /// Deprecated operations are flagged in the OpenAPI spec and answer with
+    /// `Deprecation`/`Sunset` headers
+    fn deprecations(&self) -> Vec<RouteDeprecation> {
+        vec![]
+    }
+
+    /// Return the guards in front of this module's routes
+    /// The server authenticates requests to secured routes with the authenticators
+    /// registered for their schemes, and the OpenAPI spec documents the same requirements
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![]
+    }
+
+    /// Return the named events this module emits, with their payload schemas
+    /// Emitted payloads are validated against them, and `atlas events catalog` lists them
+    fn event_schemas(&self) -> Vec<EventSchema> {
+        vec![]
+    }
+
+    /// Return the named events this module reacts to
+    /// The events module subscribes the handlers when it starts and cancels them when it
+    /// stops, so modules need not spawn listener tasks themselves
+    fn event_handlers(&self) -> Vec<EventHandler> {
+        vec![]
+    }
+
+    /// Return the cache keys each of the named events makes stale
+    /// The events module drops them from the registered cache when the event is emitted,
+    /// so modules need not invalidate by hand after every write
+    fn cache_invalidations(&self) -> Vec<CacheInvalidation> {
+        vec![]
+    }
+
+    /// Return the jobs this module runs on a schedule
+    /// The scheduler module runs each job at the times its cron expression matches and
+    /// skips a run while the previous one is still going
+    fn jobs(&self) -> Vec<Job> {
+        vec![]
+    }
+
+    /// Return the kinds of queued jobs this module runs
+    /// The queue module's workers pick enqueued jobs up and call the handler for their kind
+    fn queue_handlers(&self) -> Vec<QueueHandler> {
+        vec![]
+    }
+
     /// Return migrations contributed by this module
     /// Migrations are executed in the order returned
     fn migrations(&self) -> Vec<Migration> {
         vec![]
     }
 
+    /// Return the data `atlas seed` loads for this module
+    /// Seeds run in the order returned, after the module's migrations
+    fn seeds(&self) -> Vec<Seed> {
+        vec![]
+    }
+
     /// Start background tasks for this module
     /// Called after migrations are complete
-    async fn start(&self, _ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
         Ok(())
     }
 
+    /// React to a configuration reload
+    /// Only reloadable values (log level, rate limits, ...) change here; settings
+    /// that need a restart, such as the server port, are rejected before this is called
+    async fn on_config_change(&self, _settings: &Settings) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Report the module's current health
+    /// Aggregated by `ModuleRegistry::health_report` for readiness checks and diagnostics
+    async fn health(&self) -> crate::health::ModuleHealth {
+        crate::health::ModuleHealth::healthy()
+    }
+
     /// Stop the module and clean up resources
     /// Called during application shutdown
     async fn stop(&self) -> anyhow::Result<()> {
@@ -0,0 +1,132 @@
+//! CIDR ranges for address-based access rules
+//!
+//! IPv4-mapped IPv6 addresses (`::ffff:10.0.0.1`) are compared as the IPv4 address they
+//! carry, so dual-stack listeners match IPv4 ranges.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Reasons a CIDR range is rejected
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IpNetError {
+    #[error("'{0}' is not an IP address or CIDR range")]
+    InvalidAddress(String),
+    #[error("prefix length of '{range}' must be at most {max}")]
+    InvalidPrefix { range: String, max: u8 },
+}
+
+/// An address range such as `10.0.0.0/8` or `2001:db8::/32`; a bare address is a single host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Whether `ip` lies within the range
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = IpNetError;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let invalid = || IpNetError::InvalidAddress(range.to_string());
+        let (addr, prefix) = match range.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (range.trim(), None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| invalid())?
+            .to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(IpNetError::InvalidPrefix {
+                range: range.to_string(),
+                max,
+            });
+        }
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ranges_contain_their_addresses() {
+        let office: IpNet = "10.20.0.0/16".parse().unwrap();
+        assert!(office.contains(ip("10.20.3.4")));
+        assert!(office.contains(ip("::ffff:10.20.3.4")));
+        assert!(!office.contains(ip("10.21.0.1")));
+        assert!(!office.contains(ip("2001:db8::1")));
+
+        let host: IpNet = "192.0.2.7".parse().unwrap();
+        assert_eq!(host.prefix(), 32);
+        assert!(host.contains(ip("192.0.2.7")));
+        assert!(!host.contains(ip("192.0.2.8")));
+
+        let everything: IpNet = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains(ip("203.0.113.9")));
+
+        let vpn: IpNet = "2001:db8::/32".parse().unwrap();
+        assert!(vpn.contains(ip("2001:db8:ffff::1")));
+        assert!(!vpn.contains(ip("2001:db9::1")));
+        assert_eq!(vpn.to_string(), "2001:db8::/32");
+    }
+
+    #[test]
+    fn test_invalid_ranges_are_rejected() {
+        assert_eq!(
+            "10.0.0.0/33".parse::<IpNet>(),
+            Err(IpNetError::InvalidPrefix {
+                range: "10.0.0.0/33".to_string(),
+                max: 32
+            })
+        );
+        assert!(matches!(
+            "office".parse::<IpNet>(),
+            Err(IpNetError::InvalidAddress(_))
+        ));
+        assert!("10.0.0.0/x".parse::<IpNet>().is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Runtime-loaded plugin modules
+//!
+//! A plugin is a `cdylib` crate that depends on `atlas-kernel` and exports its
+//! modules with [`export_plugin!`](crate::export_plugin):
+//!
+//! ```ignore
+//! fn register(registrar: &mut atlas_kernel::plugin::PluginRegistrar) {
+//!     registrar.register(std::sync::Arc::new(PaymentsModule::default()));
+//! }
+//!
+//! atlas_kernel::export_plugin!(register);
+//! ```
+//!
+//! Modules cross the library boundary as Rust trait objects, so a plugin must be
+//! built with the same compiler and `atlas-kernel` version as the host; both are
+//! checked before a plugin is registered. WASM components are not supported yet.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+
+use crate::module::Module;
+use crate::registry::ModuleRegistry;
+
+/// Version of the plugin declaration layout; bumped on incompatible changes
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// Compiler the host was built with
+pub const RUSTC_VERSION: &str = env!("ATLAS_RUSTC_VERSION");
+/// `atlas-kernel` version the host was built with
+pub const KERNEL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Name of the static every plugin exports through `export_plugin!`
+const DECLARATION_SYMBOL: &str = "ATLAS_PLUGIN_DECLARATION";
+
+/// Entry point a plugin exports so the host can check compatibility and collect its modules
+///
+/// The layout is fixed with the ABI version first and the version strings next, so a host
+/// can read and reject a declaration from an incompatible build before touching the rest.
+/// Fields are only ever appended after a `PLUGIN_ABI_VERSION` bump.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub rustc_version: &'static str,
+    pub kernel_version: &'static str,
+    pub register: fn(&mut PluginRegistrar),
+}
+
+/// Collects the modules a plugin provides
+#[derive(Default)]
+pub struct PluginRegistrar {
+    modules: Vec<Arc<dyn Module>>,
+}
+
+impl PluginRegistrar {
+    /// Add a module; it is registered as a custom module
+    pub fn register(&mut self, module: Arc<dyn Module>) {
+        self.modules.push(module);
+    }
+}
+
+/// Export a plugin's registration function
+///
+/// Expands to the declaration static the host looks up after loading the library.
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:expr) => {
+        #[doc(hidden)]
+        #[no_mangle]
+        pub static ATLAS_PLUGIN_DECLARATION: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                abi_version: $crate::plugin::PLUGIN_ABI_VERSION,
+                rustc_version: $crate::plugin::RUSTC_VERSION,
+                kernel_version: $crate::plugin::KERNEL_VERSION,
+                register: $register,
+            };
+    };
+}
+
+/// Reject plugins built against a different ABI, compiler, or kernel
+fn check_compatible(path: &Path, declaration: &PluginDeclaration) -> anyhow::Result<()> {
+    if declaration.abi_version != PLUGIN_ABI_VERSION {
+        bail!(
+            "plugin {} uses ABI version {}, host expects {}",
+            path.display(),
+            declaration.abi_version,
+            PLUGIN_ABI_VERSION
+        );
+    }
+    if declaration.rustc_version != RUSTC_VERSION {
+        bail!(
+            "plugin {} was built with '{}', host was built with '{}'",
+            path.display(),
+            declaration.rustc_version,
+            RUSTC_VERSION
+        );
+    }
+    if declaration.kernel_version != KERNEL_VERSION {
+        bail!(
+            "plugin {} targets atlas-kernel {}, host runs {}",
+            path.display(),
+            declaration.kernel_version,
+            KERNEL_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Discovers and loads plugin libraries from a directory
+pub struct PluginLoader {
+    dir: PathBuf,
+}
+
+impl PluginLoader {
+    /// Create a loader for the given plugins directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Shared libraries in the plugins directory, sorted by file name
+    ///
+    /// A missing directory yields no plugins.
+    pub fn discover(&self) -> anyhow::Result<Vec<PathBuf>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let entries = std::fs::read_dir(&self.dir)
+            .with_context(|| format!("failed to read plugins directory {}", self.dir.display()))?;
+        let mut libraries: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().and_then(|ext| ext.to_str())
+                        == Some(std::env::consts::DLL_EXTENSION)
+            })
+            .collect();
+        libraries.sort();
+        Ok(libraries)
+    }
+
+    /// Load one plugin library and return the modules it registers
+    ///
+    /// The library stays loaded for the rest of the process so its modules remain valid.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initializers and trusts its exported declaration;
+    /// only load plugins from trusted sources.
+    pub unsafe fn load(&self, path: &Path) -> anyhow::Result<Vec<Arc<dyn Module>>> {
+        let declaration = sys::load_declaration(path, DECLARATION_SYMBOL)?;
+        check_compatible(path, declaration)?;
+
+        let mut registrar = PluginRegistrar::default();
+        (declaration.register)(&mut registrar);
+        Ok(registrar.modules)
+    }
+
+    /// Load every discovered plugin and register its modules as custom modules
+    ///
+    /// Returns the number of modules registered.
+    ///
+    /// # Safety
+    ///
+    /// See [`PluginLoader::load`].
+    pub unsafe fn register_all(&self, registry: &mut ModuleRegistry) -> anyhow::Result<usize> {
+        let mut registered = 0;
+        for path in self.discover()? {
+            let modules = self.load(&path)?;
+            tracing::info!(
+                plugin = %path.display(),
+                modules = modules.len(),
+                "loaded plugin"
+            );
+            for module in modules {
+                registry
+                    .register_custom(module)
+                    .with_context(|| format!("failed to register plugin {}", path.display()))?;
+                registered += 1;
+            }
+        }
+        Ok(registered)
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use anyhow::{anyhow, Context};
+
+    use super::PluginDeclaration;
+
+    fn last_error() -> String {
+        // SAFETY: dlerror returns null or a valid C string owned by the loader.
+        let message = unsafe { libc::dlerror() };
+        if message.is_null() {
+            "unknown error".to_string()
+        } else {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    /// Open the library (never closed) and return its exported plugin declaration
+    pub(super) unsafe fn load_declaration(
+        path: &Path,
+        symbol: &str,
+    ) -> anyhow::Result<&'static PluginDeclaration> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .with_context(|| format!("invalid plugin path {}", path.display()))?;
+        let handle = libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            return Err(anyhow!(
+                "failed to load plugin {}: {}",
+                path.display(),
+                last_error()
+            ));
+        }
+
+        let c_symbol = CString::new(symbol).expect("symbol name contains no NUL bytes");
+        let declaration = libc::dlsym(handle, c_symbol.as_ptr());
+        if declaration.is_null() {
+            libc::dlclose(handle);
+            return Err(anyhow!(
+                "{} is not an ATLAS plugin: missing {} (use atlas_kernel::export_plugin!)",
+                path.display(),
+                symbol
+            ));
+        }
+
+        Ok(&*(declaration as *const PluginDeclaration))
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::path::Path;
+
+    use super::PluginDeclaration;
+
+    pub(super) unsafe fn load_declaration(
+        path: &Path,
+        _symbol: &str,
+    ) -> anyhow::Result<&'static PluginDeclaration> {
+        anyhow::bail!(
+            "cannot load plugin {}: dynamic plugins are only supported on unix",
+            path.display()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declaration(kernel_version: &'static str) -> PluginDeclaration {
+        PluginDeclaration {
+            abi_version: PLUGIN_ABI_VERSION,
+            rustc_version: RUSTC_VERSION,
+            kernel_version,
+            register: |_| {},
+        }
+    }
+
+    #[test]
+    fn test_compatibility_requires_matching_versions() {
+        let path = Path::new("libpayments.so");
+        assert!(check_compatible(path, &declaration(KERNEL_VERSION)).is_ok());
+
+        let error = check_compatible(path, &declaration("0.0.1")).unwrap_err();
+        assert!(error.to_string().contains("targets atlas-kernel 0.0.1"));
+    }
+
+    #[test]
+    fn test_discover_lists_shared_libraries_only() {
+        let dir = std::env::temp_dir().join(format!("atlas-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let library = dir.join(format!("libpayments.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&library, b"not really a library").unwrap();
+        std::fs::write(dir.join("README.md"), b"docs").unwrap();
+
+        let loader = PluginLoader::new(&dir);
+        let discovered = loader.discover().unwrap();
+        let load_error = unsafe { loader.load(&library) }.err();
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(discovered, vec![library]);
+        assert!(load_error.is_some());
+        assert!(PluginLoader::new(dir.join("missing"))
+            .discover()
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,22 +1,57 @@
-use anyhow::Context;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::module::{InitCtx, Module};
+use crate::clock::{Clock, SystemClock};
+use crate::context::AppContext;
+use crate::error::{KernelError, LifecyclePhase};
+use crate::health::{HealthReport, ModuleHealthEntry};
+use crate::lifecycle::{LifecycleEvent, LifecycleListener};
+use crate::module::{
+    CacheInvalidation, EventHandler, EventSchema, InitCtx, Job, Module, ModuleCacheInvalidations,
+    ModuleEventHandlers, ModuleEventSchemas, ModuleInfo, ModuleJobs, ModuleKind,
+    ModuleQueueHandlers, ModuleRegistration, ModuleState, QueueHandler, SkipReason, SkippedModule,
+};
+use crate::resources::Resources;
+use crate::settings::{Environment, ModulesSettings, Settings};
+use crate::shutdown::ShutdownCoordinator;
+use crate::startup::{ModuleTiming, StartupBudget, StartupReport};
+use crate::tasks::TaskSupervisor;
 
-/// Core module initialization order (excluding HTTP server)
-const CORE_MODULE_ORDER: &[&str] = &[
-    "kernel",    // Kernel must be first
-    "telemetry", // Telemetry for logging
-    "db",        // Database connection
-    "authz",     // Authorization
-    "events",    // Event bus
-                 // Note: HTTP server is started separately after all modules are initialized
-];
+/// Well-known core module priorities; lower values initialize first
+///
+/// The HTTP server is not a module: it starts separately after all modules are initialized.
+pub mod priority {
+    /// Kernel must be first
+    pub const KERNEL: i32 = 0;
+    /// Telemetry for logging
+    pub const TELEMETRY: i32 = 100;
+    /// Database connection
+    pub const DB: i32 = 200;
+    /// Authorization
+    pub const AUTHZ: i32 = 300;
+    /// Event bus
+    pub const EVENTS: i32 = 400;
+    /// Priority used by `register_core` for modules without a specific slot
+    pub const DEFAULT: i32 = 1000;
+}
 
 /// Module registry for managing module lifecycle with core/custom separation
 pub struct ModuleRegistry {
     core_modules: Vec<Arc<dyn Module>>,
+    core_priorities: HashMap<&'static str, i32>,
     custom_modules: Vec<Arc<dyn Module>>,
+    skipped: Vec<SkippedModule>,
+    resources: Arc<Resources>,
+    module_settings: ModulesSettings,
+    environment: Environment,
+    states: Mutex<HashMap<&'static str, ModuleState>>,
+    tasks: TaskSupervisor,
+    startup_budget: StartupBudget,
+    shutdown: ShutdownCoordinator,
+    timings: Mutex<Vec<ModuleTiming>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl ModuleRegistry {
@@ -24,18 +59,355 @@ impl ModuleRegistry {
     pub fn new() -> Self {
         Self {
             core_modules: Vec::new(),
+            core_priorities: HashMap::new(),
             custom_modules: Vec::new(),
+            skipped: Vec::new(),
+            resources: Arc::new(Resources::new()),
+            module_settings: ModulesSettings::default(),
+            environment: Environment::default(),
+            states: Mutex::new(HashMap::new()),
+            tasks: TaskSupervisor::new(),
+            startup_budget: StartupBudget::default(),
+            shutdown: ShutdownCoordinator::default(),
+            timings: Mutex::new(Vec::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Create a registry for the configured environment that skips modules switched off
+    /// in the `[modules]` settings
+    pub fn with_settings(settings: &Settings) -> Self {
+        Self {
+            module_settings: settings.modules.clone(),
+            environment: settings.environment.clone(),
+            startup_budget: StartupBudget::from_settings(&settings.startup),
+            shutdown: ShutdownCoordinator::from_settings(&settings.shutdown),
+            ..Self::new()
         }
     }
 
-    /// Register a core module with the registry
-    pub fn register_core(&mut self, module: Arc<dyn Module>) {
+    /// Whether registration of `module` should be skipped, because settings disable it
+    /// or it is not meant for the current environment
+    ///
+    /// Skipped modules are remembered for `skipped_modules`.
+    fn is_skipped(&mut self, module: &dyn Module, kind: ModuleKind) -> bool {
+        let name = module.name();
+        let environments = module.environments();
+        let reason = if !self.module_settings.is_enabled(name) {
+            tracing::info!(
+                module = name,
+                "module disabled by settings; skipping registration"
+            );
+            SkipReason::Disabled
+        } else if !environments.is_empty() && !environments.contains(&self.environment.as_str()) {
+            tracing::info!(
+                module = name,
+                environment = %self.environment,
+                "module not registered in this environment"
+            );
+            SkipReason::OtherEnvironment
+        } else {
+            return false;
+        };
+        self.skipped.push(SkippedModule {
+            info: ModuleInfo::of(module, kind),
+            reason,
+        });
+        true
+    }
+
+    /// Register a core module with the registry at `priority::DEFAULT`
+    ///
+    /// Modules disabled in settings or limited to other environments are skipped. Fails
+    /// if a module with the same name is already registered or if the module's
+    /// dependencies would form a cycle with registered modules.
+    pub fn register_core(&mut self, module: Arc<dyn Module>) -> Result<(), KernelError> {
+        self.register_core_with_priority(module, priority::DEFAULT)
+    }
+
+    /// Register a core module that runs before core modules with a higher `priority`
+    ///
+    /// Declared dependencies still take precedence over priorities; modules with
+    /// equal priority keep their registration order.
+    pub fn register_core_with_priority(
+        &mut self,
+        module: Arc<dyn Module>,
+        priority: i32,
+    ) -> Result<(), KernelError> {
+        let name = module.name();
+        if self.is_skipped(module.as_ref(), ModuleKind::Core) {
+            return Ok(());
+        }
+        self.ensure_unique_name(name)?;
         self.core_modules.push(module);
+        if let Err(error) = self.check_dependency_cycles() {
+            self.core_modules.pop();
+            return Err(error);
+        }
+        self.core_priorities.insert(name, priority);
+        Ok(())
     }
 
     /// Register a custom module with the registry
-    pub fn register_custom(&mut self, module: Arc<dyn Module>) {
+    ///
+    /// Modules disabled in settings or limited to other environments are skipped. Fails
+    /// if a module with the same name is already registered or if the module's
+    /// dependencies would form a cycle with registered modules.
+    pub fn register_custom(&mut self, module: Arc<dyn Module>) -> Result<(), KernelError> {
+        if self.is_skipped(module.as_ref(), ModuleKind::Custom) {
+            return Ok(());
+        }
+        self.ensure_unique_name(module.name())?;
         self.custom_modules.push(module);
+        if let Err(error) = self.check_dependency_cycles() {
+            self.custom_modules.pop();
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Register every module submitted with `register_module!`
+    ///
+    /// Core modules are registered before custom modules; within each kind, modules are
+    /// registered by priority and then name so the result does not depend on link order.
+    pub fn register_submitted(&mut self) -> Result<(), KernelError> {
+        let mut submitted: Vec<(&ModuleRegistration, Arc<dyn Module>)> =
+            inventory::iter::<ModuleRegistration>
+                .into_iter()
+                .map(|registration| (registration, (registration.create)()))
+                .collect();
+        submitted.sort_by_key(|(registration, module)| {
+            (
+                registration.kind == ModuleKind::Custom,
+                registration.priority,
+                module.name(),
+            )
+        });
+
+        for (registration, module) in submitted {
+            match registration.kind {
+                ModuleKind::Core => {
+                    self.register_core_with_priority(module, registration.priority)?
+                }
+                ModuleKind::Custom => self.register_custom(module)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject a second module with the same name; names key routes, OpenAPI paths, and migrations
+    fn ensure_unique_name(&self, name: &str) -> Result<(), KernelError> {
+        if self.get_module(name).is_some() {
+            return Err(KernelError::DuplicateModule {
+                module: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Detect dependency cycles among the modules registered so far
+    ///
+    /// Dependencies on modules that are not registered yet are ignored here and
+    /// reported when the lifecycle order is computed.
+    fn check_dependency_cycles(&self) -> Result<(), KernelError> {
+        let graph: HashMap<&str, &[&'static str]> = self
+            .modules()
+            .into_iter()
+            .map(|module| (module.name(), module.depends_on()))
+            .collect();
+
+        fn visit<'a>(
+            name: &'a str,
+            graph: &HashMap<&'a str, &'a [&'static str]>,
+            visited: &mut HashSet<&'a str>,
+            path: &mut Vec<&'a str>,
+        ) -> Result<(), KernelError> {
+            if let Some(position) = path.iter().position(|&entry| entry == name) {
+                let mut cycle: Vec<String> = path[position..]
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect();
+                cycle.push(name.to_string());
+                return Err(KernelError::DependencyCycle { cycle });
+            }
+            if !visited.insert(name) {
+                return Ok(());
+            }
+
+            path.push(name);
+            for &dependency in graph.get(name).copied().unwrap_or_default() {
+                if graph.contains_key(dependency) {
+                    visit(dependency, graph, visited, path)?;
+                }
+            }
+            path.pop();
+            Ok(())
+        }
+
+        let mut visited = HashSet::new();
+        for module in self.modules() {
+            visit(module.name(), &graph, &mut visited, &mut Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Order `modules` so every module comes after its dependencies
+    ///
+    /// `modules` must already be sorted by preference; among modules whose
+    /// dependencies are satisfied the earliest one is picked, keeping the order
+    /// stable. Dependencies listed in `satisfied` are treated as already met.
+    fn topological_order<'a>(
+        modules: Vec<&'a Arc<dyn Module>>,
+        satisfied: &HashSet<&str>,
+    ) -> Result<Vec<&'a Arc<dyn Module>>, KernelError> {
+        let names: HashSet<&str> = modules.iter().map(|module| module.name()).collect();
+        for module in &modules {
+            for &dependency in module.depends_on() {
+                if !names.contains(dependency) && !satisfied.contains(dependency) {
+                    return Err(KernelError::MissingDependency {
+                        module: module.name().to_string(),
+                        dependency: dependency.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut ordered: Vec<&'a Arc<dyn Module>> = Vec::with_capacity(modules.len());
+        let mut placed: HashSet<&str> = HashSet::new();
+        let mut pending = modules;
+        while !pending.is_empty() {
+            let ready = pending
+                .iter()
+                .position(|module| {
+                    module.depends_on().iter().all(|dependency| {
+                        placed.contains(dependency) || satisfied.contains(dependency)
+                    })
+                })
+                .ok_or_else(|| KernelError::DependencyCycle {
+                    cycle: pending
+                        .iter()
+                        .map(|module| module.name().to_string())
+                        .collect(),
+                })?;
+            let module = pending.remove(ready);
+            placed.insert(module.name());
+            ordered.push(module);
+        }
+
+        Ok(ordered)
+    }
+
+    /// Core modules in lifecycle order: dependencies first, then priority
+    pub fn core_order(&self) -> Result<Vec<&Arc<dyn Module>>, KernelError> {
+        let mut modules: Vec<&Arc<dyn Module>> = self.core_modules.iter().collect();
+        modules.sort_by_key(|module| {
+            self.core_priorities
+                .get(module.name())
+                .copied()
+                .unwrap_or(priority::DEFAULT)
+        });
+
+        Self::topological_order(modules, &HashSet::new())
+    }
+
+    /// Custom modules in lifecycle order: dependencies first, then registration order
+    ///
+    /// Custom modules may depend on core modules, which always run first.
+    pub fn custom_order(&self) -> Result<Vec<&Arc<dyn Module>>, KernelError> {
+        let core_names: HashSet<&str> = self
+            .core_modules
+            .iter()
+            .map(|module| module.name())
+            .collect();
+
+        Self::topological_order(self.custom_modules.iter().collect(), &core_names)
+    }
+
+    /// Shared resources that modules publish and consume during their lifecycle
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Build the context handed to route handlers, sharing this registry's resources
+    pub fn app_context(&self, settings: Arc<Settings>) -> AppContext {
+        AppContext::new(settings, self.resources.clone()).with_clock(self.clock.clone())
+    }
+
+    /// Build an init context that shares this registry's resources
+    pub fn init_ctx(&self, settings: Arc<Settings>) -> InitCtx {
+        InitCtx::with_tasks(settings, self.resources.clone(), self.tasks.clone())
+            .with_clock(self.clock.clone())
+    }
+
+    /// Background tasks spawned by modules through their init context
+    pub fn tasks(&self) -> &TaskSupervisor {
+        &self.tasks
+    }
+
+    /// Pass a lifecycle event to the registered `Arc<dyn LifecycleListener>`, if any
+    ///
+    /// The registry announces modules starting and failing itself; whatever applies
+    /// migrations announces `MigrationApplied`.
+    pub fn announce(&self, event: LifecycleEvent) {
+        tracing::debug!(event = event.name(), "lifecycle event");
+        if let Some(listener) = self.resources.get::<Arc<dyn LifecycleListener>>() {
+            listener.on_lifecycle(&event);
+        }
+    }
+
+    /// Announce that a module failed in `phase` and wrap the error
+    fn failed(
+        &self,
+        module: &'static str,
+        kind: ModuleKind,
+        phase: LifecyclePhase,
+        error: anyhow::Error,
+    ) -> KernelError {
+        self.announce(LifecycleEvent::ModuleFailed {
+            module,
+            kind,
+            phase,
+            error: format!("{:#}", error),
+        });
+        KernelError::lifecycle(module, kind, phase, error)
+    }
+
+    /// Abort the background tasks of a module that has stopped and wait up to `timeout`
+    /// for them to end
+    pub(crate) async fn cancel_tasks(
+        &self,
+        name: &'static str,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let (cancelled, unfinished) = self.tasks.cancel_and_join(name, timeout).await;
+        if cancelled > 0 {
+            tracing::info!(
+                module = name,
+                cancelled,
+                "cancelled module background tasks"
+            );
+        }
+        if unfinished > 0 {
+            anyhow::bail!(
+                "{} background task(s) still running {}ms after being cancelled",
+                unfinished,
+                timeout.as_millis()
+            );
+        }
+        Ok(())
+    }
+
+    /// Run a module's `stop`, then cancel its tasks and wait for them within what is
+    /// left of its shutdown budget
+    async fn stop_module(&self, module: &Arc<dyn Module>) -> anyhow::Result<()> {
+        let started = Instant::now();
+        let stopped = module.stop().await;
+        let remaining = self
+            .shutdown
+            .timeout_for(module.name())
+            .saturating_sub(started.elapsed());
+        let joined = self.cancel_tasks(module.name(), remaining).await;
+        stopped.and(joined)
     }
 
     /// Get all registered modules (core + custom)
@@ -46,6 +418,25 @@ impl ModuleRegistry {
         all_modules
     }
 
+    /// Metadata of every registered module (core first, then custom, in registration order)
+    pub fn module_info(&self) -> Vec<ModuleInfo> {
+        let core = self
+            .core_modules
+            .iter()
+            .map(|module| ModuleInfo::of(module.as_ref(), ModuleKind::Core));
+        let custom = self
+            .custom_modules
+            .iter()
+            .map(|module| ModuleInfo::of(module.as_ref(), ModuleKind::Custom));
+        core.chain(custom).collect()
+    }
+
+    /// Modules left out because settings disable them or they are meant for other
+    /// environments, in the order they were offered
+    pub fn skipped_modules(&self) -> &[SkippedModule] {
+        &self.skipped
+    }
+
     /// Get a module by name (searches both core and custom modules)
     pub fn get_module(&self, name: &str) -> Option<&Arc<dyn Module>> {
         self.core_modules
@@ -68,110 +459,493 @@ impl ModuleRegistry {
         self.custom_modules.len()
     }
 
-    /// Initialize core modules in the correct order
-    pub async fn init_core_modules(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    /// Current lifecycle state of a registered module
+    pub fn module_state(&self, name: &str) -> Option<ModuleState> {
+        let module = self.get_module(name)?;
+        Some(self.state_of(module.name()))
+    }
+
+    /// Whether the module named `name` was registered as core or custom
+    pub(crate) fn module_kind(&self, name: &str) -> ModuleKind {
+        if self.core_modules.iter().any(|module| module.name() == name) {
+            ModuleKind::Core
+        } else {
+            ModuleKind::Custom
+        }
+    }
+
+    fn state_of(&self, name: &str) -> ModuleState {
+        self.states
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .copied()
+            .unwrap_or(ModuleState::Registered)
+    }
+
+    pub(crate) fn set_state(&self, name: &'static str, state: ModuleState) {
+        self.states
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(name, state);
+    }
+
+    /// Refuse to run `phase` on a module whose state does not allow it
+    fn check_transition(&self, name: &str, phase: LifecyclePhase) -> Result<(), KernelError> {
+        let state = self.state_of(name);
+        if phase.allowed_from(state) {
+            Ok(())
+        } else {
+            Err(KernelError::InvalidState {
+                module: name.to_string(),
+                phase,
+                state,
+            })
+        }
+    }
+
+    /// Hand modules and handlers `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Replace the per-module init/start budgets taken from settings
+    pub fn with_startup_budget(mut self, budget: StartupBudget) -> Self {
+        self.startup_budget = budget;
+        self
+    }
+
+    /// Replace the per-module stop budgets taken from settings
+    pub fn with_shutdown(mut self, shutdown: ShutdownCoordinator) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// How long each module spent in `init` and `start`, in the order they ran
+    pub fn startup_report(&self) -> StartupReport {
+        StartupReport {
+            modules: self
+                .timings
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+        }
+    }
+
+    /// Await a lifecycle phase, recording its duration and warning when it runs over budget
+    async fn timed<T>(
+        &self,
+        name: &'static str,
+        phase: LifecyclePhase,
+        future: impl Future<Output = T>,
+    ) -> T {
+        let started = Instant::now();
+        let output = future.await;
+        self.record_timing(name, phase, started.elapsed());
+        output
+    }
+
+    fn record_timing(&self, name: &'static str, phase: LifecyclePhase, elapsed: Duration) {
+        let budget = self.startup_budget.budget_for(name);
+        let elapsed_ms = elapsed.as_millis() as u64;
+        if elapsed > budget {
+            tracing::warn!(
+                module = name,
+                elapsed_ms,
+                budget_ms = budget.as_millis() as u64,
+                "module took longer than its budget to {}",
+                phase
+            );
+        }
+
+        let mut timings = self
+            .timings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let index = match timings.iter().position(|timing| timing.module == name) {
+            Some(index) => index,
+            None => {
+                timings.push(ModuleTiming {
+                    module: name,
+                    init_ms: None,
+                    start_ms: None,
+                    budget_ms: budget.as_millis() as u64,
+                });
+                timings.len() - 1
+            }
+        };
+        match phase {
+            LifecyclePhase::Init => timings[index].init_ms = Some(elapsed_ms),
+            LifecyclePhase::Start => timings[index].start_ms = Some(elapsed_ms),
+            LifecyclePhase::Stop | LifecyclePhase::ConfigChange => {}
+        }
+    }
+
+    /// Run a module's `init`, retrying with backoff as its `init_retry` policy allows
+    async fn init_with_retry(&self, module: &dyn Module, ctx: &InitCtx) -> anyhow::Result<()> {
+        let policy = module.init_retry();
+        let mut attempt = 1;
+        loop {
+            match module.init(ctx).await {
+                Ok(()) => return Ok(()),
+                Err(error) if attempt < policy.max_attempts => {
+                    let backoff = policy.backoff(attempt);
+                    tracing::warn!(
+                        module = module.name(),
+                        attempt,
+                        max_attempts = policy.max_attempts,
+                        backoff_ms = backoff.as_millis() as u64,
+                        error = %format!("{:#}", error),
+                        "module failed to initialize; retrying"
+                    );
+                    ctx.clock.sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(error) if attempt > 1 => {
+                    return Err(error.context(format!("gave up after {} attempts", attempt)));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Initialize core modules in dependency order
+    pub async fn init_core_modules(&self, ctx: &InitCtx) -> Result<(), KernelError> {
+        let order = self.core_order()?;
         tracing::info!(
             "initializing core modules in order: {:?}",
-            CORE_MODULE_ORDER
+            order.iter().map(|module| module.name()).collect::<Vec<_>>()
         );
 
-        for &module_name in CORE_MODULE_ORDER {
-            if let Some(module) = self.core_modules.iter().find(|m| m.name() == module_name) {
-                tracing::info!(module = module.name(), "initializing core module");
+        for module in order {
+            tracing::info!(module = module.name(), "initializing core module");
 
-                module.init(ctx).await.with_context(|| {
-                    format!("failed to initialize core module '{}'", module.name())
-                })?;
-            }
+            self.check_transition(module.name(), LifecyclePhase::Init)?;
+            self.timed(
+                module.name(),
+                LifecyclePhase::Init,
+                self.init_with_retry(module.as_ref(), &ctx.scoped(module.name())),
+            )
+            .await
+            .map_err(|error| {
+                self.failed(module.name(), ModuleKind::Core, LifecyclePhase::Init, error)
+            })?;
+            self.set_state(module.name(), ModuleState::Initialized);
         }
 
         Ok(())
     }
 
-    /// Initialize custom modules
-    pub async fn init_custom_modules(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    /// Initialize custom modules in dependency order
+    pub async fn init_custom_modules(&self, ctx: &InitCtx) -> Result<(), KernelError> {
         tracing::info!("initializing {} custom modules", self.custom_modules.len());
 
-        for module in &self.custom_modules {
+        for module in self.custom_order()? {
             tracing::info!(module = module.name(), "initializing custom module");
 
-            module.init(ctx).await.with_context(|| {
-                format!("failed to initialize custom module '{}'", module.name())
+            self.check_transition(module.name(), LifecyclePhase::Init)?;
+            self.timed(
+                module.name(),
+                LifecyclePhase::Init,
+                self.init_with_retry(module.as_ref(), &ctx.scoped(module.name())),
+            )
+            .await
+            .map_err(|error| {
+                self.failed(
+                    module.name(),
+                    ModuleKind::Custom,
+                    LifecyclePhase::Init,
+                    error,
+                )
             })?;
+            self.set_state(module.name(), ModuleState::Initialized);
         }
 
         Ok(())
     }
 
-    /// Start core modules in the correct order
-    pub async fn start_core_modules(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
-        tracing::info!("starting core modules in order: {:?}", CORE_MODULE_ORDER);
+    /// Start core modules in dependency order
+    ///
+    /// If a module fails to start, the core modules already started are stopped
+    /// in reverse order before the error is returned.
+    pub async fn start_core_modules(&self, ctx: &InitCtx) -> Result<(), KernelError> {
+        let order = self.core_order()?;
+        // Every module is initialized by now, so the events module can wire up all handlers
+        self.resources.insert(ModuleEventSchemas {
+            schemas: self.collect_event_schemas(),
+        });
+        self.resources.insert(ModuleEventHandlers {
+            handlers: self.collect_event_handlers(),
+        });
+        self.resources.insert(ModuleCacheInvalidations {
+            invalidations: self.collect_cache_invalidations(),
+        });
+        self.resources.insert(ModuleJobs {
+            jobs: self.collect_jobs(),
+        });
+        self.resources.insert(ModuleQueueHandlers {
+            handlers: self.collect_queue_handlers(),
+        });
+        tracing::info!(
+            "starting core modules in order: {:?}",
+            order.iter().map(|module| module.name()).collect::<Vec<_>>()
+        );
 
-        for &module_name in CORE_MODULE_ORDER {
-            if let Some(module) = self.core_modules.iter().find(|m| m.name() == module_name) {
-                tracing::info!(module = module.name(), "starting core module");
+        let mut started = Vec::new();
+        for module in order {
+            tracing::info!(module = module.name(), "starting core module");
 
-                module
-                    .start(ctx)
-                    .await
-                    .with_context(|| format!("failed to start core module '{}'", module.name()))?;
+            if let Err(error) = self.check_transition(module.name(), LifecyclePhase::Start) {
+                self.rollback_started(&started).await;
+                return Err(error);
             }
+            if let Err(error) = self
+                .timed(
+                    module.name(),
+                    LifecyclePhase::Start,
+                    module.start(&ctx.scoped(module.name())),
+                )
+                .await
+            {
+                self.rollback_started(&started).await;
+                return Err(self.failed(
+                    module.name(),
+                    ModuleKind::Core,
+                    LifecyclePhase::Start,
+                    error,
+                ));
+            }
+            self.set_state(module.name(), ModuleState::Started);
+            self.announce(LifecycleEvent::ModuleStarted {
+                module: module.name(),
+                kind: ModuleKind::Core,
+            });
+            started.push(module);
         }
 
         Ok(())
     }
 
-    /// Start custom modules
-    pub async fn start_custom_modules(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    /// Start custom modules in dependency order
+    ///
+    /// If a module fails to start, the custom modules already started are stopped
+    /// in reverse order before the error is returned.
+    pub async fn start_custom_modules(&self, ctx: &InitCtx) -> Result<(), KernelError> {
         tracing::info!("starting {} custom modules", self.custom_modules.len());
 
-        for module in &self.custom_modules {
+        let mut started = Vec::new();
+        for module in self.custom_order()? {
             tracing::info!(module = module.name(), "starting custom module");
 
-            module
-                .start(ctx)
+            if let Err(error) = self.check_transition(module.name(), LifecyclePhase::Start) {
+                self.rollback_started(&started).await;
+                return Err(error);
+            }
+            if let Err(error) = self
+                .timed(
+                    module.name(),
+                    LifecyclePhase::Start,
+                    module.start(&ctx.scoped(module.name())),
+                )
                 .await
-                .with_context(|| format!("failed to start custom module '{}'", module.name()))?;
+            {
+                self.rollback_started(&started).await;
+                return Err(self.failed(
+                    module.name(),
+                    ModuleKind::Custom,
+                    LifecyclePhase::Start,
+                    error,
+                ));
+            }
+            self.set_state(module.name(), ModuleState::Started);
+            self.announce(LifecycleEvent::ModuleStarted {
+                module: module.name(),
+                kind: ModuleKind::Custom,
+            });
+            started.push(module);
         }
 
         Ok(())
     }
 
-    /// Stop custom modules first (reverse order)
-    pub async fn stop_custom_modules(&self) -> anyhow::Result<()> {
+    /// Stop already-started modules in reverse order after a failed startup
+    ///
+    /// Stop failures are logged rather than returned so the original start error is preserved.
+    async fn rollback_started(&self, started: &[&Arc<dyn Module>]) {
+        for module in started.iter().rev() {
+            tracing::warn!(module = module.name(), "rolling back started module");
+
+            match self.stop_module(module).await {
+                Ok(()) => self.set_state(module.name(), ModuleState::Stopped),
+                Err(error) => {
+                    tracing::error!(
+                        module = module.name(),
+                        error = %error,
+                        "failed to stop module during startup rollback"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Stop custom modules first (reverse dependency order)
+    pub async fn stop_custom_modules(&self) -> Result<(), KernelError> {
         tracing::info!("stopping {} custom modules", self.custom_modules.len());
 
-        for module in self.custom_modules.iter().rev() {
+        for module in self.custom_order()?.into_iter().rev() {
             tracing::info!(module = module.name(), "stopping custom module");
 
-            module
-                .stop()
-                .await
-                .with_context(|| format!("failed to stop custom module '{}'", module.name()))?;
+            self.check_transition(module.name(), LifecyclePhase::Stop)?;
+            self.stop_module(module).await.map_err(|error| {
+                self.failed(
+                    module.name(),
+                    ModuleKind::Custom,
+                    LifecyclePhase::Stop,
+                    error,
+                )
+            })?;
+            self.set_state(module.name(), ModuleState::Stopped);
         }
 
         Ok(())
     }
 
-    /// Stop core modules in reverse order
-    pub async fn stop_core_modules(&self) -> anyhow::Result<()> {
+    /// Stop core modules in reverse dependency order
+    pub async fn stop_core_modules(&self) -> Result<(), KernelError> {
         tracing::info!("stopping core modules in reverse order");
 
-        // Stop core modules in reverse order of CORE_MODULE_ORDER
-        for &module_name in CORE_MODULE_ORDER.iter().rev() {
-            if let Some(module) = self.core_modules.iter().find(|m| m.name() == module_name) {
-                tracing::info!(module = module.name(), "stopping core module");
+        for module in self.core_order()?.into_iter().rev() {
+            tracing::info!(module = module.name(), "stopping core module");
 
-                module
-                    .stop()
-                    .await
-                    .with_context(|| format!("failed to stop core module '{}'", module.name()))?;
-            }
+            self.check_transition(module.name(), LifecyclePhase::Stop)?;
+            self.stop_module(module).await.map_err(|error| {
+                self.failed(module.name(), ModuleKind::Core, LifecyclePhase::Stop, error)
+            })?;
+            self.set_state(module.name(), ModuleState::Stopped);
         }
 
         Ok(())
     }
 
+    /// Modules in the order they stop: custom before core, each in reverse dependency order
+    ///
+    /// Falls back to reverse registration order if dependencies cannot be resolved, so
+    /// shutdown never fails on ordering.
+    pub fn shutdown_order(&self) -> Vec<&Arc<dyn Module>> {
+        let custom = self
+            .custom_order()
+            .unwrap_or_else(|_| self.custom_modules.iter().collect());
+        let core = self
+            .core_order()
+            .unwrap_or_else(|_| self.core_modules.iter().collect());
+        custom
+            .into_iter()
+            .rev()
+            .chain(core.into_iter().rev())
+            .collect()
+    }
+
+    /// Notify modules of reloaded settings (core first, then custom, in dependency order)
+    pub async fn notify_config_change(&self, settings: &Settings) -> Result<(), KernelError> {
+        let core = self
+            .core_order()?
+            .into_iter()
+            .map(|module| (module, ModuleKind::Core));
+        let custom = self
+            .custom_order()?
+            .into_iter()
+            .map(|module| (module, ModuleKind::Custom));
+        for (module, kind) in core.chain(custom) {
+            module.on_config_change(settings).await.map_err(|error| {
+                self.failed(module.name(), kind, LifecyclePhase::ConfigChange, error)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Query every module's health and aggregate the results (core first, then custom)
+    pub async fn health_report(&self) -> HealthReport {
+        let mut entries = Vec::new();
+        for module in self.modules() {
+            entries.push(ModuleHealthEntry {
+                module: module.name().to_string(),
+                health: module.health().await,
+            });
+        }
+        HealthReport::new(entries)
+    }
+
+    /// Collect the event schemas of all modules (core + custom), with their module names
+    pub fn collect_event_schemas(&self) -> Vec<(&'static str, EventSchema)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .event_schemas()
+                    .into_iter()
+                    .map(move |schema| (module.name(), schema))
+            })
+            .collect()
+    }
+
+    /// Collect the event handlers of all modules (core + custom), with their module names
+    pub fn collect_event_handlers(&self) -> Vec<(&'static str, EventHandler)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .event_handlers()
+                    .into_iter()
+                    .map(move |handler| (module.name(), handler))
+            })
+            .collect()
+    }
+
+    /// Collect the cache invalidations of all modules (core + custom), with their module names
+    pub fn collect_cache_invalidations(&self) -> Vec<(&'static str, CacheInvalidation)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .cache_invalidations()
+                    .into_iter()
+                    .map(move |invalidation| (module.name(), invalidation))
+            })
+            .collect()
+    }
+
+    /// Collect the scheduled jobs of all modules (core + custom), with their module names
+    pub fn collect_jobs(&self) -> Vec<(&'static str, Job)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .jobs()
+                    .into_iter()
+                    .map(move |job| (module.name(), job))
+            })
+            .collect()
+    }
+
+    /// Collect the queue handlers of all modules (core + custom), with their module names
+    pub fn collect_queue_handlers(&self) -> Vec<(&'static str, QueueHandler)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .queue_handlers()
+                    .into_iter()
+                    .map(move |handler| (module.name(), handler))
+            })
+            .collect()
+    }
+
     /// Collect all migrations from all modules (core + custom)
     pub fn collect_migrations(&self) -> Vec<(String, crate::module::Migration)> {
         let mut migrations = Vec::new();
@@ -195,6 +969,20 @@ impl ModuleRegistry {
 
         migrations
     }
+
+    /// Collect all seeds, core modules first, each module's in the order it returns them
+    pub fn collect_seeds(&self) -> Vec<(String, crate::module::Seed)> {
+        self.core_modules
+            .iter()
+            .chain(&self.custom_modules)
+            .flat_map(|module| {
+                module
+                    .seeds()
+                    .into_iter()
+                    .map(|seed| (module.name().to_string(), seed))
+            })
+            .collect()
+    }
 }
 
 impl Default for ModuleRegistry {
@@ -206,13 +994,36 @@ impl Default for ModuleRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::module::Migration;
+    use crate::module::{Migration, Seed};
     use crate::settings::Settings;
 
     struct TestModule {
         name: &'static str,
     }
 
+    struct DependentModule {
+        name: &'static str,
+        depends_on: &'static [&'static str],
+    }
+
+    impl Module for DependentModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn depends_on(&self) -> &[&'static str] {
+            self.depends_on
+        }
+    }
+
+    fn dependent(name: &'static str, depends_on: &'static [&'static str]) -> Arc<dyn Module> {
+        Arc::new(DependentModule { name, depends_on })
+    }
+
+    fn names(modules: Vec<&Arc<dyn Module>>) -> Vec<&'static str> {
+        modules.into_iter().map(|module| module.name()).collect()
+    }
+
     #[async_trait::async_trait]
     impl Module for TestModule {
         fn name(&self) -> &'static str {
@@ -223,8 +1034,49 @@ mod tests {
             vec![Migration {
                 id: "001_init",
                 up: "CREATE TABLE test;",
+                down: None,
             }]
         }
+
+        fn event_schemas(&self) -> Vec<EventSchema> {
+            vec![EventSchema {
+                name: "test.created",
+                version: 1,
+                description: None,
+                schema: serde_json::json!({ "type": "object" }),
+            }]
+        }
+
+        fn event_handlers(&self) -> Vec<EventHandler> {
+            vec![EventHandler::new("test.created", |_| async { Ok(()) })]
+        }
+
+        fn cache_invalidations(&self) -> Vec<CacheInvalidation> {
+            vec![CacheInvalidation::new("test.created", &["tests:list:*"])]
+        }
+
+        fn jobs(&self) -> Vec<Job> {
+            vec![Job::new("cleanup", "0 3 * * *", || async { Ok(()) })]
+        }
+
+        fn queue_handlers(&self) -> Vec<QueueHandler> {
+            vec![QueueHandler::new("test.export", |_| async { Ok(()) })]
+        }
+
+        fn seeds(&self) -> Vec<Seed> {
+            vec![
+                Seed {
+                    id: "authors",
+                    tables: &["author"],
+                    script: "CREATE author:ann;",
+                },
+                Seed {
+                    id: "books",
+                    tables: &["book"],
+                    script: "CREATE book:one SET author = author:ann;",
+                },
+            ]
+        }
     }
 
     #[test]
@@ -240,17 +1092,38 @@ mod tests {
         assert!(migrations.is_empty()); // No modules registered yet
     }
 
+    #[test]
+    fn test_seeds_keep_declaration_order() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(TestModule { name: "test" }))
+            .unwrap();
+
+        let seeds: Vec<_> = registry
+            .collect_seeds()
+            .into_iter()
+            .map(|(module, seed)| (module, seed.id))
+            .collect();
+
+        assert_eq!(
+            seeds,
+            vec![
+                ("test".to_string(), "authors"),
+                ("test".to_string(), "books")
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_module_lifecycle() {
         let mut registry = ModuleRegistry::new();
         let settings = Settings::default();
-        let ctx = InitCtx {
-            settings: &settings,
-        };
 
         // Register a test module
         let test_module = Arc::new(TestModule { name: "test" });
-        registry.register_custom(test_module);
+        registry.register_custom(test_module).unwrap();
+
+        let ctx = registry.init_ctx(Arc::new(settings));
 
         // These should not fail with the test module
         registry.init_core_modules(&ctx).await.unwrap();
@@ -260,4 +1133,717 @@ mod tests {
         registry.stop_custom_modules().await.unwrap();
         registry.stop_core_modules().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_event_declarations_published_before_core_modules_start() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(TestModule { name: "test" }))
+            .unwrap();
+        registry.register_core(dependent("events", &[])).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        assert!(!registry.resources().contains::<ModuleEventHandlers>());
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let published = registry
+            .resources()
+            .require::<ModuleEventHandlers>()
+            .unwrap();
+        let handlers: Vec<_> = published
+            .handlers
+            .iter()
+            .map(|(module, handler)| (*module, handler.event))
+            .collect();
+        assert_eq!(handlers, vec![("test", "test.created")]);
+        let schemas = registry
+            .resources()
+            .require::<ModuleEventSchemas>()
+            .unwrap();
+        assert_eq!(schemas.schemas[0].1.version, 1);
+        let invalidations = registry
+            .resources()
+            .require::<ModuleCacheInvalidations>()
+            .unwrap();
+        assert_eq!(invalidations.invalidations[0].1.keys, ["tests:list:*"]);
+        let jobs = registry.resources().require::<ModuleJobs>().unwrap();
+        assert_eq!(jobs.jobs[0].0, "test");
+        assert_eq!(jobs.jobs[0].1.schedule, "0 3 * * *");
+        let queue_handlers = registry
+            .resources()
+            .require::<ModuleQueueHandlers>()
+            .unwrap();
+        assert_eq!(queue_handlers.handlers[0].1.kind, "test.export");
+    }
+
+    #[test]
+    fn test_custom_modules_follow_dependencies() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(dependent("db", &[])).unwrap();
+        registry
+            .register_custom(dependent("orders", &["books", "users"]))
+            .unwrap();
+        registry
+            .register_custom(dependent("books", &["db"]))
+            .unwrap();
+        registry.register_custom(dependent("users", &[])).unwrap();
+
+        assert_eq!(
+            names(registry.custom_order().unwrap()),
+            vec!["books", "users", "orders"]
+        );
+    }
+
+    #[test]
+    fn test_core_modules_follow_dependencies_before_priority() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(dependent("events", &[]), priority::EVENTS)
+            .unwrap();
+        registry
+            .register_core_with_priority(dependent("db", &["events"]), priority::DB)
+            .unwrap();
+        registry
+            .register_core_with_priority(dependent("telemetry", &[]), priority::TELEMETRY)
+            .unwrap();
+
+        assert_eq!(
+            names(registry.core_order().unwrap()),
+            vec!["telemetry", "events", "db"]
+        );
+    }
+
+    #[test]
+    fn test_core_modules_ordered_by_priority_then_registration() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(dependent("cache", &[])).unwrap();
+        registry.register_core(dependent("search", &[])).unwrap();
+        registry
+            .register_core_with_priority(dependent("db", &[]), priority::DB)
+            .unwrap();
+        registry
+            .register_core_with_priority(dependent("kernel", &[]), priority::KERNEL)
+            .unwrap();
+
+        assert_eq!(
+            names(registry.core_order().unwrap()),
+            vec!["kernel", "db", "cache", "search"]
+        );
+    }
+
+    #[test]
+    fn test_dependency_cycle_rejected_at_registration() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_custom(dependent("a", &["b"])).unwrap();
+        registry.register_custom(dependent("b", &["c"])).unwrap();
+
+        let error = registry
+            .register_custom(dependent("c", &["a"]))
+            .unwrap_err();
+
+        assert!(error.to_string().contains("a -> b -> c -> a"));
+        assert_eq!(registry.custom_module_count(), 2);
+    }
+
+    #[test]
+    fn test_missing_dependency_reported_when_ordering() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(dependent("books", &["search"]))
+            .unwrap();
+
+        let error = registry.custom_order().err().unwrap();
+        assert!(error.to_string().contains("depends on 'search'"));
+    }
+
+    struct ProviderModule;
+
+    #[async_trait::async_trait]
+    impl Module for ProviderModule {
+        fn name(&self) -> &'static str {
+            "provider"
+        }
+
+        async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            ctx.resources.insert(String::from("shared handle"));
+            Ok(())
+        }
+    }
+
+    struct ConsumerModule;
+
+    #[async_trait::async_trait]
+    impl Module for ConsumerModule {
+        fn name(&self) -> &'static str {
+            "consumer"
+        }
+
+        fn depends_on(&self) -> &[&'static str] {
+            &["provider"]
+        }
+
+        async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            let handle = ctx.resources.require::<String>()?;
+            anyhow::ensure!(handle.as_str() == "shared handle");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resources_shared_between_modules() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(Arc::new(ProviderModule)).unwrap();
+        registry.register_custom(Arc::new(ConsumerModule)).unwrap();
+        let settings = Settings::default();
+        let ctx = registry.init_ctx(Arc::new(settings));
+
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+    }
+
+    #[derive(Default)]
+    struct RetainingModule {
+        ctx: std::sync::OnceLock<InitCtx>,
+    }
+
+    #[async_trait::async_trait]
+    impl Module for RetainingModule {
+        fn name(&self) -> &'static str {
+            "retaining"
+        }
+
+        async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            self.ctx
+                .set(ctx.clone())
+                .map_err(|_| anyhow::anyhow!("initialized twice"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_modules_can_retain_init_ctx() {
+        let module = Arc::new(RetainingModule::default());
+        let mut registry = ModuleRegistry::new();
+        registry.register_custom(module.clone()).unwrap();
+        registry.resources().insert(42u32);
+
+        let mut settings = Settings::default();
+        settings.server.port = 9090;
+        registry
+            .init_custom_modules(&registry.init_ctx(Arc::new(settings)))
+            .await
+            .unwrap();
+
+        // The context outlives the init call and can move into a spawned task
+        let ctx = module.ctx.get().unwrap().clone();
+        let (port, answer) = tokio::spawn(async move {
+            (
+                ctx.settings.server.port,
+                *ctx.resources.require::<u32>().unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+        assert_eq!(port, 9090);
+        assert_eq!(answer, 42);
+    }
+
+    struct WorkerModule;
+
+    #[async_trait::async_trait]
+    impl Module for WorkerModule {
+        fn name(&self) -> &'static str {
+            "worker"
+        }
+
+        async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            ctx.spawn("poller", std::future::pending());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_module_tasks_cancelled_on_stop() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_custom(Arc::new(WorkerModule)).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+        assert_eq!(registry.tasks().running("worker"), 1);
+
+        registry.stop_custom_modules().await.unwrap();
+        assert_eq!(registry.tasks().running("worker"), 0);
+        assert_eq!(registry.module_state("worker"), Some(ModuleState::Stopped));
+    }
+
+    struct BlockingModule {
+        running: Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl Module for BlockingModule {
+        fn name(&self) -> &'static str {
+            "blocking"
+        }
+
+        async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+            let running = self.running.clone();
+            ctx.spawn("busy", async move {
+                running.notify_one();
+                // Never reaches an await, so aborting cannot end it early
+                std::thread::sleep(Duration::from_millis(300));
+                Ok(())
+            });
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_module_not_stopped_while_its_tasks_outlive_the_budget() {
+        let running = Arc::new(tokio::sync::Notify::new());
+        let mut registry = ModuleRegistry::new().with_shutdown(
+            crate::shutdown::ShutdownCoordinator::new(Duration::from_millis(20)),
+        );
+        registry
+            .register_custom(Arc::new(BlockingModule {
+                running: running.clone(),
+            }))
+            .unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+        running.notified().await;
+
+        let error = registry.stop_custom_modules().await.unwrap_err();
+        let message = format!("{:#}", anyhow::Error::from(error));
+        assert!(message.contains("still running"), "{message}");
+        assert_ne!(
+            registry.module_state("blocking"),
+            Some(ModuleState::Stopped)
+        );
+    }
+
+    struct SlowModule;
+
+    #[async_trait::async_trait]
+    impl Module for SlowModule {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn init(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_startup_report_flags_modules_over_budget() {
+        let mut registry = ModuleRegistry::new().with_startup_budget(
+            StartupBudget::new(Duration::from_secs(5))
+                .with_module_budget("slow", Duration::from_millis(10)),
+        );
+        registry.register_custom(Arc::new(SlowModule)).unwrap();
+        registry
+            .register_custom(Arc::new(TestModule { name: "fast" }))
+            .unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+
+        let report = registry.startup_report();
+        assert_eq!(report.modules.len(), 2);
+        assert_eq!(report.slow(), vec!["slow"]);
+        let slow = report
+            .modules
+            .iter()
+            .find(|timing| timing.module == "slow")
+            .unwrap();
+        assert!(slow.init_ms.unwrap() >= 30);
+        assert!(slow.start_ms.is_some());
+        assert_eq!(slow.budget_ms, 10);
+    }
+
+    struct FlakyModule {
+        failures_left: std::sync::atomic::AtomicU32,
+        max_attempts: u32,
+    }
+
+    impl FlakyModule {
+        fn new(failures: u32, max_attempts: u32) -> Arc<Self> {
+            Arc::new(Self {
+                failures_left: std::sync::atomic::AtomicU32::new(failures),
+                max_attempts,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Module for FlakyModule {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn init_retry(&self) -> crate::retry::RetryPolicy {
+            crate::retry::RetryPolicy::exponential(self.max_attempts, Duration::from_millis(1))
+        }
+
+        async fn init(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+            use std::sync::atomic::Ordering;
+            let remaining = self.failures_left.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures_left.store(remaining - 1, Ordering::SeqCst);
+                anyhow::bail!("connection refused");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retryable_init_succeeds_within_attempts() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(FlakyModule::new(2, 3)).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+
+        registry.init_core_modules(&ctx).await.unwrap();
+        assert_eq!(
+            registry.module_state("flaky"),
+            Some(ModuleState::Initialized)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retryable_init_gives_up_after_max_attempts() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(FlakyModule::new(5, 3)).unwrap();
+        let ctx = registry.init_ctx(Arc::new(Settings::default()));
+
+        let error = registry.init_core_modules(&ctx).await.unwrap_err();
+        let message = format!("{:#}", anyhow::Error::from(error));
+        assert!(message.contains("gave up after 3 attempts"), "{message}");
+        assert!(message.contains("connection refused"), "{message}");
+        assert_eq!(
+            registry.module_state("flaky"),
+            Some(ModuleState::Registered)
+        );
+    }
+
+    struct DegradedModule;
+
+    #[async_trait::async_trait]
+    impl Module for DegradedModule {
+        fn name(&self) -> &'static str {
+            "cache"
+        }
+
+        async fn health(&self) -> crate::health::ModuleHealth {
+            crate::health::ModuleHealth::degraded("replica lagging")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_report_aggregates_modules() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(Arc::new(DegradedModule)).unwrap();
+        registry
+            .register_custom(Arc::new(TestModule { name: "books" }))
+            .unwrap();
+
+        let report = registry.health_report().await;
+
+        assert_eq!(report.status, crate::health::HealthStatus::Degraded);
+        assert_eq!(report.modules.len(), 2);
+        assert_eq!(report.modules[0].module, "cache");
+        assert_eq!(
+            report.modules[1].health.status,
+            crate::health::HealthStatus::Healthy
+        );
+    }
+
+    struct RecordingModule {
+        name: &'static str,
+        fail_start: bool,
+        events: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Module for RecordingModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+            if self.fail_start {
+                anyhow::bail!("boom");
+            }
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("start:{}", self.name));
+            Ok(())
+        }
+
+        async fn stop(&self) -> anyhow::Result<()> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("stop:{}", self.name));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_rolls_back_started_modules() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let announced = Arc::new(Announced::default());
+        let mut registry = ModuleRegistry::new();
+        registry
+            .resources()
+            .insert::<Arc<dyn LifecycleListener>>(announced.clone());
+        for (name, fail_start) in [("books", false), ("users", false), ("orders", true)] {
+            registry
+                .register_custom(Arc::new(RecordingModule {
+                    name,
+                    fail_start,
+                    events: events.clone(),
+                }))
+                .unwrap();
+        }
+        let settings = Settings::default();
+        let ctx = registry.init_ctx(Arc::new(settings));
+
+        registry.init_custom_modules(&ctx).await.unwrap();
+        let error = registry.start_custom_modules(&ctx).await.unwrap_err();
+
+        assert!(error.to_string().contains("'orders'"));
+        assert!(matches!(
+            error,
+            KernelError::Lifecycle {
+                kind: ModuleKind::Custom,
+                phase: LifecyclePhase::Start,
+                ..
+            }
+        ));
+        assert_eq!(error.module(), Some("orders"));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["start:books", "start:users", "stop:users", "stop:books"]
+        );
+        let announced: Vec<_> = announced.0.lock().unwrap().clone();
+        assert_eq!(
+            announced
+                .iter()
+                .map(|event| event.name())
+                .collect::<Vec<_>>(),
+            vec![
+                "kernel.module_started",
+                "kernel.module_started",
+                "kernel.module_failed"
+            ]
+        );
+        assert!(matches!(
+            &announced[2],
+            LifecycleEvent::ModuleFailed {
+                module: "orders",
+                phase: LifecyclePhase::Start,
+                ..
+            }
+        ));
+    }
+
+    /// Lifecycle events announced so far
+    #[derive(Default)]
+    struct Announced(std::sync::Mutex<Vec<LifecycleEvent>>);
+
+    impl LifecycleListener for Announced {
+        fn on_lifecycle(&self, event: &LifecycleEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    struct DescribedModule;
+
+    impl Module for DescribedModule {
+        fn name(&self) -> &'static str {
+            "billing"
+        }
+
+        fn version(&self) -> Option<&'static str> {
+            Some("2.1.0")
+        }
+
+        fn description(&self) -> Option<&'static str> {
+            Some("Invoices and payments")
+        }
+    }
+
+    #[test]
+    fn test_module_info_reports_metadata() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core(Arc::new(TestModule { name: "kernel" }))
+            .unwrap();
+        registry.register_custom(Arc::new(DescribedModule)).unwrap();
+
+        let info = registry.module_info();
+
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].kind, ModuleKind::Core);
+        assert_eq!(info[0].version, None);
+        assert_eq!(info[1].name, "billing");
+        assert_eq!(info[1].kind, ModuleKind::Custom);
+        assert_eq!(info[1].version, Some("2.1.0"));
+        assert_eq!(info[1].description, Some("Invoices and payments"));
+
+        let json = serde_json::to_value(&info[1]).unwrap();
+        assert_eq!(json["kind"], "custom");
+        assert!(json.get("author").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_module_names_rejected() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core(Arc::new(TestModule { name: "users" }))
+            .unwrap();
+
+        let error = registry
+            .register_custom(Arc::new(TestModule { name: "users" }))
+            .unwrap_err();
+        assert!(error.to_string().contains("'users' is already registered"));
+
+        let error = registry
+            .register_core(Arc::new(TestModule { name: "users" }))
+            .unwrap_err();
+        assert!(error.to_string().contains("'users' is already registered"));
+
+        assert_eq!(registry.modules().len(), 1);
+    }
+
+    #[test]
+    fn test_modules_disabled_in_settings_are_skipped() {
+        let mut settings = Settings::default();
+        settings.modules.enabled.insert("books".to_string(), false);
+        let mut registry = ModuleRegistry::with_settings(&settings);
+
+        registry
+            .register_custom(Arc::new(TestModule { name: "books" }))
+            .unwrap();
+        registry
+            .register_custom(Arc::new(TestModule { name: "users" }))
+            .unwrap();
+
+        assert!(registry.get_module("books").is_none());
+        assert!(registry.get_module("users").is_some());
+        let skipped = registry.skipped_modules();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].info.name, "books");
+        assert_eq!(skipped[0].reason, SkipReason::Disabled);
+    }
+
+    struct LocalOnlyModule;
+
+    impl Module for LocalOnlyModule {
+        fn name(&self) -> &'static str {
+            "mock_payments"
+        }
+
+        fn environments(&self) -> &[&'static str] {
+            &["local", "preview"]
+        }
+    }
+
+    #[test]
+    fn test_modules_limited_to_other_environments_are_skipped() {
+        let mut settings = Settings::default();
+        for (environment, registered) in [
+            (Environment::Local, true),
+            (Environment::Other("preview".to_string()), true),
+            (Environment::Production, false),
+        ] {
+            settings.environment = environment;
+            let mut registry = ModuleRegistry::with_settings(&settings);
+
+            registry.register_custom(Arc::new(LocalOnlyModule)).unwrap();
+
+            assert_eq!(registry.get_module("mock_payments").is_some(), registered);
+            assert_eq!(
+                registry
+                    .skipped_modules()
+                    .iter()
+                    .map(|skipped| skipped.reason)
+                    .collect::<Vec<_>>(),
+                if registered {
+                    vec![]
+                } else {
+                    vec![SkipReason::OtherEnvironment]
+                }
+            );
+        }
+    }
+
+    fn create_submitted_module() -> Arc<dyn Module> {
+        Arc::new(TestModule {
+            name: "submitted_at_link_time",
+        })
+    }
+
+    crate::register_module!(create_submitted_module);
+
+    #[test]
+    fn test_submitted_modules_register_themselves() {
+        let mut registry = ModuleRegistry::new();
+
+        registry.register_submitted().unwrap();
+
+        let info = registry.module_info();
+        let submitted = info
+            .iter()
+            .find(|info| info.name == "submitted_at_link_time")
+            .unwrap();
+        assert_eq!(submitted.kind, ModuleKind::Custom);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_phases_enforce_state_order() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_custom(Arc::new(TestModule { name: "books" }))
+            .unwrap();
+        let settings = Settings::default();
+        let ctx = registry.init_ctx(Arc::new(settings));
+
+        let error = registry.start_custom_modules(&ctx).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot start module 'books' while it is registered"
+        );
+
+        registry.init_custom_modules(&ctx).await.unwrap();
+        assert_eq!(
+            registry.module_state("books"),
+            Some(ModuleState::Initialized)
+        );
+        let error = registry.init_custom_modules(&ctx).await.unwrap_err();
+        assert!(matches!(
+            error,
+            KernelError::InvalidState {
+                phase: LifecyclePhase::Init,
+                state: ModuleState::Initialized,
+                ..
+            }
+        ));
+
+        registry.start_custom_modules(&ctx).await.unwrap();
+        registry.stop_custom_modules().await.unwrap();
+        assert_eq!(registry.module_state("books"), Some(ModuleState::Stopped));
+        assert!(registry.stop_custom_modules().await.is_err());
+
+        // A stopped module may be started again without re-running init
+        registry.start_custom_modules(&ctx).await.unwrap();
+        assert_eq!(registry.module_state("books"), Some(ModuleState::Started));
+    }
 }
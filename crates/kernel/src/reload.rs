@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+
+use crate::registry::ModuleRegistry;
+use crate::settings::Settings;
+
+/// Settings that are bound at startup and cannot change without a restart
+pub fn non_reloadable_changes(current: &Settings, updated: &Settings) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if current.environment != updated.environment {
+        changed.push("environment");
+    }
+    if current.server.host != updated.server.host {
+        changed.push("server.host");
+    }
+    if current.server.port != updated.server.port {
+        changed.push("server.port");
+    }
+    if current.database.endpoint != updated.database.endpoint {
+        changed.push("database.endpoint");
+    }
+    if current.database.namespace != updated.database.namespace {
+        changed.push("database.namespace");
+    }
+    if current.database.database != updated.database.database {
+        changed.push("database.database");
+    }
+    if current.modules.enabled != updated.modules.enabled {
+        changed.push("modules");
+    }
+    if current.admin.token != updated.admin.token {
+        changed.push("admin.token");
+    }
+    changed
+}
+
+/// Snapshot of the config directory used to detect edits between polls
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+fn fingerprint(config_dir: &Path) -> Fingerprint {
+    let Ok(entries) = std::fs::read_dir(config_dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Fingerprint = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (entry.path(), metadata.modified().ok(), metadata.len()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Polls the config directory and pushes reloadable changes to modules
+pub struct ConfigWatcher {
+    config_dir: PathBuf,
+    interval: Duration,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigWatcher {
+    /// Create a watcher for `config_dir`, polling every `interval`
+    pub fn new(config_dir: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            config_dir: config_dir.into(),
+            interval,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Re-apply the overrides the settings were first loaded with on every reload
+    pub fn with_overrides(mut self, overrides: Vec<(String, String)>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Create a watcher for the directory and interval configured in `settings`
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::new(
+            Settings::config_dir(),
+            Duration::from_millis(settings.reload.interval_ms),
+        )
+    }
+
+    /// Watch in the background until the returned task is aborted
+    pub fn spawn(
+        self,
+        registry: Arc<ModuleRegistry>,
+        current: Settings,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move { self.run(registry, current).await })
+    }
+
+    async fn run(self, registry: Arc<ModuleRegistry>, mut current: Settings) {
+        tracing::info!(
+            config_dir = %self.config_dir.display(),
+            interval_ms = self.interval.as_millis() as u64,
+            "watching configuration for changes"
+        );
+
+        let mut last_seen = self.snapshot().await;
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+
+            let snapshot = self.snapshot().await;
+            if snapshot == last_seen {
+                continue;
+            }
+            last_seen = snapshot;
+
+            match reload(&registry, &current, &self.overrides).await {
+                Ok(Some(updated)) => current = updated,
+                Ok(None) => {}
+                Err(error) => {
+                    tracing::error!(error = %format!("{:#}", error), "configuration reload failed");
+                }
+            }
+        }
+    }
+
+    async fn snapshot(&self) -> Fingerprint {
+        let config_dir = self.config_dir.clone();
+        tokio::task::spawn_blocking(move || fingerprint(&config_dir))
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// Load settings again and notify modules, rejecting changes that need a restart
+///
+/// Returns the applied settings, or `None` when the reload was rejected.
+pub async fn reload(
+    registry: &ModuleRegistry,
+    current: &Settings,
+    overrides: &[(String, String)],
+) -> anyhow::Result<Option<Settings>> {
+    let updated =
+        Settings::load_with_overrides(overrides).context("failed to load updated settings")?;
+
+    let rejected = non_reloadable_changes(current, &updated);
+    if !rejected.is_empty() {
+        tracing::error!(
+            fields = ?rejected,
+            "rejecting configuration reload: these settings require a restart"
+        );
+        return Ok(None);
+    }
+
+    registry.notify_config_change(&updated).await?;
+    tracing::info!("configuration reloaded");
+    Ok(Some(updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reloadable_changes_are_accepted() {
+        let current = Settings::default();
+        let mut updated = Settings::default();
+        updated.server.request_timeout_ms = 1;
+        updated.openapi.title = "Renamed".to_string();
+
+        assert!(non_reloadable_changes(&current, &updated).is_empty());
+    }
+
+    #[test]
+    fn test_port_and_database_changes_are_rejected() {
+        let current = Settings::default();
+        let mut updated = Settings::default();
+        updated.server.port = 9090;
+        updated.database.endpoint = "ws://db:8000".to_string();
+
+        assert_eq!(
+            non_reloadable_changes(&current, &updated),
+            vec!["server.port", "database.endpoint"]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_changes_when_files_change() {
+        let config_dir = std::env::temp_dir().join(format!("atlas-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join("base.toml"), "[server]\n").unwrap();
+
+        let before = fingerprint(&config_dir);
+        std::fs::write(config_dir.join("base.toml"), "[server]\nport = 9090\n").unwrap();
+        let after = fingerprint(&config_dir);
+
+        std::fs::remove_dir_all(&config_dir).ok();
+        assert_eq!(before.len(), 1);
+        assert_ne!(before, after);
+    }
+}
@@ -0,0 +1,112 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+
+/// Type-keyed container for handles shared between modules
+///
+/// Core modules insert handles (db client, event bus, cache) during `init`;
+/// modules initialized later retrieve them by type. Ports the framework leaves to the
+/// application, such as a `QueryExecutor` or `WebhookTransport`, are inserted as
+/// `Arc<dyn Trait>` before modules initialize.
+#[derive(Default)]
+pub struct Resources {
+    entries: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Resources {
+    /// Create an empty resource container
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a shared resource, returning the previous value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<Arc<T>> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    /// Insert an already shared resource, returning the previous value of the same type
+    pub fn insert_arc<T: Send + Sync + 'static>(&self, value: Arc<T>) -> Option<Arc<T>> {
+        self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(TypeId::of::<T>(), value)
+            .and_then(|previous| previous.downcast::<T>().ok())
+    }
+
+    /// Get a shared resource by type
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.entries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+
+    /// Get a shared resource by type, failing with the type name when it is missing
+    pub fn require<T: Send + Sync + 'static>(&self) -> anyhow::Result<Arc<T>> {
+        self.get::<T>().ok_or_else(|| {
+            anyhow!(
+                "shared resource '{}' is not registered; is the providing module registered and initialized first?",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Check whether a resource of the given type is present
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.entries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl std::fmt::Debug for Resources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self
+            .entries
+            .read()
+            .map(|entries| entries.len())
+            .unwrap_or_default();
+        f.debug_struct("Resources").field("len", &count).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct DbHandle(&'static str);
+
+    #[test]
+    fn test_insert_and_get_by_type() {
+        let resources = Resources::new();
+        assert!(resources.get::<DbHandle>().is_none());
+
+        resources.insert(DbHandle("primary"));
+
+        assert!(resources.contains::<DbHandle>());
+        assert_eq!(*resources.get::<DbHandle>().unwrap(), DbHandle("primary"));
+    }
+
+    #[test]
+    fn test_insert_replaces_previous_value() {
+        let resources = Resources::new();
+        resources.insert(DbHandle("old"));
+
+        let previous = resources.insert(DbHandle("new")).unwrap();
+
+        assert_eq!(*previous, DbHandle("old"));
+        assert_eq!(*resources.get::<DbHandle>().unwrap(), DbHandle("new"));
+    }
+
+    #[test]
+    fn test_require_names_missing_type() {
+        let error = Resources::new().require::<DbHandle>().unwrap_err();
+        assert!(error.to_string().contains("DbHandle"));
+    }
+}
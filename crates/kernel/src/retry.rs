@@ -0,0 +1,79 @@
+//! Bounded retries for module lifecycle phases
+
+use std::time::Duration;
+
+/// How often, and how patiently, the registry retries a failed `init`
+///
+/// Modules whose dependencies may come up after them (database, event broker, ...) return
+/// a policy from `Module::init_retry`; everything else keeps the default of a single attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one; `1` disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Cap on the delay between attempts as it doubles
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Fail on the first error
+    pub const fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    /// Try up to `max_attempts` times, doubling the delay from `initial_backoff` each retry
+    pub const fn exponential(max_attempts: u32, initial_backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Cap the delay between attempts
+    pub const fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Delay before attempt `attempt + 1`, where `attempt` counts from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy::exponential(6, Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_default_policy_makes_one_attempt() {
+        assert_eq!(RetryPolicy::default().max_attempts, 1);
+    }
+}
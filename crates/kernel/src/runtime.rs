@@ -0,0 +1,53 @@
+//! Tokio runtime configured from settings
+
+use anyhow::Context;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::settings::RuntimeSettings;
+
+/// Build the multi-threaded runtime described by the `[runtime]` settings
+///
+/// Binaries call this instead of `#[tokio::main]` so thread counts can be tuned per
+/// deployment without a rebuild.
+pub fn build_runtime(settings: &RuntimeSettings) -> anyhow::Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    builder
+        .enable_all()
+        .thread_name(settings.thread_name.clone());
+    if let Some(worker_threads) = settings.worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+    if let Some(max_blocking_threads) = settings.max_blocking_threads {
+        builder.max_blocking_threads(max_blocking_threads);
+    }
+
+    tracing::debug!(
+        worker_threads = ?settings.worker_threads,
+        max_blocking_threads = ?settings.max_blocking_threads,
+        thread_name = %settings.thread_name,
+        "building tokio runtime"
+    );
+    builder.build().context("failed to build tokio runtime")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_threads_use_configured_name() {
+        let settings = RuntimeSettings {
+            worker_threads: Some(1),
+            max_blocking_threads: Some(1),
+            thread_name: "atlas-test".to_string(),
+        };
+        let runtime = build_runtime(&settings).unwrap();
+
+        let name = runtime.block_on(async {
+            tokio::task::spawn_blocking(|| std::thread::current().name().map(str::to_string))
+                .await
+                .unwrap()
+        });
+        assert_eq!(name.as_deref(), Some("atlas-test"));
+    }
+}
@@ -0,0 +1,311 @@
+//! Bounded module shutdown
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::error::LifecyclePhase;
+use crate::lifecycle::LifecycleEvent;
+use crate::module::ModuleState;
+use crate::registry::ModuleRegistry;
+use crate::settings::ShutdownSettings;
+
+/// How a single module's `stop` ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "outcome")]
+pub enum StopOutcome {
+    Stopped,
+    Failed {
+        error: String,
+    },
+    /// The module exceeded its budget and its stop task was aborted
+    TimedOut,
+}
+
+/// Result of stopping one module
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleStop {
+    pub module: &'static str,
+    #[serde(flatten)]
+    pub outcome: StopOutcome,
+    pub elapsed_ms: u64,
+}
+
+/// What happened to every module during shutdown, in stop order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShutdownReport {
+    pub modules: Vec<ModuleStop>,
+}
+
+impl ShutdownReport {
+    /// Whether every module stopped within its budget and without errors
+    pub fn is_clean(&self) -> bool {
+        self.modules
+            .iter()
+            .all(|entry| entry.outcome == StopOutcome::Stopped)
+    }
+
+    /// Modules that exceeded their budget and were aborted
+    pub fn timed_out(&self) -> Vec<&'static str> {
+        self.modules
+            .iter()
+            .filter(|entry| entry.outcome == StopOutcome::TimedOut)
+            .map(|entry| entry.module)
+            .collect()
+    }
+
+    /// Modules whose `stop` returned an error
+    pub fn failed(&self) -> Vec<&'static str> {
+        self.modules
+            .iter()
+            .filter(|entry| matches!(entry.outcome, StopOutcome::Failed { .. }))
+            .map(|entry| entry.module)
+            .collect()
+    }
+}
+
+/// Stops every registered module with a time budget per module
+///
+/// Unlike `stop_custom_modules`/`stop_core_modules`, a failing or hanging module
+/// does not prevent the remaining modules from stopping.
+#[derive(Debug, Clone)]
+pub struct ShutdownCoordinator {
+    default_timeout: Duration,
+    module_timeouts: HashMap<String, Duration>,
+}
+
+impl ShutdownCoordinator {
+    /// Create a coordinator giving every module `default_timeout` to stop
+    pub fn new(default_timeout: Duration) -> Self {
+        Self {
+            default_timeout,
+            module_timeouts: HashMap::new(),
+        }
+    }
+
+    /// Create a coordinator from the `[shutdown]` settings
+    pub fn from_settings(settings: &ShutdownSettings) -> Self {
+        let mut coordinator = Self::new(Duration::from_millis(settings.timeout_ms));
+        for (module, timeout_ms) in &settings.modules {
+            coordinator =
+                coordinator.with_module_timeout(module, Duration::from_millis(*timeout_ms));
+        }
+        coordinator
+    }
+
+    /// Give one module a budget different from the default
+    pub fn with_module_timeout(mut self, module: &str, timeout: Duration) -> Self {
+        self.module_timeouts.insert(module.to_string(), timeout);
+        self
+    }
+
+    /// Budget for the named module, covering its `stop` and its background tasks ending
+    pub fn timeout_for(&self, module: &str) -> Duration {
+        self.module_timeouts
+            .get(module)
+            .copied()
+            .unwrap_or(self.default_timeout)
+    }
+
+    /// Stop all started modules (custom first, then core, each in reverse dependency order)
+    pub async fn shutdown(&self, registry: &ModuleRegistry) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+        let order = registry.shutdown_order();
+        registry.announce(LifecycleEvent::ShutdownBegun {
+            modules: order
+                .iter()
+                .filter(|module| registry.module_state(module.name()) == Some(ModuleState::Started))
+                .count(),
+        });
+
+        for module in order {
+            let name = module.name();
+            if registry.module_state(name) != Some(ModuleState::Started) {
+                tracing::debug!(module = name, "module not started; nothing to stop");
+                continue;
+            }
+            let timeout = self.timeout_for(name);
+            tracing::info!(
+                module = name,
+                timeout_ms = timeout.as_millis() as u64,
+                "stopping module"
+            );
+
+            // Run `stop` as its own task so a module that overruns can be aborted.
+            let started = Instant::now();
+            let stopping = module.clone();
+            let mut task = tokio::spawn(async move { stopping.stop().await });
+            let mut outcome = match tokio::time::timeout(timeout, &mut task).await {
+                Ok(Ok(Ok(()))) => StopOutcome::Stopped,
+                Ok(Ok(Err(error))) => StopOutcome::Failed {
+                    error: format!("{:#}", error),
+                },
+                Ok(Err(join_error)) => StopOutcome::Failed {
+                    error: format!("stop task panicked: {}", join_error),
+                },
+                Err(_) => {
+                    task.abort();
+                    StopOutcome::TimedOut
+                }
+            };
+            // Background tasks must end within what is left of the same budget
+            let remaining = timeout.saturating_sub(started.elapsed());
+            if let Err(error) = registry.cancel_tasks(name, remaining).await {
+                if outcome == StopOutcome::Stopped {
+                    outcome = StopOutcome::Failed {
+                        error: format!("{:#}", error),
+                    };
+                }
+            }
+            if outcome == StopOutcome::Stopped {
+                registry.set_state(name, ModuleState::Stopped);
+            }
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+
+            let error = match &outcome {
+                StopOutcome::Stopped => None,
+                StopOutcome::Failed { error } => {
+                    tracing::error!(module = name, error = %error, "module failed to stop");
+                    Some(error.clone())
+                }
+                StopOutcome::TimedOut => {
+                    tracing::error!(
+                        module = name,
+                        timeout_ms = timeout.as_millis() as u64,
+                        "module exceeded its shutdown budget and was aborted"
+                    );
+                    Some(format!(
+                        "exceeded its shutdown budget of {}ms",
+                        timeout.as_millis()
+                    ))
+                }
+            };
+            if let Some(error) = error {
+                registry.announce(LifecycleEvent::ModuleFailed {
+                    module: name,
+                    kind: registry.module_kind(name),
+                    phase: LifecyclePhase::Stop,
+                    error,
+                });
+            }
+
+            report.modules.push(ModuleStop {
+                module: name,
+                outcome,
+                elapsed_ms,
+            });
+        }
+
+        // Tasks spawned through contexts not scoped to any module
+        registry.tasks().cancel_all();
+
+        report
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::from_settings(&ShutdownSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::LifecycleListener;
+    use crate::module::Module;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    struct StoppingModule {
+        name: &'static str,
+        behavior: &'static str,
+    }
+
+    #[async_trait]
+    impl Module for StoppingModule {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn stop(&self) -> anyhow::Result<()> {
+            match self.behavior {
+                "hang" => std::future::pending().await,
+                "fail" => anyhow::bail!("flush failed"),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn module(name: &'static str, behavior: &'static str) -> Arc<dyn Module> {
+        Arc::new(StoppingModule { name, behavior })
+    }
+
+    async fn start_all(registry: &ModuleRegistry) {
+        let settings = crate::settings::Settings::default();
+        let ctx = registry.init_ctx(Arc::new(settings));
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.init_custom_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+        registry.start_custom_modules(&ctx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_reports_stragglers_and_keeps_going() {
+        let mut registry = ModuleRegistry::new();
+        registry.register_core(module("db", "ok")).unwrap();
+        registry.register_custom(module("books", "hang")).unwrap();
+        registry.register_custom(module("users", "fail")).unwrap();
+        start_all(&registry).await;
+        let announced = Arc::new(Announced::default());
+        registry
+            .resources()
+            .insert::<Arc<dyn LifecycleListener>>(announced.clone());
+
+        let report = ShutdownCoordinator::new(Duration::from_secs(5))
+            .with_module_timeout("books", Duration::from_millis(20))
+            .shutdown(&registry)
+            .await;
+
+        let order: Vec<&str> = report.modules.iter().map(|entry| entry.module).collect();
+        assert_eq!(order, vec!["users", "books", "db"]);
+        assert_eq!(report.timed_out(), vec!["books"]);
+        assert_eq!(report.failed(), vec!["users"]);
+        assert!(!report.is_clean());
+        assert_eq!(report.modules[2].outcome, StopOutcome::Stopped);
+        assert_eq!(registry.module_state("db"), Some(ModuleState::Stopped));
+        assert_eq!(registry.module_state("books"), Some(ModuleState::Started));
+
+        let announced = announced.0.lock().unwrap();
+        assert_eq!(announced[0], LifecycleEvent::ShutdownBegun { modules: 3 });
+        let failed: Vec<_> = announced[1..]
+            .iter()
+            .map(|event| match event {
+                LifecycleEvent::ModuleFailed { module, .. } => *module,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(failed, vec!["users", "books"]);
+    }
+
+    #[derive(Default)]
+    struct Announced(std::sync::Mutex<Vec<LifecycleEvent>>);
+
+    impl LifecycleListener for Announced {
+        fn on_lifecycle(&self, event: &LifecycleEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_timeouts_come_from_settings() {
+        let mut settings = ShutdownSettings::default();
+        settings.modules.insert("db".to_string(), 30000);
+
+        let coordinator = ShutdownCoordinator::from_settings(&settings);
+
+        assert_eq!(coordinator.timeout_for("db"), Duration::from_secs(30));
+        assert_eq!(coordinator.timeout_for("books"), Duration::from_secs(10));
+    }
+}
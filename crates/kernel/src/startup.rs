@@ -0,0 +1,161 @@
+//! Module startup timings
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::settings::StartupSettings;
+
+/// How long each module may take to init and to start before it is reported as slow
+#[derive(Debug, Clone)]
+pub struct StartupBudget {
+    default_budget: Duration,
+    module_budgets: HashMap<String, Duration>,
+}
+
+impl StartupBudget {
+    /// Give every module `default_budget` per phase
+    pub fn new(default_budget: Duration) -> Self {
+        Self {
+            default_budget,
+            module_budgets: HashMap::new(),
+        }
+    }
+
+    /// Create a budget from the `[startup]` settings
+    pub fn from_settings(settings: &StartupSettings) -> Self {
+        let mut budget = Self::new(Duration::from_millis(settings.budget_ms));
+        for (module, budget_ms) in &settings.modules {
+            budget = budget.with_module_budget(module, Duration::from_millis(*budget_ms));
+        }
+        budget
+    }
+
+    /// Give one module a budget different from the default
+    pub fn with_module_budget(mut self, module: &str, budget: Duration) -> Self {
+        self.module_budgets.insert(module.to_string(), budget);
+        self
+    }
+
+    /// Budget for the named module
+    pub fn budget_for(&self, module: &str) -> Duration {
+        self.module_budgets
+            .get(module)
+            .copied()
+            .unwrap_or(self.default_budget)
+    }
+}
+
+impl Default for StartupBudget {
+    fn default() -> Self {
+        Self::from_settings(&StartupSettings::default())
+    }
+}
+
+/// Time one module spent in `init` and `start`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleTiming {
+    pub module: &'static str,
+    pub init_ms: Option<u64>,
+    pub start_ms: Option<u64>,
+    pub budget_ms: u64,
+}
+
+impl ModuleTiming {
+    /// Whether either phase took longer than the module's budget
+    pub fn over_budget(&self) -> bool {
+        [self.init_ms, self.start_ms]
+            .into_iter()
+            .flatten()
+            .any(|elapsed| elapsed > self.budget_ms)
+    }
+
+    fn total_ms(&self) -> u64 {
+        self.init_ms.unwrap_or_default() + self.start_ms.unwrap_or_default()
+    }
+}
+
+/// Per-module startup breakdown, in the order modules were initialized
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupReport {
+    pub modules: Vec<ModuleTiming>,
+}
+
+impl StartupReport {
+    /// Time spent in every module's `init` and `start`
+    pub fn total_ms(&self) -> u64 {
+        self.modules.iter().map(ModuleTiming::total_ms).sum()
+    }
+
+    /// Modules that exceeded their budget in either phase
+    pub fn slow(&self) -> Vec<&'static str> {
+        self.modules
+            .iter()
+            .filter(|timing| timing.over_budget())
+            .map(|timing| timing.module)
+            .collect()
+    }
+}
+
+/// One `module=init/start` entry per module, e.g. `db=120ms/4ms books=3ms/-`
+impl fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn phase(elapsed: Option<u64>) -> String {
+            elapsed.map_or_else(|| "-".to_string(), |ms| format!("{}ms", ms))
+        }
+
+        for (index, timing) in self.modules.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(
+                f,
+                "{}={}/{}",
+                timing.module,
+                phase(timing.init_ms),
+                phase(timing.start_ms)
+            )?;
+            if timing.over_budget() {
+                write!(f, "(slow)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(module: &'static str, init_ms: u64, start_ms: Option<u64>) -> ModuleTiming {
+        ModuleTiming {
+            module,
+            init_ms: Some(init_ms),
+            start_ms,
+            budget_ms: 100,
+        }
+    }
+
+    #[test]
+    fn test_budget_overrides_per_module() {
+        let mut settings = StartupSettings::default();
+        settings.modules.insert("db".to_string(), 15000);
+        let budget = StartupBudget::from_settings(&settings);
+
+        assert_eq!(budget.budget_for("db"), Duration::from_secs(15));
+        assert_eq!(budget.budget_for("books"), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_report_flags_slow_modules() {
+        let report = StartupReport {
+            modules: vec![timing("db", 250, Some(4)), timing("books", 3, None)],
+        };
+
+        assert_eq!(report.total_ms(), 257);
+        assert_eq!(report.slow(), vec!["db"]);
+        assert_eq!(report.to_string(), "db=250ms/4ms(slow) books=3ms/-");
+    }
+}
@@ -0,0 +1,254 @@
+//! Background tasks owned by modules
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Owner recorded for tasks spawned through a context the registry did not scope to a module
+pub const KERNEL_OWNER: &str = "kernel";
+
+/// A task that panicked, as recorded by the supervisor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskPanic {
+    pub module: &'static str,
+    pub task: String,
+    pub message: String,
+}
+
+struct TrackedTask {
+    name: String,
+    abort: AbortHandle,
+    /// Finishes once the task has ended and its outcome was logged
+    reported: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Inner {
+    tasks: Mutex<HashMap<&'static str, Vec<TrackedTask>>>,
+    panics: Mutex<Vec<TaskPanic>>,
+}
+
+/// Tracks the background tasks each module spawns so they can be cancelled when it stops
+///
+/// Cheap to clone; clones share the same set of tasks.
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    inner: Arc<Inner>,
+}
+
+impl TaskSupervisor {
+    /// Create a supervisor with no tasks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn `future` on the runtime as a task owned by `module`
+    ///
+    /// Errors returned by the task are logged; panics are logged and recorded in `panics()`.
+    pub fn spawn<F>(&self, module: &'static str, name: impl Into<String>, future: F)
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let handle = tokio::spawn(future);
+        let abort = handle.abort_handle();
+
+        let inner = self.inner.clone();
+        let task = name.clone();
+        let reported = tokio::spawn(async move {
+            match handle.await {
+                Ok(Ok(())) => {
+                    tracing::debug!(module, task = %task, "background task finished");
+                }
+                Ok(Err(error)) => {
+                    tracing::error!(module, task = %task, error = %format!("{:#}", error), "background task failed");
+                }
+                Err(error) if error.is_panic() => {
+                    let message = panic_message(error.into_panic());
+                    tracing::error!(module, task = %task, panic = %message, "background task panicked");
+                    inner
+                        .panics
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push(TaskPanic {
+                            module,
+                            task,
+                            message,
+                        });
+                }
+                Err(_) => {
+                    tracing::debug!(module, task = %task, "background task cancelled");
+                }
+            }
+        });
+
+        let mut tasks = self.lock_tasks();
+        let owned = tasks.entry(module).or_default();
+        owned.retain(|tracked| !tracked.abort.is_finished());
+        owned.push(TrackedTask {
+            name,
+            abort,
+            reported,
+        });
+    }
+
+    /// Number of `module`'s tasks that are still running
+    pub fn running(&self, module: &str) -> usize {
+        self.lock_tasks()
+            .get(module)
+            .map(|owned| {
+                owned
+                    .iter()
+                    .filter(|tracked| !tracked.abort.is_finished())
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Abort every task owned by `module`, returning how many were still running
+    pub fn cancel(&self, module: &str) -> usize {
+        let owned = self.lock_tasks().remove(module).unwrap_or_default();
+        Self::abort_all(module, &owned)
+    }
+
+    /// Abort every task owned by `module` and wait up to `timeout` for them to end,
+    /// returning how many were cancelled and how many were still running afterwards
+    ///
+    /// Aborted tasks only end at their next `.await`, so one blocked in synchronous code
+    /// can outlive the wait.
+    pub async fn cancel_and_join(&self, module: &str, timeout: Duration) -> (usize, usize) {
+        let mut owned = self.lock_tasks().remove(module).unwrap_or_default();
+        let cancelled = Self::abort_all(module, &owned);
+        let joined = tokio::time::timeout(timeout, async {
+            for tracked in &mut owned {
+                let _ = (&mut tracked.reported).await;
+            }
+        })
+        .await;
+        let unfinished = match joined {
+            Ok(()) => 0,
+            Err(_) => owned
+                .iter()
+                .filter(|tracked| !tracked.reported.is_finished())
+                .count(),
+        };
+        (cancelled, unfinished)
+    }
+
+    /// Abort every tracked task, whichever module owns it
+    pub fn cancel_all(&self) -> usize {
+        let tasks = std::mem::take(&mut *self.lock_tasks());
+        tasks
+            .into_iter()
+            .map(|(module, owned)| Self::abort_all(module, &owned))
+            .sum()
+    }
+
+    /// Tasks that panicked since the supervisor was created
+    pub fn panics(&self) -> Vec<TaskPanic> {
+        self.inner
+            .panics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn abort_all(module: &str, owned: &[TrackedTask]) -> usize {
+        let mut cancelled = 0;
+        for tracked in owned {
+            if !tracked.abort.is_finished() {
+                tracing::debug!(module, task = %tracked.name, "cancelling background task");
+                tracked.abort.abort();
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    fn lock_tasks(&self) -> std::sync::MutexGuard<'_, HashMap<&'static str, Vec<TrackedTask>>> {
+        self.inner
+            .tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for TaskSupervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self
+            .inner
+            .tasks
+            .lock()
+            .map(|tasks| tasks.values().map(Vec::len).sum::<usize>())
+            .unwrap_or_default();
+        f.debug_struct("TaskSupervisor")
+            .field("tasks", &count)
+            .finish()
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_module_tasks_only() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("books", "indexer", std::future::pending());
+        supervisor.spawn("books", "cleanup", std::future::pending());
+        supervisor.spawn("users", "sync", std::future::pending());
+
+        assert_eq!(supervisor.running("books"), 2);
+        assert_eq!(supervisor.cancel("books"), 2);
+        settle().await;
+
+        assert_eq!(supervisor.running("books"), 0);
+        assert_eq!(supervisor.running("users"), 1);
+        assert_eq!(supervisor.cancel_all(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_are_not_counted() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("books", "warmup", async { Ok(()) });
+        supervisor.spawn("books", "broken", async { anyhow::bail!("no index") });
+        settle().await;
+
+        assert_eq!(supervisor.running("books"), 0);
+        assert_eq!(supervisor.cancel("books"), 0);
+        assert!(supervisor.panics().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_panics_are_recorded() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("books", "indexer", async { panic!("index corrupted") });
+        settle().await;
+
+        assert_eq!(
+            supervisor.panics(),
+            vec![TaskPanic {
+                module: "books",
+                task: "indexer".to_string(),
+                message: "index corrupted".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! The tenant a unit of work runs for
+//!
+//! The HTTP layer resolves the tenant of each request and runs its handler inside
+//! `scope`, so repositories read it with `current()` instead of threading it through
+//! every call.
+
+use std::future::Future;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+tokio::task_local! {
+    static CURRENT: TenantId;
+}
+
+/// Validated tenant identifier, e.g. `acme`
+///
+/// Lowercase letters, digits, `-` and `_`, so it is safe in subdomains, keys and queries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub fn new(id: impl Into<String>) -> anyhow::Result<Self> {
+        let id = id.into();
+        let valid = !id.is_empty()
+            && id.len() <= 64
+            && id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            bail!(
+                "invalid tenant id '{}'; use up to 64 lowercase letters, digits, '-' or '_'",
+                id
+            );
+        }
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = anyhow::Error;
+
+    fn try_from(id: String) -> anyhow::Result<Self> {
+        Self::new(id)
+    }
+}
+
+impl From<TenantId> for String {
+    fn from(tenant: TenantId) -> Self {
+        tenant.0
+    }
+}
+
+impl std::fmt::Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Run `future` on behalf of `tenant`
+pub async fn scope<F: Future>(tenant: TenantId, future: F) -> F::Output {
+    CURRENT.scope(tenant, future).await
+}
+
+/// The tenant of the surrounding `scope`, if any
+pub fn current() -> Option<TenantId> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenant_ids_are_validated() {
+        assert_eq!(TenantId::new("acme-01").unwrap().as_str(), "acme-01");
+        assert!(TenantId::new("").is_err());
+        assert!(TenantId::new("Acme").is_err());
+        assert!(TenantId::new("acme.evil").is_err());
+        assert!(TenantId::new("a".repeat(65)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_current_follows_scope() {
+        assert_eq!(current(), None);
+        let acme = TenantId::new("acme").unwrap();
+        let seen = scope(acme.clone(), async { current() }).await;
+        assert_eq!(seen, Some(acme));
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use atlas_authz::guard::RequirePermission;
+use atlas_http::{error::AppError, tenant::TenantCtx};
+
+use crate::{WebhookDelivery, WebhookEndpoint, WebhookError, Webhooks};
+
+atlas_authz::permission!(
+    /// Register, inspect and remove the tenant's webhook endpoints
+    pub WebhooksManage = "webhooks:manage"
+);
+
+/// Largest page of delivery history served
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct RegisterEndpoint {
+    url: String,
+    events: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedEndpoint {
+    #[serde(flatten)]
+    endpoint: WebhookEndpoint,
+    /// Shown once; verify deliveries with it
+    secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+}
+
+fn tenant(ctx: &Option<TenantCtx>) -> Option<&atlas_kernel::tenant::TenantId> {
+    ctx.as_ref().map(|ctx| &ctx.id)
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::not_found(format!("webhook endpoint '{}' not found", id))
+}
+
+async fn list_endpoints(
+    State(webhooks): State<Arc<Webhooks>>,
+    _: RequirePermission<WebhooksManage>,
+    ctx: Option<TenantCtx>,
+) -> Result<Json<Vec<WebhookEndpoint>>, AppError> {
+    Ok(Json(webhooks.list(tenant(&ctx)).await?))
+}
+
+async fn register_endpoint(
+    State(webhooks): State<Arc<Webhooks>>,
+    _: RequirePermission<WebhooksManage>,
+    ctx: Option<TenantCtx>,
+    Json(request): Json<RegisterEndpoint>,
+) -> Result<(StatusCode, Json<CreatedEndpoint>), AppError> {
+    let registered = webhooks
+        .register(
+            ctx.map(|ctx| ctx.id),
+            &request.url,
+            request.events,
+            request.description,
+        )
+        .await
+        .map_err(|error| match error {
+            WebhookError::InvalidUrl(_) => AppError::validation(
+                vec![json!({ "field": "url", "error": "invalid" })],
+                error.to_string(),
+            ),
+            WebhookError::NoEvents => AppError::validation(
+                vec![json!({ "field": "events", "error": "required" })],
+                error.to_string(),
+            ),
+            WebhookError::TooManyEndpoints(_) => AppError::conflict(Vec::new(), error.to_string()),
+            WebhookError::Store(error) => AppError::Internal(error),
+        })?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedEndpoint {
+            endpoint: registered.endpoint,
+            secret: registered.secret,
+        }),
+    ))
+}
+
+async fn show_endpoint(
+    State(webhooks): State<Arc<Webhooks>>,
+    _: RequirePermission<WebhooksManage>,
+    ctx: Option<TenantCtx>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookEndpoint>, AppError> {
+    webhooks
+        .get(tenant(&ctx), &id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+async fn remove_endpoint(
+    State(webhooks): State<Arc<Webhooks>>,
+    _: RequirePermission<WebhooksManage>,
+    ctx: Option<TenantCtx>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if webhooks.remove(tenant(&ctx), &id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(&id))
+    }
+}
+
+async fn list_deliveries(
+    State(webhooks): State<Arc<Webhooks>>,
+    _: RequirePermission<WebhooksManage>,
+    ctx: Option<TenantCtx>,
+    Path(id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let limit = query.limit.unwrap_or(20);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(AppError::validation(
+            vec![json!({ "field": "limit", "error": "out_of_range" })],
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    webhooks
+        .deliveries(tenant(&ctx), &id, limit)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+/// Endpoint management and delivery history for the caller's tenant
+///
+/// Every route requires the `webhooks:manage` permission.
+pub fn webhook_routes(webhooks: Arc<Webhooks>) -> Router {
+    Router::new()
+        .route("/", get(list_endpoints).post(register_endpoint))
+        .route("/{id}", get(show_endpoint).delete(remove_endpoint))
+        .route("/{id}/deliveries", get(list_deliveries))
+        .with_state(webhooks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{settings, FakeTransport};
+    use crate::MemoryWebhookStore;
+    use atlas_authz::testing::{as_subject, resources, with_context};
+    use atlas_http::testing;
+    use atlas_kernel::clock::SystemClock;
+    use serde_json::Value;
+
+    fn router() -> Router {
+        let webhooks = Arc::new(Webhooks::new(
+            Arc::new(MemoryWebhookStore::new()),
+            Arc::new(FakeTransport::default()),
+            Arc::new(SystemClock),
+            settings(),
+        ));
+        with_context(
+            webhook_routes(webhooks),
+            resources("p, ops, webhooks, manage"),
+        )
+    }
+
+    async fn send(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        subject: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = testing::request(method, uri, body.unwrap_or(Value::Null));
+        testing::send(router, as_subject(request, subject)).await
+    }
+
+    #[tokio::test]
+    async fn test_register_list_and_remove_endpoints() {
+        let router = router();
+        let request = json!({ "url": "https://hooks.example.com", "events": ["books."] });
+
+        let (status, _) = send(&router, "POST", "/", "mallory", Some(request.clone())).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, created) = send(&router, "POST", "/", "ops", Some(request)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created["secret"].as_str().unwrap().starts_with("whsec_"));
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let (status, listed) = send(&router, "GET", "/", "ops", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["id"], id);
+        assert!(listed[0].get("secret").is_none());
+
+        let (status, history) =
+            send(&router, "GET", &format!("/{}/deliveries", id), "ops", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history, json!([]));
+
+        let (status, _) = send(&router, "DELETE", &format!("/{}", id), "ops", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, "GET", &format!("/{}", id), "ops", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_registration_is_rejected() {
+        let router = router();
+
+        let (status, body) = send(
+            &router,
+            "POST",
+            "/",
+            "ops",
+            Some(json!({ "url": "http://hooks.example.com", "events": ["books."] })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["details"][0]["field"], "url");
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use atlas_http::error::AppError;
+
+use super::{InboundError, InboundReceiver, Received};
+
+impl From<InboundError> for AppError {
+    fn from(error: InboundError) -> Self {
+        match error {
+            InboundError::Missing(_) => AppError::unauthorized(error.to_string()),
+            InboundError::Stale | InboundError::Mismatch => {
+                tracing::debug!(error = %error, "rejected incoming webhook");
+                AppError::unauthorized("invalid webhook signature")
+            }
+            InboundError::Malformed(_) => AppError::bad_request(error.to_string()),
+            InboundError::Internal(error) => AppError::Internal(error),
+        }
+    }
+}
+
+async fn receive(
+    State(receiver): State<Arc<InboundReceiver>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    Ok(match receiver.receive(&headers, &body).await? {
+        Received::Emitted(event) => (StatusCode::ACCEPTED, Json(json!({ "event": event }))),
+        Received::Duplicate => (StatusCode::OK, Json(json!({ "duplicate": true }))),
+    })
+}
+
+/// `POST /` receiving `receiver`'s webhooks
+///
+/// Answers 202 once the event is emitted and 200 for a redelivery, so the sender stops
+/// retrying either way. Bodies over the receiver's `max_body_bytes` are refused.
+pub fn receiver_routes(receiver: Arc<InboundReceiver>) -> Router {
+    let limit = receiver.max_body_bytes();
+    Router::new()
+        .route("/", post(receive))
+        .layer(DefaultBodyLimit::max(limit))
+        .with_state(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inbound::tests::{settings, stripe_headers, NOW, STRIPE_BODY};
+    use crate::inbound::MemoryReceivedWebhookStore;
+    use atlas_events::EventBus;
+    use atlas_kernel::{clock::ManualClock, settings::InboundWebhookKind};
+    use axum::{body::Body, http::Request};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let mut settings = settings(InboundWebhookKind::Stripe);
+        settings.max_body_bytes = 256;
+        Router::new().nest(
+            "/stripe",
+            receiver_routes(Arc::new(InboundReceiver::from_settings(
+                "stripe",
+                &settings,
+                Arc::new(MemoryReceivedWebhookStore::new()),
+                Arc::new(EventBus::new(16)),
+                Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(NOW))),
+            ))),
+        )
+    }
+
+    fn request(headers: HeaderMap, body: String) -> Request<Body> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/stripe")
+            .body(Body::from(body))
+            .unwrap();
+        *request.headers_mut() = headers;
+        request
+    }
+
+    #[tokio::test]
+    async fn test_statuses_for_new_duplicate_and_forged_webhooks() {
+        let router = router();
+        let send = |headers: HeaderMap, body: &str| {
+            router.clone().oneshot(request(headers, body.to_string()))
+        };
+
+        let response = send(stripe_headers(STRIPE_BODY, NOW), STRIPE_BODY)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let response = send(stripe_headers(STRIPE_BODY, NOW), STRIPE_BODY)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(stripe_headers("{}", NOW), STRIPE_BODY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let large = format!(r#"{{"id":"evt_2","type":"x","pad":"{}"}}"#, "a".repeat(300));
+        let response = send(stripe_headers(&large, NOW), &large).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
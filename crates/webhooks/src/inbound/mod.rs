@@ -0,0 +1,569 @@
+//! Incoming third-party webhooks
+//!
+//! An `InboundReceiver` takes the raw body of a webhook request, checks the sender's
+//! signature with a `WebhookVerifier`, drops redeliveries of an event id it has already
+//! seen, and emits the payload on the event bus as `{prefix}.{type}`, such as
+//! `stripe.invoice.paid`. The `inbound_webhooks` module mounts one receiver per source
+//! under `[webhooks.inbound]`; modules with their own senders mount `receiver_routes`.
+
+mod http;
+mod module;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use serde_json::Value;
+
+use atlas_authz::signatures::{self, sign, sign_body, signatures_match};
+use atlas_events::EventBus;
+use atlas_kernel::{
+    settings::{InboundWebhookKind, InboundWebhookSettings},
+    Clock,
+};
+
+use crate::{DELIVERY_HEADER, EVENT_HEADER};
+
+pub use http::receiver_routes;
+pub use module::{create_module, InboundWebhooksModule};
+
+pub const STRIPE_SIGNATURE_HEADER: &str = "stripe-signature";
+pub const GITHUB_SIGNATURE_HEADER: &str = "x-hub-signature-256";
+pub const GITHUB_DELIVERY_HEADER: &str = "x-github-delivery";
+pub const GITHUB_EVENT_HEADER: &str = "x-github-event";
+
+/// A webhook whose signature checked out
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundEvent {
+    /// The sender's id for the event, stable across redeliveries
+    pub id: String,
+    /// The sender's event type, such as `invoice.paid` or `push`
+    pub kind: String,
+    /// The request body
+    pub payload: Value,
+}
+
+/// Why a webhook was rejected
+#[derive(Debug, thiserror::Error)]
+pub enum InboundError {
+    #[error("missing {0} header")]
+    Missing(&'static str),
+    #[error("webhook timestamp is missing or outside the accepted window")]
+    Stale,
+    #[error("webhook signature does not match")]
+    Mismatch,
+    #[error("malformed webhook: {0}")]
+    Malformed(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Checks the signature of one sender's webhooks and reads the event out of them
+pub trait WebhookVerifier: Send + Sync {
+    /// The event in `body`, if it was signed by the sender; `now` is in Unix seconds
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<InboundEvent, InboundError>;
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str, InboundError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or(InboundError::Missing(name))
+}
+
+fn parse_body(body: &[u8]) -> Result<Value, InboundError> {
+    serde_json::from_slice(body)
+        .map_err(|error| InboundError::Malformed(format!("body is not JSON: {}", error)))
+}
+
+fn check_timestamp(timestamp: &str, now: u64, tolerance_secs: u64) -> Result<u64, InboundError> {
+    let timestamp: u64 = timestamp.parse().map_err(|_| InboundError::Stale)?;
+    if now.abs_diff(timestamp) > tolerance_secs {
+        return Err(InboundError::Stale);
+    }
+    Ok(timestamp)
+}
+
+/// Stripe: `Stripe-Signature: t={timestamp},v1={hex}` over `{timestamp}.{body}`
+///
+/// Several `v1` entries are sent while a secret is being rolled; any of them may match.
+/// The event id and type are the `id` and `type` fields of the body.
+pub struct StripeVerifier {
+    secret: String,
+    tolerance_secs: u64,
+}
+
+impl StripeVerifier {
+    pub fn new(secret: impl Into<String>, tolerance_secs: u64) -> Self {
+        Self {
+            secret: secret.into(),
+            tolerance_secs,
+        }
+    }
+}
+
+impl WebhookVerifier for StripeVerifier {
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<InboundEvent, InboundError> {
+        let signature = header(headers, STRIPE_SIGNATURE_HEADER)?;
+        let mut timestamp = None;
+        let mut candidates = Vec::new();
+        for part in signature.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = Some(value),
+                Some(("v1", value)) => candidates.push(value),
+                _ => {}
+            }
+        }
+        let timestamp = check_timestamp(
+            timestamp.ok_or(InboundError::Stale)?,
+            now,
+            self.tolerance_secs,
+        )?;
+        let expected = sign(&self.secret, timestamp, body);
+        let expected = expected.trim_start_matches("sha256=");
+        if !candidates
+            .iter()
+            .any(|candidate| signatures_match(expected, candidate))
+        {
+            return Err(InboundError::Mismatch);
+        }
+
+        let payload = parse_body(body)?;
+        let field = |name: &str| {
+            payload[name]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| InboundError::Malformed(format!("body has no '{}'", name)))
+        };
+        Ok(InboundEvent {
+            id: field("id")?,
+            kind: field("type")?,
+            payload,
+        })
+    }
+}
+
+/// GitHub: `X-Hub-Signature-256: sha256={hex}` over the body
+///
+/// GitHub signs no timestamp, so replays are only caught by the `X-GitHub-Delivery` id.
+pub struct GithubVerifier {
+    secret: String,
+}
+
+impl GithubVerifier {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+}
+
+impl WebhookVerifier for GithubVerifier {
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        _now: u64,
+    ) -> Result<InboundEvent, InboundError> {
+        let signature = header(headers, GITHUB_SIGNATURE_HEADER)?;
+        if !signatures_match(&sign_body(&self.secret, body), signature) {
+            return Err(InboundError::Mismatch);
+        }
+        Ok(InboundEvent {
+            id: header(headers, GITHUB_DELIVERY_HEADER)?.to_string(),
+            kind: header(headers, GITHUB_EVENT_HEADER)?.to_string(),
+            payload: parse_body(body)?,
+        })
+    }
+}
+
+/// Another ATLAS deployment's outgoing webhooks
+///
+/// `x-atlas-signature` over `{x-atlas-timestamp}.{body}`; the id and type are the
+/// `x-atlas-webhook-id` and `x-atlas-event` headers.
+pub struct AtlasVerifier {
+    secret: String,
+    tolerance_secs: u64,
+}
+
+impl AtlasVerifier {
+    pub fn new(secret: impl Into<String>, tolerance_secs: u64) -> Self {
+        Self {
+            secret: secret.into(),
+            tolerance_secs,
+        }
+    }
+}
+
+impl WebhookVerifier for AtlasVerifier {
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<InboundEvent, InboundError> {
+        let timestamp = check_timestamp(
+            header(headers, signatures::TIMESTAMP_HEADER)?,
+            now,
+            self.tolerance_secs,
+        )?;
+        let signature = header(headers, signatures::SIGNATURE_HEADER)?;
+        if !signatures_match(&sign(&self.secret, timestamp, body), signature) {
+            return Err(InboundError::Mismatch);
+        }
+        Ok(InboundEvent {
+            id: header(headers, DELIVERY_HEADER)?.to_string(),
+            kind: header(headers, EVENT_HEADER)?.to_string(),
+            payload: parse_body(body)?,
+        })
+    }
+}
+
+/// The verifier for a configured source
+pub fn verifier(settings: &InboundWebhookSettings) -> Box<dyn WebhookVerifier> {
+    match settings.kind {
+        InboundWebhookKind::Stripe => Box::new(StripeVerifier::new(
+            settings.secret.clone(),
+            settings.tolerance_secs,
+        )),
+        InboundWebhookKind::Github => Box::new(GithubVerifier::new(settings.secret.clone())),
+        InboundWebhookKind::Atlas => Box::new(AtlasVerifier::new(
+            settings.secret.clone(),
+            settings.tolerance_secs,
+        )),
+    }
+}
+
+/// Event ids already received, for dropping redeliveries
+///
+/// Register one to share them between instances; otherwise each instance remembers its
+/// own in memory.
+#[async_trait]
+pub trait ReceivedWebhookStore: Send + Sync {
+    /// Record `id` from `source` for `ttl`, returning `false` if it is already recorded
+    async fn remember(
+        &self,
+        source: &str,
+        id: &str,
+        now: SystemTime,
+        ttl: Duration,
+    ) -> anyhow::Result<bool>;
+
+    /// Drop a recorded id so a redelivery is accepted
+    async fn forget(&self, source: &str, id: &str) -> anyhow::Result<()>;
+}
+
+/// Received ids in process memory, pruned as they expire
+#[derive(Default)]
+pub struct MemoryReceivedWebhookStore {
+    received: Mutex<HashMap<(String, String), SystemTime>>,
+}
+
+impl MemoryReceivedWebhookStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ReceivedWebhookStore for MemoryReceivedWebhookStore {
+    async fn remember(
+        &self,
+        source: &str,
+        id: &str,
+        now: SystemTime,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        let mut received = self
+            .received
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        received.retain(|_, expires_at| *expires_at > now);
+        let key = (source.to_string(), id.to_string());
+        if received.contains_key(&key) {
+            return Ok(false);
+        }
+        received.insert(key, now + ttl);
+        Ok(true)
+    }
+
+    async fn forget(&self, source: &str, id: &str) -> anyhow::Result<()> {
+        self.received
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&(source.to_string(), id.to_string()));
+        Ok(())
+    }
+}
+
+/// What became of a verified webhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Received {
+    /// Emitted under this event name
+    Emitted(String),
+    /// The event id was received before; nothing was emitted
+    Duplicate,
+}
+
+/// Verifies, deduplicates and emits one source's webhooks
+pub struct InboundReceiver {
+    source: String,
+    prefix: String,
+    verifier: Box<dyn WebhookVerifier>,
+    received: Arc<dyn ReceivedWebhookStore>,
+    bus: Arc<EventBus>,
+    clock: Arc<dyn Clock>,
+    dedup_ttl: Duration,
+    max_body_bytes: usize,
+}
+
+impl InboundReceiver {
+    /// A receiver checking signatures with `verifier`; `settings.kind` and `secret` are
+    /// not used
+    pub fn new(
+        source: impl Into<String>,
+        verifier: Box<dyn WebhookVerifier>,
+        settings: &InboundWebhookSettings,
+        received: Arc<dyn ReceivedWebhookStore>,
+        bus: Arc<EventBus>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let source = source.into();
+        Self {
+            prefix: settings
+                .event_prefix
+                .clone()
+                .unwrap_or_else(|| source.clone()),
+            source,
+            verifier,
+            received,
+            bus,
+            clock,
+            dedup_ttl: Duration::from_secs(settings.dedup_ttl_secs),
+            max_body_bytes: settings.max_body_bytes,
+        }
+    }
+
+    /// A receiver for a source configured under `[webhooks.inbound]`
+    pub fn from_settings(
+        source: impl Into<String>,
+        settings: &InboundWebhookSettings,
+        received: Arc<dyn ReceivedWebhookStore>,
+        bus: Arc<EventBus>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self::new(source, verifier(settings), settings, received, bus, clock)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    /// Verify a webhook and emit it unless its id was already received
+    ///
+    /// An id is recorded before the event is emitted and forgotten again if emitting
+    /// fails, so the sender's retry is accepted.
+    pub async fn receive(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Received, InboundError> {
+        let now = self.clock.now();
+        let unix = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let event = self.verifier.verify(headers, body, unix)?;
+        if !self
+            .received
+            .remember(&self.source, &event.id, now, self.dedup_ttl)
+            .await?
+        {
+            tracing::debug!(source = %self.source, id = %event.id, "dropped redelivered webhook");
+            return Ok(Received::Duplicate);
+        }
+
+        let name = format!("{}.{}", self.prefix, event.kind);
+        if let Err(error) = self.bus.emit(&name, &event.payload) {
+            self.received.forget(&self.source, &event.id).await?;
+            return Err(error
+                .context(format!("failed to emit webhook {} as '{}'", event.id, name))
+                .into());
+        }
+        tracing::debug!(source = %self.source, id = %event.id, event = %name, "received webhook");
+        Ok(Received::Emitted(name))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use atlas_events::NamedEvent;
+    use atlas_kernel::clock::ManualClock;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    pub(crate) const SECRET: &str = "whsec_inbound-secret";
+    pub(crate) const NOW: u64 = 1_700_000_000;
+    pub(crate) const STRIPE_BODY: &str =
+        r#"{"id":"evt_1","type":"invoice.paid","data":{"object":{"id":"in_1"}}}"#;
+
+    pub(crate) fn stripe_headers(body: &str, timestamp: u64) -> HeaderMap {
+        let signature = sign(SECRET, timestamp, body.as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            STRIPE_SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!(
+                "t={},v1={},v0=ignored",
+                timestamp,
+                signature.trim_start_matches("sha256=")
+            ))
+            .unwrap(),
+        );
+        headers
+    }
+
+    pub(crate) fn settings(kind: InboundWebhookKind) -> InboundWebhookSettings {
+        InboundWebhookSettings {
+            kind,
+            secret: SECRET.to_string(),
+            ..InboundWebhookSettings::default()
+        }
+    }
+
+    fn receiver(bus: Arc<EventBus>) -> InboundReceiver {
+        InboundReceiver::from_settings(
+            "stripe",
+            &settings(InboundWebhookKind::Stripe),
+            Arc::new(MemoryReceivedWebhookStore::new()),
+            bus,
+            Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(NOW))),
+        )
+    }
+
+    #[test]
+    fn test_stripe_signature_and_timestamp_are_checked() {
+        let verifier = StripeVerifier::new(SECRET, 300);
+        let body = STRIPE_BODY.as_bytes();
+
+        let event = verifier
+            .verify(&stripe_headers(STRIPE_BODY, NOW), body, NOW + 10)
+            .unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.kind, "invoice.paid");
+        assert_eq!(event.payload["data"]["object"]["id"], "in_1");
+
+        assert!(matches!(
+            verifier.verify(&stripe_headers(STRIPE_BODY, NOW), body, NOW + 301),
+            Err(InboundError::Stale)
+        ));
+        assert!(matches!(
+            verifier.verify(&stripe_headers("{}", NOW), body, NOW),
+            Err(InboundError::Mismatch)
+        ));
+        assert!(matches!(
+            verifier.verify(&HeaderMap::new(), body, NOW),
+            Err(InboundError::Missing(STRIPE_SIGNATURE_HEADER))
+        ));
+    }
+
+    #[test]
+    fn test_github_signature_and_delivery_headers() {
+        let verifier = GithubVerifier::new(SECRET);
+        let body = br#"{"zen":"Keep it logically awesome."}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            GITHUB_SIGNATURE_HEADER,
+            HeaderValue::from_str(&sign_body(SECRET, body)).unwrap(),
+        );
+        headers.insert(GITHUB_DELIVERY_HEADER, HeaderValue::from_static("d-1"));
+
+        assert!(matches!(
+            verifier.verify(&headers, body, NOW),
+            Err(InboundError::Missing(GITHUB_EVENT_HEADER))
+        ));
+        headers.insert(GITHUB_EVENT_HEADER, HeaderValue::from_static("ping"));
+        let event = verifier.verify(&headers, body, NOW).unwrap();
+        assert_eq!((event.id.as_str(), event.kind.as_str()), ("d-1", "ping"));
+        assert!(matches!(
+            verifier.verify(&headers, b"{}", NOW),
+            Err(InboundError::Mismatch)
+        ));
+    }
+
+    #[test]
+    fn test_atlas_verifier_reads_outgoing_webhook_headers() {
+        let verifier = AtlasVerifier::new(SECRET, 300);
+        let body = br#"{"id":"whd_1","event":"books.created","payload":{}}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert(signatures::TIMESTAMP_HEADER, NOW.into());
+        headers.insert(
+            signatures::SIGNATURE_HEADER,
+            HeaderValue::from_str(&sign(SECRET, NOW, body)).unwrap(),
+        );
+        headers.insert(DELIVERY_HEADER, HeaderValue::from_static("whd_1"));
+        headers.insert(EVENT_HEADER, HeaderValue::from_static("books.created"));
+
+        let event = verifier.verify(&headers, body, NOW).unwrap();
+        assert_eq!(event.id, "whd_1");
+        assert_eq!(event.kind, "books.created");
+    }
+
+    #[tokio::test]
+    async fn test_receiver_emits_once_per_event_id() {
+        let bus = Arc::new(EventBus::new(16));
+        let mut subscription = bus.subscribe::<NamedEvent>();
+        let receiver = receiver(bus);
+        let headers = stripe_headers(STRIPE_BODY, NOW);
+
+        assert_eq!(
+            receiver
+                .receive(&headers, STRIPE_BODY.as_bytes())
+                .await
+                .unwrap(),
+            Received::Emitted("stripe.invoice.paid".to_string())
+        );
+        assert_eq!(
+            receiver
+                .receive(&headers, STRIPE_BODY.as_bytes())
+                .await
+                .unwrap(),
+            Received::Duplicate
+        );
+
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(&*event.name, "stripe.invoice.paid");
+        assert_eq!(event.payload["id"], json!("evt_1"));
+        assert!(subscription.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_received_ids_expire() {
+        let store = MemoryReceivedWebhookStore::new();
+        let now = UNIX_EPOCH + Duration::from_secs(NOW);
+        let ttl = Duration::from_secs(60);
+
+        assert!(store.remember("github", "d-1", now, ttl).await.unwrap());
+        assert!(!store.remember("github", "d-1", now, ttl).await.unwrap());
+        assert!(store.remember("stripe", "d-1", now, ttl).await.unwrap());
+        assert!(store
+            .remember("github", "d-1", now + ttl, ttl)
+            .await
+            .unwrap());
+    }
+}
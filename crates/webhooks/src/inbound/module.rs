@@ -0,0 +1,214 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_events::EventsExt;
+use atlas_kernel::{InitCtx, Migration, Module};
+
+use super::{receiver_routes, InboundReceiver, MemoryReceivedWebhookStore, ReceivedWebhookStore};
+
+/// Core module receiving the third-party webhooks configured under `[webhooks.inbound]`
+///
+/// Mounts `POST /{source}` for each configured source and does nothing when there are
+/// none. Verified webhooks are emitted as named events for other modules to handle.
+#[derive(Default)]
+pub struct InboundWebhooksModule {
+    receivers: OnceLock<Vec<Arc<InboundReceiver>>>,
+}
+
+#[async_trait]
+impl Module for InboundWebhooksModule {
+    fn name(&self) -> &'static str {
+        "inbound_webhooks"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Verified third-party webhooks emitted as named events")
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["events"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let sources = &ctx.settings.webhooks.inbound;
+        if sources.is_empty() {
+            tracing::debug!("no incoming webhook sources configured");
+            return Ok(());
+        }
+
+        let received = match ctx.resources.get::<Arc<dyn ReceivedWebhookStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => {
+                tracing::warn!(
+                    "no shared received-webhook store registered; redeliveries are only detected per instance"
+                );
+                Arc::new(MemoryReceivedWebhookStore::new())
+            }
+        };
+        let bus = ctx.events()?;
+        let mut receivers: Vec<Arc<InboundReceiver>> = sources
+            .iter()
+            .map(|(source, settings)| {
+                tracing::info!(source, kind = ?settings.kind, "receiving webhooks");
+                Arc::new(InboundReceiver::from_settings(
+                    source.as_str(),
+                    settings,
+                    received.clone(),
+                    bus.clone(),
+                    ctx.clock.clone(),
+                ))
+            })
+            .collect();
+        receivers.sort_by(|a, b| a.source().cmp(b.source()));
+        self.receivers
+            .set(receivers)
+            .map_err(|_| anyhow::anyhow!("inbound webhooks module initialized twice"))?;
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        self.receivers
+            .get()
+            .into_iter()
+            .flatten()
+            .fold(Router::new(), |router, receiver| {
+                router.nest(
+                    &format!("/{}", receiver.source()),
+                    receiver_routes(receiver.clone()),
+                )
+            })
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_received_webhook",
+            up: "DEFINE TABLE received_webhook SCHEMAFULL;
+DEFINE FIELD source ON received_webhook TYPE string;
+DEFINE FIELD event_id ON received_webhook TYPE string;
+DEFINE FIELD expires_at ON received_webhook TYPE datetime;
+DEFINE INDEX received_webhook_id ON received_webhook FIELDS source, event_id UNIQUE;",
+            down: Some("REMOVE TABLE received_webhook;"),
+        }]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let sources: Vec<&str> = self
+            .receivers
+            .get()
+            .into_iter()
+            .flatten()
+            .map(|receiver| receiver.source())
+            .collect();
+        Some(json!({
+            "tags": [
+                { "name": "Inbound webhooks", "description": "Webhooks from third-party senders" }
+            ],
+            "paths": {
+                "/{source}": {
+                    "post": {
+                        "summary": "Receive a webhook",
+                        "description": "Checks the signature the source's sender uses (Stripe, GitHub or ATLAS) against the raw body, then emits the body as the event `{event_prefix}.{type}`. Redeliveries of an event id are acknowledged without emitting again.",
+                        "tags": ["Inbound webhooks"],
+                        "parameters": [
+                            { "name": "source", "in": "path", "required": true, "schema": { "type": "string", "enum": sources } }
+                        ],
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        },
+                        "responses": {
+                            "200": { "description": "Already received" },
+                            "202": {
+                                "description": "Emitted",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "object",
+                                            "properties": { "event": { "type": "string", "example": "stripe.invoice.paid" } }
+                                        }
+                                    }
+                                }
+                            },
+                            "400": { "description": "The body is not the sender's event format" },
+                            "401": { "description": "Missing or invalid signature" },
+                            "413": { "description": "Body larger than the source's `max_body_bytes`" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Create a new instance of the inbound webhooks module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(InboundWebhooksModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inbound::tests::{settings, stripe_headers, NOW, STRIPE_BODY};
+    use atlas_events::NamedEvent;
+    use atlas_kernel::{
+        clock::ManualClock,
+        registry::priority,
+        settings::{InboundWebhookKind, InboundWebhookSettings, Settings},
+        ModuleRegistry,
+    };
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_configured_sources_are_mounted_and_emit_events() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(atlas_events::create_module(), priority::EVENTS)
+            .unwrap();
+        let module = Arc::new(InboundWebhooksModule::default());
+        registry.register_core(module.clone()).unwrap();
+        let mut settings = Settings::default();
+        settings.webhooks.inbound.insert(
+            "billing".to_string(),
+            InboundWebhookSettings {
+                event_prefix: Some("stripe".to_string()),
+                ..self::settings(InboundWebhookKind::Stripe)
+            },
+        );
+        let ctx = registry
+            .init_ctx(Arc::new(settings))
+            .with_clock(Arc::new(ManualClock::new(
+                UNIX_EPOCH + Duration::from_secs(NOW),
+            )));
+        registry.init_core_modules(&ctx).await.unwrap();
+        let mut subscription = ctx.events().unwrap().subscribe::<NamedEvent>();
+
+        assert_eq!(
+            module.openapi().unwrap()["paths"]["/{source}"]["post"]["parameters"][0]["schema"]
+                ["enum"],
+            json!(["billing"])
+        );
+        let router = module.routes();
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/billing")
+            .body(Body::from(STRIPE_BODY))
+            .unwrap();
+        *request.headers_mut() = stripe_headers(STRIPE_BODY, NOW);
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            &*subscription.recv().await.unwrap().name,
+            "stripe.invoice.paid"
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde_json::json;
+
+use atlas_events::{EventsExt, NamedEvent};
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SecurityScheme};
+
+use crate::{webhook_routes, MemoryWebhookStore, WebhookStore, WebhookTransport, Webhooks};
+
+/// Core module delivering named events to the webhook endpoints tenants register
+///
+/// Does nothing unless `webhooks.enabled` is set. Deliveries go through the registered
+/// `Arc<dyn WebhookTransport>` or, with the `http-transport` feature, `HttpTransport`.
+/// Publishes `Webhooks` as a shared resource; on start it delivers every named event
+/// emitted in this process, each endpoint in its own task. Events received through a
+/// transport were delivered by the process that emitted them, so they are skipped.
+#[derive(Default)]
+pub struct WebhooksModule {
+    webhooks: OnceLock<Arc<Webhooks>>,
+}
+
+#[async_trait]
+impl Module for WebhooksModule {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Signed outgoing webhooks for named events")
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["events"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let settings = &ctx.settings.webhooks;
+        if !settings.enabled {
+            tracing::debug!("webhooks disabled");
+            return Ok(());
+        }
+
+        let transport = match ctx.resources.get::<Arc<dyn WebhookTransport>>() {
+            Some(transport) => transport.as_ref().clone(),
+            #[cfg(feature = "http-transport")]
+            None => Arc::new(crate::HttpTransport::new()),
+            #[cfg(not(feature = "http-transport"))]
+            None => anyhow::bail!(
+                "webhooks is enabled but no WebhookTransport resource is registered; \
+                 register one or enable the http-transport feature"
+            ),
+        };
+        let store = match ctx.resources.get::<Arc<dyn WebhookStore>>() {
+            Some(store) => store.as_ref().clone(),
+            None => {
+                tracing::warn!(
+                    "no persistent webhook store registered; endpoints are kept in memory"
+                );
+                Arc::new(MemoryWebhookStore::new())
+            }
+        };
+        let webhooks = Arc::new(Webhooks::new(
+            store,
+            transport,
+            ctx.clock.clone(),
+            settings.clone(),
+        ));
+        ctx.resources.insert_arc(webhooks.clone());
+        self.webhooks
+            .set(webhooks)
+            .map_err(|_| anyhow::anyhow!("webhooks module initialized twice"))?;
+        Ok(())
+    }
+
+    async fn start(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let Some(webhooks) = self.webhooks.get().cloned() else {
+            return Ok(());
+        };
+        let mut subscription = ctx.events()?.subscribe::<NamedEvent>();
+        ctx.spawn("dispatcher", async move {
+            // Dropping the set when the module stops cancels deliveries still retrying
+            let mut deliveries = tokio::task::JoinSet::new();
+            while let Some(event) = subscription.recv().await {
+                while deliveries.try_join_next().is_some() {}
+                if event.origin.is_some() {
+                    continue;
+                }
+                let endpoints = match webhooks.subscribers(&event).await {
+                    Ok(endpoints) => endpoints,
+                    Err(error) => {
+                        tracing::warn!(
+                            event = %event.name,
+                            error = format!("{:#}", error),
+                            "failed to look up webhook endpoints"
+                        );
+                        continue;
+                    }
+                };
+                for endpoint in endpoints {
+                    let webhooks = webhooks.clone();
+                    let event = event.clone();
+                    deliveries.spawn(async move {
+                        if let Err(error) = webhooks.deliver(&endpoint, &event).await {
+                            tracing::warn!(
+                                endpoint_id = %endpoint.id,
+                                event = %event.name,
+                                error = format!("{:#}", error),
+                                "failed to record webhook delivery"
+                            );
+                        }
+                    });
+                }
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.webhooks.get() {
+            Some(webhooks) => webhook_routes(webhooks.clone()),
+            None => Router::new(),
+        }
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        vec![RouteSecurity {
+            method: "*",
+            path: "*",
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission: Some("webhooks:manage"),
+        }]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_create_webhooks",
+            up: "DEFINE TABLE webhook_endpoint SCHEMAFULL;
+DEFINE FIELD tenant ON webhook_endpoint TYPE option<string>;
+DEFINE FIELD url ON webhook_endpoint TYPE string;
+DEFINE FIELD events ON webhook_endpoint TYPE array<string>;
+DEFINE FIELD description ON webhook_endpoint TYPE option<string>;
+DEFINE FIELD secret ON webhook_endpoint TYPE string;
+DEFINE FIELD created_at ON webhook_endpoint TYPE datetime;
+DEFINE INDEX webhook_endpoint_tenant ON webhook_endpoint FIELDS tenant;
+DEFINE TABLE webhook_delivery SCHEMAFULL;
+DEFINE FIELD endpoint_id ON webhook_delivery TYPE string;
+DEFINE FIELD event ON webhook_delivery TYPE string;
+DEFINE FIELD version ON webhook_delivery TYPE option<int>;
+DEFINE FIELD payload ON webhook_delivery FLEXIBLE TYPE any;
+DEFINE FIELD status ON webhook_delivery TYPE string;
+DEFINE FIELD attempts ON webhook_delivery FLEXIBLE TYPE array<object>;
+DEFINE FIELD created_at ON webhook_delivery TYPE datetime;
+DEFINE INDEX webhook_delivery_endpoint ON webhook_delivery FIELDS endpoint_id, created_at;",
+            down: Some(
+                "REMOVE TABLE webhook_endpoint;
+REMOVE TABLE webhook_delivery;",
+            ),
+        }]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let id =
+            json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } });
+        let endpoint = json!({ "$ref": "#/components/schemas/WebhookEndpoint" });
+        Some(json!({
+            "tags": [
+                { "name": "Webhooks", "description": "Signed event deliveries to tenant endpoints" }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List webhook endpoints",
+                        "tags": ["Webhooks"],
+                        "responses": {
+                            "200": {
+                                "description": "The tenant's endpoints",
+                                "content": { "application/json": { "schema": { "type": "array", "items": endpoint } } }
+                            }
+                        }
+                    },
+                    "post": {
+                        "summary": "Register a webhook endpoint",
+                        "description": "The response carries the signing secret, which is not shown again. Deliveries are signed with `x-atlas-signature: sha256={hex}`, the HMAC-SHA256 of `{x-atlas-timestamp}.{body}` under that secret.",
+                        "tags": ["Webhooks"],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["url", "events"],
+                                        "properties": {
+                                            "url": { "type": "string", "format": "uri", "example": "https://hooks.example.com/atlas" },
+                                            "events": {
+                                                "type": "array",
+                                                "items": { "type": "string" },
+                                                "description": "Event names, prefixes ending in `.`, or `*`",
+                                                "example": ["books."]
+                                            },
+                                            "description": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "201": {
+                                "description": "Registered",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "allOf": [
+                                                endpoint,
+                                                {
+                                                    "type": "object",
+                                                    "required": ["secret"],
+                                                    "properties": { "secret": { "type": "string", "example": "whsec_3f2a..." } }
+                                                }
+                                            ]
+                                        }
+                                    }
+                                }
+                            },
+                            "409": { "description": "The tenant has registered the maximum number of endpoints" },
+                            "422": { "description": "Invalid URL or no event filters" }
+                        }
+                    }
+                },
+                "/{id}": {
+                    "parameters": [id.clone()],
+                    "get": {
+                        "summary": "Show a webhook endpoint",
+                        "tags": ["Webhooks"],
+                        "responses": {
+                            "200": { "description": "The endpoint", "content": { "application/json": { "schema": endpoint } } },
+                            "404": { "description": "No such endpoint for this tenant" }
+                        }
+                    },
+                    "delete": {
+                        "summary": "Remove a webhook endpoint",
+                        "description": "Its delivery history is removed with it.",
+                        "tags": ["Webhooks"],
+                        "responses": {
+                            "204": { "description": "Removed" },
+                            "404": { "description": "No such endpoint for this tenant" }
+                        }
+                    }
+                },
+                "/{id}/deliveries": {
+                    "parameters": [id],
+                    "get": {
+                        "summary": "List deliveries to an endpoint",
+                        "description": "Newest first, each with the outcome of every attempt.",
+                        "tags": ["Webhooks"],
+                        "parameters": [
+                            { "name": "limit", "in": "query", "required": false, "schema": { "type": "integer", "minimum": 1, "maximum": 100, "default": 20 } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Delivery history",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "type": "array", "items": { "$ref": "#/components/schemas/WebhookDelivery" } }
+                                    }
+                                }
+                            },
+                            "404": { "description": "No such endpoint for this tenant" }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "WebhookEndpoint": {
+                        "type": "object",
+                        "required": ["id", "url", "events", "created_at"],
+                        "properties": {
+                            "id": { "type": "string", "example": "whk_01HV6Y5J8Q4ZK3X2T9R7M1N0PA" },
+                            "tenant": { "type": "string", "nullable": true },
+                            "url": { "type": "string", "format": "uri" },
+                            "events": { "type": "array", "items": { "type": "string" } },
+                            "description": { "type": "string", "nullable": true },
+                            "created_at": { "type": "string", "format": "date-time" }
+                        }
+                    },
+                    "WebhookDelivery": {
+                        "type": "object",
+                        "required": ["id", "endpoint_id", "event", "payload", "status", "attempts", "created_at"],
+                        "properties": {
+                            "id": { "type": "string", "example": "whd_01HV6Y5J8Q4ZK3X2T9R7M1N0PA" },
+                            "endpoint_id": { "type": "string" },
+                            "event": { "type": "string", "example": "books.created" },
+                            "version": { "type": "integer", "nullable": true },
+                            "payload": {},
+                            "status": { "type": "string", "enum": ["pending", "succeeded", "failed"] },
+                            "attempts": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["attempt", "at", "duration_ms"],
+                                    "properties": {
+                                        "attempt": { "type": "integer" },
+                                        "at": { "type": "string", "format": "date-time" },
+                                        "status": { "type": "integer", "nullable": true, "description": "HTTP status the endpoint answered with" },
+                                        "error": { "type": "string", "nullable": true },
+                                        "duration_ms": { "type": "integer" }
+                                    }
+                                }
+                            },
+                            "created_at": { "type": "string", "format": "date-time" }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Create a new instance of the webhooks module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(WebhooksModule::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{settings, FakeTransport};
+    use atlas_kernel::{registry::priority, settings::Settings, ModuleRegistry};
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register_core_with_priority(atlas_events::create_module(), priority::EVENTS)
+            .unwrap();
+        registry.register_core(create_module()).unwrap();
+        registry
+    }
+
+    fn enabled() -> Arc<Settings> {
+        let mut settings = Settings::default();
+        settings.webhooks = self::settings();
+        Arc::new(settings)
+    }
+
+    #[cfg(not(feature = "http-transport"))]
+    #[tokio::test]
+    async fn test_enabled_webhooks_require_transport() {
+        let registry = registry();
+        let error = registry
+            .init_core_modules(&registry.init_ctx(enabled()))
+            .await
+            .unwrap_err();
+        let error = anyhow::Error::from(error);
+        assert!(format!("{:#}", error).contains("WebhookTransport"));
+    }
+
+    #[cfg(feature = "http-transport")]
+    #[tokio::test]
+    async fn test_enabled_webhooks_default_to_http_transport() {
+        let registry = registry();
+        registry
+            .init_core_modules(&registry.init_ctx(enabled()))
+            .await
+            .unwrap();
+        assert!(registry.resources().contains::<Webhooks>());
+    }
+
+    #[tokio::test]
+    async fn test_emitted_events_are_delivered_to_matching_endpoints() {
+        let registry = registry();
+        let transport = Arc::new(FakeTransport::default());
+        registry
+            .resources()
+            .insert::<Arc<dyn WebhookTransport>>(transport.clone());
+        let ctx = registry.init_ctx(enabled());
+        registry.init_core_modules(&ctx).await.unwrap();
+        registry.start_core_modules(&ctx).await.unwrap();
+
+        let webhooks = registry.resources().require::<Webhooks>().unwrap();
+        let registered = webhooks
+            .register(
+                None,
+                "https://hooks.example.com",
+                vec!["books.".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        let bus = ctx.events().unwrap();
+        bus.emit("users.created", json!({ "id": 1 })).unwrap();
+        bus.emit("books.created", json!({ "id": 7 })).unwrap();
+
+        let history = loop {
+            let history = webhooks
+                .deliveries(None, &registered.endpoint.id, 10)
+                .await
+                .unwrap()
+                .unwrap();
+            if history
+                .first()
+                .is_some_and(|delivery| delivery.status != crate::DeliveryStatus::Pending)
+            {
+                break history;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].event, "books.created");
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+        registry.stop_core_modules().await.unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use atlas_kernel::tenant::TenantId;
+
+/// An endpoint a tenant registered to receive events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebhookEndpoint {
+    /// Table-prefixed id such as `whk_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Owning tenant; `None` when tenancy is off
+    pub tenant: Option<TenantId>,
+    pub url: String,
+    /// Event names, prefixes ending in `.` such as `books.`, or `*` for every event
+    pub events: Vec<String>,
+    pub description: Option<String>,
+    /// Signing secret; shown once, when the endpoint is registered
+    #[serde(skip)]
+    pub secret: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl WebhookEndpoint {
+    /// Whether the endpoint subscribed to the event `name`
+    pub fn accepts(&self, name: &str) -> bool {
+        self.events.iter().any(|filter| match filter.as_str() {
+            "*" => true,
+            prefix if prefix.ends_with('.') => name.starts_with(prefix),
+            event => event == name,
+        })
+    }
+}
+
+/// Where a delivery stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not yet accepted; more attempts will follow
+    Pending,
+    /// The endpoint answered with a 2xx status
+    Succeeded,
+    /// Every attempt failed
+    Failed,
+}
+
+/// One POST of a delivery to its endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    /// Counting from 1
+    pub attempt: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    /// HTTP status the endpoint answered with, if it answered
+    pub status: Option<u16>,
+    /// Why the attempt failed, when it did
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// An event sent, or being sent, to one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// `whd_`-prefixed ULID, sent as `x-atlas-webhook-id` so receivers can drop duplicates
+    pub id: String,
+    pub endpoint_id: String,
+    pub event: String,
+    pub version: Option<u32>,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<DeliveryAttempt>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// Persistence for endpoints and their delivery history
+///
+/// Applications keep them across restarts by publishing an `Arc<dyn WebhookStore>`
+/// resource; without one they live in memory.
+#[async_trait]
+pub trait WebhookStore: Send + Sync {
+    async fn insert_endpoint(&self, endpoint: WebhookEndpoint) -> anyhow::Result<()>;
+
+    async fn endpoint(&self, id: &str) -> anyhow::Result<Option<WebhookEndpoint>>;
+
+    /// Endpoints of `tenant`, oldest first
+    async fn endpoints(&self, tenant: Option<&TenantId>) -> anyhow::Result<Vec<WebhookEndpoint>>;
+
+    /// Remove an endpoint and its delivery history, returning whether it existed
+    async fn remove_endpoint(&self, id: &str) -> anyhow::Result<bool>;
+
+    /// Insert a delivery, or replace the stored one with the same id
+    async fn save_delivery(&self, delivery: WebhookDelivery) -> anyhow::Result<()>;
+
+    /// Deliveries to an endpoint, newest first
+    async fn deliveries(
+        &self,
+        endpoint_id: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+}
+
+/// Process-local store keeping the most recent deliveries
+#[derive(Debug)]
+pub struct MemoryWebhookStore {
+    endpoints: RwLock<Vec<WebhookEndpoint>>,
+    deliveries: RwLock<VecDeque<WebhookDelivery>>,
+    capacity: usize,
+}
+
+impl MemoryWebhookStore {
+    /// Deliveries kept before the oldest are dropped
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            endpoints: RwLock::new(Vec::new()),
+            deliveries: RwLock::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+impl Default for MemoryWebhookStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WebhookStore for MemoryWebhookStore {
+    async fn insert_endpoint(&self, endpoint: WebhookEndpoint) -> anyhow::Result<()> {
+        self.endpoints
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(endpoint);
+        Ok(())
+    }
+
+    async fn endpoint(&self, id: &str) -> anyhow::Result<Option<WebhookEndpoint>> {
+        Ok(self
+            .endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|endpoint| endpoint.id == id)
+            .cloned())
+    }
+
+    async fn endpoints(&self, tenant: Option<&TenantId>) -> anyhow::Result<Vec<WebhookEndpoint>> {
+        Ok(self
+            .endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|endpoint| endpoint.tenant.as_ref() == tenant)
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_endpoint(&self, id: &str) -> anyhow::Result<bool> {
+        let mut endpoints = self
+            .endpoints
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = endpoints.len();
+        endpoints.retain(|endpoint| endpoint.id != id);
+        self.deliveries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|delivery| delivery.endpoint_id != id);
+        Ok(endpoints.len() != before)
+    }
+
+    async fn save_delivery(&self, delivery: WebhookDelivery) -> anyhow::Result<()> {
+        let mut deliveries = self
+            .deliveries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(stored) = deliveries
+            .iter_mut()
+            .find(|stored| stored.id == delivery.id)
+        {
+            *stored = delivery;
+            return Ok(());
+        }
+        if deliveries.len() == self.capacity {
+            deliveries.pop_front();
+        }
+        deliveries.push_back(delivery);
+        Ok(())
+    }
+
+    async fn deliveries(
+        &self,
+        endpoint_id: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        Ok(self
+            .deliveries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .rev()
+            .filter(|delivery| delivery.endpoint_id == endpoint_id)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(events: &[&str]) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: "whk_1".to_string(),
+            tenant: None,
+            url: "https://hooks.example.com".to_string(),
+            events: events.iter().map(|event| event.to_string()).collect(),
+            description: None,
+            secret: "whsec_test".to_string(),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn test_event_filters_match_names_prefixes_and_wildcard() {
+        let books = endpoint(&["books.", "users.created"]);
+        assert!(books.accepts("books.created"));
+        assert!(books.accepts("users.created"));
+        assert!(!books.accepts("users.deleted"));
+        assert!(!books.accepts("bookshelf.created"));
+        assert!(endpoint(&["*"]).accepts("anything"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_scopes_endpoints_and_drops_history_with_them() {
+        let store = MemoryWebhookStore::with_capacity(2);
+        let acme = TenantId::new("acme").unwrap();
+        let mut owned = endpoint(&["*"]);
+        owned.tenant = Some(acme.clone());
+        store.insert_endpoint(owned).await.unwrap();
+        assert!(store.endpoints(None).await.unwrap().is_empty());
+        assert_eq!(store.endpoints(Some(&acme)).await.unwrap().len(), 1);
+
+        for id in ["whd_1", "whd_2", "whd_3"] {
+            store
+                .save_delivery(WebhookDelivery {
+                    id: id.to_string(),
+                    endpoint_id: "whk_1".to_string(),
+                    event: "books.created".to_string(),
+                    version: None,
+                    payload: Value::Null,
+                    status: DeliveryStatus::Pending,
+                    attempts: Vec::new(),
+                    created_at: OffsetDateTime::UNIX_EPOCH,
+                })
+                .await
+                .unwrap();
+        }
+        let ids: Vec<String> = store
+            .deliveries("whk_1", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|delivery| delivery.id)
+            .collect();
+        assert_eq!(ids, vec!["whd_3", "whd_2"]);
+
+        assert!(store.remove_endpoint("whk_1").await.unwrap());
+        assert!(store.deliveries("whk_1", 10).await.unwrap().is_empty());
+    }
+}
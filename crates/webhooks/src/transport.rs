@@ -0,0 +1,159 @@
+//! Webhook deliveries over HTTP with reqwest
+
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use url::Url;
+
+use crate::{resolve_public, WebhookTransport};
+
+/// A `WebhookTransport` POSTing with reqwest
+///
+/// Every delivery resolves its URL with `resolve_public` and connects only to the
+/// addresses returned, so a host name re-pointed at an internal address after its
+/// endpoint was registered is refused. Redirects are returned as their status rather
+/// than followed. Callers bound each post with their own timeout.
+#[derive(Debug)]
+pub struct HttpTransport {
+    public_only: bool,
+}
+
+impl HttpTransport {
+    pub fn new() -> Self {
+        Self { public_only: true }
+    }
+
+    /// A transport reaching loopback servers, for tests
+    #[cfg(test)]
+    fn local() -> Self {
+        Self { public_only: false }
+    }
+
+    async fn resolve(&self, url: &Url) -> anyhow::Result<Vec<SocketAddr>> {
+        if self.public_only {
+            return resolve_public(url.as_str()).await;
+        }
+        let port = url.port_or_known_default().context("URL has no port")?;
+        let host = url.host_str().context("URL has no host")?;
+        Ok(tokio::net::lookup_host((host, port)).await?.collect())
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        body: &[u8],
+    ) -> anyhow::Result<u16> {
+        let parsed = Url::parse(url).context("invalid webhook URL")?;
+        let addrs = self.resolve(&parsed).await?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| anyhow!("webhook URL has no host"))?;
+        // The client is pinned to this delivery's addresses, so it is not shared
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(host, &addrs)
+            .build()
+            .context("failed to create the webhook client")?;
+        let mut request = client.post(parsed).body(body.to_vec());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let response = request.send().await.context("failed to post webhook")?;
+        Ok(response.status().as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        http::{HeaderMap, StatusCode},
+        response::Redirect,
+        routing::post,
+        Router,
+    };
+
+    /// Serve `app` on a local port and return its address
+    async fn serve(app: Router) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_posts_headers_and_body_without_following_redirects() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorder = received.clone();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(move |headers: HeaderMap, body: String| async move {
+                    recorder
+                        .lock()
+                        .unwrap()
+                        .push((headers["x-atlas-event"].clone(), body));
+                    StatusCode::ACCEPTED
+                }),
+            )
+            .route("/moved", post(|| async { Redirect::temporary("/hook") }));
+        let addr = serve(app).await;
+        let transport = HttpTransport::local();
+        let headers = [("x-atlas-event", "books.created".to_string())];
+
+        let status = transport
+            .post(&format!("http://{}/hook", addr), &headers, b"{}")
+            .await
+            .unwrap();
+        assert_eq!(status, 202);
+        let moved = transport
+            .post(&format!("http://{}/moved", addr), &headers, b"{}")
+            .await
+            .unwrap();
+        assert_eq!(moved, 307);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, "books.created");
+        assert_eq!(received[0].1, "{}");
+    }
+
+    #[tokio::test]
+    async fn test_internal_addresses_are_not_connected_to() {
+        let received = Arc::new(Mutex::new(0));
+        let counter = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move || async move {
+                *counter.lock().unwrap() += 1;
+                StatusCode::OK
+            }),
+        );
+        let addr = serve(app).await;
+
+        let error = HttpTransport::new()
+            .post(
+                &format!("http://localhost:{}/hook", addr.port()),
+                &[],
+                b"{}",
+            )
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("non-public address"));
+        assert_eq!(*received.lock().unwrap(), 0);
+    }
+}
@@ -1,15 +1,17 @@
-mod modules;
-mod utils;
+use std::sync::Arc;
 
 use anyhow::Context;
-use atlas_kernel::{settings::Settings, InitCtx, ModuleRegistry};
+use atlas_app::modules;
+use atlas_kernel::{registry::priority, settings::Settings, ModuleRegistry, ShutdownCoordinator};
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::try_init().ok();
 
     let settings = Settings::load().with_context(|| "failed to load ATLAS settings")?;
+    atlas_kernel::runtime::build_runtime(&settings.runtime)?.block_on(run(settings))
+}
 
+async fn run(settings: Settings) -> anyhow::Result<()> {
     tracing::info!(
         env = ?settings.environment,
         db = %settings.database.endpoint,
@@ -17,10 +19,64 @@ async fn main() -> anyhow::Result<()> {
     );
 
     // Create module registry and register modules
-    let mut registry = ModuleRegistry::new();
+    let mut registry = ModuleRegistry::with_settings(&settings);
+    atlas_db::connect(registry.resources(), &settings.database)
+        .context("failed to connect to the database")?;
+
+    registry
+        .register_core_with_priority(atlas_events::create_module(), priority::EVENTS)
+        .context("failed to register events module")?;
+    registry
+        .register_core_with_priority(atlas_authz::create_module(), priority::AUTHZ)
+        .context("failed to register authz module")?;
+    registry
+        .register_core_with_priority(atlas_authz::api_keys::create_module(), priority::AUTHZ)
+        .context("failed to register api_keys module")?;
+    registry
+        .register_core_with_priority(atlas_authz::sessions::create_module(), priority::AUTHZ)
+        .context("failed to register sessions module")?;
+    registry
+        .register_core_with_priority(atlas_authz::oauth::create_module(), priority::AUTHZ)
+        .context("failed to register oauth module")?;
+    registry
+        .register_core_with_priority(atlas_authz::saml::create_module(), priority::AUTHZ)
+        .context("failed to register saml module")?;
+    registry
+        .register_core_with_priority(atlas_authz::ldap::create_module(), priority::AUTHZ)
+        .context("failed to register ldap module")?;
+    registry
+        .register_core_with_priority(atlas_authz::services::create_module(), priority::AUTHZ)
+        .context("failed to register services module")?;
+    registry
+        .register_core_with_priority(atlas_authz::signatures::create_module(), priority::AUTHZ)
+        .context("failed to register signatures module")?;
+    registry
+        .register_core_with_priority(atlas_authz::rate_limit::create_module(), priority::AUTHZ)
+        .context("failed to register rate_limit module")?;
+    registry
+        .register_core_with_priority(
+            atlas_authz::password_reset::create_module(),
+            priority::AUTHZ,
+        )
+        .context("failed to register auth module")?;
+    registry
+        .register_core_with_priority(atlas_authz::audit::create_module(), priority::AUTHZ)
+        .context("failed to register audit module")?;
+    registry
+        .register_core(atlas_webhooks::create_module())
+        .context("failed to register webhooks module")?;
+    registry
+        .register_core(atlas_webhooks::inbound::create_module())
+        .context("failed to register inbound webhooks module")?;
+    registry
+        .register_core(atlas_jobs::create_module())
+        .context("failed to register scheduler module")?;
+    registry
+        .register_core(atlas_jobs::queue::create_module())
+        .context("failed to register queue module")?;
 
     // Register custom modules (core modules will be registered by their respective crates)
-    modules::register_all(&mut registry);
+    modules::register_all(&mut registry).context("failed to register modules")?;
 
     tracing::info!(
         core_modules = registry.core_module_count(),
@@ -36,9 +92,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Create initialization context
-    let ctx = InitCtx {
-        settings: &settings,
-    };
+    let ctx = registry.init_ctx(Arc::new(settings.clone()));
 
     // Phase 1: Initialize core modules in order
     registry.init_core_modules(&ctx).await?;
@@ -67,19 +121,34 @@ async fn main() -> anyhow::Result<()> {
     // Phase 3: Start core modules in order
     registry.start_core_modules(&ctx).await?;
 
-    // Phase 4: Start custom modules
-    registry.start_custom_modules(&ctx).await?;
+    // Phase 4: Start custom modules, stopping core modules again if any fails
+    if let Err(error) = registry.start_custom_modules(&ctx).await {
+        registry.stop_core_modules().await.ok();
+        return Err(error.into());
+    }
 
-    tracing::info!("atlas-app bootstrap complete");
+    let startup = registry.startup_report();
+    tracing::info!(
+        total_ms = startup.total_ms(),
+        slow = ?startup.slow(),
+        "atlas-app bootstrap complete: {}",
+        startup
+    );
 
     // Simulate some runtime
     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
-    // Shutdown Phase 1: Stop custom modules first
-    registry.stop_custom_modules().await?;
-
-    // Shutdown Phase 2: Stop core modules in reverse order
-    registry.stop_core_modules().await?;
+    // Shutdown: stop custom modules, then core modules, each within its budget
+    let report = ShutdownCoordinator::from_settings(&settings.shutdown)
+        .shutdown(&registry)
+        .await;
+    if !report.is_clean() {
+        tracing::warn!(
+            timed_out = ?report.timed_out(),
+            failed = ?report.failed(),
+            "shutdown finished with modules that did not stop cleanly"
+        );
+    }
 
     tracing::info!("atlas-app shutdown complete");
     Ok(())
@@ -0,0 +1,424 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use atlas_authz::guard::RequirePermission;
+use atlas_authz::require_tenant_member;
+use atlas_http::error::AppError;
+use atlas_http::pagination::{Page, Pagination, Sort};
+
+use super::models::{Book, BookHit, CreateBook};
+use super::service::Books;
+use super::store::{BookQuery, BookStoreError, DEFAULT_SORT, SORT_FIELDS};
+
+atlas_authz::permission!(
+    /// Add, replace and delete catalog entries
+    pub BooksWrite = "books:write"
+);
+
+impl From<BookStoreError> for AppError {
+    fn from(error: BookStoreError) -> Self {
+        match error {
+            BookStoreError::DuplicateSlug(slug) => AppError::conflict(
+                vec![json!({ "field": "slug", "error": "taken" })],
+                format!("a book with slug '{}' already exists", slug),
+            ),
+            BookStoreError::Store(error) => AppError::Internal(error),
+        }
+    }
+}
+
+/// Whether `slug` is lowercase words of ASCII letters and digits joined by hyphens
+fn is_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .bytes()
+                    .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+        })
+}
+
+fn validate(request: &CreateBook) -> Result<(), AppError> {
+    let mut details = Vec::new();
+    for (field, value) in [
+        ("title", &request.title),
+        ("author", &request.author),
+        ("slug", &request.slug),
+    ] {
+        if value.trim().is_empty() {
+            details.push(json!({ "field": field, "error": "required" }));
+        }
+    }
+    let slug = request.slug.trim();
+    if !slug.is_empty() && !is_slug(slug) {
+        details.push(json!({ "field": "slug", "error": "invalid" }));
+    }
+    if details.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::validation(
+            details,
+            "title, author, and slug are required; slugs are lowercase words joined by hyphens",
+        ))
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::not_found(format!("no book '{}'", id))
+}
+
+/// Query parameters of `GET /`, besides pagination
+#[derive(Debug, Deserialize)]
+struct ListBooks {
+    author: Option<String>,
+    sort: Option<String>,
+}
+
+async fn list_books(
+    State(books): State<Arc<Books>>,
+    Query(params): Query<ListBooks>,
+    pagination: Pagination,
+) -> Result<Json<Page<Book>>, AppError> {
+    let query = BookQuery {
+        author: params.author.filter(|author| !author.trim().is_empty()),
+        sort: Sort::parse(params.sort.as_deref(), SORT_FIELDS, DEFAULT_SORT)?,
+        pagination,
+    };
+    Ok(Json(books.list(&query).await?))
+}
+
+/// Query parameters of `GET /search`, besides pagination
+#[derive(Debug, Deserialize)]
+struct SearchBooks {
+    q: Option<String>,
+}
+
+async fn search_books(
+    State(books): State<Arc<Books>>,
+    Query(params): Query<SearchBooks>,
+    pagination: Pagination,
+) -> Result<Json<Page<BookHit>>, AppError> {
+    let Some(text) = params.q.filter(|text| !text.trim().is_empty()) else {
+        return Err(AppError::validation(
+            vec![json!({ "field": "q", "error": "required" })],
+            "q is required",
+        ));
+    };
+    Ok(Json(books.search(&text, pagination).await?))
+}
+
+async fn create_book(
+    State(books): State<Arc<Books>>,
+    _: RequirePermission<BooksWrite>,
+    Json(request): Json<CreateBook>,
+) -> Result<(StatusCode, Json<Book>), AppError> {
+    validate(&request)?;
+    Ok((StatusCode::CREATED, Json(books.create(request).await?)))
+}
+
+async fn get_book(
+    State(books): State<Arc<Books>>,
+    Path(id): Path<String>,
+) -> Result<Json<Book>, AppError> {
+    books
+        .get(&id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+async fn replace_book(
+    State(books): State<Arc<Books>>,
+    _: RequirePermission<BooksWrite>,
+    Path(id): Path<String>,
+    Json(request): Json<CreateBook>,
+) -> Result<Json<Book>, AppError> {
+    validate(&request)?;
+    books
+        .replace(&id, request)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+async fn delete_book(
+    State(books): State<Arc<Books>>,
+    _: RequirePermission<BooksWrite>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if books.delete(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(&id))
+    }
+}
+
+/// Health check endpoint
+async fn health_check() -> &'static str {
+    "books module is healthy"
+}
+
+/// Error test endpoint to demonstrate the new error format
+async fn error_test() -> Result<Json<serde_json::Value>, AppError> {
+    // Return a validation error to demonstrate the new error format
+    Err(AppError::validation(
+        vec![json!({"field": "slug", "error": "required"})],
+        "This is a test validation error to demonstrate the new error format with trace_id and timestamp"
+    ))
+}
+
+/// CRUD endpoints over `books`, plus the module health check
+///
+/// Writes need `books:write`. When `books` is scoped by tenant, the catalog endpoints
+/// answer 400 to requests without one and 403 to callers who are not members of it.
+pub fn routes(books: Arc<Books>) -> Router {
+    let scoped = books.is_scoped();
+    let catalog = Router::new()
+        .route("/", get(list_books).post(create_book))
+        .route("/search", get(search_books))
+        .route("/{id}", get(get_book).put(replace_book).delete(delete_book))
+        .with_state(books);
+    let catalog = if scoped {
+        catalog.route_layer(axum::middleware::from_fn(require_tenant_member))
+    } else {
+        catalog
+    };
+    catalog
+        .route("/health", get(health_check))
+        .route("/error-test", get(error_test))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::books::store::MemoryBookStore;
+    use atlas_authz::testing::{as_subject, resources, with_context};
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::clock::SystemClock;
+    use axum::body::Body;
+    use serde_json::Value;
+
+    fn router(books: Arc<Books>) -> Router {
+        with_context(
+            Router::new().nest("/api/books", routes(books)),
+            resources("p, librarian, books, write\ng, user_librarian, librarian"),
+        )
+    }
+
+    /// A request from a member of the `librarian` role, which holds `books:write`
+    fn librarian(method: &str, uri: &str, body: Value) -> axum::http::Request<Body> {
+        as_subject(request(method, uri, body), "user_librarian")
+    }
+
+    #[test]
+    fn test_slugs_are_hyphenated_lowercase_words() {
+        assert!(is_slug("programming-rust-2"));
+        assert!(!is_slug("Programming-Rust"));
+        assert!(!is_slug("programming--rust"));
+        assert!(!is_slug("-rust"));
+        assert!(!is_slug("rust book"));
+    }
+
+    #[tokio::test]
+    async fn test_crud_endpoints() {
+        let books = Arc::new(Books::new(
+            Arc::new(MemoryBookStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let router = router(books);
+        let rust = json!({ "title": "Programming Rust", "author": "Jim Blandy", "slug": "programming-rust" });
+        let go = json!({ "title": "The Go Programming Language", "author": "Alan Donovan", "slug": "go" });
+
+        let (status, _) = send(&router, request("POST", "/api/books", rust.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_ann = as_subject(request("POST", "/api/books", rust.clone()), "user_ann");
+        let (status, _) = send(&router, as_ann).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, created) = send(&router, librarian("POST", "/api/books", rust.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created["id"].as_str().unwrap().starts_with("book_"));
+        let uri = format!("/api/books/{}", created["id"].as_str().unwrap());
+
+        let (status, error) = send(&router, librarian("POST", "/api/books", rust.clone())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["details"][0]["field"], "slug");
+
+        let (status, error) = send(
+            &router,
+            librarian(
+                "POST",
+                "/api/books",
+                json!({ "title": "", "author": "Jim Blandy", "slug": "Bad Slug" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"].as_array().unwrap().len(), 2);
+
+        let (status, _) = send(&router, librarian("POST", "/api/books", go)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let taken = json!({ "title": "Programming Rust", "author": "Jim Blandy", "slug": "go" });
+        let (status, _) = send(&router, librarian("PUT", &uri, taken)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let second = json!({ "title": "Programming Rust, 2nd Edition", "author": "Jim Blandy", "slug": "programming-rust" });
+        let (status, replaced) = send(&router, librarian("PUT", &uri, second)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replaced["title"], "Programming Rust, 2nd Edition");
+        assert_eq!(replaced["created_at"], created["created_at"]);
+
+        let (status, fetched) = send(&router, request("GET", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched, replaced);
+
+        let (status, _) = send(&router, librarian("DELETE", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, librarian("PUT", &uri, rust)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, listed) = send(&router, request("GET", "/api/books", Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["total"], 1);
+        assert_eq!(listed["items"][0]["slug"], "go");
+    }
+
+    #[tokio::test]
+    async fn test_list_filters_sorts_and_pages() {
+        let books = Arc::new(Books::new(
+            Arc::new(MemoryBookStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let router = router(books);
+        for (title, author, slug) in [
+            ("Programming Rust", "Jim Blandy", "programming-rust"),
+            ("The Rust Programming Language", "Steve Klabnik", "trpl"),
+            ("Rust in Action", "Tim McNamara", "rust-in-action"),
+        ] {
+            let book = json!({ "title": title, "author": author, "slug": slug });
+            send(&router, librarian("POST", "/api/books", book)).await;
+        }
+
+        let (status, page) = send(
+            &router,
+            request(
+                "GET",
+                "/api/books?sort=-title&per_page=2&page=2",
+                Value::Null,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            (page["total"].clone(), page["page"].clone()),
+            (json!(3), json!(2))
+        );
+        assert_eq!(page["items"][0]["slug"], "programming-rust");
+
+        let (_, page) = send(
+            &router,
+            request("GET", "/api/books?author=Tim%20McNamara", Value::Null),
+        )
+        .await;
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["slug"], "rust-in-action");
+
+        let (status, _) = send(
+            &router,
+            request("GET", "/api/books?sort=price", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _) = send(
+            &router,
+            request("GET", "/api/books?per_page=500", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_search_requires_a_query() {
+        let books = Arc::new(Books::new(
+            Arc::new(MemoryBookStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let router = router(books);
+        let book = json!({ "title": "Programming Rust", "author": "Jim Blandy", "slug": "programming-rust" });
+        send(&router, librarian("POST", "/api/books", book)).await;
+
+        let (status, page) = send(
+            &router,
+            request("GET", "/api/books/search?q=rust", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["book"]["slug"], "programming-rust");
+        assert_eq!(
+            page["items"][0]["highlights"]["title"],
+            "Programming <mark>Rust</mark>"
+        );
+
+        let (status, _) = send(
+            &router,
+            request("GET", "/api/books/search?q=%20", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_scoped_catalog_requires_a_tenant() {
+        let books = Arc::new(
+            Books::new(Arc::new(MemoryBookStore::new()), Arc::new(SystemClock))
+                .scoped_by_tenant(true),
+        );
+        let router = router(books);
+
+        let (status, _) = send(&router, request("GET", "/api/books", Value::Null)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(&router, request("GET", "/api/books/health", Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_served_writes_need_an_authenticated_librarian() {
+        let app = atlas_authz::testing::TestApp::new(
+            vec![Arc::new(crate::modules::books::BooksModule::new())],
+            "p, librarian, books, write\ng, user_librarian, librarian",
+        )
+        .await;
+        let rust = json!({ "title": "Programming Rust", "author": "Jim Blandy", "slug": "programming-rust" });
+
+        let (status, _) = send(&app.router, request("POST", "/api/books", rust.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_ann = app
+            .with_api_key(request("POST", "/api/books", rust.clone()), "user_ann")
+            .await;
+        assert_eq!(send(&app.router, as_ann).await.0, StatusCode::FORBIDDEN);
+        let create = app
+            .with_api_key(request("POST", "/api/books", rust), "user_librarian")
+            .await;
+        let (status, created) = send(&app.router, create).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let uri = format!("/api/books/{}", created["id"].as_str().unwrap());
+        let (status, _) = send(&app.router, request("GET", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app.router, request("DELETE", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let delete = app
+            .with_session(request("DELETE", &uri, Value::Null), "user_librarian")
+            .await;
+        assert_eq!(send(&app.router, delete).await.0, StatusCode::NO_CONTENT);
+    }
+}
@@ -1,16 +1,36 @@
+pub mod http;
 pub mod models;
+pub mod service;
+pub mod store;
+
+use std::sync::{Arc, OnceLock};
 
 use async_trait::async_trait;
-use atlas_kernel::{InitCtx, Migration, Module};
-use axum::{routing::get, Router};
+use atlas_http::pagination;
+use atlas_kernel::{
+    InitCtx, Migration, Module, RouteSecurity, SchemaExample, SecurityScheme, Seed,
+};
+use axum::Router;
 use serde_json::json;
 
-/// Books module implementation for testing the ATLAS module lifecycle
-pub struct BooksModule;
+use service::Books;
+use store::{BookStore, MemoryBookStore, SurrealBookStore};
+
+/// Book catalog backed by the `book` table
+///
+/// Publishes `Books` as a shared resource. With `tenancy.enabled`, each organization has
+/// its own catalog and requests must name a tenant the caller is a member of, as told by
+/// the `atlas_authz::TenantMembers` resource the orgs module publishes.
+#[derive(Default)]
+pub struct BooksModule {
+    books: OnceLock<Arc<Books>>,
+}
 
 impl BooksModule {
     pub const fn new() -> Self {
-        Self
+        Self {
+            books: OnceLock::new(),
+        }
     }
 }
 
@@ -20,7 +40,29 @@ impl Module for BooksModule {
         "books"
     }
 
-    async fn init(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Book catalog management")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn BookStore>(
+            &ctx.resources,
+            "book",
+            |db| Arc::new(SurrealBookStore::new(db)),
+            || Arc::new(MemoryBookStore::new()),
+        );
+        let books = Arc::new(
+            Books::new(store, ctx.clock.clone()).scoped_by_tenant(ctx.settings.tenancy.enabled),
+        );
+        ctx.resources.insert_arc(books.clone());
+        self.books
+            .set(books)
+            .map_err(|_| anyhow::anyhow!("books module initialized twice"))?;
+
         tracing::info!(
             module = self.name(),
             environment = ?ctx.settings.environment,
@@ -30,33 +72,114 @@ impl Module for BooksModule {
     }
 
     fn routes(&self) -> Router {
-        Router::new()
-            .route("/", get(list_books))
-            .route("/health", get(health_check))
-            .route("/error-test", get(error_test))
+        match self.books.get() {
+            Some(books) => http::routes(books.clone()),
+            None => Router::new(),
+        }
+    }
+
+    /// Writes need `books:write`; a tenant-scoped catalog also only serves its members
+    fn security(&self) -> Vec<RouteSecurity> {
+        let guarded = |method, path, permission| RouteSecurity {
+            method,
+            path,
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission,
+        };
+        let mut security = Vec::new();
+        if self.books.get().is_some_and(|books| books.is_scoped()) {
+            for path in ["/", "/search", "/{id}"] {
+                security.push(guarded("get", path, None));
+            }
+        }
+        security.extend([
+            guarded("post", "/", Some("books:write")),
+            guarded("put", "/{id}", Some("books:write")),
+            guarded("delete", "/{id}", Some("books:write")),
+        ]);
+        security
     }
 
     fn openapi(&self) -> Option<serde_json::Value> {
-        Some(serde_json::json!({
+        let error = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorResponse" }
+                    }
+                }
+            })
+        };
+        let book = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/Book" }
+                    }
+                }
+            })
+        };
+        let id = json!({
+            "name": "id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" }
+        });
+        let mut list_parameters = vec![json!({
+            "name": "author",
+            "in": "query",
+            "description": "Only books by exactly this author",
+            "schema": { "type": "string" }
+        })];
+        list_parameters.push(pagination::openapi::sort_parameter(
+            store::SORT_FIELDS,
+            store::DEFAULT_SORT,
+        ));
+        list_parameters.extend(pagination::openapi::parameters());
+        let mut search_parameters = vec![json!({
+            "name": "q",
+            "in": "query",
+            "required": true,
+            "description": "Words to find in titles and authors",
+            "schema": { "type": "string" }
+        })];
+        search_parameters.extend(pagination::openapi::parameters());
+        let create_book = json!({
+            "required": true,
+            "content": {
+                "application/json": {
+                    "schema": { "$ref": "#/components/schemas/CreateBook" }
+                }
+            }
+        });
+        Some(json!({
+            "tags": [
+                {
+                    "name": "Books",
+                    "description": "Books — catalog management",
+                    "x-order": 1
+                }
+            ],
             "paths": {
                 "/": {
                     "get": {
                         "summary": "List books",
                         "tags": ["Books"],
+                        "parameters": list_parameters,
                         "responses": {
                             "200": {
-                                "description": "List of books",
+                                "description": "One page of the matching books",
                                 "content": {
                                     "application/json": {
-                                        "schema": {
-                                            "type": "array",
-                                            "items": {
-                                                "$ref": "#/components/schemas/Book"
-                                            }
-                                        }
+                                        "schema": pagination::openapi::page_schema(
+                                            json!({ "$ref": "#/components/schemas/Book" })
+                                        )
                                     }
                                 }
                             },
+                            "422": error("Invalid page, per_page, or sort"),
                             "500": {
                                 "description": "Internal server error",
                                 "content": {
@@ -68,6 +191,74 @@ impl Module for BooksModule {
                                 }
                             }
                         }
+                    },
+                    "post": {
+                        "summary": "Add a book",
+                        "tags": ["Books"],
+                        "requestBody": create_book.clone(),
+                        "responses": {
+                            "201": book("The new book"),
+                            "409": error("Another book has this slug"),
+                            "422": error("A field is blank or the slug is malformed"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/search": {
+                    "get": {
+                        "summary": "Search books",
+                        "description": "Full-text search over titles and authors, most relevant first.",
+                        "tags": ["Books"],
+                        "parameters": search_parameters,
+                        "responses": {
+                            "200": {
+                                "description": "One page of the matching books",
+                                "content": {
+                                    "application/json": {
+                                        "schema": pagination::openapi::page_schema(
+                                            json!({ "$ref": "#/components/schemas/BookHit" })
+                                        )
+                                    }
+                                }
+                            },
+                            "422": error("Missing q, or invalid page or per_page"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{id}": {
+                    "get": {
+                        "summary": "Get a book",
+                        "tags": ["Books"],
+                        "parameters": [id],
+                        "responses": {
+                            "200": book("The book"),
+                            "404": error("No book with that id"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "put": {
+                        "summary": "Replace a book",
+                        "tags": ["Books"],
+                        "parameters": [id],
+                        "requestBody": create_book,
+                        "responses": {
+                            "200": book("The replaced book"),
+                            "404": error("No book with that id"),
+                            "409": error("Another book has this slug"),
+                            "422": error("A field is blank or the slug is malformed"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "delete": {
+                        "summary": "Delete a book",
+                        "tags": ["Books"],
+                        "parameters": [id],
+                        "responses": {
+                            "204": { "description": "Book deleted" },
+                            "404": error("No book with that id"),
+                            "500": error("Internal server error")
+                        }
                     }
                 },
                 "/health": {
@@ -137,9 +328,43 @@ impl Module for BooksModule {
                             "slug": {
                                 "type": "string",
                                 "description": "URL-friendly slug for the book"
+                            },
+                            "tenant": {
+                                "type": "string",
+                                "description": "Organization owning the book, when it was added for a tenant"
+                            },
+                            "created_at": {
+                                "type": "string",
+                                "format": "date-time",
+                                "description": "When the book was added to the catalog"
+                            },
+                            "updated_at": {
+                                "type": "string",
+                                "format": "date-time",
+                                "description": "When the book was last changed"
                             }
                         },
-                        "required": ["id", "title", "author", "slug"]
+                        "required": ["id", "title", "author", "slug", "created_at", "updated_at"]
+                    },
+                    "BookHit": {
+                        "type": "object",
+                        "properties": {
+                            "book": { "$ref": "#/components/schemas/Book" },
+                            "score": {
+                                "type": "number",
+                                "description": "Relevance; only comparable within one search"
+                            },
+                            "highlights": {
+                                "type": "object",
+                                "description": "Searched fields with matched words wrapped in <mark> tags",
+                                "properties": {
+                                    "title": { "type": "string" },
+                                    "author": { "type": "string" }
+                                },
+                                "required": ["title", "author"]
+                            }
+                        },
+                        "required": ["book", "score", "highlights"]
                     },
                     "CreateBook": {
                         "type": "object",
@@ -164,20 +389,126 @@ impl Module for BooksModule {
         }))
     }
 
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![
+            SchemaExample::new(
+                "Book",
+                json!({
+                    "id": "book_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                    "title": "The Rust Programming Language",
+                    "author": "Steve Klabnik",
+                    "slug": "rust-programming-language",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-15T10:30:00Z"
+                }),
+            ),
+            SchemaExample::new(
+                "BookHit",
+                json!({
+                    "book": {
+                        "id": "book_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                        "title": "The Rust Programming Language",
+                        "author": "Steve Klabnik",
+                        "slug": "rust-programming-language",
+                        "created_at": "2024-01-01T00:00:00Z",
+                        "updated_at": "2024-01-15T10:30:00Z"
+                    },
+                    "score": 1.73,
+                    "highlights": {
+                        "title": "The <mark>Rust</mark> Programming Language",
+                        "author": "Steve Klabnik"
+                    }
+                }),
+            ),
+            SchemaExample::new(
+                "CreateBook",
+                json!({
+                    "title": "Programming Rust",
+                    "author": "Jim Blandy",
+                    "slug": "programming-rust"
+                }),
+            ),
+        ]
+    }
+
     fn migrations(&self) -> Vec<Migration> {
-        vec![Migration {
-            id: "001_init",
-            up: r#"
+        vec![
+            Migration {
+                id: "001_init",
+                up: r#"
                 DEFINE TABLE book SCHEMAFULL;
                 DEFINE FIELD title  ON book TYPE string ASSERT $value != "";
                 DEFINE FIELD author ON book TYPE string ASSERT $value != "";
                 DEFINE FIELD slug   ON book TYPE string ASSERT $value != "";
                 DEFINE INDEX book_slug_unique ON book FIELDS slug UNIQUE;
                 "#,
+                down: Some("REMOVE TABLE book;"),
+            },
+            Migration {
+                id: "002_timestamps",
+                up: r#"
+                DEFINE FIELD created_at ON book TYPE datetime DEFAULT time::now() VALUE $before OR $value;
+                DEFINE FIELD updated_at ON book TYPE datetime DEFAULT time::now();
+                UPDATE book SET created_at = time::now(), updated_at = time::now() WHERE created_at IS NONE;
+                "#,
+                down: Some(
+                    r#"
+                REMOVE FIELD updated_at ON book;
+                REMOVE FIELD created_at ON book;
+                "#,
+                ),
+            },
+            Migration {
+                id: "003_search",
+                up: r#"
+                DEFINE ANALYZER book_search TOKENIZERS class FILTERS lowercase, ascii, snowball(english);
+                DEFINE INDEX book_title_search  ON book FIELDS title  SEARCH ANALYZER book_search BM25 HIGHLIGHTS;
+                DEFINE INDEX book_author_search ON book FIELDS author SEARCH ANALYZER book_search BM25 HIGHLIGHTS;
+                "#,
+                down: Some(
+                    r#"
+                REMOVE INDEX book_author_search ON book;
+                REMOVE INDEX book_title_search ON book;
+                REMOVE ANALYZER book_search;
+                "#,
+                ),
+            },
+            Migration {
+                id: "004_tenant",
+                up: r#"
+                DEFINE FIELD tenant ON book TYPE option<string>;
+                DEFINE INDEX OVERWRITE book_slug_unique ON book FIELDS tenant, slug UNIQUE;
+                "#,
+                down: Some(
+                    r#"
+                DEFINE INDEX OVERWRITE book_slug_unique ON book FIELDS slug UNIQUE;
+                REMOVE FIELD tenant ON book;
+                "#,
+                ),
+            },
+        ]
+    }
+
+    fn seeds(&self) -> Vec<Seed> {
+        vec![Seed {
+            id: "sample_books",
+            tables: &["book"],
+            script: r#"
+                CREATE book CONTENT {
+                    title: "The Rust Programming Language",
+                    author: "Steve Klabnik",
+                    slug: "rust-programming-language"
+                };
+                CREATE book CONTENT {
+                    title: "Programming Rust",
+                    author: "Jim Blandy",
+                    slug: "programming-rust"
+                };
+                "#,
         }]
     }
 
-    async fn start(&self, _ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
         tracing::info!(module = self.name(), "books module started");
         Ok(())
     }
@@ -188,41 +519,9 @@ impl Module for BooksModule {
     }
 }
 
-/// Health check endpoint
-async fn health_check() -> &'static str {
-    "books module is healthy"
-}
-
-/// List books endpoint (stub implementation)
-async fn list_books() -> axum::Json<Vec<models::Book>> {
-    let books = vec![
-        models::Book {
-            id: "book-1".to_string(),
-            title: "The Rust Programming Language".to_string(),
-            author: "Steve Klabnik".to_string(),
-            slug: "rust-programming-language".to_string(),
-        },
-        models::Book {
-            id: "book-2".to_string(),
-            title: "Programming Rust".to_string(),
-            author: "Jim Blandy".to_string(),
-            slug: "programming-rust".to_string(),
-        },
-    ];
-
-    axum::Json(books)
-}
-
-/// Error test endpoint to demonstrate the new error format
-async fn error_test() -> Result<axum::Json<serde_json::Value>, atlas_http::error::AppError> {
-    // Return a validation error to demonstrate the new error format
-    Err(atlas_http::error::AppError::validation(
-        vec![json!({"field": "slug", "error": "required"})],
-        "This is a test validation error to demonstrate the new error format with trace_id and timestamp"
-    ))
-}
-
 /// Create a new instance of the books module
-pub fn create_module() -> std::sync::Arc<dyn Module> {
-    std::sync::Arc::new(BooksModule::new())
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(BooksModule::new())
 }
+
+atlas_kernel::register_module!(create_module);
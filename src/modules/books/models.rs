@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 /// Example domain model for the Books module.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Book {
     /// Unique identifier for the book
     pub id: String,
@@ -11,9 +12,18 @@ pub struct Book {
     pub author: String,
     /// URL-friendly slug for the book
     pub slug: String,
+    /// Organization owning the book, when it was added for a tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// When the book was added to the catalog
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// When the book was last changed
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
 }
 
-/// Request model for creating a new book.
+/// Request model for creating a new book, or replacing one with `PUT`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateBook {
     /// Title of the book
@@ -23,3 +33,19 @@ pub struct CreateBook {
     /// URL-friendly slug for the book
     pub slug: String,
 }
+
+/// A book matching a search, with its relevance and the matched terms marked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookHit {
+    pub book: Book,
+    /// Relevance; higher is better. Only comparable within one search
+    pub score: f64,
+    pub highlights: BookHighlights,
+}
+
+/// Searched fields with matched terms wrapped in `<mark>` and `</mark>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookHighlights {
+    pub title: String,
+    pub author: String,
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+
+use atlas_db::TenantFilter;
+use atlas_http::pagination::{Page, Pagination};
+use atlas_kernel::tenant::{self, TenantId};
+use atlas_kernel::{id, Clock};
+
+use super::models::{Book, BookHit, CreateBook};
+use super::store::{BookQuery, BookStore, BookStoreError};
+
+/// Adds, reads, replaces, and removes catalog entries
+///
+/// Each organization has its own catalog: books are stamped with the tenant in scope
+/// when they are added, and the store only sees the current tenant's books.
+pub struct Books {
+    store: Arc<dyn BookStore>,
+    clock: Arc<dyn Clock>,
+    scoped: bool,
+}
+
+impl Books {
+    pub fn new(store: Arc<dyn BookStore>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            store,
+            clock,
+            scoped: false,
+        }
+    }
+
+    /// Refuse to work outside a tenant scope, as when `tenancy.enabled`
+    pub fn scoped_by_tenant(mut self, scoped: bool) -> Self {
+        self.scoped = scoped;
+        self
+    }
+
+    /// Whether every operation needs a tenant in scope
+    pub fn is_scoped(&self) -> bool {
+        self.scoped
+    }
+
+    pub async fn create(&self, request: CreateBook) -> Result<Book, BookStoreError> {
+        let tenant = self.tenant()?;
+        let now = self.now();
+        let book = Book {
+            id: id::prefixed_with("book", id::ulid_at(self.clock.now()))
+                .map_err(anyhow::Error::from)?,
+            title: request.title.trim().to_string(),
+            author: request.author.trim().to_string(),
+            slug: request.slug.trim().to_string(),
+            tenant: tenant.map(String::from),
+            created_at: now,
+            updated_at: now,
+        };
+        self.store.insert(book.clone()).await?;
+        tracing::info!(book_id = %book.id, slug = %book.slug, "created book");
+        Ok(book)
+    }
+
+    pub async fn get(&self, id: &str) -> anyhow::Result<Option<Book>> {
+        self.tenant()?;
+        self.store.get(id).await
+    }
+
+    pub async fn list(&self, query: &BookQuery) -> anyhow::Result<Page<Book>> {
+        self.tenant()?;
+        self.store.list(query).await
+    }
+
+    /// Books whose title or author matches the words of `text`, most relevant first
+    pub async fn search(
+        &self,
+        text: &str,
+        pagination: Pagination,
+    ) -> anyhow::Result<Page<BookHit>> {
+        self.tenant()?;
+        self.store.search(text.trim(), pagination).await
+    }
+
+    /// Replace every field of a book, returning `None` if there is no such book
+    pub async fn replace(
+        &self,
+        id: &str,
+        request: CreateBook,
+    ) -> Result<Option<Book>, BookStoreError> {
+        self.tenant()?;
+        let Some(stored) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+        let book = Book {
+            title: request.title.trim().to_string(),
+            author: request.author.trim().to_string(),
+            slug: request.slug.trim().to_string(),
+            updated_at: self.now(),
+            ..stored
+        };
+        if !self.store.update(book.clone()).await? {
+            return Ok(None);
+        }
+        tracing::info!(book_id = %book.id, slug = %book.slug, "replaced book");
+        Ok(Some(book))
+    }
+
+    /// Remove a book, returning `false` if there is none
+    pub async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        self.tenant()?;
+        let deleted = self.store.delete(id).await?;
+        if deleted {
+            tracing::info!(book_id = %id, "deleted book");
+        }
+        Ok(deleted)
+    }
+
+    /// The tenant in scope, which must be there when books are scoped by tenant
+    fn tenant(&self) -> anyhow::Result<Option<TenantId>> {
+        if self.scoped {
+            TenantFilter::current().map(|filter| Some(filter.tenant))
+        } else {
+            Ok(tenant::current())
+        }
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::books::store::MemoryBookStore;
+    use atlas_kernel::clock::SystemClock;
+
+    fn rust() -> CreateBook {
+        CreateBook {
+            title: "Programming Rust".to_string(),
+            author: "Jim Blandy".to_string(),
+            slug: "programming-rust".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_tenant_has_its_own_catalog() {
+        let books = Books::new(Arc::new(MemoryBookStore::new()), Arc::new(SystemClock))
+            .scoped_by_tenant(true);
+        let acme = TenantId::new("acme").unwrap();
+        let globex = TenantId::new("globex").unwrap();
+
+        let created = tenant::scope(acme.clone(), books.create(rust()))
+            .await
+            .unwrap();
+        assert_eq!(created.tenant.as_deref(), Some("acme"));
+        // Slugs only need to be unique within an organization
+        tenant::scope(globex.clone(), books.create(rust()))
+            .await
+            .unwrap();
+
+        let listed = tenant::scope(acme, books.list(&BookQuery::default()))
+            .await
+            .unwrap();
+        assert_eq!(listed.total, 1);
+        assert_eq!(listed.items[0].id, created.id);
+        assert!(tenant::scope(globex, books.get(&created.id))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(books.list(&BookQuery::default()).await.is_err());
+    }
+}
@@ -0,0 +1,683 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use atlas_db::{QueryExecutor, TenantFilter};
+use atlas_http::pagination::{Page, Pagination, Sort};
+use atlas_kernel::tenant::{self, TenantId};
+
+use super::models::{Book, BookHighlights, BookHit};
+
+/// Unique index on `book.tenant` and `book.slug` defined by the module's migrations
+pub const SLUG_INDEX: &str = "book_slug_unique";
+
+/// Why a book could not be stored
+#[derive(Debug, thiserror::Error)]
+pub enum BookStoreError {
+    #[error("a book with slug '{0}' already exists")]
+    DuplicateSlug(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Fields a book listing can be sorted by
+pub const SORT_FIELDS: &[&str] = &["title", "author", "slug", "created_at", "updated_at"];
+
+/// Oldest books first
+pub const DEFAULT_SORT: Sort = Sort::asc("created_at");
+
+/// Filters, order, and page of a book listing
+#[derive(Debug, Clone)]
+pub struct BookQuery {
+    /// Only books by exactly this author
+    pub author: Option<String>,
+    /// One of `SORT_FIELDS`
+    pub sort: Sort,
+    pub pagination: Pagination,
+}
+
+impl Default for BookQuery {
+    fn default() -> Self {
+        Self {
+            author: None,
+            sort: DEFAULT_SORT,
+            pagination: Pagination::default(),
+        }
+    }
+}
+
+/// Marks wrapped around matched terms in search highlights
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// Persistence for the book catalog
+///
+/// The module stores books in SurrealDB through the application's `QueryExecutor`;
+/// applications may publish their own `Arc<dyn BookStore>` resource instead. Without
+/// either, books live in memory.
+///
+/// Inside a tenant scope, every method only sees and changes that tenant's books, and
+/// slugs only need to be unique per tenant.
+#[async_trait]
+pub trait BookStore: Send + Sync {
+    async fn insert(&self, book: Book) -> Result<(), BookStoreError>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Book>>;
+
+    /// The page of books matching `query`, in its order
+    async fn list(&self, query: &BookQuery) -> anyhow::Result<Page<Book>>;
+
+    /// The page of books whose title or author matches the words of `text`, most
+    /// relevant first
+    async fn search(&self, text: &str, pagination: Pagination) -> anyhow::Result<Page<BookHit>>;
+
+    /// Replace the stored book with the same id, returning `false` if there is none
+    async fn update(&self, book: Book) -> Result<bool, BookStoreError>;
+
+    /// Remove a book, returning `false` if there is none
+    async fn delete(&self, id: &str) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; books are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryBookStore {
+    books: RwLock<Vec<Book>>,
+}
+
+impl MemoryBookStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BookStore for MemoryBookStore {
+    async fn insert(&self, book: Book) -> Result<(), BookStoreError> {
+        let mut books = self
+            .books
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if books
+            .iter()
+            .any(|stored| stored.slug == book.slug && stored.tenant == book.tenant)
+        {
+            return Err(BookStoreError::DuplicateSlug(book.slug));
+        }
+        books.push(book);
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Book>> {
+        let tenant = tenant::current();
+        Ok(self
+            .books
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|book| book.id == id && in_scope(book, tenant.as_ref()))
+            .cloned())
+    }
+
+    async fn list(&self, query: &BookQuery) -> anyhow::Result<Page<Book>> {
+        let tenant = tenant::current();
+        let mut books: Vec<Book> = self
+            .books
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|book| in_scope(book, tenant.as_ref()))
+            .filter(|book| {
+                query
+                    .author
+                    .as_ref()
+                    .is_none_or(|author| &book.author == author)
+            })
+            .cloned()
+            .collect();
+        books.sort_by(|a, b| query.sort.order(compare(a, b, query.sort.field)));
+        let total = books.len() as u64;
+        Ok(Page::new(
+            query.pagination.slice(books),
+            total,
+            query.pagination,
+        ))
+    }
+
+    /// Scores each book by the searched words its title and author contain
+    async fn search(&self, text: &str, pagination: Pagination) -> anyhow::Result<Page<BookHit>> {
+        let terms = terms(text);
+        let tenant = tenant::current();
+        let mut hits: Vec<BookHit> = self
+            .books
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|book| in_scope(book, tenant.as_ref()))
+            .filter_map(|book| {
+                let (title, title_matches) = highlight(&book.title, &terms);
+                let (author, author_matches) = highlight(&book.author, &terms);
+                let score = (title_matches + author_matches) as f64;
+                (score > 0.0).then(|| BookHit {
+                    book: book.clone(),
+                    score,
+                    highlights: BookHighlights { title, author },
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| compare(&a.book, &b.book, "created_at"))
+        });
+        let total = hits.len() as u64;
+        Ok(Page::new(pagination.slice(hits), total, pagination))
+    }
+
+    async fn update(&self, book: Book) -> Result<bool, BookStoreError> {
+        let tenant = tenant::current();
+        let mut books = self
+            .books
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(tenant_of_book) = books
+            .iter()
+            .find(|stored| stored.id == book.id && in_scope(stored, tenant.as_ref()))
+            .map(|stored| stored.tenant.clone())
+        else {
+            return Ok(false);
+        };
+        if books.iter().any(|stored| {
+            stored.slug == book.slug && stored.tenant == tenant_of_book && stored.id != book.id
+        }) {
+            return Err(BookStoreError::DuplicateSlug(book.slug));
+        }
+        match books.iter_mut().find(|stored| stored.id == book.id) {
+            Some(stored) => {
+                *stored = Book {
+                    tenant: tenant_of_book,
+                    ..book
+                };
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let mut books = self
+            .books
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let tenant = tenant::current();
+        let before = books.len();
+        books.retain(|book| book.id != id || !in_scope(book, tenant.as_ref()));
+        Ok(books.len() < before)
+    }
+}
+
+/// Whether `book` belongs to `tenant`; every book is in scope without a tenant
+fn in_scope(book: &Book, tenant: Option<&TenantId>) -> bool {
+    tenant.is_none_or(|tenant| book.tenant.as_deref() == Some(tenant.as_str()))
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_BOOK: &str = "SELECT *, record::id(id) AS id FROM book";
+
+/// Every field of a book but its id and tenant, which never changes
+const SET_BOOK: &str = "title = $title, author = $author, slug = $slug, \
+     created_at = <datetime> $created_at, updated_at = <datetime> $updated_at";
+
+/// Books in the `book` table defined by the module's migrations
+pub struct SurrealBookStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealBookStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// The fields of `book` bound as `$id`, `$title`, and so on
+    fn vars(book: &Book) -> anyhow::Result<Map<String, Value>> {
+        match serde_json::to_value(book).context("failed to serialize book")? {
+            Value::Object(vars) => Ok(vars),
+            _ => unreachable!("books serialize as objects"),
+        }
+    }
+
+    /// Run a statement writing `book` with `vars`
+    async fn write(
+        &self,
+        surql: &str,
+        book: &Book,
+        vars: &Map<String, Value>,
+    ) -> Result<Vec<Value>, BookStoreError> {
+        self.db.query_with(surql, vars).await.map_err(
+            |error| match atlas_db::unique_index_violation(&error) {
+                Some(index) if index == SLUG_INDEX => {
+                    BookStoreError::DuplicateSlug(book.slug.clone())
+                }
+                _ => BookStoreError::Store(error),
+            },
+        )
+    }
+
+    /// Run `surql` with `{where}` replaced by a condition on the book with `id`
+    async fn by_id(&self, surql: &str, id: &str) -> anyhow::Result<Vec<Value>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let filter = where_clause(vec!["id = type::thing('book', $id)".to_string()], &mut vars);
+        self.db
+            .query_with(&surql.replace("{where}", &filter), &vars)
+            .await
+    }
+}
+
+/// `WHERE` clause joining `conditions`, plus the current tenant's when there is one
+fn where_clause(mut conditions: Vec<String>, vars: &mut Map<String, Value>) -> String {
+    if let Some(filter) = tenant::current().map(|tenant| TenantFilter { tenant }) {
+        let (name, value) = filter.bind();
+        vars.insert(name.to_string(), json!(value));
+        conditions.push(filter.clause());
+    }
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+#[async_trait]
+impl BookStore for SurrealBookStore {
+    async fn insert(&self, book: Book) -> Result<(), BookStoreError> {
+        // A missing `$tenant` stores `NONE`, for books added outside a tenant scope
+        let surql = format!(
+            "CREATE type::thing('book', $id) SET {}, tenant = $tenant RETURN NONE;",
+            SET_BOOK
+        );
+        self.write(&surql, &book, &Self::vars(&book)?).await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Book>> {
+        let surql = format!("{} {{where}};", SELECT_BOOK);
+        let results = self
+            .by_id(&surql, id)
+            .await
+            .context("failed to read book")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn list(&self, query: &BookQuery) -> anyhow::Result<Page<Book>> {
+        let mut vars = Map::new();
+        let mut conditions = Vec::new();
+        if let Some(author) = &query.author {
+            vars.insert("author".to_string(), json!(author));
+            conditions.push("author = $author".to_string());
+        }
+        let filter = where_clause(conditions, &mut vars);
+        vars.insert("limit".to_string(), json!(query.pagination.limit()));
+        vars.insert("start".to_string(), json!(query.pagination.offset()));
+        let surql = format!(
+            "{select} {filter} {order} LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM book {filter} GROUP ALL;",
+            select = SELECT_BOOK,
+            filter = filter,
+            order = query.sort.to_surql(),
+        );
+        let mut results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to list books")?
+            .into_iter();
+        let books = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(books, total, query.pagination))
+    }
+
+    /// Ranks by the BM25 scores of the `book_title_search` and `book_author_search`
+    /// full-text indexes
+    async fn search(&self, text: &str, pagination: Pagination) -> anyhow::Result<Page<BookHit>> {
+        let mut vars = Map::new();
+        vars.insert("text".to_string(), json!(text));
+        vars.insert("start_mark".to_string(), json!(HIGHLIGHT_START));
+        vars.insert("end_mark".to_string(), json!(HIGHLIGHT_END));
+        vars.insert("limit".to_string(), json!(pagination.limit()));
+        vars.insert("start".to_string(), json!(pagination.offset()));
+        let filter = where_clause(
+            vec!["(title @0@ $text OR author @1@ $text)".to_string()],
+            &mut vars,
+        );
+        let surql = format!(
+            "SELECT *, record::id(id) AS id, \
+                 search::score(0) + search::score(1) AS score, \
+                 search::highlight($start_mark, $end_mark, 0) AS title_highlight, \
+                 search::highlight($start_mark, $end_mark, 1) AS author_highlight \
+             FROM book {filter} ORDER BY score DESC LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM book {filter} GROUP ALL;",
+            filter = filter,
+        );
+        let mut results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to search books")?
+            .into_iter();
+        let hits = atlas_db::records::<SearchRow>(results.next())?
+            .into_iter()
+            .map(SearchRow::into_hit)
+            .collect();
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(hits, total, pagination))
+    }
+
+    async fn update(&self, book: Book) -> Result<bool, BookStoreError> {
+        let mut vars = Self::vars(&book)?;
+        // A `WHERE` keeps `UPDATE` from creating a missing record
+        let filter = where_clause(vec!["id = type::thing('book', $id)".to_string()], &mut vars);
+        let surql = format!(
+            "UPDATE book SET {} {} RETURN VALUE record::id(id);",
+            SET_BOOK, filter
+        );
+        let results = self.write(&surql, &book, &vars).await?;
+        Ok(changed(results.first()))
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let results = self
+            .by_id("DELETE book {where} RETURN BEFORE;", id)
+            .await
+            .context("failed to delete book")?;
+        Ok(changed(results.first()))
+    }
+}
+
+/// Row of a `count()` query
+#[derive(serde::Deserialize)]
+struct Total {
+    total: u64,
+}
+
+/// Row of a search query: the book and its relevance
+#[derive(serde::Deserialize)]
+struct SearchRow {
+    #[serde(flatten)]
+    book: Book,
+    #[serde(default)]
+    score: f64,
+    title_highlight: Option<String>,
+    author_highlight: Option<String>,
+}
+
+impl SearchRow {
+    /// Fields without a match are highlighted as their plain value
+    fn into_hit(self) -> BookHit {
+        BookHit {
+            highlights: BookHighlights {
+                title: self
+                    .title_highlight
+                    .unwrap_or_else(|| self.book.title.clone()),
+                author: self
+                    .author_highlight
+                    .unwrap_or_else(|| self.book.author.clone()),
+            },
+            score: self.score,
+            book: self.book,
+        }
+    }
+}
+
+/// Lowercased words of a search, ignoring punctuation
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `text` with every word starting with one of `terms` marked, and how many were
+fn highlight(text: &str, terms: &[String]) -> (String, usize) {
+    let mut marked = String::with_capacity(text.len());
+    let mut matches = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_len = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        if word_len == 0 {
+            let separator = rest.chars().next().map_or(1, char::len_utf8);
+            marked.push_str(&rest[..separator]);
+            rest = &rest[separator..];
+            continue;
+        }
+        let word = &rest[..word_len];
+        let lowercase = word.to_lowercase();
+        if terms
+            .iter()
+            .any(|term| lowercase.starts_with(term.as_str()))
+        {
+            matches += 1;
+            marked.push_str(HIGHLIGHT_START);
+            marked.push_str(word);
+            marked.push_str(HIGHLIGHT_END);
+        } else {
+            marked.push_str(word);
+        }
+        rest = &rest[word_len..];
+    }
+    (marked, matches)
+}
+
+/// Ascending order of two books by one of `SORT_FIELDS`, then by id
+fn compare(a: &Book, b: &Book, field: &str) -> Ordering {
+    let ordering = match field {
+        "title" => a.title.cmp(&b.title),
+        "author" => a.author.cmp(&b.author),
+        "slug" => a.slug.cmp(&b.slug),
+        "updated_at" => a.updated_at.cmp(&b.updated_at),
+        _ => a.created_at.cmp(&b.created_at),
+    };
+    ordering.then_with(|| a.id.cmp(&b.id))
+}
+
+/// Whether a statement changed any record
+fn changed(result: Option<&Value>) -> bool {
+    matches!(result, Some(Value::Array(records)) if !records.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn book(id: &str, slug: &str) -> Book {
+        Book {
+            id: id.to_string(),
+            title: "Programming Rust".to_string(),
+            author: "Jim Blandy".to_string(),
+            slug: slug.to_string(),
+            tenant: None,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_slugs_unique() {
+        let store = MemoryBookStore::new();
+        store.insert(book("book_1", "rust")).await.unwrap();
+        store.insert(book("book_2", "go")).await.unwrap();
+
+        assert!(matches!(
+            store.insert(book("book_3", "rust")).await,
+            Err(BookStoreError::DuplicateSlug(_))
+        ));
+        assert!(matches!(
+            store.update(book("book_2", "rust")).await,
+            Err(BookStoreError::DuplicateSlug(_))
+        ));
+        assert!(store.update(book("book_1", "rust")).await.unwrap());
+        assert!(store.delete("book_2").await.unwrap());
+        assert!(!store.delete("book_2").await.unwrap());
+        assert_eq!(store.list(&BookQuery::default()).await.unwrap().total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_filters_sorts_and_pages() {
+        let store = MemoryBookStore::new();
+        for (id, slug, author) in [
+            ("book_1", "a", "Jim Blandy"),
+            ("book_2", "c", "Steve Klabnik"),
+            ("book_3", "b", "Jim Blandy"),
+        ] {
+            store
+                .insert(Book {
+                    author: author.to_string(),
+                    ..book(id, slug)
+                })
+                .await
+                .unwrap();
+        }
+
+        let page = store
+            .list(&BookQuery {
+                author: Some("Jim Blandy".to_string()),
+                sort: Sort::desc("slug"),
+                pagination: Pagination {
+                    page: 1,
+                    per_page: 1,
+                },
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].slug, "b");
+    }
+
+    struct Failing {
+        queries: Mutex<Vec<(String, Map<String, Value>)>>,
+    }
+
+    #[async_trait]
+    impl QueryExecutor for Failing {
+        async fn query_with(
+            &self,
+            surql: &str,
+            vars: &Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            self.queries
+                .lock()
+                .unwrap()
+                .push((surql.to_string(), vars.clone()));
+            anyhow::bail!(
+                "Database index `book_slug_unique` already contains 'rust', with record `book:book_1`"
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_maps_slug_index_violations() {
+        let db = Arc::new(Failing {
+            queries: Mutex::new(Vec::new()),
+        });
+        let store = SurrealBookStore::new(db.clone());
+
+        let error = store.insert(book("book_2", "rust")).await.unwrap_err();
+        assert!(matches!(error, BookStoreError::DuplicateSlug(slug) if slug == "rust"));
+
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert!(surql.starts_with("CREATE type::thing('book', $id) SET title = $title"));
+        assert_eq!(vars["id"], "book_2");
+        assert_eq!(vars["created_at"], "2024-01-01T00:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_scopes_queries_to_the_current_tenant() {
+        let db = Arc::new(Failing {
+            queries: Mutex::new(Vec::new()),
+        });
+        let store = SurrealBookStore::new(db.clone());
+
+        let acme = TenantId::new("acme").unwrap();
+        tenant::scope(acme, store.delete("book_1"))
+            .await
+            .unwrap_err();
+
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert_eq!(
+            surql,
+            "DELETE book WHERE id = type::thing('book', $id) AND tenant = $tenant RETURN BEFORE;"
+        );
+        assert_eq!(vars["tenant"], "acme");
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_ranks_and_highlights_matches() {
+        let store = MemoryBookStore::new();
+        for (id, slug, title, author) in [
+            ("book_1", "a", "Programming Rust", "Jim Blandy"),
+            (
+                "book_2",
+                "b",
+                "The Rust Programming Language",
+                "Steve Klabnik",
+            ),
+            ("book_3", "c", "Learning Go", "Jon Bodner"),
+        ] {
+            let book = Book {
+                title: title.to_string(),
+                author: author.to_string(),
+                ..book(id, slug)
+            };
+            store.insert(book).await.unwrap();
+        }
+
+        let page = store
+            .search("rust language", Pagination::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items[0].book.id, "book_2");
+        assert_eq!(
+            page.items[0].highlights.title,
+            "The <mark>Rust</mark> Programming <mark>Language</mark>"
+        );
+        assert_eq!(page.items[0].highlights.author, "Steve Klabnik");
+        assert!(page.items[0].score > page.items[1].score);
+    }
+
+    #[test]
+    fn test_search_rows_decode_into_hits() {
+        let row: SearchRow = serde_json::from_value(json!({
+            "id": "book_1",
+            "title": "Programming Rust",
+            "author": "Jim Blandy",
+            "slug": "programming-rust",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "score": 0.9,
+            "title_highlight": "Programming <mark>Rust</mark>",
+            "author_highlight": null
+        }))
+        .unwrap();
+
+        let hit = row.into_hit();
+        assert_eq!(hit.book.slug, "programming-rust");
+        assert_eq!(hit.highlights.title, "Programming <mark>Rust</mark>");
+        assert_eq!(hit.highlights.author, "Jim Blandy");
+    }
+}
@@ -0,0 +1,351 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use atlas_authz::guard::RequirePermission;
+use atlas_authz::Subject;
+use atlas_http::error::AppError;
+use atlas_http::pagination::{Page, Pagination};
+
+use super::models::{
+    AcceptInvitation, DeclineInvitation, Invitation, InvitationStatus, SendInvitation,
+};
+use super::service::{InvitationError, Invitations};
+use super::store::InvitationQuery;
+use crate::modules::orgs::models::Membership;
+use crate::modules::users::http::is_email;
+
+atlas_authz::permission!(
+    /// List the invitations of organizations the caller administers
+    pub InvitationsRead = "invitations:read"
+);
+atlas_authz::permission!(
+    /// Invite people into organizations, with roles the caller may grant there
+    pub InvitationsSend = "invitations:send"
+);
+
+impl From<InvitationError> for AppError {
+    fn from(error: InvitationError) -> Self {
+        match error {
+            InvitationError::UnknownOrg(_) | InvitationError::UnknownUser(_) => {
+                AppError::not_found(error.to_string())
+            }
+            InvitationError::AlreadyMember(_) => AppError::conflict(
+                vec![json!({ "field": "email", "error": "member" })],
+                error.to_string(),
+            ),
+            InvitationError::AlreadyInvited(_) => AppError::conflict(
+                vec![json!({ "field": "email", "error": "invited" })],
+                error.to_string(),
+            ),
+            InvitationError::InvalidToken => AppError::bad_request(error.to_string()),
+            InvitationError::WrongRecipient | InvitationError::Forbidden(_) => {
+                AppError::forbidden(error.to_string())
+            }
+            InvitationError::Store(error) => AppError::Internal(error),
+        }
+    }
+}
+
+fn required(field: &str, value: &str) -> Option<serde_json::Value> {
+    value
+        .trim()
+        .is_empty()
+        .then(|| json!({ "field": field, "error": "required" }))
+}
+
+/// Query parameters of `GET /`, besides pagination
+#[derive(Debug, Deserialize)]
+struct ListInvitations {
+    org: Option<String>,
+    status: Option<String>,
+}
+
+async fn list_invitations(
+    State(invitations): State<Arc<Invitations>>,
+    _: RequirePermission<InvitationsRead>,
+    subject: Subject,
+    Query(params): Query<ListInvitations>,
+    pagination: Pagination,
+) -> Result<Json<Page<Invitation>>, AppError> {
+    let org = params.org.unwrap_or_default();
+    if let Some(detail) = required("org", &org) {
+        return Err(AppError::validation(vec![detail], "org is required"));
+    }
+    let status = match params.status.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some("pending") => Some(InvitationStatus::Pending),
+        Some("accepted") => Some(InvitationStatus::Accepted),
+        Some("declined") => Some(InvitationStatus::Declined),
+        Some(_) => {
+            return Err(AppError::validation(
+                vec![json!({ "field": "status", "error": "invalid" })],
+                "status must be one of pending, accepted, declined",
+            ))
+        }
+    };
+    let query = InvitationQuery {
+        org: Some(org),
+        status,
+        pagination,
+    };
+    Ok(Json(invitations.list(&query, &subject.0).await?))
+}
+
+async fn send_invitation(
+    State(invitations): State<Arc<Invitations>>,
+    _: RequirePermission<InvitationsSend>,
+    subject: Subject,
+    Json(request): Json<SendInvitation>,
+) -> Result<(StatusCode, Json<Invitation>), AppError> {
+    let mut details: Vec<_> = [
+        required("org", &request.org),
+        required("email", &request.email),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let email = request.email.trim();
+    if !email.is_empty() && !is_email(email) {
+        details.push(json!({ "field": "email", "error": "invalid" }));
+    }
+    if !details.is_empty() {
+        return Err(AppError::validation(
+            details,
+            "org is required and email must be an email address",
+        ));
+    }
+    Ok((
+        StatusCode::CREATED,
+        Json(invitations.send(request, &subject.0).await?),
+    ))
+}
+
+async fn accept_invitation(
+    State(invitations): State<Arc<Invitations>>,
+    subject: Subject,
+    Json(request): Json<AcceptInvitation>,
+) -> Result<Json<Membership>, AppError> {
+    if let Some(detail) = required("token", &request.token) {
+        return Err(AppError::validation(vec![detail], "token is required"));
+    }
+    Ok(Json(invitations.accept(&request.token, &subject.0).await?))
+}
+
+async fn decline_invitation(
+    State(invitations): State<Arc<Invitations>>,
+    Json(request): Json<DeclineInvitation>,
+) -> Result<Json<Invitation>, AppError> {
+    if let Some(detail) = required("token", &request.token) {
+        return Err(AppError::validation(vec![detail], "token is required"));
+    }
+    Ok(Json(invitations.decline(&request.token).await?))
+}
+
+/// Health check endpoint
+async fn health_check() -> &'static str {
+    "invitations module is healthy"
+}
+
+/// Endpoints sending and answering invitations, plus the module health check
+pub fn routes(invitations: Arc<Invitations>) -> Router {
+    Router::new()
+        .route("/", get(list_invitations).post(send_invitation))
+        .route("/accept", post(accept_invitation))
+        .route("/decline", post(decline_invitation))
+        .with_state(invitations)
+        .route("/health", get(health_check))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::invitations::service::tests::setup;
+    use atlas_authz::testing::{as_subject, resources, with_context};
+    use atlas_http::testing::{request, send};
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_invitation_endpoints() {
+        let setup = setup().await;
+        let policy = format!(
+            "p, inviter, invitations, read\np, inviter, invitations, send\ng, {}, inviter\ng, {}, inviter",
+            setup.ann, setup.cat
+        );
+        let router = with_context(
+            Router::new().nest("/api/invitations", routes(Arc::new(setup.invitations))),
+            resources(&policy),
+        );
+        let call = |caller: &str, method: &str, uri: &str, body: Value| {
+            let router = router.clone();
+            let request = as_subject(request(method, uri, body), caller);
+            async move { send(&router, request).await }
+        };
+        let (ann, bob, cat) = (&setup.ann, &setup.bob, &setup.cat);
+
+        let bob_invite = json!({ "org": "acme", "email": "bob@example.com", "role": "member" });
+        let (status, _) = send(
+            &router,
+            request("POST", "/api/invitations", bob_invite.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(bob, "POST", "/api/invitations", bob_invite.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, invitation) = call(cat, "POST", "/api/invitations", bob_invite.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(invitation["status"], "pending");
+        assert!(invitation.get("token_hash").is_none());
+        let (status, error) = call(ann, "POST", "/api/invitations", bob_invite).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["details"][0]["error"], "invited");
+
+        // Admins cannot make owners
+        let (status, _) = call(
+            cat,
+            "POST",
+            "/api/invitations",
+            json!({ "org": "acme", "email": "dan@example.com", "role": "owner" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(
+            ann,
+            "POST",
+            "/api/invitations",
+            json!({ "org": "globex", "email": "carol@example.com", "role": "member" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, error) = call(
+            ann,
+            "POST",
+            "/api/invitations",
+            json!({ "org": "", "email": "carol", "role": "member" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"].as_array().unwrap().len(), 2);
+
+        let token = setup.mailer.last_token();
+        let accept = json!({ "token": token });
+        let (status, _) = send(
+            &router,
+            request("POST", "/api/invitations/accept", accept.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(
+            bob,
+            "POST",
+            "/api/invitations/accept",
+            json!({ "token": "inv_forged" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(ann, "POST", "/api/invitations/accept", accept.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, membership) = call(bob, "POST", "/api/invitations/accept", accept).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(membership["org"], "acme");
+        assert_eq!(membership["user_id"], bob.as_str());
+        assert_eq!(membership["role"], "member");
+
+        let accepted = "/api/invitations?org=acme&status=accepted";
+        let (status, page) = call(ann, "GET", accepted, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 1);
+        // Bob is a member now, but may not see the invitations
+        let (status, _) = call(bob, "GET", accepted, Value::Null).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(ann, "GET", "/api/invitations", Value::Null).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _) = call(
+            ann,
+            "GET",
+            "/api/invitations?org=acme&status=lost",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_served_routes_authenticate_the_caller() {
+        use crate::modules::users::{models::CreateUser, service::Users};
+
+        let app = atlas_authz::testing::TestApp::new(
+            vec![
+                Arc::new(crate::modules::users::UsersModule::new()),
+                Arc::new(crate::modules::orgs::OrgsModule::new()),
+                Arc::new(crate::modules::invitations::InvitationsModule::new()),
+            ],
+            "p, inviter, invitations, read\np, inviter, invitations, send",
+        )
+        .await;
+        let users = app.registry.resources().require::<Users>().unwrap();
+        let mut ids = Vec::new();
+        for email in ["ann@example.com", "bob@example.com"] {
+            let user = users
+                .create(CreateUser {
+                    email: email.to_string(),
+                    name: "Someone".to_string(),
+                    bio: None,
+                    avatar_url: None,
+                })
+                .await
+                .unwrap();
+            ids.push(user.id);
+        }
+        let (ann, bob) = (ids[0].as_str(), ids[1].as_str());
+        app.add_policy(&format!("g, {}, inviter", ann));
+        let create_org = app
+            .with_api_key(
+                request(
+                    "POST",
+                    "/api/orgs",
+                    json!({ "slug": "acme", "name": "Acme" }),
+                ),
+                ann,
+            )
+            .await;
+        assert_eq!(send(&app.router, create_org).await.0, StatusCode::CREATED);
+
+        let bob_invite = json!({ "org": "acme", "email": "bob@example.com", "role": "member" });
+        let (status, _) = send(
+            &app.router,
+            request("POST", "/api/invitations", bob_invite.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_bob = app
+            .with_api_key(request("POST", "/api/invitations", bob_invite.clone()), bob)
+            .await;
+        assert_eq!(send(&app.router, as_bob).await.0, StatusCode::FORBIDDEN);
+        let as_ann = app
+            .with_session(request("POST", "/api/invitations", bob_invite), ann)
+            .await;
+        let (status, invitation) = send(&app.router, as_ann).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(invitation["status"], "pending");
+
+        let accept = json!({ "token": "inv_unknown" });
+        let (status, _) = send(
+            &app.router,
+            request("POST", "/api/invitations/accept", accept.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_bob = app
+            .with_api_key(request("POST", "/api/invitations/accept", accept), bob)
+            .await;
+        assert_ne!(send(&app.router, as_bob).await.0, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,353 @@
+pub mod http;
+pub mod models;
+pub mod service;
+pub mod store;
+
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use atlas_http::pagination;
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SchemaExample, SecurityScheme};
+use axum::Router;
+use serde_json::json;
+
+use crate::modules::orgs::service::Orgs;
+use crate::modules::users::service::Users;
+use crate::utils::mail;
+use service::Invitations;
+use store::{InvitationStore, MemoryInvitationStore, SurrealInvitationStore};
+
+/// Invitations into organizations, backed by the `invitation` table
+///
+/// Tokens are emailed through the application's `Arc<dyn Mailer>`; accepting one makes
+/// the caller a member with the invited role. Senders need `invitations:send` and must be
+/// allowed to grant the invited role in the organization. Publishes `Invitations` as a shared
+/// resource.
+#[derive(Default)]
+pub struct InvitationsModule {
+    invitations: OnceLock<Arc<Invitations>>,
+}
+
+impl InvitationsModule {
+    pub const fn new() -> Self {
+        Self {
+            invitations: OnceLock::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Module for InvitationsModule {
+    fn name(&self) -> &'static str {
+        "invitations"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Email invitations into organizations")
+    }
+
+    /// Accepted invitations become memberships of users
+    fn depends_on(&self) -> &[&'static str] {
+        &["orgs", "users"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn InvitationStore>(
+            &ctx.resources,
+            "invitation",
+            |db| Arc::new(SurrealInvitationStore::new(db)),
+            || Arc::new(MemoryInvitationStore::new()),
+        );
+        let invitations = Arc::new(Invitations::new(
+            store,
+            ctx.resources.require::<Orgs>()?,
+            ctx.resources.require::<Users>()?,
+            mail::mailer(&ctx.resources, self.name()),
+            ctx.clock.clone(),
+        ));
+        ctx.resources.insert_arc(invitations.clone());
+        self.invitations
+            .set(invitations)
+            .map_err(|_| anyhow::anyhow!("invitations module initialized twice"))?;
+
+        tracing::info!(
+            module = self.name(),
+            environment = ?ctx.settings.environment,
+            "invitations module initialized"
+        );
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.invitations.get() {
+            Some(invitations) => http::routes(invitations.clone()),
+            None => Router::new(),
+        }
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        let route = |method, path, permission| RouteSecurity {
+            method,
+            path,
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission,
+        };
+        vec![
+            route("get", "/", Some("invitations:read")),
+            route("post", "/", Some("invitations:send")),
+            route("post", "/accept", None),
+        ]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let error = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorResponse" }
+                    }
+                }
+            })
+        };
+        let schema = |description: &str, schema: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let body = |schema: &str| {
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let mut list_parameters = vec![
+            json!({
+                "name": "org",
+                "in": "query",
+                "required": true,
+                "description": "Organization whose invitations to list; the caller must be an admin",
+                "schema": { "type": "string" }
+            }),
+            json!({
+                "name": "status",
+                "in": "query",
+                "schema": { "type": "string", "enum": ["pending", "accepted", "declined"] }
+            }),
+        ];
+        list_parameters.extend(pagination::openapi::parameters());
+        let text = |description: &str| json!({ "type": "string", "description": description });
+        let timestamp = |description: &str| json!({ "type": "string", "format": "date-time", "description": description });
+        let role = json!({ "type": "string", "enum": ["owner", "admin", "member"] });
+        let token = text("Invitation token from the email");
+        Some(json!({
+            "tags": [
+                {
+                    "name": "Invitations",
+                    "description": "Invitations — emailed offers to join an organization",
+                    "x-order": 4
+                }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List invitations",
+                        "tags": ["Invitations"],
+                        "parameters": list_parameters,
+                        "responses": {
+                            "200": {
+                                "description": "One page of the matching invitations, newest first",
+                                "content": {
+                                    "application/json": {
+                                        "schema": pagination::openapi::page_schema(
+                                            json!({ "$ref": "#/components/schemas/Invitation" })
+                                        )
+                                    }
+                                }
+                            },
+                            "403": error("The caller is not an admin of the organization"),
+                            "404": error("No organization with that slug among the caller's"),
+                            "422": error("Blank org, or invalid status, page, or per_page"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "post": {
+                        "summary": "Send an invitation",
+                        "description": "Emails a token that accepts or declines the invitation for seven days.",
+                        "tags": ["Invitations"],
+                        "requestBody": body("SendInvitation"),
+                        "responses": {
+                            "201": schema("The pending invitation", "Invitation"),
+                            "403": error("The caller may not grant the invited role in the organization"),
+                            "404": error("No organization with that slug among the caller's"),
+                            "409": error("The address is already a member or has a pending invitation"),
+                            "422": error("Blank org or invalid email"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/accept": {
+                    "post": {
+                        "summary": "Accept an invitation",
+                        "description": "Makes the caller a member with the invited role; their email must be the invited one.",
+                        "tags": ["Invitations"],
+                        "requestBody": body("AcceptInvitation"),
+                        "responses": {
+                            "200": schema("The caller's membership", "Membership"),
+                            "400": error("Invalid, expired, or already answered token"),
+                            "403": error("The invitation was sent to another email address"),
+                            "404": error("The caller's account or the organization was deleted"),
+                            "422": error("Blank token"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/decline": {
+                    "post": {
+                        "summary": "Decline an invitation",
+                        "tags": ["Invitations"],
+                        "requestBody": body("DeclineInvitation"),
+                        "responses": {
+                            "200": schema("The declined invitation", "Invitation"),
+                            "400": error("Invalid, expired, or already answered token"),
+                            "422": error("Blank token"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/health": {
+                    "get": {
+                        "summary": "Invitations health check",
+                        "tags": ["Invitations"],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": {
+                                    "text/plain": {
+                                        "schema": { "type": "string" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Invitation": {
+                        "type": "object",
+                        "properties": {
+                            "id": text("Unique identifier for the invitation"),
+                            "org": text("Slug of the organization"),
+                            "email": text("Address the invitation was sent to"),
+                            "role": role.clone(),
+                            "status": {
+                                "type": "string",
+                                "enum": ["pending", "accepted", "declined"]
+                            },
+                            "expires_at": timestamp("After this, the invitation can no longer be answered"),
+                            "created_at": timestamp("When the invitation was sent"),
+                            "updated_at": timestamp("When the invitation was last answered")
+                        },
+                        "required": ["id", "org", "email", "role", "status", "expires_at", "created_at", "updated_at"]
+                    },
+                    "SendInvitation": {
+                        "type": "object",
+                        "properties": {
+                            "org": text("Slug of the organization"),
+                            "email": { "type": "string", "format": "email" },
+                            "role": role
+                        },
+                        "required": ["org", "email", "role"]
+                    },
+                    "AcceptInvitation": {
+                        "type": "object",
+                        "properties": { "token": token.clone() },
+                        "required": ["token"]
+                    },
+                    "DeclineInvitation": {
+                        "type": "object",
+                        "properties": { "token": token },
+                        "required": ["token"]
+                    }
+                }
+            }
+        }))
+    }
+
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![
+            SchemaExample::new(
+                "Invitation",
+                json!({
+                    "id": "inv_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                    "org": "acme",
+                    "email": "bob@example.com",
+                    "role": "member",
+                    "status": "pending",
+                    "expires_at": "2024-01-08T00:00:00Z",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-01T00:00:00Z"
+                }),
+            ),
+            SchemaExample::new(
+                "SendInvitation",
+                json!({
+                    "org": "acme",
+                    "email": "bob@example.com",
+                    "role": "member"
+                }),
+            ),
+        ]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_init",
+            up: r#"
+                DEFINE TABLE invitation SCHEMAFULL;
+                DEFINE FIELD org        ON invitation TYPE string;
+                DEFINE FIELD email      ON invitation TYPE string ASSERT $value != "";
+                DEFINE FIELD role       ON invitation TYPE string ASSERT $value IN ["owner", "admin", "member"];
+                DEFINE FIELD status     ON invitation TYPE string ASSERT $value IN ["pending", "accepted", "declined"];
+                DEFINE FIELD token_hash ON invitation TYPE string;
+                DEFINE FIELD expires_at ON invitation TYPE datetime;
+                DEFINE FIELD created_at ON invitation TYPE datetime VALUE $before OR $value;
+                DEFINE FIELD updated_at ON invitation TYPE datetime;
+                DEFINE INDEX invitation_token_unique ON invitation FIELDS token_hash UNIQUE;
+                DEFINE INDEX invitation_org_email ON invitation FIELDS org, email;
+                "#,
+            down: Some("REMOVE TABLE invitation;"),
+        }]
+    }
+
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "invitations module started");
+        Ok(())
+    }
+
+    async fn stop(&self) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "invitations module stopped");
+        Ok(())
+    }
+}
+
+/// Create a new instance of the invitations module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(InvitationsModule::new())
+}
+
+atlas_kernel::register_module!(create_module);
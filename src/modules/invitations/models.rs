@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::modules::orgs::models::Role;
+
+/// Where an invitation stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Declined,
+}
+
+impl InvitationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvitationStatus::Pending => "pending",
+            InvitationStatus::Accepted => "accepted",
+            InvitationStatus::Declined => "declined",
+        }
+    }
+}
+
+/// An invitation for an email address to join an organization with a role
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invitation {
+    /// Unique identifier, e.g. `inv_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Slug of the organization
+    pub org: String,
+    /// Address the invitation was sent to, lowercased
+    pub email: String,
+    /// Role the invitee gets on accepting
+    pub role: Role,
+    pub status: InvitationStatus,
+    /// SHA-256 of the token sent by email; the token itself is never stored
+    #[serde(default, skip_serializing)]
+    pub token_hash: String,
+    /// After this, the invitation can no longer be accepted or declined
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// When the invitation was last answered
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl Invitation {
+    /// Whether the invitation can still be answered at `now`
+    pub fn is_open(&self, now: OffsetDateTime) -> bool {
+        self.status == InvitationStatus::Pending && now < self.expires_at
+    }
+}
+
+/// Request body of `POST /`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendInvitation {
+    pub org: String,
+    pub email: String,
+    pub role: Role,
+}
+
+/// Request body of `POST /accept`; the caller joins, so their email must be the invited one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptInvitation {
+    pub token: String,
+}
+
+/// Request body of `POST /decline`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclineInvitation {
+    pub token: String,
+}
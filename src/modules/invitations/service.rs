@@ -0,0 +1,438 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use time::OffsetDateTime;
+
+use atlas_http::pagination::Page;
+use atlas_kernel::{crypto, id, Clock};
+
+use super::models::{Invitation, InvitationStatus, SendInvitation};
+use super::store::{InvitationQuery, InvitationStore};
+use crate::modules::orgs::models::{Membership, Role};
+use crate::modules::orgs::service::{OrgError, Orgs};
+use crate::modules::users::service::Users;
+use crate::modules::users::store::UserQuery;
+use crate::utils::mail::{Email, Mailer};
+
+/// How long an invitation can be answered
+pub const TOKEN_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Prefix of every invitation token
+const TOKEN_PREFIX: &str = "inv_";
+
+/// Why an invitation could not be sent or answered
+#[derive(Debug, thiserror::Error)]
+pub enum InvitationError {
+    #[error("no organization '{0}'")]
+    UnknownOrg(String),
+    #[error("no user '{0}'")]
+    UnknownUser(String),
+    #[error("'{0}' is already a member")]
+    AlreadyMember(String),
+    #[error("'{0}' already has a pending invitation")]
+    AlreadyInvited(String),
+    #[error("invalid or expired invitation token")]
+    InvalidToken,
+    #[error("the invitation was sent to another email address")]
+    WrongRecipient,
+    #[error("{0}")]
+    Forbidden(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+impl From<OrgError> for InvitationError {
+    fn from(error: OrgError) -> Self {
+        match error {
+            OrgError::UnknownOrg(org) => InvitationError::UnknownOrg(org),
+            OrgError::UnknownUser(user_id) => InvitationError::UnknownUser(user_id),
+            OrgError::Store(error) => InvitationError::Store(error),
+            error @ OrgError::Forbidden { .. } => InvitationError::Forbidden(error.to_string()),
+            error => InvitationError::Store(anyhow!(error)),
+        }
+    }
+}
+
+/// Invites email addresses into organizations and turns accepted invitations into
+/// memberships
+///
+/// The token is only ever sent by email; the store keeps its SHA-256.
+pub struct Invitations {
+    store: Arc<dyn InvitationStore>,
+    orgs: Arc<Orgs>,
+    users: Arc<Users>,
+    mailer: Arc<dyn Mailer>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Invitations {
+    pub fn new(
+        store: Arc<dyn InvitationStore>,
+        orgs: Arc<Orgs>,
+        users: Arc<Users>,
+        mailer: Arc<dyn Mailer>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            store,
+            orgs,
+            users,
+            mailer,
+            clock,
+        }
+    }
+
+    /// Email an invitation token to `request.email` on behalf of `sender`
+    ///
+    /// The sender must be allowed to grant the invited role, so only owners invite owners.
+    /// Members and addresses with an open invitation to the organization are refused.
+    pub async fn send(
+        &self,
+        request: SendInvitation,
+        sender: &str,
+    ) -> Result<Invitation, InvitationError> {
+        self.orgs
+            .authorize_grant(&request.org, sender, request.role)
+            .await?;
+        let email = request.email.trim().to_lowercase();
+        let Some(org) = self.orgs.get(&request.org).await? else {
+            return Err(InvitationError::UnknownOrg(request.org));
+        };
+        let now = self.now();
+        if let Some(pending) = self.store.pending(&org.slug, &email).await? {
+            if pending.is_open(now) {
+                return Err(InvitationError::AlreadyInvited(email));
+            }
+        }
+        let existing = self
+            .users
+            .list(&UserQuery {
+                email: Some(email.clone()),
+                ..UserQuery::default()
+            })
+            .await?;
+        if let Some(user) = existing.items.first() {
+            if self.orgs.membership(&org.slug, &user.id).await?.is_some() {
+                return Err(InvitationError::AlreadyMember(email));
+            }
+        }
+
+        let token = crypto::generate_token(TOKEN_PREFIX)?;
+        let invitation = Invitation {
+            id: id::prefixed_with("inv", id::ulid_at(self.clock.now()))
+                .map_err(anyhow::Error::from)?,
+            org: org.slug,
+            email,
+            role: request.role,
+            status: InvitationStatus::Pending,
+            token_hash: hash(&token),
+            expires_at: now + TOKEN_TTL,
+            created_at: now,
+            updated_at: now,
+        };
+        // Deliver first: a token that failed to store is merely useless, while a stored
+        // invitation that was never delivered would block inviting the address again
+        self.mailer
+            .send(Email {
+                to: invitation.email.clone(),
+                subject: format!("You're invited to join {}", org.name),
+                body: format!(
+                    "You have been invited to join {} as {}.\n\n\
+                     Accept or decline with this invitation token within {} days:\n\n{}\n",
+                    org.name,
+                    invitation.role.as_str(),
+                    TOKEN_TTL.as_secs() / (24 * 60 * 60),
+                    token
+                ),
+            })
+            .await
+            .map_err(|error| error.context("failed to deliver invitation"))?;
+        self.store.insert(invitation.clone()).await?;
+        tracing::info!(invitation_id = %invitation.id, org = %invitation.org, "sent invitation");
+        Ok(invitation)
+    }
+
+    /// Invitations to `query.org`, which `caller` must administer
+    pub async fn list(
+        &self,
+        query: &InvitationQuery,
+        caller: &str,
+    ) -> Result<Page<Invitation>, InvitationError> {
+        let org = query.org.as_deref().unwrap_or_default();
+        self.orgs.authorize(org, caller, Role::Admin).await?;
+        Ok(self.store.list(query).await?)
+    }
+
+    /// Make `user_id` a member with the invited role
+    ///
+    /// The user's email must be the invited address. A user who is already a member
+    /// keeps the role they have.
+    pub async fn accept(&self, token: &str, user_id: &str) -> Result<Membership, InvitationError> {
+        let invitation = self.open_invitation(token).await?;
+        let Some(user) = self.users.get(user_id).await? else {
+            return Err(InvitationError::UnknownUser(user_id.to_string()));
+        };
+        if user.email != invitation.email {
+            return Err(InvitationError::WrongRecipient);
+        }
+        let membership = match self.orgs.membership(&invitation.org, user_id).await? {
+            Some(membership) => membership,
+            None => {
+                self.orgs
+                    .set_role(&invitation.org, user_id, invitation.role)
+                    .await?
+            }
+        };
+        self.answer(invitation, InvitationStatus::Accepted).await?;
+        Ok(membership)
+    }
+
+    pub async fn decline(&self, token: &str) -> Result<Invitation, InvitationError> {
+        let invitation = self.open_invitation(token).await?;
+        Ok(self.answer(invitation, InvitationStatus::Declined).await?)
+    }
+
+    async fn open_invitation(&self, token: &str) -> Result<Invitation, InvitationError> {
+        match self.store.by_token_hash(&hash(token)).await? {
+            Some(invitation) if invitation.is_open(self.now()) => Ok(invitation),
+            _ => Err(InvitationError::InvalidToken),
+        }
+    }
+
+    async fn answer(
+        &self,
+        invitation: Invitation,
+        status: InvitationStatus,
+    ) -> anyhow::Result<Invitation> {
+        let invitation = Invitation {
+            status,
+            updated_at: self.now(),
+            ..invitation
+        };
+        self.store.update(invitation.clone()).await?;
+        tracing::info!(
+            invitation_id = %invitation.id,
+            org = %invitation.org,
+            status = status.as_str(),
+            "answered invitation"
+        );
+        Ok(invitation)
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+/// Digest stored in place of the token; tokens carry 256 bits of entropy, so a plain
+/// SHA-256 is enough
+fn hash(token: &str) -> String {
+    crypto::sha256_hex(token.trim().as_bytes())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::modules::invitations::store::MemoryInvitationStore;
+    use crate::modules::orgs::models::CreateOrg;
+    use crate::modules::orgs::store::MemoryOrgStore;
+    use crate::modules::users::models::CreateUser;
+    use crate::modules::users::store::MemoryUserStore;
+    use atlas_kernel::clock::ManualClock;
+
+    /// Mailer keeping every message
+    #[derive(Default)]
+    pub(crate) struct RecordingMailer {
+        pub(crate) sent: Mutex<Vec<Email>>,
+    }
+
+    impl RecordingMailer {
+        /// The token in the last message
+        pub(crate) fn last_token(&self) -> String {
+            let sent = self.sent.lock().unwrap();
+            let body = &sent.last().expect("no email sent").body;
+            body.split_whitespace()
+                .find(|word| word.starts_with(TOKEN_PREFIX))
+                .expect("no token in email")
+                .to_string()
+        }
+    }
+
+    #[async_trait]
+    impl Mailer for RecordingMailer {
+        async fn send(&self, email: Email) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    pub(crate) struct Setup {
+        pub(crate) invitations: Invitations,
+        pub(crate) mailer: Arc<RecordingMailer>,
+        pub(crate) clock: Arc<ManualClock>,
+        /// Owner of `acme`
+        pub(crate) ann: String,
+        /// bob@example.com, not a member
+        pub(crate) bob: String,
+        /// Admin of `acme`
+        pub(crate) cat: String,
+    }
+
+    pub(crate) async fn setup() -> Setup {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::now()));
+        let users = Arc::new(Users::new(Arc::new(MemoryUserStore::new()), clock.clone()));
+        let mut ids = Vec::new();
+        for email in ["ann@example.com", "bob@example.com", "cat@example.com"] {
+            let user = users
+                .create(CreateUser {
+                    email: email.to_string(),
+                    name: "Someone".to_string(),
+                    bio: None,
+                    avatar_url: None,
+                })
+                .await
+                .unwrap();
+            ids.push(user.id);
+        }
+        let orgs = Arc::new(Orgs::new(
+            Arc::new(MemoryOrgStore::new()),
+            users.clone(),
+            clock.clone(),
+        ));
+        orgs.create(CreateOrg {
+            slug: "acme".to_string(),
+            name: "Acme Inc.".to_string(),
+            owner_id: ids[0].clone(),
+        })
+        .await
+        .unwrap();
+        orgs.set_role("acme", &ids[2], Role::Admin).await.unwrap();
+        let mailer = Arc::new(RecordingMailer::default());
+        let invitations = Invitations::new(
+            Arc::new(MemoryInvitationStore::new()),
+            orgs,
+            users,
+            mailer.clone(),
+            clock.clone(),
+        );
+        let cat = ids.pop().unwrap();
+        let bob = ids.pop().unwrap();
+        let ann = ids.pop().unwrap();
+        Setup {
+            invitations,
+            mailer,
+            clock,
+            ann,
+            bob,
+            cat,
+        }
+    }
+
+    fn invite(email: &str) -> SendInvitation {
+        SendInvitation {
+            org: "acme".to_string(),
+            email: email.to_string(),
+            role: Role::Admin,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accepting_creates_the_membership() {
+        let setup = setup().await;
+        let invitations = &setup.invitations;
+
+        let invitation = invitations
+            .send(invite("Bob@Example.com"), &setup.ann)
+            .await
+            .unwrap();
+        assert_eq!(invitation.email, "bob@example.com");
+        assert_eq!(setup.mailer.sent.lock().unwrap()[0].to, "bob@example.com");
+        assert!(matches!(
+            invitations
+                .send(invite("bob@example.com"), &setup.ann)
+                .await,
+            Err(InvitationError::AlreadyInvited(_))
+        ));
+        assert!(matches!(
+            invitations
+                .send(invite("ann@example.com"), &setup.ann)
+                .await,
+            Err(InvitationError::AlreadyMember(_))
+        ));
+
+        let token = setup.mailer.last_token();
+        assert!(matches!(
+            invitations.accept(&token, &setup.ann).await,
+            Err(InvitationError::WrongRecipient)
+        ));
+        let membership = invitations.accept(&token, &setup.bob).await.unwrap();
+        assert_eq!(membership.role, Role::Admin);
+        assert!(matches!(
+            invitations.decline(&token).await,
+            Err(InvitationError::InvalidToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_senders_grant_only_roles_they_manage() {
+        let setup = setup().await;
+        let invitations = &setup.invitations;
+        let owner = SendInvitation {
+            role: Role::Owner,
+            ..invite("dan@example.com")
+        };
+
+        assert!(matches!(
+            invitations.send(owner.clone(), &setup.cat).await,
+            Err(InvitationError::Forbidden(_))
+        ));
+        assert!(matches!(
+            invitations
+                .send(invite("dan@example.com"), &setup.bob)
+                .await,
+            Err(InvitationError::UnknownOrg(_))
+        ));
+        invitations
+            .send(invite("dan@example.com"), &setup.cat)
+            .await
+            .unwrap();
+        let owner = SendInvitation {
+            email: "eve@example.com".to_string(),
+            ..owner
+        };
+        invitations.send(owner, &setup.ann).await.unwrap();
+        assert_eq!(setup.mailer.sent.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_invitations_cannot_be_answered() {
+        let setup = setup().await;
+        let invitations = &setup.invitations;
+        invitations
+            .send(invite("carol@example.com"), &setup.ann)
+            .await
+            .unwrap();
+        let token = setup.mailer.last_token();
+
+        setup.clock.advance(TOKEN_TTL);
+        assert!(matches!(
+            invitations.decline(&token).await,
+            Err(InvitationError::InvalidToken)
+        ));
+        // An expired invitation does not block a new one
+        invitations
+            .send(invite("carol@example.com"), &setup.ann)
+            .await
+            .unwrap();
+        let declined = invitations
+            .decline(&setup.mailer.last_token())
+            .await
+            .unwrap();
+        assert_eq!(declined.status, InvitationStatus::Declined);
+    }
+}
@@ -0,0 +1,369 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use atlas_db::QueryExecutor;
+use atlas_http::pagination::{Page, Pagination};
+
+use super::models::{Invitation, InvitationStatus};
+
+/// Filters and page of an invitation listing
+#[derive(Debug, Clone, Default)]
+pub struct InvitationQuery {
+    /// Only invitations to this organization
+    pub org: Option<String>,
+    pub status: Option<InvitationStatus>,
+    pub pagination: Pagination,
+}
+
+impl InvitationQuery {
+    fn matches(&self, invitation: &Invitation) -> bool {
+        self.org.as_ref().is_none_or(|org| &invitation.org == org)
+            && self.status.is_none_or(|status| invitation.status == status)
+    }
+}
+
+/// Persistence for invitations
+///
+/// The module stores them in SurrealDB through the application's `QueryExecutor`;
+/// applications may publish their own `Arc<dyn InvitationStore>` resource instead.
+/// Without either, they live in memory.
+#[async_trait]
+pub trait InvitationStore: Send + Sync {
+    async fn insert(&self, invitation: Invitation) -> anyhow::Result<()>;
+
+    /// The invitation whose token hashes to `token_hash`
+    async fn by_token_hash(&self, token_hash: &str) -> anyhow::Result<Option<Invitation>>;
+
+    /// The latest pending invitation of `email` to `org`
+    async fn pending(&self, org: &str, email: &str) -> anyhow::Result<Option<Invitation>>;
+
+    /// The page of invitations matching `query`, newest first
+    async fn list(&self, query: &InvitationQuery) -> anyhow::Result<Page<Invitation>>;
+
+    /// Replace the stored invitation with the same id, returning `false` if there is none
+    async fn update(&self, invitation: Invitation) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; invitations are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryInvitationStore {
+    invitations: RwLock<Vec<Invitation>>,
+}
+
+impl MemoryInvitationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl InvitationStore for MemoryInvitationStore {
+    async fn insert(&self, invitation: Invitation) -> anyhow::Result<()> {
+        self.invitations
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(invitation);
+        Ok(())
+    }
+
+    async fn by_token_hash(&self, token_hash: &str) -> anyhow::Result<Option<Invitation>> {
+        Ok(self
+            .invitations
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|invitation| invitation.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn pending(&self, org: &str, email: &str) -> anyhow::Result<Option<Invitation>> {
+        Ok(self
+            .invitations
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|invitation| {
+                invitation.org == org
+                    && invitation.email == email
+                    && invitation.status == InvitationStatus::Pending
+            })
+            .max_by_key(|invitation| invitation.created_at)
+            .cloned())
+    }
+
+    async fn list(&self, query: &InvitationQuery) -> anyhow::Result<Page<Invitation>> {
+        let mut invitations: Vec<Invitation> = self
+            .invitations
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|invitation| query.matches(invitation))
+            .cloned()
+            .collect();
+        invitations.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        let total = invitations.len() as u64;
+        Ok(Page::new(
+            query.pagination.slice(invitations),
+            total,
+            query.pagination,
+        ))
+    }
+
+    async fn update(&self, invitation: Invitation) -> anyhow::Result<bool> {
+        let mut invitations = self
+            .invitations
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match invitations
+            .iter_mut()
+            .find(|stored| stored.id == invitation.id)
+        {
+            Some(stored) => {
+                *stored = invitation;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_INVITATION: &str = "SELECT *, record::id(id) AS id FROM invitation";
+
+/// Every field of an invitation but its id
+const SET_INVITATION: &str = "org = $org, email = $email, role = $role, status = $status, \
+     token_hash = $token_hash, expires_at = <datetime> $expires_at, \
+     created_at = <datetime> $created_at, updated_at = <datetime> $updated_at";
+
+/// Invitations in the `invitation` table defined by the module's migrations
+pub struct SurrealInvitationStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealInvitationStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// The fields of `invitation` bound as `$id`, `$org`, and so on
+    fn vars(invitation: &Invitation) -> anyhow::Result<Map<String, Value>> {
+        let mut vars =
+            match serde_json::to_value(invitation).context("failed to serialize invitation")? {
+                Value::Object(vars) => vars,
+                _ => unreachable!("invitations serialize as objects"),
+            };
+        // Never part of API responses, so not serialized
+        vars.insert("token_hash".to_string(), json!(invitation.token_hash));
+        Ok(vars)
+    }
+
+    async fn one(
+        &self,
+        surql: &str,
+        vars: Map<String, Value>,
+    ) -> anyhow::Result<Option<Invitation>> {
+        let results = self
+            .db
+            .query_with(surql, &vars)
+            .await
+            .context("failed to read invitation")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+}
+
+#[async_trait]
+impl InvitationStore for SurrealInvitationStore {
+    async fn insert(&self, invitation: Invitation) -> anyhow::Result<()> {
+        let surql = format!(
+            "CREATE type::thing('invitation', $id) SET {} RETURN NONE;",
+            SET_INVITATION
+        );
+        self.db
+            .query_with(&surql, &Self::vars(&invitation)?)
+            .await
+            .context("failed to store invitation")?;
+        Ok(())
+    }
+
+    async fn by_token_hash(&self, token_hash: &str) -> anyhow::Result<Option<Invitation>> {
+        let mut vars = Map::new();
+        vars.insert("token_hash".to_string(), json!(token_hash));
+        self.one(
+            &format!("{} WHERE token_hash = $token_hash;", SELECT_INVITATION),
+            vars,
+        )
+        .await
+    }
+
+    async fn pending(&self, org: &str, email: &str) -> anyhow::Result<Option<Invitation>> {
+        let mut vars = Map::new();
+        vars.insert("org".to_string(), json!(org));
+        vars.insert("email".to_string(), json!(email));
+        self.one(
+            &format!(
+                "{} WHERE org = $org AND email = $email AND status = 'pending' \
+                 ORDER BY created_at DESC LIMIT 1;",
+                SELECT_INVITATION
+            ),
+            vars,
+        )
+        .await
+    }
+
+    async fn list(&self, query: &InvitationQuery) -> anyhow::Result<Page<Invitation>> {
+        let mut vars = Map::new();
+        let mut conditions = Vec::new();
+        if let Some(org) = &query.org {
+            vars.insert("org".to_string(), json!(org));
+            conditions.push("org = $org");
+        }
+        if let Some(status) = query.status {
+            vars.insert("status".to_string(), json!(status.as_str()));
+            conditions.push("status = $status");
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        vars.insert("limit".to_string(), json!(query.pagination.limit()));
+        vars.insert("start".to_string(), json!(query.pagination.offset()));
+        let surql = format!(
+            "{select} {filter} ORDER BY created_at DESC LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM invitation {filter} GROUP ALL;",
+            select = SELECT_INVITATION,
+            filter = filter,
+        );
+        let mut results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to list invitations")?
+            .into_iter();
+        let invitations = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(invitations, total, query.pagination))
+    }
+
+    async fn update(&self, invitation: Invitation) -> anyhow::Result<bool> {
+        // A `WHERE` keeps `UPDATE` from creating a missing record
+        let surql = format!(
+            "UPDATE invitation SET {} WHERE id = type::thing('invitation', $id) \
+             RETURN VALUE record::id(id);",
+            SET_INVITATION
+        );
+        let results = self
+            .db
+            .query_with(&surql, &Self::vars(&invitation)?)
+            .await
+            .context("failed to update invitation")?;
+        Ok(matches!(results.first(), Some(Value::Array(records)) if !records.is_empty()))
+    }
+}
+
+/// Row of a `count()` query
+#[derive(serde::Deserialize)]
+struct Total {
+    total: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::modules::orgs::models::Role;
+
+    fn invitation(id: &str) -> Invitation {
+        Invitation {
+            id: id.to_string(),
+            org: "acme".to_string(),
+            email: "ann@example.com".to_string(),
+            role: Role::Member,
+            status: InvitationStatus::Pending,
+            token_hash: "abc123".to_string(),
+            expires_at: datetime!(2024-01-08 0:00 UTC),
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    struct Recording {
+        queries: Mutex<Vec<(String, Map<String, Value>)>>,
+    }
+
+    #[async_trait]
+    impl QueryExecutor for Recording {
+        async fn query_with(
+            &self,
+            surql: &str,
+            vars: &Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            self.queries
+                .lock()
+                .unwrap()
+                .push((surql.to_string(), vars.clone()));
+            Ok(vec![Value::Null])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_writes_the_token_hash() {
+        let db = Arc::new(Recording {
+            queries: Mutex::new(Vec::new()),
+        });
+        let store = SurrealInvitationStore::new(db.clone());
+
+        store.insert(invitation("inv_1")).await.unwrap();
+
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert!(surql.starts_with("CREATE type::thing('invitation', $id) SET org = $org"));
+        assert_eq!(vars["token_hash"], "abc123");
+        assert_eq!(vars["status"], "pending");
+        assert_eq!(vars["expires_at"], "2024-01-08T00:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_finds_the_latest_pending_invitation() {
+        let store = MemoryInvitationStore::new();
+        store
+            .insert(Invitation {
+                status: InvitationStatus::Declined,
+                ..invitation("inv_1")
+            })
+            .await
+            .unwrap();
+        store
+            .insert(Invitation {
+                created_at: datetime!(2024-01-02 0:00 UTC),
+                ..invitation("inv_2")
+            })
+            .await
+            .unwrap();
+
+        let pending = store.pending("acme", "ann@example.com").await.unwrap();
+        assert_eq!(pending.unwrap().id, "inv_2");
+        let page = store
+            .list(&InvitationQuery {
+                status: Some(InvitationStatus::Declined),
+                ..InvitationQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].id, "inv_1");
+    }
+}
@@ -1,10 +1,16 @@
 pub mod books;
+pub mod invitations;
+pub mod notifications;
+pub mod orgs;
 pub mod users;
 
 use atlas_kernel::ModuleRegistry;
 
 /// Register all project-specific modules with the registry
-pub fn register_all(registry: &mut ModuleRegistry) {
-    registry.register_custom(books::create_module());
-    registry.register_custom(users::create_module());
+///
+/// Modules submit themselves with `atlas_kernel::register_module!`, so adding a module
+/// only needs its `mod` declaration above.
+pub fn register_all(registry: &mut ModuleRegistry) -> anyhow::Result<()> {
+    registry.register_submitted()?;
+    Ok(())
 }
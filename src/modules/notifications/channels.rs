@@ -0,0 +1,243 @@
+//! Delivery of notifications beyond the in-app inbox
+//!
+//! Every stored notification is handed to each channel in the `NotificationChannels`
+//! resource the application registers. Without one, notifications are emailed through
+//! the registered `Arc<dyn Mailer>`, if any, and are otherwise in-app only.
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+
+use atlas_kernel::Clock;
+use atlas_webhooks::{WebhookTransport, DELIVERY_HEADER, EVENT_HEADER};
+
+use super::models::Notification;
+use crate::modules::users::models::User;
+use crate::utils::mail::{Email, Mailer};
+
+/// Somewhere notifications are sent besides the inbox
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Name the channel is logged under, e.g. `email`
+    fn name(&self) -> &'static str;
+
+    /// Send `notification` to `recipient`
+    async fn deliver(&self, notification: &Notification, recipient: &User) -> anyhow::Result<()>;
+}
+
+/// The channels notifications are delivered through
+#[derive(Clone, Default)]
+pub struct NotificationChannels {
+    pub channels: Vec<Arc<dyn NotificationChannel>>,
+}
+
+/// Emails each notification to the recipient's address
+pub struct EmailChannel {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl EmailChannel {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn deliver(&self, notification: &Notification, recipient: &User) -> anyhow::Result<()> {
+        self.mailer
+            .send(Email {
+                to: recipient.email.clone(),
+                subject: notification.title.clone(),
+                body: format!("{}\n", notification.body),
+            })
+            .await
+    }
+}
+
+/// POSTs each notification as JSON to one URL, signed like outgoing webhooks
+///
+/// `x-atlas-signature` is `sha256=` and the hex HMAC-SHA256 of `{x-atlas-timestamp}.{body}`
+/// under `secret`; `x-atlas-event` is the notification's kind. Failed posts are not retried.
+pub struct WebhookChannel {
+    url: String,
+    secret: String,
+    transport: Arc<dyn WebhookTransport>,
+    clock: Arc<dyn Clock>,
+    timeout: Duration,
+}
+
+impl WebhookChannel {
+    pub fn new(
+        url: impl Into<String>,
+        secret: impl Into<String>,
+        transport: Arc<dyn WebhookTransport>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            secret: secret.into(),
+            transport,
+            clock,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Give up on a post after `timeout`; 5 seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(&self, notification: &Notification, _recipient: &User) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(notification).context("failed to serialize notification")?;
+        let timestamp = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let headers = [
+            ("content-type", "application/json".to_string()),
+            (DELIVERY_HEADER, notification.id.clone()),
+            (EVENT_HEADER, notification.kind.clone()),
+            (
+                atlas_authz::signatures::TIMESTAMP_HEADER,
+                timestamp.to_string(),
+            ),
+            (
+                atlas_authz::signatures::SIGNATURE_HEADER,
+                atlas_authz::signatures::sign(&self.secret, timestamp, &body),
+            ),
+        ];
+        let status = tokio::time::timeout(
+            self.timeout,
+            self.transport.post(&self.url, &headers, &body),
+        )
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("timed out after {:?}", self.timeout)))?;
+        if !(200..300).contains(&status) {
+            bail!("endpoint answered HTTP {}", status);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+    use time::macros::datetime;
+
+    use super::*;
+    use atlas_kernel::clock::ManualClock;
+
+    #[derive(Clone)]
+    struct Post {
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    #[derive(Default)]
+    struct RecordingTransport {
+        posts: Mutex<Vec<Post>>,
+    }
+
+    #[async_trait]
+    impl WebhookTransport for RecordingTransport {
+        async fn post(
+            &self,
+            url: &str,
+            headers: &[(&str, String)],
+            body: &[u8],
+        ) -> anyhow::Result<u16> {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect();
+            self.posts.lock().unwrap().push(Post {
+                url: url.to_string(),
+                headers,
+                body: body.to_vec(),
+            });
+            Ok(if url.ends_with("/gone") { 410 } else { 204 })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_channel_signs_the_notification() {
+        let transport = Arc::new(RecordingTransport::default());
+        let clock = Arc::new(ManualClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let notification = Notification {
+            id: "ntf_1".to_string(),
+            user_id: "user_1".to_string(),
+            kind: "orgs.member_added".to_string(),
+            title: "You joined acme".to_string(),
+            body: "You are now a member of acme.".to_string(),
+            data: json!({ "org": "acme" }),
+            read_at: None,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+        };
+        let recipient = User {
+            id: "user_1".to_string(),
+            email: "ann@example.com".to_string(),
+            name: "Ann".to_string(),
+            bio: None,
+            avatar_url: None,
+            password_hash: None,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        };
+
+        let channel = WebhookChannel::new(
+            "https://hooks.example.com/inbox",
+            "whsec_test",
+            transport.clone(),
+            clock.clone(),
+        );
+        channel.deliver(&notification, &recipient).await.unwrap();
+
+        let Post { url, headers, body } = transport.posts.lock().unwrap()[0].clone();
+        assert_eq!(url, "https://hooks.example.com/inbox");
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert_eq!(header(EVENT_HEADER), "orgs.member_added");
+        assert_eq!(
+            header(atlas_authz::signatures::SIGNATURE_HEADER),
+            atlas_authz::signatures::sign("whsec_test", 1_700_000_000, &body)
+        );
+        let sent: Notification = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sent, notification);
+
+        let gone = WebhookChannel::new(
+            "https://hooks.example.com/gone",
+            "whsec_test",
+            transport,
+            clock,
+        );
+        let error = gone.deliver(&notification, &recipient).await.unwrap_err();
+        assert!(error.to_string().contains("HTTP 410"));
+    }
+}
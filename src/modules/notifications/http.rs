@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use atlas_authz::Subject;
+use atlas_http::error::AppError;
+use atlas_http::pagination::{Page, Pagination};
+
+use super::models::{MarkedRead, Notification, UnreadCount};
+use super::service::{NotificationError, Notifications};
+use super::store::NotificationQuery;
+
+impl From<NotificationError> for AppError {
+    fn from(error: NotificationError) -> Self {
+        match error {
+            NotificationError::UnknownUser(_) => AppError::not_found(error.to_string()),
+            NotificationError::Store(error) => AppError::Internal(error),
+        }
+    }
+}
+
+/// Query parameters of `GET /`, besides pagination
+#[derive(Debug, Deserialize)]
+struct ListNotifications {
+    #[serde(default)]
+    unread: bool,
+}
+
+async fn list_notifications(
+    State(notifications): State<Arc<Notifications>>,
+    subject: Subject,
+    Query(params): Query<ListNotifications>,
+    pagination: Pagination,
+) -> Result<Json<Page<Notification>>, AppError> {
+    let query = NotificationQuery {
+        user_id: subject.0,
+        unread: params.unread,
+        pagination,
+    };
+    Ok(Json(notifications.list(&query).await?))
+}
+
+async fn unread_count(
+    State(notifications): State<Arc<Notifications>>,
+    subject: Subject,
+) -> Result<Json<UnreadCount>, AppError> {
+    let unread = notifications.unread_count(&subject.0).await?;
+    Ok(Json(UnreadCount {
+        user_id: subject.0,
+        unread,
+    }))
+}
+
+async fn get_notification(
+    State(notifications): State<Arc<Notifications>>,
+    subject: Subject,
+    Path(id): Path<String>,
+) -> Result<Json<Notification>, AppError> {
+    match notifications.get(&id, &subject.0).await? {
+        Some(notification) => Ok(Json(notification)),
+        None => Err(AppError::not_found(format!("no notification '{}'", id))),
+    }
+}
+
+async fn mark_read(
+    State(notifications): State<Arc<Notifications>>,
+    subject: Subject,
+    Path(id): Path<String>,
+) -> Result<Json<Notification>, AppError> {
+    match notifications.mark_read(&id, &subject.0).await? {
+        Some(notification) => Ok(Json(notification)),
+        None => Err(AppError::not_found(format!("no notification '{}'", id))),
+    }
+}
+
+async fn mark_all_read(
+    State(notifications): State<Arc<Notifications>>,
+    subject: Subject,
+) -> Result<Json<MarkedRead>, AppError> {
+    let marked = notifications.mark_all_read(&subject.0).await?;
+    Ok(Json(MarkedRead { marked }))
+}
+
+/// Health check endpoint
+async fn health_check() -> &'static str {
+    "notifications module is healthy"
+}
+
+/// Endpoints over the caller's inbox, plus the module health check
+pub fn routes(notifications: Arc<Notifications>) -> Router {
+    Router::new()
+        .route("/", get(list_notifications))
+        .route("/unread_count", get(unread_count))
+        .route("/read", post(mark_all_read))
+        .route("/{id}", get(get_notification))
+        .route("/{id}/read", post(mark_read))
+        .with_state(notifications)
+        .route("/health", get(health_check))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::notifications::models::NewNotification;
+    use crate::modules::notifications::service::tests::setup;
+    use atlas_authz::testing::as_subject;
+    use atlas_http::testing::{request, send};
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_inbox_endpoints() {
+        let setup = setup().await;
+        let mut ids = Vec::new();
+        for title in ["First", "Second"] {
+            let notification = setup
+                .notifications
+                .notify(NewNotification {
+                    user_id: setup.ann.clone(),
+                    kind: "digest".to_string(),
+                    title: title.to_string(),
+                    body: "Nothing new.".to_string(),
+                    data: Value::Null,
+                })
+                .await
+                .unwrap();
+            ids.push(notification.id);
+            setup.clock.advance(std::time::Duration::from_secs(1));
+        }
+        let router =
+            Router::new().nest("/api/notifications", routes(Arc::new(setup.notifications)));
+        let call = |caller: &str, method: &str, uri: &str| {
+            let router = router.clone();
+            let request = as_subject(request(method, uri, Value::Null), caller);
+            async move { send(&router, request).await }
+        };
+        let ann = setup.ann.as_str();
+
+        let (status, page) = call(ann, "GET", "/api/notifications").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["items"][0]["title"], "Second");
+        assert!(page["items"][0].get("read_at").is_none());
+        let (status, _) = send(&router, request("GET", "/api/notifications", Value::Null)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (_, page) = call("user_bob", "GET", "/api/notifications").await;
+        assert_eq!(page["total"], 0);
+
+        // Someone else's notifications do not exist for the caller
+        let first = format!("/api/notifications/{}", ids[0]);
+        let (status, _) = call("user_bob", "GET", &first).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call("user_bob", "POST", &format!("{}/read", first)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, notification) = call(ann, "GET", &first).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(notification.get("read_at").is_none());
+
+        let (status, read) = call(ann, "POST", &format!("{}/read", first)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(read["read_at"].is_string());
+        let (status, _) = call(ann, "POST", "/api/notifications/ntf_9/read").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, count) = call(ann, "GET", "/api/notifications/unread_count").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(count["unread"], 1);
+        let (_, page) = call(ann, "GET", "/api/notifications?unread=true").await;
+        assert_eq!(page["items"][0]["id"], ids[1].as_str());
+
+        let (status, marked) = call(ann, "POST", "/api/notifications/read").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(marked["marked"], 1);
+        let (_, count) = call(ann, "GET", "/api/notifications/unread_count").await;
+        assert_eq!(count["unread"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_served_inbox_is_the_authenticated_callers() {
+        use crate::modules::users::{models::CreateUser, service::Users};
+
+        let app = atlas_authz::testing::TestApp::new(
+            vec![
+                Arc::new(crate::modules::users::UsersModule::new()),
+                Arc::new(crate::modules::notifications::NotificationsModule::new()),
+            ],
+            "",
+        )
+        .await;
+        let ann = app
+            .registry
+            .resources()
+            .require::<Users>()
+            .unwrap()
+            .create(CreateUser {
+                email: "ann@example.com".to_string(),
+                name: "Ann Lee".to_string(),
+                bio: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        app.registry
+            .resources()
+            .require::<Notifications>()
+            .unwrap()
+            .notify(NewNotification {
+                user_id: ann.id.clone(),
+                kind: "digest".to_string(),
+                title: "First".to_string(),
+                body: "Nothing new.".to_string(),
+                data: Value::Null,
+            })
+            .await
+            .unwrap();
+
+        let (status, _) = send(
+            &app.router,
+            request("GET", "/api/notifications", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let inbox = app
+            .with_api_key(request("GET", "/api/notifications", Value::Null), &ann.id)
+            .await;
+        let (status, page) = send(&app.router, inbox).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 1);
+
+        let mark_all = app
+            .with_session(
+                request("POST", "/api/notifications/read", Value::Null),
+                &ann.id,
+            )
+            .await;
+        let (status, marked) = send(&app.router, mark_all).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(marked["marked"], 1);
+    }
+}
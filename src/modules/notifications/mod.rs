@@ -0,0 +1,364 @@
+pub mod channels;
+pub mod http;
+pub mod models;
+pub mod rules;
+pub mod service;
+pub mod store;
+
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use atlas_http::pagination;
+use atlas_kernel::{
+    EventHandler, InitCtx, Migration, Module, RouteSecurity, SchemaExample, SecurityScheme,
+};
+use axum::Router;
+use serde_json::json;
+
+use crate::modules::users::service::Users;
+use crate::utils::mail::Mailer;
+use channels::{EmailChannel, NotificationChannel, NotificationChannels};
+use service::Notifications;
+use store::{MemoryNotificationStore, NotificationStore, SurrealNotificationStore};
+
+/// In-app notifications, backed by the `notification` table
+///
+/// Membership events from the orgs module land in the member's inbox through
+/// `Module::event_handlers`, and every new notification is also sent through the
+/// registered `NotificationChannels`, by default email when an `Arc<dyn Mailer>` is
+/// registered. The endpoints serve the authenticated caller's own inbox. Publishes
+/// `Notifications` as a shared resource so other modules can notify users directly.
+#[derive(Default)]
+pub struct NotificationsModule {
+    notifications: OnceLock<Arc<Notifications>>,
+}
+
+impl NotificationsModule {
+    pub const fn new() -> Self {
+        Self {
+            notifications: OnceLock::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Module for NotificationsModule {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("In-app notifications with pluggable delivery channels")
+    }
+
+    /// Notifications go to users of the users module
+    fn depends_on(&self) -> &[&'static str] {
+        &["users"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn NotificationStore>(
+            &ctx.resources,
+            "notification",
+            |db| Arc::new(SurrealNotificationStore::new(db)),
+            || Arc::new(MemoryNotificationStore::new()),
+        );
+        let channels: Vec<Arc<dyn NotificationChannel>> =
+            match ctx.resources.get::<NotificationChannels>() {
+                Some(channels) => channels.channels.clone(),
+                None => match ctx.resources.get::<Arc<dyn Mailer>>() {
+                    Some(mailer) => vec![Arc::new(EmailChannel::new(mailer.as_ref().clone()))],
+                    None => {
+                        tracing::debug!(
+                            "no notification channels registered; notifications are in-app only"
+                        );
+                        Vec::new()
+                    }
+                },
+            };
+        let channel_names: Vec<&str> = channels.iter().map(|channel| channel.name()).collect();
+        let notifications = Arc::new(Notifications::new(
+            store,
+            ctx.resources.require::<Users>()?,
+            channels,
+            ctx.clock.clone(),
+        ));
+        ctx.resources.insert_arc(notifications.clone());
+        self.notifications
+            .set(notifications)
+            .map_err(|_| anyhow::anyhow!("notifications module initialized twice"))?;
+
+        tracing::info!(
+            module = self.name(),
+            environment = ?ctx.settings.environment,
+            channels = ?channel_names,
+            "notifications module initialized"
+        );
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.notifications.get() {
+            Some(notifications) => http::routes(notifications.clone()),
+            None => Router::new(),
+        }
+    }
+
+    /// One handler per event in `rules::SUBSCRIBED`
+    fn event_handlers(&self) -> Vec<EventHandler> {
+        let Some(notifications) = self.notifications.get() else {
+            return Vec::new();
+        };
+        rules::SUBSCRIBED
+            .into_iter()
+            .map(|event| {
+                let notifications = notifications.clone();
+                EventHandler::new(event, move |payload| {
+                    let notifications = notifications.clone();
+                    async move { notifications.on_event(event, payload).await }
+                })
+            })
+            .collect()
+    }
+
+    fn security(&self) -> Vec<RouteSecurity> {
+        let caller = |method, path| RouteSecurity {
+            method,
+            path,
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission: None,
+        };
+        vec![
+            caller("get", "/"),
+            caller("get", "/unread_count"),
+            caller("post", "/read"),
+            caller("get", "/{id}"),
+            caller("post", "/{id}/read"),
+        ]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let error = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorResponse" }
+                    }
+                }
+            })
+        };
+        let schema = |description: &str, schema: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let id = json!({
+            "name": "id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" }
+        });
+        let mut list_parameters = vec![json!({
+                "name": "unread",
+                "in": "query",
+                "description": "Only notifications not read yet",
+            "schema": { "type": "boolean", "default": false }
+        })];
+        list_parameters.extend(pagination::openapi::parameters());
+        let text = |description: &str| json!({ "type": "string", "description": description });
+        let timestamp = |description: &str| json!({ "type": "string", "format": "date-time", "description": description });
+        Some(json!({
+            "tags": [
+                {
+                    "name": "Notifications",
+                    "description": "Notifications — each user's inbox of things that happened to them",
+                    "x-order": 5
+                }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List the caller's notifications",
+                        "tags": ["Notifications"],
+                        "parameters": list_parameters,
+                        "responses": {
+                            "200": {
+                                "description": "One page of the caller's notifications, newest first",
+                                "content": {
+                                    "application/json": {
+                                        "schema": pagination::openapi::page_schema(
+                                            json!({ "$ref": "#/components/schemas/Notification" })
+                                        )
+                                    }
+                                }
+                            },
+                            "400": error("unread is not a boolean"),
+                            "422": error("Invalid page or per_page"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/unread_count": {
+                    "get": {
+                        "summary": "Count the caller's unread notifications",
+                        "tags": ["Notifications"],
+                        "responses": {
+                            "200": schema("The number of unread notifications", "UnreadCount"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/read": {
+                    "post": {
+                        "summary": "Mark all of the caller's notifications read",
+                        "tags": ["Notifications"],
+                        "responses": {
+                            "200": schema("How many notifications were unread", "MarkedRead"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{id}": {
+                    "get": {
+                        "summary": "Get a notification",
+                        "tags": ["Notifications"],
+                        "parameters": [id.clone()],
+                        "responses": {
+                            "200": schema("The notification", "Notification"),
+                            "404": error("The caller has no notification with that id"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{id}/read": {
+                    "post": {
+                        "summary": "Mark a notification read",
+                        "description": "Marking a notification that is already read keeps the time it was first read.",
+                        "tags": ["Notifications"],
+                        "parameters": [id],
+                        "responses": {
+                            "200": schema("The read notification", "Notification"),
+                            "404": error("The caller has no notification with that id"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/health": {
+                    "get": {
+                        "summary": "Notifications health check",
+                        "tags": ["Notifications"],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": {
+                                    "text/plain": {
+                                        "schema": { "type": "string" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Notification": {
+                        "type": "object",
+                        "properties": {
+                            "id": text("Unique identifier for the notification"),
+                            "user_id": text("Recipient"),
+                            "kind": text("What happened, usually the name of the event behind it"),
+                            "title": text("One-line summary"),
+                            "body": text("Full text"),
+                            "data": {
+                                "description": "Details for clients to link to, such as the event's payload"
+                            },
+                            "read_at": timestamp("When the recipient marked it read; absent while unread"),
+                            "created_at": timestamp("When the notification was created")
+                        },
+                        "required": ["id", "user_id", "kind", "title", "body", "data", "created_at"]
+                    },
+                    "UnreadCount": {
+                        "type": "object",
+                        "properties": {
+                            "user_id": text("Recipient"),
+                            "unread": { "type": "integer", "minimum": 0 }
+                        },
+                        "required": ["user_id", "unread"]
+                    },
+                    "MarkedRead": {
+                        "type": "object",
+                        "properties": {
+                            "marked": {
+                                "type": "integer",
+                                "minimum": 0,
+                                "description": "Notifications that were unread until now"
+                            }
+                        },
+                        "required": ["marked"]
+                    }
+                }
+            }
+        }))
+    }
+
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![SchemaExample::new(
+            "Notification",
+            json!({
+                "id": "ntf_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                "user_id": "user_01HV6Y5J8Q4ZK3X2T9R7M1N0PB",
+                "kind": "orgs.member_added",
+                "title": "You joined acme",
+                "body": "You are now a member of acme with the member role.",
+                "data": { "org": "acme", "user_id": "user_01HV6Y5J8Q4ZK3X2T9R7M1N0PB", "role": "member" },
+                "created_at": "2024-01-01T00:00:00Z"
+            }),
+        )]
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_init",
+            up: r#"
+                DEFINE TABLE notification SCHEMAFULL;
+                DEFINE FIELD user_id    ON notification TYPE string;
+                DEFINE FIELD kind       ON notification TYPE string;
+                DEFINE FIELD title      ON notification TYPE string;
+                DEFINE FIELD body       ON notification TYPE string;
+                DEFINE FIELD data       ON notification FLEXIBLE TYPE any;
+                DEFINE FIELD read_at    ON notification TYPE option<datetime>;
+                DEFINE FIELD created_at ON notification TYPE datetime VALUE $before OR $value;
+                DEFINE INDEX notification_inbox ON notification FIELDS user_id, read_at;
+                "#,
+            down: Some("REMOVE TABLE notification;"),
+        }]
+    }
+
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "notifications module started");
+        Ok(())
+    }
+
+    async fn stop(&self) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "notifications module stopped");
+        Ok(())
+    }
+}
+
+/// Create a new instance of the notifications module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(NotificationsModule::new())
+}
+
+atlas_kernel::register_module!(create_module);
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+/// A message in a user's in-app inbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Unique identifier, e.g. `ntf_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Recipient
+    pub user_id: String,
+    /// What happened, usually the name of the event behind it, e.g. `orgs.member_added`
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    /// Details for clients to link to, such as the event's payload
+    #[serde(default)]
+    pub data: Value,
+    /// When the recipient marked it read; unread while `None`
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub read_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+/// A notification about to be stored and delivered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewNotification {
+    pub user_id: String,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Response of `POST /read`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkedRead {
+    /// Notifications that were unread until now
+    pub marked: u64,
+}
+
+/// Response of `GET /unread_count`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnreadCount {
+    pub user_id: String,
+    pub unread: u64,
+}
@@ -0,0 +1,64 @@
+//! Which named events notify whom, and what the notifications say
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use atlas_events::VersionedEvent;
+
+use super::models::NewNotification;
+use crate::modules::orgs::events::{MemberAdded, MemberRemoved, RoleChanged};
+
+/// Events the module subscribes to through `Module::event_handlers`
+pub const SUBSCRIBED: [&str; 3] = [MemberAdded::NAME, RoleChanged::NAME, MemberRemoved::NAME];
+
+/// The notifications the event `name` with `payload` calls for
+pub fn notifications_for(name: &str, payload: &Value) -> anyhow::Result<Vec<NewNotification>> {
+    let notification = match name {
+        MemberAdded::NAME => {
+            let event: MemberAdded = parse(name, payload)?;
+            NewNotification {
+                user_id: event.user_id,
+                kind: name.to_string(),
+                title: format!("You joined {}", event.org),
+                body: format!(
+                    "You are now a member of {} with the {} role.",
+                    event.org,
+                    event.role.as_str()
+                ),
+                data: payload.clone(),
+            }
+        }
+        RoleChanged::NAME => {
+            let event: RoleChanged = parse(name, payload)?;
+            NewNotification {
+                user_id: event.user_id,
+                kind: name.to_string(),
+                title: format!("Your role in {} changed", event.org),
+                body: format!(
+                    "Your role in {} changed from {} to {}.",
+                    event.org,
+                    event.previous.as_str(),
+                    event.role.as_str()
+                ),
+                data: payload.clone(),
+            }
+        }
+        MemberRemoved::NAME => {
+            let event: MemberRemoved = parse(name, payload)?;
+            NewNotification {
+                user_id: event.user_id,
+                kind: name.to_string(),
+                title: format!("You left {}", event.org),
+                body: format!("You are no longer a member of {}.", event.org),
+                data: payload.clone(),
+            }
+        }
+        _ => return Ok(Vec::new()),
+    };
+    Ok(vec![notification])
+}
+
+fn parse<T: DeserializeOwned>(name: &str, payload: &Value) -> anyhow::Result<T> {
+    serde_json::from_value(payload.clone())
+        .map_err(|error| anyhow::anyhow!("unexpected payload of event '{}': {}", name, error))
+}
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use atlas_http::pagination::Page;
+use atlas_kernel::{id, Clock};
+
+use super::channels::NotificationChannel;
+use super::models::{NewNotification, Notification};
+use super::rules;
+use super::store::{NotificationQuery, NotificationStore};
+use crate::modules::users::service::Users;
+
+/// Why a notification could not be stored
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationError {
+    #[error("no user '{0}'")]
+    UnknownUser(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Keeps each user's in-app notifications and delivers new ones through the channels
+///
+/// A notification is stored before any channel sees it; a failing channel is logged and
+/// does not affect the others or the inbox.
+pub struct Notifications {
+    store: Arc<dyn NotificationStore>,
+    users: Arc<Users>,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Notifications {
+    pub fn new(
+        store: Arc<dyn NotificationStore>,
+        users: Arc<Users>,
+        channels: Vec<Arc<dyn NotificationChannel>>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            store,
+            users,
+            channels,
+            clock,
+        }
+    }
+
+    /// Store `new` in the recipient's inbox and deliver it through every channel
+    pub async fn notify(&self, new: NewNotification) -> Result<Notification, NotificationError> {
+        let Some(recipient) = self.users.get(&new.user_id).await? else {
+            return Err(NotificationError::UnknownUser(new.user_id));
+        };
+        let notification = Notification {
+            id: id::prefixed_with("ntf", id::ulid_at(self.clock.now()))
+                .map_err(anyhow::Error::from)?,
+            user_id: new.user_id,
+            kind: new.kind,
+            title: new.title,
+            body: new.body,
+            data: new.data,
+            read_at: None,
+            created_at: self.now(),
+        };
+        self.store.insert(notification.clone()).await?;
+        tracing::info!(
+            notification_id = %notification.id,
+            user_id = %notification.user_id,
+            kind = %notification.kind,
+            "stored notification"
+        );
+
+        for channel in &self.channels {
+            if let Err(error) = channel.deliver(&notification, &recipient).await {
+                tracing::warn!(
+                    notification_id = %notification.id,
+                    channel = channel.name(),
+                    error = format!("{:#}", error),
+                    "failed to deliver notification"
+                );
+            }
+        }
+        Ok(notification)
+    }
+
+    /// Store and deliver the notifications the named event calls for
+    pub async fn on_event(&self, name: &str, payload: Value) -> anyhow::Result<()> {
+        for new in rules::notifications_for(name, &payload)? {
+            match self.notify(new).await {
+                Ok(_) => {}
+                // The user was deleted since; there is nobody left to tell
+                Err(NotificationError::UnknownUser(user_id)) => {
+                    tracing::debug!(event = name, user_id = %user_id, "dropped notification");
+                }
+                Err(NotificationError::Store(error)) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// The notification, unless there is none or it is someone else's
+    pub async fn get(&self, id: &str, user_id: &str) -> anyhow::Result<Option<Notification>> {
+        Ok(self
+            .store
+            .get(id)
+            .await?
+            .filter(|notification| notification.user_id == user_id))
+    }
+
+    pub async fn list(&self, query: &NotificationQuery) -> anyhow::Result<Page<Notification>> {
+        self.store.list(query).await
+    }
+
+    pub async fn unread_count(&self, user_id: &str) -> anyhow::Result<u64> {
+        self.store.unread_count(user_id).await
+    }
+
+    /// Mark one of `user_id`'s notifications read, returning `None` if they have no such
+    /// notification
+    ///
+    /// Marking it again keeps the time it was first read.
+    pub async fn mark_read(&self, id: &str, user_id: &str) -> anyhow::Result<Option<Notification>> {
+        if self.get(id, user_id).await?.is_none() {
+            return Ok(None);
+        }
+        self.store.mark_read(id, self.now()).await
+    }
+
+    /// Mark all of a user's notifications read, returning how many were unread
+    pub async fn mark_all_read(&self, user_id: &str) -> anyhow::Result<u64> {
+        let marked = self.store.mark_all_read(user_id, self.now()).await?;
+        tracing::debug!(user_id = %user_id, marked, "marked notifications read");
+        Ok(marked)
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    use super::*;
+    use crate::modules::notifications::store::MemoryNotificationStore;
+    use crate::modules::users::models::{CreateUser, User};
+    use crate::modules::users::store::MemoryUserStore;
+    use atlas_kernel::clock::ManualClock;
+
+    /// Channel recording the kind and recipient of every notification it was handed
+    #[derive(Default)]
+    pub(crate) struct RecordingChannel {
+        pub(crate) delivered: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn deliver(
+            &self,
+            notification: &Notification,
+            recipient: &User,
+        ) -> anyhow::Result<()> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .push(format!("{} to {}", notification.kind, recipient.email));
+            Ok(())
+        }
+    }
+
+    struct FailingChannel;
+
+    #[async_trait]
+    impl NotificationChannel for FailingChannel {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn deliver(&self, _: &Notification, _: &User) -> anyhow::Result<()> {
+            anyhow::bail!("unreachable")
+        }
+    }
+
+    pub(crate) struct Setup {
+        pub(crate) notifications: Notifications,
+        pub(crate) channel: Arc<RecordingChannel>,
+        pub(crate) clock: Arc<ManualClock>,
+        /// ann@example.com
+        pub(crate) ann: String,
+    }
+
+    pub(crate) async fn setup() -> Setup {
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::now()));
+        let users = Arc::new(Users::new(Arc::new(MemoryUserStore::new()), clock.clone()));
+        let ann = users
+            .create(CreateUser {
+                email: "ann@example.com".to_string(),
+                name: "Ann".to_string(),
+                bio: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        let channel = Arc::new(RecordingChannel::default());
+        let notifications = Notifications::new(
+            Arc::new(MemoryNotificationStore::new()),
+            users,
+            vec![Arc::new(FailingChannel), channel.clone()],
+            clock.clone(),
+        );
+        Setup {
+            notifications,
+            channel,
+            clock,
+            ann: ann.id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_membership_events_notify_the_member() {
+        let setup = setup().await;
+        let notifications = &setup.notifications;
+
+        notifications
+            .on_event(
+                "orgs.role_changed",
+                json!({ "org": "acme", "user_id": setup.ann, "role": "admin", "previous": "member" }),
+            )
+            .await
+            .unwrap();
+        notifications
+            .on_event("books.created", json!({ "id": 7 }))
+            .await
+            .unwrap();
+        notifications
+            .on_event(
+                "orgs.member_removed",
+                json!({ "org": "acme", "user_id": "user_deleted" }),
+            )
+            .await
+            .unwrap();
+        assert!(notifications
+            .on_event("orgs.member_added", json!({ "org": "acme" }))
+            .await
+            .is_err());
+
+        let page = notifications
+            .list(&NotificationQuery {
+                user_id: setup.ann.clone(),
+                ..NotificationQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        let notification = &page.items[0];
+        assert!(notification.id.starts_with("ntf_"));
+        assert_eq!(notification.title, "Your role in acme changed");
+        assert_eq!(
+            notification.body,
+            "Your role in acme changed from member to admin."
+        );
+        assert_eq!(notification.data["org"], "acme");
+        // The failing channel does not keep the others from delivering
+        assert_eq!(
+            *setup.channel.delivered.lock().unwrap(),
+            vec!["orgs.role_changed to ann@example.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_recipients_are_refused() {
+        let setup = setup().await;
+        let result = setup
+            .notifications
+            .notify(NewNotification {
+                user_id: "user_missing".to_string(),
+                kind: "digest".to_string(),
+                title: "Weekly digest".to_string(),
+                body: "Nothing new.".to_string(),
+                data: Value::Null,
+            })
+            .await;
+        assert!(matches!(result, Err(NotificationError::UnknownUser(_))));
+        assert_eq!(
+            setup
+                .notifications
+                .unread_count("user_missing")
+                .await
+                .unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+
+use atlas_db::QueryExecutor;
+use atlas_http::pagination::{Page, Pagination};
+
+use super::models::Notification;
+
+/// A page of one user's notifications
+#[derive(Debug, Clone, Default)]
+pub struct NotificationQuery {
+    pub user_id: String,
+    /// Only notifications not read yet
+    pub unread: bool,
+    pub pagination: Pagination,
+}
+
+impl NotificationQuery {
+    fn matches(&self, notification: &Notification) -> bool {
+        notification.user_id == self.user_id && !(self.unread && notification.is_read())
+    }
+}
+
+/// Persistence for notifications
+///
+/// The module stores them in SurrealDB through the application's `QueryExecutor`;
+/// applications may publish their own `Arc<dyn NotificationStore>` resource instead.
+/// Without either, they live in memory.
+#[async_trait]
+pub trait NotificationStore: Send + Sync {
+    async fn insert(&self, notification: Notification) -> anyhow::Result<()>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Notification>>;
+
+    /// The page of notifications matching `query`, newest first
+    async fn list(&self, query: &NotificationQuery) -> anyhow::Result<Page<Notification>>;
+
+    async fn unread_count(&self, user_id: &str) -> anyhow::Result<u64>;
+
+    /// Mark the notification read at `at` unless it already is, returning it as stored
+    /// afterwards; `None` if there is none
+    async fn mark_read(&self, id: &str, at: OffsetDateTime)
+        -> anyhow::Result<Option<Notification>>;
+
+    /// Mark every unread notification of the user read at `at`, returning how many were
+    async fn mark_all_read(&self, user_id: &str, at: OffsetDateTime) -> anyhow::Result<u64>;
+}
+
+/// Process-local store; notifications are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryNotificationStore {
+    notifications: RwLock<Vec<Notification>>,
+}
+
+impl MemoryNotificationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NotificationStore for MemoryNotificationStore {
+    async fn insert(&self, notification: Notification) -> anyhow::Result<()> {
+        self.notifications
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(notification);
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Notification>> {
+        Ok(self
+            .notifications
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|notification| notification.id == id)
+            .cloned())
+    }
+
+    async fn list(&self, query: &NotificationQuery) -> anyhow::Result<Page<Notification>> {
+        let mut notifications: Vec<Notification> = self
+            .notifications
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|notification| query.matches(notification))
+            .cloned()
+            .collect();
+        notifications.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        let total = notifications.len() as u64;
+        Ok(Page::new(
+            query.pagination.slice(notifications),
+            total,
+            query.pagination,
+        ))
+    }
+
+    async fn unread_count(&self, user_id: &str) -> anyhow::Result<u64> {
+        Ok(self
+            .notifications
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|notification| notification.user_id == user_id && !notification.is_read())
+            .count() as u64)
+    }
+
+    async fn mark_read(
+        &self,
+        id: &str,
+        at: OffsetDateTime,
+    ) -> anyhow::Result<Option<Notification>> {
+        let mut notifications = self
+            .notifications
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(notifications
+            .iter_mut()
+            .find(|notification| notification.id == id)
+            .map(|notification| {
+                notification.read_at.get_or_insert(at);
+                notification.clone()
+            }))
+    }
+
+    async fn mark_all_read(&self, user_id: &str, at: OffsetDateTime) -> anyhow::Result<u64> {
+        let mut notifications = self
+            .notifications
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut marked = 0;
+        for notification in notifications
+            .iter_mut()
+            .filter(|notification| notification.user_id == user_id && !notification.is_read())
+        {
+            notification.read_at = Some(at);
+            marked += 1;
+        }
+        Ok(marked)
+    }
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_NOTIFICATION: &str = "SELECT *, record::id(id) AS id FROM notification";
+
+/// Notifications in the `notification` table defined by the module's migrations
+pub struct SurrealNotificationStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealNotificationStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NotificationStore for SurrealNotificationStore {
+    async fn insert(&self, notification: Notification) -> anyhow::Result<()> {
+        let vars = match serde_json::to_value(&notification)
+            .context("failed to serialize notification")?
+        {
+            Value::Object(vars) => vars,
+            _ => unreachable!("notifications serialize as objects"),
+        };
+        // New notifications are unread, so `read_at` stays `NONE`
+        self.db
+            .query_with(
+                "CREATE type::thing('notification', $id) SET user_id = $user_id, kind = $kind, \
+                 title = $title, body = $body, data = $data, \
+                 created_at = <datetime> $created_at RETURN NONE;",
+                &vars,
+            )
+            .await
+            .context("failed to store notification")?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Notification>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let results = self
+            .db
+            .query_with(
+                &format!(
+                    "{} WHERE id = type::thing('notification', $id);",
+                    SELECT_NOTIFICATION
+                ),
+                &vars,
+            )
+            .await
+            .context("failed to read notification")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn list(&self, query: &NotificationQuery) -> anyhow::Result<Page<Notification>> {
+        let mut vars = Map::new();
+        vars.insert("user_id".to_string(), json!(query.user_id));
+        vars.insert("limit".to_string(), json!(query.pagination.limit()));
+        vars.insert("start".to_string(), json!(query.pagination.offset()));
+        let filter = if query.unread {
+            "WHERE user_id = $user_id AND read_at IS NONE"
+        } else {
+            "WHERE user_id = $user_id"
+        };
+        let surql = format!(
+            "{select} {filter} ORDER BY created_at DESC LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM notification {filter} GROUP ALL;",
+            select = SELECT_NOTIFICATION,
+            filter = filter,
+        );
+        let mut results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to list notifications")?
+            .into_iter();
+        let notifications = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(notifications, total, query.pagination))
+    }
+
+    async fn unread_count(&self, user_id: &str) -> anyhow::Result<u64> {
+        let mut vars = Map::new();
+        vars.insert("user_id".to_string(), json!(user_id));
+        let results = self
+            .db
+            .query_with(
+                "SELECT count() AS total FROM notification \
+                 WHERE user_id = $user_id AND read_at IS NONE GROUP ALL;",
+                &vars,
+            )
+            .await
+            .context("failed to count unread notifications")?;
+        Ok(atlas_db::records::<Total>(results.into_iter().next())?
+            .first()
+            .map_or(0, |count| count.total))
+    }
+
+    async fn mark_read(
+        &self,
+        id: &str,
+        at: OffsetDateTime,
+    ) -> anyhow::Result<Option<Notification>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        vars.insert("at".to_string(), json!(rfc3339(at)));
+        // A `WHERE` keeps `UPDATE` from creating a missing record
+        let surql = format!(
+            "UPDATE notification SET read_at = <datetime> $at \
+             WHERE id = type::thing('notification', $id) AND read_at IS NONE RETURN NONE;\n\
+             {} WHERE id = type::thing('notification', $id);",
+            SELECT_NOTIFICATION
+        );
+        let results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to mark notification read")?;
+        Ok(atlas_db::records(results.into_iter().nth(1))?
+            .into_iter()
+            .next())
+    }
+
+    async fn mark_all_read(&self, user_id: &str, at: OffsetDateTime) -> anyhow::Result<u64> {
+        let mut vars = Map::new();
+        vars.insert("user_id".to_string(), json!(user_id));
+        vars.insert("at".to_string(), json!(rfc3339(at)));
+        let results = self
+            .db
+            .query_with(
+                "UPDATE notification SET read_at = <datetime> $at \
+                 WHERE user_id = $user_id AND read_at IS NONE RETURN VALUE record::id(id);",
+                &vars,
+            )
+            .await
+            .context("failed to mark notifications read")?;
+        Ok(match results.first() {
+            Some(Value::Array(records)) => records.len() as u64,
+            _ => 0,
+        })
+    }
+}
+
+fn rfc3339(at: OffsetDateTime) -> String {
+    at.format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default()
+}
+
+/// Row of a `count()` query
+#[derive(serde::Deserialize)]
+struct Total {
+    total: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn notification(id: &str, user_id: &str) -> Notification {
+        Notification {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            kind: "orgs.member_added".to_string(),
+            title: "You joined acme".to_string(),
+            body: "You are now a member of acme.".to_string(),
+            data: json!({ "org": "acme" }),
+            read_at: None,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    struct Recording {
+        queries: Mutex<Vec<(String, Map<String, Value>)>>,
+    }
+
+    #[async_trait]
+    impl QueryExecutor for Recording {
+        async fn query_with(
+            &self,
+            surql: &str,
+            vars: &Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            self.queries
+                .lock()
+                .unwrap()
+                .push((surql.to_string(), vars.clone()));
+            Ok(vec![json!(["ntf_1", "ntf_2"])])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_marks_only_unread_notifications() {
+        let db = Arc::new(Recording {
+            queries: Mutex::new(Vec::new()),
+        });
+        let store = SurrealNotificationStore::new(db.clone());
+
+        let marked = store
+            .mark_all_read("user_1", datetime!(2024-01-02 0:00 UTC))
+            .await
+            .unwrap();
+
+        assert_eq!(marked, 2);
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert!(surql.contains("WHERE user_id = $user_id AND read_at IS NONE"));
+        assert_eq!(vars["at"], "2024-01-02T00:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_the_first_read_time() {
+        let store = MemoryNotificationStore::new();
+        store.insert(notification("ntf_1", "user_1")).await.unwrap();
+        store.insert(notification("ntf_2", "user_1")).await.unwrap();
+        store.insert(notification("ntf_3", "user_2")).await.unwrap();
+
+        let first = datetime!(2024-01-02 0:00 UTC);
+        let read = store.mark_read("ntf_1", first).await.unwrap().unwrap();
+        assert_eq!(read.read_at, Some(first));
+        let again = store
+            .mark_read("ntf_1", datetime!(2024-01-03 0:00 UTC))
+            .await
+            .unwrap();
+        assert_eq!(again.unwrap().read_at, Some(first));
+        assert!(store.mark_read("ntf_9", first).await.unwrap().is_none());
+
+        assert_eq!(store.unread_count("user_1").await.unwrap(), 1);
+        let unread = store
+            .list(&NotificationQuery {
+                user_id: "user_1".to_string(),
+                unread: true,
+                ..NotificationQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(unread.total, 1);
+        assert_eq!(unread.items[0].id, "ntf_2");
+        assert_eq!(store.mark_all_read("user_1", first).await.unwrap(), 1);
+        assert_eq!(store.unread_count("user_2").await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,86 @@
+//! Named events the orgs module emits when memberships change
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use atlas_events::VersionedEvent;
+use atlas_kernel::EventSchema;
+
+use super::models::Role;
+
+/// A user joined an existing organization; its creator's membership is not announced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberAdded {
+    pub org: String,
+    pub user_id: String,
+    pub role: Role,
+}
+
+impl VersionedEvent for MemberAdded {
+    const NAME: &'static str = "orgs.member_added";
+    const VERSION: u32 = 1;
+}
+
+/// A member's role changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleChanged {
+    pub org: String,
+    pub user_id: String,
+    pub role: Role,
+    pub previous: Role,
+}
+
+impl VersionedEvent for RoleChanged {
+    const NAME: &'static str = "orgs.role_changed";
+    const VERSION: u32 = 1;
+}
+
+/// A user stopped being a member; deleting an organization is not announced per member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberRemoved {
+    pub org: String,
+    pub user_id: String,
+}
+
+impl VersionedEvent for MemberRemoved {
+    const NAME: &'static str = "orgs.member_removed";
+    const VERSION: u32 = 1;
+}
+
+/// Schemas of the events above, declared through `Module::event_schemas`
+pub(crate) fn schemas() -> Vec<EventSchema> {
+    let text = json!({ "type": "string" });
+    let role = json!({ "type": "string", "enum": ["owner", "admin", "member"] });
+    vec![
+        EventSchema {
+            name: MemberAdded::NAME,
+            version: MemberAdded::VERSION,
+            description: Some("A user joined an organization"),
+            schema: json!({
+                "type": "object",
+                "required": ["org", "user_id", "role"],
+                "properties": { "org": text, "user_id": text, "role": role }
+            }),
+        },
+        EventSchema {
+            name: RoleChanged::NAME,
+            version: RoleChanged::VERSION,
+            description: Some("A member's role in an organization changed"),
+            schema: json!({
+                "type": "object",
+                "required": ["org", "user_id", "role", "previous"],
+                "properties": { "org": text, "user_id": text, "role": role, "previous": role }
+            }),
+        },
+        EventSchema {
+            name: MemberRemoved::NAME,
+            version: MemberRemoved::VERSION,
+            description: Some("A user was removed from an organization"),
+            schema: json!({
+                "type": "object",
+                "required": ["org", "user_id"],
+                "properties": { "org": text, "user_id": text }
+            }),
+        },
+    ]
+}
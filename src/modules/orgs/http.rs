@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+    Json, Router,
+};
+use serde_json::json;
+
+use atlas_authz::Subject;
+use atlas_http::error::AppError;
+use atlas_http::pagination::{Page, Pagination};
+use atlas_kernel::tenant::TenantId;
+
+use super::models::{CreateOrg, Membership, NewOrg, Org, Role, SetRole};
+use super::service::{OrgError, Orgs};
+use super::store::OrgQuery;
+
+impl From<OrgError> for AppError {
+    fn from(error: OrgError) -> Self {
+        match error {
+            OrgError::DuplicateSlug(slug) => AppError::conflict(
+                vec![json!({ "field": "slug", "error": "taken" })],
+                format!("an organization with slug '{}' already exists", slug),
+            ),
+            OrgError::UnknownOrg(slug) => not_found(&slug),
+            OrgError::UnknownUser(user_id) => AppError::not_found(format!("no user '{}'", user_id)),
+            error @ OrgError::LastOwner(_) => AppError::conflict(
+                vec![json!({ "field": "role", "error": "last_owner" })],
+                error.to_string(),
+            ),
+            error @ OrgError::Forbidden { .. } => AppError::forbidden(error.to_string()),
+            OrgError::Store(error) => AppError::Internal(error),
+        }
+    }
+}
+
+fn validate(request: &NewOrg) -> Result<(), AppError> {
+    let mut details = Vec::new();
+    for (field, value) in [("slug", &request.slug), ("name", &request.name)] {
+        if value.trim().is_empty() {
+            details.push(json!({ "field": field, "error": "required" }));
+        }
+    }
+    let slug = request.slug.trim();
+    if !slug.is_empty() && TenantId::new(slug).is_err() {
+        details.push(json!({ "field": "slug", "error": "invalid" }));
+    }
+    if details.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::validation(
+            details,
+            "slug and name are required; slugs are valid tenant ids",
+        ))
+    }
+}
+
+fn not_found(slug: &str) -> AppError {
+    AppError::not_found(format!("no organization '{}'", slug))
+}
+
+/// The organizations the caller belongs to
+async fn list_orgs(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    pagination: Pagination,
+) -> Result<Json<Page<Org>>, AppError> {
+    let query = OrgQuery {
+        member: Some(subject.0),
+        pagination,
+    };
+    Ok(Json(orgs.list(&query).await?))
+}
+
+async fn create_org(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Json(request): Json<NewOrg>,
+) -> Result<(StatusCode, Json<Org>), AppError> {
+    validate(&request)?;
+    let request = CreateOrg {
+        slug: request.slug,
+        name: request.name,
+        owner_id: subject.0,
+    };
+    Ok((StatusCode::CREATED, Json(orgs.create(request).await?)))
+}
+
+async fn get_org(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Path(slug): Path<String>,
+) -> Result<Json<Org>, AppError> {
+    orgs.authorize(&slug, &subject.0, Role::Member).await?;
+    orgs.get(&slug)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&slug))
+}
+
+async fn delete_org(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Path(slug): Path<String>,
+) -> Result<StatusCode, AppError> {
+    orgs.authorize(&slug, &subject.0, Role::Owner).await?;
+    if orgs.delete(&slug).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(&slug))
+    }
+}
+
+async fn list_members(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Path(slug): Path<String>,
+    pagination: Pagination,
+) -> Result<Json<Page<Membership>>, AppError> {
+    orgs.authorize(&slug, &subject.0, Role::Member).await?;
+    Ok(Json(orgs.members(&slug, pagination).await?))
+}
+
+/// The role a change to `user_id`'s membership gives or takes away
+async fn at_stake(orgs: &Orgs, slug: &str, user_id: &str, role: Role) -> Result<Role, AppError> {
+    let current = orgs.membership(slug, user_id).await?;
+    Ok(match current {
+        Some(current) if current.role.includes(role) => current.role,
+        _ => role,
+    })
+}
+
+async fn set_role(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Path((slug, user_id)): Path<(String, String)>,
+    Json(request): Json<SetRole>,
+) -> Result<Json<Membership>, AppError> {
+    let role = at_stake(&orgs, &slug, &user_id, request.role).await?;
+    orgs.authorize_grant(&slug, &subject.0, role).await?;
+    Ok(Json(orgs.set_role(&slug, &user_id, request.role).await?))
+}
+
+/// Members may leave; removing someone else takes the right to grant their role
+async fn remove_member(
+    State(orgs): State<Arc<Orgs>>,
+    subject: Subject,
+    Path((slug, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    if subject.0 == user_id {
+        orgs.authorize(&slug, &subject.0, Role::Member).await?;
+    } else {
+        let role = at_stake(&orgs, &slug, &user_id, Role::Member).await?;
+        orgs.authorize_grant(&slug, &subject.0, role).await?;
+    }
+    if orgs.remove_member(&slug, &user_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::not_found(format!(
+            "'{}' is not a member of '{}'",
+            user_id, slug
+        )))
+    }
+}
+
+/// Health check endpoint
+async fn health_check() -> &'static str {
+    "orgs module is healthy"
+}
+
+/// Organization and membership endpoints over `orgs`, each checking the caller's role in
+/// the organization, plus the module health check
+pub fn routes(orgs: Arc<Orgs>) -> Router {
+    Router::new()
+        .route("/", get(list_orgs).post(create_org))
+        .route("/{slug}", get(get_org).delete(delete_org))
+        .route("/{slug}/members", get(list_members))
+        .route(
+            "/{slug}/members/{user_id}",
+            put(set_role).delete(remove_member),
+        )
+        .with_state(orgs)
+        .route("/health", get(health_check))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::orgs::store::MemoryOrgStore;
+    use crate::modules::users::models::CreateUser;
+    use crate::modules::users::service::Users;
+    use crate::modules::users::store::MemoryUserStore;
+    use atlas_authz::testing::as_subject;
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::clock::SystemClock;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_org_and_membership_endpoints() {
+        let users = Arc::new(Users::new(
+            Arc::new(MemoryUserStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let mut ids = Vec::new();
+        for email in ["ann@example.com", "bob@example.com", "cat@example.com"] {
+            let user = users
+                .create(CreateUser {
+                    email: email.to_string(),
+                    name: "Someone".to_string(),
+                    bio: None,
+                    avatar_url: None,
+                })
+                .await
+                .unwrap();
+            ids.push(user.id);
+        }
+        let (ann, bob, cat) = (ids[0].as_str(), ids[1].as_str(), ids[2].as_str());
+        let orgs = Arc::new(Orgs::new(
+            Arc::new(MemoryOrgStore::new()),
+            users,
+            Arc::new(SystemClock),
+        ));
+        let router = Router::new().nest("/api/orgs", routes(orgs));
+        let call = |caller: &str, method: &str, uri: &str, body: Value| {
+            let router = router.clone();
+            let request = as_subject(request(method, uri, body), caller);
+            async move { send(&router, request).await }
+        };
+
+        let acme = json!({ "slug": "acme", "name": "Acme Inc." });
+        let (status, _) = send(&router, request("POST", "/api/orgs", acme.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, created) = call(ann, "POST", "/api/orgs", acme.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["slug"], "acme");
+        let (status, _) = call(ann, "POST", "/api/orgs", acme).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, error) = call(
+            ann,
+            "POST",
+            "/api/orgs",
+            json!({ "slug": "Acme Corp", "name": "" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"].as_array().unwrap().len(), 2);
+
+        // Outsiders cannot tell the organization exists
+        let (status, _) = call(bob, "GET", "/api/orgs/acme", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, listed) = call(bob, "GET", "/api/orgs", Value::Null).await;
+        assert_eq!((status, &listed["total"]), (StatusCode::OK, &json!(0)));
+
+        let bob_uri = format!("/api/orgs/acme/members/{}", bob);
+        let cat_uri = format!("/api/orgs/acme/members/{}", cat);
+        let ann_uri = format!("/api/orgs/acme/members/{}", ann);
+        let (status, _) = call(bob, "PUT", &bob_uri, json!({ "role": "admin" })).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, membership) = call(ann, "PUT", &bob_uri, json!({ "role": "admin" })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(membership["role"], "admin");
+        let (status, _) = call(ann, "PUT", &bob_uri, json!({ "role": "root" })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Admins manage members and admins, but only owners manage owners
+        let (status, _) = call(bob, "PUT", &cat_uri, json!({ "role": "member" })).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(bob, "PUT", &cat_uri, json!({ "role": "owner" })).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(bob, "DELETE", &ann_uri, Value::Null).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(cat, "DELETE", &bob_uri, Value::Null).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(bob, "DELETE", "/api/orgs/acme", Value::Null).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, error) = call(ann, "DELETE", &ann_uri, Value::Null).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["details"][0]["error"], "last_owner");
+
+        let (status, members) = call(cat, "GET", "/api/orgs/acme/members", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(members["total"], 3);
+        let (_, listed) = call(cat, "GET", "/api/orgs", Value::Null).await;
+        assert_eq!(listed["total"], 1);
+
+        // Members may leave on their own
+        let (status, _) = call(cat, "DELETE", &cat_uri, Value::Null).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = call(ann, "DELETE", "/api/orgs/acme", Value::Null).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = call(ann, "GET", "/api/orgs/acme/members", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_served_routes_act_as_the_authenticated_caller() {
+        let app = atlas_authz::testing::TestApp::new(
+            vec![
+                Arc::new(crate::modules::users::UsersModule::new()),
+                Arc::new(crate::modules::orgs::OrgsModule::new()),
+            ],
+            "",
+        )
+        .await;
+        let users = app.registry.resources().require::<Users>().unwrap();
+        let ann = users
+            .create(CreateUser {
+                email: "ann@example.com".to_string(),
+                name: "Ann Lee".to_string(),
+                bio: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        let acme = json!({ "slug": "acme", "name": "Acme Inc." });
+
+        let (status, _) = send(&app.router, request("POST", "/api/orgs", acme.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let create = app
+            .with_api_key(request("POST", "/api/orgs", acme), &ann.id)
+            .await;
+        let (status, _) = send(&app.router, create).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let members = app
+            .with_session(
+                request("GET", "/api/orgs/acme/members", Value::Null),
+                &ann.id,
+            )
+            .await;
+        let (status, members) = send(&app.router, members).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(members["items"][0]["user_id"], json!(ann.id));
+
+        let (status, _) = send(&app.router, request("GET", "/api/orgs/health", Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
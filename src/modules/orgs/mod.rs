@@ -0,0 +1,415 @@
+pub mod events;
+pub mod http;
+pub mod models;
+pub mod service;
+pub mod store;
+
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use atlas_authz::TenantMembers;
+use atlas_events::EventBus;
+use atlas_http::pagination;
+use atlas_kernel::{
+    EventSchema, InitCtx, Migration, Module, RouteSecurity, SchemaExample, SecurityScheme,
+};
+use axum::Router;
+use serde_json::json;
+
+use crate::modules::users::service::Users;
+use service::Orgs;
+use store::{MemoryOrgStore, OrgStore, SurrealOrgStore};
+
+/// Organizations and their memberships, backed by the `org` and `membership` tables
+///
+/// An organization's slug is its tenant id: with `tenancy.enabled`, requests naming it
+/// work on its own slice of tenant-scoped data such as the book catalog, provided the
+/// caller is a member: `Orgs` is published as the `TenantMembers` that
+/// `atlas_authz::require_tenant_member` asks, and as a shared resource. Membership changes are emitted as the named events in
+/// [`events`] when the event bus is available.
+#[derive(Default)]
+pub struct OrgsModule {
+    orgs: OnceLock<Arc<Orgs>>,
+}
+
+impl OrgsModule {
+    pub const fn new() -> Self {
+        Self {
+            orgs: OnceLock::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Module for OrgsModule {
+    fn name(&self) -> &'static str {
+        "orgs"
+    }
+
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("Organizations, memberships, and roles")
+    }
+
+    /// Members are users of the users module
+    fn depends_on(&self) -> &[&'static str] {
+        &["users"]
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn OrgStore>(
+            &ctx.resources,
+            "organization",
+            |db| Arc::new(SurrealOrgStore::new(db)),
+            || Arc::new(MemoryOrgStore::new()),
+        );
+        let users = ctx.resources.require::<Users>()?;
+        let orgs = Orgs::new(store, users, ctx.clock.clone());
+        let orgs = Arc::new(match ctx.resources.get::<EventBus>() {
+            Some(bus) => orgs.with_events(bus),
+            None => orgs,
+        });
+        ctx.resources.insert_arc(orgs.clone());
+        ctx.resources.insert::<Arc<dyn TenantMembers>>(orgs.clone());
+        self.orgs
+            .set(orgs)
+            .map_err(|_| anyhow::anyhow!("orgs module initialized twice"))?;
+
+        tracing::info!(
+            module = self.name(),
+            environment = ?ctx.settings.environment,
+            "orgs module initialized"
+        );
+        Ok(())
+    }
+
+    fn routes(&self) -> Router {
+        match self.orgs.get() {
+            Some(orgs) => http::routes(orgs.clone()),
+            None => Router::new(),
+        }
+    }
+
+    /// Every route but the health check acts as the caller, whose role is checked per
+    /// organization
+    fn security(&self) -> Vec<RouteSecurity> {
+        let caller = |method, path| RouteSecurity {
+            method,
+            path,
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission: None,
+        };
+        vec![
+            caller("*", "/"),
+            caller("*", "/{slug}"),
+            caller("*", "/{slug}/members"),
+            caller("*", "/{slug}/members/{user_id}"),
+        ]
+    }
+
+    fn openapi(&self) -> Option<serde_json::Value> {
+        let error = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorResponse" }
+                    }
+                }
+            })
+        };
+        let schema = |description: &str, schema: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let body = |schema: &str| {
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let page = |description: &str, item: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": pagination::openapi::page_schema(
+                            json!({ "$ref": format!("#/components/schemas/{}", item) })
+                        )
+                    }
+                }
+            })
+        };
+        let path = |name: &str| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" }
+            })
+        };
+        let mut member_parameters = vec![path("slug")];
+        member_parameters.extend(pagination::openapi::parameters());
+        let text = |description: &str| json!({ "type": "string", "description": description });
+        let timestamp = |description: &str| json!({ "type": "string", "format": "date-time", "description": description });
+        let role = json!({
+            "type": "string",
+            "enum": ["owner", "admin", "member"],
+            "description": "What the member may do in the organization"
+        });
+        Some(json!({
+            "tags": [
+                {
+                    "name": "Organizations",
+                    "description": "Organizations — tenants, memberships, and roles",
+                    "x-order": 3
+                }
+            ],
+            "paths": {
+                "/": {
+                    "get": {
+                        "summary": "List the caller's organizations",
+                        "tags": ["Organizations"],
+                        "parameters": pagination::openapi::parameters(),
+                        "responses": {
+                            "200": page("One page of the organizations the caller belongs to, oldest first", "Org"),
+                            "422": error("Invalid page or per_page"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "post": {
+                        "summary": "Create an organization",
+                        "description": "The caller becomes its first owner.",
+                        "tags": ["Organizations"],
+                        "requestBody": body("NewOrg"),
+                        "responses": {
+                            "201": schema("The new organization", "Org"),
+                            "404": error("The caller is not a known user"),
+                            "409": error("Another organization has this slug"),
+                            "422": error("A field is blank or the slug is not a valid tenant id"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{slug}": {
+                    "get": {
+                        "summary": "Get an organization",
+                        "description": "Any member may read it.",
+                        "tags": ["Organizations"],
+                        "parameters": [path("slug")],
+                        "responses": {
+                            "200": schema("The organization", "Org"),
+                            "404": error("No organization with that slug that the caller belongs to"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "delete": {
+                        "summary": "Delete an organization",
+                        "description": "Only owners may delete it; its memberships go with it.",
+                        "tags": ["Organizations"],
+                        "parameters": [path("slug")],
+                        "responses": {
+                            "204": { "description": "Organization deleted" },
+                            "403": error("The caller is not an owner"),
+                            "404": error("No organization with that slug that the caller belongs to"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{slug}/members": {
+                    "get": {
+                        "summary": "List members",
+                        "description": "Any member may list them.",
+                        "tags": ["Organizations"],
+                        "parameters": member_parameters,
+                        "responses": {
+                            "200": page("One page of the memberships, earliest first", "Membership"),
+                            "404": error("No organization with that slug that the caller belongs to"),
+                            "422": error("Invalid page or per_page"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{slug}/members/{user_id}": {
+                    "put": {
+                        "summary": "Add a member or change their role",
+                        "description": "Admins add members and admins; only owners grant or take away ownership.",
+                        "tags": ["Organizations"],
+                        "parameters": [path("slug"), path("user_id")],
+                        "requestBody": body("SetRole"),
+                        "responses": {
+                            "200": schema("The membership", "Membership"),
+                            "403": error("The caller's role does not allow this change"),
+                            "404": error("No organization the caller belongs to, or no user with that id"),
+                            "409": error("The organization would be left without an owner"),
+                            "422": error("Unknown role"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "delete": {
+                        "summary": "Remove a member",
+                        "description": "Members may leave; removing someone else takes the role that could grant theirs.",
+                        "tags": ["Organizations"],
+                        "parameters": [path("slug"), path("user_id")],
+                        "responses": {
+                            "204": { "description": "Member removed" },
+                            "403": error("The caller's role does not allow this removal"),
+                            "404": error("No such organization or membership"),
+                            "409": error("The organization would be left without an owner"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/health": {
+                    "get": {
+                        "summary": "Organizations health check",
+                        "tags": ["Organizations"],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": {
+                                    "text/plain": {
+                                        "schema": { "type": "string" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Org": {
+                        "type": "object",
+                        "properties": {
+                            "id": text("Unique identifier for the organization"),
+                            "slug": text("Tenant id requests name the organization by"),
+                            "name": text("Display name"),
+                            "created_at": timestamp("When the organization was created"),
+                            "updated_at": timestamp("When the organization was last changed")
+                        },
+                        "required": ["id", "slug", "name", "created_at", "updated_at"]
+                    },
+                    "NewOrg": {
+                        "type": "object",
+                        "properties": {
+                            "slug": text("Up to 64 lowercase letters, digits, '-' or '_'"),
+                            "name": text("Display name")
+                        },
+                        "required": ["slug", "name"]
+                    },
+                    "Membership": {
+                        "type": "object",
+                        "properties": {
+                            "org": text("Slug of the organization"),
+                            "user_id": text("The member"),
+                            "role": role.clone(),
+                            "created_at": timestamp("When the user joined"),
+                            "updated_at": timestamp("When the role last changed")
+                        },
+                        "required": ["org", "user_id", "role", "created_at", "updated_at"]
+                    },
+                    "SetRole": {
+                        "type": "object",
+                        "properties": { "role": role },
+                        "required": ["role"]
+                    }
+                }
+            }
+        }))
+    }
+
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![
+            SchemaExample::new(
+                "Org",
+                json!({
+                    "id": "org_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                    "slug": "acme",
+                    "name": "Acme Inc.",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-01T00:00:00Z"
+                }),
+            ),
+            SchemaExample::new(
+                "NewOrg",
+                json!({
+                    "slug": "acme",
+                    "name": "Acme Inc."
+                }),
+            ),
+            SchemaExample::new(
+                "Membership",
+                json!({
+                    "org": "acme",
+                    "user_id": "user_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                    "role": "admin",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-15T10:30:00Z"
+                }),
+            ),
+        ]
+    }
+
+    fn event_schemas(&self) -> Vec<EventSchema> {
+        events::schemas()
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![Migration {
+            id: "001_init",
+            up: r#"
+                DEFINE TABLE org SCHEMAFULL;
+                DEFINE FIELD slug       ON org TYPE string ASSERT $value != "";
+                DEFINE FIELD name       ON org TYPE string ASSERT $value != "";
+                DEFINE FIELD created_at ON org TYPE datetime VALUE $before OR $value;
+                DEFINE FIELD updated_at ON org TYPE datetime;
+                DEFINE INDEX org_slug_unique ON org FIELDS slug UNIQUE;
+
+                DEFINE TABLE membership SCHEMAFULL;
+                DEFINE FIELD org        ON membership TYPE string;
+                DEFINE FIELD user_id    ON membership TYPE string;
+                DEFINE FIELD role       ON membership TYPE string ASSERT $value IN ["owner", "admin", "member"];
+                DEFINE FIELD created_at ON membership TYPE datetime VALUE $before OR $value;
+                DEFINE FIELD updated_at ON membership TYPE datetime;
+                DEFINE INDEX membership_user ON membership FIELDS user_id;
+                "#,
+            down: Some(
+                r#"
+                REMOVE TABLE membership;
+                REMOVE TABLE org;
+                "#,
+            ),
+        }]
+    }
+
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "orgs module started");
+        Ok(())
+    }
+
+    async fn stop(&self) -> anyhow::Result<()> {
+        tracing::info!(module = self.name(), "orgs module stopped");
+        Ok(())
+    }
+}
+
+/// Create a new instance of the orgs module
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(OrgsModule::new())
+}
+
+atlas_kernel::register_module!(create_module);
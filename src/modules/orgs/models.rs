@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// An organization: the tenant that owns a slice of the application's data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Org {
+    /// Unique identifier, e.g. `org_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Tenant id requests name the organization by, e.g. `acme`
+    pub slug: String,
+    /// Display name
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Request body of `POST /`; the caller becomes the organization's first owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewOrg {
+    pub slug: String,
+    pub name: String,
+}
+
+/// An organization to create, with its first owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateOrg {
+    pub slug: String,
+    pub name: String,
+    /// User who becomes the organization's first owner
+    pub owner_id: String,
+}
+
+/// What a member may do in an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Full control; every organization keeps at least one
+    Owner,
+    Admin,
+    Member,
+}
+
+impl Role {
+    /// Whether this role may do everything `other` may
+    pub fn includes(self, other: Role) -> bool {
+        self.rank() >= other.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Role::Owner => 2,
+            Role::Admin => 1,
+            Role::Member => 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Member => "member",
+        }
+    }
+}
+
+/// A user's role in an organization
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Membership {
+    /// Slug of the organization
+    pub org: String,
+    pub user_id: String,
+    pub role: Role,
+    /// When the user joined
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// When the role last changed
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Request body of `PUT /{slug}/members/{user_id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetRole {
+    pub role: Role,
+}
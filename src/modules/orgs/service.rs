@@ -0,0 +1,404 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use atlas_authz::TenantMembers;
+use atlas_events::{EventBus, VersionedEvent};
+use atlas_http::pagination::{Page, Pagination};
+use atlas_kernel::{id, tenant::TenantId, Clock};
+
+use super::events::{MemberAdded, MemberRemoved, RoleChanged};
+use super::models::{CreateOrg, Membership, Org, Role};
+use super::store::{OrgQuery, OrgStore, OrgStoreError};
+use crate::modules::users::service::Users;
+
+/// Why an organization or membership change was refused
+#[derive(Debug, thiserror::Error)]
+pub enum OrgError {
+    #[error("an organization with slug '{0}' already exists")]
+    DuplicateSlug(String),
+    #[error("no organization '{0}'")]
+    UnknownOrg(String),
+    #[error("no user '{0}'")]
+    UnknownUser(String),
+    #[error("organization '{0}' must keep at least one owner")]
+    LastOwner(String),
+    #[error("requires the {} role in '{}'", .needed.as_str(), .org)]
+    Forbidden { org: String, needed: Role },
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+impl From<OrgStoreError> for OrgError {
+    fn from(error: OrgStoreError) -> Self {
+        match error {
+            OrgStoreError::DuplicateSlug(slug) => OrgError::DuplicateSlug(slug),
+            OrgStoreError::Store(error) => OrgError::Store(error),
+        }
+    }
+}
+
+/// Creates organizations and manages who belongs to them
+///
+/// An organization's slug is the tenant id requests name it by, so its members work on
+/// the data other modules scope to that tenant. With an event bus, membership changes
+/// are emitted as the events in [`super::events`].
+pub struct Orgs {
+    store: Arc<dyn OrgStore>,
+    users: Arc<Users>,
+    clock: Arc<dyn Clock>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl Orgs {
+    pub fn new(store: Arc<dyn OrgStore>, users: Arc<Users>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            store,
+            users,
+            clock,
+            events: None,
+        }
+    }
+
+    /// Emit membership changes on `bus`
+    pub fn with_events(mut self, bus: Arc<EventBus>) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// Store a new organization with `request.owner_id` as its owner
+    pub async fn create(&self, request: CreateOrg) -> Result<Org, OrgError> {
+        self.require_user(&request.owner_id).await?;
+        let now = self.now();
+        let org = Org {
+            id: id::prefixed_with("org", id::ulid_at(self.clock.now()))
+                .map_err(anyhow::Error::from)?,
+            slug: request.slug.trim().to_string(),
+            name: request.name.trim().to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        let owner = Membership {
+            org: org.slug.clone(),
+            user_id: request.owner_id,
+            role: Role::Owner,
+            created_at: now,
+            updated_at: now,
+        };
+        self.store.insert(org.clone(), owner).await?;
+        tracing::info!(org_id = %org.id, slug = %org.slug, "created organization");
+        Ok(org)
+    }
+
+    pub async fn get(&self, slug: &str) -> anyhow::Result<Option<Org>> {
+        self.store.get(slug).await
+    }
+
+    pub async fn list(&self, query: &OrgQuery) -> anyhow::Result<Page<Org>> {
+        self.store.list(query).await
+    }
+
+    /// Remove an organization and its memberships, returning `false` if there is none
+    pub async fn delete(&self, slug: &str) -> anyhow::Result<bool> {
+        let deleted = self.store.delete(slug).await?;
+        if deleted {
+            tracing::info!(slug = %slug, "deleted organization");
+        }
+        Ok(deleted)
+    }
+
+    /// The user's membership of `org`, if they belong to it
+    pub async fn membership(&self, org: &str, user_id: &str) -> anyhow::Result<Option<Membership>> {
+        self.store.membership(org, user_id).await
+    }
+
+    pub async fn members(
+        &self,
+        org: &str,
+        pagination: Pagination,
+    ) -> Result<Page<Membership>, OrgError> {
+        self.require_org(org).await?;
+        Ok(self.store.members(org, pagination).await?)
+    }
+
+    /// Add the user to `org` with `role`, or change the role they have
+    ///
+    /// The last owner cannot be demoted.
+    pub async fn set_role(
+        &self,
+        org: &str,
+        user_id: &str,
+        role: Role,
+    ) -> Result<Membership, OrgError> {
+        self.require_org(org).await?;
+        let now = self.now();
+        let previous = self.store.membership(org, user_id).await?;
+        let membership = match previous.clone() {
+            Some(stored) => {
+                if stored.role == Role::Owner && role != Role::Owner {
+                    self.keep_an_owner(org).await?;
+                }
+                Membership {
+                    role,
+                    updated_at: now,
+                    ..stored
+                }
+            }
+            None => {
+                self.require_user(user_id).await?;
+                Membership {
+                    org: org.to_string(),
+                    user_id: user_id.to_string(),
+                    role,
+                    created_at: now,
+                    updated_at: now,
+                }
+            }
+        };
+        self.store.put_membership(membership.clone()).await?;
+        tracing::info!(org = %org, user_id = %user_id, role = role.as_str(), "set member role");
+        match previous {
+            None => self.emit(&MemberAdded {
+                org: org.to_string(),
+                user_id: user_id.to_string(),
+                role,
+            }),
+            Some(previous) if previous.role != role => self.emit(&RoleChanged {
+                org: org.to_string(),
+                user_id: user_id.to_string(),
+                role,
+                previous: previous.role,
+            }),
+            Some(_) => {}
+        }
+        Ok(membership)
+    }
+
+    /// Remove the user from `org`, returning `false` if they were not a member
+    ///
+    /// The last owner cannot be removed.
+    pub async fn remove_member(&self, org: &str, user_id: &str) -> Result<bool, OrgError> {
+        self.require_org(org).await?;
+        let Some(stored) = self.store.membership(org, user_id).await? else {
+            return Ok(false);
+        };
+        if stored.role == Role::Owner {
+            self.keep_an_owner(org).await?;
+        }
+        let removed = self.store.remove_membership(org, user_id).await?;
+        if removed {
+            tracing::info!(org = %org, user_id = %user_id, "removed member");
+            self.emit(&MemberRemoved {
+                org: org.to_string(),
+                user_id: user_id.to_string(),
+            });
+        }
+        Ok(removed)
+    }
+
+    /// The caller's membership in `org`, if its role includes `needed`
+    ///
+    /// An organization the caller does not belong to is reported as unknown, so callers
+    /// cannot probe which slugs exist.
+    pub async fn authorize(
+        &self,
+        org: &str,
+        caller: &str,
+        needed: Role,
+    ) -> Result<Membership, OrgError> {
+        let Some(membership) = self.store.membership(org, caller).await? else {
+            return Err(OrgError::UnknownOrg(org.to_string()));
+        };
+        if !membership.role.includes(needed) {
+            return Err(OrgError::Forbidden {
+                org: org.to_string(),
+                needed,
+            });
+        }
+        Ok(membership)
+    }
+
+    /// The caller's membership in `org`, if it may give others `role` there or take it
+    /// away: admins manage members and admins, and only owners manage owners
+    pub async fn authorize_grant(
+        &self,
+        org: &str,
+        caller: &str,
+        role: Role,
+    ) -> Result<Membership, OrgError> {
+        let needed = if role == Role::Owner {
+            Role::Owner
+        } else {
+            Role::Admin
+        };
+        self.authorize(org, caller, needed).await
+    }
+
+    /// Fail unless `org` has an owner besides the one about to lose the role
+    async fn keep_an_owner(&self, org: &str) -> Result<(), OrgError> {
+        if self.store.owner_count(org).await? <= 1 {
+            return Err(OrgError::LastOwner(org.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn require_org(&self, org: &str) -> Result<(), OrgError> {
+        match self.store.get(org).await? {
+            Some(_) => Ok(()),
+            None => Err(OrgError::UnknownOrg(org.to_string())),
+        }
+    }
+
+    async fn require_user(&self, user_id: &str) -> Result<(), OrgError> {
+        match self.users.get(user_id).await? {
+            Some(_) => Ok(()),
+            None => Err(OrgError::UnknownUser(user_id.to_string())),
+        }
+    }
+
+    /// Emit `event` if there is a bus; the change is already stored, so failing to
+    /// announce it is only logged
+    ///
+    /// Emitted by name rather than as its declared version, which the bus only knows once
+    /// the events module has loaded the catalog.
+    fn emit<E: VersionedEvent>(&self, event: &E) {
+        let Some(bus) = &self.events else {
+            return;
+        };
+        if let Err(error) = bus.emit(E::NAME, event) {
+            tracing::warn!(
+                event = E::NAME,
+                error = format!("{:#}", error),
+                "failed to emit membership event"
+            );
+        }
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+#[async_trait]
+impl TenantMembers for Orgs {
+    async fn is_member(&self, tenant: &TenantId, subject: &str) -> anyhow::Result<bool> {
+        Ok(self
+            .store
+            .membership(tenant.as_str(), subject)
+            .await?
+            .is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::orgs::store::MemoryOrgStore;
+    use crate::modules::users::models::CreateUser;
+    use crate::modules::users::store::MemoryUserStore;
+    use atlas_kernel::clock::SystemClock;
+
+    async fn setup() -> (Orgs, String, String) {
+        let users = Arc::new(Users::new(
+            Arc::new(MemoryUserStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let mut ids = Vec::new();
+        for email in ["ann@example.com", "bob@example.com"] {
+            let user = users
+                .create(CreateUser {
+                    email: email.to_string(),
+                    name: "Someone".to_string(),
+                    bio: None,
+                    avatar_url: None,
+                })
+                .await
+                .unwrap();
+            ids.push(user.id);
+        }
+        let orgs = Orgs::new(
+            Arc::new(MemoryOrgStore::new()),
+            users,
+            Arc::new(SystemClock),
+        );
+        let bob = ids.pop().unwrap();
+        let ann = ids.pop().unwrap();
+        (orgs, ann, bob)
+    }
+
+    fn acme(owner_id: &str) -> CreateOrg {
+        CreateOrg {
+            slug: "acme".to_string(),
+            name: "Acme Inc.".to_string(),
+            owner_id: owner_id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creator_becomes_owner() {
+        let (orgs, ann, _) = setup().await;
+
+        let org = orgs.create(acme(&ann)).await.unwrap();
+        assert!(org.id.starts_with("org_"));
+        let membership = orgs.membership("acme", &ann).await.unwrap().unwrap();
+        assert_eq!(membership.role, Role::Owner);
+
+        assert!(matches!(
+            orgs.create(acme(&ann)).await,
+            Err(OrgError::DuplicateSlug(_))
+        ));
+        assert!(matches!(
+            orgs.create(acme("user_missing")).await,
+            Err(OrgError::UnknownUser(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_last_owner_cannot_leave_or_be_demoted() {
+        let (orgs, ann, bob) = setup().await;
+        orgs.create(acme(&ann)).await.unwrap();
+
+        assert!(matches!(
+            orgs.set_role("acme", &ann, Role::Admin).await,
+            Err(OrgError::LastOwner(_))
+        ));
+        assert!(matches!(
+            orgs.remove_member("acme", &ann).await,
+            Err(OrgError::LastOwner(_))
+        ));
+
+        orgs.set_role("acme", &bob, Role::Owner).await.unwrap();
+        let demoted = orgs.set_role("acme", &ann, Role::Member).await.unwrap();
+        assert_eq!(demoted.role, Role::Member);
+        assert!(orgs.remove_member("acme", &ann).await.unwrap());
+        assert!(!orgs.remove_member("acme", &ann).await.unwrap());
+        assert!(matches!(
+            orgs.set_role("globex", &ann, Role::Member).await,
+            Err(OrgError::UnknownOrg(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_membership_changes_are_emitted() {
+        let (orgs, ann, bob) = setup().await;
+        let bus = Arc::new(EventBus::new(16));
+        let orgs = orgs.with_events(bus.clone());
+        let mut events = bus.subscribe::<atlas_events::NamedEvent>();
+        orgs.create(acme(&ann)).await.unwrap();
+
+        orgs.set_role("acme", &bob, Role::Member).await.unwrap();
+        orgs.set_role("acme", &bob, Role::Member).await.unwrap();
+        orgs.set_role("acme", &bob, Role::Admin).await.unwrap();
+        orgs.remove_member("acme", &bob).await.unwrap();
+
+        let added = events.try_recv().unwrap();
+        assert_eq!(&*added.name, "orgs.member_added");
+        assert_eq!(added.payload["user_id"], bob.as_str());
+        let changed = events.try_recv().unwrap();
+        assert_eq!(&*changed.name, "orgs.role_changed");
+        assert_eq!(changed.payload["previous"], "member");
+        assert_eq!(&*events.try_recv().unwrap().name, "orgs.member_removed");
+        assert!(events.try_recv().is_none());
+    }
+}
@@ -0,0 +1,556 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use atlas_db::QueryExecutor;
+use atlas_http::pagination::{Page, Pagination};
+
+use super::models::{Membership, Org, Role};
+
+/// Unique index on `org.slug` defined by the module's migrations
+pub const SLUG_INDEX: &str = "org_slug_unique";
+
+/// Why an organization could not be stored
+#[derive(Debug, thiserror::Error)]
+pub enum OrgStoreError {
+    #[error("an organization with slug '{0}' already exists")]
+    DuplicateSlug(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Filters and page of an organization listing
+#[derive(Debug, Clone, Default)]
+pub struct OrgQuery {
+    /// Only organizations this user is a member of
+    pub member: Option<String>,
+    pub pagination: Pagination,
+}
+
+/// Persistence for organizations and their memberships
+///
+/// The module stores them in SurrealDB through the application's `QueryExecutor`;
+/// applications may publish their own `Arc<dyn OrgStore>` resource instead. Without
+/// either, they live in memory.
+#[async_trait]
+pub trait OrgStore: Send + Sync {
+    /// Store a new organization together with its first owner
+    async fn insert(&self, org: Org, owner: Membership) -> Result<(), OrgStoreError>;
+
+    async fn get(&self, slug: &str) -> anyhow::Result<Option<Org>>;
+
+    /// The page of organizations matching `query`, oldest first
+    async fn list(&self, query: &OrgQuery) -> anyhow::Result<Page<Org>>;
+
+    /// Remove an organization and its memberships, returning `false` if there is none
+    async fn delete(&self, slug: &str) -> anyhow::Result<bool>;
+
+    async fn membership(&self, org: &str, user_id: &str) -> anyhow::Result<Option<Membership>>;
+
+    /// The page of `org`'s memberships, earliest first
+    async fn members(&self, org: &str, pagination: Pagination) -> anyhow::Result<Page<Membership>>;
+
+    /// How many members of `org` are owners
+    async fn owner_count(&self, org: &str) -> anyhow::Result<u64>;
+
+    /// Add the membership, or replace the one of the same organization and user
+    async fn put_membership(&self, membership: Membership) -> anyhow::Result<()>;
+
+    /// Remove a membership, returning `false` if there is none
+    async fn remove_membership(&self, org: &str, user_id: &str) -> anyhow::Result<bool>;
+}
+
+#[derive(Debug, Default)]
+struct Data {
+    orgs: Vec<Org>,
+    memberships: Vec<Membership>,
+}
+
+/// Process-local store; organizations are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryOrgStore {
+    data: RwLock<Data>,
+}
+
+impl MemoryOrgStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl OrgStore for MemoryOrgStore {
+    async fn insert(&self, org: Org, owner: Membership) -> Result<(), OrgStoreError> {
+        let mut data = self
+            .data
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if data.orgs.iter().any(|stored| stored.slug == org.slug) {
+            return Err(OrgStoreError::DuplicateSlug(org.slug));
+        }
+        data.orgs.push(org);
+        data.memberships.push(owner);
+        Ok(())
+    }
+
+    async fn get(&self, slug: &str) -> anyhow::Result<Option<Org>> {
+        Ok(self
+            .data
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .orgs
+            .iter()
+            .find(|org| org.slug == slug)
+            .cloned())
+    }
+
+    async fn list(&self, query: &OrgQuery) -> anyhow::Result<Page<Org>> {
+        let data = self
+            .data
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut orgs: Vec<Org> = data
+            .orgs
+            .iter()
+            .filter(|org| {
+                query.member.as_ref().is_none_or(|user_id| {
+                    data.memberships.iter().any(|membership| {
+                        membership.org == org.slug && &membership.user_id == user_id
+                    })
+                })
+            })
+            .cloned()
+            .collect();
+        orgs.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        let total = orgs.len() as u64;
+        Ok(Page::new(
+            query.pagination.slice(orgs),
+            total,
+            query.pagination,
+        ))
+    }
+
+    async fn delete(&self, slug: &str) -> anyhow::Result<bool> {
+        let mut data = self
+            .data
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = data.orgs.len();
+        data.orgs.retain(|org| org.slug != slug);
+        data.memberships.retain(|membership| membership.org != slug);
+        Ok(data.orgs.len() < before)
+    }
+
+    async fn membership(&self, org: &str, user_id: &str) -> anyhow::Result<Option<Membership>> {
+        Ok(self
+            .data
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .memberships
+            .iter()
+            .find(|membership| membership.org == org && membership.user_id == user_id)
+            .cloned())
+    }
+
+    async fn members(&self, org: &str, pagination: Pagination) -> anyhow::Result<Page<Membership>> {
+        let mut members: Vec<Membership> = self
+            .data
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .memberships
+            .iter()
+            .filter(|membership| membership.org == org)
+            .cloned()
+            .collect();
+        members.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.user_id.cmp(&b.user_id))
+        });
+        let total = members.len() as u64;
+        Ok(Page::new(pagination.slice(members), total, pagination))
+    }
+
+    async fn owner_count(&self, org: &str) -> anyhow::Result<u64> {
+        Ok(self
+            .data
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .memberships
+            .iter()
+            .filter(|membership| membership.org == org && membership.role == Role::Owner)
+            .count() as u64)
+    }
+
+    async fn put_membership(&self, membership: Membership) -> anyhow::Result<()> {
+        let mut data = self
+            .data
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match data
+            .memberships
+            .iter_mut()
+            .find(|stored| stored.org == membership.org && stored.user_id == membership.user_id)
+        {
+            Some(stored) => *stored = membership,
+            None => data.memberships.push(membership),
+        }
+        Ok(())
+    }
+
+    async fn remove_membership(&self, org: &str, user_id: &str) -> anyhow::Result<bool> {
+        let mut data = self
+            .data
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = data.memberships.len();
+        data.memberships
+            .retain(|membership| membership.org != org || membership.user_id != user_id);
+        Ok(data.memberships.len() < before)
+    }
+}
+
+/// Columns every organization query returns, with the record id as the plain `id` string
+const SELECT_ORG: &str = "SELECT *, record::id(id) AS id FROM org";
+
+/// Columns every membership query returns; its record id is `[org, user_id]`
+const SELECT_MEMBERSHIP: &str = "SELECT org, user_id, role, created_at, updated_at FROM membership";
+
+/// Every field of a membership, keyed by its organization and user
+const SET_MEMBERSHIP: &str = "org = $org, user_id = $user_id, role = $role, \
+     created_at = <datetime> $created_at, updated_at = <datetime> $updated_at";
+
+/// Organizations in the `org` table and memberships in the `membership` table defined
+/// by the module's migrations
+pub struct SurrealOrgStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealOrgStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    async fn run(&self, surql: &str, vars: &[(&str, Value)]) -> anyhow::Result<Vec<Value>> {
+        let vars: Map<String, Value> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        self.db.query_with(surql, &vars).await
+    }
+}
+
+/// Fields of `value` bound by name
+fn fields(value: &impl Serialize) -> anyhow::Result<Map<String, Value>> {
+    match serde_json::to_value(value).context("failed to serialize record")? {
+        Value::Object(fields) => Ok(fields),
+        _ => unreachable!("organizations and memberships serialize as objects"),
+    }
+}
+
+#[async_trait]
+impl OrgStore for SurrealOrgStore {
+    async fn insert(&self, org: Org, owner: Membership) -> Result<(), OrgStoreError> {
+        let mut vars = fields(&owner)?;
+        vars.extend(fields(&org)?);
+        let surql = format!(
+            "BEGIN TRANSACTION;\n\
+             CREATE type::thing('org', $id) SET slug = $slug, name = $name, \
+                 created_at = <datetime> $created_at, updated_at = <datetime> $updated_at \
+                 RETURN NONE;\n\
+             CREATE type::thing('membership', [$org, $user_id]) SET {} RETURN NONE;\n\
+             COMMIT TRANSACTION;",
+            SET_MEMBERSHIP
+        );
+        self.db.query_with(&surql, &vars).await.map_err(|error| {
+            match atlas_db::unique_index_violation(&error) {
+                Some(index) if index == SLUG_INDEX => OrgStoreError::DuplicateSlug(org.slug),
+                _ => OrgStoreError::Store(error),
+            }
+        })?;
+        Ok(())
+    }
+
+    async fn get(&self, slug: &str) -> anyhow::Result<Option<Org>> {
+        let results = self
+            .run(
+                &format!("{} WHERE slug = $slug;", SELECT_ORG),
+                &[("slug", json!(slug))],
+            )
+            .await
+            .context("failed to read organization")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn list(&self, query: &OrgQuery) -> anyhow::Result<Page<Org>> {
+        let mut vars = vec![
+            ("limit", json!(query.pagination.limit())),
+            ("start", json!(query.pagination.offset())),
+        ];
+        let filter = match &query.member {
+            Some(user_id) => {
+                vars.push(("member", json!(user_id)));
+                "WHERE slug IN (SELECT VALUE org FROM membership WHERE user_id = $member)"
+            }
+            None => "",
+        };
+        let surql = format!(
+            "{select} {filter} ORDER BY created_at ASC LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM org {filter} GROUP ALL;",
+            select = SELECT_ORG,
+            filter = filter,
+        );
+        let mut results = self
+            .run(&surql, &vars)
+            .await
+            .context("failed to list organizations")?
+            .into_iter();
+        let orgs = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(orgs, total, query.pagination))
+    }
+
+    async fn delete(&self, slug: &str) -> anyhow::Result<bool> {
+        let results = self
+            .run(
+                "BEGIN TRANSACTION;\n\
+                 DELETE membership WHERE org = $slug;\n\
+                 DELETE org WHERE slug = $slug RETURN BEFORE;\n\
+                 COMMIT TRANSACTION;",
+                &[("slug", json!(slug))],
+            )
+            .await
+            .context("failed to delete organization")?;
+        Ok(changed(results.last()))
+    }
+
+    async fn membership(&self, org: &str, user_id: &str) -> anyhow::Result<Option<Membership>> {
+        let results = self
+            .run(
+                &format!(
+                    "{} WHERE id = type::thing('membership', [$org, $user_id]);",
+                    SELECT_MEMBERSHIP
+                ),
+                &[("org", json!(org)), ("user_id", json!(user_id))],
+            )
+            .await
+            .context("failed to read membership")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn members(&self, org: &str, pagination: Pagination) -> anyhow::Result<Page<Membership>> {
+        let surql = format!(
+            "{} WHERE org = $org ORDER BY created_at ASC LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM membership WHERE org = $org GROUP ALL;",
+            SELECT_MEMBERSHIP
+        );
+        let mut results = self
+            .run(
+                &surql,
+                &[
+                    ("org", json!(org)),
+                    ("limit", json!(pagination.limit())),
+                    ("start", json!(pagination.offset())),
+                ],
+            )
+            .await
+            .context("failed to list members")?
+            .into_iter();
+        let members = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(members, total, pagination))
+    }
+
+    async fn owner_count(&self, org: &str) -> anyhow::Result<u64> {
+        let results = self
+            .run(
+                "SELECT count() AS total FROM membership \
+                 WHERE org = $org AND role = 'owner' GROUP ALL;",
+                &[("org", json!(org))],
+            )
+            .await
+            .context("failed to count owners")?;
+        Ok(atlas_db::records::<Total>(results.into_iter().next())?
+            .first()
+            .map_or(0, |count| count.total))
+    }
+
+    async fn put_membership(&self, membership: Membership) -> anyhow::Result<()> {
+        let surql = format!(
+            "UPSERT type::thing('membership', [$org, $user_id]) SET {} RETURN NONE;",
+            SET_MEMBERSHIP
+        );
+        self.db
+            .query_with(&surql, &fields(&membership)?)
+            .await
+            .context("failed to store membership")?;
+        Ok(())
+    }
+
+    async fn remove_membership(&self, org: &str, user_id: &str) -> anyhow::Result<bool> {
+        let results = self
+            .run(
+                "DELETE type::thing('membership', [$org, $user_id]) RETURN BEFORE;",
+                &[("org", json!(org)), ("user_id", json!(user_id))],
+            )
+            .await
+            .context("failed to remove membership")?;
+        Ok(changed(results.first()))
+    }
+}
+
+/// Row of a `count()` query
+#[derive(serde::Deserialize)]
+struct Total {
+    total: u64,
+}
+
+/// Whether a statement changed any record
+fn changed(result: Option<&Value>) -> bool {
+    matches!(result, Some(Value::Array(records)) if !records.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn org(id: &str, slug: &str) -> Org {
+        Org {
+            id: id.to_string(),
+            slug: slug.to_string(),
+            name: "Acme".to_string(),
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    fn membership(org: &str, user_id: &str, role: Role) -> Membership {
+        Membership {
+            org: org.to_string(),
+            user_id: user_id.to_string(),
+            role,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_memberships_with_their_org() {
+        let store = MemoryOrgStore::new();
+        store
+            .insert(
+                org("org_1", "acme"),
+                membership("acme", "user_1", Role::Owner),
+            )
+            .await
+            .unwrap();
+        store
+            .insert(
+                org("org_2", "globex"),
+                membership("globex", "user_2", Role::Owner),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .insert(
+                    org("org_3", "acme"),
+                    membership("acme", "user_2", Role::Owner)
+                )
+                .await,
+            Err(OrgStoreError::DuplicateSlug(_))
+        ));
+
+        store
+            .put_membership(membership("acme", "user_2", Role::Member))
+            .await
+            .unwrap();
+        store
+            .put_membership(membership("acme", "user_2", Role::Admin))
+            .await
+            .unwrap();
+        let members = store.members("acme", Pagination::default()).await.unwrap();
+        assert_eq!(members.total, 2);
+        assert_eq!(store.owner_count("acme").await.unwrap(), 1);
+        let orgs = store
+            .list(&OrgQuery {
+                member: Some("user_2".to_string()),
+                ..OrgQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(orgs.total, 2);
+
+        assert!(store.delete("acme").await.unwrap());
+        assert!(store.membership("acme", "user_2").await.unwrap().is_none());
+        assert!(store
+            .membership("globex", "user_2")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    struct Recording {
+        queries: Mutex<Vec<(String, Map<String, Value>)>>,
+    }
+
+    #[async_trait]
+    impl QueryExecutor for Recording {
+        async fn query_with(
+            &self,
+            surql: &str,
+            vars: &Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            self.queries
+                .lock()
+                .unwrap()
+                .push((surql.to_string(), vars.clone()));
+            anyhow::bail!(
+                "Database index `org_slug_unique` already contains 'acme', with record `org:org_1`"
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_creates_org_and_owner_together() {
+        let db = Arc::new(Recording {
+            queries: Mutex::new(Vec::new()),
+        });
+        let store = SurrealOrgStore::new(db.clone());
+
+        let error = store
+            .insert(
+                org("org_2", "acme"),
+                membership("acme", "user_1", Role::Owner),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, OrgStoreError::DuplicateSlug(slug) if slug == "acme"));
+
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert!(surql.starts_with("BEGIN TRANSACTION;"));
+        assert!(surql.contains("CREATE type::thing('membership', [$org, $user_id])"));
+        assert_eq!(vars["id"], "org_2");
+        assert_eq!(vars["org"], "acme");
+        assert_eq!(vars["role"], "owner");
+    }
+}
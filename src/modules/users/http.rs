@@ -0,0 +1,380 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use atlas_authz::guard::RequirePermission;
+use atlas_http::error::AppError;
+use atlas_http::pagination::{Page, Pagination, Sort};
+
+use super::models::{CreateUser, RegisterUser, UpdateUser, User};
+use super::password;
+use super::service::Users;
+use super::store::{UserQuery, UserStoreError, DEFAULT_SORT, SORT_FIELDS};
+
+atlas_authz::permission!(
+    /// List and look up user accounts
+    pub UsersRead = "users:read"
+);
+atlas_authz::permission!(
+    /// Create, edit and delete user accounts other than by self-registration
+    pub UsersWrite = "users:write"
+);
+
+impl From<UserStoreError> for AppError {
+    fn from(error: UserStoreError) -> Self {
+        match error {
+            UserStoreError::DuplicateEmail(email) => AppError::conflict(
+                vec![json!({ "field": "email", "error": "taken" })],
+                format!("a user with email '{}' already exists", email),
+            ),
+            UserStoreError::Store(error) => AppError::Internal(error),
+        }
+    }
+}
+
+/// Whether `email` looks deliverable: one `@` between a local part and a dotted domain,
+/// without whitespace
+///
+/// Deliberately loose; only a confirmation email proves an address works.
+pub(crate) fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && domain
+            .split_once('.')
+            .is_some_and(|(host, rest)| !host.is_empty() && !rest.is_empty())
+        && !domain.ends_with('.')
+}
+
+/// Validation details for the email and name being written; `None` fields are unchanged
+fn profile_errors(email: Option<&str>, name: Option<&str>) -> Vec<serde_json::Value> {
+    let mut details = Vec::new();
+    match email.map(str::trim) {
+        Some("") => details.push(json!({ "field": "email", "error": "required" })),
+        Some(email) if !is_email(email) => {
+            details.push(json!({ "field": "email", "error": "invalid" }))
+        }
+        _ => {}
+    }
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        details.push(json!({ "field": "name", "error": "required" }));
+    }
+    details
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::not_found(format!("no user '{}'", id))
+}
+
+/// Query parameters of `GET /`, besides pagination
+#[derive(Debug, Deserialize)]
+struct ListUsers {
+    email: Option<String>,
+    sort: Option<String>,
+}
+
+async fn list_users(
+    State(users): State<Arc<Users>>,
+    _: RequirePermission<UsersRead>,
+    Query(params): Query<ListUsers>,
+    pagination: Pagination,
+) -> Result<Json<Page<User>>, AppError> {
+    let query = UserQuery {
+        email: params.email.filter(|email| !email.trim().is_empty()),
+        sort: Sort::parse(params.sort.as_deref(), SORT_FIELDS, DEFAULT_SORT)?,
+        pagination,
+    };
+    Ok(Json(users.list(&query).await?))
+}
+
+async fn create_user(
+    State(users): State<Arc<Users>>,
+    _: RequirePermission<UsersWrite>,
+    Json(request): Json<CreateUser>,
+) -> Result<(StatusCode, Json<User>), AppError> {
+    let details = profile_errors(Some(&request.email), Some(&request.name));
+    if !details.is_empty() {
+        return Err(AppError::validation(
+            details,
+            "a valid email and a name are required",
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(users.create(request).await?)))
+}
+
+async fn register_user(
+    State(users): State<Arc<Users>>,
+    Json(request): Json<RegisterUser>,
+) -> Result<(StatusCode, Json<User>), AppError> {
+    let mut details = profile_errors(Some(&request.email), Some(&request.name));
+    let length = request.password.chars().count();
+    if !(password::MIN_LENGTH..=password::MAX_LENGTH).contains(&length) {
+        details.push(json!({ "field": "password", "error": "length" }));
+    }
+    if !details.is_empty() {
+        return Err(AppError::validation(
+            details,
+            format!(
+                "a valid email, a name, and a password of {} to {} characters are required",
+                password::MIN_LENGTH,
+                password::MAX_LENGTH
+            ),
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(users.register(request).await?)))
+}
+
+async fn get_user(
+    State(users): State<Arc<Users>>,
+    _: RequirePermission<UsersRead>,
+    Path(id): Path<String>,
+) -> Result<Json<User>, AppError> {
+    users
+        .get(&id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+async fn update_user(
+    State(users): State<Arc<Users>>,
+    _: RequirePermission<UsersWrite>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateUser>,
+) -> Result<Json<User>, AppError> {
+    let details = profile_errors(request.email.as_deref(), request.name.as_deref());
+    if !details.is_empty() {
+        return Err(AppError::validation(
+            details,
+            "email must be valid and name may not be blank",
+        ));
+    }
+    users
+        .update(&id, request)
+        .await?
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+async fn delete_user(
+    State(users): State<Arc<Users>>,
+    _: RequirePermission<UsersWrite>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if users.delete(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(&id))
+    }
+}
+
+/// Health check endpoint
+async fn health_check() -> &'static str {
+    "users module is healthy"
+}
+
+/// CRUD endpoints over `users`, guarded by `users:read` and `users:write`, plus the open
+/// self-registration endpoint and the module health check
+pub fn routes(users: Arc<Users>) -> Router {
+    Router::new()
+        .route("/", get(list_users).post(create_user))
+        .route("/register", post(register_user))
+        .route(
+            "/{id}",
+            get(get_user).patch(update_user).delete(delete_user),
+        )
+        .with_state(users)
+        .route("/health", get(health_check))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::users::store::MemoryUserStore;
+    use atlas_authz::testing::{as_subject, resources, with_context};
+    use atlas_http::testing::{request, send};
+    use atlas_kernel::clock::SystemClock;
+    use axum::body::Body;
+    use serde_json::Value;
+
+    /// A request from a member of the `staff` role, which holds both permissions
+    fn staff(method: &str, uri: &str, body: Value) -> axum::http::Request<Body> {
+        as_subject(request(method, uri, body), "user_staff")
+    }
+
+    #[tokio::test]
+    async fn test_crud_endpoints() {
+        let users = Arc::new(Users::new(
+            Arc::new(MemoryUserStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let router = with_context(
+            Router::new().nest("/api/users", routes(users)),
+            resources("p, staff, users, read\np, staff, users, write\ng, user_staff, staff"),
+        );
+        let ann = json!({ "email": "ann@example.com", "name": "Ann Lee" });
+
+        let (status, _) = send(&router, request("GET", "/api/users", Value::Null)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_ann = as_subject(request("POST", "/api/users", ann.clone()), "user_ann");
+        let (status, _) = send(&router, as_ann).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, created) = send(&router, staff("POST", "/api/users", ann.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/api/users/{}", created["id"].as_str().unwrap());
+
+        let (status, error) = send(&router, staff("POST", "/api/users", ann)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["details"][0]["field"], "email");
+
+        let (status, error) = send(
+            &router,
+            staff("POST", "/api/users", json!({ "email": "", "name": "Bob" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"][0]["field"], "email");
+
+        let (status, updated) =
+            send(&router, staff("PATCH", &uri, json!({ "bio": "Reads" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["bio"], "Reads");
+        assert_eq!(updated["name"], "Ann Lee");
+
+        let (status, fetched) = send(&router, staff("GET", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched, updated);
+
+        send(
+            &router,
+            staff(
+                "POST",
+                "/api/users",
+                json!({ "email": "bob@example.com", "name": "Bob" }),
+            ),
+        )
+        .await;
+        let (status, listed) =
+            send(&router, staff("GET", "/api/users?sort=-name", Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["total"], 2);
+        assert_eq!(listed["items"][0]["name"], "Bob");
+        let (_, listed) = send(
+            &router,
+            staff(
+                "GET",
+                "/api/users?email=ANN@example.com&per_page=1",
+                Value::Null,
+            ),
+        )
+        .await;
+        assert_eq!(listed["items"][0]["id"], created["id"]);
+        assert_eq!(listed["total"], 1);
+
+        let (status, _) = send(&router, staff("DELETE", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, staff("GET", &uri, Value::Null)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_served_routes_authenticate_api_keys_and_sessions() {
+        let app = atlas_authz::testing::TestApp::new(
+            vec![Arc::new(crate::modules::users::UsersModule::new())],
+            "p, staff, users, read\np, staff, users, write\ng, user_staff, staff",
+        )
+        .await;
+        let ann = json!({ "email": "ann@example.com", "name": "Ann Lee" });
+
+        let (status, _) = send(&app.router, request("POST", "/api/users", ann.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let as_ann = app
+            .with_api_key(request("POST", "/api/users", ann.clone()), "user_ann")
+            .await;
+        let (status, _) = send(&app.router, as_ann).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let create = app
+            .with_api_key(request("POST", "/api/users", ann), "user_staff")
+            .await;
+        let (status, created) = send(&app.router, create).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let uri = format!("/api/users/{}", created["id"].as_str().unwrap());
+        let update = app
+            .with_session(
+                request("PATCH", &uri, json!({ "bio": "Reads" })),
+                "user_staff",
+            )
+            .await;
+        let (status, updated) = send(&app.router, update).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["bio"], "Reads");
+    }
+
+    #[test]
+    fn test_email_format() {
+        assert!(is_email("ann@example.com"));
+        assert!(is_email("ann+books@mail.example.co.uk"));
+        for invalid in [
+            "ann",
+            "@example.com",
+            "ann@example",
+            "ann@@example.com",
+            "a nn@example.com",
+            "ann@example.",
+        ] {
+            assert!(!is_email(invalid), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_endpoint() {
+        let users = Arc::new(Users::new(
+            Arc::new(MemoryUserStore::new()),
+            Arc::new(SystemClock),
+        ));
+        let router = Router::new().nest("/api/users", routes(users));
+        let ann =
+            json!({ "email": "Ann@example.com", "name": "Ann Lee", "password": "correct horse" });
+
+        let (status, registered) =
+            send(&router, request("POST", "/api/users/register", ann.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(registered["email"], "ann@example.com");
+        assert!(registered.get("password").is_none());
+        assert!(registered.get("password_hash").is_none());
+
+        let (status, _) = send(&router, request("POST", "/api/users/register", ann)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, error) = send(
+            &router,
+            request(
+                "POST",
+                "/api/users/register",
+                json!({ "email": "bob", "name": "Bob", "password": "short" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let fields: Vec<&str> = error["error"]["details"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|detail| detail["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, ["email", "password"]);
+    }
+}
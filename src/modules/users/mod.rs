@@ -1,14 +1,37 @@
+pub mod http;
+pub mod models;
+pub mod password;
+pub mod service;
+pub mod store;
+
+use std::sync::{Arc, OnceLock};
+
 use async_trait::async_trait;
-use atlas_kernel::{InitCtx, Migration, Module};
-use axum::{routing::get, Router};
+use atlas_authz::{CredentialVerifier, PasswordAccounts};
+use atlas_http::pagination;
+use atlas_kernel::{InitCtx, Migration, Module, RouteSecurity, SchemaExample, SecurityScheme};
+use axum::Router;
 use serde_json::json;
 
-/// Users module implementation for testing dynamic OpenAPI collection
-pub struct UsersModule;
+use crate::utils::mail;
+use service::{UserPasswordAccounts, Users};
+use store::{MemoryUserStore, SurrealUserStore, UserStore};
+
+/// User accounts backed by the `user` table
+///
+/// Publishes `Users` as a shared resource, as the `CredentialVerifier` behind session
+/// login unless LDAP login is enabled, and as the `PasswordAccounts` password resets act
+/// on, emailing tokens through the application's `Arc<dyn Mailer>`.
+#[derive(Default)]
+pub struct UsersModule {
+    users: OnceLock<Arc<Users>>,
+}
 
 impl UsersModule {
     pub const fn new() -> Self {
-        Self
+        Self {
+            users: OnceLock::new(),
+        }
     }
 }
 
@@ -18,7 +41,38 @@ impl Module for UsersModule {
         "users"
     }
 
-    async fn init(&self, ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    fn version(&self) -> Option<&'static str> {
+        Some(env!("CARGO_PKG_VERSION"))
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        Some("User accounts and profiles")
+    }
+
+    async fn init(&self, ctx: &InitCtx) -> anyhow::Result<()> {
+        let store = atlas_db::select_store::<dyn UserStore>(
+            &ctx.resources,
+            "user",
+            |db| Arc::new(SurrealUserStore::new(db)),
+            || Arc::new(MemoryUserStore::new()),
+        );
+        let users = Arc::new(Users::new(store, ctx.clock.clone()));
+        ctx.resources.insert_arc(users.clone());
+        if ctx.settings.auth.ldap.enabled {
+            tracing::debug!("auth.ldap is enabled; LDAP verifies login credentials");
+        } else {
+            ctx.resources
+                .insert::<Arc<dyn CredentialVerifier>>(users.clone());
+        }
+        ctx.resources
+            .insert::<Arc<dyn PasswordAccounts>>(Arc::new(UserPasswordAccounts::new(
+                users.clone(),
+                mail::mailer(&ctx.resources, self.name()),
+            )));
+        self.users
+            .set(users)
+            .map_err(|_| anyhow::anyhow!("users module initialized twice"))?;
+
         tracing::info!(
             module = self.name(),
             environment = ?ctx.settings.environment,
@@ -28,85 +82,180 @@ impl Module for UsersModule {
     }
 
     fn routes(&self) -> Router {
-        Router::new()
-            .route("/", get(list_users))
-            .route("/health", get(health_check))
-            .route("/profile", get(get_profile))
+        match self.users.get() {
+            Some(users) => http::routes(users.clone()),
+            None => Router::new(),
+        }
+    }
+
+    /// Everything but self-registration needs `users:read` or `users:write`
+    fn security(&self) -> Vec<RouteSecurity> {
+        let guarded = |method, path, permission| RouteSecurity {
+            method,
+            path,
+            schemes: &[SecurityScheme::ApiKey, SecurityScheme::Session],
+            permission: Some(permission),
+        };
+        vec![
+            guarded("get", "/", "users:read"),
+            guarded("post", "/", "users:write"),
+            guarded("get", "/{id}", "users:read"),
+            guarded("patch", "/{id}", "users:write"),
+            guarded("delete", "/{id}", "users:write"),
+        ]
     }
 
     fn openapi(&self) -> Option<serde_json::Value> {
+        let error = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ErrorResponse" }
+                    }
+                }
+            })
+        };
+        let user = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/User" }
+                    }
+                }
+            })
+        };
+        let mut list_parameters = vec![json!({
+            "name": "email",
+            "in": "query",
+            "description": "Only the user with this email, compared case-insensitively",
+            "schema": { "type": "string", "format": "email" }
+        })];
+        list_parameters.push(pagination::openapi::sort_parameter(
+            store::SORT_FIELDS,
+            store::DEFAULT_SORT,
+        ));
+        list_parameters.extend(pagination::openapi::parameters());
+        let id = json!({
+            "name": "id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" }
+        });
+        let body = |schema: &str| {
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+                    }
+                }
+            })
+        };
+        let text = |description: &str| json!({ "type": "string", "description": description });
         Some(json!({
+            "tags": [
+                {
+                    "name": "Users",
+                    "description": "Users — accounts and profiles",
+                    "x-order": 2
+                }
+            ],
             "paths": {
                 "/": {
                     "get": {
                         "summary": "List users",
                         "tags": ["Users"],
+                        "parameters": list_parameters,
                         "responses": {
                             "200": {
-                                "description": "List of users",
+                                "description": "One page of the matching users",
                                 "content": {
                                     "application/json": {
-                                        "schema": {
-                                            "type": "array",
-                                            "items": {
-                                                "$ref": "#/components/schemas/User"
-                                            }
-                                        }
+                                        "schema": pagination::openapi::page_schema(
+                                            json!({ "$ref": "#/components/schemas/User" })
+                                        )
                                     }
                                 }
                             },
-                            "500": {
-                                "description": "Internal server error",
-                                "content": {
-                                    "application/json": {
-                                        "schema": {
-                                            "$ref": "#/components/schemas/ErrorResponse"
-                                        }
-                                    }
-                                }
-                            }
+                            "422": error("Invalid page, per_page, or sort"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "post": {
+                        "summary": "Create a user",
+                        "tags": ["Users"],
+                        "requestBody": body("CreateUser"),
+                        "responses": {
+                            "201": user("The new user"),
+                            "409": error("Another user has this email"),
+                            "422": error("Email or name is blank"),
+                            "500": error("Internal server error")
                         }
                     }
                 },
-                "/health": {
+                "/register": {
+                    "post": {
+                        "summary": "Register a user",
+                        "description": "Creates a user with a password, which is stored only as an Argon2 hash and never returned.",
+                        "tags": ["Users"],
+                        "requestBody": body("RegisterUser"),
+                        "responses": {
+                            "201": user("The registered user"),
+                            "409": error("Another user has this email"),
+                            "422": error("Invalid email, blank name, or password of the wrong length"),
+                            "500": error("Internal server error")
+                        }
+                    }
+                },
+                "/{id}": {
                     "get": {
-                        "summary": "Users health check",
+                        "summary": "Get a user",
                         "tags": ["Users"],
+                        "parameters": [id],
                         "responses": {
-                            "200": {
-                                "description": "OK",
-                                "content": {
-                                    "text/plain": {
-                                        "schema": {
-                                            "type": "string"
-                                        }
-                                    }
-                                }
-                            }
+                            "200": user("The user"),
+                            "404": error("No user with that id"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "patch": {
+                        "summary": "Update a user",
+                        "description": "Changes the fields present in the body; an empty `bio` or `avatar_url` clears it.",
+                        "tags": ["Users"],
+                        "parameters": [id],
+                        "requestBody": body("UpdateUser"),
+                        "responses": {
+                            "200": user("The updated user"),
+                            "404": error("No user with that id"),
+                            "409": error("Another user has this email"),
+                            "422": error("Email or name is blank"),
+                            "500": error("Internal server error")
+                        }
+                    },
+                    "delete": {
+                        "summary": "Delete a user",
+                        "tags": ["Users"],
+                        "parameters": [id],
+                        "responses": {
+                            "204": { "description": "User deleted" },
+                            "404": error("No user with that id"),
+                            "500": error("Internal server error")
                         }
                     }
                 },
-                "/profile": {
+                "/health": {
                     "get": {
-                        "summary": "Get user profile",
+                        "summary": "Users health check",
                         "tags": ["Users"],
                         "responses": {
                             "200": {
-                                "description": "User profile",
-                                "content": {
-                                    "application/json": {
-                                        "schema": {
-                                            "$ref": "#/components/schemas/UserProfile"
-                                        }
-                                    }
-                                }
-                            },
-                            "404": {
-                                "description": "User not found",
+                                "description": "OK",
                                 "content": {
-                                    "application/json": {
+                                    "text/plain": {
                                         "schema": {
-                                            "$ref": "#/components/schemas/ErrorResponse"
+                                            "type": "string"
                                         }
                                     }
                                 }
@@ -120,74 +269,134 @@ impl Module for UsersModule {
                     "User": {
                         "type": "object",
                         "properties": {
-                            "id": {
-                                "type": "string",
-                                "description": "Unique identifier for the user"
-                            },
+                            "id": text("Unique identifier for the user"),
                             "email": {
                                 "type": "string",
                                 "format": "email",
-                                "description": "User's email address"
+                                "description": "User's email address, lowercased"
                             },
-                            "name": {
+                            "name": text("User's full name"),
+                            "bio": text("User's biography"),
+                            "avatar_url": {
                                 "type": "string",
-                                "description": "User's full name"
+                                "format": "uri",
+                                "description": "URL to user's avatar image"
                             },
                             "created_at": {
                                 "type": "string",
                                 "format": "date-time",
                                 "description": "When the user was created"
+                            },
+                            "updated_at": {
+                                "type": "string",
+                                "format": "date-time",
+                                "description": "When the user was last updated"
                             }
                         },
-                        "required": ["id", "email", "name", "created_at"]
+                        "required": ["id", "email", "name", "created_at", "updated_at"]
                     },
-                    "UserProfile": {
+                    "CreateUser": {
                         "type": "object",
                         "properties": {
-                            "id": {
+                            "email": {
                                 "type": "string",
-                                "description": "Unique identifier for the user"
+                                "format": "email",
+                                "description": "User's email address; must be unique"
                             },
+                            "name": text("User's full name"),
+                            "bio": text("User's biography"),
+                            "avatar_url": {
+                                "type": "string",
+                                "format": "uri",
+                                "description": "URL to user's avatar image"
+                            }
+                        },
+                        "required": ["email", "name"]
+                    },
+                    "RegisterUser": {
+                        "type": "object",
+                        "properties": {
                             "email": {
                                 "type": "string",
                                 "format": "email",
-                                "description": "User's email address"
+                                "description": "User's email address; must be unique"
                             },
-                            "name": {
+                            "name": text("User's full name"),
+                            "password": {
                                 "type": "string",
-                                "description": "User's full name"
-                            },
-                            "bio": {
+                                "format": "password",
+                                "minLength": password::MIN_LENGTH,
+                                "maxLength": password::MAX_LENGTH,
+                                "writeOnly": true
+                            }
+                        },
+                        "required": ["email", "name", "password"]
+                    },
+                    "UpdateUser": {
+                        "type": "object",
+                        "properties": {
+                            "email": {
                                 "type": "string",
-                                "description": "User's biography"
+                                "format": "email",
+                                "description": "User's email address; must be unique"
                             },
+                            "name": text("User's full name"),
+                            "bio": text("User's biography"),
                             "avatar_url": {
                                 "type": "string",
                                 "format": "uri",
                                 "description": "URL to user's avatar image"
-                            },
-                            "created_at": {
-                                "type": "string",
-                                "format": "date-time",
-                                "description": "When the user was created"
-                            },
-                            "updated_at": {
-                                "type": "string",
-                                "format": "date-time",
-                                "description": "When the user was last updated"
                             }
-                        },
-                        "required": ["id", "email", "name", "created_at"]
+                        }
                     }
                 }
             }
         }))
     }
 
+    fn openapi_examples(&self) -> Vec<SchemaExample> {
+        vec![
+            SchemaExample::new(
+                "User",
+                json!({
+                    "id": "user_01HV6Y5J8Q4ZK3X2T9R7M1N0PA",
+                    "email": "john@example.com",
+                    "name": "John Doe",
+                    "bio": "Software developer passionate about Rust",
+                    "avatar_url": "https://example.com/avatars/john.jpg",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-15T10:30:00Z"
+                }),
+            ),
+            SchemaExample::new(
+                "CreateUser",
+                json!({
+                    "email": "john@example.com",
+                    "name": "John Doe"
+                }),
+            ),
+            SchemaExample::new(
+                "RegisterUser",
+                json!({
+                    "email": "john@example.com",
+                    "name": "John Doe",
+                    "password": "correct horse battery staple"
+                }),
+            ),
+            SchemaExample::new(
+                "UpdateUser",
+                json!({
+                    "bio": "Software developer passionate about Rust"
+                }),
+            ),
+        ]
+    }
+
     fn migrations(&self) -> Vec<Migration> {
-        vec![Migration {
-            id: "001_init",
-            up: r#"
+        vec![
+            Migration {
+                id: "001_init",
+                up: r#"
                 DEFINE TABLE user SCHEMAFULL;
                 DEFINE FIELD email     ON user TYPE string ASSERT $value != "";
                 DEFINE FIELD name      ON user TYPE string ASSERT $value != "";
@@ -195,10 +404,34 @@ impl Module for UsersModule {
                 DEFINE FIELD avatar_url ON user TYPE string;
                 DEFINE INDEX user_email_unique ON user FIELDS email UNIQUE;
                 "#,
-        }]
+                down: Some("REMOVE TABLE user;"),
+            },
+            Migration {
+                id: "002_profile_fields",
+                up: r#"
+                DEFINE FIELD OVERWRITE bio        ON user TYPE option<string>;
+                DEFINE FIELD OVERWRITE avatar_url ON user TYPE option<string>;
+                DEFINE FIELD created_at ON user TYPE datetime VALUE $before OR $value;
+                DEFINE FIELD updated_at ON user TYPE datetime;
+                "#,
+                down: Some(
+                    r#"
+                REMOVE FIELD updated_at ON user;
+                REMOVE FIELD created_at ON user;
+                DEFINE FIELD OVERWRITE bio        ON user TYPE string;
+                DEFINE FIELD OVERWRITE avatar_url ON user TYPE string;
+                "#,
+                ),
+            },
+            Migration {
+                id: "003_password_hash",
+                up: "DEFINE FIELD password_hash ON user TYPE option<string>;",
+                down: Some("REMOVE FIELD password_hash ON user;"),
+            },
+        ]
     }
 
-    async fn start(&self, _ctx: &InitCtx<'_>) -> anyhow::Result<()> {
+    async fn start(&self, _ctx: &InitCtx) -> anyhow::Result<()> {
         tracing::info!(module = self.name(), "users module started");
         Ok(())
     }
@@ -209,45 +442,9 @@ impl Module for UsersModule {
     }
 }
 
-/// Health check endpoint
-async fn health_check() -> &'static str {
-    "users module is healthy"
-}
-
-/// List users endpoint (stub implementation)
-async fn list_users() -> axum::Json<Vec<serde_json::Value>> {
-    let users = vec![
-        json!({
-            "id": "user-1",
-            "email": "john@example.com",
-            "name": "John Doe",
-            "created_at": "2024-01-01T00:00:00Z"
-        }),
-        json!({
-            "id": "user-2",
-            "email": "jane@example.com",
-            "name": "Jane Smith",
-            "created_at": "2024-01-02T00:00:00Z"
-        }),
-    ];
-
-    axum::Json(users)
-}
-
-/// Get user profile endpoint (stub implementation)
-async fn get_profile() -> axum::Json<serde_json::Value> {
-    axum::Json(json!({
-        "id": "user-1",
-        "email": "john@example.com",
-        "name": "John Doe",
-        "bio": "Software developer passionate about Rust",
-        "avatar_url": "https://example.com/avatars/john.jpg",
-        "created_at": "2024-01-01T00:00:00Z",
-        "updated_at": "2024-01-15T10:30:00Z"
-    }))
-}
-
 /// Create a new instance of the users module
-pub fn create_module() -> std::sync::Arc<dyn Module> {
-    std::sync::Arc::new(UsersModule::new())
+pub fn create_module() -> Arc<dyn Module> {
+    Arc::new(UsersModule::new())
 }
+
+atlas_kernel::register_module!(create_module);
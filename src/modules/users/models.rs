@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// A user account as stored in the `user` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    /// Table-prefixed id such as `user_01HV6Y5J8Q4ZK3X2T9R7M1N0PA`
+    pub id: String,
+    /// Email address, lowercased; unique across users
+    pub email: String,
+    /// Full name
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    /// Argon2 PHC string of the password of registered users; never serialized
+    #[serde(default, skip_serializing)]
+    pub password_hash: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Request model for creating a user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUser {
+    pub email: String,
+    pub name: String,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+/// Request model for changing a user; omitted fields keep their value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateUser {
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+/// Request model for self-service registration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegisterUser {
+    pub email: String,
+    pub name: String,
+    pub password: String,
+}
@@ -0,0 +1,53 @@
+//! Password hashing for registered users
+//!
+//! Passwords are stored as Argon2id PHC strings, which carry their own salt and
+//! parameters, so hashes stay verifiable if the defaults change.
+
+use anyhow::anyhow;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// Shortest accepted password
+pub const MIN_LENGTH: usize = 8;
+/// Longest accepted password, bounding the work a single request can cause
+pub const MAX_LENGTH: usize = 128;
+
+/// PHC string for `password` under a fresh random salt
+///
+/// Deliberately slow; call it from `spawn_blocking` in request handlers.
+pub fn hash(password: &str) -> anyhow::Result<String> {
+    let mut salt = [0u8; 16];
+    getrandom::fill(&mut salt).map_err(|error| anyhow!("failed to generate salt: {}", error))?;
+    let salt = SaltString::encode_b64(&salt)
+        .map_err(|error| anyhow!("failed to encode salt: {}", error))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|error| anyhow!("failed to hash password: {}", error))
+}
+
+/// Whether `password` matches a hash produced by `hash`
+pub fn verify(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_are_salted_and_verifiable() {
+        let first = hash("correct horse").unwrap();
+        let second = hash("correct horse").unwrap();
+
+        assert!(first.starts_with("$argon2id$"));
+        assert_ne!(first, second);
+        assert!(verify("correct horse", &first));
+        assert!(!verify("wrong horse", &first));
+        assert!(!verify("correct horse", "not a hash"));
+    }
+}
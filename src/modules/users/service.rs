@@ -0,0 +1,374 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use atlas_authz::{CredentialVerifier, PasswordAccounts};
+use atlas_http::pagination::Page;
+use atlas_kernel::{id, Clock};
+
+use super::models::{CreateUser, RegisterUser, UpdateUser, User};
+use super::password;
+use super::store::{UserQuery, UserStore, UserStoreError};
+use crate::utils::mail::{Email, Mailer};
+
+/// Creates, reads, changes, and removes user accounts
+pub struct Users {
+    store: Arc<dyn UserStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Users {
+    pub fn new(store: Arc<dyn UserStore>, clock: Arc<dyn Clock>) -> Self {
+        Self { store, clock }
+    }
+
+    /// Store a new user; emails are compared lowercased
+    pub async fn create(&self, request: CreateUser) -> Result<User, UserStoreError> {
+        let user = self.new_user(&request.email, &request.name)?;
+        let user = User {
+            bio: non_empty(request.bio),
+            avatar_url: non_empty(request.avatar_url),
+            ..user
+        };
+        self.store.insert(user.clone()).await?;
+        tracing::info!(user_id = %user.id, "created user");
+        Ok(user)
+    }
+
+    /// Store a new user with a password, which is kept only as a hash
+    pub async fn register(&self, request: RegisterUser) -> Result<User, UserStoreError> {
+        let user = self.new_user(&request.email, &request.name)?;
+        let password = request.password;
+        let hash = tokio::task::spawn_blocking(move || password::hash(&password))
+            .await
+            .map_err(anyhow::Error::from)??;
+        let user = User {
+            password_hash: Some(hash),
+            ..user
+        };
+        self.store.insert(user.clone()).await?;
+        tracing::info!(user_id = %user.id, "registered user");
+        Ok(user)
+    }
+
+    fn new_user(&self, email: &str, name: &str) -> anyhow::Result<User> {
+        let now = self.now();
+        Ok(User {
+            id: id::prefixed_with("user", id::ulid_at(self.clock.now()))?,
+            email: normalize_email(email),
+            name: name.trim().to_string(),
+            bio: None,
+            avatar_url: None,
+            password_hash: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn get(&self, id: &str) -> anyhow::Result<Option<User>> {
+        self.store.get(id).await
+    }
+
+    /// The page of users matching `query`; its email filter is compared lowercased
+    pub async fn list(&self, query: &UserQuery) -> anyhow::Result<Page<User>> {
+        let query = UserQuery {
+            email: query.email.as_deref().map(normalize_email),
+            ..query.clone()
+        };
+        self.store.list(&query).await
+    }
+
+    /// Apply the fields present in `request`, returning `None` if there is no such user
+    ///
+    /// An empty `bio` or `avatar_url` clears it.
+    pub async fn update(
+        &self,
+        id: &str,
+        request: UpdateUser,
+    ) -> Result<Option<User>, UserStoreError> {
+        let Some(mut user) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+        if let Some(email) = request.email {
+            user.email = normalize_email(&email);
+        }
+        if let Some(name) = request.name {
+            user.name = name.trim().to_string();
+        }
+        if request.bio.is_some() {
+            user.bio = non_empty(request.bio);
+        }
+        if request.avatar_url.is_some() {
+            user.avatar_url = non_empty(request.avatar_url);
+        }
+        user.updated_at = self.now();
+
+        if !self.store.update(user.clone()).await? {
+            return Ok(None);
+        }
+        tracing::info!(user_id = %user.id, "updated user");
+        Ok(Some(user))
+    }
+
+    /// The user registered under `email`, compared lowercased
+    pub async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<User>> {
+        let page = self
+            .list(&UserQuery {
+                email: Some(email.to_string()),
+                ..UserQuery::default()
+            })
+            .await?;
+        Ok(page.items.into_iter().next())
+    }
+
+    /// The user registered under `email`, if `password` is theirs
+    ///
+    /// Users without a password never match; hashes are checked off the async runtime.
+    pub async fn authenticate(&self, email: &str, password: &str) -> anyhow::Result<Option<User>> {
+        if password.len() > password::MAX_LENGTH {
+            return Ok(None);
+        }
+        let Some(user) = self.find_by_email(email).await? else {
+            return Ok(None);
+        };
+        let Some(hash) = user.password_hash.clone() else {
+            return Ok(None);
+        };
+        let password = password.to_string();
+        let matches =
+            tokio::task::spawn_blocking(move || password::verify(&password, &hash)).await?;
+        Ok(matches.then_some(user))
+    }
+
+    /// Replace a user's password, returning `false` if there is no such user
+    pub async fn set_password(&self, id: &str, password: &str) -> anyhow::Result<bool> {
+        let Some(user) = self.store.get(id).await? else {
+            return Ok(false);
+        };
+        let password = password.to_string();
+        let hash = tokio::task::spawn_blocking(move || password::hash(&password)).await??;
+        let user = User {
+            password_hash: Some(hash),
+            updated_at: self.now(),
+            ..user
+        };
+        if !self.store.update(user).await? {
+            return Ok(false);
+        }
+        tracing::info!(user_id = %id, "changed password");
+        Ok(true)
+    }
+
+    /// Remove a user, returning `false` if there is none
+    pub async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let deleted = self.store.delete(id).await?;
+        if deleted {
+            tracing::info!(user_id = %id, "deleted user");
+        }
+        Ok(deleted)
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from(self.clock.now())
+    }
+}
+
+/// Logs users in by email and password, as their user id
+#[async_trait]
+impl CredentialVerifier for Users {
+    async fn verify(&self, username: &str, password: &str) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .authenticate(username, password)
+            .await?
+            .map(|user| user.id))
+    }
+}
+
+/// The user accounts password resets act on, emailing reset tokens through `mailer`
+pub struct UserPasswordAccounts {
+    users: Arc<Users>,
+    mailer: Arc<dyn Mailer>,
+}
+
+impl UserPasswordAccounts {
+    pub fn new(users: Arc<Users>, mailer: Arc<dyn Mailer>) -> Self {
+        Self { users, mailer }
+    }
+}
+
+#[async_trait]
+impl PasswordAccounts for UserPasswordAccounts {
+    async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.users.find_by_email(email).await?.map(|user| user.id))
+    }
+
+    async fn send_reset(&self, _subject: &str, email: &str, token: &str) -> anyhow::Result<()> {
+        self.mailer
+            .send(Email {
+                to: email.to_string(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Someone asked to reset the password of your account. If it was you, \
+                     set a new one with this token:\n\n{}\n\n\
+                     Otherwise you can ignore this email.\n",
+                    token
+                ),
+            })
+            .await
+    }
+
+    async fn set_password(&self, subject: &str, password: &str) -> anyhow::Result<()> {
+        if !self.users.set_password(subject, password).await? {
+            anyhow::bail!("no user '{}'", subject);
+        }
+        Ok(())
+    }
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::users::store::MemoryUserStore;
+    use crate::utils::mail::LogMailer;
+    use atlas_kernel::clock::SystemClock;
+
+    fn users() -> Users {
+        Users::new(Arc::new(MemoryUserStore::new()), Arc::new(SystemClock))
+    }
+
+    fn create(email: &str) -> CreateUser {
+        CreateUser {
+            email: email.to_string(),
+            name: "Ann Lee".to_string(),
+            bio: Some("Reads a lot".to_string()),
+            avatar_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emails_are_unique_regardless_of_case() {
+        let users = users();
+        let ann = users.create(create(" Ann@Example.com")).await.unwrap();
+
+        assert!(ann.id.starts_with("user_"));
+        assert_eq!(ann.email, "ann@example.com");
+        assert!(matches!(
+            users.create(create("ANN@example.com")).await,
+            Err(UserStoreError::DuplicateEmail(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_registration_stores_only_a_password_hash() {
+        let users = users();
+        let registered = users
+            .register(RegisterUser {
+                email: "ann@example.com".to_string(),
+                name: "Ann Lee".to_string(),
+                password: "correct horse".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let stored = users.get(&registered.id).await.unwrap().unwrap();
+        let hash = stored.password_hash.unwrap();
+        assert!(password::verify("correct horse", &hash));
+        assert!(serde_json::to_value(&registered)
+            .unwrap()
+            .get("password_hash")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_passwords_log_in_and_can_be_reset() {
+        let users = Arc::new(users());
+        let ann = users
+            .register(RegisterUser {
+                email: "ann@example.com".to_string(),
+                name: "Ann Lee".to_string(),
+                password: "correct horse".to_string(),
+            })
+            .await
+            .unwrap();
+        let bob = users.create(create("bob@example.com")).await.unwrap();
+        let verifier: &dyn CredentialVerifier = users.as_ref();
+
+        assert_eq!(
+            verifier
+                .verify("Ann@Example.com", "correct horse")
+                .await
+                .unwrap(),
+            Some(ann.id.clone())
+        );
+        assert_eq!(
+            verifier
+                .verify("ann@example.com", "wrong horse")
+                .await
+                .unwrap(),
+            None
+        );
+        // Accounts created without a password cannot log in with any
+        assert_eq!(verifier.verify("bob@example.com", "").await.unwrap(), None);
+
+        let accounts = UserPasswordAccounts::new(users.clone(), Arc::new(LogMailer));
+        assert_eq!(
+            accounts.find_by_email("bob@example.com").await.unwrap(),
+            Some(bob.id.clone())
+        );
+        accounts
+            .set_password(&bob.id, "battery staple")
+            .await
+            .unwrap();
+        assert_eq!(
+            verifier
+                .verify("bob@example.com", "battery staple")
+                .await
+                .unwrap(),
+            Some(bob.id)
+        );
+        assert!(accounts
+            .set_password("user_missing", "battery staple")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_merges_present_fields() {
+        let users = users();
+        let ann = users.create(create("ann@example.com")).await.unwrap();
+
+        let updated = users
+            .update(
+                &ann.id,
+                UpdateUser {
+                    name: Some("Ann Smith".to_string()),
+                    bio: Some(String::new()),
+                    ..UpdateUser::default()
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(updated.name, "Ann Smith");
+        assert_eq!(updated.email, "ann@example.com");
+        assert_eq!(updated.bio, None);
+        assert_eq!(updated.created_at, ann.created_at);
+        assert!(users
+            .update("user_missing", UpdateUser::default())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
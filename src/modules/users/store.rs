@@ -0,0 +1,446 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use atlas_db::QueryExecutor;
+use atlas_http::pagination::{Page, Pagination, Sort};
+
+use super::models::User;
+
+/// Unique index on `user.email` defined by the module's migrations
+pub const EMAIL_INDEX: &str = "user_email_unique";
+
+/// Why a user could not be stored
+#[derive(Debug, thiserror::Error)]
+pub enum UserStoreError {
+    #[error("a user with email '{0}' already exists")]
+    DuplicateEmail(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Fields a user listing can be sorted by
+pub const SORT_FIELDS: &[&str] = &["name", "email", "created_at", "updated_at"];
+
+/// Oldest users first
+pub const DEFAULT_SORT: Sort = Sort::asc("created_at");
+
+/// Filters, order, and page of a user listing
+#[derive(Debug, Clone)]
+pub struct UserQuery {
+    /// Only the user with this lowercased email
+    pub email: Option<String>,
+    /// One of `SORT_FIELDS`
+    pub sort: Sort,
+    pub pagination: Pagination,
+}
+
+impl Default for UserQuery {
+    fn default() -> Self {
+        Self {
+            email: None,
+            sort: DEFAULT_SORT,
+            pagination: Pagination::default(),
+        }
+    }
+}
+
+/// Persistence for user accounts
+///
+/// The module stores users in SurrealDB through the application's `QueryExecutor`;
+/// applications may publish their own `Arc<dyn UserStore>` resource instead. Without
+/// either, users live in memory.
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    async fn insert(&self, user: User) -> Result<(), UserStoreError>;
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<User>>;
+
+    /// The page of users matching `query`, in its order
+    async fn list(&self, query: &UserQuery) -> anyhow::Result<Page<User>>;
+
+    /// Replace the stored user with the same id, returning `false` if there is none
+    async fn update(&self, user: User) -> Result<bool, UserStoreError>;
+
+    /// Remove a user, returning `false` if there is none
+    async fn delete(&self, id: &str) -> anyhow::Result<bool>;
+}
+
+/// Process-local store; users are lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryUserStore {
+    users: RwLock<Vec<User>>,
+}
+
+impl MemoryUserStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryUserStore {
+    async fn insert(&self, user: User) -> Result<(), UserStoreError> {
+        let mut users = self
+            .users
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if users.iter().any(|stored| stored.email == user.email) {
+            return Err(UserStoreError::DuplicateEmail(user.email));
+        }
+        users.push(user);
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<User>> {
+        Ok(self
+            .users
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|user| user.id == id)
+            .cloned())
+    }
+
+    async fn list(&self, query: &UserQuery) -> anyhow::Result<Page<User>> {
+        let mut users: Vec<User> = self
+            .users
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|user| {
+                query
+                    .email
+                    .as_ref()
+                    .is_none_or(|email| &user.email == email)
+            })
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| query.sort.order(compare(a, b, query.sort.field)));
+        let total = users.len() as u64;
+        Ok(Page::new(
+            query.pagination.slice(users),
+            total,
+            query.pagination,
+        ))
+    }
+
+    async fn update(&self, user: User) -> Result<bool, UserStoreError> {
+        let mut users = self
+            .users
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if users
+            .iter()
+            .any(|stored| stored.email == user.email && stored.id != user.id)
+        {
+            return Err(UserStoreError::DuplicateEmail(user.email));
+        }
+        match users.iter_mut().find(|stored| stored.id == user.id) {
+            Some(stored) => {
+                *stored = user;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let mut users = self
+            .users
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = users.len();
+        users.retain(|user| user.id != id);
+        Ok(users.len() < before)
+    }
+}
+
+/// Columns every query returns, with the record id as the plain `id` string
+const SELECT_USER: &str = "SELECT *, record::id(id) AS id FROM user";
+
+/// Users in the `user` table defined by the module's migrations
+pub struct SurrealUserStore {
+    db: Arc<dyn QueryExecutor>,
+}
+
+impl SurrealUserStore {
+    pub fn new(db: Arc<dyn QueryExecutor>) -> Self {
+        Self { db }
+    }
+
+    /// `SET` clause writing every field of `user` but its id, with the bind values
+    ///
+    /// Missing optional fields are set to `NONE`, which `option<string>` fields accept.
+    fn assignments(user: &User) -> (String, Map<String, Value>) {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(user.id));
+        vars.insert("email".to_string(), json!(user.email));
+        vars.insert("name".to_string(), json!(user.name));
+        vars.insert("created_at".to_string(), json!(rfc3339(user.created_at)));
+        vars.insert("updated_at".to_string(), json!(rfc3339(user.updated_at)));
+        let mut fields = vec![
+            "email = $email".to_string(),
+            "name = $name".to_string(),
+            "created_at = <datetime> $created_at".to_string(),
+            "updated_at = <datetime> $updated_at".to_string(),
+        ];
+        for (field, value) in [
+            ("bio", &user.bio),
+            ("avatar_url", &user.avatar_url),
+            ("password_hash", &user.password_hash),
+        ] {
+            match value {
+                Some(value) => {
+                    fields.push(format!("{} = ${}", field, field));
+                    vars.insert(field.to_string(), json!(value));
+                }
+                None => fields.push(format!("{} = NONE", field)),
+            }
+        }
+        (fields.join(", "), vars)
+    }
+
+    async fn write(&self, surql: &str, user: &User) -> Result<Vec<Value>, UserStoreError> {
+        let (_, vars) = Self::assignments(user);
+        self.db.query_with(surql, &vars).await.map_err(|error| {
+            match atlas_db::unique_index_violation(&error) {
+                Some(index) if index == EMAIL_INDEX => {
+                    UserStoreError::DuplicateEmail(user.email.clone())
+                }
+                _ => UserStoreError::Store(error),
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl UserStore for SurrealUserStore {
+    async fn insert(&self, user: User) -> Result<(), UserStoreError> {
+        let (set, _) = Self::assignments(&user);
+        let surql = format!("CREATE type::thing('user', $id) SET {} RETURN NONE;", set);
+        self.write(&surql, &user).await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<User>> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let results = self
+            .db
+            .query_with(
+                &format!("{} WHERE id = type::thing('user', $id);", SELECT_USER),
+                &vars,
+            )
+            .await
+            .context("failed to read user")?;
+        Ok(atlas_db::records(results.into_iter().next())?
+            .into_iter()
+            .next())
+    }
+
+    async fn list(&self, query: &UserQuery) -> anyhow::Result<Page<User>> {
+        let mut vars = Map::new();
+        let filter = match &query.email {
+            Some(email) => {
+                vars.insert("email".to_string(), json!(email));
+                "WHERE email = $email"
+            }
+            None => "",
+        };
+        vars.insert("limit".to_string(), json!(query.pagination.limit()));
+        vars.insert("start".to_string(), json!(query.pagination.offset()));
+        let surql = format!(
+            "{select} {filter} {order} LIMIT $limit START $start;\n\
+             SELECT count() AS total FROM user {filter} GROUP ALL;",
+            select = SELECT_USER,
+            filter = filter,
+            order = query.sort.to_surql(),
+        );
+        let mut results = self
+            .db
+            .query_with(&surql, &vars)
+            .await
+            .context("failed to list users")?
+            .into_iter();
+        let users = atlas_db::records(results.next())?;
+        let total = atlas_db::records::<Total>(results.next())?
+            .first()
+            .map_or(0, |count| count.total);
+        Ok(Page::new(users, total, query.pagination))
+    }
+
+    async fn update(&self, user: User) -> Result<bool, UserStoreError> {
+        let (set, _) = Self::assignments(&user);
+        // A `WHERE` keeps `UPDATE` from creating a missing record
+        let surql = format!(
+            "UPDATE user SET {} WHERE id = type::thing('user', $id) RETURN VALUE record::id(id);",
+            set
+        );
+        let results = self.write(&surql, &user).await?;
+        Ok(updated(results.first()))
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let mut vars = Map::new();
+        vars.insert("id".to_string(), json!(id));
+        let results = self
+            .db
+            .query_with(
+                "DELETE user WHERE id = type::thing('user', $id) RETURN BEFORE;",
+                &vars,
+            )
+            .await
+            .context("failed to delete user")?;
+        Ok(updated(results.first()))
+    }
+}
+
+fn rfc3339(at: time::OffsetDateTime) -> String {
+    at.format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default()
+}
+
+/// Row of a `count()` query
+#[derive(serde::Deserialize)]
+struct Total {
+    total: u64,
+}
+
+/// Ascending order of two users by one of `SORT_FIELDS`, then by id
+fn compare(a: &User, b: &User, field: &str) -> Ordering {
+    let ordering = match field {
+        "name" => a.name.cmp(&b.name),
+        "email" => a.email.cmp(&b.email),
+        "updated_at" => a.updated_at.cmp(&b.updated_at),
+        _ => a.created_at.cmp(&b.created_at),
+    };
+    ordering.then_with(|| a.id.cmp(&b.id))
+}
+
+/// Whether a statement changed any record
+fn updated(result: Option<&Value>) -> bool {
+    matches!(result, Some(Value::Array(records)) if !records.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            name: "Ann".to_string(),
+            bio: None,
+            avatar_url: None,
+            password_hash: None,
+            created_at: datetime!(2024-01-01 0:00 UTC),
+            updated_at: datetime!(2024-01-01 0:00 UTC),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_emails_unique() {
+        let store = MemoryUserStore::new();
+        store
+            .insert(user("user_1", "ann@example.com"))
+            .await
+            .unwrap();
+        store
+            .insert(user("user_2", "bob@example.com"))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            store.insert(user("user_3", "ann@example.com")).await,
+            Err(UserStoreError::DuplicateEmail(_))
+        ));
+        assert!(matches!(
+            store.update(user("user_2", "ann@example.com")).await,
+            Err(UserStoreError::DuplicateEmail(_))
+        ));
+        assert!(!store
+            .update(user("user_9", "eve@example.com"))
+            .await
+            .unwrap());
+        assert!(store.delete("user_1").await.unwrap());
+        assert!(!store.delete("user_1").await.unwrap());
+        assert_eq!(store.list(&UserQuery::default()).await.unwrap().total, 1);
+    }
+
+    /// Records each query and answers with a canned result or error
+    struct Recorded {
+        queries: Mutex<Vec<(String, Map<String, Value>)>>,
+        answer: fn() -> anyhow::Result<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl QueryExecutor for Recorded {
+        async fn query_with(
+            &self,
+            surql: &str,
+            vars: &Map<String, Value>,
+        ) -> anyhow::Result<Vec<Value>> {
+            self.queries
+                .lock()
+                .unwrap()
+                .push((surql.to_string(), vars.clone()));
+            (self.answer)()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_binds_values_and_maps_index_violations() {
+        let db = Arc::new(Recorded {
+            queries: Mutex::new(Vec::new()),
+            answer: || {
+                anyhow::bail!(
+                    "Database index `user_email_unique` already contains 'ann@example.com', with record `user:user_1`"
+                )
+            },
+        });
+        let store = SurrealUserStore::new(db.clone());
+
+        let error = store
+            .insert(user("user_2", "ann@example.com"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, UserStoreError::DuplicateEmail(_)));
+
+        let (surql, vars) = db.queries.lock().unwrap()[0].clone();
+        assert!(surql.starts_with("CREATE type::thing('user', $id) SET email = $email"));
+        assert!(surql.contains("bio = NONE"));
+        assert_eq!(vars["email"], "ann@example.com");
+        assert_eq!(vars["created_at"], "2024-01-01T00:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_surreal_store_reads_records() {
+        let db = Arc::new(Recorded {
+            queries: Mutex::new(Vec::new()),
+            answer: || {
+                Ok(vec![json!([{
+                    "id": "user_1",
+                    "email": "ann@example.com",
+                    "name": "Ann",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-02T00:00:00Z"
+                }])])
+            },
+        });
+        let store = SurrealUserStore::new(db);
+
+        let found = store.get("user_1").await.unwrap().unwrap();
+        assert_eq!(found.email, "ann@example.com");
+        assert_eq!(found.bio, None);
+        assert!(store.delete("user_1").await.unwrap());
+    }
+}
@@ -0,0 +1,49 @@
+//! Outgoing email
+//!
+//! Modules send mail through the `Arc<dyn Mailer>` resource the application registers,
+//! e.g. an SMTP or provider API client. Without one, `mailer` falls back to `LogMailer`,
+//! which only logs what would have been sent.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use atlas_kernel::Resources;
+
+/// A plain-text message to one recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> anyhow::Result<()>;
+}
+
+/// Mailer that logs each message instead of delivering it
+#[derive(Debug, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        tracing::info!(to = %email.to, subject = %email.subject, "email not delivered; no mailer registered");
+        Ok(())
+    }
+}
+
+/// The registered mailer, or a `LogMailer` with a warning when there is none
+pub fn mailer(resources: &Resources, module: &str) -> Arc<dyn Mailer> {
+    match resources.get::<Arc<dyn Mailer>>() {
+        Some(mailer) => mailer.as_ref().clone(),
+        None => {
+            tracing::warn!(module, "no mailer registered; emails are logged, not sent");
+            Arc::new(LogMailer)
+        }
+    }
+}
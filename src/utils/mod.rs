@@ -1 +1,3 @@
 //! Project-specific utilities live here.
+
+pub mod mail;